@@ -0,0 +1,69 @@
+/*
+ * C bindings for shared-memory objects from todc-mem.
+ *
+ * Objects are created with a *_new function, which returns an opaque pointer
+ * that must eventually be passed to the matching *_free function. All other
+ * functions may be called concurrently from multiple threads.
+ */
+#ifndef TODC_H
+#define TODC_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The status returned by functions that succeed. */
+#define TODC_OK 0
+
+/* The status returned by functions whose arguments are invalid. */
+#define TODC_INVALID_ARGUMENT -1
+
+/* A register containing a uint64_t. */
+typedef struct TodcRegister TodcRegister;
+
+/* A snapshot object, in which each component contains a uint64_t. */
+typedef struct TodcSnapshot TodcSnapshot;
+
+/* Creates a register that initially contains 0. */
+TodcRegister *todc_register_new(void);
+
+/* Frees a register. Freeing a null pointer has no effect. */
+void todc_register_free(TodcRegister *reg);
+
+/* Returns the value contained in the register. */
+uint64_t todc_register_read(const TodcRegister *reg);
+
+/* Sets the contents of the register to the specified value. */
+void todc_register_write(const TodcRegister *reg, uint64_t value);
+
+/* Creates a snapshot object with n components, each initially containing 0. */
+TodcSnapshot *todc_snapshot_new(size_t n);
+
+/* Frees a snapshot object. Freeing a null pointer has no effect. */
+void todc_snapshot_free(TodcSnapshot *snapshot);
+
+/* Returns the number of components in the snapshot object. */
+size_t todc_snapshot_components(const TodcSnapshot *snapshot);
+
+/*
+ * Scans the snapshot object on behalf of process i, and writes the value of
+ * each component into view, which has room for len values. Returns
+ * TODC_INVALID_ARGUMENT, and writes nothing, if i is not the index of a
+ * component or view is too small.
+ */
+int32_t todc_snapshot_scan(const TodcSnapshot *snapshot, size_t i, uint64_t *view, size_t len);
+
+/*
+ * Sets the contents of component i of the snapshot object to the specified
+ * value. Returns TODC_INVALID_ARGUMENT if i is not the index of a component.
+ */
+int32_t todc_snapshot_update(const TodcSnapshot *snapshot, size_t i, uint64_t value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TODC_H */
@@ -0,0 +1,265 @@
+//! C and Python bindings for shared-memory objects from [`todc_mem`].
+//!
+//! This crate exposes a small number of objects through a C ABI, so that
+//! experiments driven from other languages can use exactly the same
+//! implementations that are benchmarked in Rust:
+//!
+//! * A register, backed by a [`todc_mem::register::AtomicRegister`], that
+//!   contains a `uint64_t`.
+//! * A snapshot object with a number of components chosen at runtime, backed
+//!   by a [`todc_mem::snapshot::DynBoundedMutexSnapshot`], in which each
+//!   component contains a `uint64_t`.
+//!
+//! Objects are created with a `*_new` function, which returns an opaque
+//! pointer that must eventually be passed to the matching `*_free` function.
+//! All other functions take such a pointer, and may be called concurrently
+//! from multiple threads. A C header declaring every function is available at
+//! [`todc-ffi/include/todc.h`](https://github.com/kaymanb/todc/blob/main/todc-ffi/include/todc.h).
+//!
+//! With the `python` feature enabled, the same objects are also exposed as a
+//! Python extension module named `todc`. See the `python` module for
+//! details.
+//!
+//! # Examples
+//!
+//! ```c
+//! #include <stdint.h>
+//! #include "todc.h"
+//!
+//! int main(void) {
+//!     TodcSnapshot *snapshot = todc_snapshot_new(3);
+//!     todc_snapshot_update(snapshot, 1, 123);
+//!
+//!     uint64_t view[3];
+//!     todc_snapshot_scan(snapshot, 0, view, 3);
+//!     // view is now {0, 123, 0}
+//!
+//!     todc_snapshot_free(snapshot);
+//!     return 0;
+//! }
+//! ```
+use std::slice;
+
+use todc_mem::register::{AtomicRegister, Register};
+use todc_mem::snapshot::{DynBoundedMutexSnapshot, DynSnapshot};
+
+#[cfg(feature = "python")]
+pub mod python;
+
+/// A register containing a `uint64_t`.
+pub struct TodcRegister(AtomicRegister<u64>);
+
+/// A snapshot object, in which each component contains a `uint64_t`.
+pub struct TodcSnapshot {
+    snapshot: DynBoundedMutexSnapshot<u64>,
+    components: usize,
+}
+
+/// The status returned by functions that succeed.
+pub const TODC_OK: i32 = 0;
+
+/// The status returned by functions whose arguments are invalid, such as an
+/// index that is out of bounds, or a buffer that is too small.
+pub const TODC_INVALID_ARGUMENT: i32 = -1;
+
+/// Creates a register that initially contains `0`.
+///
+/// The register must be freed with [`todc_register_free`].
+#[no_mangle]
+pub extern "C" fn todc_register_new() -> *mut TodcRegister {
+    Box::into_raw(Box::new(TodcRegister(AtomicRegister::new())))
+}
+
+/// Frees a register. Freeing a null pointer has no effect.
+///
+/// # Safety
+///
+/// The register must have been created by [`todc_register_new`], must not
+/// have already been freed, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn todc_register_free(register: *mut TodcRegister) {
+    if !register.is_null() {
+        drop(Box::from_raw(register));
+    }
+}
+
+/// Returns the value contained in the register.
+///
+/// # Safety
+///
+/// The register must have been created by [`todc_register_new`], and not yet
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn todc_register_read(register: *const TodcRegister) -> u64 {
+    (*register).0.read()
+}
+
+/// Sets the contents of the register to the specified value.
+///
+/// # Safety
+///
+/// The register must have been created by [`todc_register_new`], and not yet
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn todc_register_write(register: *const TodcRegister, value: u64) {
+    (*register).0.write(value)
+}
+
+/// Creates a snapshot object with `n` components, each of which initially
+/// contains `0`.
+///
+/// The snapshot must be freed with [`todc_snapshot_free`].
+#[no_mangle]
+pub extern "C" fn todc_snapshot_new(n: usize) -> *mut TodcSnapshot {
+    Box::into_raw(Box::new(TodcSnapshot {
+        snapshot: DynBoundedMutexSnapshot::new(n),
+        components: n,
+    }))
+}
+
+/// Frees a snapshot object. Freeing a null pointer has no effect.
+///
+/// # Safety
+///
+/// The snapshot must have been created by [`todc_snapshot_new`], must not
+/// have already been freed, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn todc_snapshot_free(snapshot: *mut TodcSnapshot) {
+    if !snapshot.is_null() {
+        drop(Box::from_raw(snapshot));
+    }
+}
+
+/// Returns the number of components in the snapshot object.
+///
+/// # Safety
+///
+/// The snapshot must have been created by [`todc_snapshot_new`], and not yet
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn todc_snapshot_components(snapshot: *const TodcSnapshot) -> usize {
+    (*snapshot).components
+}
+
+/// Scans the snapshot object on behalf of the _i^{th}_ process, and writes
+/// the value of each component into `view`, which has room for `len` values.
+///
+/// Returns [`TODC_OK`] on success, and [`TODC_INVALID_ARGUMENT`] if `i` is
+/// not the index of a component or `view` is too small, in which case
+/// nothing is written.
+///
+/// # Safety
+///
+/// The snapshot must have been created by [`todc_snapshot_new`], and not yet
+/// freed, and `view` must point to `len` writable values.
+#[no_mangle]
+pub unsafe extern "C" fn todc_snapshot_scan(
+    snapshot: *const TodcSnapshot,
+    i: usize,
+    view: *mut u64,
+    len: usize,
+) -> i32 {
+    let snapshot = &*snapshot;
+    if i >= snapshot.components || len < snapshot.components || view.is_null() {
+        return TODC_INVALID_ARGUMENT;
+    }
+    let values = snapshot.snapshot.scan(i);
+    slice::from_raw_parts_mut(view, len)[..values.len()].copy_from_slice(&values);
+    TODC_OK
+}
+
+/// Sets the contents of the _i^{th}_ component of the snapshot object to the
+/// specified value.
+///
+/// Returns [`TODC_OK`] on success, and [`TODC_INVALID_ARGUMENT`] if `i` is
+/// not the index of a component.
+///
+/// # Safety
+///
+/// The snapshot must have been created by [`todc_snapshot_new`], and not yet
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn todc_snapshot_update(
+    snapshot: *const TodcSnapshot,
+    i: usize,
+    value: u64,
+) -> i32 {
+    let snapshot = &*snapshot;
+    if i >= snapshot.components {
+        return TODC_INVALID_ARGUMENT;
+    }
+    snapshot.snapshot.update(i, value);
+    TODC_OK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod register {
+        use super::*;
+
+        #[test]
+        fn reads_most_recent_write() {
+            unsafe {
+                let register = todc_register_new();
+                assert_eq!(todc_register_read(register), 0);
+                todc_register_write(register, 123);
+                assert_eq!(todc_register_read(register), 123);
+                todc_register_free(register);
+            }
+        }
+
+        #[test]
+        fn freeing_null_has_no_effect() {
+            unsafe { todc_register_free(std::ptr::null_mut()) }
+        }
+    }
+
+    mod snapshot {
+        use super::*;
+
+        #[test]
+        fn scan_contains_updated_values() {
+            unsafe {
+                let snapshot = todc_snapshot_new(3);
+                assert_eq!(todc_snapshot_components(snapshot), 3);
+                assert_eq!(todc_snapshot_update(snapshot, 1, 123), TODC_OK);
+
+                let mut view = [u64::MAX; 3];
+                assert_eq!(
+                    todc_snapshot_scan(snapshot, 0, view.as_mut_ptr(), 3),
+                    TODC_OK
+                );
+                assert_eq!(view, [0, 123, 0]);
+                todc_snapshot_free(snapshot);
+            }
+        }
+
+        #[test]
+        fn scan_rejects_small_buffer() {
+            unsafe {
+                let snapshot = todc_snapshot_new(3);
+                let mut view = [u64::MAX; 2];
+                assert_eq!(
+                    todc_snapshot_scan(snapshot, 0, view.as_mut_ptr(), 2),
+                    TODC_INVALID_ARGUMENT
+                );
+                assert_eq!(view, [u64::MAX; 2]);
+                todc_snapshot_free(snapshot);
+            }
+        }
+
+        #[test]
+        fn update_rejects_missing_component() {
+            unsafe {
+                let snapshot = todc_snapshot_new(3);
+                assert_eq!(
+                    todc_snapshot_update(snapshot, 3, 123),
+                    TODC_INVALID_ARGUMENT
+                );
+                todc_snapshot_free(snapshot);
+            }
+        }
+    }
+}
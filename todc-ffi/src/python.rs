@@ -0,0 +1,120 @@
+//! A Python extension module exposing the same objects as the C ABI.
+//!
+//! The module is named `todc`, and can be built with
+//! [`maturin`](https://www.maturin.rs/) by enabling the `python` feature:
+//!
+//! ```text
+//! maturin develop --manifest-path todc-ffi/Cargo.toml --features python
+//! ```
+//!
+//! # Examples
+//!
+//! ```python
+//! import threading
+//! import todc
+//!
+//! snapshot = todc.Snapshot(3)
+//!
+//! def worker(i):
+//!     snapshot.update(i, i + 1)
+//!
+//! threads = [threading.Thread(target=worker, args=(i,)) for i in range(3)]
+//! for thread in threads:
+//!     thread.start()
+//! for thread in threads:
+//!     thread.join()
+//!
+//! assert snapshot.scan(0) == [1, 2, 3]
+//! ```
+//!
+//! This module requires the `python` feature.
+use pyo3::exceptions::PyIndexError;
+use pyo3::prelude::*;
+
+use todc_mem::register::{AtomicRegister, Register as _};
+use todc_mem::snapshot::{DynBoundedMutexSnapshot, DynSnapshot};
+
+/// A register containing an unsigned 64-bit integer.
+#[pyclass(name = "AtomicRegister", frozen)]
+pub struct PyAtomicRegister(AtomicRegister<u64>);
+
+#[pymethods]
+impl PyAtomicRegister {
+    /// Creates a register that initially contains `0`.
+    #[new]
+    fn new() -> Self {
+        Self(AtomicRegister::new())
+    }
+
+    /// Returns the value contained in the register.
+    fn read(&self) -> u64 {
+        self.0.read()
+    }
+
+    /// Sets the contents of the register to the specified value.
+    fn write(&self, value: u64) {
+        self.0.write(value)
+    }
+}
+
+/// A snapshot object, in which each component contains an unsigned 64-bit
+/// integer.
+#[pyclass(name = "Snapshot", frozen)]
+pub struct PySnapshot {
+    snapshot: DynBoundedMutexSnapshot<u64>,
+    components: usize,
+}
+
+impl PySnapshot {
+    /// Returns an error if `i` is not the index of a component.
+    fn check_component(&self, i: usize) -> PyResult<()> {
+        if i < self.components {
+            Ok(())
+        } else {
+            Err(PyIndexError::new_err(format!(
+                "Component {i} does not exist in a snapshot with {} components",
+                self.components
+            )))
+        }
+    }
+}
+
+#[pymethods]
+impl PySnapshot {
+    /// Creates a snapshot object with `n` components, each of which initially
+    /// contains `0`.
+    #[new]
+    fn new(n: usize) -> Self {
+        Self {
+            snapshot: DynBoundedMutexSnapshot::new(n),
+            components: n,
+        }
+    }
+
+    /// Returns the number of components in the snapshot object.
+    fn __len__(&self) -> usize {
+        self.components
+    }
+
+    /// Returns a list containing the value of each component, on behalf of
+    /// the _i^{th}_ process.
+    fn scan(&self, py: Python<'_>, i: usize) -> PyResult<Vec<u64>> {
+        self.check_component(i)?;
+        Ok(py.allow_threads(|| self.snapshot.scan(i)))
+    }
+
+    /// Sets the contents of the _i^{th}_ component to the specified value.
+    fn update(&self, py: Python<'_>, i: usize, value: u64) -> PyResult<()> {
+        self.check_component(i)?;
+        py.allow_threads(|| self.snapshot.update(i, value));
+        Ok(())
+    }
+}
+
+/// Shared-memory objects from `todc-mem`.
+#[pymodule]
+fn todc(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyAtomicRegister>()?;
+    module.add_class::<PySnapshot>()?;
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Resource allocation, built on top of a snapshot object.
+//!
+//! This module contains an implementation of _long-lived renaming_, in which
+//! `N` processes repeatedly acquire and release names, such that no two
+//! processes ever hold the same name at the same time. Names are taken from
+//! the range `0..2N-1`, so a name can be used to index into a pool of
+//! `2N-1` resources, each of which is held by at most one process.
+//!
+//! The algorithm is a long-lived variant of the snapshot-based renaming
+//! algorithm of [\[AW04\]](https://doi.org/10.1002/0471478210), Section 16.3.
+//! Each process stores the name it _suggests_ for itself in its component of
+//! an `N`-process [`Snapshot`]. After suggesting a name, a process scans the
+//! snapshot, and keeps the name if no other process has suggested it.
+//! Otherwise, if it is the _r^{th}_ process, by identifier, among those that
+//! are suggesting names, then it suggests the _r^{th}_ name that is not
+//! suggested by any other process, and tries again. Releasing a name clears
+//! the component of the releasing process.
+//!
+//! Because scans are linearizable, any two processes that suggest the same
+//! name cannot both miss each other's suggestion, and so at most one of them
+//! keeps it. Acquiring a name is not wait-free. However, a process that
+//! eventually runs without interference from other processes acquiring names
+//! always acquires one after a bounded number of its own steps.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::thread;
+//! use todc_mem::allocation::MutexRenaming;
+//!
+//! const N: usize = 3;
+//!
+//! let renaming: Arc<MutexRenaming<N>> = Arc::new(MutexRenaming::new());
+//!
+//! let handles: Vec<_> = (0..N)
+//!     .map(|i| {
+//!         let renaming = renaming.clone();
+//!         thread::spawn(move || {
+//!             let name = renaming.acquire(i);
+//!             // ... use the resource identified by name ...
+//!             renaming.release(i);
+//!             name
+//!         })
+//!     })
+//!     .collect();
+//!
+//! for handle in handles {
+//!     assert!(handle.join().unwrap() < renaming.names());
+//! }
+//! ```
+use crate::snapshot::{BoundedMutexSnapshot, ProcessId, Snapshot};
+
+/// An `N`-process long-lived renaming object backed by a
+/// [`BoundedMutexSnapshot`](crate::snapshot::BoundedMutexSnapshot).
+///
+/// This object is **not** lock-free.
+pub type MutexRenaming<const N: usize> = Renaming<BoundedMutexSnapshot<Option<usize>, N>, N>;
+
+/// An `N`-process long-lived renaming object.
+///
+/// See the [`allocation`](crate::allocation) module-level documentation for
+/// more details.
+pub struct Renaming<S, const N: usize> {
+    snapshot: S,
+}
+
+impl<S, const N: usize> Renaming<S, N>
+where
+    S: Snapshot<N, Value = Option<usize>>,
+{
+    /// Creates a new renaming object, in which no names are held.
+    pub fn new() -> Self {
+        Self { snapshot: S::new() }
+    }
+
+    /// Returns the number of names that can be acquired, which is `2N-1`.
+    pub fn names(&self) -> usize {
+        2 * N - 1
+    }
+
+    /// Acquires a name for the _i^{th}_ process, and returns it.
+    ///
+    /// The name is held by the process, and by no other, until it is
+    /// released. A process that already holds a name must release it before
+    /// acquiring another.
+    pub fn acquire(&self, i: ProcessId) -> usize {
+        let mut suggestion = 0;
+        loop {
+            self.snapshot.update(i, Some(suggestion));
+            let view = self.snapshot.scan(i);
+            let others = || {
+                view.iter()
+                    .enumerate()
+                    .filter(move |(j, _)| *j != i)
+                    .filter_map(|(_, name)| *name)
+            };
+            if others().all(|name| name != suggestion) {
+                return suggestion;
+            }
+            // The rank of this process among those suggesting names, starting
+            // from zero. At most N - 1 names are suggested by other
+            // processes, so at least N names are free, and there is always a
+            // free name of every rank.
+            let rank = view[..i].iter().filter(|name| name.is_some()).count();
+            suggestion = (0..self.names())
+                .filter(|name| others().all(|other| other != *name))
+                .nth(rank)
+                .unwrap();
+        }
+    }
+
+    /// Releases the name held by the _i^{th}_ process, if any.
+    pub fn release(&self, i: ProcessId) {
+        self.snapshot.update(i, None);
+    }
+}
+
+impl<S, const N: usize> Default for Renaming<S, N>
+where
+    S: Snapshot<N, Value = Option<usize>>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::{Arc, Barrier, Mutex};
+    use std::thread;
+
+    mod names {
+        use super::*;
+
+        #[test]
+        fn is_one_less_than_twice_number_of_processes() {
+            let renaming: MutexRenaming<4> = MutexRenaming::new();
+            assert_eq!(renaming.names(), 7);
+        }
+    }
+
+    mod acquire {
+        use super::*;
+
+        #[test]
+        fn solo_process_acquires_first_name() {
+            let renaming: MutexRenaming<3> = MutexRenaming::new();
+            assert_eq!(renaming.acquire(2), 0);
+        }
+
+        #[test]
+        fn sequential_processes_acquire_distinct_names() {
+            let renaming: MutexRenaming<3> = MutexRenaming::new();
+            let names: HashSet<usize> = (0..3).map(|i| renaming.acquire(i)).collect();
+            assert_eq!(names.len(), 3);
+            assert!(names.iter().all(|name| *name < renaming.names()));
+        }
+
+        #[test]
+        fn released_name_can_be_reacquired() {
+            let renaming: MutexRenaming<2> = MutexRenaming::new();
+            assert_eq!(renaming.acquire(0), 0);
+            renaming.release(0);
+            assert_eq!(renaming.acquire(1), 0);
+        }
+
+        #[test]
+        fn concurrent_processes_hold_distinct_names() {
+            const N: usize = 4;
+            let renaming: Arc<MutexRenaming<N>> = Arc::new(MutexRenaming::new());
+            let holders: Arc<Mutex<Vec<Option<ProcessId>>>> =
+                Arc::new(Mutex::new(vec![None; renaming.names()]));
+            let barrier = Arc::new(Barrier::new(N));
+            let handles: Vec<_> = (0..N)
+                .map(|i| {
+                    let renaming = renaming.clone();
+                    let holders = holders.clone();
+                    let barrier = barrier.clone();
+                    thread::spawn(move || {
+                        barrier.wait();
+                        for _ in 0..100 {
+                            let name = renaming.acquire(i);
+                            {
+                                let mut holders = holders.lock().unwrap();
+                                assert_eq!(holders[name], None);
+                                holders[name] = Some(i);
+                            }
+                            holders.lock().unwrap()[name] = None;
+                            renaming.release(i);
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        }
+    }
+}
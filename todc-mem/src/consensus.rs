@@ -0,0 +1,261 @@
+//! Consensus objects.
+//!
+//! A consensus object allows a set of processes to agree on a single value.
+//! Each process [`decide`](Consensus::decide)s at most once, by proposing a
+//! value, and every process decides the same value, which was proposed by
+//! one of them.
+//!
+//! The _consensus number_ of an object is the largest number of processes
+//! for which consensus can be solved, in a wait-free manner, using any
+//! number of copies of the object and of read/write registers. Herlihy
+//! [\[Her91\]](https://dl.acm.org/doi/10.1145/114005.102808) showed that an
+//! object with consensus number `n` is _universal_ for `n` processes: it can
+//! be used to implement a wait-free linearizable version of any object
+//! shared by `n` processes. This gives a hierarchy of objects, of which this
+//! module contains small demonstrations:
+//!
+//! - A [`QueueConsensus`] solves consensus between two processes using a
+//!   [queue](crate::queue) that initially contains two tokens. A
+//!   [`TestAndSetConsensus`] does the same using a
+//!   [test-and-set](crate::test_and_set) object. Both queues and
+//!   test-and-set have consensus number 2.
+//! - A [`CASConsensus`] solves consensus between any number of processes
+//!   using a single compare-and-swap, which has infinite consensus number.
+//!
+//! # Impossibility
+//!
+//! There is no wait-free implementation of consensus between two processes
+//! from read/write registers alone, which have consensus number 1, as shown
+//! by Loui and Abu-Amara \[LAA87\] and by Herlihy. This is why each of the
+//! objects in this module needs a stronger primitive than the registers in
+//! which processes announce their proposals. Similarly, there is no
+//! wait-free implementation of consensus between _three_ processes from
+//! queues, test-and-set, or [fetch-and-add](crate::fetch_and_add), and so
+//! neither a
+//! [`QueueConsensus`] nor a [`TestAndSetConsensus`] can be generalized
+//! beyond two processes, and none of these primitives can be used to
+//! implement compare-and-swap for three or more processes.
+//!
+//! Randomization circumvents these results: a
+//! [`RandomizedTestAndSet`](crate::test_and_set::RandomizedTestAndSet) is
+//! built from registers alone, and terminates with probability 1, rather
+//! than in a bounded number of steps.
+//!
+//! # Examples
+//!
+//! Agree on the proposal of one of a set of threads.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::thread;
+//! use todc_mem::consensus::{CASConsensus, Consensus};
+//!
+//! const N: usize = 4;
+//!
+//! let consensus: Arc<CASConsensus<usize, N>> = Arc::new(CASConsensus::new());
+//!
+//! let handles: Vec<_> = (0..N)
+//!     .map(|i| {
+//!         let consensus = consensus.clone();
+//!         thread::spawn(move || consensus.decide(i, i * 10))
+//!     })
+//!     .collect();
+//!
+//! let decisions: Vec<usize> = handles
+//!     .into_iter()
+//!     .map(|handle| handle.join().unwrap())
+//!     .collect();
+//! assert!(decisions.iter().all(|&decision| decision == decisions[0]));
+//! assert_eq!(decisions[0] % 10, 0);
+//! ```
+use core::array::from_fn;
+
+use crate::queue::{MSQueue, Queue};
+use crate::register::{MutexRegister, Register};
+use crate::snapshot::ProcessId;
+use crate::sync::{AtomicU64, Ordering};
+use crate::test_and_set::{NativeTestAndSet, TestAndSet};
+
+/// An `N`-process consensus object.
+pub trait Consensus<const N: usize> {
+    type Value;
+
+    /// Creates a new consensus object, on which no value has been decided.
+    fn new() -> Self;
+
+    /// Proposes a value on behalf of the _i^{th}_ process, and returns the
+    /// value that was decided.
+    ///
+    /// Each process must call this method at most once.
+    fn decide(&self, i: ProcessId, value: Self::Value) -> Self::Value;
+}
+
+/// A wait-free two-process consensus object, built from a queue and
+/// registers.
+///
+/// The queue initially contains a _winning_ token, followed by a _losing_
+/// token. Each process announces its proposal in its own register, and then
+/// dequeues a token. The process that dequeues the winning token decides its
+/// own proposal, and the other process, which dequeues the losing token,
+/// decides the proposal of the winner. Since the winner announced its
+/// proposal before dequeuing, the loser always reads it.
+///
+/// This object is wait-free if the queue is. With a third process, the
+/// queue would need to reveal which of the two other processes dequeued
+/// first, which it cannot. See the
+/// [`consensus`](crate::consensus) module-level documentation.
+pub struct QueueConsensus<T, Q = MSQueue<bool, 2>, R = MutexRegister<T>>
+where
+    Q: Queue<2, Value = bool>,
+    R: Register<Value = T>,
+{
+    tokens: Q,
+    proposals: [R; 2],
+}
+
+impl<T, Q, R> Consensus<2> for QueueConsensus<T, Q, R>
+where
+    Q: Queue<2, Value = bool>,
+    R: Register<Value = T>,
+{
+    type Value = T;
+
+    fn new() -> Self {
+        let tokens = Q::new();
+        tokens.enqueue(0, true);
+        tokens.enqueue(0, false);
+        Self {
+            tokens,
+            proposals: from_fn(|_| R::new()),
+        }
+    }
+
+    fn decide(&self, i: ProcessId, value: T) -> T {
+        self.proposals[i].write(value);
+        match self.tokens.dequeue(i) {
+            Some(true) => self.proposals[i].read(),
+            _ => self.proposals[1 - i].read(),
+        }
+    }
+}
+
+/// A wait-free two-process consensus object, built from a test-and-set
+/// object and registers.
+///
+/// Each process announces its proposal in its own register, and then
+/// performs a [`test_and_set`](TestAndSet::test_and_set). The winner decides
+/// its own proposal, and the loser decides the proposal of the winner.
+///
+/// This object is wait-free if the test-and-set object is.
+pub struct TestAndSetConsensus<T, S = NativeTestAndSet, R = MutexRegister<T>>
+where
+    S: TestAndSet<2>,
+    R: Register<Value = T>,
+{
+    tas: S,
+    proposals: [R; 2],
+}
+
+impl<T, S, R> Consensus<2> for TestAndSetConsensus<T, S, R>
+where
+    S: TestAndSet<2>,
+    R: Register<Value = T>,
+{
+    type Value = T;
+
+    fn new() -> Self {
+        Self {
+            tas: S::new(),
+            proposals: from_fn(|_| R::new()),
+        }
+    }
+
+    fn decide(&self, i: ProcessId, value: T) -> T {
+        self.proposals[i].write(value);
+        if self.tas.test_and_set(i) {
+            self.proposals[1 - i].read()
+        } else {
+            self.proposals[i].read()
+        }
+    }
+}
+
+/// A wait-free `N`-process consensus object, built from a compare-and-swap
+/// object and registers.
+///
+/// Each process announces its proposal in its own register, and then tries
+/// to swap its own ID into a shared word that initially contains no ID. The
+/// process whose swap succeeds is the winner, and every process decides the
+/// proposal of the winner.
+pub struct CASConsensus<T, const N: usize, R = MutexRegister<T>>
+where
+    R: Register<Value = T>,
+{
+    // One more than the ID of the winner, or 0 if no process has won yet.
+    winner: AtomicU64,
+    proposals: [R; N],
+}
+
+impl<T, const N: usize, R> Consensus<N> for CASConsensus<T, N, R>
+where
+    R: Register<Value = T>,
+{
+    type Value = T;
+
+    fn new() -> Self {
+        Self {
+            winner: AtomicU64::new(0),
+            proposals: from_fn(|_| R::new()),
+        }
+    }
+
+    fn decide(&self, i: ProcessId, value: T) -> T {
+        self.proposals[i].write(value);
+        let winner =
+            match self
+                .winner
+                .compare_exchange(0, i as u64 + 1, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(_) => i,
+                Err(winner) => winner as usize - 1,
+            };
+        self.proposals[winner].read()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that two processes that decide one after the other both
+    /// decide the proposal of the first.
+    fn assert_first_proposal_is_decided<C: Consensus<2, Value = u32>>() {
+        let consensus = C::new();
+        assert_eq!(consensus.decide(1, 10), 10);
+        assert_eq!(consensus.decide(0, 20), 10);
+    }
+
+    #[test]
+    fn queue_consensus_decides_first_proposal() {
+        assert_first_proposal_is_decided::<QueueConsensus<u32>>();
+    }
+
+    #[test]
+    fn test_and_set_consensus_decides_first_proposal() {
+        assert_first_proposal_is_decided::<TestAndSetConsensus<u32>>();
+    }
+
+    #[test]
+    fn cas_consensus_decides_first_proposal() {
+        assert_first_proposal_is_decided::<CASConsensus<u32, 2>>();
+    }
+
+    #[test]
+    fn cas_consensus_decides_for_many_processes() {
+        let consensus: CASConsensus<String, 5> = CASConsensus::new();
+        assert_eq!(consensus.decide(3, String::from("three")), "three");
+        for i in [0, 1, 2, 4] {
+            assert_eq!(consensus.decide(i, i.to_string()), "three");
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! Double-ended queue objects.
+//!
+//! A double-ended queue, or _deque_, contains a sequence of values. Values
+//! can be added to either end of the deque with
+//! [`push_front`](Deque::push_front) and [`push_back`](Deque::push_back), and
+//! removed from either end with [`pop_front`](Deque::pop_front) and
+//! [`pop_back`](Deque::pop_back).
+//!
+//! This module contains an [`HLMDeque`], the obstruction-free deque described
+//! by Herlihy, Luchangco and Moir
+//! [\[HLM03\]](https://doi.org/10.1109/ICDCS.2003.1203503).
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::thread;
+//! use todc_mem::deque::{Deque, HLMDeque};
+//!
+//! const N: usize = 2;
+//!
+//! let deque: Arc<HLMDeque<usize, N, 8>> = Arc::new(HLMDeque::new());
+//!
+//! let other = deque.clone();
+//! let handle = thread::spawn(move || other.push_front(1, 1).unwrap());
+//! deque.push_back(0, 0).unwrap();
+//! handle.join().unwrap();
+//!
+//! let mut values = vec![deque.pop_front(0).unwrap(), deque.pop_back(0).unwrap()];
+//! values.sort();
+//! assert_eq!(values, vec![0, 1]);
+//! assert_eq!(deque.pop_back(0), None);
+//! ```
+use core::marker::PhantomData;
+
+use crate::snapshot::ProcessId;
+use crate::sync::{AtomicU128, Ordering};
+
+/// An `N`-process double-ended queue.
+pub trait Deque<const N: usize> {
+    type Value;
+
+    /// Creates an empty deque.
+    fn new() -> Self;
+
+    /// Adds a value to the front of the deque, on behalf of the _i^{th}_
+    /// process, or returns it if there is no room at the front.
+    fn push_front(&self, i: ProcessId, value: Self::Value) -> Result<(), Self::Value>;
+
+    /// Adds a value to the back of the deque, on behalf of the _i^{th}_
+    /// process, or returns it if there is no room at the back.
+    fn push_back(&self, i: ProcessId, value: Self::Value) -> Result<(), Self::Value>;
+
+    /// Removes and returns the value at the front of the deque, on behalf of
+    /// the _i^{th}_ process, or returns `None` if the deque is empty.
+    fn pop_front(&self, i: ProcessId) -> Option<Self::Value>;
+
+    /// Removes and returns the value at the back of the deque, on behalf of
+    /// the _i^{th}_ process, or returns `None` if the deque is empty.
+    fn pop_back(&self, i: ProcessId) -> Option<Self::Value>;
+}
+
+/// The word stored in slots to the left of every value.
+const LEFT_NULL: u64 = 0;
+
+/// The word stored in slots to the right of every value.
+const RIGHT_NULL: u64 = u64::MAX;
+
+/// The contents of a slot in an [`HLMDeque`].
+///
+/// The word is either [`LEFT_NULL`], [`RIGHT_NULL`], or a pointer to a boxed
+/// value. Neither null can be a pointer to a box, since boxes are never at
+/// address `0`, and never end past the last address. The version is
+/// incremented by every successful compare-and-swap, which prevents the
+/// _ABA problem_.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Slot {
+    word: u64,
+    version: u64,
+}
+
+impl From<Slot> for u128 {
+    fn from(slot: Slot) -> Self {
+        (slot.version as u128) << 64 | slot.word as u128
+    }
+}
+
+impl From<u128> for Slot {
+    fn from(value: u128) -> Self {
+        Self {
+            word: value as u64,
+            version: (value >> 64) as u64,
+        }
+    }
+}
+
+/// An `N`-process obstruction-free deque, as described by Herlihy, Luchangco
+/// and Moir, that has room for at most `CAPACITY` values.
+///
+/// The deque is an array of `CAPACITY + 2` slots, each of which contains
+/// either a value, a _left null_ or a _right null_. The values of the deque
+/// are always in contiguous slots, with left nulls to their left and right
+/// nulls to their right. Initially, half of the slots are left nulls and
+/// half are right nulls, and the first and last slots never change.
+///
+/// To push a value onto the back of the deque, a process finds the leftmost
+/// right null, and then performs two compare-and-swaps: one that changes
+/// nothing but the version of the slot to its left, which fails any
+/// concurrent operation that has read it, and one that replaces the null
+/// with the value. Popping a value from the back replaces the leftmost right
+/// null, and then the value to its left, with right nulls. Operations on the
+/// front of the deque are symmetric. An operation that fails either
+/// compare-and-swap starts over.
+///
+/// Values never move between slots, so a push fails once it reaches the end
+/// of the array, even if there is room at the other end. As a result, the
+/// deque may not be able to hold `CAPACITY` values at once.
+///
+/// All operations are obstruction-free: an operation that eventually runs
+/// without interference from other processes completes, but operations that
+/// contend with one another can prevent each other from completing forever.
+/// Each slot is a single [`AtomicU128`], and so operations are only
+/// obstruction-free if [`HLMDeque::is_lock_free`] is `true`.
+pub struct HLMDeque<T, const N: usize, const CAPACITY: usize> {
+    slots: Box<[AtomicU128]>,
+    value: PhantomData<T>,
+}
+
+impl<T, const N: usize, const CAPACITY: usize> HLMDeque<T, N, CAPACITY> {
+    /// Returns whether compare-and-swap on a slot is lock-free on the current
+    /// hardware.
+    pub fn is_lock_free() -> bool {
+        AtomicU128::is_lock_free()
+    }
+
+    /// Returns the contents of the slot at an index.
+    fn load(&self, index: usize) -> Slot {
+        self.slots[index].load(Ordering::SeqCst).into()
+    }
+
+    /// Stores a word in the slot at an index, and increments its version, if
+    /// the contents of the slot have not changed since `current` was loaded.
+    /// Returns whether the store succeeded.
+    fn compare_and_swap(&self, index: usize, current: Slot, word: u64) -> bool {
+        let new = Slot {
+            word,
+            version: current.version.wrapping_add(1),
+        };
+        self.slots[index]
+            .compare_exchange(
+                current.into(),
+                new.into(),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            )
+            .is_ok()
+    }
+
+    /// Returns the index of the rightmost left null.
+    ///
+    /// Slots are read one at a time, so the result may already be out of date.
+    /// Operations only use it as a guess, and check that it is correct.
+    fn left_oracle(&self) -> usize {
+        (1..self.slots.len() - 1)
+            .rev()
+            .find(|&index| self.load(index).word == LEFT_NULL)
+            .unwrap_or(0)
+    }
+
+    /// Returns the index of the leftmost right null.
+    ///
+    /// As for [`left_oracle`](Self::left_oracle), the result may already be
+    /// out of date.
+    fn right_oracle(&self) -> usize {
+        (1..self.slots.len() - 1)
+            .find(|&index| self.load(index).word == RIGHT_NULL)
+            .unwrap_or(self.slots.len() - 1)
+    }
+}
+
+impl<T: Send, const N: usize, const CAPACITY: usize> Deque<N> for HLMDeque<T, N, CAPACITY> {
+    type Value = T;
+
+    fn new() -> Self {
+        let slots = (0..CAPACITY + 2)
+            .map(|index| {
+                let word = if index <= CAPACITY / 2 {
+                    LEFT_NULL
+                } else {
+                    RIGHT_NULL
+                };
+                AtomicU128::new(Slot { word, version: 0 }.into())
+            })
+            .collect();
+        Self {
+            slots,
+            value: PhantomData,
+        }
+    }
+
+    fn push_front(&self, _: ProcessId, value: T) -> Result<(), T> {
+        let value = Box::into_raw(Box::new(value));
+        loop {
+            let index = self.left_oracle();
+            let previous = self.load(index + 1);
+            let current = self.load(index);
+            if previous.word != LEFT_NULL && current.word == LEFT_NULL {
+                if index == 0 {
+                    // SAFETY: The value has not been shared with other
+                    // processes.
+                    return Err(*unsafe { Box::from_raw(value) });
+                }
+                if self.compare_and_swap(index + 1, previous, previous.word)
+                    && self.compare_and_swap(index, current, value as u64)
+                {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn push_back(&self, _: ProcessId, value: T) -> Result<(), T> {
+        let value = Box::into_raw(Box::new(value));
+        loop {
+            let index = self.right_oracle();
+            let previous = self.load(index - 1);
+            let current = self.load(index);
+            if previous.word != RIGHT_NULL && current.word == RIGHT_NULL {
+                if index == self.slots.len() - 1 {
+                    // SAFETY: The value has not been shared with other
+                    // processes.
+                    return Err(*unsafe { Box::from_raw(value) });
+                }
+                if self.compare_and_swap(index - 1, previous, previous.word)
+                    && self.compare_and_swap(index, current, value as u64)
+                {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn pop_front(&self, _: ProcessId) -> Option<T> {
+        loop {
+            let index = self.left_oracle();
+            let current = self.load(index + 1);
+            let next = self.load(index);
+            if current.word != LEFT_NULL && next.word == LEFT_NULL {
+                if current.word == RIGHT_NULL {
+                    // If the right null has not changed, then the deque was
+                    // empty when the left null was read.
+                    if self.load(index + 1) == current {
+                        return None;
+                    }
+                } else if self.compare_and_swap(index, next, LEFT_NULL)
+                    && self.compare_and_swap(index + 1, current, LEFT_NULL)
+                {
+                    // SAFETY: Only the process that replaces a value with a
+                    // null takes ownership of it, and no other process
+                    // dereferences it.
+                    return Some(*unsafe { Box::from_raw(current.word as *mut T) });
+                }
+            }
+        }
+    }
+
+    fn pop_back(&self, _: ProcessId) -> Option<T> {
+        loop {
+            let index = self.right_oracle();
+            let current = self.load(index - 1);
+            let next = self.load(index);
+            if current.word != RIGHT_NULL && next.word == RIGHT_NULL {
+                if current.word == LEFT_NULL {
+                    // If the left null has not changed, then the deque was
+                    // empty when the right null was read.
+                    if self.load(index - 1) == current {
+                        return None;
+                    }
+                } else if self.compare_and_swap(index, next, RIGHT_NULL)
+                    && self.compare_and_swap(index - 1, current, RIGHT_NULL)
+                {
+                    // SAFETY: As for `pop_front`.
+                    return Some(*unsafe { Box::from_raw(current.word as *mut T) });
+                }
+            }
+        }
+    }
+}
+
+impl<T, const N: usize, const CAPACITY: usize> Drop for HLMDeque<T, N, CAPACITY> {
+    fn drop(&mut self) {
+        for index in 0..self.slots.len() {
+            let word = self.load(index).word;
+            if word != LEFT_NULL && word != RIGHT_NULL {
+                // SAFETY: No other process can access the deque, and every
+                // word that is not a null points to a value that is still
+                // in the deque.
+                drop(unsafe { Box::from_raw(word as *mut T) });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    type HerlihyLuchangcoMoir = HLMDeque<u32, 2, 4>;
+
+    #[test]
+    fn pop_returns_none_if_empty() {
+        let deque = HerlihyLuchangcoMoir::new();
+        assert_eq!(deque.pop_front(0), None);
+        assert_eq!(deque.pop_back(1), None);
+    }
+
+    #[test]
+    fn pops_values_from_either_end() {
+        let deque = HerlihyLuchangcoMoir::new();
+        deque.push_back(0, 2).unwrap();
+        deque.push_front(1, 1).unwrap();
+        deque.push_back(0, 3).unwrap();
+        assert_eq!(deque.pop_back(1), Some(3));
+        assert_eq!(deque.pop_front(0), Some(1));
+        assert_eq!(deque.pop_front(1), Some(2));
+        assert_eq!(deque.pop_back(0), None);
+    }
+
+    #[test]
+    fn pops_values_pushed_onto_other_end() {
+        let deque = HerlihyLuchangcoMoir::new();
+        deque.push_front(0, 1).unwrap();
+        deque.push_front(0, 2).unwrap();
+        assert_eq!(deque.pop_back(1), Some(1));
+        assert_eq!(deque.pop_back(1), Some(2));
+        assert_eq!(deque.pop_front(0), None);
+    }
+
+    #[test]
+    fn push_returns_value_if_end_is_full() {
+        let deque = HerlihyLuchangcoMoir::new();
+        deque.push_back(0, 1).unwrap();
+        deque.push_back(0, 2).unwrap();
+        assert_eq!(deque.push_back(0, 3), Err(3));
+        deque.push_front(1, 4).unwrap();
+        deque.push_front(1, 5).unwrap();
+        assert_eq!(deque.push_front(1, 6), Err(6));
+    }
+
+    #[test]
+    fn pop_makes_room_at_that_end() {
+        let deque = HerlihyLuchangcoMoir::new();
+        deque.push_back(0, 1).unwrap();
+        deque.push_back(0, 2).unwrap();
+        assert_eq!(deque.pop_back(0), Some(2));
+        deque.push_back(0, 3).unwrap();
+        assert_eq!(deque.pop_front(0), Some(1));
+        assert_eq!(deque.pop_front(0), Some(3));
+    }
+
+    #[test]
+    fn drops_remaining_values() {
+        let value = Arc::new(());
+        let deque: HLMDeque<Arc<()>, 1, 4> = HLMDeque::new();
+        deque.push_front(0, value.clone()).unwrap();
+        deque.push_back(0, value.clone()).unwrap();
+        drop(deque.pop_back(0));
+        assert_eq!(Arc::strong_count(&value), 2);
+        drop(deque);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+}
@@ -0,0 +1,151 @@
+//! Fetch-and-add objects.
+//!
+//! A fetch-and-add object contains a single integer, which is initially `0`.
+//! The [`fetch_and_add`](FetchAndAdd::fetch_and_add) operation adds a value to
+//! the integer and returns its previous value.
+//!
+//! Like [test-and-set](crate::test_and_set), fetch-and-add has consensus
+//! number 2, and so cannot be implemented from registers in a wait-free
+//! manner. This module contains both a [`NativeFetchAndAdd`], which makes
+//! use of the hardware instruction, and a [`LockFetchAndAdd`], which is built
+//! from registers and any of the [mutual exclusion](crate::mutex) algorithms.
+//!
+//! # Examples
+//!
+//! Hand out unique tickets to a set of threads.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::thread;
+//! use todc_mem::fetch_and_add::{BakeryFetchAndAdd, FetchAndAdd};
+//!
+//! const N: usize = 3;
+//!
+//! let counter: Arc<BakeryFetchAndAdd<N>> = Arc::new(BakeryFetchAndAdd::new());
+//!
+//! let handles: Vec<_> = (0..N)
+//!     .map(|i| {
+//!         let counter = counter.clone();
+//!         thread::spawn(move || counter.fetch_and_add(i, 1))
+//!     })
+//!     .collect();
+//!
+//! let mut tickets: Vec<u64> = handles
+//!     .into_iter()
+//!     .map(|handle| handle.join().unwrap())
+//!     .collect();
+//! tickets.sort();
+//! assert_eq!(tickets, vec![0, 1, 2]);
+//! ```
+use crate::mutex::{AtomicBakeryLock, Lock};
+use crate::register::{AtomicRegister, Register};
+use crate::snapshot::ProcessId;
+use crate::sync::{AtomicU64, Ordering};
+
+/// An `N`-process fetch-and-add object.
+pub trait FetchAndAdd<const N: usize> {
+    /// Creates a new fetch-and-add object, containing `0`.
+    fn new() -> Self;
+
+    /// Adds `delta` to the contained value, and returns the previous value.
+    ///
+    /// Additions wrap around on overflow.
+    fn fetch_and_add(&self, i: ProcessId, delta: u64) -> u64;
+}
+
+/// A fetch-and-add object backed by an [`AtomicU64`].
+///
+/// This object is wait-free for any number of processes, and is provided
+/// as a baseline against which implementations from weaker primitives
+/// can be compared.
+#[derive(Debug)]
+pub struct NativeFetchAndAdd {
+    value: AtomicU64,
+}
+
+impl<const N: usize> FetchAndAdd<N> for NativeFetchAndAdd {
+    fn new() -> Self {
+        Self {
+            value: AtomicU64::new(0),
+        }
+    }
+
+    fn fetch_and_add(&self, _: ProcessId, delta: u64) -> u64 {
+        self.value.fetch_add(delta, Ordering::SeqCst)
+    }
+}
+
+/// An `N`-process fetch-and-add object, using an [`AtomicBakeryLock`] and
+/// [`AtomicRegister`] objects.
+///
+/// For implementation details, see [`LockFetchAndAdd`].
+pub type BakeryFetchAndAdd<const N: usize> =
+    LockFetchAndAdd<AtomicBakeryLock<N>, AtomicRegister<u64>, N>;
+
+/// An `N`-process fetch-and-add object, built from a register and a lock.
+///
+/// Each operation reads and then writes the register while holding the
+/// lock. This object is only as strong as the lock `L`, and so is **not**
+/// lock-free.
+pub struct LockFetchAndAdd<L: Lock<N>, R: Register<Value = u64>, const N: usize> {
+    lock: L,
+    value: R,
+}
+
+impl<L: Lock<N>, R: Register<Value = u64>, const N: usize> FetchAndAdd<N>
+    for LockFetchAndAdd<L, R, N>
+{
+    fn new() -> Self {
+        Self {
+            lock: L::new(),
+            value: R::new(),
+        }
+    }
+
+    fn fetch_and_add(&self, i: ProcessId, delta: u64) -> u64 {
+        self.lock.lock(i);
+        let value = self.value.read();
+        self.value.write(value.wrapping_add(delta));
+        self.lock.unlock(i);
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod native_fetch_and_add {
+        use super::*;
+
+        #[test]
+        fn returns_previous_value() {
+            let faa: NativeFetchAndAdd = FetchAndAdd::<1>::new();
+            assert_eq!(FetchAndAdd::<1>::fetch_and_add(&faa, 0, 5), 0);
+            assert_eq!(FetchAndAdd::<1>::fetch_and_add(&faa, 0, 5), 5);
+        }
+    }
+
+    mod lock_fetch_and_add {
+        use super::*;
+        use crate::mutex::MutexFilterLock;
+        use crate::register::MutexRegister;
+
+        #[test]
+        fn returns_previous_value() {
+            let faa: BakeryFetchAndAdd<2> = BakeryFetchAndAdd::new();
+            assert_eq!(faa.fetch_and_add(0, 5), 0);
+            assert_eq!(faa.fetch_and_add(1, 2), 5);
+            assert_eq!(faa.fetch_and_add(0, 0), 7);
+        }
+
+        #[test]
+        fn wraps_around_on_overflow() {
+            let faa: LockFetchAndAdd<MutexFilterLock<1>, MutexRegister<u64>, 1> =
+                LockFetchAndAdd::new();
+            faa.fetch_and_add(0, u64::MAX);
+            assert_eq!(faa.fetch_and_add(0, 2), u64::MAX);
+            assert_eq!(faa.fetch_and_add(0, 0), 1);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Lattice agreement objects.
+//!
+//! In the lattice agreement problem, each process [proposes](LatticeAgreement::propose)
+//! a value from a join semi-[`Lattice`], and decides on a value in return. The
+//! decisions of every process must satisfy the following properties:
+//!
+//! * **Validity:** The decision of a process is at least as large as the
+//!   value it proposed, and no larger than the join of all proposed values.
+//! * **Comparability:** The decisions of any two processes are comparable.
+//!
+//! Many constructions of [snapshot](crate::snapshot) and renaming objects
+//! are phrased in terms of lattice agreement. For example, the snapshot
+//! implementation of Attiya and Rachman
+//! [\[AR93\]](https://doi.org/10.1137/S0097539795279463) performs lattice
+//! agreement on the views of its components. Conversely, lattice agreement
+//! can be solved using any snapshot object, and this module contains an
+//! implementation that does exactly that.
+//!
+//! The objects in this module are _long-lived_, in that each process may
+//! propose any number of values. Each decision is at least as large as
+//! every value previously proposed by the same process, and the decisions
+//! of a process grow over time.
+//!
+//! # Examples
+//!
+//! Agree on comparable sets of proposed values.
+//!
+//! ```
+//! use std::collections::BTreeSet;
+//! use std::sync::Arc;
+//! use std::thread;
+//! use todc_mem::lattice_agreement::{LatticeAgreement, MutexLatticeAgreement};
+//!
+//! const N: usize = 3;
+//!
+//! let agreement: Arc<MutexLatticeAgreement<BTreeSet<usize>, N>> =
+//!     Arc::new(MutexLatticeAgreement::new());
+//!
+//! let handles: Vec<_> = (0..N)
+//!     .map(|i| {
+//!         let agreement = agreement.clone();
+//!         thread::spawn(move || agreement.propose(i, BTreeSet::from([i])))
+//!     })
+//!     .collect();
+//!
+//! let mut decisions: Vec<BTreeSet<usize>> = handles
+//!     .into_iter()
+//!     .map(|handle| handle.join().unwrap())
+//!     .collect();
+//!
+//! // Decisions are comparable, and so form a chain.
+//! decisions.sort_by_key(|decision| decision.len());
+//! for pair in decisions.windows(2) {
+//!     assert!(pair[0].is_subset(&pair[1]));
+//! }
+//! ```
+use std::collections::BTreeSet;
+
+use crate::snapshot::{ProcessId, Snapshot, UnboundedMutexSnapshot};
+
+/// A join semi-lattice, whose default value is its least element.
+pub trait Lattice: Clone + Default {
+    /// Returns the least upper bound of two values.
+    fn join(&self, other: &Self) -> Self;
+}
+
+impl<T: Clone + Ord> Lattice for BTreeSet<T> {
+    fn join(&self, other: &Self) -> Self {
+        self.union(other).cloned().collect()
+    }
+}
+
+macro_rules! impl_lattice_for_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl Lattice for $t {
+                fn join(&self, other: &Self) -> Self {
+                    *self.max(other)
+                }
+            }
+        )*
+    };
+}
+
+impl_lattice_for_unsigned!(u8, u16, u32, u64, usize);
+
+/// An `N`-process long-lived lattice agreement object.
+pub trait LatticeAgreement<const N: usize> {
+    type Value: Lattice;
+
+    /// Creates a lattice agreement object.
+    fn new() -> Self;
+
+    /// Proposes a value on behalf of the _i^{th}_ process, and returns its
+    /// decision.
+    fn propose(&self, i: ProcessId, value: Self::Value) -> Self::Value;
+}
+
+/// An `N`-process lattice agreement object, using an [`UnboundedMutexSnapshot`].
+///
+/// This object is **not** lock-free. For implementation details, see
+/// [`SnapshotLatticeAgreement`].
+pub type MutexLatticeAgreement<T, const N: usize> =
+    SnapshotLatticeAgreement<UnboundedMutexSnapshot<T, N>, N>;
+
+/// An `N`-process lattice agreement object, built from a snapshot object.
+///
+/// The _i^{th}_ component of the snapshot contains the join of all values
+/// proposed by the _i^{th}_ process. To propose a value, a process joins it
+/// into its component, and then decides on the join of every component in a
+/// [`scan`](Snapshot::scan) of the snapshot. Since components only grow, and
+/// scans are linearizable, the decisions of any two processes are comparable.
+///
+/// This object is wait-free if, and only if, the snapshot `S` is.
+pub struct SnapshotLatticeAgreement<S: Snapshot<N>, const N: usize> {
+    snapshot: S,
+}
+
+impl<S: Snapshot<N>, const N: usize> LatticeAgreement<N> for SnapshotLatticeAgreement<S, N>
+where
+    S::Value: Lattice,
+{
+    type Value = S::Value;
+
+    fn new() -> Self {
+        Self { snapshot: S::new() }
+    }
+
+    fn propose(&self, i: ProcessId, value: Self::Value) -> Self::Value {
+        let proposed = self.snapshot.scan(i)[i].join(&value);
+        self.snapshot.update(i, proposed);
+        self.snapshot
+            .scan(i)
+            .iter()
+            .fold(Self::Value::default(), |joined, value| joined.join(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod lattice {
+        use super::*;
+
+        #[test]
+        fn join_of_sets_is_union() {
+            let a = BTreeSet::from([1, 2]);
+            let b = BTreeSet::from([2, 3]);
+            assert_eq!(a.join(&b), BTreeSet::from([1, 2, 3]));
+        }
+
+        #[test]
+        fn join_of_integers_is_maximum() {
+            assert_eq!(3_u32.join(&5), 5);
+            assert_eq!(5_u32.join(&3), 5);
+        }
+    }
+
+    mod snapshot_lattice_agreement {
+        use super::*;
+
+        type Agreement = MutexLatticeAgreement<BTreeSet<u32>, 3>;
+
+        #[test]
+        fn decides_on_proposed_value_when_alone() {
+            let agreement = Agreement::new();
+            assert_eq!(
+                agreement.propose(0, BTreeSet::from([1])),
+                BTreeSet::from([1])
+            );
+        }
+
+        #[test]
+        fn decides_on_join_of_earlier_proposals() {
+            let agreement = Agreement::new();
+            agreement.propose(0, BTreeSet::from([1]));
+            agreement.propose(1, BTreeSet::from([2]));
+            assert_eq!(
+                agreement.propose(2, BTreeSet::from([3])),
+                BTreeSet::from([1, 2, 3])
+            );
+        }
+
+        #[test]
+        fn decisions_include_own_earlier_proposals() {
+            let agreement = Agreement::new();
+            agreement.propose(0, BTreeSet::from([1]));
+            assert_eq!(agreement.propose(0, BTreeSet::new()), BTreeSet::from([1]));
+        }
+    }
+}
@@ -0,0 +1,590 @@
+//! An experimental tamper-evident ledger, built on top of a snapshot object.
+//!
+//! Each process owns a [hash chain](https://en.wikipedia.org/wiki/Hash_chain)
+//! of values, stored in its component of an `N`-process [`Snapshot`]. Appending
+//! a value to the chain extends it with a new [`Link`], whose digest commits to
+//! the value, its height in the chain, and the digest of the previous link.
+//! Digests are computed over a canonical encoding of each value, given by its
+//! implementation of [`Encode`], and so are the same on every platform.
+//!
+//! Scanning the ledger returns a [`Receipt`], which contains the most recent
+//! link of every chain along with a single digest of the entire view. Because
+//! snapshot scans are linearizable, the views contained in any two receipts are
+//! always _comparable_, meaning that one of them contains at least as many links
+//! of every chain as the other. This makes it possible to audit, after the fact,
+//! that a set of reported scans were consistent with one another, and with the
+//! values that each process claims to have appended.
+//!
+//! # Progress
+//!
+//! If the underlying snapshot is wait-free, then so are all operations on the
+//! ledger, since each process keeps the end of its own chain behind a lock
+//! that no other process takes. However, due to the size constraints of
+//! [`AtomicRegister`](crate::register::AtomicRegister), there is not yet a
+//! wait-free snapshot that can store links, and so this module only provides
+//! the [`MutexLedger`], which is **not** lock-free.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_mem::ledger::MutexLedger;
+//!
+//! let ledger: MutexLedger<u32, 3> = MutexLedger::new();
+//!
+//! ledger.append(0, 10);
+//! ledger.append(1, 20);
+//! ledger.append(0, 11);
+//!
+//! let receipt = ledger.scan(2);
+//! assert_eq!(receipt.values(), [11, 20, 0]);
+//!
+//! // The receipt can be checked against the values that each
+//! // process reports having appended.
+//! assert!(receipt.verify());
+//! assert!(receipt.audit(&[vec![10, 11], vec![20], vec![]]));
+//!
+//! // A log that omits an appended value does not match the receipt.
+//! assert!(!receipt.audit(&[vec![11], vec![20], vec![]]));
+//! ```
+use core::array::from_fn;
+
+use sha2::{Digest as _, Sha256};
+
+use crate::snapshot::{BoundedMutexSnapshot, ProcessId, Snapshot};
+use crate::sync::Mutex;
+
+/// A SHA-256 digest.
+pub type Digest = [u8; 32];
+
+/// An `N`-process ledger backed by a
+/// [`BoundedMutexSnapshot`](crate::snapshot::BoundedMutexSnapshot).
+///
+/// This ledger is **not** lock-free.
+pub type MutexLedger<T, const N: usize> = Ledger<BoundedMutexSnapshot<Link<T>, N>, N>;
+
+/// A value with a canonical encoding as bytes, from which its digest is
+/// computed.
+///
+/// Encodings must be the same on every platform, and distinct values must
+/// have distinct encodings. Integers are encoded in little-endian byte order,
+/// with [`usize`] and [`isize`] widened to `64` bits, and sequences are
+/// prefixed with their length, so that the encoding of a value is never a
+/// prefix of the encoding of another value of the same type.
+///
+/// # Examples
+///
+/// ```
+/// use todc_mem::ledger::Encode;
+///
+/// let mut bytes = Vec::new();
+/// "Hi".encode(&mut bytes);
+/// assert_eq!(bytes, [2, 0, 0, 0, 0, 0, 0, 0, b'H', b'i']);
+/// ```
+pub trait Encode {
+    /// Appends the encoding of this value to `bytes`.
+    fn encode(&self, bytes: &mut Vec<u8>);
+}
+
+macro_rules! impl_encode_for_int {
+    ($($int:ty),*) => {
+        $(
+            impl Encode for $int {
+                fn encode(&self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_le_bytes())
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_for_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Encode for usize {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        (*self as u64).encode(bytes)
+    }
+}
+
+impl Encode for isize {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        (*self as i64).encode(bytes)
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        u8::from(*self).encode(bytes)
+    }
+}
+
+impl Encode for char {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        u32::from(*self).encode(bytes)
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.len().encode(bytes);
+        bytes.extend_from_slice(self.as_bytes())
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.as_str().encode(bytes)
+    }
+}
+
+impl<T: Encode> Encode for [T] {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.len().encode(bytes);
+        for value in self {
+            value.encode(bytes)
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.as_slice().encode(bytes)
+    }
+}
+
+impl<T: Encode, const M: usize> Encode for [T; M] {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        for value in self {
+            value.encode(bytes)
+        }
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            None => false.encode(bytes),
+            Some(value) => {
+                true.encode(bytes);
+                value.encode(bytes)
+            }
+        }
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        self.0.encode(bytes);
+        self.1.encode(bytes)
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        (**self).encode(bytes)
+    }
+}
+
+/// A link in the hash chain belonging to a single process.
+///
+/// The default link has height `0` and a digest of all zeros, and is the
+/// first link of every chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Link<T> {
+    value: T,
+    height: u64,
+    digest: Digest,
+}
+
+impl<T: Encode> Link<T> {
+    /// Returns a new link that extends this one with the specified value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_mem::ledger::Link;
+    ///
+    /// let first = Link::default().append("Hello");
+    /// let second = first.append("World");
+    /// assert_eq!(second.height(), 2);
+    /// assert!(second.follows(&first));
+    /// ```
+    pub fn append(&self, value: T) -> Self {
+        let height = self.height + 1;
+        let digest = Self::digest_of(&self.digest, height, &value);
+        Self {
+            value,
+            height,
+            digest,
+        }
+    }
+
+    /// Returns whether this link immediately follows `previous` in a chain.
+    pub fn follows(&self, previous: &Self) -> bool {
+        self.height == previous.height + 1
+            && self.digest == Self::digest_of(&previous.digest, self.height, &self.value)
+    }
+
+    fn digest_of(previous: &Digest, height: u64, value: &T) -> Digest {
+        let mut bytes = previous.to_vec();
+        height.encode(&mut bytes);
+        value.encode(&mut bytes);
+        Sha256::digest(bytes).into()
+    }
+}
+
+impl<T> Link<T> {
+    /// Returns the value stored in this link.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the number of links in the chain that ends with this link,
+    /// excluding the default link.
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// Returns the digest of this link.
+    pub fn digest(&self) -> Digest {
+        self.digest
+    }
+}
+
+impl<T: Clone + Default + Encode> Link<T> {
+    /// Returns the last link of the chain obtained by appending each of
+    /// the specified values, in order, starting from the default link.
+    pub fn replay<'a>(values: impl IntoIterator<Item = &'a T>) -> Self
+    where
+        T: 'a,
+    {
+        values
+            .into_iter()
+            .fold(Self::default(), |link, value| link.append(value.clone()))
+    }
+}
+
+/// The result of scanning a [`Ledger`].
+///
+/// A receipt contains the most-recent link of the chain belonging to each
+/// process, along with a digest that commits to all of them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Receipt<T, const N: usize> {
+    links: [Link<T>; N],
+    digest: Digest,
+}
+
+impl<T, const N: usize> Receipt<T, N> {
+    fn new(links: [Link<T>; N]) -> Self {
+        let digest = Self::digest_of(&links);
+        Self { links, digest }
+    }
+
+    fn digest_of(links: &[Link<T>; N]) -> Digest {
+        let mut bytes = Vec::new();
+        for link in links {
+            link.height.encode(&mut bytes);
+            link.digest.encode(&mut bytes);
+        }
+        Sha256::digest(bytes).into()
+    }
+
+    /// Returns the digest of the view contained in this receipt.
+    pub fn digest(&self) -> Digest {
+        self.digest
+    }
+
+    /// Returns the most-recent link of each chain in the view.
+    pub fn links(&self) -> &[Link<T>; N] {
+        &self.links
+    }
+
+    /// Returns whether the digest of this receipt matches the links that it
+    /// contains.
+    pub fn verify(&self) -> bool {
+        self.digest == Self::digest_of(&self.links)
+    }
+
+    /// Returns whether the view in this receipt and the view in `other` could
+    /// have both been returned by scans of the same ledger.
+    ///
+    /// Views returned by a linearizable snapshot object are totally ordered,
+    /// and so for any two receipts, one must contain at least as many links of
+    /// every chain as the other. If both views contain a chain of the same
+    /// height, then the links at the end of those chains must be identical.
+    pub fn is_comparable(&self, other: &Self) -> bool {
+        let pairs = || self.links.iter().zip(other.links.iter());
+        let same_links = pairs().all(|(a, b)| a.height != b.height || a.digest == b.digest);
+        let below = pairs().all(|(a, b)| a.height <= b.height);
+        let above = pairs().all(|(a, b)| a.height >= b.height);
+        same_links && (below || above)
+    }
+}
+
+impl<T: Clone + Default + Encode, const N: usize> Receipt<T, N> {
+    /// Returns the value at the end of each chain in the view.
+    pub fn values(&self) -> [T; N] {
+        from_fn(|i| self.links[i].value.clone())
+    }
+
+    /// Returns whether this receipt is consistent with a log of the values
+    /// that each process has appended to the ledger, in order.
+    ///
+    /// A receipt is consistent with the logs if, for each process `i`, the
+    /// chain obtained by appending a prefix of `logs[i]` ends with the link
+    /// contained in the receipt.
+    pub fn audit(&self, logs: &[Vec<T>; N]) -> bool {
+        self.verify()
+            && self.links.iter().zip(logs.iter()).all(|(link, log)| {
+                match usize::try_from(link.height) {
+                    Ok(height) if height <= log.len() => {
+                        Link::replay(&log[..height]).digest == link.digest
+                    }
+                    _ => false,
+                }
+            })
+    }
+}
+
+/// An `N`-process ledger of hash-chained values.
+///
+/// See the [`ledger`](crate::ledger) module-level documentation for more
+/// details.
+pub struct Ledger<S: Snapshot<N>, const N: usize> {
+    snapshot: S,
+    /// The last link of the chain belonging to each process.
+    tails: [Mutex<S::Value>; N],
+}
+
+impl<T, S, const N: usize> Ledger<S, N>
+where
+    T: Clone + Default + Encode,
+    S: Snapshot<N, Value = Link<T>>,
+{
+    /// Creates a new ledger, in which every chain is empty.
+    pub fn new() -> Self {
+        Self {
+            snapshot: S::new(),
+            tails: from_fn(|_| Mutex::new(Link::default())),
+        }
+    }
+
+    /// Appends a value to the chain belonging to the _i^{th}_ process, and
+    /// returns the newly created link.
+    pub fn append(&self, i: ProcessId, value: T) -> Link<T> {
+        // Only process i ever locks the end of its own chain, so the lock is
+        // never contended, and appending does not need to scan the snapshot.
+        let mut tail = self.tails[i].lock().unwrap();
+        let link = tail.append(value);
+        self.snapshot.update(i, link.clone());
+        *tail = link.clone();
+        link
+    }
+
+    /// Returns a receipt containing a consistent view of every chain.
+    pub fn scan(&self, i: ProcessId) -> Receipt<T, N> {
+        Receipt::new(self.snapshot.scan(i))
+    }
+}
+
+impl<T, S, const N: usize> Default for Ledger<S, N>
+where
+    T: Clone + Default + Encode,
+    S: Snapshot<N, Value = Link<T>>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod link {
+        use super::*;
+
+        #[test]
+        fn default_has_height_zero() {
+            assert_eq!(Link::<u32>::default().height(), 0);
+        }
+
+        #[test]
+        fn append_increments_height() {
+            let link = Link::default().append(1).append(2);
+            assert_eq!(link.height(), 2);
+            assert_eq!(*link.value(), 2);
+        }
+
+        #[test]
+        fn follows_previous_link() {
+            let first = Link::default().append(1);
+            let second = first.append(2);
+            assert!(second.follows(&first));
+            assert!(!first.follows(&second));
+        }
+
+        #[test]
+        fn does_not_follow_link_with_different_history() {
+            let first = Link::default().append(1);
+            let other = Link::default().append(3);
+            assert!(!first.append(2).follows(&other));
+        }
+
+        #[test]
+        fn digest_commits_to_canonical_encoding() {
+            let mut bytes = vec![0; 32];
+            bytes.extend_from_slice(&1u64.to_le_bytes());
+            bytes.extend_from_slice(&7u32.to_le_bytes());
+            let expected: Digest = Sha256::digest(bytes).into();
+            assert_eq!(Link::default().append(7u32).digest(), expected);
+        }
+
+        #[test]
+        fn digest_does_not_depend_on_width_of_usize() {
+            let link = Link::default().append(7usize);
+            assert_eq!(link.digest(), Link::default().append(7u64).digest());
+        }
+
+        #[test]
+        fn replay_matches_repeated_appends() {
+            let link = Link::default().append(1).append(2).append(3);
+            assert_eq!(Link::replay(&[1, 2, 3]), link);
+        }
+    }
+
+    mod receipt {
+        use super::*;
+
+        #[test]
+        fn verifies_untampered_receipt() {
+            let ledger: MutexLedger<u32, 2> = MutexLedger::new();
+            ledger.append(0, 1);
+            assert!(ledger.scan(1).verify());
+        }
+
+        #[test]
+        fn does_not_verify_tampered_receipt() {
+            let ledger: MutexLedger<u32, 2> = MutexLedger::new();
+            ledger.append(0, 1);
+            let mut receipt = ledger.scan(1);
+            receipt.links[0] = Link::default().append(2);
+            assert!(!receipt.verify());
+        }
+
+        #[test]
+        fn audit_rejects_reordered_log() {
+            let ledger: MutexLedger<u32, 2> = MutexLedger::new();
+            ledger.append(0, 1);
+            ledger.append(0, 2);
+            let receipt = ledger.scan(1);
+            assert!(receipt.audit(&[vec![1, 2], vec![]]));
+            assert!(!receipt.audit(&[vec![2, 1], vec![]]));
+        }
+
+        #[test]
+        fn audit_accepts_log_with_later_values() {
+            let ledger: MutexLedger<u32, 2> = MutexLedger::new();
+            ledger.append(0, 1);
+            let receipt = ledger.scan(1);
+            ledger.append(0, 2);
+            assert!(receipt.audit(&[vec![1, 2], vec![]]));
+        }
+
+        #[test]
+        fn sequential_scans_are_comparable() {
+            let ledger: MutexLedger<u32, 2> = MutexLedger::new();
+            let first = ledger.scan(0);
+            ledger.append(0, 1);
+            let second = ledger.scan(1);
+            assert!(first.is_comparable(&second));
+            assert!(second.is_comparable(&first));
+        }
+
+        #[test]
+        fn views_with_crossing_heights_are_not_comparable() {
+            let a = Receipt::new([Link::default().append(1), Link::default()]);
+            let b = Receipt::new([Link::default(), Link::default().append(1)]);
+            assert!(!a.is_comparable(&b));
+        }
+
+        #[test]
+        fn views_with_forked_chains_are_not_comparable() {
+            let a = Receipt::new([Link::default().append(1), Link::default()]);
+            let b = Receipt::new([Link::default().append(2), Link::default()]);
+            assert!(!a.is_comparable(&b));
+        }
+    }
+
+    mod encode {
+        use super::*;
+
+        fn encoding(value: impl Encode) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            value.encode(&mut bytes);
+            bytes
+        }
+
+        #[test]
+        fn encodes_integers_in_little_endian_order() {
+            assert_eq!(encoding(0x0102u16), [0x02, 0x01]);
+            assert_eq!(encoding(-2i32), [0xfe, 0xff, 0xff, 0xff]);
+        }
+
+        #[test]
+        fn prefixes_sequences_with_their_length() {
+            assert_eq!(encoding(vec![1u8, 2]), [2, 0, 0, 0, 0, 0, 0, 0, 1, 2]);
+        }
+
+        #[test]
+        fn distinguishes_how_values_are_split() {
+            let a = (String::from("ab"), String::from("c"));
+            let b = (String::from("a"), String::from("bc"));
+            assert_ne!(encoding(a), encoding(b));
+        }
+    }
+
+    mod ledger {
+        use super::*;
+        use std::sync::Arc;
+        use std::thread;
+
+        #[test]
+        fn append_returns_end_of_chain() {
+            let ledger: MutexLedger<u32, 2> = MutexLedger::new();
+            let first = ledger.append(0, 1);
+            let second = ledger.append(0, 2);
+            assert!(second.follows(&first));
+            assert_eq!(ledger.scan(1).links()[0], second);
+        }
+
+        #[test]
+        fn concurrent_scans_are_pairwise_comparable() {
+            const N: usize = 4;
+            let ledger: Arc<MutexLedger<usize, N>> = Arc::new(MutexLedger::new());
+            let mut handles = Vec::new();
+            for i in 0..N {
+                let ledger = ledger.clone();
+                handles.push(thread::spawn(move || {
+                    let mut receipts = Vec::new();
+                    for j in 0..50 {
+                        ledger.append(i, j);
+                        receipts.push(ledger.scan(i));
+                    }
+                    receipts
+                }));
+            }
+            let receipts: Vec<Receipt<usize, N>> = handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect();
+
+            let logs = from_fn(|_| (0..50).collect());
+            for a in receipts.iter() {
+                assert!(a.audit(&logs));
+                for b in receipts.iter() {
+                    assert!(a.is_comparable(b));
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,16 @@
 //! Algorithms for shared-memory distributed systems.
+pub mod allocation;
+pub mod consensus;
+pub mod deque;
+pub mod fetch_and_add;
+pub mod lattice_agreement;
+pub mod ledger;
+pub mod mutex;
+pub mod queue;
 pub mod register;
+pub mod safe_agreement;
+pub mod simulator;
 pub mod snapshot;
-pub(crate) mod sync;
+pub mod stack;
+pub mod sync;
+pub mod test_and_set;
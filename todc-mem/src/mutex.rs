@@ -0,0 +1,104 @@
+//! Mutual exclusion algorithms.
+//!
+//! This module contains classic algorithms for solving the
+//! [mutual exclusion](https://en.wikipedia.org/wiki/Mutual_exclusion) problem
+//! among `N` processes, using only read/write registers. All implementations are
+//! abstracted over a generic type `R: Register`, which is the type of primitive
+//! used to store data.
+//!
+//! Each algorithm relies on the underlying registers being at least sequentially
+//! consistent, which is the case for both
+//! [`AtomicRegister`](crate::register::AtomicRegister) and
+//! [`MutexRegister`](crate::register::MutexRegister). None of the algorithms in
+//! this module are lock-free, by definition.
+//!
+//! The module also contains a [`TicketLock`], which is built from a
+//! [fetch-and-add](crate::fetch_and_add) object and serves processes in the
+//! order in which they arrive, and a [`RegisterRwLock`], which implements the
+//! [`RwLock`] trait and allows many readers to hold the lock at once.
+//!
+//! # Examples
+//!
+//! Protect a critical section that is shared by a pair of threads.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::sync::atomic::{AtomicU32, Ordering};
+//! use std::thread;
+//! use todc_mem::mutex::{AtomicPetersonLock, Lock};
+//!
+//! let lock: Arc<AtomicPetersonLock> = Arc::new(AtomicPetersonLock::new());
+//! let counter = Arc::new(AtomicU32::new(0));
+//!
+//! let handles: Vec<_> = (0..2)
+//!     .map(|i| {
+//!         let lock = lock.clone();
+//!         let counter = counter.clone();
+//!         thread::spawn(move || {
+//!             for _ in 0..100 {
+//!                 lock.lock(i);
+//!                 // Without the lock, concurrent increments could be lost.
+//!                 let value = counter.load(Ordering::Relaxed);
+//!                 counter.store(value + 1, Ordering::Relaxed);
+//!                 lock.unlock(i);
+//!             }
+//!         })
+//!     })
+//!     .collect();
+//!
+//! for handle in handles {
+//!     handle.join().unwrap();
+//! }
+//! assert_eq!(counter.load(Ordering::Relaxed), 200);
+//! ```
+mod bakery;
+mod filter;
+mod peterson;
+mod rw;
+mod ticket;
+
+pub use self::bakery::{AtomicBakeryLock, BakeryLock, MutexBakeryLock};
+pub use self::filter::{AtomicFilterLock, FilterLock, MutexFilterLock};
+pub use self::peterson::{AtomicPetersonLock, MutexPetersonLock, PetersonLock};
+pub use self::rw::{AtomicRwLock, MutexRwLock, RegisterRwLock};
+pub use self::ticket::{NativeTicketLock, TicketLock};
+
+pub use crate::snapshot::ProcessId;
+
+/// An `N`-process mutual exclusion lock.
+///
+/// Each process must identify itself with a unique [`ProcessId`] in `0..N`,
+/// and must only call [`unlock`](Lock::unlock) after a matching call to
+/// [`lock`](Lock::lock).
+pub trait Lock<const N: usize> {
+    /// Creates a new, unlocked, lock.
+    fn new() -> Self;
+
+    /// Blocks until the _i^{th}_ process has acquired the lock.
+    fn lock(&self, i: ProcessId);
+
+    /// Releases the lock held by the _i^{th}_ process.
+    fn unlock(&self, i: ProcessId);
+}
+
+/// An `N`-process readers-writer lock, which can be held by any number of
+/// readers at once, or by a single writer.
+///
+/// Each process must identify itself with a unique [`ProcessId`] in `0..N`,
+/// and must only release the lock in the same mode in which it acquired it.
+pub trait RwLock<const N: usize> {
+    /// Creates a new, unlocked, lock.
+    fn new() -> Self;
+
+    /// Blocks until the _i^{th}_ process has acquired the lock as a reader.
+    fn read_lock(&self, i: ProcessId);
+
+    /// Releases the lock held as a reader by the _i^{th}_ process.
+    fn read_unlock(&self, i: ProcessId);
+
+    /// Blocks until the _i^{th}_ process has acquired the lock as a writer.
+    fn write_lock(&self, i: ProcessId);
+
+    /// Releases the lock held as a writer by the _i^{th}_ process.
+    fn write_unlock(&self, i: ProcessId);
+}
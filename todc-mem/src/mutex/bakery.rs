@@ -0,0 +1,113 @@
+use core::array::from_fn;
+
+use crate::mutex::{Lock, ProcessId};
+use crate::register::{AtomicRegister, MutexRegister, Register};
+use crate::sync::spin_loop;
+
+/// An `N`-process lock, backed by [`AtomicRegister`] objects.
+///
+/// For implementation details, see [`BakeryLock`].
+pub type AtomicBakeryLock<const N: usize> = BakeryLock<AtomicRegister<u64>, N>;
+
+/// An `N`-process lock, backed by [`MutexRegister`] objects.
+///
+/// For implementation details, see [`BakeryLock`].
+pub type MutexBakeryLock<const N: usize> = BakeryLock<MutexRegister<u64>, N>;
+
+/// An `N`-process lock, as described by Lamport
+/// [\[Lam74\]](https://doi.org/10.1145/361082.361093).
+///
+/// Like customers in a bakery, each process that wants to enter the critical
+/// section takes a numbered _label_ that is larger than all labels it has
+/// seen, and then waits until it holds the smallest label of any interested
+/// process. Ties are broken by process ID. Processes acquire the lock in
+/// first-come-first-served order.
+///
+/// Labels are never reset, not even when the lock is released, and each
+/// lock operation may take a label that is one larger than any label before
+/// it. Labels therefore grow without bound, and behaviour is undefined once
+/// more than [`u64::MAX`] lock operations have been performed in total.
+pub struct BakeryLock<R: Register<Value = u64>, const N: usize> {
+    flags: [R; N],
+    labels: [R; N],
+}
+
+impl<R: Register<Value = u64>, const N: usize> BakeryLock<R, N> {
+    /// Returns whether the _k^{th}_ process is interested in the lock and
+    /// should go before the _i^{th}_.
+    fn goes_before(&self, k: ProcessId, i: ProcessId, label: u64) -> bool {
+        self.flags[k].read() == 1 && (self.labels[k].read(), k) < (label, i)
+    }
+}
+
+impl<R: Register<Value = u64>, const N: usize> Lock<N> for BakeryLock<R, N> {
+    fn new() -> Self {
+        Self {
+            flags: from_fn(|_| R::new()),
+            labels: from_fn(|_| R::new()),
+        }
+    }
+
+    fn lock(&self, i: ProcessId) {
+        self.flags[i].write(1);
+        let label = self.labels.iter().map(|l| l.read()).max().unwrap() + 1;
+        self.labels[i].write(label);
+        while (0..N).any(|k| k != i && self.goes_before(k, i, label)) {
+            spin_loop();
+        }
+    }
+
+    fn unlock(&self, i: ProcessId) {
+        self.flags[i].write(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn locks_and_unlocks() {
+        let lock: AtomicBakeryLock<3> = AtomicBakeryLock::new();
+        for i in 0..3 {
+            lock.lock(i);
+            lock.unlock(i);
+        }
+    }
+
+    #[test]
+    fn takes_larger_label_than_previous_process() {
+        let lock: MutexBakeryLock<2> = MutexBakeryLock::new();
+        lock.lock(0);
+        lock.unlock(0);
+        lock.lock(1);
+        assert!(lock.labels[1].read() > lock.labels[0].read());
+        lock.unlock(1);
+    }
+
+    #[test]
+    fn protects_critical_section() {
+        const N: usize = 4;
+        let lock: Arc<AtomicBakeryLock<N>> = Arc::new(AtomicBakeryLock::new());
+        let counter = Arc::new(MutexRegister::<u64>::new());
+        let handles: Vec<_> = (0..N)
+            .map(|i| {
+                let lock = lock.clone();
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..250 {
+                        lock.lock(i);
+                        counter.write(counter.read() + 1);
+                        lock.unlock(i);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.read(), 1000);
+    }
+}
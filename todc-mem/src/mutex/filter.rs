@@ -0,0 +1,106 @@
+use core::array::from_fn;
+
+use crate::mutex::{Lock, ProcessId};
+use crate::register::{AtomicRegister, MutexRegister, Register};
+use crate::sync::spin_loop;
+
+/// An `N`-process lock, backed by [`AtomicRegister`] objects.
+///
+/// For implementation details, see [`FilterLock`].
+pub type AtomicFilterLock<const N: usize> = FilterLock<AtomicRegister<u64>, N>;
+
+/// An `N`-process lock, backed by [`MutexRegister`] objects.
+///
+/// For implementation details, see [`FilterLock`].
+pub type MutexFilterLock<const N: usize> = FilterLock<MutexRegister<u64>, N>;
+
+/// An `N`-process lock that generalizes [`PetersonLock`](super::PetersonLock)
+/// to `N` processes, as described by Peterson
+/// [\[Pet81\]](https://doi.org/10.1016/0020-0190(81)90106-X).
+///
+/// A process acquires the lock by passing through `N - 1` _levels_. At each
+/// level, at least one process that is trying to enter is held back as the
+/// victim, so that at most `N - L` processes can reach level `L`, and only a
+/// single process can pass through the last level.
+pub struct FilterLock<R: Register<Value = u64>, const N: usize> {
+    levels: [R; N],
+    victims: [R; N],
+}
+
+impl<R: Register<Value = u64>, const N: usize> FilterLock<R, N> {
+    /// Returns whether some process other than the _i^{th}_ is at, or above,
+    /// the input level.
+    fn is_contested(&self, i: ProcessId, level: u64) -> bool {
+        (0..N).any(|k| k != i && self.levels[k].read() >= level)
+    }
+}
+
+impl<R: Register<Value = u64>, const N: usize> Lock<N> for FilterLock<R, N> {
+    fn new() -> Self {
+        Self {
+            levels: from_fn(|_| R::new()),
+            victims: from_fn(|_| R::new()),
+        }
+    }
+
+    fn lock(&self, i: ProcessId) {
+        for level in 1..N {
+            self.levels[i].write(level as u64);
+            self.victims[level].write(i as u64);
+            while self.is_contested(i, level as u64) && self.victims[level].read() == i as u64 {
+                spin_loop();
+            }
+        }
+    }
+
+    fn unlock(&self, i: ProcessId) {
+        self.levels[i].write(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn locks_and_unlocks() {
+        let lock: AtomicFilterLock<3> = AtomicFilterLock::new();
+        for i in 0..3 {
+            lock.lock(i);
+            lock.unlock(i);
+        }
+    }
+
+    #[test]
+    fn supports_a_single_process() {
+        let lock: MutexFilterLock<1> = MutexFilterLock::new();
+        lock.lock(0);
+        lock.unlock(0);
+    }
+
+    #[test]
+    fn protects_critical_section() {
+        const N: usize = 4;
+        let lock: Arc<AtomicFilterLock<N>> = Arc::new(AtomicFilterLock::new());
+        let counter = Arc::new(MutexRegister::<u64>::new());
+        let handles: Vec<_> = (0..N)
+            .map(|i| {
+                let lock = lock.clone();
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..250 {
+                        lock.lock(i);
+                        counter.write(counter.read() + 1);
+                        lock.unlock(i);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.read(), 1000);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::mutex::{Lock, ProcessId};
+use crate::register::{AtomicRegister, MutexRegister, Register};
+use crate::sync::spin_loop;
+
+/// A 2-process lock, backed by [`AtomicRegister`] objects.
+///
+/// For implementation details, see [`PetersonLock`].
+pub type AtomicPetersonLock = PetersonLock<AtomicRegister<u64>>;
+
+/// A 2-process lock, backed by [`MutexRegister`] objects.
+///
+/// For implementation details, see [`PetersonLock`].
+pub type MutexPetersonLock = PetersonLock<MutexRegister<u64>>;
+
+/// A 2-process lock, as described by Peterson
+/// [\[Pet81\]](https://doi.org/10.1016/0020-0190(81)90106-X).
+///
+/// Each process announces its interest in the lock by raising a flag, and then
+/// politely lets the other process go first by marking itself as the _victim_.
+/// A process waits only while the other process is interested and it is still
+/// the victim, which guarantees both mutual exclusion and starvation-freedom.
+pub struct PetersonLock<R: Register<Value = u64>> {
+    flags: [R; 2],
+    victim: R,
+}
+
+impl<R: Register<Value = u64>> Lock<2> for PetersonLock<R> {
+    fn new() -> Self {
+        Self {
+            flags: [R::new(), R::new()],
+            victim: R::new(),
+        }
+    }
+
+    fn lock(&self, i: ProcessId) {
+        let j = 1 - i;
+        self.flags[i].write(1);
+        self.victim.write(i as u64);
+        while self.flags[j].read() == 1 && self.victim.read() == i as u64 {
+            spin_loop();
+        }
+    }
+
+    fn unlock(&self, i: ProcessId) {
+        self.flags[i].write(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn locks_and_unlocks() {
+        let lock = AtomicPetersonLock::new();
+        lock.lock(0);
+        lock.unlock(0);
+        lock.lock(1);
+        lock.unlock(1);
+    }
+
+    #[test]
+    fn does_not_wait_if_other_process_is_not_interested() {
+        let lock = MutexPetersonLock::new();
+        lock.lock(1);
+        lock.unlock(1);
+        lock.lock(1);
+        lock.unlock(1);
+    }
+
+    #[test]
+    fn protects_critical_section() {
+        let lock = Arc::new(AtomicPetersonLock::new());
+        let counter = Arc::new(MutexRegister::<u64>::new());
+        let handles: Vec<_> = (0..2)
+            .map(|i| {
+                let lock = lock.clone();
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        lock.lock(i);
+                        counter.write(counter.read() + 1);
+                        lock.unlock(i);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.read(), 2000);
+    }
+}
@@ -0,0 +1,153 @@
+use core::array::from_fn;
+
+use crate::mutex::{AtomicBakeryLock, Lock, MutexBakeryLock, ProcessId, RwLock};
+use crate::register::{AtomicRegister, MutexRegister, Register};
+use crate::sync::spin_loop;
+
+/// An `N`-process readers-writer lock, backed by [`AtomicRegister`] objects
+/// and an [`AtomicBakeryLock`].
+///
+/// For implementation details, see [`RegisterRwLock`].
+pub type AtomicRwLock<const N: usize> = RegisterRwLock<AtomicRegister<u64>, AtomicBakeryLock<N>, N>;
+
+/// An `N`-process readers-writer lock, backed by [`MutexRegister`] objects
+/// and a [`MutexBakeryLock`].
+///
+/// For implementation details, see [`RegisterRwLock`].
+pub type MutexRwLock<const N: usize> = RegisterRwLock<MutexRegister<u64>, MutexBakeryLock<N>, N>;
+
+/// An `N`-process readers-writer lock, built from registers and a mutual
+/// exclusion lock `L`.
+///
+/// Each reader raises a flag and then checks whether a writer is present. If
+/// one is, then it lowers its flag and waits for the writer to leave before
+/// trying again. A writer first acquires `L`, which excludes other writers,
+/// then announces that it is present, and waits until every reader has
+/// lowered its flag. Since a reader raises its flag before checking for a
+/// writer, and a writer announces itself before checking the flags, at
+/// least one of them sees the other, and so no reader and writer are ever in
+/// the critical section together.
+///
+/// The lock prefers writers. Readers that arrive while a writer is present
+/// wait, and so a writer only waits for the readers that were already in the
+/// critical section, and never starves. Writers acquire the lock in the same
+/// order as they acquire `L`, which is first-come-first-served if `L` is a
+/// [`BakeryLock`](super::BakeryLock). Readers, on the other hand, can be
+/// starved by a steady stream of writers.
+pub struct RegisterRwLock<R: Register<Value = u64>, L: Lock<N>, const N: usize> {
+    readers: [R; N],
+    writer: R,
+    lock: L,
+}
+
+impl<R: Register<Value = u64>, L: Lock<N>, const N: usize> RwLock<N> for RegisterRwLock<R, L, N> {
+    fn new() -> Self {
+        Self {
+            readers: from_fn(|_| R::new()),
+            writer: R::new(),
+            lock: L::new(),
+        }
+    }
+
+    fn read_lock(&self, i: ProcessId) {
+        loop {
+            self.readers[i].write(1);
+            if self.writer.read() == 0 {
+                return;
+            }
+            self.readers[i].write(0);
+            while self.writer.read() == 1 {
+                spin_loop();
+            }
+        }
+    }
+
+    fn read_unlock(&self, i: ProcessId) {
+        self.readers[i].write(0);
+    }
+
+    fn write_lock(&self, i: ProcessId) {
+        self.lock.lock(i);
+        self.writer.write(1);
+        while (0..N).any(|k| k != i && self.readers[k].read() == 1) {
+            spin_loop();
+        }
+    }
+
+    fn write_unlock(&self, i: ProcessId) {
+        self.writer.write(0);
+        self.lock.unlock(i);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn readers_share_the_lock() {
+        let lock: AtomicRwLock<3> = AtomicRwLock::new();
+        lock.read_lock(0);
+        lock.read_lock(1);
+        assert_eq!(lock.readers.iter().map(|r| r.read()).sum::<u64>(), 2);
+        lock.read_unlock(0);
+        lock.read_unlock(1);
+        lock.write_lock(2);
+        lock.write_unlock(2);
+    }
+
+    #[test]
+    fn writers_exclude_each_other() {
+        const N: usize = 4;
+        let lock: Arc<MutexRwLock<N>> = Arc::new(MutexRwLock::new());
+        let counter = Arc::new(MutexRegister::<u64>::new());
+        let handles: Vec<_> = (0..N)
+            .map(|i| {
+                let lock = lock.clone();
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..250 {
+                        lock.write_lock(i);
+                        counter.write(counter.read() + 1);
+                        lock.write_unlock(i);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.read(), 1000);
+    }
+
+    #[test]
+    fn writer_is_not_starved_by_readers() {
+        const N: usize = 4;
+        let lock: Arc<AtomicRwLock<N>> = Arc::new(AtomicRwLock::new());
+        let written = Arc::new(AtomicBool::new(false));
+        // Readers repeatedly acquire the lock, so that it is almost always
+        // held by some reader, until the writer has written.
+        let readers: Vec<_> = (1..N)
+            .map(|i| {
+                let lock = lock.clone();
+                let written = written.clone();
+                thread::spawn(move || {
+                    while !written.load(Ordering::SeqCst) {
+                        lock.read_lock(i);
+                        thread::yield_now();
+                        lock.read_unlock(i);
+                    }
+                })
+            })
+            .collect();
+        lock.write_lock(0);
+        written.store(true, Ordering::SeqCst);
+        lock.write_unlock(0);
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::fetch_and_add::{FetchAndAdd, NativeFetchAndAdd};
+use crate::mutex::{Lock, ProcessId};
+use crate::register::{AtomicRegister, Register};
+use crate::sync::spin_loop;
+
+/// An `N`-process lock, using a [`NativeFetchAndAdd`] and an
+/// [`AtomicRegister`].
+///
+/// For implementation details, see [`TicketLock`].
+pub type NativeTicketLock<const N: usize> = TicketLock<NativeFetchAndAdd, AtomicRegister<u64>, N>;
+
+/// An `N`-process lock, built from a fetch-and-add object and a register, as
+/// described by Mellor-Crummey and Scott
+/// [\[MS91\]](https://doi.org/10.1145/103727.103729).
+///
+/// Each process that wants to enter the critical section takes the next
+/// _ticket_ from a fetch-and-add object, and then waits until the ticket is
+/// being served. A process that leaves the critical section serves the next
+/// ticket. Processes acquire the lock in the order in which they took their
+/// tickets, and so the lock is first-come-first-served. Unlike the
+/// [`BakeryLock`](super::BakeryLock), a process does not need to read the
+/// registers of every other process, and so each step of taking a ticket
+/// is independent of `N`.
+///
+/// This lock is only as strong as the fetch-and-add object `F`. Tickets wrap
+/// around on overflow, which is harmless as long as fewer than [`u64::MAX`]
+/// processes are waiting at once.
+pub struct TicketLock<F: FetchAndAdd<N>, R: Register<Value = u64>, const N: usize> {
+    next: F,
+    serving: R,
+}
+
+impl<F: FetchAndAdd<N>, R: Register<Value = u64>, const N: usize> Lock<N> for TicketLock<F, R, N> {
+    fn new() -> Self {
+        Self {
+            next: F::new(),
+            serving: R::new(),
+        }
+    }
+
+    fn lock(&self, i: ProcessId) {
+        let ticket = self.next.fetch_and_add(i, 1);
+        while self.serving.read() != ticket {
+            spin_loop();
+        }
+    }
+
+    fn unlock(&self, _: ProcessId) {
+        // Only the process that holds the lock writes to the register, and
+        // so the read and the write do not need to happen atomically.
+        self.serving.write(self.serving.read().wrapping_add(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetch_and_add::BakeryFetchAndAdd;
+    use crate::register::MutexRegister;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[test]
+    fn locks_and_unlocks() {
+        let lock: NativeTicketLock<3> = NativeTicketLock::new();
+        for i in 0..3 {
+            lock.lock(i);
+            lock.unlock(i);
+        }
+        assert_eq!(lock.serving.read(), 3);
+    }
+
+    #[test]
+    fn protects_critical_section_with_lock_based_fetch_and_add() {
+        const N: usize = 3;
+        let lock: Arc<TicketLock<BakeryFetchAndAdd<N>, MutexRegister<u64>, N>> =
+            Arc::new(TicketLock::new());
+        let counter = Arc::new(MutexRegister::<u64>::new());
+        let handles: Vec<_> = (0..N)
+            .map(|i| {
+                let lock = lock.clone();
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        lock.lock(i);
+                        counter.write(counter.read() + 1);
+                        lock.unlock(i);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.read(), 300);
+    }
+
+    #[test]
+    fn serves_tickets_in_order() {
+        const N: usize = 4;
+        let lock: Arc<NativeTicketLock<N>> = Arc::new(NativeTicketLock::new());
+        // The ticket being served each time the critical section is entered.
+        let served = Arc::new(Mutex::new(Vec::new()));
+        let handles: Vec<_> = (0..N)
+            .map(|i| {
+                let lock = lock.clone();
+                let served = served.clone();
+                thread::spawn(move || {
+                    for _ in 0..250 {
+                        lock.lock(i);
+                        served.lock().unwrap().push(lock.serving.read());
+                        lock.unlock(i);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let served = served.lock().unwrap();
+        assert!(served.iter().copied().eq(0..(N as u64 * 250)));
+    }
+}
@@ -0,0 +1,238 @@
+//! Queue objects.
+//!
+//! A queue contains a sequence of values. The [`enqueue`](Queue::enqueue)
+//! operation adds a value to the back of the queue, and the
+//! [`dequeue`](Queue::dequeue) operation removes and returns the value at the
+//! front of the queue, if there is one.
+//!
+//! This module contains an [`MSQueue`], the lock-free queue described by
+//! Michael and Scott [\[MS96\]](https://dl.acm.org/doi/10.1145/248052.248106).
+//! For bounded, wait-free queues with a single consumer, see the
+//! [`ring`] module.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::thread;
+//! use todc_mem::queue::{MSQueue, Queue};
+//!
+//! const N: usize = 3;
+//!
+//! let queue: Arc<MSQueue<usize, N>> = Arc::new(MSQueue::new());
+//!
+//! let handles: Vec<_> = (0..N)
+//!     .map(|i| {
+//!         let queue = queue.clone();
+//!         thread::spawn(move || queue.enqueue(i, i))
+//!     })
+//!     .collect();
+//! for handle in handles {
+//!     handle.join().unwrap();
+//! }
+//!
+//! let mut values: Vec<usize> = (0..N).map(|_| queue.dequeue(0).unwrap()).collect();
+//! values.sort();
+//! assert_eq!(values, vec![0, 1, 2]);
+//! assert_eq!(queue.dequeue(0), None);
+//! ```
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+use crate::snapshot::ProcessId;
+use crate::sync::{AtomicPtr, Collector, Ordering};
+
+pub mod ring;
+pub use self::ring::{MPSCConsumer, MPSCProducer, SPSCConsumer, SPSCProducer};
+
+/// An `N`-process queue.
+pub trait Queue<const N: usize> {
+    type Value;
+
+    /// Creates an empty queue.
+    fn new() -> Self;
+
+    /// Adds a value to the back of the queue, on behalf of the _i^{th}_
+    /// process.
+    fn enqueue(&self, i: ProcessId, value: Self::Value);
+
+    /// Removes and returns the value at the front of the queue, on behalf of
+    /// the _i^{th}_ process, or returns `None` if the queue is empty.
+    fn dequeue(&self, i: ProcessId) -> Option<Self::Value>;
+}
+
+/// A node in an [`MSQueue`].
+struct Node<T> {
+    // The value of the sentinel node at the head of the queue has either
+    // never been initialized, or has already been moved out by a dequeue.
+    value: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn new(value: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            value,
+            next: AtomicPtr::new(ptr::null_mut()),
+        }))
+    }
+}
+
+/// An `N`-process lock-free queue, as described by Michael and Scott.
+///
+/// The queue is a linked list of nodes, along with pointers to the nodes at
+/// its head and tail. The node at the head is a _sentinel_, whose successor
+/// holds the value at the front of the queue. An enqueue links a new node
+/// after the tail, and a dequeue advances the head to its successor, which
+/// becomes the new sentinel. Processes that observe a tail which lags behind
+/// the end of the list help to advance it, so that no process can prevent
+/// others from making progress. Removed nodes are reclaimed with a
+/// [`Collector`], which ensures that no process is still reading a node when
+/// it is destroyed, and so also avoids the _ABA problem_.
+///
+/// All operations are lock-free.
+pub struct MSQueue<T, const N: usize> {
+    head: AtomicPtr<Node<T>>,
+    tail: AtomicPtr<Node<T>>,
+    collector: Collector<N>,
+    value: PhantomData<T>,
+}
+
+impl<T: Send + 'static, const N: usize> Queue<N> for MSQueue<T, N> {
+    type Value = T;
+
+    fn new() -> Self {
+        let sentinel = Node::new(MaybeUninit::uninit());
+        Self {
+            head: AtomicPtr::new(sentinel),
+            tail: AtomicPtr::new(sentinel),
+            collector: Collector::new(),
+            value: PhantomData,
+        }
+    }
+
+    fn enqueue(&self, i: ProcessId, value: T) {
+        let node = Node::new(MaybeUninit::new(value));
+        let _guard = self.collector.pin(i);
+        loop {
+            let tail = self.tail.load(Ordering::SeqCst);
+            // SAFETY: The tail cannot be destroyed while this process is
+            // pinned, since it is only retired after it is removed as the
+            // head, which the tail never lags behind.
+            let next = unsafe { &(*tail).next }.load(Ordering::SeqCst);
+            if tail != self.tail.load(Ordering::SeqCst) {
+                continue;
+            }
+            if next.is_null() {
+                // SAFETY: As above.
+                let linked = unsafe { &(*tail).next }
+                    .compare_exchange(next, node, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok();
+                if linked {
+                    // If this fails, then another process advanced the tail.
+                    let _ =
+                        self.tail
+                            .compare_exchange(tail, node, Ordering::SeqCst, Ordering::SeqCst);
+                    return;
+                }
+            } else {
+                // Help the process that linked the next node.
+                let _ = self
+                    .tail
+                    .compare_exchange(tail, next, Ordering::SeqCst, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn dequeue(&self, i: ProcessId) -> Option<T> {
+        let guard = self.collector.pin(i);
+        loop {
+            let head = self.head.load(Ordering::SeqCst);
+            let tail = self.tail.load(Ordering::SeqCst);
+            // SAFETY: The head cannot be destroyed while this process is
+            // pinned.
+            let next = unsafe { &(*head).next }.load(Ordering::SeqCst);
+            if head != self.head.load(Ordering::SeqCst) {
+                continue;
+            }
+            if next.is_null() {
+                return None;
+            }
+            if head == tail {
+                // Help the process that linked the next node, so that the
+                // tail never lags behind the head.
+                let _ = self
+                    .tail
+                    .compare_exchange(tail, next, Ordering::SeqCst, Ordering::SeqCst);
+                continue;
+            }
+            if self
+                .head
+                .compare_exchange(head, next, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                // SAFETY: Only the process that advanced the head moves the
+                // value out of the new sentinel, which was initialized when it
+                // was enqueued. The old sentinel is no longer reachable.
+                unsafe {
+                    let value = ptr::read((*next).value.as_ptr());
+                    guard.defer_destroy(head);
+                    return Some(value);
+                }
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for MSQueue<T, N> {
+    fn drop(&mut self) {
+        // SAFETY: No other process can access the queue. The value of the
+        // sentinel is not initialized, but the value of every other node is.
+        let sentinel = unsafe { Box::from_raw(self.head.load(Ordering::SeqCst)) };
+        let mut node = sentinel.next.load(Ordering::SeqCst);
+        while !node.is_null() {
+            let mut boxed = unsafe { Box::from_raw(node) };
+            unsafe { boxed.value.assume_init_drop() };
+            node = boxed.next.load(Ordering::SeqCst);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    type MichaelScott = MSQueue<u32, 2>;
+
+    #[test]
+    fn dequeue_returns_none_if_empty() {
+        let queue = MichaelScott::new();
+        assert_eq!(queue.dequeue(0), None);
+    }
+
+    #[test]
+    fn dequeues_values_in_order() {
+        let queue = MichaelScott::new();
+        queue.enqueue(0, 1);
+        queue.enqueue(1, 2);
+        assert_eq!(queue.dequeue(1), Some(1));
+        queue.enqueue(0, 3);
+        assert_eq!(queue.dequeue(0), Some(2));
+        assert_eq!(queue.dequeue(1), Some(3));
+        assert_eq!(queue.dequeue(0), None);
+    }
+
+    #[test]
+    fn drops_remaining_values() {
+        let value = Arc::new(());
+        let queue: MSQueue<Arc<()>, 1> = MSQueue::new();
+        queue.enqueue(0, value.clone());
+        queue.enqueue(0, value.clone());
+        drop(queue.dequeue(0));
+        assert_eq!(Arc::strong_count(&value), 2);
+        drop(queue);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+}
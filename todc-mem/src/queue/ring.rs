@@ -0,0 +1,472 @@
+//! Bounded, wait-free ring buffers.
+//!
+//! A ring buffer is a queue that can hold at most `C` values at once, whose
+//! values are stored in a fixed array of slots. Unlike an [`MSQueue`], an
+//! enqueue does not allocate, but instead fails if the buffer is full.
+//!
+//! This module contains two ring buffers, both of which are built entirely
+//! from [`AtomicRegister`] objects, and so are wait-free:
+//!
+//! * [`spsc`] creates a buffer with a single producer and a single consumer,
+//!   as described by Lamport
+//!   [\[Lam83\]](https://doi.org/10.1145/69624.357207). The producer is the
+//!   only process that writes the _tail_ of the buffer, which counts the
+//!   values that have been enqueued, and the consumer is the only process
+//!   that writes the _head_, which counts the values that have been
+//!   dequeued. Each process reads the index of the other to learn which
+//!   slots it may access.
+//! * [`mpsc`] creates a buffer with `N` producers and a single consumer.
+//!   Each producer enqueues values into a single-producer single-consumer
+//!   buffer of its own, and labels each value with a timestamp that is
+//!   larger than that of any value whose enqueue completed before it began,
+//!   in the style of Lamport's bakery algorithm. The consumer dequeues the
+//!   value with the smallest timestamp among those at the front of each
+//!   buffer, ignoring values whose timestamp was chosen after the dequeue
+//!   began. This is the approach of the timestamped queue of Dodds, Haas
+//!   and Kirsch [\[DHK15\]](https://doi.org/10.1145/2775051.2676963),
+//!   which is simpler with a single consumer, since values are never
+//!   removed concurrently.
+//!
+//! Each buffer is split into a producer and a consumer handle, so that the
+//! type system guarantees that there is only ever one consumer, and that
+//! each producer of an [`mpsc`] buffer is only used by one process at a
+//! time. Both buffers are linearizable, with the exception that an enqueue
+//! into an [`mpsc`] buffer fails once `C` of the values enqueued by _that
+//! producer_ have yet to be dequeued, regardless of the values enqueued by
+//! others.
+//!
+//! The slots of both buffers are accessed without synchronization, and so
+//! their memory orderings are checked with loom, in addition to shuttle. See
+//! the [`sync`](crate::sync) module for details.
+//!
+//! [`MSQueue`]: super::MSQueue
+//!
+//! # Examples
+//!
+//! ```
+//! use std::thread;
+//! use todc_mem::queue::ring;
+//!
+//! let (mut producers, mut consumer) = ring::mpsc::<usize, 2, 4>();
+//! let handles: Vec<_> = producers
+//!     .drain(..)
+//!     .enumerate()
+//!     .map(|(i, mut producer)| thread::spawn(move || producer.enqueue(i).unwrap()))
+//!     .collect();
+//! for handle in handles {
+//!     handle.join().unwrap();
+//! }
+//!
+//! let mut values = vec![consumer.dequeue().unwrap(), consumer.dequeue().unwrap()];
+//! values.sort();
+//! assert_eq!(values, vec![0, 1]);
+//! assert_eq!(consumer.dequeue(), None);
+//! ```
+use core::mem::MaybeUninit;
+
+use std::sync::Arc;
+
+use crate::register::{AtomicRegister, Register};
+use crate::snapshot::ProcessId;
+use crate::sync::UnsafeCell;
+
+/// A single-producer single-consumer ring buffer of capacity `C`.
+///
+/// The methods of this type are unsafe, since the buffer does not itself
+/// ensure that there is only one producer and one consumer. The handles
+/// returned by [`spsc`] and [`mpsc`] ensure this instead.
+struct Ring<T, const C: usize> {
+    // The number of values that have been dequeued, written only by the
+    // consumer.
+    head: AtomicRegister<u64>,
+    // The number of values that have been enqueued, written only by the
+    // producer.
+    tail: AtomicRegister<u64>,
+    // The slots from `head` up to `tail`, modulo `C`, are initialized, and
+    // are only accessed by the consumer. The rest are only accessed by the
+    // producer.
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+}
+
+// SAFETY: Each slot is only accessed by one process at a time, and values
+// are moved from the producer to the consumer.
+unsafe impl<T: Send, const C: usize> Send for Ring<T, C> {}
+unsafe impl<T: Send, const C: usize> Sync for Ring<T, C> {}
+
+impl<T, const C: usize> Ring<T, C> {
+    fn new() -> Self {
+        Self {
+            head: AtomicRegister::new(),
+            tail: AtomicRegister::new(),
+            slots: (0..C)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+        }
+    }
+
+    fn slot(&self, index: u64) -> &UnsafeCell<MaybeUninit<T>> {
+        &self.slots[(index % C as u64) as usize]
+    }
+
+    /// Adds a value to the back of the buffer, or returns it if the buffer
+    /// is full.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called by the producer.
+    unsafe fn push(&self, value: T) -> Result<(), T> {
+        let tail = self.tail.read();
+        if tail - self.head.read() == C as u64 {
+            return Err(value);
+        }
+        // SAFETY: The slot is not between the head and the tail, and so is
+        // not accessed by the consumer until the tail is advanced past it.
+        self.slot(tail)
+            .with_mut(|slot| unsafe { (*slot).write(value) });
+        self.tail.write(tail + 1);
+        Ok(())
+    }
+
+    /// Returns a reference to the value at the front of the buffer, if
+    /// there is one.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called by the consumer, and the reference must not be
+    /// used once the value is popped.
+    unsafe fn peek(&self) -> Option<&T> {
+        let head = self.head.read();
+        if head == self.tail.read() {
+            return None;
+        }
+        // SAFETY: The slot is between the head and the tail, and so was
+        // initialized by the producer, which does not access it until the
+        // head is advanced past it.
+        Some(
+            self.slot(head)
+                .with(|slot| unsafe { (*slot).assume_init_ref() }),
+        )
+    }
+
+    /// Removes and returns the value at the front of the buffer, if there
+    /// is one.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called by the consumer.
+    unsafe fn pop(&self) -> Option<T> {
+        let head = self.head.read();
+        if head == self.tail.read() {
+            return None;
+        }
+        // SAFETY: As in `peek`. The value is moved out before the head is
+        // advanced, after which the producer may overwrite it.
+        let value = self
+            .slot(head)
+            .with(|slot| unsafe { (*slot).assume_init_read() });
+        self.head.write(head + 1);
+        Some(value)
+    }
+}
+
+impl<T, const C: usize> Drop for Ring<T, C> {
+    fn drop(&mut self) {
+        // SAFETY: No other process can access the buffer, and so this
+        // process is both its producer and its consumer.
+        while unsafe { self.pop() }.is_some() {}
+    }
+}
+
+/// Creates a wait-free ring buffer of capacity `C`, with a single producer
+/// and a single consumer.
+///
+/// See the [`ring`](crate::queue::ring) module-level documentation for more
+/// details.
+///
+/// # Examples
+///
+/// ```
+/// use todc_mem::queue::ring;
+///
+/// let (mut producer, mut consumer) = ring::spsc::<u32, 2>();
+/// producer.enqueue(1).unwrap();
+/// producer.enqueue(2).unwrap();
+/// assert_eq!(producer.enqueue(3), Err(3));
+///
+/// assert_eq!(consumer.dequeue(), Some(1));
+/// producer.enqueue(3).unwrap();
+/// assert_eq!(consumer.dequeue(), Some(2));
+/// assert_eq!(consumer.dequeue(), Some(3));
+/// assert_eq!(consumer.dequeue(), None);
+/// ```
+pub fn spsc<T: Send, const C: usize>() -> (SPSCProducer<T, C>, SPSCConsumer<T, C>) {
+    let ring = Arc::new(Ring::new());
+    (SPSCProducer { ring: ring.clone() }, SPSCConsumer { ring })
+}
+
+/// The producer of a single-producer single-consumer ring buffer, created
+/// by [`spsc`].
+pub struct SPSCProducer<T, const C: usize> {
+    ring: Arc<Ring<T, C>>,
+}
+
+impl<T, const C: usize> SPSCProducer<T, C> {
+    /// Adds a value to the back of the buffer, or returns it if the buffer
+    /// already contains `C` values.
+    pub fn enqueue(&mut self, value: T) -> Result<(), T> {
+        // SAFETY: This is the only producer, since it is not `Clone`, and
+        // `&mut self` prevents it from being used concurrently.
+        unsafe { self.ring.push(value) }
+    }
+}
+
+/// The consumer of a single-producer single-consumer ring buffer, created
+/// by [`spsc`].
+pub struct SPSCConsumer<T, const C: usize> {
+    ring: Arc<Ring<T, C>>,
+}
+
+impl<T, const C: usize> SPSCConsumer<T, C> {
+    /// Removes and returns the value at the front of the buffer, or returns
+    /// `None` if the buffer is empty.
+    pub fn dequeue(&mut self) -> Option<T> {
+        // SAFETY: As for the producer.
+        unsafe { self.ring.pop() }
+    }
+}
+
+/// The state that is shared by the handles of an [`mpsc`] buffer.
+struct Shared<T, const N: usize, const C: usize> {
+    // The buffer of each producer, whose values are labeled with the
+    // timestamp at which they were enqueued.
+    rings: [Ring<(u64, T), C>; N],
+    // The most recent timestamp chosen by each producer, written only by
+    // that producer.
+    timestamps: [AtomicRegister<u64>; N],
+}
+
+impl<T, const N: usize, const C: usize> Shared<T, N, C> {
+    /// Returns the largest timestamp that has been chosen by any producer.
+    fn latest(&self) -> u64 {
+        self.timestamps
+            .iter()
+            .map(|timestamp| timestamp.read())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Creates a wait-free ring buffer with `N` producers and a single
+/// consumer, in which each producer can have up to `C` values enqueued at
+/// once.
+///
+/// The `i`th producer that is returned is the _i^{th}_ process. See the
+/// [`ring`](crate::queue::ring) module-level documentation for more details.
+///
+/// # Examples
+///
+/// ```
+/// use todc_mem::queue::ring;
+///
+/// let (mut producers, mut consumer) = ring::mpsc::<u32, 2, 1>();
+/// producers[1].enqueue(1).unwrap();
+/// producers[0].enqueue(2).unwrap();
+///
+/// // Each producer has its own capacity.
+/// assert_eq!(producers[0].enqueue(3), Err(3));
+///
+/// assert_eq!(consumer.dequeue(), Some(1));
+/// assert_eq!(consumer.dequeue(), Some(2));
+/// assert_eq!(consumer.dequeue(), None);
+/// ```
+pub fn mpsc<T: Send, const N: usize, const C: usize>(
+) -> (Vec<MPSCProducer<T, N, C>>, MPSCConsumer<T, N, C>) {
+    let shared = Arc::new(Shared {
+        rings: core::array::from_fn(|_| Ring::new()),
+        timestamps: core::array::from_fn(|_| AtomicRegister::new()),
+    });
+    let producers = (0..N)
+        .map(|i| MPSCProducer {
+            shared: shared.clone(),
+            i,
+        })
+        .collect();
+    (producers, MPSCConsumer { shared })
+}
+
+/// A producer of a multi-producer single-consumer ring buffer, created by
+/// [`mpsc`].
+pub struct MPSCProducer<T, const N: usize, const C: usize> {
+    shared: Arc<Shared<T, N, C>>,
+    i: ProcessId,
+}
+
+impl<T, const N: usize, const C: usize> MPSCProducer<T, N, C> {
+    /// Returns the ID of the process that this producer belongs to.
+    pub fn id(&self) -> ProcessId {
+        self.i
+    }
+
+    /// Adds a value to the back of the buffer, or returns it if `C` of the
+    /// values enqueued by this producer have yet to be dequeued.
+    pub fn enqueue(&mut self, value: T) -> Result<(), T> {
+        // The timestamp is larger than that of any value whose enqueue
+        // completed before this one began, since each producer writes its
+        // timestamp before enqueuing its value.
+        let timestamp = self.shared.latest() + 1;
+        self.shared.timestamps[self.i].write(timestamp);
+        // SAFETY: This is the only producer of its buffer, since it is not
+        // `Clone`, and `&mut self` prevents it from being used concurrently.
+        unsafe { self.shared.rings[self.i].push((timestamp, value)) }.map_err(|(_, value)| value)
+    }
+}
+
+/// The consumer of a multi-producer single-consumer ring buffer, created by
+/// [`mpsc`].
+pub struct MPSCConsumer<T, const N: usize, const C: usize> {
+    shared: Arc<Shared<T, N, C>>,
+}
+
+impl<T, const N: usize, const C: usize> MPSCConsumer<T, N, C> {
+    /// Removes and returns the value at the front of the buffer, or returns
+    /// `None` if the buffer is empty.
+    pub fn dequeue(&mut self) -> Option<T> {
+        // Values with larger timestamps were enqueued by operations that
+        // began after this one, and so can be ordered after it, even if
+        // they have already completed.
+        let latest = self.shared.latest();
+        let mut oldest: Option<(u64, &Ring<(u64, T), C>)> = None;
+        for ring in self.shared.rings.iter() {
+            // SAFETY: This is the only consumer, since it is not `Clone`,
+            // and `&mut self` prevents it from being used concurrently. The
+            // reference is dropped before any value is popped.
+            let Some(&(timestamp, _)) = (unsafe { ring.peek() }) else {
+                continue;
+            };
+            if timestamp <= latest && oldest.is_none_or(|(oldest, _)| timestamp < oldest) {
+                oldest = Some((timestamp, ring));
+            }
+        }
+        // SAFETY: As above.
+        oldest.and_then(|(_, ring)| unsafe { ring.pop() }.map(|(_, value)| value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod spsc {
+        use super::*;
+
+        #[test]
+        fn dequeues_values_in_order() {
+            let (mut producer, mut consumer) = spsc::<u32, 4>();
+            for value in 0..4 {
+                producer.enqueue(value).unwrap();
+            }
+            for value in 0..4 {
+                assert_eq!(consumer.dequeue(), Some(value));
+            }
+            assert_eq!(consumer.dequeue(), None);
+        }
+
+        #[test]
+        fn wraps_around_slots() {
+            let (mut producer, mut consumer) = spsc::<u32, 2>();
+            for value in 0..10 {
+                producer.enqueue(value).unwrap();
+                assert_eq!(consumer.dequeue(), Some(value));
+            }
+        }
+
+        #[test]
+        fn rejects_values_once_full() {
+            let (mut producer, mut consumer) = spsc::<u32, 1>();
+            producer.enqueue(1).unwrap();
+            assert_eq!(producer.enqueue(2), Err(2));
+            assert_eq!(consumer.dequeue(), Some(1));
+            producer.enqueue(2).unwrap();
+        }
+
+        #[test]
+        fn rejects_every_value_without_capacity() {
+            let (mut producer, mut consumer) = spsc::<u32, 0>();
+            assert_eq!(producer.enqueue(1), Err(1));
+            assert_eq!(consumer.dequeue(), None);
+        }
+
+        #[test]
+        fn drops_remaining_values() {
+            let value = Arc::new(());
+            let (mut producer, consumer) = spsc::<Arc<()>, 4>();
+            producer.enqueue(value.clone()).unwrap();
+            producer.enqueue(value.clone()).unwrap();
+            assert_eq!(Arc::strong_count(&value), 3);
+            drop(producer);
+            drop(consumer);
+            assert_eq!(Arc::strong_count(&value), 1);
+        }
+    }
+
+    mod mpsc {
+        use super::*;
+
+        #[test]
+        fn dequeues_values_in_order_of_enqueues() {
+            let (mut producers, mut consumer) = mpsc::<u32, 3, 4>();
+            producers[2].enqueue(1).unwrap();
+            producers[0].enqueue(2).unwrap();
+            producers[2].enqueue(3).unwrap();
+            producers[1].enqueue(4).unwrap();
+            for value in 1..=4 {
+                assert_eq!(consumer.dequeue(), Some(value));
+            }
+            assert_eq!(consumer.dequeue(), None);
+        }
+
+        #[test]
+        fn limits_capacity_of_each_producer() {
+            let (mut producers, mut consumer) = mpsc::<u32, 2, 1>();
+            producers[0].enqueue(1).unwrap();
+            assert_eq!(producers[0].enqueue(2), Err(2));
+            producers[1].enqueue(2).unwrap();
+            assert_eq!(consumer.dequeue(), Some(1));
+            producers[0].enqueue(3).unwrap();
+            assert_eq!(consumer.dequeue(), Some(2));
+            assert_eq!(consumer.dequeue(), Some(3));
+        }
+
+        #[test]
+        fn ignores_values_timestamped_after_dequeue_began() {
+            let (mut producers, mut consumer) = mpsc::<u32, 2, 4>();
+            // Simulate a value whose timestamp was chosen after the consumer
+            // read the timestamps of every producer.
+            unsafe { consumer.shared.rings[1].push((1, 1)) }.unwrap();
+            assert_eq!(consumer.dequeue(), None);
+            // Once another value has the same timestamp, both are dequeued,
+            // with ties broken in favor of the first producer.
+            producers[0].enqueue(2).unwrap();
+            assert_eq!(consumer.dequeue(), Some(2));
+            assert_eq!(consumer.dequeue(), Some(1));
+        }
+
+        #[test]
+        fn numbers_producers_in_order() {
+            let (producers, _) = mpsc::<u32, 3, 1>();
+            let ids: Vec<ProcessId> = producers.iter().map(|p| p.id()).collect();
+            assert_eq!(ids, vec![0, 1, 2]);
+        }
+
+        #[test]
+        fn drops_remaining_values() {
+            let value = Arc::new(());
+            let (mut producers, consumer) = mpsc::<Arc<()>, 2, 4>();
+            producers[0].enqueue(value.clone()).unwrap();
+            producers[1].enqueue(value.clone()).unwrap();
+            drop(producers);
+            drop(consumer);
+            assert_eq!(Arc::strong_count(&value), 1);
+        }
+    }
+}
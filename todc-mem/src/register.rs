@@ -1,13 +1,56 @@
 //! Shared read/write registers.
 //!
-//! See [`AtomicRegister`].
+//! See [`AtomicRegister`], or [`Atomic128Register`] for values that need
+//! more than 64 bits. For a multi-writer register built from
+//! single-writer ones, see [`MWMRRegister`]. For constructions of atomic
+//! registers from weaker registers, see [`transformations`]. For registers
+//! that inject delays and faults into tests, see [`faults`].
+//!
+//! # Generic Backends
+//!
+//! Every register implements the [`Register`] trait, as do the atomic types
+//! of [`std`], such as [`AtomicBool`](std::sync::atomic::AtomicBool) and
+//! [`AtomicUsize`](std::sync::atomic::AtomicUsize). Algorithms that are
+//! written against the trait can therefore be run on top of any of them.
+//!
+//! ```
+//! use std::sync::atomic::AtomicUsize;
+//! use todc_mem::register::{AtomicRegister, MutexRegister, Register};
+//!
+//! /// Increments a counter that only one process writes to.
+//! fn increment<R: Register<Value = usize>>(counter: &R) {
+//!     counter.write(counter.read() + 1);
+//! }
+//!
+//! let atomic: AtomicUsize = Register::new();
+//! increment(&atomic);
+//! assert_eq!(Register::read(&atomic), 1);
+//!
+//! let mutex: MutexRegister<usize> = MutexRegister::new();
+//! increment(&mutex);
+//! assert_eq!(mutex.read(), 1);
+//! ```
 mod atomic;
 pub use self::atomic::AtomicRegister;
+mod atomic128;
+pub use self::atomic128::Atomic128Register;
+pub mod faults;
 mod mutex;
 pub use self::mutex::MutexRegister;
+mod mwmr;
+pub use self::mwmr::MWMRRegister;
+mod std_atomics;
+pub mod transformations;
 
 /// A shared-memory register.
+///
+/// A register holds a single value, which every process can read and write.
+/// Implementations differ in the guarantees that they provide to operations
+/// that overlap, and in the values that they can hold, but all of them can be
+/// used wherever a `Register` is expected. See the
+/// [`register`](crate::register) module-level documentation for an example.
 pub trait Register {
+    /// The type of value contained in the register.
     type Value;
 
     /// Creates a new register.
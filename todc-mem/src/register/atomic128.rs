@@ -0,0 +1,140 @@
+use std::marker::PhantomData;
+
+use crate::sync::{AtomicU128, Ordering};
+
+use super::Register;
+
+/// A shared-memory register, backed by 128 bits of "atomic" memory.
+///
+/// This object works like an [`AtomicRegister`](super::AtomicRegister), but
+/// serializes data into an [`AtomicU128`], and so can store twice as much.
+///
+/// # Hardware Support
+///
+/// Only some hardware can read and write 128 bits of memory atomically, for
+/// example `x86_64` processors that support the `cmpxchg16b` instruction.
+/// Support is detected when the program is run, and on hardware without it
+/// the register falls back to using locks, in which case it is **not**
+/// lock-free. Use [`Atomic128Register::is_lock_free`] to check which is the
+/// case.
+///
+/// # Atomics and Memory Ordering
+///
+/// Operations on an `Atomic128Register` are sequentially consistent, with
+/// the same caveats as those on an [`AtomicRegister`](super::AtomicRegister).
+///
+/// # Examples
+///
+/// ```
+/// use todc_mem::register::{Atomic128Register, Register};
+///
+/// #[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// struct Pair(u64, u64);
+///
+/// impl From<Pair> for u128 {
+///     fn from(pair: Pair) -> Self {
+///         (pair.0 as u128) << 64 | pair.1 as u128
+///     }
+/// }
+///
+/// impl From<u128> for Pair {
+///     fn from(value: u128) -> Self {
+///         Pair((value >> 64) as u64, value as u64)
+///     }
+/// }
+///
+/// let register: Atomic128Register<Pair> = Atomic128Register::new();
+/// register.write(Pair(u64::MAX, 1));
+/// assert_eq!(register.read(), Pair(u64::MAX, 1));
+/// ```
+pub struct Atomic128Register<T: Default + From<u128> + Into<u128>> {
+    register: AtomicU128,
+    _value_type: PhantomData<T>,
+}
+
+impl<T: Default + From<u128> + Into<u128>> Atomic128Register<T> {
+    /// Returns whether operations on the register are lock-free on the
+    /// current hardware.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_mem::register::Atomic128Register;
+    ///
+    /// if !Atomic128Register::<u128>::is_lock_free() {
+    ///     println!("128-bit atomics are emulated with locks");
+    /// }
+    /// ```
+    pub fn is_lock_free() -> bool {
+        AtomicU128::is_lock_free()
+    }
+}
+
+impl<T: Default + From<u128> + Into<u128>> Register for Atomic128Register<T> {
+    type Value = T;
+
+    /// Creates a new register containing the default value of `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_mem::register::{Atomic128Register, Register};
+    ///
+    /// let register: Atomic128Register<u128> = Atomic128Register::new();
+    /// assert_eq!(register.read(), u128::default());
+    /// ```
+    fn new() -> Self {
+        Self {
+            register: AtomicU128::new(T::default().into()),
+            _value_type: PhantomData,
+        }
+    }
+
+    /// Returns the value currently contained in the register.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_mem::register::{Atomic128Register, Register};
+    ///
+    /// let register: Atomic128Register<u128> = Atomic128Register::new();
+    /// assert_eq!(register.read(), 0);
+    /// ```
+    fn read(&self) -> T {
+        self.register.load(Ordering::SeqCst).into()
+    }
+
+    /// Sets contents of the register to the specified value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_mem::register::{Atomic128Register, Register};
+    ///
+    /// let register: Atomic128Register<u128> = Atomic128Register::new();
+    /// register.write(u128::MAX);
+    /// assert_eq!(register.read(), u128::MAX);
+    /// ```
+    fn write(&self, value: T) {
+        self.register.store(value.into(), Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initializes_to_default() {
+        let register: Atomic128Register<u128> = Atomic128Register::new();
+        assert_eq!(0, register.read());
+    }
+
+    #[test]
+    fn read_returns_previously_written_value() {
+        let value = u128::MAX - (1 << 64);
+        let register: Atomic128Register<u128> = Atomic128Register::new();
+        register.write(value);
+        assert_eq!(value, register.read());
+    }
+}
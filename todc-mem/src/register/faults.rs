@@ -0,0 +1,397 @@
+//! Registers that inject delays and faults, for testing.
+//!
+//! Many algorithms are only incorrect under rare schedules, in which one
+//! process is slow at exactly the wrong moment. Exploring every schedule
+//! quickly becomes infeasible, so this module contains decorators that make
+//! such schedules more likely instead:
+//!
+//! - A [`DelayedRegister`] waits for a random number of _steps_ before and
+//!   after each operation, as if the process performing it were slow.
+//! - A [`FaultyRegister`] can be [frozen](FaultyRegister::freeze), so that
+//!   operations stall until it is [thawed](FaultyRegister::thaw), or
+//!   [crashed](FaultyRegister::crash), so that its contents never change
+//!   again.
+//!
+//! A step is a call to [`spin_loop`]. When the `shuttle` feature is enabled,
+//! every step gives the scheduler a chance to preempt the thread, and so
+//! delays widen the windows in which other threads can interleave. All
+//! delays are drawn from a seeded random number generator, so that a failing
+//! schedule can be replayed.
+//!
+//! # Examples
+//!
+//! Build a multi-writer register from single-writer registers that are
+//! delayed by the default amount.
+//!
+//! ```
+//! use todc_mem::register::faults::DelayedRegister;
+//! use todc_mem::register::transformations::Labeled;
+//! use todc_mem::register::{MWMRRegister, MutexRegister};
+//!
+//! type Delayed = DelayedRegister<MutexRegister<Labeled<u32>>>;
+//!
+//! let register: MWMRRegister<u32, 2, Delayed> = MWMRRegister::new();
+//! register.write(0, 123);
+//! assert_eq!(register.read(1), 123);
+//! ```
+//!
+//! Crash a register once it has performed two operations.
+//!
+//! ```
+//! use todc_mem::register::faults::FaultyRegister;
+//! use todc_mem::register::{MutexRegister, Register};
+//!
+//! let register: FaultyRegister<MutexRegister<u32>> = FaultyRegister::new().with_crash_after(2);
+//! register.write(1);
+//! register.write(2);
+//! register.write(3);
+//! assert!(register.is_crashed());
+//! assert_eq!(register.read(), 2);
+//! ```
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::register::Register;
+use crate::sync::{spin_loop, thread_rng, AtomicBool, AtomicU64, Mutex, Ordering, Rng};
+
+/// A number of steps to wait for, chosen uniformly at random from a range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Delay {
+    min: u32,
+    max: u32,
+}
+
+impl Delay {
+    /// A delay of no steps.
+    pub const fn none() -> Self {
+        Self { min: 0, max: 0 }
+    }
+
+    /// A delay of exactly the specified number of steps.
+    pub const fn fixed(steps: u32) -> Self {
+        Self {
+            min: steps,
+            max: steps,
+        }
+    }
+
+    /// A delay of between `min` and `max` steps, inclusive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is larger than `max`.
+    pub const fn between(min: u32, max: u32) -> Self {
+        assert!(min <= max, "Minimum delay must not exceed maximum delay");
+        Self { min, max }
+    }
+}
+
+/// The delay before and after each operation of a [`DelayedRegister`],
+/// unless it is configured otherwise.
+pub const DEFAULT_DELAY: Delay = Delay::between(0, 8);
+
+/// A register that waits for a random number of steps before and after each
+/// operation on an inner register `R`.
+///
+/// A register created with [`Register::new`] waits for a [`DEFAULT_DELAY`]
+/// around every operation, and draws its delays from a generator that is
+/// seeded by [`thread_rng`], which is deterministic when the `shuttle`
+/// feature is enabled. This allows it to be used within constructions, such
+/// as [`MWMRRegister`](crate::register::MWMRRegister), that create their own
+/// registers. Otherwise, the delays and seed can be configured with the
+/// `with_*` methods.
+///
+/// # Examples
+///
+/// ```
+/// use todc_mem::register::faults::{Delay, DelayedRegister};
+/// use todc_mem::register::{MutexRegister, Register};
+///
+/// let register: DelayedRegister<MutexRegister<u32>> = DelayedRegister::new()
+///     .with_read_delays(Delay::none(), Delay::fixed(10))
+///     .with_write_delays(Delay::between(5, 50), Delay::none())
+///     .with_seed(42);
+///
+/// register.write(123);
+/// assert_eq!(register.read(), 123);
+/// ```
+#[derive(Debug)]
+pub struct DelayedRegister<R: Register> {
+    register: R,
+    read_delays: (Delay, Delay),
+    write_delays: (Delay, Delay),
+    rng: Mutex<StdRng>,
+}
+
+impl<R: Register> DelayedRegister<R> {
+    /// Sets the delays before and after each read.
+    pub fn with_read_delays(mut self, before: Delay, after: Delay) -> Self {
+        self.read_delays = (before, after);
+        self
+    }
+
+    /// Sets the delays before and after each write.
+    pub fn with_write_delays(mut self, before: Delay, after: Delay) -> Self {
+        self.write_delays = (before, after);
+        self
+    }
+
+    /// Sets the seed from which delays are drawn.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            ..self
+        }
+    }
+
+    /// Waits for a number of steps drawn from the specified delay.
+    fn wait(&self, delay: Delay) {
+        let steps = if delay.min == delay.max {
+            delay.min
+        } else {
+            self.rng.lock().unwrap().gen_range(delay.min..=delay.max)
+        };
+        for _ in 0..steps {
+            spin_loop();
+        }
+    }
+}
+
+impl<R: Register> Register for DelayedRegister<R> {
+    type Value = R::Value;
+
+    fn new() -> Self {
+        Self {
+            register: R::new(),
+            read_delays: (DEFAULT_DELAY, DEFAULT_DELAY),
+            write_delays: (DEFAULT_DELAY, DEFAULT_DELAY),
+            rng: Mutex::new(StdRng::seed_from_u64(thread_rng().gen())),
+        }
+    }
+
+    fn read(&self) -> Self::Value {
+        let (before, after) = self.read_delays;
+        self.wait(before);
+        let value = self.register.read();
+        self.wait(after);
+        value
+    }
+
+    fn write(&self, value: Self::Value) {
+        let (before, after) = self.write_delays;
+        self.wait(before);
+        self.register.write(value);
+        self.wait(after);
+    }
+}
+
+/// A register that can be frozen or crashed, backed by an inner register `R`.
+///
+/// While the register is [frozen](Self::freeze), operations that begin wait
+/// until it is [thawed](Self::thaw), as if the processes performing them
+/// had stopped taking steps. Once the register has [crashed](Self::crash),
+/// writes no longer have any effect, and reads return its contents at the
+/// moment of the crash. A crash is permanent, and can also be scheduled to
+/// occur after a number of operations with
+/// [`with_crash_after`](Self::with_crash_after).
+///
+/// Operations that are in progress when the register is frozen or crashed
+/// may or may not take effect.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use todc_mem::register::faults::FaultyRegister;
+/// use todc_mem::register::{MutexRegister, Register};
+///
+/// let register: Arc<FaultyRegister<MutexRegister<u32>>> = Arc::new(FaultyRegister::new());
+/// register.freeze();
+///
+/// let writer = register.clone();
+/// let handle = thread::spawn(move || writer.write(123));
+///
+/// // The write cannot finish until the register is thawed.
+/// register.thaw();
+/// handle.join().unwrap();
+/// assert_eq!(register.read(), 123);
+/// ```
+#[derive(Debug)]
+pub struct FaultyRegister<R: Register> {
+    register: R,
+    frozen: AtomicBool,
+    crashed: AtomicBool,
+    operations: AtomicU64,
+    crash_after: Option<u64>,
+}
+
+impl<R: Register> FaultyRegister<R> {
+    /// Schedules the register to crash once it has performed the specified
+    /// number of operations.
+    pub fn with_crash_after(self, operations: u64) -> Self {
+        Self {
+            crash_after: Some(operations),
+            ..self
+        }
+    }
+
+    /// Freezes the register, so that operations wait until it is thawed.
+    pub fn freeze(&self) {
+        self.frozen.store(true, Ordering::SeqCst);
+    }
+
+    /// Thaws the register, allowing waiting operations to continue.
+    pub fn thaw(&self) {
+        self.frozen.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns whether the register is frozen.
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::SeqCst)
+    }
+
+    /// Crashes the register, so that its contents never change again.
+    pub fn crash(&self) {
+        self.crashed.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether the register has crashed.
+    pub fn is_crashed(&self) -> bool {
+        self.crashed.load(Ordering::SeqCst)
+    }
+
+    /// Waits until the register is not frozen, and then counts an operation,
+    /// crashing the register if it has performed as many operations as it
+    /// was scheduled to. Returns whether the operation can take effect.
+    fn step(&self) -> bool {
+        while self.is_frozen() {
+            spin_loop();
+        }
+        let performed = self.operations.fetch_add(1, Ordering::SeqCst);
+        if self.crash_after.is_some_and(|limit| performed >= limit) {
+            self.crash();
+        }
+        !self.is_crashed()
+    }
+}
+
+impl<R: Register> Register for FaultyRegister<R> {
+    type Value = R::Value;
+
+    fn new() -> Self {
+        Self {
+            register: R::new(),
+            frozen: AtomicBool::new(false),
+            crashed: AtomicBool::new(false),
+            operations: AtomicU64::new(0),
+            crash_after: None,
+        }
+    }
+
+    fn read(&self) -> Self::Value {
+        self.step();
+        self.register.read()
+    }
+
+    fn write(&self, value: Self::Value) {
+        if self.step() {
+            self.register.write(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::MutexRegister;
+
+    mod delay {
+        use super::*;
+
+        #[test]
+        fn none_is_default() {
+            assert_eq!(Delay::none(), Delay::default());
+        }
+
+        #[test]
+        #[should_panic]
+        fn between_panics_if_min_exceeds_max() {
+            Delay::between(2, 1);
+        }
+    }
+
+    mod delayed_register {
+        use super::*;
+
+        type Delayed = DelayedRegister<MutexRegister<u32>>;
+
+        #[test]
+        fn reads_default_value() {
+            let register = Delayed::new();
+            assert_eq!(register.read(), 0);
+        }
+
+        #[test]
+        fn reads_written_value() {
+            let register = Delayed::new()
+                .with_read_delays(Delay::fixed(3), Delay::between(0, 5))
+                .with_write_delays(Delay::between(1, 10), Delay::fixed(2));
+            register.write(123);
+            assert_eq!(register.read(), 123);
+        }
+
+        #[test]
+        fn same_seed_draws_same_delays() {
+            let delays = |seed| {
+                let mut rng = Delayed::new().with_seed(seed).rng.into_inner().unwrap();
+                (0..10).map(|_| rng.gen_range(0..100)).collect::<Vec<u32>>()
+            };
+            assert_eq!(delays(42), delays(42));
+        }
+    }
+
+    mod faulty_register {
+        use super::*;
+
+        type Faulty = FaultyRegister<MutexRegister<u32>>;
+
+        #[test]
+        fn reads_written_value_while_healthy() {
+            let register = Faulty::new();
+            register.write(123);
+            assert_eq!(register.read(), 123);
+        }
+
+        #[test]
+        fn ignores_writes_after_crash() {
+            let register = Faulty::new();
+            register.write(123);
+            register.crash();
+            register.write(456);
+            assert_eq!(register.read(), 123);
+        }
+
+        #[test]
+        fn crashes_after_scheduled_number_of_operations() {
+            let register = Faulty::new().with_crash_after(2);
+            register.write(123);
+            assert_eq!(register.read(), 123);
+            assert!(!register.is_crashed());
+            register.write(456);
+            assert!(register.is_crashed());
+            assert_eq!(register.read(), 123);
+        }
+
+        #[test]
+        fn thaw_unfreezes_register() {
+            let register = Faulty::new();
+            register.freeze();
+            assert!(register.is_frozen());
+            register.thaw();
+            assert!(!register.is_frozen());
+            register.write(123);
+            assert_eq!(register.read(), 123);
+        }
+    }
+}
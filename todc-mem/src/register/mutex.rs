@@ -53,18 +53,29 @@ use super::Register;
 /// assert_eq!(register.read(), numbers);
 /// ```
 ///
+/// Values do not need to be [`Copy`], so heap-allocated types can be stored
+/// as well. Reading from the register returns a clone of its contents.
+///
+/// ```
+/// use todc_mem::register::{MutexRegister, Register};
+///
+/// let register: MutexRegister<String> = MutexRegister::new();
+/// register.write(String::from("hello"));
+/// assert_eq!(register.read(), "hello");
+/// ```
+///
 #[derive(Debug)]
-pub struct MutexRegister<T: Copy + Default> {
+pub struct MutexRegister<T: Clone + Default> {
     mutex: Mutex<T>,
 }
 
-impl<T: Copy + Default> Default for MutexRegister<T> {
+impl<T: Clone + Default> Default for MutexRegister<T> {
     fn default() -> Self {
         MutexRegister::<T>::new()
     }
 }
 
-impl<T: Copy + Default> Register for MutexRegister<T> {
+impl<T: Clone + Default> Register for MutexRegister<T> {
     type Value = T;
 
     /// Creates a new register containing the default value of `T`.
@@ -94,7 +105,7 @@ impl<T: Copy + Default> Register for MutexRegister<T> {
     /// assert_eq!(register.read(), false);
     /// ```
     fn read(&self) -> Self::Value {
-        *self.mutex.lock().unwrap()
+        self.mutex.lock().unwrap().clone()
     }
 
     /// Sets contents of the register to the specified value.
@@ -113,7 +124,7 @@ impl<T: Copy + Default> Register for MutexRegister<T> {
     }
 }
 
-impl<T: Copy + Default> Clone for MutexRegister<T> {
+impl<T: Clone + Default> Clone for MutexRegister<T> {
     fn clone(&self) -> Self {
         let clone = Self::new();
         clone.write(self.read());
@@ -169,6 +180,23 @@ mod tests {
         }
     }
 
+    mod heap_allocated {
+        use super::{MutexRegister, Register};
+
+        #[test]
+        fn read() {
+            let register: MutexRegister<Vec<u8>> = MutexRegister::new();
+            assert!(register.read().is_empty());
+        }
+
+        #[test]
+        fn write() {
+            let register = MutexRegister::new();
+            register.write(String::from("hello"));
+            assert_eq!(register.read(), "hello");
+        }
+    }
+
     mod custom_struct {
         use super::{MutexRegister, Register};
 
@@ -0,0 +1,155 @@
+use core::array::from_fn;
+
+use crate::register::transformations::Labeled;
+use crate::register::{MutexRegister, Register};
+use crate::snapshot::ProcessId;
+
+/// A wait-free atomic multi-writer multi-reader register for `N` processes,
+/// built from `N` atomic single-writer multi-reader registers.
+///
+/// This is the timestamp-based construction of Vitányi and Awerbuch, as
+/// presented in Section 10.2 of _Distributed Computing: Fundamentals,
+/// Simulations and Advanced Topics_ by Attiya and Welch. Process `i` is the
+/// only writer of the _i^{th}_ register. To write, a process reads all `N`
+/// registers, and then writes its value to its own register with a timestamp
+/// larger than any that it read. To read, a process returns the value with the
+/// largest label, where labels are ordered by timestamp and then by the ID of
+/// the process that wrote them.
+///
+/// By default, the underlying registers are [`MutexRegister`]s, but any
+/// linearizable [`Register`] that contains [`Labeled`] values can be used
+/// instead. Unlike the
+/// [`AtomicMRMWRegister`](crate::register::transformations::AtomicMRMWRegister),
+/// which builds its single-writer registers from weaker ones, this register
+/// assumes that the underlying registers are already atomic.
+///
+/// Timestamps are unbounded, although in practice a `u64` will not overflow.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use todc_mem::register::MWMRRegister;
+///
+/// const N: usize = 3;
+///
+/// let register: Arc<MWMRRegister<u32, N>> = Arc::new(MWMRRegister::new());
+///
+/// let handles: Vec<_> = (0..N)
+///     .map(|i| {
+///         let register = register.clone();
+///         thread::spawn(move || register.write(i, i as u32))
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+///
+/// // All readers agree on which of the concurrent writes was last.
+/// let value = register.read(0);
+/// assert!(value < N as u32);
+/// assert_eq!(register.read(1), value);
+/// ```
+pub struct MWMRRegister<T, const N: usize, R = MutexRegister<Labeled<T>>>
+where
+    R: Register<Value = Labeled<T>>,
+{
+    registers: [R; N],
+}
+
+impl<T: Clone, const N: usize, R: Register<Value = Labeled<T>>> MWMRRegister<T, N, R> {
+    /// Creates a new register.
+    pub fn new() -> Self {
+        Self {
+            registers: from_fn(|_| R::new()),
+        }
+    }
+
+    /// Returns the value with the largest label among all registers.
+    fn latest(&self) -> Labeled<T> {
+        self.registers
+            .iter()
+            .map(|register| register.read())
+            .max_by_key(|labeled| labeled.label())
+            .expect("There must be at least one register")
+    }
+
+    /// Returns the value currently contained in the register, as read by the
+    /// _i^{th}_ process.
+    ///
+    /// Reads do not modify shared state, so the value is the same no matter
+    /// which process performs the read.
+    pub fn read(&self, _i: ProcessId) -> T {
+        self.latest().value
+    }
+
+    /// Sets the contents of the register to the specified value, as written
+    /// by the _i^{th}_ process.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= N`.
+    pub fn write(&self, i: ProcessId, value: T) {
+        let stamp = self.latest().stamp + 1;
+        self.registers[i].write(Labeled {
+            stamp,
+            writer: i,
+            value,
+        });
+    }
+}
+
+impl<T: Clone, const N: usize, R: Register<Value = Labeled<T>>> Default for MWMRRegister<T, N, R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Mwmr = MWMRRegister<u32, 3>;
+
+    #[test]
+    fn reads_default_value_initially() {
+        let register = Mwmr::new();
+        assert_eq!(register.read(0), 0);
+    }
+
+    #[test]
+    fn reads_value_from_latest_write() {
+        let register = Mwmr::new();
+        register.write(0, 1);
+        register.write(1, 2);
+        assert_eq!(register.read(2), 2);
+        register.write(0, 3);
+        assert_eq!(register.read(1), 3);
+    }
+
+    #[test]
+    fn writes_larger_timestamp_than_any_read() {
+        let register = Mwmr::new();
+        register.write(0, 1);
+        register.write(0, 2);
+        register.write(1, 3);
+        assert_eq!(register.registers[1].read().stamp, 3);
+    }
+
+    #[test]
+    fn breaks_ties_by_process_id() {
+        let register = Mwmr::new();
+        register.registers[0].write(Labeled {
+            stamp: 1,
+            writer: 0,
+            value: 1,
+        });
+        register.registers[2].write(Labeled {
+            stamp: 1,
+            writer: 2,
+            value: 2,
+        });
+        assert_eq!(register.read(1), 2);
+    }
+}
@@ -0,0 +1,91 @@
+//! Implementations of [`Register`] for the atomic types of [`std`].
+//!
+//! Every operation uses [`Ordering::SeqCst`], and so, as described for
+//! [`AtomicRegister`](super::AtomicRegister), operations are sequentially
+//! consistent. Unlike the types re-exported by [`sync`](crate::sync), these
+//! types are always those of [`std`], and so shuttle does not schedule
+//! around them when the `shuttle` feature is enabled.
+use std::sync::atomic::{
+    AtomicBool, AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicIsize, AtomicU16, AtomicU32,
+    AtomicU64, AtomicU8, AtomicUsize, Ordering,
+};
+
+use super::Register;
+
+macro_rules! impl_register {
+    ($($atomic:ty => $value:ty),* $(,)?) => {
+        $(
+            impl Register for $atomic {
+                type Value = $value;
+
+                /// Creates a new register containing the default value.
+                fn new() -> Self {
+                    <$atomic>::new(<$value>::default())
+                }
+
+                /// Returns the value currently contained in the register.
+                fn read(&self) -> Self::Value {
+                    self.load(Ordering::SeqCst)
+                }
+
+                /// Sets contents of the register to the specified value.
+                fn write(&self, value: Self::Value) {
+                    self.store(value, Ordering::SeqCst)
+                }
+            }
+        )*
+    };
+}
+
+impl_register! {
+    AtomicBool => bool,
+    AtomicI8 => i8,
+    AtomicI16 => i16,
+    AtomicI32 => i32,
+    AtomicI64 => i64,
+    AtomicIsize => isize,
+    AtomicU8 => u8,
+    AtomicU16 => u16,
+    AtomicU32 => u32,
+    AtomicU64 => u64,
+    AtomicUsize => usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod atomic_bool {
+        use super::*;
+
+        #[test]
+        fn reads_default_value() {
+            let register: AtomicBool = Register::new();
+            assert!(!Register::read(&register));
+        }
+
+        #[test]
+        fn reads_written_value() {
+            let register: AtomicBool = Register::new();
+            Register::write(&register, true);
+            assert!(Register::read(&register));
+        }
+    }
+
+    mod atomic_usize {
+        use super::*;
+
+        #[test]
+        fn reads_default_value() {
+            let register: AtomicUsize = Register::new();
+            assert_eq!(Register::read(&register), 0);
+        }
+
+        #[test]
+        fn reads_written_value() {
+            let register: AtomicUsize = Register::new();
+            Register::write(&register, 123);
+            assert_eq!(Register::read(&register), 123);
+        }
+    }
+}
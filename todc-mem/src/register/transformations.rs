@@ -0,0 +1,64 @@
+//! Constructions of stronger registers from weaker ones.
+//!
+//! Registers are classified by the guarantees they provide to reads that
+//! overlap with writes, as described by Lamport
+//! [\[Lam86\]](https://doi.org/10.1007/BF01786227):
+//!
+//! - A [_safe_](SafeRegister) register may return _any_ value from a read
+//!   that overlaps with a write.
+//! - A [_regular_](RegularRegister) register returns either the value that was
+//!   most recently written, or the value of some overlapping write.
+//! - An _atomic_ register is linearizable.
+//!
+//! Registers are further classified by the number of processes that may read
+//! from or write to them, e.g. a single-reader single-writer (SRSW) register,
+//! or a multi-reader multi-writer (MRMW) register.
+//!
+//! This module contains simulations of safe and regular registers, along with
+//! the classic constructions that transform them, step by step, into atomic
+//! MRMW registers. The constructions follow those presented in Chapter 4 of
+//! _The Art of Multiprocessor Programming_ by Herlihy and Shavit:
+//!
+//! 1. A safe boolean register becomes a [`RegularBooleanRegister`].
+//! 2. Regular boolean registers become a [`RegularMultiValuedRegister`].
+//! 3. A regular register becomes an [`AtomicSRSWRegister`].
+//! 4. Atomic SRSW registers become an [`AtomicMRSWRegister`].
+//! 5. Atomic MRSW registers become an [`AtomicMRMWRegister`].
+//!
+//! Constructions that only support a single reader or writer rely on the caller
+//! to respect that restriction. Local variables that belong to a single process,
+//! such as the last value written by the writer, are stored alongside the
+//! shared registers.
+//!
+//! # Examples
+//!
+//! Construct an atomic MRMW register for `3` processes, starting from simulated
+//! regular registers.
+//!
+//! ```
+//! use todc_mem::register::transformations::{
+//!     AtomicMRMWRegister, AtomicSRSWRegister, Labeled, RegularRegister, Stamped,
+//! };
+//!
+//! const N: usize = 3;
+//!
+//! // An atomic SRSW register of values T, built from a regular register.
+//! type Srsw<T> = AtomicSRSWRegister<RegularRegister<Stamped<T>>, T>;
+//! // An atomic MRMW register, built from N^3 atomic SRSW registers.
+//! type Mrmw = AtomicMRMWRegister<Srsw<Stamped<Labeled<u32>>>, u32, N>;
+//!
+//! let register = Mrmw::new();
+//! register.write(0, 123);
+//! assert_eq!(register.read(1), 123);
+//! register.write(2, 321);
+//! assert_eq!(register.read(0), 321);
+//! ```
+mod atomic;
+mod regular;
+mod weak;
+
+pub use self::atomic::{
+    AtomicMRMWRegister, AtomicMRSWRegister, AtomicSRSWRegister, Labeled, Stamped,
+};
+pub use self::regular::{RegularBooleanRegister, RegularMultiValuedRegister};
+pub use self::weak::{RegularRegister, SafeRegister};
@@ -0,0 +1,278 @@
+use core::array::from_fn;
+
+use crate::register::Register;
+use crate::snapshot::ProcessId;
+use crate::sync::{AtomicU64, Mutex, Ordering};
+
+/// A value along with a timestamp.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stamped<T> {
+    pub stamp: u64,
+    pub value: T,
+}
+
+/// A value along with a timestamp and the ID of the process that wrote it.
+///
+/// Labels are ordered by timestamp, with ties broken by process ID.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Labeled<T> {
+    pub stamp: u64,
+    pub writer: ProcessId,
+    pub value: T,
+}
+
+impl<T> Labeled<T> {
+    /// Returns the label, used to order values.
+    pub(crate) fn label(&self) -> (u64, ProcessId) {
+        (self.stamp, self.writer)
+    }
+}
+
+/// An atomic single-reader single-writer register, built from a regular
+/// register.
+///
+/// The writer attaches an increasing timestamp to each value. The reader
+/// remembers the value with the largest timestamp it has read so far, and
+/// never returns a value with a smaller timestamp. This prevents the
+/// _new-old inversions_ that are allowed by regular registers.
+pub struct AtomicSRSWRegister<R: Register<Value = Stamped<T>>, T> {
+    register: R,
+    // Local to the writer.
+    last_stamp: AtomicU64,
+    // Local to the reader.
+    last_read: Mutex<Stamped<T>>,
+}
+
+impl<R: Register<Value = Stamped<T>>, T: Clone + Default> Register for AtomicSRSWRegister<R, T> {
+    type Value = T;
+
+    fn new() -> Self {
+        Self {
+            register: R::new(),
+            last_stamp: AtomicU64::new(0),
+            last_read: Mutex::new(Stamped::default()),
+        }
+    }
+
+    fn read(&self) -> T {
+        let stamped = self.register.read();
+        let mut last_read = self.last_read.lock().unwrap();
+        if stamped.stamp > last_read.stamp {
+            *last_read = stamped;
+        }
+        last_read.value.clone()
+    }
+
+    fn write(&self, value: T) {
+        let stamp = self.last_stamp.load(Ordering::SeqCst) + 1;
+        self.last_stamp.store(stamp, Ordering::SeqCst);
+        self.register.write(Stamped { stamp, value });
+    }
+}
+
+/// An atomic `N`-reader single-writer register, built from `N^2` atomic
+/// single-reader single-writer registers.
+///
+/// The registers are arranged in a table. The writer writes a timestamped
+/// value to each register on the diagonal, and every other register `[i][j]`
+/// is written by reader `i` and read by reader `j`. Before returning, each reader tells all
+/// other readers about the value it is about to return, so that no later read
+/// can return an older value.
+pub struct AtomicMRSWRegister<R: Register<Value = Stamped<T>>, T, const N: usize> {
+    table: [[R; N]; N],
+    // Local to the writer.
+    last_stamp: AtomicU64,
+}
+
+impl<R: Register<Value = Stamped<T>>, T: Clone, const N: usize> AtomicMRSWRegister<R, T, N> {
+    /// Creates a new register.
+    pub fn new() -> Self {
+        Self {
+            table: from_fn(|_| from_fn(|_| R::new())),
+            last_stamp: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the value currently contained in the register, as read by the
+    /// _i^{th}_ reader.
+    pub fn read(&self, i: ProcessId) -> T {
+        let mut latest = self.table[i][i].read();
+        for j in 0..N {
+            let stamped = self.table[j][i].read();
+            if stamped.stamp > latest.stamp {
+                latest = stamped;
+            }
+        }
+        // Register [i][i] belongs to the writer, so reader i must not write it.
+        for j in (0..N).filter(|&j| j != i) {
+            self.table[i][j].write(latest.clone());
+        }
+        latest.value
+    }
+
+    /// Sets the contents of the register to the specified value.
+    pub fn write(&self, value: T) {
+        let stamp = self.last_stamp.load(Ordering::SeqCst) + 1;
+        self.last_stamp.store(stamp, Ordering::SeqCst);
+        for i in 0..N {
+            self.table[i][i].write(Stamped {
+                stamp,
+                value: value.clone(),
+            });
+        }
+    }
+}
+
+impl<R: Register<Value = Stamped<T>>, T: Clone, const N: usize> Default
+    for AtomicMRSWRegister<R, T, N>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An atomic `N`-reader `N`-writer register, built from `N` atomic `N`-reader
+/// single-writer registers.
+///
+/// Process `i` is the only writer of the _i^{th}_ register. To write, a process
+/// reads all registers and writes its value with a timestamp larger than any
+/// it has seen. To read, a process returns the value with the largest label.
+pub struct AtomicMRMWRegister<R: Register<Value = Stamped<Labeled<T>>>, T, const N: usize> {
+    registers: [AtomicMRSWRegister<R, Labeled<T>, N>; N],
+}
+
+impl<R: Register<Value = Stamped<Labeled<T>>>, T: Clone, const N: usize>
+    AtomicMRMWRegister<R, T, N>
+{
+    /// Creates a new register.
+    pub fn new() -> Self {
+        Self {
+            registers: from_fn(|_| AtomicMRSWRegister::new()),
+        }
+    }
+
+    /// Returns the register value with the largest label, as read by the
+    /// _i^{th}_ process.
+    fn latest(&self, i: ProcessId) -> Labeled<T> {
+        self.registers
+            .iter()
+            .map(|register| register.read(i))
+            .max_by_key(|labeled| labeled.label())
+            .expect("There must be at least one register")
+    }
+
+    /// Returns the value currently contained in the register, as read by the
+    /// _i^{th}_ process.
+    pub fn read(&self, i: ProcessId) -> T {
+        self.latest(i).value
+    }
+
+    /// Sets the contents of the register to the specified value, as written
+    /// by the _i^{th}_ process.
+    pub fn write(&self, i: ProcessId, value: T) {
+        let stamp = self.latest(i).stamp + 1;
+        self.registers[i].write(Labeled {
+            stamp,
+            writer: i,
+            value,
+        });
+    }
+}
+
+impl<R: Register<Value = Stamped<Labeled<T>>>, T: Clone, const N: usize> Default
+    for AtomicMRMWRegister<R, T, N>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::transformations::RegularRegister;
+
+    type Srsw<T> = AtomicSRSWRegister<RegularRegister<Stamped<T>>, T>;
+
+    mod atomic_srsw_register {
+        use super::*;
+
+        #[test]
+        fn reads_previously_written_value() {
+            let register: Srsw<u32> = Srsw::new();
+            assert_eq!(register.read(), 0);
+            register.write(123);
+            assert_eq!(register.read(), 123);
+        }
+
+        #[test]
+        fn does_not_return_older_value() {
+            let register: Srsw<u32> = Srsw::new();
+            register.write(1);
+            register.write(2);
+            assert_eq!(register.read(), 2);
+            // Simulate a regular register returning an older value.
+            register.register.write(Stamped { stamp: 1, value: 1 });
+            assert_eq!(register.read(), 2);
+        }
+    }
+
+    mod atomic_mrsw_register {
+        use super::*;
+
+        type Mrsw = AtomicMRSWRegister<Srsw<Stamped<u32>>, u32, 3>;
+
+        #[test]
+        fn all_readers_read_written_value() {
+            let register = Mrsw::new();
+            register.write(123);
+            for i in 0..3 {
+                assert_eq!(register.read(i), 123);
+            }
+        }
+
+        #[test]
+        fn readers_inform_each_other() {
+            let register = Mrsw::new();
+            register.write(123);
+            register.read(0);
+            // Reader 1 learns of the value from reader 0, even if the
+            // writer had not yet written to reader 1's register.
+            register.table[1][1].write(Stamped::default());
+            assert_eq!(register.read(1), 123);
+        }
+    }
+
+    mod atomic_mrmw_register {
+        use super::*;
+
+        type Mrmw = AtomicMRMWRegister<Srsw<Stamped<Labeled<u32>>>, u32, 3>;
+
+        #[test]
+        fn reads_value_from_latest_write() {
+            let register = Mrmw::new();
+            assert_eq!(register.read(0), 0);
+            register.write(0, 1);
+            register.write(1, 2);
+            assert_eq!(register.read(2), 2);
+            register.write(0, 3);
+            assert_eq!(register.read(1), 3);
+        }
+
+        #[test]
+        fn breaks_ties_by_process_id() {
+            let register = Mrmw::new();
+            register.registers[0].write(Labeled {
+                stamp: 1,
+                writer: 0,
+                value: 1,
+            });
+            register.registers[2].write(Labeled {
+                stamp: 1,
+                writer: 2,
+                value: 2,
+            });
+            assert_eq!(register.read(1), 2);
+        }
+    }
+}
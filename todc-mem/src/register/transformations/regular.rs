@@ -0,0 +1,159 @@
+use core::array::from_fn;
+
+use crate::register::Register;
+use crate::sync::{AtomicBool, Ordering};
+
+/// A regular single-writer boolean register, built from a safe boolean
+/// register.
+///
+/// The writer only writes to the underlying register if the new value differs
+/// from the old one. Since any value read during an overlapping write is
+/// either `true` or `false`, it must be either the old or the new value.
+pub struct RegularBooleanRegister<R: Register<Value = bool>> {
+    register: R,
+    // Local to the writer.
+    last_written: AtomicBool,
+}
+
+impl<R: Register<Value = bool>> Register for RegularBooleanRegister<R> {
+    type Value = bool;
+
+    fn new() -> Self {
+        Self {
+            register: R::new(),
+            last_written: AtomicBool::new(false),
+        }
+    }
+
+    fn read(&self) -> bool {
+        self.register.read()
+    }
+
+    fn write(&self, value: bool) {
+        if self.last_written.load(Ordering::SeqCst) != value {
+            self.register.write(value);
+            self.last_written.store(value, Ordering::SeqCst);
+        }
+    }
+}
+
+/// A regular single-writer register containing values in `0..M`, built from
+/// regular boolean registers.
+///
+/// Values are stored in _unary_. Writing the value `x` sets the `x`th bit and
+/// then clears all lower bits, in descending order. Reads return the index of
+/// the lowest bit that is set.
+///
+/// # Panics
+///
+/// Writing a value that is not less than `M` will panic.
+pub struct RegularMultiValuedRegister<R: Register<Value = bool>, const M: usize> {
+    bits: [R; M],
+}
+
+impl<R: Register<Value = bool>, const M: usize> Register for RegularMultiValuedRegister<R, M> {
+    type Value = usize;
+
+    fn new() -> Self {
+        let bits: [R; M] = from_fn(|_| R::new());
+        bits[0].write(true);
+        Self { bits }
+    }
+
+    fn read(&self) -> usize {
+        (0..M)
+            .find(|&i| self.bits[i].read())
+            .expect("Some bit must be set")
+    }
+
+    fn write(&self, value: usize) {
+        assert!(value < M, "Value must be less than {M}");
+        self.bits[value].write(true);
+        for i in (0..value).rev() {
+            self.bits[i].write(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::transformations::SafeRegister;
+    use crate::sync::AtomicU64;
+
+    type Boolean = RegularBooleanRegister<SafeRegister<bool>>;
+
+    mod regular_boolean_register {
+        use super::*;
+
+        #[test]
+        fn reads_previously_written_value() {
+            let register = Boolean::new();
+            assert!(!register.read());
+            register.write(true);
+            assert!(register.read());
+            register.write(false);
+            assert!(!register.read());
+        }
+
+        #[test]
+        fn does_not_write_unchanged_value() {
+            // A register that counts the number of writes performed on it.
+            #[derive(Default)]
+            struct Counting(AtomicU64);
+
+            impl Register for Counting {
+                type Value = bool;
+
+                fn new() -> Self {
+                    Self::default()
+                }
+
+                fn read(&self) -> bool {
+                    self.0.load(Ordering::SeqCst) % 2 == 1
+                }
+
+                fn write(&self, _: bool) {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+
+            let register: RegularBooleanRegister<Counting> = RegularBooleanRegister::new();
+            register.write(false);
+            register.write(true);
+            register.write(true);
+            assert_eq!(register.register.0.load(Ordering::SeqCst), 1);
+        }
+    }
+
+    mod regular_multi_valued_register {
+        use super::*;
+
+        #[test]
+        fn reads_default_value() {
+            let register: RegularMultiValuedRegister<Boolean, 4> =
+                RegularMultiValuedRegister::new();
+            assert_eq!(register.read(), 0);
+        }
+
+        #[test]
+        fn reads_previously_written_value() {
+            let register: RegularMultiValuedRegister<Boolean, 4> =
+                RegularMultiValuedRegister::new();
+            register.write(3);
+            assert_eq!(register.read(), 3);
+            register.write(1);
+            assert_eq!(register.read(), 1);
+            register.write(2);
+            assert_eq!(register.read(), 2);
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_value_is_too_large() {
+            let register: RegularMultiValuedRegister<Boolean, 4> =
+                RegularMultiValuedRegister::new();
+            register.write(4);
+        }
+    }
+}
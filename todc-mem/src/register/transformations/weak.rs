@@ -0,0 +1,163 @@
+use rand::distributions::{Distribution, Standard};
+
+use crate::register::Register;
+use crate::sync::{spin_loop, thread_rng, Mutex, Rng};
+
+/// A simulated _safe_ single-writer register.
+///
+/// Reads that overlap with a write return an arbitrary value of type `T`,
+/// chosen at random. Reads that do not overlap with a write return the
+/// most recently written value.
+///
+/// # Examples
+///
+/// ```
+/// use todc_mem::register::Register;
+/// use todc_mem::register::transformations::SafeRegister;
+///
+/// let register: SafeRegister<u8> = SafeRegister::new();
+/// register.write(42);
+/// assert_eq!(register.read(), 42);
+/// ```
+#[derive(Debug)]
+pub struct SafeRegister<T> {
+    // The current value, and whether a write is in progress.
+    state: Mutex<(T, bool)>,
+}
+
+impl<T: Clone + Default> Register for SafeRegister<T>
+where
+    Standard: Distribution<T>,
+{
+    type Value = T;
+
+    fn new() -> Self {
+        Self {
+            state: Mutex::new((T::default(), false)),
+        }
+    }
+
+    fn read(&self) -> T {
+        let state = self.state.lock().unwrap();
+        if state.1 {
+            thread_rng().gen()
+        } else {
+            state.0.clone()
+        }
+    }
+
+    fn write(&self, value: T) {
+        self.state.lock().unwrap().1 = true;
+        spin_loop();
+        *self.state.lock().unwrap() = (value, false);
+    }
+}
+
+/// A simulated _regular_ single-writer register.
+///
+/// Reads that overlap with a write return either the previous value or the
+/// value being written, chosen at random. Reads that do not overlap with a
+/// write return the most recently written value.
+///
+/// # Examples
+///
+/// ```
+/// use todc_mem::register::Register;
+/// use todc_mem::register::transformations::RegularRegister;
+///
+/// let register: RegularRegister<String> = RegularRegister::new();
+/// register.write(String::from("hello"));
+/// assert_eq!(register.read(), "hello");
+/// ```
+#[derive(Debug)]
+pub struct RegularRegister<T> {
+    // The previous value, the current value, and whether a write is in progress.
+    state: Mutex<(T, T, bool)>,
+}
+
+impl<T: Clone + Default> Register for RegularRegister<T> {
+    type Value = T;
+
+    fn new() -> Self {
+        Self {
+            state: Mutex::new((T::default(), T::default(), false)),
+        }
+    }
+
+    fn read(&self) -> T {
+        let state = self.state.lock().unwrap();
+        if state.2 && thread_rng().gen_bool(0.5) {
+            state.0.clone()
+        } else {
+            state.1.clone()
+        }
+    }
+
+    fn write(&self, value: T) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.0 = std::mem::replace(&mut state.1, value);
+            state.2 = true;
+        }
+        spin_loop();
+        self.state.lock().unwrap().2 = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod safe_register {
+        use super::*;
+
+        #[test]
+        fn reads_default_value() {
+            let register: SafeRegister<u32> = SafeRegister::new();
+            assert_eq!(register.read(), 0);
+        }
+
+        #[test]
+        fn reads_previously_written_value() {
+            let register: SafeRegister<bool> = SafeRegister::new();
+            register.write(true);
+            assert!(register.read());
+        }
+
+        #[test]
+        fn reads_arbitrary_value_while_writing() {
+            let register: SafeRegister<u64> = SafeRegister::new();
+            register.state.lock().unwrap().1 = true;
+            // A random u64 is zero with negligible probability.
+            assert!((0..10).any(|_| register.read() != 0));
+        }
+    }
+
+    mod regular_register {
+        use super::*;
+
+        #[test]
+        fn reads_default_value() {
+            let register: RegularRegister<u32> = RegularRegister::new();
+            assert_eq!(register.read(), 0);
+        }
+
+        #[test]
+        fn reads_previously_written_value() {
+            let register: RegularRegister<u32> = RegularRegister::new();
+            register.write(1);
+            register.write(2);
+            assert_eq!(register.read(), 2);
+        }
+
+        #[test]
+        fn reads_old_or_new_value_while_writing() {
+            let register: RegularRegister<u32> = RegularRegister::new();
+            register.write(1);
+            *register.state.lock().unwrap() = (1, 2, true);
+            for _ in 0..10 {
+                assert!([1, 2].contains(&register.read()));
+            }
+        }
+    }
+}
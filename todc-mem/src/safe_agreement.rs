@@ -0,0 +1,225 @@
+//! Safe agreement objects.
+//!
+//! Safe agreement is a weakening of consensus, introduced by Borowsky and
+//! Gafni [\[BG93\]](https://doi.org/10.1145/167088.167119) as the building
+//! block of the _BG simulation_, in which a set of processes simulate a
+//! larger system that tolerates fewer failures. Each process may
+//! [propose](SafeAgreement::propose) a value at most once, and may try to
+//! [resolve](SafeAgreement::resolve) the object any number of times. The
+//! resolutions of every process satisfy the following properties:
+//!
+//! * **Agreement:** Every process that resolves the object resolves it to the
+//!   same value.
+//! * **Validity:** The object resolves to a value that was proposed.
+//! * **Termination:** If every process that has started to propose a value
+//!   has finished doing so, and at least one has, then the object resolves.
+//!
+//! Unlike consensus, which cannot be solved using registers alone, safe
+//! agreement is solvable because a proposal contains an _unsafe window_.
+//! While any process is within its unsafe window, attempts to resolve the
+//! object return `None`, and if a process crashes within its window, the
+//! object never resolves. Every operation is wait-free, and so a crash can
+//! only block resolution by crashing during the short window of a proposal.
+//!
+//! This module contains an implementation of safe agreement built on top of
+//! a snapshot object, as described by Borowsky et al.
+//! [\[BGLR01\]](https://doi.org/10.1007/PL00008926).
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::thread;
+//! use todc_mem::safe_agreement::MutexSafeAgreement;
+//!
+//! const N: usize = 3;
+//!
+//! let agreement: Arc<MutexSafeAgreement<u32, N>> = Arc::new(MutexSafeAgreement::new());
+//!
+//! let handles: Vec<_> = (0..N)
+//!     .map(|i| {
+//!         let agreement = agreement.clone();
+//!         thread::spawn(move || agreement.propose(i, i as u32 * 10))
+//!     })
+//!     .collect();
+//!
+//! for handle in handles {
+//!     handle.join().unwrap();
+//! }
+//!
+//! // Every proposal has finished, so every process resolves the same value.
+//! let value = agreement.resolve(0).unwrap();
+//! assert!([0, 10, 20].contains(&value));
+//! for i in 1..N {
+//!     assert_eq!(agreement.resolve(i), Some(value));
+//! }
+//! ```
+use crate::snapshot::{ProcessId, Snapshot, UnboundedMutexSnapshot};
+
+/// The proposal of a process, as stored in its component of the snapshot.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Proposal<T> {
+    /// The process has not proposed a value.
+    #[default]
+    Absent,
+    /// The process is within the unsafe window of its proposal.
+    Unsafe(T),
+    /// The process finished proposing its value before any other process
+    /// had finished, and so its value may be chosen.
+    Safe(T),
+    /// The process finished proposing after another process had finished,
+    /// and so its value is never chosen.
+    Withdrawn,
+}
+
+/// An `N`-process safe agreement object, using an [`UnboundedMutexSnapshot`].
+///
+/// This object is **not** lock-free. For implementation details, see
+/// [`SafeAgreement`].
+pub type MutexSafeAgreement<T, const N: usize> =
+    SafeAgreement<UnboundedMutexSnapshot<Proposal<T>, N>, N>;
+
+/// An `N`-process safe agreement object, built from a snapshot object.
+///
+/// The _i^{th}_ component of the snapshot contains the [`Proposal`] of the
+/// _i^{th}_ process. To propose a value, a process first marks it as
+/// [`Unsafe`](Proposal::Unsafe), and then scans the snapshot. If some other
+/// process has already finished proposing, then the process withdraws,
+/// and otherwise it marks its value as [`Safe`](Proposal::Safe). Once no
+/// process is within its unsafe window, the set of safe values can never
+/// change, and the object resolves to the safe value of the process with the
+/// smallest identifier.
+///
+/// This object is wait-free if, and only if, the snapshot `S` is.
+pub struct SafeAgreement<S, const N: usize> {
+    snapshot: S,
+}
+
+impl<S, T, const N: usize> SafeAgreement<S, N>
+where
+    S: Snapshot<N, Value = Proposal<T>>,
+    T: Clone,
+{
+    /// Creates a new safe agreement object, to which no values have been
+    /// proposed.
+    pub fn new() -> Self {
+        Self { snapshot: S::new() }
+    }
+
+    /// Proposes a value on behalf of the _i^{th}_ process.
+    ///
+    /// Each process may propose at most one value. The unsafe window of the
+    /// proposal lasts from the start of this call until it returns.
+    pub fn propose(&self, i: ProcessId, value: T) {
+        self.snapshot.update(i, Proposal::Unsafe(value.clone()));
+        let view = self.snapshot.scan(i);
+        if view
+            .iter()
+            .any(|proposal| matches!(proposal, Proposal::Safe(_)))
+        {
+            self.snapshot.update(i, Proposal::Withdrawn);
+        } else {
+            self.snapshot.update(i, Proposal::Safe(value));
+        }
+    }
+
+    /// Attempts to resolve the object on behalf of the _i^{th}_ process, and
+    /// returns the value it resolves to, or `None` if it cannot yet be
+    /// resolved.
+    ///
+    /// The object cannot be resolved while any process is within the unsafe
+    /// window of its proposal, or if no process has finished proposing.
+    pub fn resolve(&self, i: ProcessId) -> Option<T> {
+        let view = self.snapshot.scan(i);
+        if view
+            .iter()
+            .any(|proposal| matches!(proposal, Proposal::Unsafe(_)))
+        {
+            return None;
+        }
+        view.into_iter().find_map(|proposal| match proposal {
+            Proposal::Safe(value) => Some(value),
+            _ => None,
+        })
+    }
+}
+
+impl<S, T, const N: usize> Default for SafeAgreement<S, N>
+where
+    S: Snapshot<N, Value = Proposal<T>>,
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Agreement = MutexSafeAgreement<u32, 3>;
+
+    mod propose {
+        use super::*;
+
+        #[test]
+        fn first_proposal_is_safe() {
+            let agreement = Agreement::new();
+            agreement.propose(1, 123);
+            assert_eq!(agreement.snapshot.scan(0)[1], Proposal::Safe(123));
+        }
+
+        #[test]
+        fn proposal_after_safe_proposal_is_withdrawn() {
+            let agreement = Agreement::new();
+            agreement.propose(1, 123);
+            agreement.propose(0, 456);
+            assert_eq!(agreement.snapshot.scan(0)[0], Proposal::Withdrawn);
+        }
+
+        #[test]
+        fn proposal_concurrent_with_unsafe_proposal_is_safe() {
+            let agreement = Agreement::new();
+            agreement.snapshot.update(2, Proposal::Unsafe(789));
+            agreement.propose(1, 123);
+            assert_eq!(agreement.snapshot.scan(0)[1], Proposal::Safe(123));
+        }
+    }
+
+    mod resolve {
+        use super::*;
+
+        #[test]
+        fn does_not_resolve_without_proposals() {
+            let agreement = Agreement::new();
+            assert_eq!(agreement.resolve(0), None);
+        }
+
+        #[test]
+        fn resolves_to_first_proposal() {
+            let agreement = Agreement::new();
+            agreement.propose(2, 123);
+            agreement.propose(0, 456);
+            assert_eq!(agreement.resolve(1), Some(123));
+        }
+
+        #[test]
+        fn does_not_resolve_during_unsafe_window() {
+            let agreement = Agreement::new();
+            agreement.propose(0, 123);
+            agreement.snapshot.update(2, Proposal::Unsafe(789));
+            assert_eq!(agreement.resolve(1), None);
+        }
+
+        #[test]
+        fn resolves_to_safe_value_of_smallest_process() {
+            let agreement = Agreement::new();
+            // Processes 1 and 2 both scanned before either finished.
+            agreement.snapshot.update(2, Proposal::Safe(789));
+            agreement.snapshot.update(1, Proposal::Safe(456));
+            agreement.propose(0, 123);
+            assert_eq!(agreement.resolve(0), Some(456));
+        }
+    }
+}
@@ -0,0 +1,599 @@
+//! A simulator for prototyping message-passing algorithms.
+//!
+//! Although this crate is concerned with shared-memory algorithms, many of
+//! them are derived from, or compared against, algorithms for message-passing
+//! systems. Prototyping such an algorithm over a real network, as in
+//! `todc-net`, involves a lot of machinery that is irrelevant to its logic.
+//! This module contains a lightweight [`Simulator`] instead, in which every
+//! process is an `async` task that runs inside the current thread, and
+//! messages are passed between processes over a simulated network.
+//!
+//! Time in the simulation is measured in _ticks_. Each message sent over the
+//! network is delayed by a random number of ticks, and may be dropped or
+//! duplicated, as set by a [`NetworkConfig`]. All randomness, including the
+//! order in which processes are scheduled, is derived from a single seed, so
+//! that every run of a simulation with the same seed behaves identically.
+//!
+//! # Examples
+//!
+//! Each process sends a ping to every process, and waits for a pong in reply.
+//!
+//! ```
+//! use std::cell::Cell;
+//! use std::rc::Rc;
+//! use todc_mem::simulator::{NetworkConfig, Simulator};
+//!
+//! #[derive(Clone, Debug)]
+//! enum Message {
+//!     Ping,
+//!     Pong,
+//! }
+//!
+//! let network = NetworkConfig::new().with_delay(1, 10);
+//! let mut simulator: Simulator<Message> = Simulator::new(42).with_network(network);
+//! let pongs = Rc::new(Cell::new(0));
+//!
+//! for _ in 0..3 {
+//!     let pongs = pongs.clone();
+//!     simulator.spawn(|context| async move {
+//!         context.broadcast(Message::Ping);
+//!         let mut received = 0;
+//!         while received < 3 {
+//!             match context.recv().await {
+//!                 (from, Message::Ping) => context.send(from, Message::Pong),
+//!                 (_, Message::Pong) => received += 1,
+//!             }
+//!         }
+//!         pongs.set(pongs.get() + received);
+//!         // Keep answering pings from slower processes.
+//!         while let Some((from, message)) = context.recv_timeout(100).await {
+//!             if let Message::Ping = message {
+//!                 context.send(from, Message::Pong);
+//!             }
+//!         }
+//!     });
+//! }
+//!
+//! simulator.run();
+//! assert!(simulator.is_finished());
+//! assert_eq!(pongs.get(), 9);
+//! ```
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll, Wake, Waker};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// An ID for a simulated process.
+pub type ProcessId = usize;
+
+/// The behavior of the network over which simulated processes communicate.
+///
+/// By default, every message is delivered exactly once, one tick after it
+/// is sent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NetworkConfig {
+    min_delay: u64,
+    max_delay: u64,
+    drop_probability: f64,
+    duplicate_probability: f64,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            min_delay: 1,
+            max_delay: 1,
+            drop_probability: 0.0,
+            duplicate_probability: 0.0,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// Creates a network on which every message is delivered exactly once,
+    /// one tick after it is sent.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delays each message by a number of ticks chosen uniformly at random
+    /// from `min..=max`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is `0`, or greater than `max`.
+    pub fn with_delay(self, min: u64, max: u64) -> Self {
+        assert!(min > 0, "Messages must be delayed by at least one tick");
+        assert!(min <= max, "Minimum delay cannot exceed maximum delay");
+        Self {
+            min_delay: min,
+            max_delay: max,
+            ..self
+        }
+    }
+
+    /// Drops each message with probability `p`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in `0.0..=1.0`.
+    pub fn with_drop_probability(self, p: f64) -> Self {
+        assert!((0.0..=1.0).contains(&p), "Probability must be in [0, 1]");
+        Self {
+            drop_probability: p,
+            ..self
+        }
+    }
+
+    /// Delivers each message that is not dropped a second time, after an
+    /// independent delay, with probability `p`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in `0.0..=1.0`.
+    pub fn with_duplicate_probability(self, p: f64) -> Self {
+        assert!((0.0..=1.0).contains(&p), "Probability must be in [0, 1]");
+        Self {
+            duplicate_probability: p,
+            ..self
+        }
+    }
+}
+
+/// Something that happens at a scheduled time.
+enum Event<M> {
+    /// A message is delivered to a process.
+    Deliver {
+        from: ProcessId,
+        to: ProcessId,
+        message: M,
+    },
+    /// A process that is waiting for some amount of time is woken.
+    Wake(ProcessId),
+}
+
+/// An event, along with the time at which it happens.
+///
+/// Events that happen at the same time are ordered randomly, by their key.
+struct Scheduled<M> {
+    at: u64,
+    key: (u64, u64),
+    event: Event<M>,
+}
+
+impl<M> PartialEq for Scheduled<M> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.key) == (other.at, other.key)
+    }
+}
+
+impl<M> Eq for Scheduled<M> {}
+
+impl<M> PartialOrd for Scheduled<M> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<M> Ord for Scheduled<M> {
+    // Reversed, so that the earliest event is at the top of a max-heap.
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.at, other.key).cmp(&(self.at, self.key))
+    }
+}
+
+/// The state of a simulation that is shared between all processes.
+struct World<M> {
+    config: NetworkConfig,
+    rng: StdRng,
+    time: u64,
+    /// The number of events that have been scheduled.
+    scheduled: u64,
+    events: BinaryHeap<Scheduled<M>>,
+    mailboxes: Vec<VecDeque<(ProcessId, M)>>,
+    /// Whether each process should be polled.
+    woken: Vec<bool>,
+}
+
+impl<M: Clone> World<M> {
+    fn schedule(&mut self, at: u64, event: Event<M>) {
+        let key = (self.rng.gen(), self.scheduled);
+        self.scheduled += 1;
+        self.events.push(Scheduled { at, key, event });
+    }
+
+    fn send(&mut self, from: ProcessId, to: ProcessId, message: M) {
+        assert!(to < self.mailboxes.len(), "Process {to} does not exist");
+        if self.rng.gen_bool(self.config.drop_probability) {
+            return;
+        }
+        let copies = if self.rng.gen_bool(self.config.duplicate_probability) {
+            2
+        } else {
+            1
+        };
+        for _ in 0..copies {
+            let delay = self
+                .rng
+                .gen_range(self.config.min_delay..=self.config.max_delay);
+            let message = message.clone();
+            self.schedule(self.time + delay, Event::Deliver { from, to, message });
+        }
+    }
+
+    /// Returns the time at which a process that waits for `ticks` is woken,
+    /// and schedules it to be woken at that time.
+    fn wake_after(&mut self, process: ProcessId, ticks: u64) -> u64 {
+        let at = self.time + ticks;
+        self.schedule(at, Event::Wake(process));
+        at
+    }
+}
+
+/// A handle, given to each simulated process, through which it interacts
+/// with the simulation.
+pub struct Context<M> {
+    id: ProcessId,
+    world: Rc<RefCell<World<M>>>,
+}
+
+impl<M> Clone for Context<M> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            world: self.world.clone(),
+        }
+    }
+}
+
+impl<M: Clone> Context<M> {
+    /// Returns the ID of this process.
+    pub fn id(&self) -> ProcessId {
+        self.id
+    }
+
+    /// Returns the number of processes in the simulation.
+    pub fn processes(&self) -> usize {
+        self.world.borrow().mailboxes.len()
+    }
+
+    /// Returns the current time, in ticks.
+    pub fn now(&self) -> u64 {
+        self.world.borrow().time
+    }
+
+    /// Sends a message to a process.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the process does not exist.
+    pub fn send(&self, to: ProcessId, message: M) {
+        self.world.borrow_mut().send(self.id, to, message);
+    }
+
+    /// Sends a message to every process, including this one.
+    pub fn broadcast(&self, message: M) {
+        let mut world = self.world.borrow_mut();
+        for to in 0..world.mailboxes.len() {
+            world.send(self.id, to, message.clone());
+        }
+    }
+
+    /// Waits for a message to be delivered to this process, and returns it
+    /// along with the ID of its sender.
+    pub async fn recv(&self) -> (ProcessId, M) {
+        poll_fn(
+            |_| match self.world.borrow_mut().mailboxes[self.id].pop_front() {
+                Some(delivered) => Poll::Ready(delivered),
+                None => Poll::Pending,
+            },
+        )
+        .await
+    }
+
+    /// Waits at most `ticks` for a message to be delivered to this process,
+    /// and returns it along with the ID of its sender, or `None` if no
+    /// message was delivered in time.
+    pub async fn recv_timeout(&self, ticks: u64) -> Option<(ProcessId, M)> {
+        let deadline = self.world.borrow_mut().wake_after(self.id, ticks);
+        poll_fn(|_| {
+            let mut world = self.world.borrow_mut();
+            match world.mailboxes[self.id].pop_front() {
+                Some(delivered) => Poll::Ready(Some(delivered)),
+                None if world.time >= deadline => Poll::Ready(None),
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    /// Waits for `ticks` to pass.
+    pub async fn sleep(&self, ticks: u64) {
+        let deadline = self.world.borrow_mut().wake_after(self.id, ticks);
+        poll_fn(|_| {
+            if self.world.borrow().time >= deadline {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+/// A waker that does nothing, since the simulator tracks which processes
+/// to poll on its own.
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// A deterministic simulation of processes that communicate by passing
+/// messages of type `M`.
+///
+/// See the [`simulator`](crate::simulator) module-level documentation for
+/// more details.
+pub struct Simulator<M> {
+    world: Rc<RefCell<World<M>>>,
+    processes: Vec<Option<Pin<Box<dyn Future<Output = ()>>>>>,
+}
+
+impl<M: Clone + 'static> Simulator<M> {
+    /// Creates a simulation, whose randomness is derived from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            world: Rc::new(RefCell::new(World {
+                config: NetworkConfig::default(),
+                rng: StdRng::seed_from_u64(seed),
+                time: 0,
+                scheduled: 0,
+                events: BinaryHeap::new(),
+                mailboxes: Vec::new(),
+                woken: Vec::new(),
+            })),
+            processes: Vec::new(),
+        }
+    }
+
+    /// Sets the behavior of the network over which processes communicate.
+    pub fn with_network(self, config: NetworkConfig) -> Self {
+        self.world.borrow_mut().config = config;
+        self
+    }
+
+    /// Adds a process to the simulation, and returns its ID.
+    ///
+    /// The process is the future returned by `f`, which is given a
+    /// [`Context`] through which to communicate with other processes.
+    pub fn spawn<F, Fut>(&mut self, f: F) -> ProcessId
+    where
+        F: FnOnce(Context<M>) -> Fut,
+        Fut: Future<Output = ()> + 'static,
+    {
+        let id = self.processes.len();
+        {
+            let mut world = self.world.borrow_mut();
+            world.mailboxes.push(VecDeque::new());
+            world.woken.push(true);
+        }
+        let context = Context {
+            id,
+            world: self.world.clone(),
+        };
+        self.processes.push(Some(Box::pin(f(context))));
+        id
+    }
+
+    /// Returns the current time, in ticks.
+    pub fn time(&self) -> u64 {
+        self.world.borrow().time
+    }
+
+    /// Returns whether every process has finished.
+    pub fn is_finished(&self) -> bool {
+        self.processes.iter().all(Option::is_none)
+    }
+
+    /// Runs the simulation until every process has either finished, or is
+    /// waiting for a message that will never be delivered.
+    pub fn run(&mut self) {
+        self.run_until(u64::MAX)
+    }
+
+    /// Runs the simulation until every process has either finished, or is
+    /// waiting for a message that will never be delivered, or until the
+    /// time `limit` is reached.
+    pub fn run_until(&mut self, limit: u64) {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = TaskContext::from_waker(&waker);
+        loop {
+            // Poll woken processes, in a random order, until every process
+            // is waiting for a future event.
+            loop {
+                let mut woken: Vec<ProcessId> = self
+                    .world
+                    .borrow_mut()
+                    .woken
+                    .iter_mut()
+                    .enumerate()
+                    .filter_map(|(i, woken)| std::mem::take(woken).then_some(i))
+                    .collect();
+                if woken.is_empty() {
+                    break;
+                }
+                woken.shuffle(&mut self.world.borrow_mut().rng);
+                for i in woken {
+                    if let Some(process) = &mut self.processes[i] {
+                        if process.as_mut().poll(&mut cx).is_ready() {
+                            self.processes[i] = None;
+                        }
+                    }
+                }
+            }
+
+            // Advance time to the next event, and perform every event that
+            // happens at that time.
+            let mut world = self.world.borrow_mut();
+            let Some(next) = world.events.peek().map(|event| event.at) else {
+                return;
+            };
+            if next > limit {
+                return;
+            }
+            world.time = next;
+            while world.events.peek().is_some_and(|event| event.at == next) {
+                let event = world.events.pop().unwrap().event;
+                match event {
+                    Event::Deliver { from, to, message } => {
+                        world.mailboxes[to].push_back((from, message));
+                        world.woken[to] = true;
+                    }
+                    Event::Wake(process) => world.woken[process] = true,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Runs a simulation in which process 0 sends the numbers `0..count` to
+    /// process 1, and returns the messages received by process 1, along with
+    /// the time at which each was received.
+    fn send_numbers(seed: u64, network: NetworkConfig, count: u32) -> Vec<(u64, u32)> {
+        let mut simulator = Simulator::new(seed).with_network(network);
+        let received = Rc::new(RefCell::new(Vec::new()));
+        simulator.spawn(move |context| async move {
+            for i in 0..count {
+                context.send(1, i);
+            }
+        });
+        let log = received.clone();
+        simulator.spawn(move |context| async move {
+            while let Some((_, i)) = context.recv_timeout(1_000).await {
+                log.borrow_mut().push((context.now(), i));
+            }
+        });
+        simulator.run();
+        assert!(simulator.is_finished());
+        let received = received.borrow().clone();
+        received
+    }
+
+    mod network_config {
+        use super::*;
+
+        #[test]
+        #[should_panic]
+        fn with_delay_panics_if_min_exceeds_max() {
+            NetworkConfig::new().with_delay(5, 1);
+        }
+
+        #[test]
+        #[should_panic]
+        fn with_drop_probability_panics_if_not_probability() {
+            NetworkConfig::new().with_drop_probability(1.5);
+        }
+    }
+
+    mod simulator {
+        use super::*;
+
+        #[test]
+        fn delivers_every_message_by_default() {
+            let received = send_numbers(0, NetworkConfig::new(), 10);
+            let values: Vec<u32> = received.iter().map(|(_, i)| *i).collect();
+            assert_eq!(values.len(), 10);
+            assert!(received.iter().all(|(time, _)| *time == 1));
+        }
+
+        #[test]
+        fn delays_messages_within_range() {
+            let network = NetworkConfig::new().with_delay(5, 10);
+            let received = send_numbers(0, network, 100);
+            assert_eq!(received.len(), 100);
+            assert!(received.iter().all(|(time, _)| (5..=10).contains(time)));
+        }
+
+        #[test]
+        fn drops_messages() {
+            let network = NetworkConfig::new().with_drop_probability(1.0);
+            assert!(send_numbers(0, network, 10).is_empty());
+        }
+
+        #[test]
+        fn duplicates_messages() {
+            let network = NetworkConfig::new().with_duplicate_probability(1.0);
+            assert_eq!(send_numbers(0, network, 10).len(), 20);
+        }
+
+        #[test]
+        fn is_deterministic_for_the_same_seed() {
+            let network = NetworkConfig::new()
+                .with_delay(1, 100)
+                .with_drop_probability(0.2)
+                .with_duplicate_probability(0.2);
+            assert_eq!(send_numbers(7, network, 100), send_numbers(7, network, 100));
+            assert_ne!(send_numbers(7, network, 100), send_numbers(8, network, 100));
+        }
+
+        #[test]
+        fn recv_timeout_returns_none_if_no_message_arrives() {
+            let mut simulator: Simulator<()> = Simulator::new(0);
+            let result = Rc::new(Cell::new(None));
+            let r = result.clone();
+            simulator.spawn(move |context| async move {
+                let received = context.recv_timeout(10).await;
+                r.set(Some((received.is_none(), context.now())));
+            });
+            simulator.run();
+            assert_eq!(result.get(), Some((true, 10)));
+        }
+
+        #[test]
+        fn sleep_advances_time() {
+            let mut simulator: Simulator<()> = Simulator::new(0);
+            simulator.spawn(|context| async move {
+                context.sleep(25).await;
+                assert_eq!(context.now(), 25);
+            });
+            simulator.run();
+            assert!(simulator.is_finished());
+            assert_eq!(simulator.time(), 25);
+        }
+
+        #[test]
+        fn stops_when_processes_wait_forever() {
+            let mut simulator: Simulator<()> = Simulator::new(0);
+            simulator.spawn(|context| async move {
+                context.recv().await;
+            });
+            simulator.run();
+            assert!(!simulator.is_finished());
+        }
+
+        #[test]
+        fn run_until_stops_at_limit() {
+            let mut simulator: Simulator<()> = Simulator::new(0);
+            simulator.spawn(|context| async move {
+                loop {
+                    context.sleep(10).await;
+                }
+            });
+            simulator.run_until(100);
+            assert_eq!(simulator.time(), 100);
+        }
+    }
+}
@@ -17,6 +17,40 @@
 //! Similarily, the number `N` of components available in these snapshots is
 //! limited to `6` and `5`, respectively.
 //!
+//! The [`BoundedAtomic128Snapshot`] and [`UnboundedAtomic128Snapshot`] objects
+//! pack their components into 128 bits of atomic memory instead, and so can
+//! store values of type [`u8`], [`u16`] or [`u32`], with up to `13` components
+//! of [`u8`] values. On hardware without 128-bit atomics, such as `x86_64`
+//! processors that lack the `cmpxchg16b` instruction, they fall back to using
+//! locks.
+//!
+//! Snapshots backed by [`MutexRegister`](crate::register::MutexRegister)
+//! objects have no such restrictions, and can store any type that implements
+//! [`Clone`] and [`Default`], including heap-allocated types like [`String`].
+//!
+//! # Adapting to Contention
+//!
+//! Every scan of the snapshot objects in [`aad_plus_93`] and [`ar_98`] reads
+//! from all `N` components, even when only a few processes use the object.
+//! The [`AdaptiveMutexSnapshot`] and [`DynAdaptiveMutexSnapshot`] objects
+//! instead only read the components of processes that have updated them, so
+//! that their operations are cheaper when there is little contention. See
+//! the [`adaptive`] module for details.
+//!
+//! # Caching Scans
+//!
+//! In read-heavy workloads, many scans are performed while no component is
+//! being updated, and return the same view. A [`CachedSnapshot`] wraps any
+//! other snapshot, and returns a cached view from such scans, rather than
+//! scanning again. See the [`cached`] module for details.
+//!
+//! # Measuring Performance
+//!
+//! The [`workload`] module drives concurrent updates and scans over any
+//! [`DynSnapshot`], and reports their throughput and latency. It is used by
+//! the benchmarks in this crate, and can be used to compare implementations
+//! in other experiments.
+//!
 //! # Examples
 //!
 //! Obtain a consistent view of progress being made by a set of threads.
@@ -75,13 +109,27 @@
 //! }
 //! ```
 pub mod aad_plus_93;
+pub mod adaptive;
 pub mod ar_98;
+pub mod cached;
+pub mod durable;
 pub mod mutex;
+pub mod workload;
 
 pub use self::aad_plus_93::{
-    BoundedAtomicSnapshot, BoundedMutexSnapshot, UnboundedAtomicSnapshot, UnboundedMutexSnapshot,
+    BoundedAtomic128Snapshot, BoundedAtomicSnapshot, BoundedMutexSnapshot,
+    DynBoundedAtomicSnapshot, DynBoundedMutexSnapshot, DynUnboundedAtomicSnapshot,
+    DynUnboundedMutexSnapshot, UnboundedAtomic128Snapshot, UnboundedAtomicSnapshot,
+    UnboundedMutexSnapshot,
+};
+pub use self::adaptive::{AdaptiveMutexSnapshot, DynAdaptiveMutexSnapshot};
+pub use self::ar_98::{
+    DynLatticeMutexSnapshot, DynUnboundedLatticeMutexSnapshot, LatticeMutexSnapshot,
+    UnboundedLatticeMutexSnapshot,
 };
-pub use self::ar_98::LatticeMutexSnapshot;
+pub use self::cached::CachedSnapshot;
+pub use self::durable::DurableSnapshot;
+pub use self::mutex::DynMutexSnapshot;
 
 /// An ID for a process (or thread).
 pub type ProcessId = usize;
@@ -99,3 +147,35 @@ pub trait Snapshot<const N: usize> {
     /// Sets contents of the _i^{th}_ component to the specified value.
     fn update(&self, i: ProcessId, value: Self::Value);
 }
+
+/// A snapshot object whose number of components is chosen at runtime.
+///
+/// Each implementation of [`Snapshot`] in this module has a corresponding
+/// implementation of [`DynSnapshot`] that uses the same algorithm, but
+/// allocates its registers when the object is created.
+///
+/// # Examples
+///
+/// ```
+/// use todc_mem::snapshot::{DynSnapshot, DynUnboundedMutexSnapshot};
+///
+/// let n: usize = "3".parse().unwrap();
+/// let snapshot: DynUnboundedMutexSnapshot<u32> = DynUnboundedMutexSnapshot::new(n);
+/// snapshot.update(1, 123);
+/// assert_eq!(snapshot.scan(0), vec![0, 123, 0]);
+/// ```
+pub trait DynSnapshot {
+    type Value: Clone;
+
+    /// Creates a snapshot object with `n` components.
+    fn new(n: usize) -> Self;
+
+    /// Returns the number of components in the object.
+    fn components(&self) -> usize;
+
+    /// Returns a vector containing the value of each component in the object.
+    fn scan(&self, i: ProcessId) -> Vec<Self::Value>;
+
+    /// Sets contents of the _i^{th}_ component to the specified value.
+    fn update(&self, i: ProcessId, value: Self::Value);
+}
@@ -3,12 +3,22 @@
 //!
 //! # Examples
 //! For examples, see the [`snapshot`](super) documentation.
+mod packing;
+
 mod unbounded;
+pub use unbounded::DynUnboundedAtomicSnapshot;
+pub use unbounded::DynUnboundedMutexSnapshot;
+pub use unbounded::DynUnboundedSnapshot;
+pub use unbounded::UnboundedAtomic128Snapshot;
 pub use unbounded::UnboundedAtomicSnapshot;
 pub use unbounded::UnboundedMutexSnapshot;
 pub use unbounded::UnboundedSnapshot;
 
 mod bounded;
+pub use bounded::BoundedAtomic128Snapshot;
 pub use bounded::BoundedAtomicSnapshot;
 pub use bounded::BoundedMutexSnapshot;
 pub use bounded::BoundedSnapshot;
+pub use bounded::DynBoundedAtomicSnapshot;
+pub use bounded::DynBoundedMutexSnapshot;
+pub use bounded::DynBoundedSnapshot;
@@ -1,19 +1,41 @@
 use core::array::from_fn;
 use std::fmt::Debug;
 
-use crate::register::{AtomicRegister, MutexRegister, Register};
-use crate::snapshot::Snapshot;
+use crate::register::{Atomic128Register, AtomicRegister, MutexRegister, Register};
+use crate::snapshot::{DynSnapshot, Snapshot};
 use crate::sync::{AtomicBool, Ordering};
 
+use super::packing::{Packable, Packer, Unpacker, Word};
+
 /// A wait-free `N`-process atomic snapshot object, backed by [`AtomicRegister`]
 /// objects.
 ///
 /// Due to limitations on [`AtomicRegister`], this snapshot can only contain
-/// `N <= 6` components of [`u8`] values. For implementation details, see
+/// `N <= 6` components of [`u8`] values. For more components or larger
+/// values, see [`BoundedAtomic128Snapshot`]. For implementation details, see
 /// [`BoundedSnapshot`].
 pub type BoundedAtomicSnapshot<const N: usize> =
     BoundedSnapshot<AtomicRegister<BoundedAtomicContents<N>>, N>;
 
+/// A wait-free `N`-process atomic snapshot object, backed by
+/// [`Atomic128Register`] objects.
+///
+/// Components can contain values of type [`u8`], [`u16`] or [`u32`], and are
+/// packed into 128 bits alongside `N + 1` bits of handshakes and toggles. This
+/// allows, for example, `N <= 13` components of [`u8`] values or `N <= 6`
+/// components of [`u16`] values.
+///
+/// This snapshot is only wait-free on hardware that supports 128-bit atomics,
+/// see [`Atomic128Register`] for details. For implementation details, see
+/// [`BoundedSnapshot`].
+///
+/// # Panics
+///
+/// Creating the snapshot panics if `N` components of type `T` do not fit into
+/// 128 bits.
+pub type BoundedAtomic128Snapshot<T, const N: usize> =
+    BoundedSnapshot<Atomic128Register<BoundedAtomicContents<N, T>>, N>;
+
 /// An `N`-process atomic snapshot object, backed by [`MutexRegister`] objects.
 ///
 /// This snapshot is **not** lock-free. For implementation details, see
@@ -22,7 +44,7 @@ pub type BoundedMutexSnapshot<T, const N: usize> =
     BoundedSnapshot<MutexRegister<BoundedContents<T, N>>, N>;
 
 pub trait Contents<const N: usize>: Default {
-    type Value: Copy + Debug;
+    type Value: Clone + Debug;
 
     fn new(value: Self::Value, view: [Self::Value; N], handshakes: [bool; N], toggle: bool)
         -> Self;
@@ -133,26 +155,26 @@ where
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct BoundedContents<T: Copy + Default, const N: usize> {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoundedContents<T: Clone + Default, const N: usize> {
     value: T,
     view: [T; N],
     handshakes: [bool; N],
     toggle: bool,
 }
 
-impl<T: Copy + Default, const N: usize> Default for BoundedContents<T, N> {
+impl<T: Clone + Default, const N: usize> Default for BoundedContents<T, N> {
     fn default() -> Self {
         Self {
             value: T::default(),
-            view: [T::default(); N],
+            view: from_fn(|_| T::default()),
             handshakes: [bool::default(); N],
             toggle: bool::default(),
         }
     }
 }
 
-impl<T: Copy + Default + Debug, const N: usize> Contents<N> for BoundedContents<T, N> {
+impl<T: Clone + Default + Debug, const N: usize> Contents<N> for BoundedContents<T, N> {
     type Value = T;
 
     fn new(
@@ -170,11 +192,11 @@ impl<T: Copy + Default + Debug, const N: usize> Contents<N> for BoundedContents<
     }
 
     fn value(&self) -> Self::Value {
-        self.value
+        self.value.clone()
     }
 
     fn view(&self) -> [Self::Value; N] {
-        self.view
+        self.view.clone()
     }
 
     fn handshake(&self, i: usize) -> bool {
@@ -186,32 +208,75 @@ impl<T: Copy + Default + Debug, const N: usize> Contents<N> for BoundedContents<
     }
 }
 
+/// The contents of a component of a [`BoundedAtomicSnapshot`] or a
+/// [`BoundedAtomic128Snapshot`].
+///
+/// Contents are packed into a single word, with the value in the right-most
+/// bits, followed by the view and then the handshakes. The toggle occupies
+/// the left-most bit.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub struct BoundedAtomicContents<const N: usize> {
-    // Occupies a total of 63 = 8 + (8*6) + (6*1) + 1 bits
-    value: u8,
-    view: [u8; N],
+pub struct BoundedAtomicContents<const N: usize, T = u8> {
+    value: T,
+    view: [T; N],
     handshakes: [bool; N],
     toggle: bool,
 }
 
-impl<const N: usize> Default for BoundedAtomicContents<N> {
+impl<const N: usize, T: Packable> BoundedAtomicContents<N, T> {
+    /// The number of bits occupied by the contents, once packed. For example,
+    /// 6 components of [`u8`] values occupy 8 + (8*6) + (6*1) + 1 = 63 bits.
+    const BITS: u32 = T::BITS * (N as u32 + 1) + N as u32 + 1;
+
+    /// Panics if the contents do not fit into a word of type `W`.
+    fn assert_fits<W: Word>() {
+        assert!(
+            Self::BITS <= W::BITS,
+            "BoundedAtomicContents with {N} components of {}-bit values require {} bits, \
+             but only {} are available",
+            T::BITS,
+            Self::BITS,
+            W::BITS
+        );
+    }
+
+    fn pack<W: Word>(self) -> W {
+        Self::assert_fits::<W>();
+        let mut packer = Packer::<W>::new();
+        packer.push(self.value);
+        for value in self.view {
+            packer.push(value);
+        }
+        for handshake in self.handshakes {
+            packer.push(handshake);
+        }
+        packer.finish() | self.toggle.pack::<W>() << (W::BITS - 1) as usize
+    }
+
+    fn unpack<W: Word>(word: W) -> Self {
+        Self::assert_fits::<W>();
+        let mut unpacker = Unpacker::new(word);
+        Self {
+            value: unpacker.pop(),
+            view: from_fn(|_| unpacker.pop()),
+            handshakes: from_fn(|_| unpacker.pop()),
+            toggle: bool::unpack(word >> (W::BITS - 1) as usize),
+        }
+    }
+}
+
+impl<const N: usize, T: Packable> Default for BoundedAtomicContents<N, T> {
     fn default() -> Self {
-        // 6 process requires a total of 8 + (8*6) + (6*1) + 1 = 63 bits
-        if N > 6 {
-            panic!("BoundedAtomicContents are only valid for 6 threads or fewer")
-        };
         Self {
-            value: u8::default(),
-            view: [u8::default(); N],
+            value: T::default(),
+            view: [T::default(); N],
             handshakes: [bool::default(); N],
             toggle: bool::default(),
         }
     }
 }
 
-impl<const N: usize> Contents<N> for BoundedAtomicContents<N> {
-    type Value = u8;
+impl<const N: usize, T: Packable> Contents<N> for BoundedAtomicContents<N, T> {
+    type Value = T;
 
     fn new(
         value: Self::Value,
@@ -244,41 +309,178 @@ impl<const N: usize> Contents<N> for BoundedAtomicContents<N> {
     }
 }
 
-impl<const N: usize> From<BoundedAtomicContents<N>> for u64 {
-    fn from(contents: BoundedAtomicContents<N>) -> Self {
-        let mut result: u64 = 0;
-        // Encode value as right-most 8 bits
-        result |= contents.value as u64;
-        // Encode view as (reversed) sequence of 8-bit values
-        for (i, value) in contents.view.iter().enumerate() {
-            result |= (*value as u64) << (8 * (i + 1));
+macro_rules! impl_word_conversions {
+    ($($word:ty),*) => {
+        $(
+            impl<const N: usize, T: Packable> From<BoundedAtomicContents<N, T>> for $word {
+                fn from(contents: BoundedAtomicContents<N, T>) -> Self {
+                    contents.pack()
+                }
+            }
+
+            impl<const N: usize, T: Packable> From<$word> for BoundedAtomicContents<N, T> {
+                fn from(encoding: $word) -> Self {
+                    Self::unpack(encoding)
+                }
+            }
+        )*
+    };
+}
+
+impl_word_conversions!(u64, u128);
+
+/// A wait-free atomic snapshot object, backed by [`AtomicRegister`] objects,
+/// whose number of components is chosen at runtime.
+///
+/// The same limitations as [`BoundedAtomicSnapshot`] apply, so this snapshot
+/// can only contain `n <= 6` components of [`u8`] values. For implementation
+/// details, see [`BoundedSnapshot`].
+pub type DynBoundedAtomicSnapshot = DynBoundedSnapshot<AtomicRegister<DynBoundedAtomicContents>>;
+
+/// An atomic snapshot object, backed by [`MutexRegister`] objects, whose
+/// number of components is chosen at runtime.
+///
+/// This snapshot is **not** lock-free. For implementation details, see
+/// [`BoundedSnapshot`].
+pub type DynBoundedMutexSnapshot<T> = DynBoundedSnapshot<MutexRegister<DynBoundedContents<T>>>;
+
+/// The contents of a component of a snapshot object whose number of
+/// components is chosen at runtime.
+pub trait DynContents: Default {
+    type Value: Clone + Debug;
+
+    /// The maximum number of components that can be stored.
+    const MAX_COMPONENTS: usize = usize::MAX;
+
+    fn new(value: Self::Value, view: Vec<Self::Value>, handshakes: Vec<bool>, toggle: bool)
+        -> Self;
+
+    fn value(&self) -> Self::Value;
+
+    fn view(&self) -> Vec<Self::Value>;
+
+    fn handshake(&self, i: usize) -> bool;
+
+    fn toggle(&self) -> bool;
+}
+
+/// A wait-free snapshot object whose number of components is chosen at
+/// runtime.
+///
+/// This is the same algorithm as [`BoundedSnapshot`], with registers
+/// allocated when the object is created.
+pub struct DynBoundedSnapshot<R: Register>
+where
+    R::Value: DynContents,
+{
+    registers: Vec<R>,
+    shared_handshakes: Vec<Vec<AtomicBool>>,
+}
+
+impl<R: Register> DynBoundedSnapshot<R>
+where
+    R::Value: DynContents,
+{
+    fn collect(&self) -> Vec<R::Value> {
+        self.registers.iter().map(|r| r.read()).collect()
+    }
+
+    /// Returns whether process _i_ has seen process _j_ move while performing
+    /// a double collect.
+    fn has_moved(&self, first: &[R::Value], second: &[R::Value], i: usize, j: usize) -> bool {
+        let first_changed =
+            first[j].handshake(i) != self.shared_handshakes[i][j].load(Ordering::SeqCst);
+        let second_changed =
+            second[j].handshake(i) != self.shared_handshakes[i][j].load(Ordering::SeqCst);
+        let toggle_changed = first[j].toggle() != second[j].toggle();
+        first_changed || second_changed || toggle_changed
+    }
+}
+
+impl<R: Register> DynSnapshot for DynBoundedSnapshot<R>
+where
+    R::Value: DynContents,
+{
+    type Value = <R::Value as DynContents>::Value;
+
+    /// Creates a new snapshot object with `n` components.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the contents of the registers cannot store `n` components.
+    fn new(n: usize) -> Self {
+        let max = <R::Value as DynContents>::MAX_COMPONENTS;
+        if n > max {
+            panic!("This snapshot supports at most {max} components")
         }
-        // Encode handshakes as (reversed) sequence of N bits
-        for (i, boolean) in contents.handshakes.iter().enumerate() {
-            result |= (*boolean as u64) << (8 * (N + 1) + i);
+        Self {
+            registers: (0..n).map(|_| R::new()).collect(),
+            shared_handshakes: (0..n)
+                .map(|_| (0..n).map(|_| AtomicBool::new(false)).collect())
+                .collect(),
+        }
+    }
+
+    fn components(&self) -> usize {
+        self.registers.len()
+    }
+
+    fn scan(&self, i: usize) -> Vec<Self::Value> {
+        let n = self.components();
+        let mut moved = vec![0; n];
+        loop {
+            for j in 0..n {
+                let bit = self.registers[j].read().handshake(i);
+                self.shared_handshakes[i][j].store(bit, Ordering::SeqCst);
+            }
+            let first = self.collect();
+            let second = self.collect();
+            if (0..n).all(|j| !self.has_moved(&first, &second, i, j)) {
+                return second.iter().map(|c| c.value()).collect();
+            }
+            for j in 0..n {
+                if self.has_moved(&first, &second, i, j) {
+                    if moved[j] == 1 {
+                        let mut view = second[j].view();
+                        view.truncate(n);
+                        return view;
+                    } else {
+                        moved[j] += 1;
+                    }
+                }
+            }
         }
-        // Encode toggle as left-most bit.
-        result |= (contents.toggle as u64) << 63;
-        result
+    }
+
+    fn update(&self, i: usize, value: Self::Value) {
+        let n = self.components();
+        let view = self.scan(i);
+        let toggle = !self.registers[i].read().toggle();
+        let handshakes: Vec<bool> = (0..n)
+            .map(|j| !self.shared_handshakes[j][i].load(Ordering::SeqCst))
+            .collect();
+        let contents = DynContents::new(value, view, handshakes, toggle);
+        self.registers[i].write(contents);
     }
 }
 
-impl<const N: usize> From<u64> for BoundedAtomicContents<N> {
-    fn from(encoding: u64) -> Self {
-        // Decode value from right-must 8 bits
-        let value = (encoding & (u8::MAX as u64)) as u8;
-        // Decode view from (reversed) sequence of 8-bit values
-        let view = from_fn(|i| {
-            let shift = 8 * (i + 1);
-            ((encoding & (u8::MAX as u64) << shift) >> shift) as u8
-        });
-        // Decode handshakes from (reversed) sequence of N bits
-        let handshakes = from_fn(|i| {
-            let shift = 8 * (N + 1) + i;
-            (encoding & 1 << shift) > 0
-        });
-        // Decode toggle from left-most bit.
-        let toggle = (encoding & 1 << 63) > 0;
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DynBoundedContents<T: Clone + Default> {
+    value: T,
+    view: Vec<T>,
+    handshakes: Vec<bool>,
+    toggle: bool,
+}
+
+impl<T: Clone + Default + Debug> DynContents for DynBoundedContents<T> {
+    type Value = T;
+
+    fn new(
+        value: Self::Value,
+        view: Vec<Self::Value>,
+        handshakes: Vec<bool>,
+        toggle: bool,
+    ) -> Self {
         Self {
             value,
             view,
@@ -286,6 +488,78 @@ impl<const N: usize> From<u64> for BoundedAtomicContents<N> {
             toggle,
         }
     }
+
+    fn value(&self) -> Self::Value {
+        self.value.clone()
+    }
+
+    fn view(&self) -> Vec<Self::Value> {
+        self.view.clone()
+    }
+
+    fn handshake(&self, i: usize) -> bool {
+        // Components that have never been updated store no handshakes.
+        self.handshakes.get(i).copied().unwrap_or_default()
+    }
+
+    fn toggle(&self) -> bool {
+        self.toggle
+    }
+}
+
+/// The contents of a component of a [`DynBoundedAtomicSnapshot`].
+///
+/// Views and handshakes are always stored with `6` components, and are
+/// truncated by the snapshot object.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DynBoundedAtomicContents(BoundedAtomicContents<6>);
+
+impl DynContents for DynBoundedAtomicContents {
+    type Value = u8;
+
+    const MAX_COMPONENTS: usize = 6;
+
+    fn new(
+        value: Self::Value,
+        view: Vec<Self::Value>,
+        handshakes: Vec<bool>,
+        toggle: bool,
+    ) -> Self {
+        Self(BoundedAtomicContents {
+            value,
+            view: from_fn(|i| view.get(i).copied().unwrap_or_default()),
+            handshakes: from_fn(|i| handshakes.get(i).copied().unwrap_or_default()),
+            toggle,
+        })
+    }
+
+    fn value(&self) -> Self::Value {
+        self.0.value
+    }
+
+    fn view(&self) -> Vec<Self::Value> {
+        self.0.view.to_vec()
+    }
+
+    fn handshake(&self, i: usize) -> bool {
+        self.0.handshakes[i]
+    }
+
+    fn toggle(&self) -> bool {
+        self.0.toggle
+    }
+}
+
+impl From<u64> for DynBoundedAtomicContents {
+    fn from(encoding: u64) -> Self {
+        Self(BoundedAtomicContents::from(encoding))
+    }
+}
+
+impl From<DynBoundedAtomicContents> for u64 {
+    fn from(contents: DynBoundedAtomicContents) -> Self {
+        contents.0.into()
+    }
 }
 
 #[cfg(test)]
@@ -303,6 +577,15 @@ mod tests {
             snapshot.update(2, 12);
             assert_eq!([0, 11, 12], snapshot.scan(0));
         }
+
+        #[test]
+        fn reads_and_writes_strings() {
+            let snapshot: BoundedMutexSnapshot<String, 3> = BoundedMutexSnapshot::new();
+            assert_eq!(["", "", ""], snapshot.scan(0));
+            snapshot.update(1, String::from("one"));
+            snapshot.update(2, String::from("two"));
+            assert_eq!(["", "one", "two"], snapshot.scan(0));
+        }
     }
 
     mod bounded_atomic_snapshot {
@@ -318,6 +601,65 @@ mod tests {
         }
     }
 
+    mod bounded_atomic_128_snapshot {
+        use super::{BoundedAtomic128Snapshot, Snapshot};
+
+        #[test]
+        fn reads_and_writes() {
+            let snapshot: BoundedAtomic128Snapshot<u8, 13> = BoundedAtomic128Snapshot::new();
+            assert_eq!([0; 13], snapshot.scan(0));
+            snapshot.update(1, 11);
+            snapshot.update(12, 22);
+            assert_eq!([0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 22], snapshot.scan(0));
+        }
+
+        #[test]
+        fn reads_and_writes_wide_values() {
+            let snapshot: BoundedAtomic128Snapshot<u16, 6> = BoundedAtomic128Snapshot::new();
+            snapshot.update(1, 1_000);
+            snapshot.update(5, u16::MAX);
+            assert_eq!([0, 1_000, 0, 0, 0, u16::MAX], snapshot.scan(0));
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_too_many_components() {
+            let _: BoundedAtomic128Snapshot<u8, 14> = BoundedAtomic128Snapshot::new();
+        }
+    }
+
+    mod dyn_bounded_mutex_snapshot {
+        use super::*;
+
+        #[test]
+        fn reads_and_writes() {
+            let snapshot: DynBoundedMutexSnapshot<usize> = DynBoundedMutexSnapshot::new(3);
+            assert_eq!(vec![0, 0, 0], snapshot.scan(0));
+            snapshot.update(1, 11);
+            snapshot.update(2, 12);
+            assert_eq!(vec![0, 11, 12], snapshot.scan(0));
+        }
+    }
+
+    mod dyn_bounded_atomic_snapshot {
+        use super::*;
+
+        #[test]
+        fn reads_and_writes() {
+            let snapshot = DynBoundedAtomicSnapshot::new(3);
+            assert_eq!(vec![0, 0, 0], snapshot.scan(0));
+            snapshot.update(1, 11);
+            snapshot.update(2, 12);
+            assert_eq!(vec![0, 11, 12], snapshot.scan(0));
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_too_many_components() {
+            DynBoundedAtomicSnapshot::new(7);
+        }
+    }
+
     mod bounded_atomic_contents {
         use super::BoundedAtomicContents;
 
@@ -330,7 +672,7 @@ mod tests {
 
         #[test]
         fn decodes_zeroes_as_default() {
-            let actual: BoundedAtomicContents<6> = 0.into();
+            let actual: BoundedAtomicContents<6> = 0_u64.into();
             let expected: BoundedAtomicContents<6> = BoundedAtomicContents::default();
             assert_eq!(actual, expected);
         }
@@ -353,7 +695,7 @@ mod tests {
 
         #[test]
         fn decodes_from_u64_correctly() {
-            let contents = BoundedAtomicContents {
+            let contents: BoundedAtomicContents<6> = BoundedAtomicContents {
                 value: 200,
                 view: [1, 2, 3, 4, 5, 6],
                 handshakes: [true, false, false, false, false, true],
@@ -362,5 +704,34 @@ mod tests {
             let encoding: u64 = contents.into();
             assert_eq!(contents, BoundedAtomicContents::from(encoding));
         }
+
+        #[test]
+        fn decodes_from_u128_correctly() {
+            let contents: BoundedAtomicContents<6, u16> = BoundedAtomicContents {
+                value: u16::MAX,
+                view: [1, 2, 3, 4, 5, u16::MAX - 1],
+                handshakes: [true, false, false, true, false, true],
+                toggle: true,
+            };
+            let encoding: u128 = contents.into();
+            assert_eq!(contents, BoundedAtomicContents::from(encoding));
+        }
+
+        #[test]
+        fn encodes_toggle_as_left_most_bit_of_u128() {
+            let contents: BoundedAtomicContents<13> = BoundedAtomicContents {
+                toggle: true,
+                ..Default::default()
+            };
+            let actual: u128 = contents.into();
+            assert_eq!(actual, 1 << 127);
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_too_many_components_for_u64() {
+            let contents: BoundedAtomicContents<7> = BoundedAtomicContents::default();
+            let _: u64 = contents.into();
+        }
     }
 }
@@ -0,0 +1,141 @@
+//! Packing the contents of snapshot components into words of atomic memory.
+use std::fmt::Debug;
+
+use num::{NumCast, PrimInt, Unsigned};
+
+/// An unsigned integer that the contents of a component can be packed into,
+/// so that they can be stored in a single atomic register.
+pub trait Word: PrimInt + Unsigned {
+    /// The number of bits in the word.
+    const BITS: u32;
+}
+
+impl Word for u64 {
+    const BITS: u32 = u64::BITS;
+}
+
+impl Word for u128 {
+    const BITS: u32 = u128::BITS;
+}
+
+/// A value that can be packed into a [`Word`].
+pub trait Packable: Copy + Debug + Default + Eq {
+    /// The number of bits the value occupies once packed.
+    const BITS: u32;
+
+    /// Returns the value, occupying the right-most bits of a word.
+    fn pack<W: Word>(self) -> W;
+
+    /// Returns the value stored in the right-most bits of a word.
+    fn unpack<W: Word>(word: W) -> Self;
+}
+
+macro_rules! impl_packable_for_int {
+    ($($int:ty),*) => {
+        $(
+            impl Packable for $int {
+                const BITS: u32 = <$int>::BITS;
+
+                fn pack<W: Word>(self) -> W {
+                    <W as NumCast>::from(self).unwrap()
+                }
+
+                fn unpack<W: Word>(word: W) -> Self {
+                    let mask = <W as NumCast>::from(<$int>::MAX).unwrap();
+                    <$int as NumCast>::from(word & mask).unwrap()
+                }
+            }
+        )*
+    };
+}
+
+impl_packable_for_int!(u8, u16, u32);
+
+impl Packable for bool {
+    const BITS: u32 = 1;
+
+    fn pack<W: Word>(self) -> W {
+        if self {
+            W::one()
+        } else {
+            W::zero()
+        }
+    }
+
+    fn unpack<W: Word>(word: W) -> Self {
+        word & W::one() == W::one()
+    }
+}
+
+/// Packs a sequence of values into a word, starting from its right-most bits.
+pub struct Packer<W: Word> {
+    word: W,
+    offset: u32,
+}
+
+impl<W: Word> Packer<W> {
+    pub fn new() -> Self {
+        Self {
+            word: W::zero(),
+            offset: 0,
+        }
+    }
+
+    /// Packs a value into the bits to the left of those already packed.
+    pub fn push<P: Packable>(&mut self, value: P) {
+        self.word = self.word | value.pack::<W>() << self.offset as usize;
+        self.offset += P::BITS;
+    }
+
+    /// Returns the packed word.
+    pub fn finish(self) -> W {
+        self.word
+    }
+}
+
+/// Unpacks a sequence of values from a word, in the order they were packed
+/// by a [`Packer`].
+pub struct Unpacker<W: Word> {
+    word: W,
+    offset: u32,
+}
+
+impl<W: Word> Unpacker<W> {
+    pub fn new(word: W) -> Self {
+        Self { word, offset: 0 }
+    }
+
+    /// Unpacks the next value.
+    pub fn pop<P: Packable>(&mut self) -> P {
+        let value = P::unpack(self.word >> self.offset as usize);
+        self.offset += P::BITS;
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpacks_values_in_order_they_were_packed() {
+        let mut packer: Packer<u128> = Packer::new();
+        packer.push(u8::MAX);
+        packer.push(true);
+        packer.push(1_000_u16);
+        packer.push(u32::MAX - 1);
+        let mut unpacker = Unpacker::new(packer.finish());
+        assert_eq!(u8::MAX, unpacker.pop());
+        assert!(unpacker.pop::<bool>());
+        assert_eq!(1_000_u16, unpacker.pop());
+        assert_eq!(u32::MAX - 1, unpacker.pop());
+    }
+
+    #[test]
+    fn packs_first_value_in_right_most_bits() {
+        let mut packer: Packer<u64> = Packer::new();
+        packer.push(0b0000_0001_u8);
+        packer.push(0b1000_0000_u8);
+        assert_eq!(0b1000_0000_0000_0001, packer.finish());
+    }
+}
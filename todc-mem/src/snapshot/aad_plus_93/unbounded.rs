@@ -2,8 +2,10 @@ use core::array::from_fn;
 
 use num::{One, PrimInt, Unsigned};
 
-use crate::register::{AtomicRegister, MutexRegister, Register};
-use crate::snapshot::Snapshot;
+use crate::register::{Atomic128Register, AtomicRegister, MutexRegister, Register};
+use crate::snapshot::{DynSnapshot, Snapshot};
+
+use super::packing::{Packable, Packer, Unpacker, Word};
 
 /// A wait-free `N`-process atomic snapshot object, using [`AtomicRegister`]
 /// objects of unbounded size.
@@ -12,10 +14,31 @@ use crate::snapshot::Snapshot;
 /// `N <= 5` components of [`u8`] values. For similar reasons, and the fact
 /// that this implementation relies on registers of unbounded size, behaviour
 /// is undefined once some process performs more than [`u16::MAX`] operations.
+/// For more components or larger values, see [`UnboundedAtomic128Snapshot`].
 /// For more implementation details, see [`UnboundedSnapshot`].
 pub type UnboundedAtomicSnapshot<const N: usize> =
     UnboundedSnapshot<AtomicRegister<UnboundedAtomicContents<N>>, N>;
 
+/// A wait-free `N`-process atomic snapshot object, using [`Atomic128Register`]
+/// objects of unbounded size.
+///
+/// Components can contain values of type [`u8`], [`u16`] or [`u32`], and are
+/// packed into 128 bits alongside a 16-bit sequence number. This allows, for
+/// example, `N <= 13` components of [`u8`] values or `N <= 6` components of
+/// [`u16`] values. As with [`UnboundedAtomicSnapshot`], behaviour is undefined
+/// once some process performs more than [`u16::MAX`] operations.
+///
+/// This snapshot is only wait-free on hardware that supports 128-bit atomics,
+/// see [`Atomic128Register`] for details. For implementation details, see
+/// [`UnboundedSnapshot`].
+///
+/// # Panics
+///
+/// Creating the snapshot panics if `N` components of type `T` do not fit into
+/// 128 bits.
+pub type UnboundedAtomic128Snapshot<T, const N: usize> =
+    UnboundedSnapshot<Atomic128Register<UnboundedAtomicContents<N, T>>, N>;
+
 /// An `N`-process atomic snapshot object, using [`MutexRegister`] objects
 /// of unbounded size.
 ///
@@ -26,7 +49,7 @@ pub type UnboundedMutexSnapshot<T, const N: usize> =
 
 /// The contents of a component of the snapshot object.
 pub trait Contents<const N: usize>: Default {
-    type Value: Copy;
+    type Value: Clone;
     type SeqSize: PrimInt + Unsigned + One;
 
     /// Creates a new component.
@@ -118,24 +141,24 @@ where
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct UnboundedContents<T: Copy + Default, const N: usize> {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnboundedContents<T: Clone + Default, const N: usize> {
     value: T,
     view: [T; N],
     sequence: u32,
 }
 
-impl<T: Copy + Default, const N: usize> Default for UnboundedContents<T, N> {
+impl<T: Clone + Default, const N: usize> Default for UnboundedContents<T, N> {
     fn default() -> Self {
         Self {
             value: T::default(),
-            view: [T::default(); N],
+            view: from_fn(|_| T::default()),
             sequence: 0,
         }
     }
 }
 
-impl<T: Copy + Default, const N: usize> Contents<N> for UnboundedContents<T, N> {
+impl<T: Clone + Default, const N: usize> Contents<N> for UnboundedContents<T, N> {
     type Value = T;
     type SeqSize = u32;
 
@@ -148,11 +171,11 @@ impl<T: Copy + Default, const N: usize> Contents<N> for UnboundedContents<T, N>
     }
 
     fn value(&self) -> Self::Value {
-        self.value
+        self.value.clone()
     }
 
     fn view(&self) -> [Self::Value; N] {
-        self.view
+        self.view.clone()
     }
 
     fn sequence(&self) -> Self::SeqSize {
@@ -160,15 +183,58 @@ impl<T: Copy + Default, const N: usize> Contents<N> for UnboundedContents<T, N>
     }
 }
 
+/// The contents of a component of an [`UnboundedAtomicSnapshot`] or an
+/// [`UnboundedAtomic128Snapshot`].
+///
+/// Contents are packed into a single word, with the value in the right-most
+/// bits, followed by the view and then the sequence number.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-pub struct UnboundedAtomicContents<const N: usize> {
-    value: u8,
-    view: [u8; N],
+pub struct UnboundedAtomicContents<const N: usize, T = u8> {
+    value: T,
+    view: [T; N],
     sequence: u16,
 }
 
-impl<const N: usize> Contents<N> for UnboundedAtomicContents<N> {
-    type Value = u8;
+impl<const N: usize, T: Packable> UnboundedAtomicContents<N, T> {
+    /// The number of bits occupied by the contents, once packed.
+    const BITS: u32 = T::BITS * (N as u32 + 1) + u16::BITS;
+
+    /// Panics if the contents do not fit into a word of type `W`.
+    fn assert_fits<W: Word>() {
+        assert!(
+            Self::BITS <= W::BITS,
+            "UnboundedAtomicContents with {N} components of {}-bit values require {} bits, \
+             but only {} are available",
+            T::BITS,
+            Self::BITS,
+            W::BITS
+        );
+    }
+
+    fn pack<W: Word>(self) -> W {
+        Self::assert_fits::<W>();
+        let mut packer = Packer::<W>::new();
+        packer.push(self.value);
+        for value in self.view {
+            packer.push(value);
+        }
+        packer.push(self.sequence);
+        packer.finish()
+    }
+
+    fn unpack<W: Word>(word: W) -> Self {
+        Self::assert_fits::<W>();
+        let mut unpacker = Unpacker::new(word);
+        Self {
+            value: unpacker.pop(),
+            view: from_fn(|_| unpacker.pop()),
+            sequence: unpacker.pop(),
+        }
+    }
+}
+
+impl<const N: usize, T: Packable> Contents<N> for UnboundedAtomicContents<N, T> {
+    type Value = T;
     type SeqSize = u16;
 
     fn new(value: Self::Value, sequence: Self::SeqSize, view: [Self::Value; N]) -> Self {
@@ -192,52 +258,232 @@ impl<const N: usize> Contents<N> for UnboundedAtomicContents<N> {
     }
 }
 
-impl<const N: usize> Default for UnboundedAtomicContents<N> {
+impl<const N: usize, T: Packable> Default for UnboundedAtomicContents<N, T> {
     fn default() -> Self {
-        // TODO: Find a better way to bound N
-        if N > 5 {
-            panic!("UnboundedAtomicContents are only valid for 5 threads or fewer")
-        };
         Self {
-            value: 0,
-            view: [0; N],
+            value: T::default(),
+            view: [T::default(); N],
             sequence: 0,
         }
     }
 }
 
-impl<const N: usize> From<u64> for UnboundedAtomicContents<N> {
-    fn from(encoding: u64) -> Self {
-        // Decode value from right-must 8 bits
-        let value = (encoding & (u8::MAX as u64)) as u8;
-        // Decode view from (reversed) sequence of 8-bit values
-        let view = from_fn(|i| {
-            let shift = 8 * (i + 1);
-            ((encoding & (u8::MAX as u64) << shift) >> shift) as u8
-        });
-        // Decode sequence number from remaining left-most bits
-        let shift = 8 * (N + 1);
-        let sequence = ((encoding & ((u16::MAX as u64) << shift)) >> shift) as u16;
+macro_rules! impl_word_conversions {
+    ($($word:ty),*) => {
+        $(
+            impl<const N: usize, T: Packable> From<$word> for UnboundedAtomicContents<N, T> {
+                fn from(encoding: $word) -> Self {
+                    Self::unpack(encoding)
+                }
+            }
+
+            impl<const N: usize, T: Packable> From<UnboundedAtomicContents<N, T>> for $word {
+                fn from(contents: UnboundedAtomicContents<N, T>) -> Self {
+                    contents.pack()
+                }
+            }
+        )*
+    };
+}
+
+impl_word_conversions!(u64, u128);
+
+/// A wait-free atomic snapshot object, using [`AtomicRegister`] objects of
+/// unbounded size, whose number of components is chosen at runtime.
+///
+/// The same limitations as [`UnboundedAtomicSnapshot`] apply, so this snapshot
+/// can only contain `n <= 5` components of [`u8`] values. For implementation
+/// details, see [`UnboundedSnapshot`].
+pub type DynUnboundedAtomicSnapshot =
+    DynUnboundedSnapshot<AtomicRegister<DynUnboundedAtomicContents>>;
+
+/// An atomic snapshot object, using [`MutexRegister`] objects of unbounded
+/// size, whose number of components is chosen at runtime.
+///
+/// This snapshot is **not** lock-free. For implementation details, see
+/// [`UnboundedSnapshot`].
+pub type DynUnboundedMutexSnapshot<T> =
+    DynUnboundedSnapshot<MutexRegister<DynUnboundedContents<T>>>;
+
+/// The contents of a component of a snapshot object whose number of
+/// components is chosen at runtime.
+pub trait DynContents: Default {
+    type Value: Clone;
+    type SeqSize: PrimInt + Unsigned + One;
+
+    /// The maximum number of components that can be stored.
+    const MAX_COMPONENTS: usize = usize::MAX;
+
+    /// Creates a new component.
+    fn new(value: Self::Value, sequence: Self::SeqSize, view: Vec<Self::Value>) -> Self;
+
+    /// Returns the sequence number stored in this component.
+    fn sequence(&self) -> Self::SeqSize;
+
+    /// Returns the value stored in this component.
+    fn value(&self) -> Self::Value;
+
+    /// Returns the view stored in this component.
+    fn view(&self) -> Vec<Self::Value>;
+}
+
+/// A wait-free snapshot object using unbounded memory, whose number of
+/// components is chosen at runtime.
+///
+/// This is the same algorithm as [`UnboundedSnapshot`], with registers
+/// allocated when the object is created.
+pub struct DynUnboundedSnapshot<R: Register>
+where
+    R::Value: DynContents,
+{
+    registers: Vec<R>,
+}
+
+impl<R: Register> DynUnboundedSnapshot<R>
+where
+    R::Value: DynContents,
+{
+    /// Returns a vector of values, obtained by sequentially
+    /// performing a read on each component of the snapshot.
+    fn collect(&self) -> Vec<R::Value> {
+        self.registers.iter().map(|r| r.read()).collect()
+    }
+}
+
+impl<R: Register> DynSnapshot for DynUnboundedSnapshot<R>
+where
+    R::Value: DynContents,
+{
+    type Value = <R::Value as DynContents>::Value;
+
+    /// Creates a new snapshot object with `n` components.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the contents of the registers cannot store `n` components.
+    fn new(n: usize) -> Self {
+        let max = <R::Value as DynContents>::MAX_COMPONENTS;
+        if n > max {
+            panic!("This snapshot supports at most {max} components")
+        }
+        Self {
+            registers: (0..n).map(|_| R::new()).collect(),
+        }
+    }
+
+    fn components(&self) -> usize {
+        self.registers.len()
+    }
+
+    fn scan(&self, _: usize) -> Vec<Self::Value> {
+        let n = self.components();
+        let mut moved = vec![0; n];
+        loop {
+            let first = self.collect();
+            let second = self.collect();
+            if (0..n).all(|j| first[j].sequence() == second[j].sequence()) {
+                return second.iter().map(|c| c.value()).collect();
+            }
+            for j in 0..n {
+                if first[j].sequence() != second[j].sequence() {
+                    if moved[j] == 1 {
+                        let mut view = second[j].view();
+                        view.truncate(n);
+                        return view;
+                    } else {
+                        moved[j] += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    fn update(&self, i: usize, value: Self::Value) {
+        let contents = DynContents::new(
+            value,
+            self.registers[i].read().sequence() + <R::Value as DynContents>::SeqSize::one(),
+            self.scan(i),
+        );
+        self.registers[i].write(contents);
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DynUnboundedContents<T: Clone + Default> {
+    value: T,
+    view: Vec<T>,
+    sequence: u32,
+}
+
+impl<T: Clone + Default> DynContents for DynUnboundedContents<T> {
+    type Value = T;
+    type SeqSize = u32;
+
+    fn new(value: Self::Value, sequence: Self::SeqSize, view: Vec<Self::Value>) -> Self {
         Self {
             value,
             view,
             sequence,
         }
     }
+
+    fn value(&self) -> Self::Value {
+        self.value.clone()
+    }
+
+    fn view(&self) -> Vec<Self::Value> {
+        // Components that have never been updated store an empty view.
+        self.view.clone()
+    }
+
+    fn sequence(&self) -> Self::SeqSize {
+        self.sequence
+    }
 }
 
-impl<const N: usize> From<UnboundedAtomicContents<N>> for u64 {
-    fn from(contents: UnboundedAtomicContents<N>) -> Self {
-        let mut result: u64 = 0;
-        // Encode value as right-most 8 bits
-        result |= contents.value as u64;
-        // Encode view as (reversed) sequence of 8-bit values
-        for (i, value) in contents.view.iter().enumerate() {
-            result |= (*value as u64) << (8 * (i + 1))
-        }
-        // Encode sequence number in remaining left-most bits
-        result |= (contents.sequence as u64) << (8 * (N + 1));
-        result
+/// The contents of a component of a [`DynUnboundedAtomicSnapshot`].
+///
+/// Views are always stored with `5` components, and are truncated by the
+/// snapshot object.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DynUnboundedAtomicContents(UnboundedAtomicContents<5>);
+
+impl DynContents for DynUnboundedAtomicContents {
+    type Value = u8;
+    type SeqSize = u16;
+
+    const MAX_COMPONENTS: usize = 5;
+
+    fn new(value: Self::Value, sequence: Self::SeqSize, view: Vec<Self::Value>) -> Self {
+        Self(UnboundedAtomicContents {
+            value,
+            view: from_fn(|i| view.get(i).copied().unwrap_or_default()),
+            sequence,
+        })
+    }
+
+    fn value(&self) -> Self::Value {
+        self.0.value
+    }
+
+    fn view(&self) -> Vec<Self::Value> {
+        self.0.view.to_vec()
+    }
+
+    fn sequence(&self) -> Self::SeqSize {
+        self.0.sequence
+    }
+}
+
+impl From<u64> for DynUnboundedAtomicContents {
+    fn from(encoding: u64) -> Self {
+        Self(UnboundedAtomicContents::from(encoding))
+    }
+}
+
+impl From<DynUnboundedAtomicContents> for u64 {
+    fn from(contents: DynUnboundedAtomicContents) -> Self {
+        contents.0.into()
     }
 }
 
@@ -256,6 +502,15 @@ mod tests {
             snapshot.update(2, 12);
             assert_eq!([0, 11, 12], snapshot.scan(0));
         }
+
+        #[test]
+        fn reads_and_writes_strings() {
+            let snapshot: UnboundedMutexSnapshot<String, 3> = UnboundedMutexSnapshot::new();
+            assert_eq!(["", "", ""], snapshot.scan(0));
+            snapshot.update(1, String::from("one"));
+            snapshot.update(2, String::from("two"));
+            assert_eq!(["", "one", "two"], snapshot.scan(0));
+        }
     }
 
     mod unbounded_atomic_snapshot {
@@ -271,6 +526,65 @@ mod tests {
         }
     }
 
+    mod unbounded_atomic_128_snapshot {
+        use super::*;
+
+        #[test]
+        fn reads_and_writes() {
+            let snapshot: UnboundedAtomic128Snapshot<u8, 13> = UnboundedAtomic128Snapshot::new();
+            assert_eq!([0; 13], snapshot.scan(0));
+            snapshot.update(1, 11);
+            snapshot.update(12, 22);
+            assert_eq!([0, 11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 22], snapshot.scan(0));
+        }
+
+        #[test]
+        fn reads_and_writes_wide_values() {
+            let snapshot: UnboundedAtomic128Snapshot<u16, 6> = UnboundedAtomic128Snapshot::new();
+            snapshot.update(1, 1_000);
+            snapshot.update(5, u16::MAX);
+            assert_eq!([0, 1_000, 0, 0, 0, u16::MAX], snapshot.scan(0));
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_too_many_components() {
+            let _: UnboundedAtomic128Snapshot<u8, 14> = UnboundedAtomic128Snapshot::new();
+        }
+    }
+
+    mod dyn_unbounded_mutex_snapshot {
+        use super::*;
+
+        #[test]
+        fn reads_and_writes() {
+            let snapshot: DynUnboundedMutexSnapshot<usize> = DynUnboundedMutexSnapshot::new(3);
+            assert_eq!(vec![0, 0, 0], snapshot.scan(0));
+            snapshot.update(1, 11);
+            snapshot.update(2, 12);
+            assert_eq!(vec![0, 11, 12], snapshot.scan(0));
+        }
+    }
+
+    mod dyn_unbounded_atomic_snapshot {
+        use super::*;
+
+        #[test]
+        fn reads_and_writes() {
+            let snapshot = DynUnboundedAtomicSnapshot::new(3);
+            assert_eq!(vec![0, 0, 0], snapshot.scan(0));
+            snapshot.update(1, 11);
+            snapshot.update(2, 12);
+            assert_eq!(vec![0, 11, 12], snapshot.scan(0));
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_too_many_components() {
+            DynUnboundedAtomicSnapshot::new(6);
+        }
+    }
+
     mod unbounded_int_contents {
         use super::*;
 
@@ -279,7 +593,7 @@ mod tests {
 
             #[test]
             fn decodes_if_two_processes() {
-                let contents = UnboundedAtomicContents {
+                let contents: UnboundedAtomicContents<2> = UnboundedAtomicContents {
                     value: 200,
                     view: [1, 2],
                     sequence: 10_000,
@@ -290,7 +604,7 @@ mod tests {
 
             #[test]
             fn decodes_if_three_processes() {
-                let contents = UnboundedAtomicContents {
+                let contents: UnboundedAtomicContents<3> = UnboundedAtomicContents {
                     value: 200,
                     view: [1, 2, 3],
                     sequence: 10_000,
@@ -301,7 +615,7 @@ mod tests {
 
             #[test]
             fn decodes_if_four_processes() {
-                let contents = UnboundedAtomicContents {
+                let contents: UnboundedAtomicContents<4> = UnboundedAtomicContents {
                     value: 200,
                     view: [1, 2, 3, 4],
                     sequence: 10_000,
@@ -312,7 +626,7 @@ mod tests {
 
             #[test]
             fn decodes_if_five_processes() {
-                let contents = UnboundedAtomicContents {
+                let contents: UnboundedAtomicContents<5> = UnboundedAtomicContents {
                     value: 200,
                     view: [1, 2, 3, 4, 5],
                     sequence: 10_000,
@@ -399,4 +713,49 @@ mod tests {
             }
         }
     }
+
+    mod unbounded_atomic_128_contents {
+        use super::*;
+
+        #[test]
+        fn decodes_if_thirteen_processes() {
+            let contents: UnboundedAtomicContents<13> = UnboundedAtomicContents {
+                value: 200,
+                view: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13],
+                sequence: 10_000,
+            };
+            let encoding: u128 = contents.into();
+            assert_eq!(contents, UnboundedAtomicContents::from(encoding));
+        }
+
+        #[test]
+        fn decodes_wide_values() {
+            let contents: UnboundedAtomicContents<2, u32> = UnboundedAtomicContents {
+                value: u32::MAX,
+                view: [1, u32::MAX - 1],
+                sequence: u16::MAX,
+            };
+            let encoding: u128 = contents.into();
+            assert_eq!(contents, UnboundedAtomicContents::from(encoding));
+        }
+
+        #[test]
+        fn encodes_same_bits_as_u64() {
+            let contents: UnboundedAtomicContents<5> = UnboundedAtomicContents {
+                value: 0b00100100,
+                view: [0b10001111, 0b10000111, 0b10000011, 0b10000001, 0b10000000],
+                sequence: 0b11000000_11000000,
+            };
+            let narrow: u64 = contents.into();
+            let wide: u128 = contents.into();
+            assert_eq!(narrow as u128, wide);
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_too_many_components_for_u64() {
+            let contents: UnboundedAtomicContents<6> = UnboundedAtomicContents::default();
+            let _: u64 = contents.into();
+        }
+    }
 }
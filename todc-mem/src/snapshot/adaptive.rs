@@ -0,0 +1,274 @@
+//! Snapshot objects whose step complexity adapts to contention, in the
+//! style of Attiya and Fouren, _Algorithms Adapting to Point Contention_
+//! \[AF03\].
+//!
+//! The snapshot objects in [`aad_plus_93`](super::aad_plus_93) and
+//! [`ar_98`](super::ar_98) read from the register of every one of the `n`
+//! components during each [`scan`](DynSnapshot::scan), even if only a few
+//! processes ever use the object. An _adaptive_ snapshot object instead
+//! performs a number of [`read`](Register::read) and
+//! [`write`](Register::write) operations that depends on the number `k` of
+//! processes that actually participate, which can be much smaller than `n`.
+//!
+//! # Implementation
+//!
+//! Each process _joins_ the object before its first update, by claiming the
+//! next free slot of an _active set_ with a fetch-and-add, and announcing its
+//! ID in that slot. A scan only collects the components of processes that
+//! have announced themselves, and otherwise follows the unbounded algorithm
+//! of [\[AAD+93\]](https://dl.acm.org/doi/10.1145/153724.153741): it
+//! repeatedly performs two collects until they are identical, or until it
+//! sees some process change its component twice, in which case it returns
+//! the view that process embedded alongside its latest update. Views only
+//! contain the components of processes that had joined, and every other
+//! component contains its default value.
+//!
+//! Since a process that has not yet joined has never changed its component,
+//! a scan that misses it, because it has not yet announced itself, can be
+//! linearized before its first update. Each scan performs _O(_ k _)_
+//! collects of _O(_ k _)_ components, and so each operation performs
+//! _O(_ k^2 _)_ operations on the underlying registers, regardless of `n`.
+//!
+//! Since the object is long-lived, the value of a component persists after
+//! the process that wrote it stops participating, and so `k` is the number of
+//! processes that have **ever** updated the object, which is also known as
+//! its _total contention_. Processes that only scan the object never join
+//! it. Attiya and Fouren describe more intricate algorithms that adapt to
+//! the number of processes that are active at the same time, which this
+//! implementation does not attempt.
+//!
+//! # Examples
+//!
+//! A snapshot with many components that is only ever updated by a few
+//! processes.
+//!
+//! ```
+//! use todc_mem::snapshot::{DynAdaptiveMutexSnapshot, DynSnapshot};
+//!
+//! let snapshot: DynAdaptiveMutexSnapshot<u32> = DynAdaptiveMutexSnapshot::new(1000);
+//! snapshot.update(3, 30);
+//! snapshot.update(7, 70);
+//! assert_eq!(snapshot.participants(), 2);
+//!
+//! let view = snapshot.scan(0);
+//! assert_eq!((view[3], view[7]), (30, 70));
+//! assert_eq!(view.iter().sum::<u32>(), 100);
+//! ```
+use core::array::from_fn;
+
+use super::{DynSnapshot, ProcessId, Snapshot};
+use crate::register::{MutexRegister, Register};
+use crate::sync::{AtomicU64, Ordering};
+
+/// The contents of one component of a snapshot object.
+#[derive(Clone, Default)]
+struct Component<T: Clone + Default> {
+    value: T,
+    sequence: u32,
+    view: Vec<(ProcessId, T)>,
+}
+
+/// An adaptive `N`-process atomic snapshot object, using [`MutexRegister`]
+/// objects.
+///
+/// This snapshot object is **not** lock-free. For implementation details,
+/// see [`DynAdaptiveMutexSnapshot`].
+pub struct AdaptiveMutexSnapshot<T: Clone + Default, const N: usize> {
+    snapshot: DynAdaptiveMutexSnapshot<T>,
+}
+
+impl<T: Clone + Default, const N: usize> Snapshot<N> for AdaptiveMutexSnapshot<T, N> {
+    type Value = T;
+
+    fn new() -> Self {
+        Self {
+            snapshot: DynAdaptiveMutexSnapshot::new(N),
+        }
+    }
+
+    fn scan(&self, i: ProcessId) -> [Self::Value; N] {
+        let mut values = self.snapshot.scan(i).into_iter();
+        from_fn(|_| values.next().unwrap())
+    }
+
+    fn update(&self, i: ProcessId, value: Self::Value) {
+        self.snapshot.update(i, value);
+    }
+}
+
+/// An adaptive atomic snapshot object, using [`MutexRegister`] objects,
+/// whose number of components is chosen at runtime.
+///
+/// Each operation performs _O(_ k^2 _)_ operations on the underlying
+/// registers, where `k` is the number of processes that have updated the
+/// object. See the [`adaptive`](super::adaptive) module-level documentation
+/// for more details.
+///
+/// This snapshot object is **not** lock-free.
+pub struct DynAdaptiveMutexSnapshot<T: Clone + Default> {
+    registers: Vec<MutexRegister<Component<T>>>,
+    /// The number of slots of the active set that have been claimed.
+    joined: AtomicU64,
+    /// The active set, in which each slot contains one more than the ID of
+    /// the process that claimed it, or `0` if it has not been announced yet.
+    slots: Vec<AtomicU64>,
+}
+
+impl<T: Clone + Default> DynAdaptiveMutexSnapshot<T> {
+    /// Returns the number of processes that have joined the object.
+    pub fn participants(&self) -> usize {
+        (self.joined.load(Ordering::SeqCst) as usize).min(self.slots.len())
+    }
+
+    /// Adds the _i^{th}_ process to the active set.
+    fn join(&self, i: ProcessId) {
+        let slot = self.joined.fetch_add(1, Ordering::SeqCst) as usize;
+        self.slots[slot].store(i as u64 + 1, Ordering::SeqCst);
+    }
+
+    /// Reads the component of each process that has announced itself in the
+    /// active set, indexed by the slot that it claimed.
+    fn collect(&self) -> Vec<Option<(ProcessId, Component<T>)>> {
+        self.slots[..self.participants()]
+            .iter()
+            .map(|slot| match slot.load(Ordering::SeqCst) {
+                0 => None,
+                id => {
+                    let j = id as usize - 1;
+                    Some((j, self.registers[j].read()))
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the value of the component of each process that had joined
+    /// the object, as pairs of process IDs and values.
+    fn sparse_scan(&self) -> Vec<(ProcessId, T)> {
+        // A process has moved if its sequence number has been incremented.
+        let mut moved: Vec<u8> = Vec::new();
+        loop {
+            let first = self.collect();
+            let second = self.collect();
+            moved.resize(second.len(), 0);
+            let mut identical = true;
+            for (slot, contents) in second.iter().enumerate() {
+                let Some((_, component)) = contents else {
+                    continue;
+                };
+                // A process that was not seen by the first collect had not
+                // yet updated its component when it was performed.
+                let before = match first.get(slot) {
+                    Some(Some((_, component))) => component.sequence,
+                    _ => 0,
+                };
+                if before != component.sequence {
+                    identical = false;
+                    // If a process is observed to have moved twice, then it
+                    // must have performed a complete update during this scan,
+                    // and the view it embedded can be borrowed.
+                    if moved[slot] == 1 {
+                        return component.view.clone();
+                    }
+                    moved[slot] += 1;
+                }
+            }
+            if identical {
+                return second
+                    .into_iter()
+                    .flatten()
+                    .map(|(j, component)| (j, component.value))
+                    .collect();
+            }
+        }
+    }
+}
+
+impl<T: Clone + Default> DynSnapshot for DynAdaptiveMutexSnapshot<T> {
+    type Value = T;
+
+    fn new(n: usize) -> Self {
+        Self {
+            registers: (0..n).map(|_| MutexRegister::new()).collect(),
+            joined: AtomicU64::new(0),
+            slots: (0..n).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    fn components(&self) -> usize {
+        self.registers.len()
+    }
+
+    fn scan(&self, _: ProcessId) -> Vec<Self::Value> {
+        let mut values = vec![T::default(); self.registers.len()];
+        for (j, value) in self.sparse_scan() {
+            values[j] = value;
+        }
+        values
+    }
+
+    fn update(&self, i: ProcessId, value: Self::Value) {
+        let current = self.registers[i].read();
+        if current.sequence == 0 {
+            self.join(i);
+        }
+        let view = self.sparse_scan();
+        self.registers[i].write(Component {
+            value,
+            sequence: current.sequence + 1,
+            view,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_and_writes() {
+        let snapshot: AdaptiveMutexSnapshot<usize, 3> = AdaptiveMutexSnapshot::new();
+        assert_eq!([0, 0, 0], snapshot.scan(0));
+        snapshot.update(1, 1);
+        snapshot.update(2, 2);
+        assert_eq!([0, 1, 2], snapshot.scan(0));
+        snapshot.update(0, 10);
+        snapshot.update(1, 11);
+        snapshot.update(2, 12);
+        assert_eq!([10, 11, 12], snapshot.scan(0));
+    }
+
+    #[test]
+    fn reads_and_writes_strings() {
+        let snapshot: DynAdaptiveMutexSnapshot<String> = DynAdaptiveMutexSnapshot::new(3);
+        snapshot.update(1, String::from("one"));
+        snapshot.update(2, String::from("two"));
+        assert_eq!(vec!["", "one", "two"], snapshot.scan(0));
+    }
+
+    #[test]
+    fn only_updating_processes_join() {
+        let snapshot: DynAdaptiveMutexSnapshot<usize> = DynAdaptiveMutexSnapshot::new(100);
+        snapshot.scan(0);
+        assert_eq!(snapshot.participants(), 0);
+        for value in 1..=10 {
+            snapshot.update(42, value);
+            snapshot.scan(0);
+        }
+        assert_eq!(snapshot.participants(), 1);
+        assert_eq!(snapshot.scan(0)[42], 10);
+    }
+
+    #[test]
+    fn collects_only_participants() {
+        let snapshot: DynAdaptiveMutexSnapshot<usize> = DynAdaptiveMutexSnapshot::new(100);
+        snapshot.update(7, 1);
+        snapshot.update(3, 2);
+        let participants: Vec<ProcessId> = snapshot
+            .collect()
+            .into_iter()
+            .flatten()
+            .map(|(j, _)| j)
+            .collect();
+        assert_eq!(participants, vec![7, 3]);
+    }
+}
@@ -14,40 +14,75 @@
 //!
 //! For examples, see the [`snapshot`](super) documentation.
 //!
+//! # Unbounded Number of Operations
+//!
+//! The [`LatticeMutexSnapshot`] is an `M`-shot object, meaning that it
+//! supports at most `M` operations in total, across all processes. For
+//! long-running workloads, an [`UnboundedLatticeMutexSnapshot`] instead
+//! supports any number of operations, by performing them on a sequence of
+//! `M`-shot objects. Once every operation on one object has returned, the
+//! next object is created with the values that its components ended with,
+//! and the old object is dropped.
+//!
+//! ```
+//! use todc_mem::snapshot::{Snapshot, UnboundedLatticeMutexSnapshot};
+//!
+//! let snapshot: UnboundedLatticeMutexSnapshot<usize, 3, 16> =
+//!     UnboundedLatticeMutexSnapshot::new();
+//! for value in 1..=100 {
+//!     snapshot.update(0, value);
+//! }
+//! assert_eq!([100, 0, 0], snapshot.scan(1));
+//! ```
+//!
+//! A process that finds that every operation on the current object has
+//! already been claimed waits for the operations of other processes to
+//! return, and so, like the `M`-shot object, this snapshot object is **not**
+//! lock-free.
+//!
 //! # Wait-Free Variant
 //!
 //! Due to the size constraints of
 //! [`AtomicRegister`](crate::register::AtomicRegister) there is no wait-free,
 //! or even lock-free, implementation of this snapshot object available.
-use super::Snapshot;
+use std::sync::Arc;
+
+use super::{DynSnapshot, Snapshot};
 use crate::register::{MutexRegister, Register};
+use crate::sync::{spin_loop, Mutex};
 use core::array::from_fn;
 
 /// The contents of one component of a snapshot object.
-#[derive(Clone, Copy, Default)]
-struct Component<T: Copy + Default> {
+#[derive(Clone, Default)]
+struct Component<T: Clone + Default> {
     value: T,
     sequence: u32,
     counter: u32,
 }
 
 /// A view of all components of a snapshot object.
-#[derive(Clone, Copy)]
-struct View<T: Copy + Default, const N: usize> {
-    components: [Component<T>; N],
+#[derive(Clone, Default)]
+struct View<T: Clone + Default> {
+    components: Vec<Component<T>>,
 }
 
-impl<T: Copy + Default, const N: usize> View<T, N> {
-    /// Returns the union of an array of views.
+impl<T: Clone + Default> View<T> {
+    /// Returns the union of a collection of views of `n` components.
     ///
-    /// The union of an array of views V_1...V_N is a new view V where each
-    /// component V[i] is equal to the component V_j[i] with maximal _sequence_ field.
-    fn union_many(views: [View<T, N>; N]) -> View<T, N> {
+    /// The union of views V_1...V_N is a new view V where each component V[i]
+    /// is equal to the component V_j[i] with maximal _sequence_ field. Views that
+    /// have never been written are empty, and are ignored.
+    fn union_many(views: Vec<View<T>>, n: usize) -> View<T> {
         View {
-            components: from_fn(|i| {
-                let max = views.iter().max_by_key(|v| v.components[i].sequence);
-                max.unwrap().components[i]
-            }),
+            components: (0..n)
+                .map(|i| {
+                    let max = views
+                        .iter()
+                        .filter_map(|v| v.components.get(i))
+                        .max_by_key(|c| c.sequence);
+                    max.cloned().unwrap_or_default()
+                })
+                .collect(),
         }
     }
 
@@ -57,48 +92,40 @@ impl<T: Copy + Default, const N: usize> View<T, N> {
     /// Intutively, the size of the view corresponds to the amount of knowledge
     /// that the view contains.
     fn size(&self) -> u32 {
-        self.components.map(|c| c.counter).iter().sum()
+        self.components.iter().map(|c| c.counter).sum()
     }
 
-    /// Returns an array of values stored in the components of the view.
-    fn values(&self) -> [T; N] {
-        self.components.map(|c| c.value)
-    }
-}
-
-impl<T: Copy + Default, const N: usize> Default for View<T, N> {
-    fn default() -> Self {
-        Self {
-            components: [(); N].map(|_| Component::default()),
-        }
+    /// Returns the values stored in the components of the view.
+    fn values(&self) -> Vec<T> {
+        self.components.iter().map(|c| c.value.clone()).collect()
     }
 }
 
 /// Groups for classifying processes based on their view of the components
 /// of a snapshot object.
-enum Group<T: Copy + Default, const N: usize> {
-    Primary(View<T, N>),
+enum Group<T: Clone + Default> {
+    Primary(View<T>),
     Secondary,
 }
 
 /// An object for classifying processes into two disjoint groups and updating
 /// their knowledge of the contents of a snapshot objects components.
-struct Classifier<T: Copy + Default, const N: usize> {
-    registers: [MutexRegister<View<T, N>>; N],
+#[derive(Default)]
+struct Classifier<T: Clone + Default> {
+    registers: Vec<MutexRegister<View<T>>>,
 }
 
-impl<T: Copy + Default, const N: usize> Default for Classifier<T, N> {
-    fn default() -> Self {
+impl<T: Clone + Default> Classifier<T> {
+    /// Creates a classifier for `n` processes.
+    fn new(n: usize) -> Self {
         Self {
-            registers: [(); N].map(|_| MutexRegister::new()),
+            registers: (0..n).map(|_| MutexRegister::new()).collect(),
         }
     }
-}
 
-impl<T: Copy + Default, const N: usize> Classifier<T, N> {
-    /// Reads from each register and returns an array of the results.
-    fn collect(&self) -> [View<T, N>; N] {
-        from_fn(|i| self.registers[i].read())
+    /// Reads from each register and returns the results.
+    fn collect(&self) -> Vec<View<T>> {
+        self.registers.iter().map(|r| r.read()).collect()
     }
 
     /// Classify the input process into either a _primary_ or _secondary group_, and
@@ -115,9 +142,9 @@ impl<T: Copy + Default, const N: usize> Classifier<T, N> {
     /// greater than the knowledge bound, then it is placed in the primary group.
     /// If the amount of knowledge a process has is less than the knowledge bound,
     /// it is placed in the secondary group.
-    fn classify(&self, i: usize, knowledge_bound: u32, view: View<T, N>) -> Group<T, N> {
-        self.registers[i].write(view);
-        let union = View::union_many(self.collect());
+    fn classify(&self, i: usize, knowledge_bound: u32, view: &View<T>) -> Group<T> {
+        self.registers[i].write(view.clone());
+        let union = View::union_many(self.collect(), self.registers.len());
         if union.size() > knowledge_bound {
             Group::Primary(union)
         } else {
@@ -128,42 +155,113 @@ impl<T: Copy + Default, const N: usize> Classifier<T, N> {
 
 /// A lattice-agreement based `N`-process atomic snapshot object, using [`MutexRegister`] objects.
 ///
+/// This snapshot object is **not** lock-free. For implementation details, see
+/// [`DynLatticeMutexSnapshot`].
+pub struct LatticeMutexSnapshot<T: Clone + Default, const N: usize, const M: u32> {
+    snapshot: DynLatticeMutexSnapshot<T, M>,
+}
+
+impl<T: Clone + Default, const N: usize, const M: u32> Snapshot<N>
+    for LatticeMutexSnapshot<T, N, M>
+{
+    type Value = T;
+
+    /// Create a new snapshot object.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if M, the number of operations that can be
+    /// applied to the object, is not a power of 2.
+    fn new() -> Self {
+        Self {
+            snapshot: DynLatticeMutexSnapshot::new(N),
+        }
+    }
+
+    fn scan(&self, i: usize) -> [Self::Value; N] {
+        let mut values = self.snapshot.scan(i).into_iter();
+        from_fn(|_| values.next().unwrap())
+    }
+
+    fn update(&self, i: usize, value: Self::Value) {
+        self.snapshot.update(i, value);
+    }
+}
+
+/// A lattice-agreement based atomic snapshot object, using [`MutexRegister`]
+/// objects, whose number of components is chosen at runtime.
+///
+/// This snapshot object supports at most `M` operations. For a snapshot
+/// object that supports any number of operations, see
+/// [`DynUnboundedLatticeMutexSnapshot`].
+///
 /// This snapshot object is **not** lock-free.
-// TODO: Modify this implementation to an infinity-shot snapshot object, as
-// described in the paper.
-pub struct LatticeMutexSnapshot<T: Copy + Default, const N: usize, const M: u32> {
-    components: [MutexRegister<Component<T>>; N],
-    root: Box<CompleteBinaryTree<Classifier<T, N>>>,
+pub struct DynLatticeMutexSnapshot<T: Clone + Default, const M: u32> {
+    components: Vec<MutexRegister<Component<T>>>,
+    root: Box<CompleteBinaryTree<Classifier<T>>>,
 }
 
-impl<T: Copy + Default, const N: usize, const M: u32> LatticeMutexSnapshot<T, N, M> {
-    /// Reads from each register and returns an array of the results.
-    fn collect(&self) -> View<T, N> {
+impl<T: Clone + Default, const M: u32> DynLatticeMutexSnapshot<T, M> {
+    /// Creates a new snapshot object whose components initially contain the
+    /// given values.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if M, the number of operations that can be
+    /// applied to the object, is not a power of 2.
+    fn with_values(values: Vec<T>) -> Self {
+        // log_2(M) must be an integer to construct a complete binary tree of
+        // that height.
+        if !((M as f32).log2() == (M as f32).log2().floor()) {
+            panic!("The number M of supported operations must be a power of 2")
+        }
+        let height = (M as f32).log2().floor() as u32;
+        let n = values.len();
+        let components = values
+            .into_iter()
+            .map(|value| {
+                let register = MutexRegister::new();
+                register.write(Component {
+                    value,
+                    ..Component::default()
+                });
+                register
+            })
+            .collect();
+        Self {
+            components,
+            root: Box::new(CompleteBinaryTree::new_with(height, &|| Classifier::new(n))),
+        }
+    }
+
+    /// Reads from each register and returns the results.
+    fn collect(&self) -> View<T> {
         View {
-            components: from_fn(|i| self.components[i].read()),
+            components: self.components.iter().map(|c| c.read()).collect(),
         }
     }
 
-    /// Returns an array of values based on the contents of the snapshot object.
+    /// Returns the values of the snapshot object, based on the knowledge
+    /// obtained by traversing the tree.
     ///
     /// The values are determined by having the process traverse through log_2(M)
     /// levels of a complete binary tree. At each level, the knowledge the process
     /// has about the contents of the snapshot object either increases (and the
     /// process decends to the right) or stays the same (and the process decends to
-    /// the left). Once the process reaches a leaf, it returns an array of values
+    /// the left). Once the process reaches a leaf, it returns the values
     /// based on the knowledge it obtained during this traversal.
     fn traverse(
         i: usize,
-        node: &CompleteBinaryTree<Classifier<T, N>>,
-        view: View<T, N>,
+        node: &CompleteBinaryTree<Classifier<T>>,
+        view: View<T>,
         label: u32,
-    ) -> [T; N] {
+    ) -> Vec<T> {
         match node {
-            CompleteBinaryTree::Leaf(cls) => match cls.classify(i, label, view) {
+            CompleteBinaryTree::Leaf(cls) => match cls.classify(i, label, &view) {
                 Group::Primary(union) => union.values(),
                 Group::Secondary => view.values(),
             },
-            CompleteBinaryTree::Node(cls, left, right) => match cls.classify(i, label, view) {
+            CompleteBinaryTree::Node(cls, left, right) => match cls.classify(i, label, &view) {
                 Group::Primary(union) => {
                     let label = label + (M / 2_u32.pow(right.level() + 1));
                     Self::traverse(i, right, union, label)
@@ -178,7 +276,7 @@ impl<T: Copy + Default, const N: usize, const M: u32> LatticeMutexSnapshot<T, N,
 
     /// Returns a view of the snapshot object and updates the ith component to
     /// contain the input value.
-    fn scate(&self, i: usize, value: T) -> [T; N] {
+    fn scate(&self, i: usize, value: T) -> Vec<T> {
         let component = self.components[i].read();
         self.components[i].write(Component {
             value,
@@ -189,8 +287,45 @@ impl<T: Copy + Default, const N: usize, const M: u32> LatticeMutexSnapshot<T, N,
     }
 }
 
-impl<T: Copy + Default, const N: usize, const M: u32> Snapshot<N>
-    for LatticeMutexSnapshot<T, N, M>
+impl<T: Clone + Default, const M: u32> DynSnapshot for DynLatticeMutexSnapshot<T, M> {
+    type Value = T;
+
+    /// Create a new snapshot object with `n` components.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if M, the number of operations that can be
+    /// applied to the object, is not a power of 2.
+    fn new(n: usize) -> Self {
+        Self::with_values(vec![T::default(); n])
+    }
+
+    fn components(&self) -> usize {
+        self.components.len()
+    }
+
+    fn scan(&self, i: usize) -> Vec<Self::Value> {
+        self.scate(i, self.components[i].read().value)
+    }
+
+    fn update(&self, i: usize, value: Self::Value) {
+        self.scate(i, value);
+    }
+}
+
+/// A lattice-agreement based `N`-process atomic snapshot object, using
+/// [`MutexRegister`] objects, that supports any number of operations.
+///
+/// Operations are performed on a sequence of [`LatticeMutexSnapshot`] objects
+/// that each support `M` operations, which must be a power of 2. This
+/// snapshot object is **not** lock-free. For implementation details, see
+/// [`DynUnboundedLatticeMutexSnapshot`].
+pub struct UnboundedLatticeMutexSnapshot<T: Clone + Default, const N: usize, const M: u32> {
+    snapshot: DynUnboundedLatticeMutexSnapshot<T, M>,
+}
+
+impl<T: Clone + Default, const N: usize, const M: u32> Snapshot<N>
+    for UnboundedLatticeMutexSnapshot<T, N, M>
 {
     type Value = T;
 
@@ -199,45 +334,177 @@ impl<T: Copy + Default, const N: usize, const M: u32> Snapshot<N>
     /// # Panics
     ///
     /// This method will panic if M, the number of operations that can be
-    /// applied to the object, is not a power of 2.
+    /// applied to each underlying `M`-shot object, is not a power of 2.
     fn new() -> Self {
-        // log_2(M) must be an integer to construct a complete binary tree of
-        // that height.
-        if !((M as f32).log2() == (M as f32).log2().floor()) {
-            panic!("The number M of supported operations must be a power of 2")
-        }
-        let height = (M as f32).log2().floor() as u32;
         Self {
-            components: [(); N].map(|_| MutexRegister::new()),
-            root: Box::new(CompleteBinaryTree::new(height)),
+            snapshot: DynUnboundedLatticeMutexSnapshot::new(N),
         }
     }
 
     fn scan(&self, i: usize) -> [Self::Value; N] {
-        self.scate(i, self.components[i].read().value)
+        let mut values = self.snapshot.scan(i).into_iter();
+        from_fn(|_| values.next().unwrap())
     }
 
     fn update(&self, i: usize, value: Self::Value) {
-        self.scate(i, value);
+        self.snapshot.update(i, value);
+    }
+}
+
+/// The number of operations that have begun and returned on an epoch.
+#[derive(Default)]
+struct Operations {
+    begun: u32,
+    returned: u32,
+}
+
+/// One of the `M`-shot snapshot objects on which the operations of a
+/// [`DynUnboundedLatticeMutexSnapshot`] are performed.
+struct Epoch<T: Clone + Default, const M: u32> {
+    snapshot: DynLatticeMutexSnapshot<T, M>,
+    operations: Mutex<Operations>,
+}
+
+impl<T: Clone + Default, const M: u32> Epoch<T, M> {
+    /// Creates an epoch whose components initially contain the given values.
+    fn new(values: Vec<T>) -> Self {
+        Self {
+            snapshot: DynLatticeMutexSnapshot::with_values(values),
+            operations: Mutex::new(Operations::default()),
+        }
+    }
+
+    /// Claims one of the `M` operations supported by this epoch, and returns
+    /// whether there was one left to claim.
+    fn begin(&self) -> bool {
+        let mut operations = self.operations.lock().unwrap();
+        if operations.begun == M {
+            return false;
+        }
+        operations.begun += 1;
+        true
+    }
+
+    /// Records that an operation claimed with [`begin`](Epoch::begin) has
+    /// returned.
+    fn end(&self) {
+        self.operations.lock().unwrap().returned += 1;
+    }
+
+    /// Waits until all `M` operations on this epoch have returned, and then
+    /// returns the values of its components.
+    fn close(&self) -> Vec<T> {
+        while self.operations.lock().unwrap().returned < M {
+            spin_loop();
+        }
+        self.snapshot.collect().values()
+    }
+}
+
+/// A lattice-agreement based atomic snapshot object, using [`MutexRegister`]
+/// objects, whose number of components is chosen at runtime, and that
+/// supports any number of operations.
+///
+/// Operations are performed on a sequence of _epochs_, each of which is a
+/// [`DynLatticeMutexSnapshot`] that supports `M` operations. A process claims
+/// one of the operations of the current epoch before performing its own
+/// operation on it. If every operation has already been claimed, the process
+/// waits until all of them have returned, and then replaces the epoch with a
+/// new one whose components contain the values that the old one ended with.
+///
+/// Since no operation is performed on an epoch until every operation on the
+/// previous epoch has returned, the operations on each epoch can be
+/// linearized after all of those on the previous one, and so the snapshot
+/// object is linearizable.
+///
+/// This snapshot object is **not** lock-free.
+pub struct DynUnboundedLatticeMutexSnapshot<T: Clone + Default, const M: u32> {
+    epoch: Mutex<Arc<Epoch<T, M>>>,
+    components: usize,
+}
+
+impl<T: Clone + Default, const M: u32> DynUnboundedLatticeMutexSnapshot<T, M> {
+    /// Claims an operation on the current epoch, first replacing it with a
+    /// new epoch if all of its operations have been claimed, and returns the
+    /// epoch on which the operation must be performed.
+    ///
+    /// The caller must call [`end`](Epoch::end) on the returned epoch once
+    /// its operation returns.
+    fn claim(&self) -> Arc<Epoch<T, M>> {
+        loop {
+            let epoch = self.epoch.lock().unwrap().clone();
+            if epoch.begin() {
+                return epoch;
+            }
+            let values = epoch.close();
+            let mut current = self.epoch.lock().unwrap();
+            // Only the first process to close the epoch replaces it.
+            if Arc::ptr_eq(&current, &epoch) {
+                *current = Arc::new(Epoch::new(values));
+            }
+        }
+    }
+}
+
+impl<T: Clone + Default, const M: u32> DynSnapshot for DynUnboundedLatticeMutexSnapshot<T, M> {
+    type Value = T;
+
+    /// Create a new snapshot object with `n` components.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if M, the number of operations that can be
+    /// applied to each underlying `M`-shot object, is not a power of 2.
+    fn new(n: usize) -> Self {
+        Self {
+            epoch: Mutex::new(Arc::new(Epoch::new(vec![T::default(); n]))),
+            components: n,
+        }
+    }
+
+    fn components(&self) -> usize {
+        self.components
+    }
+
+    fn scan(&self, i: usize) -> Vec<Self::Value> {
+        let epoch = self.claim();
+        let values = epoch.snapshot.scan(i);
+        epoch.end();
+        values
+    }
+
+    fn update(&self, i: usize, value: Self::Value) {
+        let epoch = self.claim();
+        epoch.snapshot.update(i, value);
+        epoch.end();
     }
 }
 
 /// A complete binary tree.
 #[derive(Debug)]
-enum CompleteBinaryTree<T: Default> {
+enum CompleteBinaryTree<T> {
     Leaf(T),
     Node(T, Box<CompleteBinaryTree<T>>, Box<CompleteBinaryTree<T>>),
 }
 
 impl<T: Default> CompleteBinaryTree<T> {
     /// Creates a new complete binary tree of a given height.
+    #[cfg(test)]
     fn new(height: u32) -> Self {
+        Self::new_with(height, &T::default)
+    }
+}
+
+impl<T> CompleteBinaryTree<T> {
+    /// Creates a new complete binary tree of a given height, where the
+    /// contents of each node are created by calling `f`.
+    fn new_with(height: u32, f: &impl Fn() -> T) -> Self {
         match height {
-            1 => Self::Leaf(T::default()),
+            1 => Self::Leaf(f()),
             _ => Self::Node(
-                T::default(),
-                Box::new(Self::new(height - 1)),
-                Box::new(Self::new(height - 1)),
+                f(),
+                Box::new(Self::new_with(height - 1, f)),
+                Box::new(Self::new_with(height - 1, f)),
             ),
         }
     }
@@ -257,7 +524,12 @@ impl<T: Default> CompleteBinaryTree<T> {
 
 #[cfg(test)]
 mod tests {
-    use super::{LatticeMutexSnapshot, Snapshot};
+    use super::{
+        DynLatticeMutexSnapshot, DynSnapshot, DynUnboundedLatticeMutexSnapshot,
+        LatticeMutexSnapshot, Snapshot, UnboundedLatticeMutexSnapshot,
+    };
+    use std::sync::Arc;
+    use std::thread;
 
     #[test]
     fn reads_and_writes() {
@@ -271,6 +543,71 @@ mod tests {
         snapshot.update(2, 12);
         assert_eq!([10, 11, 12], snapshot.scan(0));
     }
+
+    #[test]
+    fn dyn_reads_and_writes() {
+        let snapshot: DynLatticeMutexSnapshot<usize, 16> = DynLatticeMutexSnapshot::new(3);
+        assert_eq!(vec![0, 0, 0], snapshot.scan(0));
+        snapshot.update(1, 1);
+        snapshot.update(2, 2);
+        assert_eq!(vec![0, 1, 2], snapshot.scan(0));
+    }
+
+    #[test]
+    fn reads_and_writes_strings() {
+        let snapshot: LatticeMutexSnapshot<String, 3, 16> = LatticeMutexSnapshot::new();
+        snapshot.update(1, String::from("one"));
+        snapshot.update(2, String::from("two"));
+        assert_eq!(["", "one", "two"], snapshot.scan(0));
+    }
+
+    #[test]
+    fn unbounded_supports_more_than_m_operations() {
+        let snapshot: UnboundedLatticeMutexSnapshot<usize, 3, 4> =
+            UnboundedLatticeMutexSnapshot::new();
+        for value in 1..=50 {
+            snapshot.update(value % 3, value);
+            assert_eq!(snapshot.scan(0)[value % 3], value);
+        }
+        assert_eq!([48, 49, 50], snapshot.scan(0));
+    }
+
+    #[test]
+    fn dyn_unbounded_reads_and_writes_strings() {
+        let snapshot: DynUnboundedLatticeMutexSnapshot<String, 2> =
+            DynUnboundedLatticeMutexSnapshot::new(3);
+        for _ in 0..5 {
+            snapshot.update(1, String::from("one"));
+            snapshot.update(2, String::from("two"));
+        }
+        assert_eq!(vec!["", "one", "two"], snapshot.scan(0));
+    }
+
+    #[test]
+    fn unbounded_scans_never_go_backwards() {
+        const N: usize = 4;
+        let snapshot: Arc<UnboundedLatticeMutexSnapshot<usize, N, 8>> =
+            Arc::new(UnboundedLatticeMutexSnapshot::new());
+        let handles: Vec<_> = (0..N)
+            .map(|i| {
+                let snapshot = snapshot.clone();
+                thread::spawn(move || {
+                    let mut previous = [0; N];
+                    for value in 1..=100 {
+                        snapshot.update(i, value);
+                        let view = snapshot.scan(i);
+                        assert_eq!(view[i], value);
+                        assert!(view.iter().zip(previous).all(|(&v, p)| v >= p));
+                        previous = view;
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!([100; N], snapshot.scan(0));
+    }
 }
 
 #[cfg(test)]
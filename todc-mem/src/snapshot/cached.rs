@@ -0,0 +1,158 @@
+//! A snapshot object that caches the result of its scans.
+//!
+//! Every scan of the snapshot objects in [`aad_plus_93`](super::aad_plus_93)
+//! performs at least two collects, each of which reads all `N` components,
+//! even if no component has been updated since the previous scan. In
+//! read-heavy workloads, most of this work is redundant.
+//!
+//! A [`CachedSnapshot`] wraps another snapshot `S`, and maintains a
+//! _version_, which counts the updates that have been performed on it. A
+//! scan that completes while no update is in progress stores its view in a
+//! cache, alongside the version that it observed. Subsequent scans return
+//! the cached view, without scanning `S`, until another update begins.
+//!
+//! Updates increment one counter before they are performed on `S`, and
+//! another once they have completed. A view is only cached if the two
+//! counters were equal before the scan began, and the first was unchanged
+//! once it completed, in which case no update was performed on `S` while
+//! it was being scanned. A cached view is only returned while the first
+//! counter is still equal to its version, in which case no update has been
+//! performed on `S` since, and so the view is equal to the result of a scan
+//! performed at that moment.
+//!
+//! The cache is stored in a [`MutexRegister`], and so the fast path is
+//! **not** lock-free, even if `S` is.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_mem::snapshot::{CachedSnapshot, Snapshot, UnboundedMutexSnapshot};
+//!
+//! let snapshot: CachedSnapshot<UnboundedMutexSnapshot<u32, 3>, 3> = CachedSnapshot::new();
+//! snapshot.update(1, 123);
+//!
+//! // The first scan is stored in the cache, and the second is returned from it.
+//! assert_eq!(snapshot.scan(0), [0, 123, 0]);
+//! assert_eq!(snapshot.scan(2), [0, 123, 0]);
+//! assert_eq!(snapshot.version(), 1);
+//! ```
+use crate::register::{MutexRegister, Register};
+use crate::snapshot::{ProcessId, Snapshot};
+use crate::sync::{AtomicU64, Ordering};
+
+/// A view of a snapshot, alongside the version at which it was scanned.
+type Cached<T, const N: usize> = Option<(u64, [T; N])>;
+
+/// A snapshot object, backed by another snapshot `S`, that returns a cached
+/// view when it has not been updated since a previous scan.
+///
+/// If `S` is linearizable, then [`CachedSnapshot<S, N>`] is as well. See
+/// the [`cached`](crate::snapshot::cached) module-level documentation for
+/// more details.
+pub struct CachedSnapshot<S: Snapshot<N>, const N: usize> {
+    snapshot: S,
+    // The number of updates that have begun.
+    started: AtomicU64,
+    // The number of updates that have completed.
+    completed: AtomicU64,
+    cache: MutexRegister<Cached<S::Value, N>>,
+}
+
+impl<S: Snapshot<N>, const N: usize> CachedSnapshot<S, N> {
+    /// Returns the version of the object, which is the number of updates
+    /// that have completed.
+    pub fn version(&self) -> u64 {
+        self.completed.load(Ordering::SeqCst)
+    }
+}
+
+impl<S: Snapshot<N>, const N: usize> Snapshot<N> for CachedSnapshot<S, N> {
+    type Value = S::Value;
+
+    fn new() -> Self {
+        Self {
+            snapshot: S::new(),
+            started: AtomicU64::new(0),
+            completed: AtomicU64::new(0),
+            cache: MutexRegister::new(),
+        }
+    }
+
+    fn scan(&self, i: ProcessId) -> [Self::Value; N] {
+        // The cache must be read before the version is, so that the cached
+        // view is known to be current at the moment the version is read.
+        if let Some((version, view)) = self.cache.read() {
+            if version == self.started.load(Ordering::SeqCst) {
+                return view;
+            }
+        }
+        // Reading the completed counter first ensures that, if both are
+        // equal, then no update was in progress when the started counter
+        // was read.
+        let completed = self.completed.load(Ordering::SeqCst);
+        let started = self.started.load(Ordering::SeqCst);
+        let view = self.snapshot.scan(i);
+        if completed == started && started == self.started.load(Ordering::SeqCst) {
+            self.cache.write(Some((started, view.clone())));
+        }
+        view
+    }
+
+    fn update(&self, i: ProcessId, value: Self::Value) {
+        self.started.fetch_add(1, Ordering::SeqCst);
+        self.snapshot.update(i, value);
+        self.completed.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::UnboundedMutexSnapshot;
+
+    type Cached = CachedSnapshot<UnboundedMutexSnapshot<u32, 3>, 3>;
+
+    #[test]
+    fn reads_and_writes() {
+        let snapshot = Cached::new();
+        assert_eq!(snapshot.scan(0), [0, 0, 0]);
+        snapshot.update(1, 11);
+        snapshot.update(2, 12);
+        assert_eq!(snapshot.scan(0), [0, 11, 12]);
+    }
+
+    #[test]
+    fn caches_scan_if_not_updated() {
+        let snapshot = Cached::new();
+        snapshot.update(1, 11);
+        let view = snapshot.scan(0);
+        assert_eq!(snapshot.cache.read(), Some((1, view)));
+        assert_eq!(snapshot.scan(2), view);
+    }
+
+    #[test]
+    fn ignores_cache_after_update() {
+        let snapshot = Cached::new();
+        assert_eq!(snapshot.scan(0), [0, 0, 0]);
+        snapshot.update(1, 11);
+        assert_eq!(snapshot.scan(0), [0, 11, 0]);
+        assert_eq!(snapshot.cache.read(), Some((1, [0, 11, 0])));
+    }
+
+    #[test]
+    fn does_not_cache_scan_during_update() {
+        let snapshot = Cached::new();
+        // Simulate an update that has begun, but not yet completed.
+        snapshot.started.fetch_add(1, Ordering::SeqCst);
+        snapshot.scan(0);
+        assert_eq!(snapshot.cache.read(), None);
+    }
+
+    #[test]
+    fn counts_completed_updates() {
+        let snapshot = Cached::new();
+        snapshot.update(0, 1);
+        snapshot.update(0, 2);
+        assert_eq!(snapshot.version(), 2);
+    }
+}
@@ -0,0 +1,175 @@
+//! A snapshot object that survives simulated crashes.
+//!
+//! A [`DurableSnapshot`] models a snapshot object stored in persistent
+//! memory, in the style of Izraelevitz, Mendes, and Scott
+//! [\[IMS16\]](https://doi.org/10.1007/978-3-662-53426-7_23). The contents of
+//! the object, along with an _epoch_ counter, are persistent, and survive a
+//! crash. The state of each process is volatile, and is lost.
+//!
+//! A crash is simulated with [`crash`](DurableSnapshot::crash), which begins
+//! a new epoch. Before performing any further operations, each process must
+//! call [`recover`](DurableSnapshot::recover) to re-initialize its state for
+//! the new epoch. Operations that were in progress when the crash occurred
+//! may or may not have taken effect, and histories of such operations can be
+//! checked with the
+//! [`durable`](https://docs.rs/todc-utils/latest/todc_utils/linearizability/durable/index.html)
+//! linearizability checker in `todc-utils`.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_mem::snapshot::mutex::MutexSnapshot;
+//! use todc_mem::snapshot::{DurableSnapshot, Snapshot};
+//!
+//! let mut snapshot: DurableSnapshot<MutexSnapshot<u32, 3>, 3> = DurableSnapshot::new();
+//! snapshot.update(1, 123);
+//!
+//! snapshot.crash();
+//! assert_eq!(snapshot.epoch(), 1);
+//!
+//! // After recovering, a process finds that its update persisted.
+//! assert_eq!(snapshot.recover(1), 123);
+//! assert_eq!(snapshot.scan(1), [0, 123, 0]);
+//! ```
+use core::array::from_fn;
+
+use crate::snapshot::{ProcessId, Snapshot};
+use crate::sync::{AtomicU64, Ordering};
+
+/// A snapshot object, backed by another snapshot `S`, whose contents survive
+/// simulated crashes.
+///
+/// See the [`durable`](crate::snapshot::durable) module-level documentation
+/// for more details.
+pub struct DurableSnapshot<S: Snapshot<N>, const N: usize> {
+    // Persistent state.
+    snapshot: S,
+    epoch: AtomicU64,
+    // Volatile state. The epoch in which each process last recovered.
+    recovered: [AtomicU64; N],
+}
+
+impl<S: Snapshot<N>, const N: usize> DurableSnapshot<S, N> {
+    /// Returns the current epoch, which is the number of crashes that have
+    /// occurred.
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    /// Simulates a crash, after which every process must
+    /// [`recover`](Self::recover) before performing another operation.
+    ///
+    /// Since this requires exclusive access to the object, no operation can
+    /// be in progress when the crash occurs. Operations that are interrupted
+    /// by a crash can instead be simulated by abandoning them, either before
+    /// or after they are performed.
+    pub fn crash(&mut self) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Re-initializes the state of the _i^{th}_ process after a crash, and
+    /// returns the value of its component that persisted.
+    pub fn recover(&self, i: ProcessId) -> S::Value {
+        self.recovered[i].store(self.epoch(), Ordering::SeqCst);
+        self.snapshot.scan(i)[i].clone()
+    }
+
+    /// Panics if the _i^{th}_ process has not recovered since the last crash.
+    fn check_recovered(&self, i: ProcessId) {
+        let recovered = self.recovered[i].load(Ordering::SeqCst);
+        assert_eq!(
+            recovered,
+            self.epoch(),
+            "Process {i} must recover after a crash before performing operations"
+        );
+    }
+}
+
+impl<S: Snapshot<N>, const N: usize> Snapshot<N> for DurableSnapshot<S, N> {
+    type Value = S::Value;
+
+    fn new() -> Self {
+        Self {
+            snapshot: S::new(),
+            epoch: AtomicU64::new(0),
+            recovered: from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+
+    /// Returns an array containing the value of each component in the object.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the _i^{th}_ process has not recovered since the last crash.
+    fn scan(&self, i: ProcessId) -> [Self::Value; N] {
+        self.check_recovered(i);
+        self.snapshot.scan(i)
+    }
+
+    /// Sets contents of the _i^{th}_ component to the specified value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the _i^{th}_ process has not recovered since the last crash.
+    fn update(&self, i: ProcessId, value: Self::Value) {
+        self.check_recovered(i);
+        self.snapshot.update(i, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::mutex::MutexSnapshot;
+
+    type Durable = DurableSnapshot<MutexSnapshot<u32, 3>, 3>;
+
+    #[test]
+    fn starts_in_first_epoch() {
+        let snapshot = Durable::new();
+        assert_eq!(snapshot.epoch(), 0);
+    }
+
+    #[test]
+    fn contents_survive_crash() {
+        let mut snapshot = Durable::new();
+        snapshot.update(1, 123);
+        snapshot.crash();
+        snapshot.recover(0);
+        assert_eq!(snapshot.scan(0), [0, 123, 0]);
+    }
+
+    #[test]
+    fn crash_begins_new_epoch() {
+        let mut snapshot = Durable::new();
+        snapshot.crash();
+        snapshot.crash();
+        assert_eq!(snapshot.epoch(), 2);
+    }
+
+    #[test]
+    fn recover_returns_persisted_component() {
+        let mut snapshot = Durable::new();
+        snapshot.update(2, 321);
+        snapshot.crash();
+        assert_eq!(snapshot.recover(2), 321);
+    }
+
+    #[test]
+    #[should_panic(expected = "must recover")]
+    fn operations_before_recovery_panic() {
+        let mut snapshot = Durable::new();
+        snapshot.crash();
+        snapshot.update(0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Process 1 must recover")]
+    fn processes_recover_independently() {
+        let mut snapshot = Durable::new();
+        snapshot.crash();
+        snapshot.recover(0);
+        snapshot.update(0, 1);
+        snapshot.scan(1);
+    }
+}
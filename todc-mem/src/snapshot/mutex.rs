@@ -1,17 +1,17 @@
 //! An atomic snapshot backed by [`Mutex`] objects.
 use crate::sync::Mutex;
 
-use crate::snapshot::Snapshot;
+use crate::snapshot::{DynSnapshot, Snapshot};
 
 /// A [`Mutex`]-based atomic snapshot.
 ///
 /// This implementation uses a mutex to protect against concurrent memory
 /// access. It is **not** lock-free.
-pub struct MutexSnapshot<T: Copy + Default, const N: usize> {
+pub struct MutexSnapshot<T: Clone + Default, const N: usize> {
     mutex: Mutex<[T; N]>,
 }
 
-impl<T: Copy + Default, const N: usize> Snapshot<N> for MutexSnapshot<T, N> {
+impl<T: Clone + Default, const N: usize> Snapshot<N> for MutexSnapshot<T, N> {
     type Value = T;
 
     fn new() -> Self {
@@ -22,7 +22,41 @@ impl<T: Copy + Default, const N: usize> Snapshot<N> for MutexSnapshot<T, N> {
 
     /// Returns an array containing the value of each component in the object.
     fn scan(&self, _i: usize) -> [Self::Value; N] {
-        *self.mutex.lock().unwrap()
+        self.mutex.lock().unwrap().clone()
+    }
+
+    /// Sets contents of the ith component to the specified value.
+    fn update(&self, i: usize, value: Self::Value) {
+        let mut data = self.mutex.lock().unwrap();
+        data[i] = value;
+    }
+}
+
+/// A [`Mutex`]-based atomic snapshot, whose number of components is chosen
+/// at runtime.
+///
+/// This implementation uses a mutex to protect against concurrent memory
+/// access. It is **not** lock-free.
+pub struct DynMutexSnapshot<T: Clone + Default> {
+    mutex: Mutex<Vec<T>>,
+}
+
+impl<T: Clone + Default> DynSnapshot for DynMutexSnapshot<T> {
+    type Value = T;
+
+    fn new(n: usize) -> Self {
+        Self {
+            mutex: Mutex::new(vec![T::default(); n]),
+        }
+    }
+
+    fn components(&self) -> usize {
+        self.mutex.lock().unwrap().len()
+    }
+
+    /// Returns a vector containing the value of each component in the object.
+    fn scan(&self, _i: usize) -> Vec<Self::Value> {
+        self.mutex.lock().unwrap().clone()
     }
 
     /// Sets contents of the ith component to the specified value.
@@ -52,4 +86,19 @@ mod tests {
         let view = snapshot.scan(2);
         assert_eq!(view, [0, 123, 321]);
     }
+
+    #[test]
+    fn dyn_reads_and_writes() {
+        let snapshot: DynMutexSnapshot<usize> = DynMutexSnapshot::new(3);
+        assert_eq!(snapshot.components(), 3);
+        snapshot.update(1, 123);
+        assert_eq!(snapshot.scan(0), vec![0, 123, 0]);
+    }
+
+    #[test]
+    fn reads_and_writes_strings() {
+        let snapshot: MutexSnapshot<String, 2> = MutexSnapshot::new();
+        snapshot.update(1, String::from("hello"));
+        assert_eq!(snapshot.scan(0), [String::new(), String::from("hello")]);
+    }
 }
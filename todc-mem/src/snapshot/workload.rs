@@ -0,0 +1,329 @@
+//! Workloads of concurrent updates and scans over snapshot objects.
+//!
+//! A [`Workload`] has a number of processes each perform a sequence of
+//! operations on a shared [`DynSnapshot`], where each operation is a scan
+//! with some probability, and otherwise an update. It measures how long the
+//! processes took, along with the latency of each operation, so that the same
+//! code can drive benchmarks and experiments over any snapshot
+//! implementation, with a number of processes chosen at runtime.
+//!
+//! The choice of operations made by each process is derived from a seed, so
+//! that every run of a workload with the same seed performs the same
+//! operations.
+//!
+//! Workloads run on [`std`] threads, and so cannot be used when the `shuttle`
+//! feature is enabled.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use todc_mem::snapshot::{DynSnapshot, DynUnboundedMutexSnapshot};
+//! use todc_mem::snapshot::workload::Workload;
+//!
+//! let snapshot = Arc::new(DynUnboundedMutexSnapshot::<usize>::new(4));
+//! let workload = Workload::new()
+//!     .with_operations(50)
+//!     .with_scan_probability(0.25);
+//!
+//! let stats = workload.run(&snapshot, |_, j| j);
+//! assert_eq!(stats.operations(), 4 * 50);
+//! println!("{:.0} operations per second", stats.throughput());
+//! ```
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::{DynSnapshot, ProcessId};
+
+/// A workload of concurrent updates and scans.
+///
+/// By default, every component of the snapshot is updated by its own
+/// process, and each process performs `100` operations, half of which are
+/// scans.
+///
+/// See the [`workload`](crate::snapshot::workload) module-level documentation
+/// for more details.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Workload {
+    processes: Option<usize>,
+    operations: usize,
+    scan_probability: f64,
+    seed: u64,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self {
+            processes: None,
+            operations: 100,
+            scan_probability: 0.5,
+            seed: 0,
+        }
+    }
+}
+
+impl Workload {
+    /// Creates a workload in which each component of the snapshot is updated
+    /// by its own process, and each process performs `100` operations, half
+    /// of which are scans.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the workload with only the first `n` processes, rather than one
+    /// process for each component of the snapshot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is `0`.
+    pub fn with_processes(self, n: usize) -> Self {
+        assert!(n > 0, "A workload must have at least one process");
+        Self {
+            processes: Some(n),
+            ..self
+        }
+    }
+
+    /// Has each process perform `n` operations.
+    pub fn with_operations(self, n: usize) -> Self {
+        Self {
+            operations: n,
+            ..self
+        }
+    }
+
+    /// Has each operation be a scan with probability `p`, and otherwise an
+    /// update.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `p` is not in `0.0..=1.0`.
+    pub fn with_scan_probability(self, p: f64) -> Self {
+        assert!((0.0..=1.0).contains(&p), "Probability must be in [0, 1]");
+        Self {
+            scan_probability: p,
+            ..self
+        }
+    }
+
+    /// Derives the operations performed by each process from `seed`.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// Runs the workload on a snapshot, and returns statistics about the
+    /// operations that were performed.
+    ///
+    /// When the _i^{th}_ process performs its _j^{th}_ operation, and that
+    /// operation is an update, it sets its component to `value(i, j)`. The
+    /// processes begin performing operations at the same time, once all of
+    /// their threads have been spawned, and the time taken to spawn and join
+    /// the threads is not included in the statistics.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the workload has more processes than the snapshot has
+    /// components.
+    pub fn run<S, F>(&self, snapshot: &Arc<S>, value: F) -> WorkloadStats
+    where
+        S: DynSnapshot + Send + Sync + 'static,
+        F: Fn(ProcessId, usize) -> S::Value + Send + Sync + 'static,
+    {
+        let processes = self.processes.unwrap_or(snapshot.components());
+        assert!(
+            processes <= snapshot.components(),
+            "Workload has {processes} processes, but the snapshot has {} components",
+            snapshot.components()
+        );
+
+        // The main thread waits on the barrier along with every process, so
+        // that it knows when they begin performing operations.
+        let barrier = Arc::new(Barrier::new(processes + 1));
+        let value = Arc::new(value);
+        let (operations, scan_probability) = (self.operations, self.scan_probability);
+        let handles: Vec<_> = (0..processes)
+            .map(|i| {
+                let snapshot = snapshot.clone();
+                let barrier = barrier.clone();
+                let value = value.clone();
+                let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(i as u64));
+                thread::spawn(move || {
+                    let mut updates = Latencies::default();
+                    let mut scans = Latencies::default();
+                    barrier.wait();
+                    for j in 0..operations {
+                        if rng.gen_bool(scan_probability) {
+                            let start = Instant::now();
+                            snapshot.scan(i);
+                            scans.record(start.elapsed());
+                        } else {
+                            let value = value(i, j);
+                            let start = Instant::now();
+                            snapshot.update(i, value);
+                            updates.record(start.elapsed());
+                        }
+                    }
+                    (updates, scans, Instant::now())
+                })
+            })
+            .collect();
+
+        barrier.wait();
+        let start = Instant::now();
+        let mut updates = Latencies::default();
+        let mut scans = Latencies::default();
+        let mut end = start;
+        for handle in handles {
+            let (u, s, finished) = handle.join().unwrap();
+            updates.merge(u);
+            scans.merge(s);
+            end = end.max(finished);
+        }
+        WorkloadStats {
+            updates: updates.count,
+            scans: scans.count,
+            elapsed: end - start,
+            mean_update_latency: updates.mean(),
+            mean_scan_latency: scans.mean(),
+            max_latency: updates.max.max(scans.max),
+        }
+    }
+}
+
+/// Statistics about the operations performed by a [`Workload`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WorkloadStats {
+    /// The number of updates performed.
+    pub updates: usize,
+    /// The number of scans performed.
+    pub scans: usize,
+    /// The time between when the processes began performing operations and
+    /// when the last of them finished.
+    pub elapsed: Duration,
+    /// The mean latency of an update, or `None` if there were no updates.
+    pub mean_update_latency: Option<Duration>,
+    /// The mean latency of a scan, or `None` if there were no scans.
+    pub mean_scan_latency: Option<Duration>,
+    /// The largest latency of any operation.
+    pub max_latency: Duration,
+}
+
+impl WorkloadStats {
+    /// Returns the number of operations performed.
+    pub fn operations(&self) -> usize {
+        self.updates + self.scans
+    }
+
+    /// Returns the number of operations performed per second.
+    pub fn throughput(&self) -> f64 {
+        self.operations() as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+/// The latencies of the operations of one kind.
+#[derive(Default)]
+struct Latencies {
+    count: usize,
+    total: Duration,
+    max: Duration,
+}
+
+impl Latencies {
+    fn record(&mut self, latency: Duration) {
+        self.count += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
+
+    fn merge(&mut self, other: Latencies) {
+        self.count += other.count;
+        self.total += other.total;
+        self.max = self.max.max(other.max);
+    }
+
+    fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.total / self.count as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::DynUnboundedMutexSnapshot;
+
+    type MutexSnapshot = DynUnboundedMutexSnapshot<usize>;
+
+    mod run {
+        use super::*;
+
+        #[test]
+        fn performs_operations_on_each_process() {
+            let snapshot = Arc::new(MutexSnapshot::new(3));
+            let stats = Workload::new().with_operations(10).run(&snapshot, |_, j| j);
+            assert_eq!(stats.operations(), 30);
+        }
+
+        #[test]
+        fn performs_only_updates_if_scans_are_impossible() {
+            let snapshot = Arc::new(MutexSnapshot::new(2));
+            let stats = Workload::new()
+                .with_scan_probability(0.0)
+                .run(&snapshot, |_, j| j);
+            assert_eq!(stats.updates, 200);
+            assert_eq!(stats.mean_scan_latency, None);
+            assert!(stats.mean_update_latency.is_some());
+        }
+
+        #[test]
+        fn performs_only_scans_if_updates_are_impossible() {
+            let snapshot = Arc::new(MutexSnapshot::new(2));
+            let stats = Workload::new()
+                .with_scan_probability(1.0)
+                .run(&snapshot, |_, j| j);
+            assert_eq!(stats.scans, 200);
+            assert_eq!(stats.mean_update_latency, None);
+        }
+
+        #[test]
+        fn updates_with_given_values() {
+            let snapshot = Arc::new(MutexSnapshot::new(3));
+            Workload::new()
+                .with_processes(2)
+                .with_operations(5)
+                .with_scan_probability(0.0)
+                .run(&snapshot, |i, j| 10 * i + j);
+            assert_eq!(snapshot.scan(0), vec![4, 14, 0]);
+        }
+
+        #[test]
+        fn performs_same_operations_with_same_seed() {
+            let snapshot = Arc::new(MutexSnapshot::new(3));
+            let workload = Workload::new().with_seed(123);
+            let first = workload.run(&snapshot, |_, j| j);
+            let second = workload.run(&snapshot, |_, j| j);
+            assert_eq!(first.updates, second.updates);
+            assert_eq!(first.scans, second.scans);
+        }
+
+        #[test]
+        #[should_panic(expected = "Workload has 3 processes, but the snapshot has 2 components")]
+        fn panics_if_there_are_more_processes_than_components() {
+            let snapshot = Arc::new(MutexSnapshot::new(2));
+            Workload::new().with_processes(3).run(&snapshot, |_, j| j);
+        }
+    }
+
+    mod with_scan_probability {
+        use super::*;
+
+        #[test]
+        #[should_panic(expected = "Probability must be in [0, 1]")]
+        fn panics_if_probability_is_invalid() {
+            Workload::new().with_scan_probability(1.5);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Stack objects.
+//!
+//! A stack contains a sequence of values. The [`push`](Stack::push) operation
+//! adds a value to the top of the stack, and the [`pop`](Stack::pop)
+//! operation removes and returns the value at the top of the stack, if there
+//! is one.
+//!
+//! This module contains a [`TreiberStack`], the classic lock-free stack
+//! described by Treiber [\[Tre86\]](https://dominoweb.draco.res.ibm.com/58319a2ed2b1078985257003004617ef.html).
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::thread;
+//! use todc_mem::stack::{Stack, TreiberStack};
+//!
+//! const N: usize = 3;
+//!
+//! let stack: Arc<TreiberStack<usize, N>> = Arc::new(TreiberStack::new());
+//!
+//! let handles: Vec<_> = (0..N)
+//!     .map(|i| {
+//!         let stack = stack.clone();
+//!         thread::spawn(move || stack.push(i, i))
+//!     })
+//!     .collect();
+//! for handle in handles {
+//!     handle.join().unwrap();
+//! }
+//!
+//! let mut values: Vec<usize> = (0..N).map(|_| stack.pop(0).unwrap()).collect();
+//! values.sort();
+//! assert_eq!(values, vec![0, 1, 2]);
+//! assert_eq!(stack.pop(0), None);
+//! ```
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ptr;
+
+use crate::snapshot::ProcessId;
+use crate::sync::{AtomicPtr, Collector, Ordering};
+
+/// An `N`-process stack.
+pub trait Stack<const N: usize> {
+    type Value;
+
+    /// Creates an empty stack.
+    fn new() -> Self;
+
+    /// Pushes a value onto the top of the stack, on behalf of the _i^{th}_
+    /// process.
+    fn push(&self, i: ProcessId, value: Self::Value);
+
+    /// Removes and returns the value at the top of the stack, on behalf of
+    /// the _i^{th}_ process, or returns `None` if the stack is empty.
+    fn pop(&self, i: ProcessId) -> Option<Self::Value>;
+}
+
+/// A node in a [`TreiberStack`].
+struct Node<T> {
+    // Popping a node moves its value out, after which the node itself is
+    // destroyed without dropping the value again.
+    value: ManuallyDrop<T>,
+    next: *mut Node<T>,
+}
+
+// SAFETY: Nodes are only ever accessed by the processes that operate on the
+// stack, which requires `T` to be `Send`.
+unsafe impl<T: Send> Send for Node<T> {}
+
+/// An `N`-process lock-free stack, as described by Treiber.
+///
+/// The stack is a linked list of nodes, and a pointer to the node at the top
+/// of the stack. Each operation repeatedly reads the top of the stack, and
+/// then attempts to replace it with compare-and-swap, until it succeeds.
+/// Popped nodes are reclaimed with a [`Collector`], which ensures that no
+/// process is still reading a node when it is destroyed, and so also avoids
+/// the _ABA problem_.
+///
+/// All operations are lock-free.
+pub struct TreiberStack<T, const N: usize> {
+    top: AtomicPtr<Node<T>>,
+    collector: Collector<N>,
+    value: PhantomData<T>,
+}
+
+impl<T: Send + 'static, const N: usize> Stack<N> for TreiberStack<T, N> {
+    type Value = T;
+
+    fn new() -> Self {
+        Self {
+            top: AtomicPtr::new(ptr::null_mut()),
+            collector: Collector::new(),
+            value: PhantomData,
+        }
+    }
+
+    fn push(&self, i: ProcessId, value: T) {
+        let node = Box::into_raw(Box::new(Node {
+            value: ManuallyDrop::new(value),
+            next: ptr::null_mut(),
+        }));
+        let _guard = self.collector.pin(i);
+        loop {
+            let top = self.top.load(Ordering::SeqCst);
+            // SAFETY: The node has not yet been shared with other processes.
+            unsafe { (*node).next = top };
+            if self
+                .top
+                .compare_exchange(top, node, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                return;
+            }
+        }
+    }
+
+    fn pop(&self, i: ProcessId) -> Option<T> {
+        let guard = self.collector.pin(i);
+        loop {
+            let top = self.top.load(Ordering::SeqCst);
+            if top.is_null() {
+                return None;
+            }
+            // SAFETY: The node cannot be destroyed while this process is
+            // pinned, and its next pointer never changes once it is shared.
+            let next = unsafe { (*top).next };
+            if self
+                .top
+                .compare_exchange(top, next, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                // SAFETY: Only the process that removed the node from the
+                // stack moves its value out, and the node is then retired.
+                unsafe {
+                    let value = ptr::read(&*(*top).value);
+                    guard.defer_destroy(top);
+                    return Some(value);
+                }
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for TreiberStack<T, N> {
+    fn drop(&mut self) {
+        let mut node = self.top.load(Ordering::SeqCst);
+        while !node.is_null() {
+            // SAFETY: No other process can access the stack, and each node
+            // still in the stack holds a value that has not been moved out.
+            let mut boxed = unsafe { Box::from_raw(node) };
+            unsafe { ManuallyDrop::drop(&mut boxed.value) };
+            node = boxed.next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    type Treiber = TreiberStack<u32, 2>;
+
+    #[test]
+    fn pop_returns_none_if_empty() {
+        let stack = Treiber::new();
+        assert_eq!(stack.pop(0), None);
+    }
+
+    #[test]
+    fn pops_values_in_reverse_order() {
+        let stack = Treiber::new();
+        stack.push(0, 1);
+        stack.push(1, 2);
+        stack.push(0, 3);
+        assert_eq!(stack.pop(1), Some(3));
+        assert_eq!(stack.pop(0), Some(2));
+        assert_eq!(stack.pop(1), Some(1));
+        assert_eq!(stack.pop(0), None);
+    }
+
+    #[test]
+    fn drops_remaining_values() {
+        let value = Arc::new(());
+        let stack: TreiberStack<Arc<()>, 1> = TreiberStack::new();
+        stack.push(0, value.clone());
+        stack.push(0, value.clone());
+        drop(stack.pop(0));
+        assert_eq!(Arc::strong_count(&value), 2);
+        drop(stack);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+}
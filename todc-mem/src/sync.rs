@@ -1,10 +1,183 @@
+//! Synchronization primitives.
+//!
+//! This module contains [`LoadLinkedStoreConditional`], which allows
+//! algorithms described in terms of load-linked/store-conditional to be
+//! implemented on hardware that only provides compare-and-swap, and a
+//! [`Collector`], which safely reclaims memory that is retired by lock-free
+//! objects.
+//!
+//! # Model Checking
+//!
+//! Every object in this crate is built from the atomic types, [`Mutex`],
+//! [`spin_loop`] and [`thread_rng`] that are re-exported by this module. By
+//! default, these are the types of [`std`] and [`rand`]. When the `shuttle`
+//! feature is enabled, they are instead the types of
+//! [shuttle](https://github.com/awslabs/shuttle), which controls the
+//! scheduling of threads in order to test many of their interleavings.
+//!
+//! The exception is [`AtomicU128`], which [`std`] does not yet provide. By
+//! default it is the type of
+//! [portable-atomic](https://github.com/taiki-e/portable-atomic), which uses
+//! instructions such as `cmpxchg16b` when they are available and falls back
+//! to locks otherwise. Shuttle does not provide one either, and so it is
+//! emulated with a shuttle [`Mutex`] when the `shuttle` feature is enabled.
+//!
+//! Downstream crates can therefore test their own uses of these objects with
+//! shuttle, by enabling the feature and running them on shuttle threads. New
+//! objects that are built from the types in this module can be tested in the
+//! same way.
+//!
+//! Shuttle explores interleavings of sequentially consistent operations, and
+//! so cannot find bugs that only arise under weaker memory orderings. When
+//! compiled with `RUSTFLAGS="--cfg loom"`, the atomic types, [`Mutex`],
+//! [`spin_loop`] and [`UnsafeCell`] are instead those of
+//! [loom](https://github.com/tokio-rs/loom), which also explores the values
+//! that each load may observe under the C11 memory model, and reports any
+//! access to an [`UnsafeCell`] that races with another. Loom exhaustively
+//! checks every execution, and so is only practical for small tests, such as
+//! those in `tests/loom.rs`:
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test -p todc-mem --test loom --release
+//! ```
+//!
+//! ```
+//! use std::sync::Arc;
+//! use todc_mem::stack::{Stack, TreiberStack};
+//!
+//! shuttle::check_random(
+//!     || {
+//!         let stack: Arc<TreiberStack<usize, 2>> = Arc::new(TreiberStack::new());
+//!         let other = stack.clone();
+//!         let handle = shuttle::thread::spawn(move || other.push(1, 1));
+//!         stack.push(0, 0);
+//!         handle.join().unwrap();
+//!
+//!         let mut values = vec![stack.pop(0).unwrap(), stack.pop(0).unwrap()];
+//!         values.sort();
+//!         assert_eq!(values, vec![0, 1]);
+//!     },
+//!     100,
+//! );
+//! ```
+mod ebr;
+mod llsc;
+pub use self::ebr::{Collector, Guard};
+pub use self::llsc::{Link, LoadLinkedStoreConditional};
+
+// The remaining items switch between `std`, `shuttle` and `loom` types
+// depending on whether the `shuttle` feature is enabled, or the `loom` cfg is
+// set. Loom does not provide 128-bit atomics or randomness, and so those are
+// unchanged when it is set.
+#[cfg(all(loom, feature = "shuttle"))]
+compile_error!("The `shuttle` feature cannot be enabled together with `--cfg loom`");
+
 #[cfg(feature = "shuttle")]
-pub(crate) use shuttle::sync::{
-    atomic::{AtomicBool, AtomicU64, Ordering},
+pub use self::emulated::AtomicU128;
+#[cfg(not(loom))]
+pub use self::unsafe_cell::UnsafeCell;
+#[cfg(loom)]
+pub use loom::cell::UnsafeCell;
+#[cfg(loom)]
+pub use loom::hint::spin_loop;
+#[cfg(loom)]
+pub use loom::sync::{
+    atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering},
     Mutex,
 };
 #[cfg(not(feature = "shuttle"))]
-pub(crate) use std::sync::{
-    atomic::{AtomicBool, AtomicU64, Ordering},
+pub use portable_atomic::AtomicU128;
+#[cfg(not(feature = "shuttle"))]
+pub use rand::{thread_rng, Rng};
+#[cfg(feature = "shuttle")]
+pub use shuttle::hint::spin_loop;
+#[cfg(feature = "shuttle")]
+pub use shuttle::rand::{thread_rng, Rng};
+#[cfg(feature = "shuttle")]
+pub use shuttle::sync::{
+    atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering},
     Mutex,
 };
+#[cfg(not(any(loom, feature = "shuttle")))]
+pub use std::hint::spin_loop;
+#[cfg(not(any(loom, feature = "shuttle")))]
+pub use std::sync::{
+    atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering},
+    Mutex,
+};
+
+/// A [`core::cell::UnsafeCell`] whose contents are only accessed through
+/// closures, as with the `UnsafeCell` of loom, so that loom can check every
+/// access for races.
+#[cfg(not(loom))]
+mod unsafe_cell {
+    /// A cell whose contents may be mutated through a shared reference.
+    #[derive(Debug, Default)]
+    pub struct UnsafeCell<T>(core::cell::UnsafeCell<T>);
+
+    impl<T> UnsafeCell<T> {
+        /// Creates a new cell containing the given value.
+        pub const fn new(value: T) -> Self {
+            Self(core::cell::UnsafeCell::new(value))
+        }
+
+        /// Calls `f` with an immutable pointer to the contents of the cell.
+        pub fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+            f(self.0.get())
+        }
+
+        /// Calls `f` with a mutable pointer to the contents of the cell.
+        pub fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+            f(self.0.get())
+        }
+    }
+}
+
+/// Shuttle does not provide 128-bit atomics, so they are emulated with a
+/// [`Mutex`] that shuttle can schedule around.
+#[cfg(feature = "shuttle")]
+mod emulated {
+    use super::{Mutex, Ordering};
+
+    /// An integer type which can be safely shared between threads.
+    pub struct AtomicU128(Mutex<u128>);
+
+    impl AtomicU128 {
+        /// Creates a new atomic integer.
+        pub fn new(value: u128) -> Self {
+            Self(Mutex::new(value))
+        }
+
+        /// Returns whether operations on this type are lock-free.
+        pub fn is_lock_free() -> bool {
+            false
+        }
+
+        /// Loads a value from the atomic integer.
+        pub fn load(&self, _: Ordering) -> u128 {
+            *self.0.lock().unwrap()
+        }
+
+        /// Stores a value into the atomic integer.
+        pub fn store(&self, value: u128, _: Ordering) {
+            *self.0.lock().unwrap() = value;
+        }
+
+        /// Stores a value into the atomic integer if the current value is the
+        /// same as `current`, and returns the previous value.
+        pub fn compare_exchange(
+            &self,
+            current: u128,
+            new: u128,
+            _: Ordering,
+            _: Ordering,
+        ) -> Result<u128, u128> {
+            let mut value = self.0.lock().unwrap();
+            if *value == current {
+                *value = new;
+                Ok(current)
+            } else {
+                Err(*value)
+            }
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use core::array::from_fn;
+
+use crate::snapshot::ProcessId;
+use crate::sync::{AtomicU64, Mutex, Ordering};
+
+/// The bit of an announcement that is set while a process is pinned. The
+/// remaining bits store the epoch that the process announced.
+const PINNED: u64 = 1;
+
+/// A function that destroys some retired memory, along with the epoch in
+/// which the memory was retired.
+struct Deferred {
+    epoch: u64,
+    destroy: Box<dyn FnOnce() + Send>,
+}
+
+/// A raw pointer that can be sent to whichever process ends up destroying it.
+struct SendPtr<T>(*mut T);
+
+// SAFETY: The pointer is only dereferenced once, by `Box::from_raw`, after
+// every process has stopped accessing it.
+unsafe impl<T: Send> Send for SendPtr<T> {}
+
+/// An `N`-process collector of memory that is retired by lock-free objects,
+/// using epoch-based reclamation.
+///
+/// Lock-free objects often remove a node from a shared structure while
+/// other processes may still be reading it, and so cannot free that node
+/// immediately. Instead, each process [`pin`](Self::pin)s itself before
+/// accessing shared memory, and retires nodes that it removes by
+/// [deferring](Guard::defer) their destruction. A retired node is destroyed
+/// only once every process that was pinned when it was retired has since
+/// unpinned itself.
+///
+/// The collector maintains a global _epoch_. When a process is pinned, it
+/// announces the current epoch. The epoch can only be advanced once every
+/// pinned process has announced it, and memory retired in some epoch is
+/// destroyed once the global epoch is two greater, at which point no pinned
+/// process can still hold a reference to it. Retired memory is destroyed by
+/// the process that retired it, the next time that it is pinned, or when
+/// the collector is dropped.
+///
+/// Pinning is lock-free, but a process that stays pinned forever prevents
+/// any further memory from being destroyed.
+///
+/// # Examples
+///
+/// Replace a shared value while other processes may still be reading it.
+///
+/// ```
+/// use std::sync::atomic::{AtomicPtr, Ordering};
+/// use todc_mem::sync::Collector;
+///
+/// let collector: Collector<2> = Collector::new();
+/// let shared = AtomicPtr::new(Box::into_raw(Box::new(1)));
+///
+/// // Process 0 reads the value.
+/// let reader = collector.pin(0);
+/// let value = unsafe { &*shared.load(Ordering::SeqCst) };
+///
+/// // Process 1 replaces the value, and retires the old one.
+/// let writer = collector.pin(1);
+/// let old = shared.swap(Box::into_raw(Box::new(2)), Ordering::SeqCst);
+/// unsafe { writer.defer_destroy(old) };
+/// drop(writer);
+///
+/// // The old value is not destroyed while process 0 is still pinned.
+/// assert_eq!(*value, 1);
+/// drop(reader);
+/// # unsafe { drop(Box::from_raw(shared.load(Ordering::SeqCst))) };
+/// ```
+pub struct Collector<const N: usize> {
+    epoch: AtomicU64,
+    /// The epoch announced by each process, along with whether it is pinned.
+    announcements: [AtomicU64; N],
+    /// The memory retired by each process that has not yet been destroyed.
+    retired: [Mutex<Vec<Deferred>>; N],
+}
+
+impl<const N: usize> Collector<N> {
+    /// Creates a new collector.
+    pub fn new() -> Self {
+        Self {
+            epoch: AtomicU64::new(0),
+            announcements: from_fn(|_| AtomicU64::new(0)),
+            retired: from_fn(|_| Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Returns the global epoch.
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    /// Pins the _i^{th}_ process, which may then access shared memory until
+    /// the returned guard is dropped.
+    ///
+    /// Pinning also attempts to advance the global epoch, and destroys any
+    /// memory retired by the process that is no longer accessible.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the _i^{th}_ process is already pinned.
+    pub fn pin(&self, i: ProcessId) -> Guard<'_, N> {
+        let announcement = &self.announcements[i];
+        assert_eq!(
+            announcement.load(Ordering::SeqCst) & PINNED,
+            0,
+            "Process {i} is already pinned"
+        );
+        announcement.store((self.epoch() << 1) | PINNED, Ordering::SeqCst);
+        self.try_advance();
+        self.collect(i);
+        Guard {
+            collector: self,
+            process: i,
+        }
+    }
+
+    /// Advances the global epoch if every pinned process has announced it.
+    fn try_advance(&self) {
+        let epoch = self.epoch();
+        let announced = self.announcements.iter().all(|announcement| {
+            let announcement = announcement.load(Ordering::SeqCst);
+            announcement & PINNED == 0 || announcement >> 1 == epoch
+        });
+        if announced {
+            // If this fails, then another process advanced the epoch.
+            let _ =
+                self.epoch
+                    .compare_exchange(epoch, epoch + 1, Ordering::SeqCst, Ordering::SeqCst);
+        }
+    }
+
+    /// Destroys the memory retired by the _i^{th}_ process that can no
+    /// longer be accessed by any pinned process.
+    fn collect(&self, i: ProcessId) {
+        let epoch = self.epoch();
+        let safe: Vec<Deferred> = {
+            let mut retired = self.retired[i].lock().unwrap();
+            let (safe, pending): (Vec<_>, Vec<_>) = retired
+                .drain(..)
+                .partition(|deferred| deferred.epoch + 2 <= epoch);
+            *retired = pending;
+            safe
+        };
+        for deferred in safe {
+            (deferred.destroy)();
+        }
+    }
+}
+
+impl<const N: usize> Default for Collector<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Drop for Collector<N> {
+    fn drop(&mut self) {
+        // No process can be pinned, since every guard borrows the collector.
+        for retired in &self.retired {
+            for deferred in retired.lock().unwrap().drain(..) {
+                (deferred.destroy)();
+            }
+        }
+    }
+}
+
+/// A guard that keeps a process pinned to a [`Collector`] until it is dropped.
+pub struct Guard<'a, const N: usize> {
+    collector: &'a Collector<N>,
+    process: ProcessId,
+}
+
+impl<const N: usize> Guard<'_, N> {
+    /// Defers a function until no process that is currently pinned could
+    /// still be accessing the memory that it destroys.
+    pub fn defer<F: FnOnce() + Send + 'static>(&self, f: F) {
+        let deferred = Deferred {
+            epoch: self.collector.epoch(),
+            destroy: Box::new(f),
+        };
+        self.collector.retired[self.process]
+            .lock()
+            .unwrap()
+            .push(deferred);
+    }
+
+    /// Defers dropping the boxed value pointed to by `ptr` until no process
+    /// that is currently pinned could still be accessing it.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must have been created by [`Box::into_raw`], must not be
+    /// destroyed in any other way, and must no longer be reachable by any
+    /// process that pins itself after this call.
+    pub unsafe fn defer_destroy<T: Send + 'static>(&self, ptr: *mut T) {
+        let ptr = SendPtr(ptr);
+        self.defer(move || {
+            // Capture the whole wrapper, rather than just its raw pointer.
+            let ptr = ptr;
+            drop(Box::from_raw(ptr.0));
+        });
+    }
+}
+
+impl<const N: usize> Drop for Guard<'_, N> {
+    fn drop(&mut self) {
+        self.collector.announcements[self.process].store(0, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    /// Returns a function that counts how many times it has been called.
+    fn counter() -> (Arc<AtomicUsize>, impl Fn() -> Box<dyn FnOnce() + Send>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let c = count.clone();
+        let make = move || {
+            let c = c.clone();
+            Box::new(move || {
+                c.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }) as Box<dyn FnOnce() + Send>
+        };
+        (count, make)
+    }
+
+    fn destroyed(count: &AtomicUsize) -> usize {
+        count.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    mod pin {
+        use super::*;
+
+        #[test]
+        fn advances_epoch_if_no_other_process_is_pinned() {
+            let collector: Collector<2> = Collector::new();
+            drop(collector.pin(0));
+            assert_eq!(collector.epoch(), 1);
+        }
+
+        #[test]
+        fn does_not_advance_epoch_past_pinned_process() {
+            let collector: Collector<2> = Collector::new();
+            let _guard = collector.pin(1);
+            for _ in 0..5 {
+                drop(collector.pin(0));
+            }
+            assert_eq!(collector.epoch(), 1);
+        }
+
+        #[test]
+        #[should_panic(expected = "already pinned")]
+        fn panics_if_already_pinned() {
+            let collector: Collector<1> = Collector::new();
+            let _guard = collector.pin(0);
+            collector.pin(0);
+        }
+    }
+
+    mod defer {
+        use super::*;
+
+        #[test]
+        fn destroys_memory_once_epoch_advances_twice() {
+            let collector: Collector<1> = Collector::new();
+            let (count, destroy) = counter();
+            collector.pin(0).defer(destroy());
+            drop(collector.pin(0));
+            assert_eq!(destroyed(&count), 0);
+            drop(collector.pin(0));
+            assert_eq!(destroyed(&count), 1);
+        }
+
+        #[test]
+        fn does_not_destroy_memory_while_other_process_is_pinned() {
+            let collector: Collector<2> = Collector::new();
+            let (count, destroy) = counter();
+            let reader = collector.pin(1);
+            collector.pin(0).defer(destroy());
+            for _ in 0..5 {
+                drop(collector.pin(0));
+            }
+            assert_eq!(destroyed(&count), 0);
+            drop(reader);
+            for _ in 0..3 {
+                drop(collector.pin(0));
+            }
+            assert_eq!(destroyed(&count), 1);
+        }
+
+        #[test]
+        fn destroys_remaining_memory_when_collector_is_dropped() {
+            let collector: Collector<1> = Collector::new();
+            let (count, destroy) = counter();
+            collector.pin(0).defer(destroy());
+            drop(collector);
+            assert_eq!(destroyed(&count), 1);
+        }
+
+        #[test]
+        fn defer_destroy_drops_boxed_value() {
+            let collector: Collector<1> = Collector::new();
+            let value = Arc::new(());
+            let ptr = Box::into_raw(Box::new(value.clone()));
+            unsafe { collector.pin(0).defer_destroy(ptr) };
+            assert_eq!(Arc::strong_count(&value), 2);
+            drop(collector);
+            assert_eq!(Arc::strong_count(&value), 1);
+        }
+    }
+}
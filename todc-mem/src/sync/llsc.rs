@@ -0,0 +1,187 @@
+use std::marker::PhantomData;
+
+use crate::sync::{AtomicU64, Ordering};
+
+/// The number of bits of the underlying word that store a value. The
+/// remaining bits store a tag.
+const VALUE_BITS: u32 = 32;
+
+/// A mask selecting the bits of the underlying word that store a value.
+const VALUE_MASK: u64 = (1 << VALUE_BITS) - 1;
+
+/// The result of a [`load_linked`](LoadLinkedStoreConditional::load_linked)
+/// operation, which must be passed to a later
+/// [`store_conditional`](LoadLinkedStoreConditional::store_conditional).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Link<T> {
+    word: u64,
+    value: PhantomData<T>,
+}
+
+impl<T: From<u32>> Link<T> {
+    /// Returns the value that was loaded.
+    pub fn value(&self) -> T {
+        T::from((self.word & VALUE_MASK) as u32)
+    }
+}
+
+/// A load-linked/store-conditional (LL/SC) object, built from a single
+/// [`AtomicU64`] using compare-and-swap.
+///
+/// A [`store_conditional`](Self::store_conditional) only succeeds if no other
+/// store has succeeded since the matching
+/// [`load_linked`](Self::load_linked). Unlike a compare-and-swap, this holds
+/// even if the contents of the object were changed and then changed back, so
+/// algorithms that use LL/SC do not suffer from the _ABA problem_.
+///
+/// To prevent the ABA problem, the upper 32 bits of the word hold a tag that
+/// is incremented by every successful store, and the lower 32 bits hold the
+/// value. As a result, values must be convertible to and from [`u32`]. The
+/// tag wraps around after `2^32` successful stores, so a store conditional
+/// may incorrectly succeed if _exactly_ a multiple of `2^32` other stores
+/// succeed between it and its load linked.
+///
+/// All operations are wait-free.
+///
+/// # Examples
+///
+/// Increment a shared counter from multiple threads.
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+/// use todc_mem::sync::LoadLinkedStoreConditional;
+///
+/// let counter: Arc<LoadLinkedStoreConditional<u32>> = Arc::new(LoadLinkedStoreConditional::new(0));
+///
+/// let handles: Vec<_> = (0..3)
+///     .map(|_| {
+///         let counter = counter.clone();
+///         thread::spawn(move || loop {
+///             let link = counter.load_linked();
+///             if counter.store_conditional(link, link.value() + 1) {
+///                 break;
+///             }
+///         })
+///     })
+///     .collect();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+/// assert_eq!(counter.load(), 3);
+/// ```
+#[derive(Debug)]
+pub struct LoadLinkedStoreConditional<T: From<u32> + Into<u32>> {
+    word: AtomicU64,
+    value: PhantomData<T>,
+}
+
+impl<T: From<u32> + Into<u32>> LoadLinkedStoreConditional<T> {
+    /// Creates a new object containing the given value.
+    pub fn new(value: T) -> Self {
+        Self {
+            word: AtomicU64::new(value.into() as u64),
+            value: PhantomData,
+        }
+    }
+
+    /// Returns the value contained in the object.
+    pub fn load(&self) -> T {
+        T::from((self.word.load(Ordering::SeqCst) & VALUE_MASK) as u32)
+    }
+
+    /// Returns the value contained in the object, along with a [`Link`] that
+    /// can be used to conditionally store a new value.
+    pub fn load_linked(&self) -> Link<T> {
+        Link {
+            word: self.word.load(Ordering::SeqCst),
+            value: PhantomData,
+        }
+    }
+
+    /// Stores a value if no other store has succeeded since `link` was
+    /// loaded, and returns whether the store succeeded.
+    pub fn store_conditional(&self, link: Link<T>, value: T) -> bool {
+        let tag = (link.word >> VALUE_BITS).wrapping_add(1);
+        let word = (tag << VALUE_BITS) | value.into() as u64;
+        self.word
+            .compare_exchange(link.word, word, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    /// Returns whether no store has succeeded since `link` was loaded.
+    ///
+    /// A later store conditional using `link` may still fail, if another
+    /// store succeeds in the meantime.
+    pub fn validate(&self, link: Link<T>) -> bool {
+        self.word.load(Ordering::SeqCst) == link.word
+    }
+}
+
+impl<T: Default + From<u32> + Into<u32>> Default for LoadLinkedStoreConditional<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Llsc = LoadLinkedStoreConditional<u32>;
+
+    mod store_conditional {
+        use super::*;
+
+        #[test]
+        fn succeeds_if_no_other_store_succeeded() {
+            let llsc = Llsc::new(1);
+            let link = llsc.load_linked();
+            assert_eq!(link.value(), 1);
+            assert!(llsc.store_conditional(link, 2));
+            assert_eq!(llsc.load(), 2);
+        }
+
+        #[test]
+        fn fails_if_another_store_succeeded() {
+            let llsc = Llsc::new(1);
+            let first = llsc.load_linked();
+            let second = llsc.load_linked();
+            assert!(llsc.store_conditional(second, 2));
+            assert!(!llsc.store_conditional(first, 3));
+            assert_eq!(llsc.load(), 2);
+        }
+
+        #[test]
+        fn fails_even_if_value_was_restored() {
+            let llsc = Llsc::new(1);
+            let link = llsc.load_linked();
+            assert!(llsc.store_conditional(llsc.load_linked(), 2));
+            assert!(llsc.store_conditional(llsc.load_linked(), 1));
+            assert_eq!(llsc.load(), 1);
+            assert!(!llsc.store_conditional(link, 3));
+        }
+
+        #[test]
+        fn wraps_tag_around() {
+            let llsc = Llsc::new(1);
+            llsc.word.store(u64::MAX, Ordering::SeqCst);
+            let link = llsc.load_linked();
+            assert!(llsc.store_conditional(link, 2));
+            assert_eq!(llsc.word.load(Ordering::SeqCst), 2);
+        }
+    }
+
+    mod validate {
+        use super::*;
+
+        #[test]
+        fn is_false_after_another_store_succeeds() {
+            let llsc = Llsc::new(1);
+            let link = llsc.load_linked();
+            assert!(llsc.validate(link));
+            llsc.store_conditional(llsc.load_linked(), 1);
+            assert!(!llsc.validate(link));
+        }
+    }
+}
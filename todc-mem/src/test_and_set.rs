@@ -0,0 +1,96 @@
+//! Test-and-set objects.
+//!
+//! A test-and-set object contains a single bit, which is initially unset.
+//! The [`test_and_set`](TestAndSet::test_and_set) operation sets the bit
+//! and returns its previous value. Exactly one process, the _winner_, will
+//! ever observe the bit as unset.
+//!
+//! Test-and-set has consensus number 2, and so, unlike read/write registers,
+//! it can be used to solve consensus between two processes. As a result, there
+//! is no deterministic wait-free implementation of test-and-set from registers.
+//! This module contains both a [`NativeTestAndSet`], which makes use of
+//! the hardware instruction, and a [`RandomizedTestAndSet`], which is built
+//! entirely from registers and is wait-free with probability 1.
+//!
+//! # Examples
+//!
+//! Elect a leader among a set of threads.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::thread;
+//! use todc_mem::test_and_set::{AtomicRandomizedTestAndSet, TestAndSet};
+//!
+//! const N: usize = 4;
+//!
+//! let tas: Arc<AtomicRandomizedTestAndSet<N>> = Arc::new(AtomicRandomizedTestAndSet::new());
+//!
+//! let handles: Vec<_> = (0..N)
+//!     .map(|i| {
+//!         let tas = tas.clone();
+//!         thread::spawn(move || !tas.test_and_set(i))
+//!     })
+//!     .collect();
+//!
+//! let leaders = handles
+//!     .into_iter()
+//!     .map(|handle| handle.join().unwrap())
+//!     .filter(|&is_leader| is_leader)
+//!     .count();
+//! assert_eq!(leaders, 1);
+//! ```
+use crate::snapshot::ProcessId;
+use crate::sync::{AtomicBool, Ordering};
+
+mod randomized;
+pub use self::randomized::{
+    AtomicRandomizedTestAndSet, MutexRandomizedTestAndSet, RandomizedTestAndSet,
+};
+
+/// An `N`-process test-and-set object.
+pub trait TestAndSet<const N: usize> {
+    /// Creates a new test-and-set object, in which the bit is unset.
+    fn new() -> Self;
+
+    /// Sets the bit and returns its previous value.
+    fn test_and_set(&self, i: ProcessId) -> bool;
+}
+
+/// A test-and-set object backed by an [`AtomicBool`].
+///
+/// This object is wait-free for any number of processes, and is provided
+/// as a baseline against which implementations from weaker primitives
+/// can be compared.
+#[derive(Debug)]
+pub struct NativeTestAndSet {
+    bit: AtomicBool,
+}
+
+impl<const N: usize> TestAndSet<N> for NativeTestAndSet {
+    fn new() -> Self {
+        Self {
+            bit: AtomicBool::new(false),
+        }
+    }
+
+    fn test_and_set(&self, _: ProcessId) -> bool {
+        self.bit.swap(true, Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod native_test_and_set {
+        use super::*;
+
+        #[test]
+        fn only_first_operation_returns_false() {
+            let tas: NativeTestAndSet = TestAndSet::<2>::new();
+            assert!(!TestAndSet::<2>::test_and_set(&tas, 0));
+            assert!(TestAndSet::<2>::test_and_set(&tas, 1));
+            assert!(TestAndSet::<2>::test_and_set(&tas, 0));
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use core::array::from_fn;
+
+use crate::register::{AtomicRegister, MutexRegister, Register};
+use crate::snapshot::ProcessId;
+use crate::sync::{spin_loop, thread_rng, Rng};
+use crate::test_and_set::TestAndSet;
+
+/// The maximum number of rounds performed by each instance of two-process
+/// consensus.
+///
+/// Each round reaches agreement with constant probability, so this limit is
+/// only reached with negligible probability.
+const MAX_ROUNDS: usize = 64;
+
+/// The probability that a process writes to a conciliator during a step.
+const WRITE_PROBABILITY: f64 = 1.0 / 4.0;
+
+/// An `N`-process randomized test-and-set object, using [`AtomicRegister`]
+/// objects.
+///
+/// For implementation details, see [`RandomizedTestAndSet`].
+pub type AtomicRandomizedTestAndSet<const N: usize> = RandomizedTestAndSet<AtomicRegister<u64>, N>;
+
+/// An `N`-process randomized test-and-set object, using [`MutexRegister`]
+/// objects.
+///
+/// This object is **not** lock-free. For implementation details, see
+/// [`RandomizedTestAndSet`].
+pub type MutexRandomizedTestAndSet<const N: usize> = RandomizedTestAndSet<MutexRegister<u64>, N>;
+
+/// The result of an adopt-commit operation.
+enum Decision {
+    Adopt(u64),
+    Commit(u64),
+}
+
+/// A single round of two-process randomized consensus, consisting of a
+/// conciliator followed by an adopt-commit object.
+///
+/// Values are stored in registers offset by one, so that `0` can be used to
+/// represent an empty register.
+struct Round<R: Register<Value = u64>> {
+    conciliator: R,
+    proposals: [R; 2],
+    flags: [R; 2],
+}
+
+impl<R: Register<Value = u64>> Round<R> {
+    fn new() -> Self {
+        Self {
+            conciliator: R::new(),
+            proposals: [R::new(), R::new()],
+            flags: [R::new(), R::new()],
+        }
+    }
+
+    /// Returns a value that was input by some process, such that with
+    /// constant probability all processes return the same value.
+    ///
+    /// Each process repeatedly checks whether some value has been chosen, and
+    /// otherwise chooses its own value with a small probability. Agreement fails
+    /// only if both processes choose a value before seeing the other's choice.
+    fn conciliate(&self, value: u64) -> u64 {
+        let mut rng = thread_rng();
+        loop {
+            let chosen = self.conciliator.read();
+            if chosen != 0 {
+                return chosen - 1;
+            }
+            if rng.gen_bool(WRITE_PROBABILITY) {
+                self.conciliator.write(value + 1);
+            } else {
+                spin_loop();
+            }
+        }
+    }
+
+    /// Returns a value that was input by some process, along with a decision
+    /// to either _commit_ to it or _adopt_ it.
+    ///
+    /// If some process commits to a value, then all processes return that
+    /// value, and if all processes input the same value then all processes
+    /// commit to it. This construction is due to Gafni
+    /// [\[Gaf98\]](https://doi.org/10.1145/277697.277724).
+    fn adopt_commit(&self, side: usize, value: u64) -> Decision {
+        let other = 1 - side;
+        self.proposals[side].write(value + 1);
+        let proposal = self.proposals[other].read();
+        let unanimous = proposal == 0 || proposal == value + 1;
+        let flag = ((value + 1) << 1) | unanimous as u64;
+        self.flags[side].write(flag);
+
+        let other_flag = self.flags[other].read();
+        let other_unanimous = other_flag & 1 == 1;
+        if unanimous && (other_flag == 0 || other_flag == flag) {
+            Decision::Commit(value)
+        } else if other_unanimous {
+            Decision::Adopt((other_flag >> 1) - 1)
+        } else {
+            Decision::Adopt(value)
+        }
+    }
+}
+
+/// A wait-free, randomized, two-process consensus object.
+///
+/// This implementation follows the framework described by Aspnes
+/// [\[Asp12\]](https://doi.org/10.1007/s00446-012-0162-z), where each round
+/// uses a conciliator to make agreement likely, followed by an adopt-commit
+/// object to detect whether agreement was reached.
+struct Consensus<R: Register<Value = u64>> {
+    rounds: Vec<Round<R>>,
+}
+
+impl<R: Register<Value = u64>> Consensus<R> {
+    fn new() -> Self {
+        Self {
+            rounds: (0..MAX_ROUNDS).map(|_| Round::new()).collect(),
+        }
+    }
+
+    /// Returns the value decided upon by both processes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if agreement is not reached after [`MAX_ROUNDS`] rounds.
+    fn decide(&self, side: usize, value: u64) -> u64 {
+        let mut value = value;
+        for round in self.rounds.iter() {
+            value = round.conciliate(value);
+            match round.adopt_commit(side, value) {
+                Decision::Commit(decided) => return decided,
+                Decision::Adopt(adopted) => value = adopted,
+            }
+        }
+        panic!("Consensus was not reached after {MAX_ROUNDS} rounds")
+    }
+}
+
+/// A wait-free `N`-process test-and-set object, built from registers.
+///
+/// Processes compete in a tournament tree, as described by Afek, Gafni,
+/// Tromp and Vitányi [\[AGTV92\]](https://doi.org/10.1007/3-540-56188-9_2).
+/// At each node of the tree, the two processes arriving from either subtree
+/// use randomized two-process consensus to determine which of them continues
+/// upwards. The process that wins at the root wins the test-and-set.
+///
+/// To ensure linearizability, processes must first pass through a _doorway_.
+/// A process that finds the doorway closed has arrived after some other process
+/// has already started, and immediately loses.
+///
+/// Each operation terminates with probability 1, provided that the scheduler
+/// cannot observe the outcome of coin flips before deciding which process to
+/// run next. Since memory is allocated up front, each instance of consensus is
+/// limited to a fixed number of rounds and the operation panics if agreement is
+/// not reached in time, although this happens with negligible probability.
+pub struct RandomizedTestAndSet<R: Register<Value = u64>, const N: usize> {
+    doorway: R,
+    invoked: [R; N],
+    // The internal nodes of a complete binary tree with (at least) N leaves,
+    // stored in heap-order starting at index 1.
+    nodes: Vec<Consensus<R>>,
+}
+
+impl<R: Register<Value = u64>, const N: usize> RandomizedTestAndSet<R, N> {
+    /// Returns the number of leaves in the tournament tree.
+    fn width() -> usize {
+        N.next_power_of_two()
+    }
+}
+
+impl<R: Register<Value = u64>, const N: usize> TestAndSet<N> for RandomizedTestAndSet<R, N> {
+    fn new() -> Self {
+        Self {
+            doorway: R::new(),
+            invoked: from_fn(|_| R::new()),
+            nodes: (0..Self::width()).map(|_| Consensus::new()).collect(),
+        }
+    }
+
+    fn test_and_set(&self, i: ProcessId) -> bool {
+        // Each process only competes once, after which the bit is set.
+        if self.invoked[i].read() == 1 {
+            return true;
+        }
+        self.invoked[i].write(1);
+
+        if self.doorway.read() == 1 {
+            return true;
+        }
+        self.doorway.write(1);
+
+        let mut position = Self::width() + i;
+        while position > 1 {
+            let side = position % 2;
+            position /= 2;
+            if self.nodes[position].decide(side, side as u64) != side as u64 {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    mod round {
+        use super::*;
+
+        #[test]
+        fn commits_if_running_alone() {
+            let round: Round<MutexRegister<u64>> = Round::new();
+            let value = round.conciliate(1);
+            assert_eq!(value, 1);
+            assert!(matches!(round.adopt_commit(0, value), Decision::Commit(1)));
+        }
+
+        #[test]
+        fn adopts_committed_value() {
+            let round: Round<MutexRegister<u64>> = Round::new();
+            assert!(matches!(round.adopt_commit(0, 0), Decision::Commit(0)));
+            assert!(matches!(round.adopt_commit(1, 1), Decision::Adopt(0)));
+        }
+
+        #[test]
+        fn conciliates_to_chosen_value() {
+            let round: Round<MutexRegister<u64>> = Round::new();
+            assert_eq!(round.conciliate(0), 0);
+            assert_eq!(round.conciliate(1), 0);
+        }
+    }
+
+    mod consensus {
+        use super::*;
+
+        #[test]
+        fn decides_on_input_if_running_alone() {
+            let consensus: Consensus<AtomicRegister<u64>> = Consensus::new();
+            assert_eq!(consensus.decide(1, 1), 1);
+            assert_eq!(consensus.decide(0, 0), 1);
+        }
+
+        #[test]
+        fn concurrent_processes_agree() {
+            for _ in 0..100 {
+                let consensus: Arc<Consensus<AtomicRegister<u64>>> = Arc::new(Consensus::new());
+                let handles: Vec<_> = (0..2)
+                    .map(|side| {
+                        let consensus = consensus.clone();
+                        thread::spawn(move || consensus.decide(side, side as u64))
+                    })
+                    .collect();
+                let decisions: Vec<u64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+                assert_eq!(decisions[0], decisions[1]);
+            }
+        }
+    }
+
+    mod randomized_test_and_set {
+        use super::*;
+
+        #[test]
+        fn only_first_operation_returns_false() {
+            let tas: MutexRandomizedTestAndSet<3> = MutexRandomizedTestAndSet::new();
+            assert!(!tas.test_and_set(2));
+            assert!(tas.test_and_set(0));
+            assert!(tas.test_and_set(1));
+            assert!(tas.test_and_set(2));
+        }
+
+        #[test]
+        fn supports_a_single_process() {
+            let tas: AtomicRandomizedTestAndSet<1> = AtomicRandomizedTestAndSet::new();
+            assert!(!tas.test_and_set(0));
+            assert!(tas.test_and_set(0));
+        }
+
+        #[test]
+        fn concurrent_processes_elect_one_winner() {
+            const N: usize = 5;
+            for _ in 0..50 {
+                let tas: Arc<AtomicRandomizedTestAndSet<N>> =
+                    Arc::new(AtomicRandomizedTestAndSet::new());
+                let handles: Vec<_> = (0..N)
+                    .map(|i| {
+                        let tas = tas.clone();
+                        thread::spawn(move || tas.test_and_set(i))
+                    })
+                    .collect();
+                let winners = handles
+                    .into_iter()
+                    .map(|h| h.join().unwrap())
+                    .filter(|&result| !result)
+                    .count();
+                assert_eq!(winners, 1);
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::Arc;
+
+use shuttle::sync::Mutex;
+use shuttle::thread;
+use todc_mem::allocation::MutexRenaming;
+use todc_utils::specifications::renaming::{RenamingOperation, RenamingSpecification};
+use todc_utils::{Action, History, WGLChecker};
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_OPERATIONS: usize = 3;
+const NUM_THREADS: usize = 3;
+
+type Log = Vec<(usize, Action<RenamingOperation>)>;
+
+/// Acquires and then releases a name, recording each operation in the log.
+fn acquire_and_release(renaming: &MutexRenaming<NUM_THREADS>, log: &Mutex<Log>, i: usize) {
+    let record = |action| log.lock().unwrap().push((i, action));
+
+    record(Action::Call(RenamingOperation::Acquire(i, None)));
+    let name = renaming.acquire(i);
+    record(Action::Response(RenamingOperation::Acquire(i, Some(name))));
+
+    record(Action::Call(RenamingOperation::Release(i)));
+    renaming.release(i);
+    record(Action::Response(RenamingOperation::Release(i)));
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn acquired_names_are_linearizable() {
+    shuttle::check_random(
+        || {
+            let renaming: Arc<MutexRenaming<NUM_THREADS>> = Arc::new(MutexRenaming::new());
+            let log: Arc<Mutex<Log>> = Arc::new(Mutex::new(Vec::new()));
+            let handles: Vec<_> = (0..NUM_THREADS)
+                .map(|i| {
+                    let renaming = renaming.clone();
+                    let log = log.clone();
+                    thread::spawn(move || {
+                        for _ in 0..NUM_OPERATIONS {
+                            acquire_and_release(&renaming, &log, i);
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+
+            let history = History::from_actions(log.lock().unwrap().clone());
+            assert!(
+                WGLChecker::<RenamingSpecification<NUM_THREADS>>::is_linearizable(history.clone()),
+                "History is not linearizable:\n{}",
+                history.render_timeline()
+            );
+        },
+        NUM_ITERATIONS,
+    );
+}
@@ -0,0 +1,71 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::Arc;
+
+use shuttle::thread;
+use todc_mem::consensus::{CASConsensus, Consensus, QueueConsensus, TestAndSetConsensus};
+use todc_mem::test_and_set::AtomicRandomizedTestAndSet;
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_THREADS: usize = 5;
+
+/// Asserts that every process decides the same value, and that the value
+/// was proposed by one of them.
+///
+/// # Panics
+///
+/// Panics if two processes decide different values, or if a process
+/// decides a value that was never proposed.
+fn assert_agreement<const N: usize, C: Consensus<N, Value = usize> + 'static + Send + Sync>() {
+    let consensus: Arc<C> = Arc::new(C::new());
+    let handles: Vec<_> = (0..N)
+        .map(|i| {
+            let consensus = consensus.clone();
+            thread::spawn(move || consensus.decide(i, i + 100))
+        })
+        .collect();
+
+    let decisions: Vec<usize> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    assert!(decisions.iter().all(|&decision| decision == decisions[0]));
+    assert!((100..100 + N).contains(&decisions[0]));
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn queue_consensus_agrees() {
+    shuttle::check_random(assert_agreement::<2, QueueConsensus<usize>>, NUM_ITERATIONS);
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn test_and_set_consensus_agrees() {
+    shuttle::check_random(
+        assert_agreement::<2, TestAndSetConsensus<usize>>,
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn randomized_test_and_set_consensus_agrees() {
+    shuttle::check_random(
+        assert_agreement::<2, TestAndSetConsensus<usize, AtomicRandomizedTestAndSet<2>>>,
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn cas_consensus_agrees() {
+    shuttle::check_random(
+        assert_agreement::<NUM_THREADS, CASConsensus<usize, NUM_THREADS>>,
+        NUM_ITERATIONS,
+    );
+}
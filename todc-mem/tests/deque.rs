@@ -0,0 +1,187 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::{Arc, Mutex};
+
+use shuttle::thread;
+use todc_mem::deque::{Deque, HLMDeque};
+use todc_utils::specifications::deque::{
+    DequeOperation::{PopBack, PopFront, PushBack, PushFront},
+    DequeSpecification,
+};
+use todc_utils::{Action, History, WGLChecker};
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_OPERATIONS: usize = 4;
+const NUM_PREEMPTIONS: usize = 3;
+const NUM_THREADS: usize = 3;
+
+/// Enough room at each end of the deque for every value to be pushed onto
+/// it.
+const CAPACITY: usize = 2 * NUM_THREADS * NUM_OPERATIONS;
+
+/// Asserts that a history of concurrent pushes and pops is linearizable.
+///
+/// Each process alternates between pushing a unique value and popping, and
+/// different processes start at different ends of the deque.
+///
+/// # Panics
+///
+/// Panics if the history of operations is not linearizable.
+fn assert_deque_is_linearizable<D>()
+where
+    D: Deque<NUM_THREADS, Value = usize> + 'static + Send + Sync,
+{
+    let deque: Arc<D> = Arc::new(D::new());
+    let actions = Arc::new(Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = (0..NUM_THREADS)
+        .map(|i| {
+            let deque = deque.clone();
+            let actions = actions.clone();
+            thread::spawn(move || {
+                for op in 0..NUM_OPERATIONS {
+                    let front = (i + op / 2) % 2 == 0;
+                    if op % 2 == 0 {
+                        let value = i * NUM_OPERATIONS + op;
+                        let operation = if front {
+                            PushFront(value)
+                        } else {
+                            PushBack(value)
+                        };
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Call(operation.clone())));
+                        let pushed = if front {
+                            deque.push_front(i, value)
+                        } else {
+                            deque.push_back(i, value)
+                        };
+                        assert!(pushed.is_ok(), "Deque has no room for value");
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Response(operation)));
+                    } else if front {
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Call(PopFront(None))));
+                        let value = deque.pop_front(i);
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Response(PopFront(Some(value)))));
+                    } else {
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Call(PopBack(None))));
+                        let value = deque.pop_back(i);
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Response(PopBack(Some(value)))));
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let actions = actions.lock().unwrap().clone();
+    let history = History::from_actions(actions);
+    assert!(
+        WGLChecker::<DequeSpecification<usize>>::is_linearizable(history.clone()),
+        "History is not linearizable:\n{}",
+        history.render_timeline()
+    );
+}
+
+/// Asserts that every value pushed onto the deque is popped exactly once.
+///
+/// Each process pushes many values onto both ends of the deque, and pops as
+/// many values as it pushes, from either end. Histories of this length are
+/// too long to check for linearizability.
+///
+/// # Panics
+///
+/// Panics if a value is lost or popped more than once.
+fn assert_deque_does_not_lose_values<D>()
+where
+    D: Deque<NUM_THREADS, Value = usize> + 'static + Send + Sync,
+{
+    const NUM_VALUES: usize = 4 * NUM_OPERATIONS;
+
+    let deque: Arc<D> = Arc::new(D::new());
+    let handles: Vec<_> = (0..NUM_THREADS)
+        .map(|i| {
+            let deque = deque.clone();
+            thread::spawn(move || {
+                let mut popped = Vec::new();
+                for n in 0..NUM_VALUES {
+                    let value = i * NUM_VALUES + n;
+                    let pushed = if n % 2 == 0 {
+                        deque.push_front(i, value)
+                    } else {
+                        deque.push_back(i, value)
+                    };
+                    assert!(pushed.is_ok(), "Deque has no room for value");
+                    if n % 3 == i % 3 {
+                        popped.extend(deque.pop_front(i));
+                    } else {
+                        popped.extend(deque.pop_back(i));
+                    }
+                }
+                popped
+            })
+        })
+        .collect();
+
+    let mut values: Vec<usize> = handles
+        .into_iter()
+        .flat_map(|handle| handle.join().unwrap())
+        .collect();
+    while let Some(value) = deque.pop_front(0) {
+        values.push(value);
+    }
+    values.sort();
+
+    let expected: Vec<usize> = (0..NUM_THREADS * NUM_VALUES).collect();
+    assert_eq!(values, expected);
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn hlm_deque_is_linearizable() {
+    shuttle::check_pct(
+        assert_deque_is_linearizable::<HLMDeque<usize, NUM_THREADS, CAPACITY>>,
+        NUM_ITERATIONS,
+        NUM_PREEMPTIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn hlm_deque_is_linearizable_under_random_schedules() {
+    shuttle::check_random(
+        assert_deque_is_linearizable::<HLMDeque<usize, NUM_THREADS, CAPACITY>>,
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn hlm_deque_does_not_lose_values_under_random_schedules() {
+    shuttle::check_random(
+        assert_deque_does_not_lose_values::<HLMDeque<usize, NUM_THREADS, { 8 * CAPACITY }>>,
+        NUM_ITERATIONS,
+    );
+}
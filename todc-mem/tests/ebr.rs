@@ -0,0 +1,69 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::Arc;
+
+use shuttle::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use shuttle::thread;
+use todc_mem::sync::Collector;
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_OPERATIONS: usize = 5;
+const NUM_THREADS: usize = 4;
+const NUM_VALUES: usize = NUM_THREADS * NUM_OPERATIONS + 1;
+
+/// Asserts that retired values are never destroyed while a process that
+/// may be reading them is pinned.
+///
+/// Each value is identified by an index into a list of flags, which record
+/// whether the value has been destroyed. Half of the processes repeatedly
+/// read the current value, while the other half replace it, and retire the
+/// value that they replaced.
+///
+/// # Panics
+///
+/// Panics if a process reads a value that has been destroyed.
+fn assert_retired_values_outlive_readers() {
+    let collector: Arc<Collector<NUM_THREADS>> = Arc::new(Collector::new());
+    let destroyed: Arc<Vec<AtomicBool>> =
+        Arc::new((0..NUM_VALUES).map(|_| AtomicBool::new(false)).collect());
+    let current = Arc::new(AtomicUsize::new(0));
+    let next = Arc::new(AtomicUsize::new(1));
+
+    let handles: Vec<_> = (0..NUM_THREADS)
+        .map(|i| {
+            let collector = collector.clone();
+            let destroyed = destroyed.clone();
+            let current = current.clone();
+            let next = next.clone();
+            thread::spawn(move || {
+                for _ in 0..NUM_OPERATIONS {
+                    let guard = collector.pin(i);
+                    if i % 2 == 0 {
+                        let value = current.load(Ordering::SeqCst);
+                        thread::yield_now();
+                        assert!(!destroyed[value].load(Ordering::SeqCst));
+                    } else {
+                        let value = next.fetch_add(1, Ordering::SeqCst);
+                        let old = current.swap(value, Ordering::SeqCst);
+                        let destroyed = destroyed.clone();
+                        guard.defer(move || destroyed[old].store(true, Ordering::SeqCst));
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn retired_values_are_not_destroyed_while_read() {
+    shuttle::check_random(assert_retired_values_outlive_readers, NUM_ITERATIONS);
+}
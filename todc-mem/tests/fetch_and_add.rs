@@ -0,0 +1,63 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::Arc;
+
+use shuttle::thread;
+use todc_mem::fetch_and_add::{BakeryFetchAndAdd, FetchAndAdd, NativeFetchAndAdd};
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_OPERATIONS: usize = 5;
+const NUM_THREADS: usize = 4;
+
+/// Asserts that concurrent increments each observe a distinct value, and
+/// that no increment is lost.
+///
+/// # Panics
+///
+/// Panics if two increments return the same value, or if the final value
+/// does not account for every increment.
+fn assert_no_lost_increments<const N: usize, F: FetchAndAdd<N> + 'static + Send + Sync>() {
+    let faa: Arc<F> = Arc::new(F::new());
+    let handles: Vec<_> = (0..N)
+        .map(|i| {
+            let faa = faa.clone();
+            thread::spawn(move || {
+                (0..NUM_OPERATIONS)
+                    .map(|_| faa.fetch_and_add(i, 1))
+                    .collect::<Vec<u64>>()
+            })
+        })
+        .collect();
+
+    let mut values: Vec<u64> = handles
+        .into_iter()
+        .flat_map(|handle| handle.join().unwrap())
+        .collect();
+    values.sort();
+
+    let expected: Vec<u64> = (0..(N * NUM_OPERATIONS) as u64).collect();
+    assert_eq!(values, expected);
+    assert_eq!(faa.fetch_and_add(0, 0), (N * NUM_OPERATIONS) as u64);
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn native_fetch_and_add_loses_no_increments() {
+    shuttle::check_random(
+        assert_no_lost_increments::<NUM_THREADS, NativeFetchAndAdd>,
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn bakery_fetch_and_add_loses_no_increments() {
+    shuttle::check_random(
+        assert_no_lost_increments::<NUM_THREADS, BakeryFetchAndAdd<NUM_THREADS>>,
+        NUM_ITERATIONS,
+    );
+}
@@ -0,0 +1,88 @@
+#![allow(dead_code, unused_imports)]
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use shuttle::thread;
+use todc_mem::lattice_agreement::{LatticeAgreement, MutexLatticeAgreement};
+use todc_utils::specifications::lattice_agreement::{
+    LatticeAgreementOperation::Propose, LatticeAgreementSpecification,
+};
+use todc_utils::{Action, History, WGLChecker};
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_OPERATIONS: usize = 5;
+const NUM_PREEMPTIONS: usize = 3;
+const NUM_THREADS: usize = 4;
+
+/// Asserts that a history of concurrent proposals is linearizable, and that
+/// every decision is contained in the union of the values proposed before it
+/// was made.
+///
+/// # Panics
+///
+/// Panics if the history of proposals is not linearizable, or if a process
+/// decides on a value that had not yet been proposed.
+fn assert_proposals_are_linearizable<A>()
+where
+    A: LatticeAgreement<NUM_THREADS, Value = BTreeSet<usize>> + 'static + Send + Sync,
+{
+    let agreement: Arc<A> = Arc::new(A::new());
+    let actions = Arc::new(Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = (0..NUM_THREADS)
+        .map(|i| {
+            let agreement = agreement.clone();
+            let actions = actions.clone();
+            thread::spawn(move || {
+                for op in 0..NUM_OPERATIONS {
+                    let proposal = BTreeSet::from([i * NUM_OPERATIONS + op]);
+                    let call = Action::Call(Propose(i, proposal.clone(), None));
+                    actions.lock().unwrap().push((i, call));
+
+                    let decision = agreement.propose(i, proposal.clone());
+
+                    let response = Action::Response(Propose(i, proposal, Some(decision)));
+                    actions.lock().unwrap().push((i, response));
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let actions = actions.lock().unwrap().clone();
+    let mut proposed: BTreeSet<usize> = BTreeSet::new();
+    for (_, action) in &actions {
+        match action {
+            Action::Call(Propose(_, proposal, _)) => proposed.extend(proposal.iter().cloned()),
+            Action::Response(Propose(_, _, decision)) => {
+                assert!(decision.as_ref().unwrap().is_subset(&proposed))
+            }
+            _ => unreachable!("Every proposal returns a decision"),
+        }
+    }
+
+    let history = History::from_actions(actions);
+    assert!(
+        WGLChecker::<LatticeAgreementSpecification<usize>>::is_linearizable(history.clone()),
+        "History is not linearizable:\n{}",
+        history.render_timeline()
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mutex_lattice_agreement_is_linearizable() {
+    shuttle::check_pct(
+        assert_proposals_are_linearizable::<MutexLatticeAgreement<BTreeSet<usize>, NUM_THREADS>>,
+        NUM_ITERATIONS,
+        NUM_PREEMPTIONS,
+    );
+}
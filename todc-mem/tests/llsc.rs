@@ -0,0 +1,86 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::Arc;
+
+use shuttle::thread;
+use todc_mem::sync::LoadLinkedStoreConditional;
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_OPERATIONS: usize = 5;
+const NUM_THREADS: usize = 4;
+
+/// Increments the value contained in the object, and returns its previous
+/// value.
+fn increment(llsc: &LoadLinkedStoreConditional<u32>) -> u32 {
+    loop {
+        let link = llsc.load_linked();
+        if llsc.store_conditional(link, link.value() + 1) {
+            return link.value();
+        }
+    }
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn increments_with_store_conditional_are_not_lost() {
+    shuttle::check_random(
+        || {
+            let llsc = Arc::new(LoadLinkedStoreConditional::new(0));
+            let handles: Vec<_> = (0..NUM_THREADS)
+                .map(|_| {
+                    let llsc = llsc.clone();
+                    thread::spawn(move || {
+                        (0..NUM_OPERATIONS)
+                            .map(|_| increment(&llsc))
+                            .collect::<Vec<u32>>()
+                    })
+                })
+                .collect();
+
+            let mut values: Vec<u32> = handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect();
+            values.sort();
+
+            let expected: Vec<u32> = (0..(NUM_THREADS * NUM_OPERATIONS) as u32).collect();
+            assert_eq!(values, expected);
+            assert_eq!(llsc.load(), (NUM_THREADS * NUM_OPERATIONS) as u32);
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn store_conditional_fails_after_aba() {
+    shuttle::check_random(
+        || {
+            let llsc = Arc::new(LoadLinkedStoreConditional::new(0));
+            let link = llsc.load_linked();
+
+            // Another thread changes the value, and then changes it back.
+            let other = {
+                let llsc = llsc.clone();
+                thread::spawn(move || {
+                    increment(&llsc);
+                    loop {
+                        let link = llsc.load_linked();
+                        if llsc.store_conditional(link, link.value() - 1) {
+                            break;
+                        }
+                    }
+                })
+            };
+            other.join().unwrap();
+
+            assert_eq!(llsc.load(), 0);
+            assert!(!llsc.store_conditional(link, 1));
+        },
+        NUM_ITERATIONS,
+    );
+}
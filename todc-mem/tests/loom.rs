@@ -0,0 +1,94 @@
+//! Tests that check the memory orderings of objects with loom.
+//!
+//! These tests only run when compiled with `RUSTFLAGS="--cfg loom"`. See the
+//! [`sync`](todc_mem::sync) module for details.
+#![cfg(loom)]
+use loom::thread;
+use todc_mem::queue::ring;
+
+mod ring_spsc {
+    use super::*;
+
+    /// Asserts that values are moved from the producer to the consumer
+    /// intact and in order, even as slots are reused.
+    #[test]
+    fn transfers_values_in_order() {
+        loom::model(|| {
+            let (mut producer, mut consumer) = ring::spsc::<String, 1>();
+            let handle = thread::spawn(move || {
+                for value in ["first", "second"] {
+                    let mut value = value.to_string();
+                    while let Err(rejected) = producer.enqueue(value) {
+                        value = rejected;
+                        thread::yield_now();
+                    }
+                }
+            });
+            let mut values = Vec::new();
+            while values.len() < 2 {
+                match consumer.dequeue() {
+                    Some(value) => values.push(value),
+                    None => thread::yield_now(),
+                }
+            }
+            handle.join().unwrap();
+            assert_eq!(values, vec!["first", "second"]);
+        });
+    }
+}
+
+mod ring_mpsc {
+    use super::*;
+
+    /// Asserts that every value enqueued concurrently by different producers
+    /// is dequeued exactly once.
+    #[test]
+    fn dequeues_every_value_once() {
+        loom::model(|| {
+            let (producers, mut consumer) = ring::mpsc::<String, 2, 1>();
+            let handles: Vec<_> = producers
+                .into_iter()
+                .map(|mut producer| {
+                    thread::spawn(move || producer.enqueue(producer.id().to_string()).unwrap())
+                })
+                .collect();
+            let mut values = Vec::new();
+            while values.len() < 2 {
+                match consumer.dequeue() {
+                    Some(value) => values.push(value),
+                    None => thread::yield_now(),
+                }
+            }
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            values.sort();
+            assert_eq!(values, vec!["0", "1"]);
+        });
+    }
+
+    /// Asserts that a value whose enqueue completed before another began is
+    /// dequeued first, even though the two were enqueued by different
+    /// producers on different threads.
+    #[test]
+    fn dequeues_values_in_order_of_enqueues() {
+        loom::model(|| {
+            let (mut producers, mut consumer) = ring::mpsc::<String, 2, 1>();
+            let mut second = producers.pop().unwrap();
+            let mut first = producers.pop().unwrap();
+            let handle = thread::spawn(move || {
+                first.enqueue("first".to_string()).unwrap();
+                // The second value is only enqueued once the first has been.
+                thread::spawn(move || second.enqueue("second".to_string()).unwrap())
+            });
+            let value = loop {
+                match consumer.dequeue() {
+                    Some(value) => break value,
+                    None => thread::yield_now(),
+                }
+            };
+            handle.join().unwrap().join().unwrap();
+            assert_eq!(value, "first");
+        });
+    }
+}
@@ -0,0 +1,9 @@
+#![allow(dead_code, unused_imports)]
+mod mutex {
+    mod bakery;
+    mod common;
+    mod filter;
+    mod peterson;
+    mod rw;
+    mod ticket;
+}
@@ -0,0 +1,27 @@
+use super::common::{assert_mutual_exclusion, NUM_ITERATIONS, NUM_THREADS};
+use todc_mem::mutex::{AtomicBakeryLock, MutexBakeryLock};
+
+type AtomicLock = AtomicBakeryLock<NUM_THREADS>;
+type MutexLock = MutexBakeryLock<NUM_THREADS>;
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn atomic_lock_guarantees_mutual_exclusion() {
+    shuttle::check_random(
+        || {
+            assert_mutual_exclusion::<NUM_THREADS, AtomicLock>();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mutex_lock_guarantees_mutual_exclusion() {
+    shuttle::check_random(
+        || {
+            assert_mutual_exclusion::<NUM_THREADS, MutexLock>();
+        },
+        NUM_ITERATIONS,
+    );
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use shuttle::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use shuttle::thread;
+use todc_mem::mutex::{Lock, RwLock};
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+pub const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+pub const NUM_ITERATIONS: usize = 250;
+
+pub const NUM_OPERATIONS: usize = 5;
+pub const NUM_THREADS: usize = 4;
+
+/// Asserts that no two threads are ever inside the critical section
+/// protected by the lock at the same time.
+///
+/// # Panics
+///
+/// Panics if two threads are observed inside the critical section
+/// concurrently.
+pub fn assert_mutual_exclusion<const N: usize, L: Lock<N> + 'static + Send + Sync>() {
+    let lock: Arc<L> = Arc::new(L::new());
+    let occupied = Arc::new(AtomicBool::new(false));
+
+    let mut handles = Vec::new();
+    for i in 0..N {
+        let lock = lock.clone();
+        let occupied = occupied.clone();
+        handles.push(thread::spawn(move || {
+            for _ in 0..NUM_OPERATIONS {
+                lock.lock(i);
+                assert!(
+                    !occupied.swap(true, Ordering::SeqCst),
+                    "Process {i} entered an occupied critical section"
+                );
+                thread::yield_now();
+                occupied.store(false, Ordering::SeqCst);
+                lock.unlock(i);
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+/// Asserts that a writer is never inside the critical section protected by
+/// the lock at the same time as any other reader or writer.
+///
+/// Even-numbered threads acquire the lock as readers, and odd-numbered
+/// threads acquire it as writers.
+///
+/// # Panics
+///
+/// Panics if a writer is observed inside the critical section concurrently
+/// with another thread.
+pub fn assert_readers_writer_exclusion<const N: usize, L: RwLock<N> + 'static + Send + Sync>() {
+    let lock: Arc<L> = Arc::new(L::new());
+    let readers = Arc::new(AtomicUsize::new(0));
+    let writing = Arc::new(AtomicBool::new(false));
+
+    let mut handles = Vec::new();
+    for i in 0..N {
+        let lock = lock.clone();
+        let readers = readers.clone();
+        let writing = writing.clone();
+        handles.push(thread::spawn(move || {
+            for _ in 0..NUM_OPERATIONS {
+                if i % 2 == 0 {
+                    lock.read_lock(i);
+                    readers.fetch_add(1, Ordering::SeqCst);
+                    assert!(
+                        !writing.load(Ordering::SeqCst),
+                        "Reader {i} entered a critical section occupied by a writer"
+                    );
+                    thread::yield_now();
+                    readers.fetch_sub(1, Ordering::SeqCst);
+                    lock.read_unlock(i);
+                } else {
+                    lock.write_lock(i);
+                    assert!(
+                        !writing.swap(true, Ordering::SeqCst),
+                        "Writer {i} entered a critical section occupied by a writer"
+                    );
+                    assert_eq!(
+                        readers.load(Ordering::SeqCst),
+                        0,
+                        "Writer {i} entered a critical section occupied by a reader"
+                    );
+                    thread::yield_now();
+                    writing.store(false, Ordering::SeqCst);
+                    lock.write_unlock(i);
+                }
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+/// Asserts that a writer eventually acquires the lock, even while every
+/// other thread repeatedly acquires it as a reader until the writer is done.
+pub fn assert_writer_progress<const N: usize, L: RwLock<N> + 'static + Send + Sync>() {
+    let lock: Arc<L> = Arc::new(L::new());
+    let written = Arc::new(AtomicBool::new(false));
+
+    let mut handles = Vec::new();
+    for i in 1..N {
+        let lock = lock.clone();
+        let written = written.clone();
+        handles.push(thread::spawn(move || {
+            while !written.load(Ordering::SeqCst) {
+                lock.read_lock(i);
+                thread::yield_now();
+                lock.read_unlock(i);
+            }
+        }));
+    }
+
+    lock.write_lock(0);
+    written.store(true, Ordering::SeqCst);
+    lock.write_unlock(0);
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
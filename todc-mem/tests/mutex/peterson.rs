@@ -0,0 +1,27 @@
+use super::common::{assert_mutual_exclusion, NUM_ITERATIONS};
+use todc_mem::mutex::{AtomicPetersonLock, MutexPetersonLock};
+
+type AtomicLock = AtomicPetersonLock;
+type MutexLock = MutexPetersonLock;
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn atomic_lock_guarantees_mutual_exclusion() {
+    shuttle::check_random(
+        || {
+            assert_mutual_exclusion::<2, AtomicLock>();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mutex_lock_guarantees_mutual_exclusion() {
+    shuttle::check_random(
+        || {
+            assert_mutual_exclusion::<2, MutexLock>();
+        },
+        NUM_ITERATIONS,
+    );
+}
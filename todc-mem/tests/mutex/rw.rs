@@ -0,0 +1,40 @@
+use super::common::{
+    assert_readers_writer_exclusion, assert_writer_progress, NUM_ITERATIONS, NUM_THREADS,
+};
+use todc_mem::mutex::{AtomicRwLock, MutexRwLock};
+
+type AtomicLock = AtomicRwLock<NUM_THREADS>;
+type MutexLock = MutexRwLock<NUM_THREADS>;
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn atomic_lock_guarantees_readers_writer_exclusion() {
+    shuttle::check_random(
+        || {
+            assert_readers_writer_exclusion::<NUM_THREADS, AtomicLock>();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mutex_lock_guarantees_readers_writer_exclusion() {
+    shuttle::check_random(
+        || {
+            assert_readers_writer_exclusion::<NUM_THREADS, MutexLock>();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn atomic_lock_does_not_starve_writers() {
+    shuttle::check_random(
+        || {
+            assert_writer_progress::<NUM_THREADS, AtomicLock>();
+        },
+        NUM_ITERATIONS,
+    );
+}
@@ -0,0 +1,29 @@
+use super::common::{assert_mutual_exclusion, NUM_ITERATIONS, NUM_THREADS};
+use todc_mem::fetch_and_add::BakeryFetchAndAdd;
+use todc_mem::mutex::{NativeTicketLock, TicketLock};
+use todc_mem::register::MutexRegister;
+
+type NativeLock = NativeTicketLock<NUM_THREADS>;
+type BakeryLock = TicketLock<BakeryFetchAndAdd<NUM_THREADS>, MutexRegister<u64>, NUM_THREADS>;
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn native_lock_guarantees_mutual_exclusion() {
+    shuttle::check_random(
+        || {
+            assert_mutual_exclusion::<NUM_THREADS, NativeLock>();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn bakery_lock_guarantees_mutual_exclusion() {
+    shuttle::check_random(
+        || {
+            assert_mutual_exclusion::<NUM_THREADS, BakeryLock>();
+        },
+        NUM_ITERATIONS,
+    );
+}
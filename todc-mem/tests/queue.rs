@@ -0,0 +1,215 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::{Arc, Mutex};
+
+use shuttle::thread;
+use todc_mem::queue::{ring, MSQueue, Queue};
+use todc_utils::specifications::queue::{
+    QueueOperation::{self, Dequeue, Enqueue},
+    QueueSpecification,
+};
+use todc_utils::{Action, History, WGLChecker};
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_OPERATIONS: usize = 4;
+const NUM_PREEMPTIONS: usize = 3;
+const NUM_THREADS: usize = 3;
+
+/// Asserts that a history of concurrent enqueues and dequeues is
+/// linearizable.
+///
+/// Each process alternates between enqueuing a unique value and dequeuing.
+///
+/// # Panics
+///
+/// Panics if the history of operations is not linearizable.
+fn assert_queue_is_linearizable<Q>()
+where
+    Q: Queue<NUM_THREADS, Value = usize> + 'static + Send + Sync,
+{
+    let queue: Arc<Q> = Arc::new(Q::new());
+    let actions = Arc::new(Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = (0..NUM_THREADS)
+        .map(|i| {
+            let queue = queue.clone();
+            let actions = actions.clone();
+            thread::spawn(move || {
+                for op in 0..NUM_OPERATIONS {
+                    if op % 2 == 0 {
+                        let value = i * NUM_OPERATIONS + op;
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Call(Enqueue(value))));
+                        queue.enqueue(i, value);
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Response(Enqueue(value))));
+                    } else {
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Call(Dequeue(None))));
+                        let value = queue.dequeue(i);
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Response(Dequeue(Some(value)))));
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let actions = actions.lock().unwrap().clone();
+    let history = History::from_actions(actions);
+    assert!(
+        WGLChecker::<QueueSpecification<usize>>::is_linearizable(history.clone()),
+        "History is not linearizable:\n{}",
+        history.render_timeline()
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn ms_queue_is_linearizable() {
+    shuttle::check_pct(
+        assert_queue_is_linearizable::<MSQueue<usize, NUM_THREADS>>,
+        NUM_ITERATIONS,
+        NUM_PREEMPTIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn ms_queue_is_linearizable_under_random_schedules() {
+    shuttle::check_random(
+        assert_queue_is_linearizable::<MSQueue<usize, NUM_THREADS>>,
+        NUM_ITERATIONS,
+    );
+}
+
+/// Actions recorded by concurrent processes.
+type Actions = Mutex<Vec<(usize, Action<QueueOperation<usize>>)>>;
+
+/// Records an enqueue of a value by process `i`, which must succeed.
+fn record_enqueue(
+    actions: &Actions,
+    i: usize,
+    value: usize,
+    enqueue: impl FnOnce(usize) -> Result<(), usize>,
+) {
+    actions
+        .lock()
+        .unwrap()
+        .push((i, Action::Call(Enqueue(value))));
+    enqueue(value).expect("capacity is large enough for every value");
+    actions
+        .lock()
+        .unwrap()
+        .push((i, Action::Response(Enqueue(value))));
+}
+
+/// Records a dequeue by process `i`.
+fn record_dequeue(actions: &Actions, i: usize, dequeue: impl FnOnce() -> Option<usize>) {
+    actions
+        .lock()
+        .unwrap()
+        .push((i, Action::Call(Dequeue(None))));
+    let value = dequeue();
+    actions
+        .lock()
+        .unwrap()
+        .push((i, Action::Response(Dequeue(Some(value)))));
+}
+
+/// Asserts that a history of recorded actions is linearizable.
+fn assert_linearizable(actions: &Actions) {
+    let actions = actions.lock().unwrap().clone();
+    let history = History::from_actions(actions);
+    assert!(
+        WGLChecker::<QueueSpecification<usize>>::is_linearizable(history.clone()),
+        "History is not linearizable:\n{}",
+        history.render_timeline()
+    );
+}
+
+/// Asserts that a history of concurrent enqueues and dequeues on a
+/// single-producer single-consumer ring buffer is linearizable.
+fn assert_spsc_is_linearizable() {
+    let (mut producer, mut consumer) = ring::spsc::<usize, NUM_OPERATIONS>();
+    let actions = Arc::new(Mutex::new(Vec::new()));
+
+    let handle = {
+        let actions = actions.clone();
+        thread::spawn(move || {
+            for value in 0..NUM_OPERATIONS {
+                record_enqueue(&actions, 0, value, |value| producer.enqueue(value));
+            }
+        })
+    };
+    for _ in 0..NUM_OPERATIONS {
+        record_dequeue(&actions, 1, || consumer.dequeue());
+    }
+    handle.join().unwrap();
+
+    assert_linearizable(&actions);
+}
+
+/// Asserts that a history of concurrent enqueues and dequeues on a
+/// multi-producer single-consumer ring buffer is linearizable.
+fn assert_mpsc_is_linearizable() {
+    const NUM_PRODUCERS: usize = NUM_THREADS - 1;
+    let (producers, mut consumer) = ring::mpsc::<usize, NUM_PRODUCERS, NUM_OPERATIONS>();
+    let actions = Arc::new(Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = producers
+        .into_iter()
+        .map(|mut producer| {
+            let actions = actions.clone();
+            thread::spawn(move || {
+                let i = producer.id();
+                for op in 0..NUM_OPERATIONS / 2 {
+                    let value = i * NUM_OPERATIONS + op;
+                    record_enqueue(&actions, i, value, |value| producer.enqueue(value));
+                }
+            })
+        })
+        .collect();
+    for _ in 0..NUM_OPERATIONS {
+        record_dequeue(&actions, NUM_PRODUCERS, || consumer.dequeue());
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_linearizable(&actions);
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn spsc_ring_is_linearizable() {
+    shuttle::check_pct(assert_spsc_is_linearizable, NUM_ITERATIONS, NUM_PREEMPTIONS);
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mpsc_ring_is_linearizable() {
+    shuttle::check_pct(assert_mpsc_is_linearizable, NUM_ITERATIONS, NUM_PREEMPTIONS);
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mpsc_ring_is_linearizable_under_random_schedules() {
+    shuttle::check_random(assert_mpsc_is_linearizable, NUM_ITERATIONS);
+}
@@ -0,0 +1,8 @@
+#![allow(dead_code, unused_imports)]
+mod register {
+    mod common;
+    mod crash_stop;
+    mod faults;
+    mod mwmr;
+    mod transformations;
+}
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use shuttle::sync::Mutex;
+use todc_utils::linearizability::durable::{complete, Event};
+use todc_utils::specifications::register::{RegisterOperation, RegisterSpecification};
+use todc_utils::{Action, WGLChecker};
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+pub const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+pub const NUM_ITERATIONS: usize = 250;
+
+pub const NUM_OPERATIONS: usize = 10;
+pub const NUM_THREADS: usize = 3;
+
+type Operation = RegisterOperation<u32>;
+type Log = Vec<(usize, Action<Operation>)>;
+
+/// The point within an operation at which a process stops.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrashPoint {
+    /// The process stops after calling the operation, but before taking any
+    /// steps, so the operation never takes effect.
+    BeforeEffect,
+    /// The process stops after performing the operation, but before it
+    /// returns, so the operation takes effect without a response.
+    AfterEffect,
+}
+
+/// A crash that is scheduled to stop a process during one of its operations.
+#[derive(Clone, Copy, Debug)]
+struct Crash {
+    /// The number of operations that the process completes before crashing.
+    after: usize,
+    point: CrashPoint,
+}
+
+/// A log of the actions performed by each process.
+///
+/// Processes can be scheduled to crash during one of their operations with
+/// [`with_crash`](Recorder::with_crash). The operation that a process crashes
+/// during is recorded without a response, and the process performs no
+/// further operations, as in the crash-stop failure model of message-passing
+/// systems.
+#[derive(Clone, Default)]
+pub struct Recorder {
+    actions: Arc<Mutex<Log>>,
+    // The crash scheduled for each process, if any.
+    crashes: HashMap<usize, Crash>,
+    // The number of operations each process has called.
+    calls: Arc<Mutex<HashMap<usize, usize>>>,
+}
+
+impl Recorder {
+    /// Schedules a process to crash at the specified point of its operation
+    /// with index `after`, counting from zero.
+    pub fn with_crash(mut self, process: usize, after: usize, point: CrashPoint) -> Self {
+        self.crashes.insert(process, Crash { after, point });
+        self
+    }
+
+    /// Records a read, and returns whether the process is still running.
+    pub fn read(&self, process: usize, read: impl FnOnce() -> u32) -> bool {
+        let crash = self.call(process, RegisterOperation::Read(None));
+        if crash == Some(CrashPoint::BeforeEffect) {
+            return false;
+        }
+        let value = read();
+        if crash.is_some() {
+            return false;
+        }
+        self.record(
+            process,
+            Action::Response(RegisterOperation::Read(Some(value))),
+        );
+        true
+    }
+
+    /// Records a write, and returns whether the process is still running.
+    pub fn write(&self, process: usize, value: u32, write: impl FnOnce(u32)) -> bool {
+        let crash = self.call(process, RegisterOperation::Write(value));
+        if crash == Some(CrashPoint::BeforeEffect) {
+            return false;
+        }
+        write(value);
+        if crash.is_some() {
+            return false;
+        }
+        self.record(process, Action::Response(RegisterOperation::Write(value)));
+        true
+    }
+
+    /// Returns whether the process has crashed.
+    pub fn is_crashed(&self, process: usize) -> bool {
+        let calls = self.calls.lock().unwrap();
+        match (self.crashes.get(&process), calls.get(&process)) {
+            (Some(crash), Some(calls)) => *calls > crash.after,
+            _ => false,
+        }
+    }
+
+    /// Records the call of an operation, and returns the point at which the
+    /// process crashes during it, if it does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the process has already crashed.
+    fn call(&self, process: usize, operation: Operation) -> Option<CrashPoint> {
+        assert!(!self.is_crashed(process), "Process {process} has crashed");
+        self.record(process, Action::Call(operation));
+        let mut calls = self.calls.lock().unwrap();
+        let index = calls.entry(process).or_default();
+        *index += 1;
+        self.crashes
+            .get(&process)
+            .filter(|crash| crash.after == *index - 1)
+            .map(|crash| crash.point)
+    }
+
+    fn record(&self, process: usize, action: Action<Operation>) {
+        self.actions.lock().unwrap().push((process, action));
+    }
+
+    /// Asserts that the recorded actions form a linearizable history, in
+    /// which the operations of crashed processes may or may not have taken
+    /// effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the history is not linearizable.
+    pub fn assert_linearizable(&self) {
+        let actions = self.actions.lock().unwrap().clone();
+        assert!(
+            WGLChecker::<RegisterSpecification<u32>>::is_linearizable_with_pending(actions.clone()),
+            "History is not linearizable:\n{}",
+            complete(
+                actions
+                    .into_iter()
+                    .map(|(process, action)| Event::Action(process, action))
+                    .collect()
+            )
+            .render_timeline()
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use shuttle::rand::{thread_rng, Rng};
+use shuttle::thread;
+use todc_mem::register::faults::DelayedRegister;
+use todc_mem::register::transformations::Labeled;
+use todc_mem::register::{MWMRRegister, MutexRegister, Register};
+
+use crate::register::common::{CrashPoint, Recorder, NUM_ITERATIONS, NUM_OPERATIONS, NUM_THREADS};
+
+type Delayed = DelayedRegister<MutexRegister<Labeled<u32>>>;
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mwmr_register_is_linearizable_when_a_process_crashes() {
+    shuttle::check_random(
+        || {
+            let register: Arc<MWMRRegister<u32, NUM_THREADS, Delayed>> =
+                Arc::new(MWMRRegister::new());
+            let mut rng = thread_rng();
+            let point = if rng.gen_bool(0.5) {
+                CrashPoint::BeforeEffect
+            } else {
+                CrashPoint::AfterEffect
+            };
+            let recorder = Recorder::default().with_crash(
+                rng.gen_range(0..NUM_THREADS),
+                rng.gen_range(0..NUM_OPERATIONS),
+                point,
+            );
+
+            let mut handles = Vec::new();
+            for i in 0..NUM_THREADS {
+                let (register, recorder) = (register.clone(), recorder.clone());
+                handles.push(thread::spawn(move || {
+                    let mut rng = thread_rng();
+                    for _ in 0..NUM_OPERATIONS {
+                        let running = if rng.gen_bool(0.5) {
+                            let value = rng.gen();
+                            recorder.write(i, value, |value| register.write(i, value))
+                        } else {
+                            recorder.read(i, || register.read(i))
+                        };
+                        if !running {
+                            break;
+                        }
+                    }
+                }));
+            }
+
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            recorder.assert_linearizable();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn crashed_write_may_take_effect_without_response() {
+    shuttle::check_random(
+        || {
+            let register: Arc<MutexRegister<u32>> = Arc::new(MutexRegister::new());
+            let recorder = Recorder::default().with_crash(0, 0, CrashPoint::AfterEffect);
+
+            let (writer, crashed) = (register.clone(), recorder.clone());
+            let handle = thread::spawn(move || {
+                assert!(!crashed.write(0, 123, |value| writer.write(value)));
+                assert!(crashed.is_crashed(0));
+            });
+            handle.join().unwrap();
+
+            assert!(recorder.read(1, || register.read()));
+            assert_eq!(register.read(), 123);
+            recorder.assert_linearizable();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn crashed_write_before_effect_leaves_register_unchanged() {
+    shuttle::check_random(
+        || {
+            let register: Arc<MutexRegister<u32>> = Arc::new(MutexRegister::new());
+            let recorder = Recorder::default().with_crash(0, 1, CrashPoint::BeforeEffect);
+
+            assert!(recorder.write(0, 1, |value| register.write(value)));
+            assert!(!recorder.write(0, 2, |value| register.write(value)));
+            assert!(recorder.read(1, || register.read()));
+            assert_eq!(register.read(), 1);
+            recorder.assert_linearizable();
+        },
+        NUM_ITERATIONS,
+    );
+}
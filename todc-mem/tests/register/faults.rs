@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use shuttle::rand::{thread_rng, Rng};
+use shuttle::sync::atomic::{AtomicBool, Ordering};
+use shuttle::thread;
+use todc_mem::register::faults::{DelayedRegister, FaultyRegister};
+use todc_mem::register::transformations::Labeled;
+use todc_mem::register::{MWMRRegister, MutexRegister, Register};
+
+use crate::register::common::{Recorder, NUM_ITERATIONS, NUM_OPERATIONS, NUM_THREADS};
+
+type Delayed = DelayedRegister<MutexRegister<Labeled<u32>>>;
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mwmr_register_of_delayed_registers_is_linearizable() {
+    shuttle::check_random(
+        || {
+            let register: Arc<MWMRRegister<u32, NUM_THREADS, Delayed>> =
+                Arc::new(MWMRRegister::new());
+            let recorder = Recorder::default();
+
+            let mut handles = Vec::new();
+            for i in 0..NUM_THREADS {
+                let (register, recorder) = (register.clone(), recorder.clone());
+                handles.push(thread::spawn(move || {
+                    let mut rng = thread_rng();
+                    for _ in 0..NUM_OPERATIONS {
+                        if rng.gen_bool(0.5) {
+                            let value = rng.gen();
+                            recorder.write(i, value, |value| register.write(i, value));
+                        } else {
+                            recorder.read(i, || register.read(i));
+                        }
+                    }
+                }));
+            }
+
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            recorder.assert_linearizable();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn frozen_write_takes_effect_once_thawed() {
+    shuttle::check_random(
+        || {
+            let register: Arc<FaultyRegister<MutexRegister<u32>>> = Arc::new(FaultyRegister::new());
+            let done = Arc::new(AtomicBool::new(false));
+            register.freeze();
+
+            let (writer, finished) = (register.clone(), done.clone());
+            let handle = thread::spawn(move || {
+                writer.write(123);
+                finished.store(true, Ordering::SeqCst);
+            });
+
+            // The write cannot finish while the register is frozen.
+            thread::yield_now();
+            assert!(!done.load(Ordering::SeqCst));
+            register.thaw();
+            handle.join().unwrap();
+            assert_eq!(register.read(), 123);
+        },
+        NUM_ITERATIONS,
+    );
+}
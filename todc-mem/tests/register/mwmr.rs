@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use shuttle::rand::{thread_rng, Rng};
+use shuttle::thread;
+use todc_mem::register::MWMRRegister;
+
+use crate::register::common::{Recorder, NUM_ITERATIONS, NUM_OPERATIONS, NUM_THREADS};
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mwmr_register_is_linearizable() {
+    shuttle::check_random(
+        || {
+            let register: Arc<MWMRRegister<u32, NUM_THREADS>> = Arc::new(MWMRRegister::new());
+            let recorder = Recorder::default();
+
+            let mut handles = Vec::new();
+            for i in 0..NUM_THREADS {
+                let (register, recorder) = (register.clone(), recorder.clone());
+                handles.push(thread::spawn(move || {
+                    let mut rng = thread_rng();
+                    for _ in 0..NUM_OPERATIONS {
+                        if rng.gen_bool(0.5) {
+                            let value = rng.gen();
+                            recorder.write(i, value, |value| register.write(i, value));
+                        } else {
+                            recorder.read(i, || register.read(i));
+                        }
+                    }
+                }));
+            }
+
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            recorder.assert_linearizable();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mwmr_register_with_two_processes_is_linearizable() {
+    shuttle::check_pct(
+        || {
+            let register: Arc<MWMRRegister<u32, 2>> = Arc::new(MWMRRegister::new());
+            let recorder = Recorder::default();
+
+            let handles: Vec<_> = (0..2)
+                .map(|i| {
+                    let (register, recorder) = (register.clone(), recorder.clone());
+                    thread::spawn(move || {
+                        for j in 0..NUM_OPERATIONS as u32 {
+                            let value = 100 * i as u32 + j;
+                            recorder.write(i, value, |value| register.write(i, value));
+                            recorder.read(i, || register.read(i));
+                        }
+                    })
+                })
+                .collect();
+
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            recorder.assert_linearizable();
+        },
+        NUM_ITERATIONS,
+        3,
+    );
+}
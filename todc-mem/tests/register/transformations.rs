@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use shuttle::rand::{thread_rng, Rng};
+use shuttle::thread;
+use todc_mem::register::transformations::{
+    AtomicMRMWRegister, AtomicMRSWRegister, AtomicSRSWRegister, Labeled, RegularRegister, Stamped,
+};
+use todc_mem::register::Register;
+
+use crate::register::common::{Recorder, NUM_ITERATIONS, NUM_OPERATIONS, NUM_THREADS};
+
+type Srsw<T> = AtomicSRSWRegister<RegularRegister<Stamped<T>>, T>;
+type Mrsw = AtomicMRSWRegister<Srsw<Stamped<u32>>, u32, NUM_THREADS>;
+type Mrmw = AtomicMRMWRegister<Srsw<Stamped<Labeled<u32>>>, u32, NUM_THREADS>;
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn atomic_srsw_register_is_linearizable() {
+    shuttle::check_random(
+        || {
+            let register: Arc<Srsw<u32>> = Arc::new(Srsw::new());
+            let recorder = Recorder::default();
+
+            let writer = {
+                let (register, recorder) = (register.clone(), recorder.clone());
+                thread::spawn(move || {
+                    for _ in 0..NUM_OPERATIONS {
+                        let value = thread_rng().gen();
+                        recorder.write(0, value, |value| register.write(value));
+                    }
+                })
+            };
+            let reader = {
+                let (register, recorder) = (register.clone(), recorder.clone());
+                thread::spawn(move || {
+                    for _ in 0..NUM_OPERATIONS {
+                        recorder.read(1, || register.read());
+                    }
+                })
+            };
+            writer.join().unwrap();
+            reader.join().unwrap();
+            recorder.assert_linearizable();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn atomic_mrsw_register_is_linearizable() {
+    shuttle::check_random(
+        || {
+            let register: Arc<Mrsw> = Arc::new(Mrsw::new());
+            let recorder = Recorder::default();
+
+            let mut handles = Vec::new();
+            for i in 0..NUM_THREADS {
+                let (register, recorder) = (register.clone(), recorder.clone());
+                handles.push(thread::spawn(move || {
+                    for _ in 0..NUM_OPERATIONS {
+                        recorder.read(i, || register.read(i));
+                    }
+                }));
+            }
+            let (writer, recorder_clone) = (register.clone(), recorder.clone());
+            handles.push(thread::spawn(move || {
+                for _ in 0..NUM_OPERATIONS {
+                    let value = thread_rng().gen();
+                    recorder_clone.write(NUM_THREADS, value, |value| writer.write(value));
+                }
+            }));
+
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            recorder.assert_linearizable();
+        },
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn atomic_mrmw_register_is_linearizable() {
+    shuttle::check_random(
+        || {
+            let register: Arc<Mrmw> = Arc::new(Mrmw::new());
+            let recorder = Recorder::default();
+
+            let mut handles = Vec::new();
+            for i in 0..NUM_THREADS {
+                let (register, recorder) = (register.clone(), recorder.clone());
+                handles.push(thread::spawn(move || {
+                    let mut rng = thread_rng();
+                    for _ in 0..NUM_OPERATIONS {
+                        if rng.gen_bool(0.5) {
+                            let value = rng.gen();
+                            recorder.write(i, value, |value| register.write(i, value));
+                        } else {
+                            recorder.read(i, || register.read(i));
+                        }
+                    }
+                }));
+            }
+
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            recorder.assert_linearizable();
+        },
+        NUM_ITERATIONS,
+    );
+}
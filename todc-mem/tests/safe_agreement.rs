@@ -0,0 +1,121 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::Arc;
+
+use shuttle::sync::Mutex;
+use shuttle::thread;
+use todc_mem::safe_agreement::MutexSafeAgreement;
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_PREEMPTIONS: usize = 3;
+const NUM_THREADS: usize = 4;
+
+type Agreement = MutexSafeAgreement<usize, NUM_THREADS>;
+
+/// Asserts that every resolution is the same proposed value.
+///
+/// # Panics
+///
+/// Panics if two resolutions differ, or if a resolution is not one of the
+/// proposed values.
+fn assert_agreement(resolutions: &[usize], proposed: &[usize]) {
+    for resolution in resolutions {
+        assert!(proposed.contains(resolution));
+        assert_eq!(*resolution, resolutions[0]);
+    }
+}
+
+/// Proposes a value from every thread but the last, while each thread tries
+/// to resolve the object, and asserts that all threads that resolve it agree
+/// on the same value, including the last thread after it makes a late
+/// proposal of its own.
+fn assert_resolutions_agree() {
+    let agreement: Arc<Agreement> = Arc::new(Agreement::new());
+    let resolutions = Arc::new(Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = (0..NUM_THREADS - 1)
+        .map(|i| {
+            let agreement = agreement.clone();
+            let resolutions = resolutions.clone();
+            thread::spawn(move || {
+                agreement.propose(i, i);
+                if let Some(value) = agreement.resolve(i) {
+                    resolutions.lock().unwrap().push(value);
+                }
+            })
+        })
+        .collect();
+
+    // The last thread only tries to resolve the object while the others are
+    // proposing.
+    let last = NUM_THREADS - 1;
+    if let Some(value) = agreement.resolve(last) {
+        resolutions.lock().unwrap().push(value);
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // Once every proposal has finished, the object must resolve.
+    let resolved = agreement.resolve(last).expect("Object should resolve");
+    resolutions.lock().unwrap().push(resolved);
+
+    // A proposal made after the object has resolved does not change it.
+    agreement.propose(last, last);
+    resolutions
+        .lock()
+        .unwrap()
+        .push(agreement.resolve(last).unwrap());
+
+    let proposed: Vec<usize> = (0..NUM_THREADS - 1).collect();
+    assert_agreement(&resolutions.lock().unwrap(), &proposed);
+}
+
+/// Proposes a value from every thread, after which each thread retries
+/// resolving the object until it succeeds, and asserts that every thread
+/// resolves it to the same value.
+fn assert_every_thread_resolves() {
+    let agreement: Arc<Agreement> = Arc::new(Agreement::new());
+
+    let handles: Vec<_> = (0..NUM_THREADS)
+        .map(|i| {
+            let agreement = agreement.clone();
+            thread::spawn(move || {
+                agreement.propose(i, i);
+                // The object resolves once no other thread is within the
+                // unsafe window of its proposal.
+                loop {
+                    match agreement.resolve(i) {
+                        Some(value) => return value,
+                        None => thread::yield_now(),
+                    }
+                }
+            })
+        })
+        .collect();
+
+    let resolutions: Vec<usize> = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+
+    let proposed: Vec<usize> = (0..NUM_THREADS).collect();
+    assert_agreement(&resolutions, &proposed);
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn resolutions_agree() {
+    shuttle::check_pct(assert_resolutions_agree, NUM_ITERATIONS, NUM_PREEMPTIONS);
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn every_thread_resolves_once_proposals_finish() {
+    shuttle::check_random(assert_every_thread_resolves, NUM_ITERATIONS);
+}
@@ -1,6 +1,9 @@
 #![allow(dead_code, unused_imports)]
 mod snapshot {
     mod aad_plus_93;
+    mod adaptive;
     mod ar_98;
+    mod cached;
     mod common;
+    mod durable;
 }
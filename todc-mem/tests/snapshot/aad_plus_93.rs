@@ -1,13 +1,17 @@
 use super::common::{
-    assert_random_operations_are_linearizable, NUM_ITERATIONS, NUM_PREEMPTIONS, NUM_THREADS,
+    assert_random_operations_are_linearizable, FixedSnapshot, NUM_ITERATIONS, NUM_PREEMPTIONS,
+    NUM_THREADS,
 };
 
 mod unbounded {
     use super::*;
-    use todc_mem::snapshot::{UnboundedAtomicSnapshot, UnboundedMutexSnapshot};
+    use todc_mem::snapshot::{
+        UnboundedAtomic128Snapshot, UnboundedAtomicSnapshot, UnboundedMutexSnapshot,
+    };
 
     type MutexSnapshot = UnboundedMutexSnapshot<u32, NUM_THREADS>;
     type AtomicSnapshot = UnboundedAtomicSnapshot<NUM_THREADS>;
+    type Atomic128Snapshot = UnboundedAtomic128Snapshot<u16, NUM_THREADS>;
 
     #[cfg(feature = "shuttle")]
     #[test]
@@ -32,14 +36,105 @@ mod unbounded {
             NUM_PREEMPTIONS,
         );
     }
+
+    #[cfg(feature = "shuttle")]
+    #[test]
+    fn atomic_128_snapshot_is_linearizable() {
+        shuttle::check_pct(
+            || {
+                assert_random_operations_are_linearizable::<NUM_THREADS, Atomic128Snapshot>();
+            },
+            NUM_ITERATIONS,
+            NUM_PREEMPTIONS,
+        );
+    }
 }
 
 mod bounded {
     use super::*;
-    use todc_mem::snapshot::{BoundedAtomicSnapshot, BoundedMutexSnapshot};
+    use todc_mem::snapshot::{
+        BoundedAtomic128Snapshot, BoundedAtomicSnapshot, BoundedMutexSnapshot,
+    };
 
     type MutexSnapshot = BoundedMutexSnapshot<u32, NUM_THREADS>;
     type AtomicSnapshot = BoundedAtomicSnapshot<NUM_THREADS>;
+    type Atomic128Snapshot = BoundedAtomic128Snapshot<u16, NUM_THREADS>;
+
+    #[cfg(feature = "shuttle")]
+    #[test]
+    fn mutex_snapshot_is_linearizable() {
+        shuttle::check_pct(
+            || {
+                assert_random_operations_are_linearizable::<NUM_THREADS, MutexSnapshot>();
+            },
+            NUM_ITERATIONS,
+            NUM_PREEMPTIONS,
+        );
+    }
+
+    #[cfg(feature = "shuttle")]
+    #[test]
+    fn atomic_snapshot_is_linearizable() {
+        shuttle::check_pct(
+            || {
+                assert_random_operations_are_linearizable::<NUM_THREADS, AtomicSnapshot>();
+            },
+            NUM_ITERATIONS,
+            NUM_PREEMPTIONS,
+        );
+    }
+
+    #[cfg(feature = "shuttle")]
+    #[test]
+    fn atomic_128_snapshot_is_linearizable() {
+        shuttle::check_pct(
+            || {
+                assert_random_operations_are_linearizable::<NUM_THREADS, Atomic128Snapshot>();
+            },
+            NUM_ITERATIONS,
+            NUM_PREEMPTIONS,
+        );
+    }
+}
+
+mod dyn_unbounded {
+    use super::*;
+    use todc_mem::snapshot::{DynUnboundedAtomicSnapshot, DynUnboundedMutexSnapshot};
+
+    type MutexSnapshot = FixedSnapshot<DynUnboundedMutexSnapshot<u32>, NUM_THREADS>;
+    type AtomicSnapshot = FixedSnapshot<DynUnboundedAtomicSnapshot, NUM_THREADS>;
+
+    #[cfg(feature = "shuttle")]
+    #[test]
+    fn mutex_snapshot_is_linearizable() {
+        shuttle::check_pct(
+            || {
+                assert_random_operations_are_linearizable::<NUM_THREADS, MutexSnapshot>();
+            },
+            NUM_ITERATIONS,
+            NUM_PREEMPTIONS,
+        );
+    }
+
+    #[cfg(feature = "shuttle")]
+    #[test]
+    fn atomic_snapshot_is_linearizable() {
+        shuttle::check_pct(
+            || {
+                assert_random_operations_are_linearizable::<NUM_THREADS, AtomicSnapshot>();
+            },
+            NUM_ITERATIONS,
+            NUM_PREEMPTIONS,
+        );
+    }
+}
+
+mod dyn_bounded {
+    use super::*;
+    use todc_mem::snapshot::{DynBoundedAtomicSnapshot, DynBoundedMutexSnapshot};
+
+    type MutexSnapshot = FixedSnapshot<DynBoundedMutexSnapshot<u32>, NUM_THREADS>;
+    type AtomicSnapshot = FixedSnapshot<DynBoundedAtomicSnapshot, NUM_THREADS>;
 
     #[cfg(feature = "shuttle")]
     #[test]
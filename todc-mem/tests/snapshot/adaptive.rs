@@ -0,0 +1,19 @@
+use super::common::{
+    assert_random_operations_are_linearizable, NUM_ITERATIONS, NUM_PREEMPTIONS, NUM_THREADS,
+};
+
+use todc_mem::snapshot::AdaptiveMutexSnapshot;
+
+type MutexSnapshot = AdaptiveMutexSnapshot<u32, NUM_THREADS>;
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mutex_snapshot_is_linearizable() {
+    shuttle::check_pct(
+        || {
+            assert_random_operations_are_linearizable::<NUM_THREADS, MutexSnapshot>();
+        },
+        NUM_ITERATIONS,
+        NUM_PREEMPTIONS,
+    );
+}
@@ -37,3 +37,24 @@ mod lattice {
         );
     }
 }
+
+mod unbounded_lattice {
+    use super::*;
+    use todc_mem::snapshot::UnboundedLatticeMutexSnapshot;
+
+    // Constant M is much smaller than NUM_OPERATIONS * NUM_THREADS, so that
+    // operations are performed across many underlying M-shot objects.
+    type MutexSnapshot = UnboundedLatticeMutexSnapshot<u32, NUM_THREADS, 8>;
+
+    #[cfg(feature = "shuttle")]
+    #[test]
+    fn mutex_snapshot_is_linearizable() {
+        shuttle::check_pct(
+            || {
+                assert_random_operations_are_linearizable::<NUM_THREADS, MutexSnapshot>();
+            },
+            NUM_ITERATIONS,
+            NUM_PREEMPTIONS,
+        );
+    }
+}
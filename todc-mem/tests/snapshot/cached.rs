@@ -0,0 +1,32 @@
+use todc_mem::snapshot::{CachedSnapshot, UnboundedAtomicSnapshot, UnboundedMutexSnapshot};
+
+use super::common::{
+    assert_random_operations_are_linearizable, NUM_ITERATIONS, NUM_PREEMPTIONS, NUM_THREADS,
+};
+
+type MutexSnapshot = CachedSnapshot<UnboundedMutexSnapshot<u32, NUM_THREADS>, NUM_THREADS>;
+type AtomicSnapshot = CachedSnapshot<UnboundedAtomicSnapshot<NUM_THREADS>, NUM_THREADS>;
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mutex_snapshot_is_linearizable() {
+    shuttle::check_pct(
+        || {
+            assert_random_operations_are_linearizable::<NUM_THREADS, MutexSnapshot>();
+        },
+        NUM_ITERATIONS,
+        NUM_PREEMPTIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn atomic_snapshot_is_linearizable() {
+    shuttle::check_pct(
+        || {
+            assert_random_operations_are_linearizable::<NUM_THREADS, AtomicSnapshot>();
+        },
+        NUM_ITERATIONS,
+        NUM_PREEMPTIONS,
+    );
+}
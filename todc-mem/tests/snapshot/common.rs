@@ -2,15 +2,14 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::marker::{Send, Sync};
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
 
 use rand::distributions::Standard;
 use rand::prelude::Distribution;
 use shuttle::rand::{rngs::ThreadRng, thread_rng, Rng};
 use shuttle::thread;
-use todc_mem::snapshot::Snapshot;
+use todc_mem::snapshot::{DynSnapshot, Snapshot};
 use todc_utils::specifications::snapshot::{ProcessId, SnapshotOperation, SnapshotSpecification};
-use todc_utils::{Action, History, WGLChecker};
+use todc_utils::{Action, History, TimedAction, WGLChecker};
 
 // HACK: Run fewer iterations when calculating code coverage.
 #[cfg(coverage)]
@@ -22,20 +21,23 @@ pub const NUM_OPERATIONS: usize = 50;
 pub const NUM_PREEMPTIONS: usize = 3;
 pub const NUM_THREADS: usize = 5;
 
-#[derive(Debug, Clone)]
-pub struct TimedAction<T, const N: usize> {
-    process: ProcessId,
-    action: Action<SnapshotOperation<T, N>>,
-    happened_at: Instant,
-}
+/// A fixed-size snapshot backed by a runtime-sized [`DynSnapshot`] with `N`
+/// components.
+pub struct FixedSnapshot<S: DynSnapshot, const N: usize>(S);
 
-impl<T, const N: usize> TimedAction<T, N> {
-    fn new(process: ProcessId, action: Action<SnapshotOperation<T, N>>) -> Self {
-        Self {
-            process,
-            action,
-            happened_at: Instant::now(),
-        }
+impl<S: DynSnapshot, const N: usize> Snapshot<N> for FixedSnapshot<S, N> {
+    type Value = S::Value;
+
+    fn new() -> Self {
+        Self(S::new(N))
+    }
+
+    fn scan(&self, i: ProcessId) -> [Self::Value; N] {
+        self.0.scan(i).try_into().unwrap_or_else(|_| unreachable!())
+    }
+
+    fn update(&self, i: ProcessId, value: Self::Value) {
+        self.0.update(i, value)
     }
 }
 
@@ -45,26 +47,25 @@ impl<T, const N: usize> TimedAction<T, N> {
 /// # Panics
 ///
 /// Panics if the history of snapshot actions is not linearizable.
-fn assert_linearizable<T, const N: usize>(mut actions: Vec<TimedAction<T, N>>)
+fn assert_linearizable<T, const N: usize>(actions: Vec<TimedAction<SnapshotOperation<T, N>>>)
 where
     T: Clone + Debug + Default + Eq + Hash,
 {
-    actions.sort_by(|a, b| a.happened_at.cmp(&b.happened_at));
-    let history = History::from_actions(
-        actions
-            .iter()
-            .map(|ta| (ta.process, ta.action.clone()))
-            .collect(),
-    );
+    let history = History::from_timed_actions(actions);
 
-    assert!(WGLChecker::<SnapshotSpecification<T, N>>::is_linearizable(
-        history
-    ));
+    assert!(
+        WGLChecker::<SnapshotSpecification<T, N>>::is_linearizable(history.clone()),
+        "History is not linearizable:\n{}",
+        history.render_timeline()
+    );
 }
 
+/// Actions recorded by concurrent processes.
+type Actions<T, const N: usize> = Arc<Mutex<Vec<TimedAction<SnapshotOperation<T, N>>>>>;
+
 /// A snapshot that records metadata about operations performed on it.
 pub struct RecordingSnapshot<const N: usize, S: Snapshot<{ N }>> {
-    actions: Arc<Mutex<Vec<TimedAction<S::Value, N>>>>,
+    actions: Actions<S::Value, N>,
     snapshot: S,
 }
 
@@ -90,7 +91,7 @@ where
     }
 
     fn record(&self, i: ProcessId, action: Action<SnapshotOperation<S::Value, N>>) {
-        let timed_action = TimedAction::new(i, action);
+        let timed_action = TimedAction::now(i, action);
         let mut actions = self.actions.lock().unwrap();
         actions.push(timed_action);
     }
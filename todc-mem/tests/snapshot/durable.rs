@@ -0,0 +1,89 @@
+use std::sync::{Arc, Mutex};
+
+use shuttle::rand::{thread_rng, Rng};
+use shuttle::thread;
+use todc_mem::snapshot::mutex::MutexSnapshot;
+use todc_mem::snapshot::{DurableSnapshot, Snapshot};
+use todc_utils::linearizability::durable::Event;
+use todc_utils::specifications::snapshot::{SnapshotOperation, SnapshotSpecification};
+use todc_utils::{Action, WGLChecker};
+
+use super::common::{NUM_ITERATIONS, NUM_PREEMPTIONS, NUM_THREADS};
+
+const NUM_EPOCHS: usize = 3;
+const NUM_OPERATIONS_PER_EPOCH: usize = 10;
+
+type Durable = DurableSnapshot<MutexSnapshot<u32, NUM_THREADS>, NUM_THREADS>;
+
+/// Performs random operations on a durable snapshot, crashing it at the end
+/// of each epoch, and asserts that the resulting history is durably
+/// linearizable.
+///
+/// Each process abandons its last operation of an epoch with some
+/// probability, either before or after performing it, to simulate an
+/// operation that is interrupted by the crash.
+fn assert_random_operations_with_crashes_are_durably_linearizable() {
+    const UPDATE_PROBABILITY: f64 = 1.0 / 2.0;
+    const INTERRUPT_PROBABILITY: f64 = 1.0 / 2.0;
+
+    let mut snapshot: Arc<Durable> = Arc::new(Durable::new());
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    for _ in 0..NUM_EPOCHS {
+        let mut handles = Vec::new();
+        for i in 0..NUM_THREADS {
+            let snapshot = snapshot.clone();
+            let events = events.clone();
+            handles.push(thread::spawn(move || {
+                let mut rng = thread_rng();
+                let record = |action| events.lock().unwrap().push(Event::Action(i, action));
+                snapshot.recover(i);
+                for op in 0..NUM_OPERATIONS_PER_EPOCH {
+                    let interrupted =
+                        op == NUM_OPERATIONS_PER_EPOCH - 1 && rng.gen_bool(INTERRUPT_PROBABILITY);
+                    let performed = !interrupted || rng.gen_bool(1.0 / 2.0);
+                    if rng.gen_bool(UPDATE_PROBABILITY) {
+                        let value: u32 = rng.gen();
+                        record(Action::Call(SnapshotOperation::Update(i, value)));
+                        if performed {
+                            snapshot.update(i, value);
+                        }
+                        if !interrupted {
+                            record(Action::Response(SnapshotOperation::Update(i, value)));
+                        }
+                    } else {
+                        record(Action::Call(SnapshotOperation::Scan(i, None)));
+                        if performed {
+                            let view = snapshot.scan(i);
+                            if !interrupted {
+                                let scan = SnapshotOperation::Scan(i, Some(view));
+                                record(Action::Response(scan));
+                            }
+                        }
+                    }
+                }
+            }));
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        Arc::get_mut(&mut snapshot).unwrap().crash();
+        events.lock().unwrap().push(Event::Crash);
+    }
+
+    let events = events.lock().unwrap().clone();
+    assert!(
+        WGLChecker::<SnapshotSpecification<u32, NUM_THREADS>>::is_durably_linearizable(events),
+        "History is not durably linearizable"
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mutex_snapshot_is_durably_linearizable() {
+    shuttle::check_pct(
+        assert_random_operations_with_crashes_are_durably_linearizable,
+        NUM_ITERATIONS,
+        NUM_PREEMPTIONS,
+    );
+}
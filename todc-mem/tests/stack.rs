@@ -0,0 +1,94 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::{Arc, Mutex};
+
+use shuttle::thread;
+use todc_mem::stack::{Stack, TreiberStack};
+use todc_utils::specifications::stack::{
+    StackOperation::{Pop, Push},
+    StackSpecification,
+};
+use todc_utils::{Action, History, WGLChecker};
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_OPERATIONS: usize = 4;
+const NUM_PREEMPTIONS: usize = 3;
+const NUM_THREADS: usize = 3;
+
+/// Asserts that a history of concurrent pushes and pops is
+/// linearizable.
+///
+/// Each process alternates between pushing a unique value and popping.
+///
+/// # Panics
+///
+/// Panics if the history of operations is not linearizable.
+fn assert_stack_is_linearizable<S>()
+where
+    S: Stack<NUM_THREADS, Value = usize> + 'static + Send + Sync,
+{
+    let stack: Arc<S> = Arc::new(S::new());
+    let actions = Arc::new(Mutex::new(Vec::new()));
+
+    let handles: Vec<_> = (0..NUM_THREADS)
+        .map(|i| {
+            let stack = stack.clone();
+            let actions = actions.clone();
+            thread::spawn(move || {
+                for op in 0..NUM_OPERATIONS {
+                    if op % 2 == 0 {
+                        let value = i * NUM_OPERATIONS + op;
+                        actions.lock().unwrap().push((i, Action::Call(Push(value))));
+                        stack.push(i, value);
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Response(Push(value))));
+                    } else {
+                        actions.lock().unwrap().push((i, Action::Call(Pop(None))));
+                        let value = stack.pop(i);
+                        actions
+                            .lock()
+                            .unwrap()
+                            .push((i, Action::Response(Pop(Some(value)))));
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let actions = actions.lock().unwrap().clone();
+    let history = History::from_actions(actions);
+    assert!(
+        WGLChecker::<StackSpecification<usize>>::is_linearizable(history.clone()),
+        "History is not linearizable:\n{}",
+        history.render_timeline()
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn treiber_stack_is_linearizable() {
+    shuttle::check_pct(
+        assert_stack_is_linearizable::<TreiberStack<usize, NUM_THREADS>>,
+        NUM_ITERATIONS,
+        NUM_PREEMPTIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn treiber_stack_is_linearizable_under_random_schedules() {
+    shuttle::check_random(
+        assert_stack_is_linearizable::<TreiberStack<usize, NUM_THREADS>>,
+        NUM_ITERATIONS,
+    );
+}
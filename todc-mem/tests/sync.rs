@@ -0,0 +1,94 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::Arc;
+
+use shuttle::thread;
+use todc_mem::sync::{spin_loop, AtomicBool, AtomicU64, Ordering};
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_OPERATIONS: usize = 3;
+const NUM_THREADS: usize = 3;
+
+/// A counter built from the primitives re-exported by `todc_mem::sync`, as a
+/// downstream crate might build its own object.
+#[derive(Default)]
+struct Counter {
+    locked: AtomicBool,
+    value: AtomicU64,
+}
+
+impl Counter {
+    /// Increments the counter while holding a spin lock.
+    fn increment(&self) {
+        while self
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spin_loop();
+        }
+        self.increment_racy();
+        self.locked.store(false, Ordering::Release);
+    }
+
+    /// Increments the counter without holding the lock, so that concurrent
+    /// increments can be lost.
+    fn increment_racy(&self) {
+        let value = self.value.load(Ordering::SeqCst);
+        self.value.store(value + 1, Ordering::SeqCst);
+    }
+}
+
+/// Asserts that no increments to the counter are lost when processes
+/// increment it concurrently.
+///
+/// # Panics
+///
+/// Panics if the final value of the counter is not the total number of
+/// increments.
+fn assert_increments_are_not_lost(increment: fn(&Counter)) {
+    let counter = Arc::new(Counter::default());
+    let handles: Vec<_> = (0..NUM_THREADS)
+        .map(|_| {
+            let counter = counter.clone();
+            thread::spawn(move || {
+                for _ in 0..NUM_OPERATIONS {
+                    increment(&counter);
+                }
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(
+        counter.value.load(Ordering::SeqCst),
+        (NUM_THREADS * NUM_OPERATIONS) as u64
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn increments_with_lock_are_not_lost() {
+    shuttle::check_random(
+        || assert_increments_are_not_lost(Counter::increment),
+        NUM_ITERATIONS,
+    );
+}
+
+// Shuttle can only find this bug if the counter is built from its types,
+// since it cannot preempt a thread between operations on `std` atomics.
+#[cfg(feature = "shuttle")]
+#[test]
+#[should_panic]
+fn finds_lost_increments_without_lock() {
+    shuttle::check_random(
+        || assert_increments_are_not_lost(Counter::increment_racy),
+        NUM_ITERATIONS,
+    );
+}
@@ -0,0 +1,64 @@
+#![allow(dead_code, unused_imports)]
+use std::sync::Arc;
+
+use shuttle::thread;
+use todc_mem::test_and_set::{
+    AtomicRandomizedTestAndSet, MutexRandomizedTestAndSet, NativeTestAndSet, TestAndSet,
+};
+
+// HACK: Run fewer iterations when calculating code coverage.
+#[cfg(coverage)]
+const NUM_ITERATIONS: usize = 5;
+#[cfg(not(coverage))]
+const NUM_ITERATIONS: usize = 250;
+
+const NUM_THREADS: usize = 5;
+
+/// Asserts that exactly one process wins the test-and-set.
+///
+/// # Panics
+///
+/// Panics if zero, or more than one, processes win.
+fn assert_one_winner<const N: usize, T: TestAndSet<N> + 'static + Send + Sync>() {
+    let tas: Arc<T> = Arc::new(T::new());
+    let handles: Vec<_> = (0..N)
+        .map(|i| {
+            let tas = tas.clone();
+            thread::spawn(move || tas.test_and_set(i))
+        })
+        .collect();
+
+    let winners = handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .filter(|&result| !result)
+        .count();
+    assert_eq!(winners, 1);
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn native_test_and_set_has_one_winner() {
+    shuttle::check_random(
+        assert_one_winner::<NUM_THREADS, NativeTestAndSet>,
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn atomic_randomized_test_and_set_has_one_winner() {
+    shuttle::check_random(
+        assert_one_winner::<NUM_THREADS, AtomicRandomizedTestAndSet<NUM_THREADS>>,
+        NUM_ITERATIONS,
+    );
+}
+
+#[cfg(feature = "shuttle")]
+#[test]
+fn mutex_randomized_test_and_set_has_one_winner() {
+    shuttle::check_random(
+        assert_one_winner::<NUM_THREADS, MutexRandomizedTestAndSet<NUM_THREADS>>,
+        NUM_ITERATIONS,
+    );
+}
@@ -0,0 +1,101 @@
+use std::net::SocketAddr;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use hyper::server::conn::{http1, http2};
+use hyper::Uri;
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+
+use todc_net::register::AtomicRegister;
+use todc_net::transport::HttpTransport;
+use todc_net::{TokioExecutor, TokioIo};
+
+const MIN_NUM_INSTANCES: usize = 3;
+const MAX_NUM_INSTANCES: usize = 7;
+
+/// The number of operations that are performed concurrently in each
+/// iteration, so that messages to the same neighbor can be multiplexed.
+const NUM_CONCURRENT_OPERATIONS: u32 = 8;
+
+/// Serves n instances of a register on localhost, where every instance
+/// communicates over HTTP/2 if `use_http2` is set, and HTTP/1 otherwise.
+async fn serve_registers(n: usize, use_http2: bool) -> Vec<AtomicRegister<u32>> {
+    let mut listeners = Vec::new();
+    for _ in 0..n {
+        let addr: SocketAddr = ([127, 0, 0, 1], 0).into();
+        listeners.push(TcpListener::bind(addr).await.unwrap());
+    }
+    let urls: Vec<Uri> = listeners
+        .iter()
+        .map(|listener| {
+            let addr = listener.local_addr().unwrap();
+            format!("http://{addr}").parse().unwrap()
+        })
+        .collect();
+
+    let mut registers = Vec::new();
+    for (i, listener) in listeners.into_iter().enumerate() {
+        let mut neighbors = urls.clone();
+        neighbors.remove(i);
+        let transport = HttpTransport::default().with_http2(use_http2);
+        let register = AtomicRegister::with_transport(neighbors, transport);
+        registers.push(register.clone());
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let io = TokioIo::new(stream);
+                let register = register.clone();
+                tokio::spawn(async move {
+                    if use_http2 {
+                        let _ = http2::Builder::new(TokioExecutor)
+                            .serve_connection(io, register)
+                            .await;
+                    } else {
+                        let _ = http1::Builder::new().serve_connection(io, register).await;
+                    }
+                });
+            }
+        });
+    }
+    registers
+}
+
+/// Performs concurrent writes and reads at the first instance of a register.
+async fn do_writes_and_reads(register: &AtomicRegister<u32>) {
+    let mut handles = Vec::new();
+    for value in 0..NUM_CONCURRENT_OPERATIONS {
+        let register = register.clone();
+        handles.push(tokio::spawn(async move {
+            register.write(value).await.unwrap();
+            register.read().await.unwrap();
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+}
+
+fn benchmark_transport(c: &mut Criterion, runtime: &Runtime, name: &str, use_http2: bool) {
+    let mut group = c.benchmark_group("Quorum Latency");
+    for n in (MIN_NUM_INSTANCES..MAX_NUM_INSTANCES + 1).step_by(2) {
+        let registers = runtime.block_on(serve_registers(n, use_http2));
+        group.bench_with_input(BenchmarkId::new(name, n), &registers[0], |b, register| {
+            b.to_async(runtime).iter(|| do_writes_and_reads(register))
+        });
+    }
+    group.finish();
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    benchmark_transport(c, &runtime, "HTTP/1", false);
+    benchmark_transport(c, &runtime, "HTTP/2", true);
+}
+
+criterion_group! {
+    all_transports,
+    criterion_benchmark,
+}
+criterion_main! {
+    all_transports
+}
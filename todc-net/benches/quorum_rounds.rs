@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use hyper::Uri;
+use tokio::runtime::Runtime;
+
+use todc_net::register::AtomicRegister;
+use todc_net::transport::{Handler, Message, Transport};
+
+const NUM_INSTANCES: usize = 5;
+
+/// The sizes, in bytes, of the values that are written to the register.
+const VALUE_SIZES: [usize; 3] = [16, 1024, 64 * 1024];
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+type Register = AtomicRegister<String, Loopback>;
+
+/// A transport that delivers each message directly to the handler of the
+/// neighbor it is addressed to, so that a round of communication costs only
+/// the work of encoding, decoding and handling its messages.
+#[derive(Clone, Default)]
+struct Loopback {
+    instances: Arc<Mutex<HashMap<Uri, Register>>>,
+}
+
+impl Transport for Loopback {
+    fn send(
+        &self,
+        neighbor: Uri,
+        message: Message,
+    ) -> impl Future<Output = Result<Bytes, Error>> + Send {
+        let instance = self.instances.lock().unwrap().get(&neighbor).cloned();
+        // The future is boxed, since handling a message can itself send
+        // messages over this transport.
+        let reply: Pin<Box<dyn Future<Output = Result<Bytes, Error>> + Send>> =
+            Box::pin(async move {
+                match instance {
+                    Some(instance) => instance.handle(message).await,
+                    None => Err(Error::from("Unknown neighbor")),
+                }
+            });
+        reply
+    }
+}
+
+/// Creates n instances of a register that communicate over a [`Loopback`].
+fn loopback_registers(n: usize) -> Vec<Register> {
+    let transport = Loopback::default();
+    let urls: Vec<Uri> = (0..n)
+        .map(|i| format!("http://instance-{i}").parse().unwrap())
+        .collect();
+    let mut registers = Vec::new();
+    for (i, url) in urls.iter().enumerate() {
+        let mut neighbors = urls.clone();
+        neighbors.remove(i);
+        let register = AtomicRegister::with_transport(neighbors, transport.clone());
+        transport
+            .instances
+            .lock()
+            .unwrap()
+            .insert(url.clone(), register.clone());
+        registers.push(register);
+    }
+    registers
+}
+
+/// Performs a write, followed by a read, at an instance of a register. Each
+/// operation requires two rounds of communication with a quorum.
+async fn do_write_and_read(register: &Register, value: &str) {
+    register.write(value.to_string()).await.unwrap();
+    register.read().await.unwrap();
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let registers = loopback_registers(NUM_INSTANCES);
+    let mut group = c.benchmark_group("Quorum Rounds");
+    for size in VALUE_SIZES {
+        let value = "a".repeat(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(
+            BenchmarkId::new("Write and Read", size),
+            &value,
+            |b, value| {
+                b.to_async(&runtime)
+                    .iter(|| do_write_and_read(&registers[0], value))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group! {
+    quorum_rounds,
+    criterion_benchmark,
+}
+criterion_main! {
+    quorum_rounds
+}
@@ -1,5 +1,6 @@
 use std::env;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
 
 use bytes::{Buf, Bytes};
 use http_body_util::{BodyExt, Full};
@@ -12,7 +13,8 @@ use hyper_util::rt::TokioIo;
 use serde_json::{json, Value as JSON};
 use tokio::net::TcpListener;
 
-use todc_net::abd_95::AtomicRegister;
+use todc_net::discovery::{Discovery, HeadlessService};
+use todc_net::register::AtomicRegister;
 
 fn mk_response(body: JSON) -> Response<Full<Bytes>> {
     Response::builder()
@@ -43,42 +45,45 @@ async fn router(
     }
 }
 
-/// Returns a vector containing the URL of all neighboring
-/// AtomicRegister instances in the local cluster.
-fn find_neighbors() -> Vec<Uri> {
-    let pod_name =
-        env::var("POD_NAME").expect("environmental variable 'POD_NAME' should be set by K8s");
+/// The DNS name of the headless service that selects every instance.
+const SERVICE: &str = "atomic-register.default.svc.cluster.local";
 
-    let (app_name, ordinal_str) = pod_name
-        .rsplit_once('-')
-        .expect("pod name should be of the format {APP_NAME}-{ORDINAL}");
-    println!("App Name: {app_name:?}");
-
-    let ordinal: u32 = ordinal_str.parse().expect("Ordinal should be a valid u32");
-    println!("Ordinal: {ordinal:?}");
-
-    let num_replicas: u32 = env::var("NUM_REPLICAS")
-        .expect("environmental variable 'NUM_RECORDS' should be set by K8s")
+/// Returns the URL of all neighboring AtomicRegister instances in the local
+/// cluster, once every replica of the StatefulSet can be found.
+async fn find_neighbors(discovery: &HeadlessService) -> Vec<Uri> {
+    let num_replicas: usize = env::var("NUM_REPLICAS")
+        .expect("environmental variable 'NUM_REPLICAS' should be set by K8s")
         .parse()
-        .expect("environmental variable 'NUM_RECORDS' should be valid u32");
+        .expect("environmental variable 'NUM_REPLICAS' should be valid usize");
     println!("Number of Replicas: {num_replicas:?}");
+    if num_replicas <= 1 {
+        return Vec::new();
+    }
 
-    (0..num_replicas)
-        .filter(|i| i != &ordinal)
-        .map(|i| {
-            format!("http://{app_name}-{i}.default.svc.cluster.local:3000")
-                .parse()
-                .unwrap()
-        })
-        .collect()
+    loop {
+        match discovery.discover().await {
+            Ok(neighbors) if neighbors.len() + 1 >= num_replicas => return neighbors,
+            Ok(neighbors) => println!("Found {} of {num_replicas} replicas", neighbors.len() + 1),
+            Err(err) => println!("Failed to find neighbors: {err}"),
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let addr: SocketAddr = ([0, 0, 0, 0], 3000).into();
 
-    let neighbors = find_neighbors();
+    let pod_ip: IpAddr = env::var("POD_IP")
+        .expect("environmental variable 'POD_IP' should be set by K8s")
+        .parse()
+        .expect("environmental variable 'POD_IP' should be a valid IP address");
+    let discovery = HeadlessService::new(SERVICE, 3000).excluding(pod_ip);
+
+    let neighbors = find_neighbors(&discovery).await;
     let register: AtomicRegister<String> = AtomicRegister::new(neighbors);
+    // Follow the StatefulSet as it is scaled up or down.
+    register.spawn_discovery(discovery, Duration::from_secs(10));
 
     let listener = TcpListener::bind(addr).await?;
     println!("Listening on http://{}", addr);
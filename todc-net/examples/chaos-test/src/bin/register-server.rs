@@ -0,0 +1,68 @@
+use std::env;
+use std::net::SocketAddr;
+
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::{service_fn, Service};
+use hyper::{Request, Response, Uri};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+
+use todc_net::register::AtomicRegister;
+use todc_net::storage::FileStorage;
+
+/// The file in which an instance stores its local value, so that it
+/// recovers it after being killed and restarted.
+const DEFAULT_STORAGE_PATH: &str = "/var/lib/register.json";
+
+/// Passes every request to the register, which serves reads and writes at
+/// `/register`, as well as the internal requests made by other instances.
+async fn router(
+    register: AtomicRegister<u64>,
+    req: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, Box<dyn std::error::Error + Send + Sync>> {
+    register.call(req).await
+}
+
+/// Returns the URLs of the neighbors of this instance, from the
+/// environmental variable `NEIGHBORS`, which is a comma-separated list of
+/// URLs.
+fn find_neighbors() -> Vec<Uri> {
+    let neighbors: Vec<Uri> = env::var("NEIGHBORS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(|url| {
+            url.parse()
+                .expect("environmental variable 'NEIGHBORS' should contain valid URLs")
+        })
+        .collect();
+    println!("Neighbors: {neighbors:?}");
+    neighbors
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let path = env::var("STORAGE_PATH").unwrap_or(String::from(DEFAULT_STORAGE_PATH));
+    let register: AtomicRegister<u64> =
+        AtomicRegister::new(find_neighbors()).with_storage(FileStorage::new(path))?;
+
+    let addr: SocketAddr = ([0, 0, 0, 0], 3000).into();
+    let listener = TcpListener::bind(addr).await?;
+    println!("Listening on http://{}", addr);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let io = TokioIo::new(stream);
+        let register = register.clone();
+        tokio::task::spawn(async move {
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(io, service_fn(move |req| router(register.clone(), req)))
+                .await
+            {
+                println!("Error serving connection: {:?}", err)
+            }
+        });
+    }
+}
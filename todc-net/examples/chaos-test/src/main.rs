@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::env;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use bollard::container::{KillContainerOptions, ListContainersOptions, StartContainerOptions};
+use bollard::Docker;
+use hyper::Uri;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use tokio::task::JoinSet;
+use tokio::time::{sleep, Instant};
+
+use todc_net::client::RegisterClient;
+use todc_net::register::history::HistorySink;
+use todc_utils::specifications::register::RegisterSpecification;
+use todc_utils::WGLChecker;
+
+type GenericError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The name of the Docker Compose project that runs the instances.
+const PROJECT: &str = "chaos-test";
+
+/// The URLs at which the instances are published on the host.
+const URLS: [&str; 3] = [
+    "http://localhost:3001",
+    "http://localhost:3002",
+    "http://localhost:3003",
+];
+
+/// The parameters of a run of the chaos test.
+struct Config {
+    /// How long clients perform operations for.
+    duration: Duration,
+    /// The number of clients that perform operations concurrently.
+    clients: usize,
+    /// How long to wait between killing instances.
+    fault_interval: Duration,
+    /// How long a killed instance stays down before it is restarted.
+    downtime: Duration,
+}
+
+/// Returns the value of an environmental variable, or a default if it is
+/// not set.
+fn env_or(name: &str, default: u64) -> u64 {
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("environmental variable '{name}' should be a valid u64")),
+        Err(_) => default,
+    }
+}
+
+/// Returns the configuration of the chaos test, from environmental
+/// variables.
+fn configure() -> Config {
+    let config = Config {
+        duration: Duration::from_secs(env_or("CHAOS_DURATION_SECS", 60)),
+        clients: env_or("CHAOS_CLIENTS", 5) as usize,
+        fault_interval: Duration::from_secs(env_or("CHAOS_FAULT_INTERVAL_SECS", 5)),
+        downtime: Duration::from_secs(env_or("CHAOS_DOWNTIME_SECS", 3)),
+    };
+    println!(
+        "Running {} clients for {:?}, killing an instance every {:?} for {:?}",
+        config.clients, config.duration, config.fault_interval, config.downtime
+    );
+    config
+}
+
+/// Returns the service name and ID of the container of each instance,
+/// ordered by service name.
+async fn find_containers(docker: &Docker) -> Result<Vec<(String, String)>, GenericError> {
+    let filters = HashMap::from([(
+        String::from("label"),
+        vec![format!("com.docker.compose.project={PROJECT}")],
+    )]);
+    let options = ListContainersOptions {
+        all: true,
+        filters,
+        ..Default::default()
+    };
+    let mut containers: Vec<(String, String)> = docker
+        .list_containers(Some(options))
+        .await?
+        .into_iter()
+        .filter_map(|container| {
+            let service = container.labels?.get("com.docker.compose.service")?.clone();
+            Some((service, container.id?))
+        })
+        .collect();
+    containers.sort();
+    if containers.is_empty() {
+        return Err(format!("No containers found for project {PROJECT:?}").into());
+    }
+    Ok(containers)
+}
+
+/// Performs random reads and writes until the deadline, recording them in
+/// the sink.
+///
+/// Registers are single-writer, so every write is performed through the
+/// first instance, while reads are performed through randomly chosen
+/// instances. Each value that is written is unique, which makes the history
+/// much faster to check. Operations that fail or time out are abandoned,
+/// and may or may not have taken effect.
+async fn run_client(id: u64, sink: HistorySink<u64>, deadline: Instant) {
+    let mut rng = StdRng::from_entropy();
+    let urls: Vec<Uri> = URLS.iter().map(|url| url.parse().unwrap()).collect();
+    let client = |url: &Uri| -> RegisterClient<u64> {
+        RegisterClient::new(url.clone())
+            .with_timeout(Duration::from_secs(2))
+            .with_history(sink.clone())
+    };
+    let mut writes: u64 = 0;
+    while Instant::now() < deadline {
+        if rng.gen_bool(0.5) {
+            writes += 1;
+            let _ = client(&urls[0]).write((id << 32) | writes).await;
+        } else {
+            let _ = client(urls.choose(&mut rng).unwrap()).read().await;
+        }
+    }
+}
+
+/// Kills a randomly chosen instance once every interval until the
+/// deadline, and restarts it after it has been down for a while.
+///
+/// Only one instance is down at a time, so a majority of instances is
+/// always available, and operations continue to succeed.
+async fn run_nemesis(
+    docker: Docker,
+    containers: Vec<(String, String)>,
+    config: &Config,
+    deadline: Instant,
+) -> Result<(), GenericError> {
+    let mut rng = StdRng::from_entropy();
+    while Instant::now() + config.fault_interval + config.downtime < deadline {
+        sleep(config.fault_interval).await;
+        let (service, id) = containers.choose(&mut rng).unwrap();
+        println!("Killing {service}");
+        docker
+            .kill_container(id, None::<KillContainerOptions<String>>)
+            .await?;
+        sleep(config.downtime).await;
+        println!("Restarting {service}");
+        docker
+            .start_container(id, None::<StartContainerOptions<String>>)
+            .await?;
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode, GenericError> {
+    let config = configure();
+    let docker = Docker::connect_with_local_defaults()?;
+    let containers = find_containers(&docker).await?;
+
+    let sink = HistorySink::new();
+    let deadline = Instant::now() + config.duration;
+    let mut clients = JoinSet::new();
+    for id in 0..config.clients {
+        clients.spawn(run_client(id as u64, sink.clone(), deadline));
+    }
+    run_nemesis(docker, containers, &config, deadline).await?;
+    while let Some(result) = clients.join_next().await {
+        result?;
+    }
+
+    let actions = sink.actions();
+    if actions.is_empty() {
+        return Err("No operations were performed".into());
+    }
+    println!("Checking a history of {} actions...", actions.len());
+    let history = sink.history();
+    if WGLChecker::<RegisterSpecification<u64>>::is_linearizable(history.clone()) {
+        println!("History is linearizable");
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!(
+            "History is not linearizable:\n{}",
+            history.render_timeline()
+        );
+        Ok(ExitCode::FAILURE)
+    }
+}
@@ -0,0 +1,168 @@
+use std::env;
+use std::fs;
+use std::net::SocketAddr;
+use std::thread::available_parallelism;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::http::StatusCode;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, Uri};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JSON};
+use tokio::net::TcpListener;
+
+mod snapshot;
+
+use snapshot::Snapshot;
+
+/// The status that an instance reports in its component of the snapshot.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+struct Status {
+    /// The name of the Pod that the instance is running in.
+    name: String,
+    /// Whether the load on the instance is less than its number of CPUs.
+    healthy: bool,
+    /// The average load on the instance over the last minute, multiplied by
+    /// `100`, or `None` if it could not be read.
+    load: Option<u32>,
+    /// The number of seconds since the instance started.
+    uptime: u64,
+}
+
+// Instances that have not reported yet have no status.
+type ClusterView = Snapshot<Option<Status>>;
+
+fn mk_response(status: StatusCode, body: JSON) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .body(Full::new(Bytes::from(body.to_string())))
+        .unwrap()
+}
+
+/// Routes requests to the appropriate snapshot operations.
+async fn router(
+    view: ClusterView,
+    req: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, Box<dyn std::error::Error + Send + Sync>> {
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/") => Ok(mk_response(
+            StatusCode::OK,
+            json!("Try submitting requests to /cluster!"),
+        )),
+        // Scan the snapshot, which returns the status of every instance
+        // as it was at a single moment.
+        (&Method::GET, "/cluster") => match view.scan().await {
+            Ok(statuses) => Ok(mk_response(StatusCode::OK, json!(statuses))),
+            Err(err) => Ok(mk_response(err.status_code(), json!(err.to_string()))),
+        },
+        // Allow the snapshot to handle the internal requests that instances
+        // make to the registers of its components.
+        (_, path) if path.starts_with("/snapshot/") => view.call(req).await,
+        _ => Ok(mk_response(StatusCode::NOT_FOUND, json!(null))),
+    }
+}
+
+/// Returns the average load on this instance over the last minute,
+/// multiplied by `100`, if it can be read.
+fn load() -> Option<u32> {
+    let loadavg = fs::read_to_string("/proc/loadavg").ok()?;
+    let load: f64 = loadavg.split_whitespace().next()?.parse().ok()?;
+    Some((load * 100.0).round() as u32)
+}
+
+/// Periodically updates the component of this instance with its status.
+async fn report(view: ClusterView, name: String, interval: Duration) {
+    let started = Instant::now();
+    let cpus = available_parallelism().map_or(1, |cpus| cpus.get()) as u32;
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        let load = load();
+        // An instance whose load cannot be read is assumed to be healthy,
+        // since it is still able to report its status.
+        let healthy = match load {
+            Some(load) => load < cpus * 100,
+            None => true,
+        };
+        let status = Status {
+            name: name.clone(),
+            healthy,
+            load,
+            uptime: started.elapsed().as_secs(),
+        };
+        // An update fails if a majority of instances cannot be reached, in
+        // which case the next one is tried at the next interval.
+        if let Err(err) = view.update(Some(status)).await {
+            println!("Failed to report status: {err}");
+        }
+    }
+}
+
+/// Returns the name and ordinal of this instance, and the URLs of all of
+/// its neighbors in the local cluster.
+fn find_neighbors() -> (String, usize, usize, Vec<Uri>) {
+    let pod_name =
+        env::var("POD_NAME").expect("environmental variable 'POD_NAME' should be set by K8s");
+
+    let (app_name, ordinal_str) = pod_name
+        .rsplit_once('-')
+        .expect("pod name should be of the format {APP_NAME}-{ORDINAL}");
+    println!("App Name: {app_name:?}");
+
+    let ordinal: usize = ordinal_str
+        .parse()
+        .expect("Ordinal should be a valid usize");
+    println!("Ordinal: {ordinal:?}");
+
+    let num_replicas: usize = env::var("NUM_REPLICAS")
+        .expect("environmental variable 'NUM_REPLICAS' should be set by K8s")
+        .parse()
+        .expect("environmental variable 'NUM_REPLICAS' should be a valid usize");
+    println!("Number of Replicas: {num_replicas:?}");
+
+    let neighbors = (0..num_replicas)
+        .filter(|i| i != &ordinal)
+        .map(|i| {
+            format!("http://{app_name}-{i}.default.svc.cluster.local:3000")
+                .parse()
+                .unwrap()
+        })
+        .collect();
+    (pod_name, ordinal, num_replicas, neighbors)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let addr: SocketAddr = ([0, 0, 0, 0], 3000).into();
+
+    let (name, ordinal, num_replicas, neighbors) = find_neighbors();
+    let interval: u64 = env::var("UPDATE_INTERVAL_SECS")
+        .map(|secs| {
+            secs.parse()
+                .expect("environmental variable 'UPDATE_INTERVAL_SECS' should be a valid u64")
+        })
+        .unwrap_or(5);
+    let view: ClusterView = Snapshot::new(ordinal, num_replicas, neighbors);
+    tokio::spawn(report(view.clone(), name, Duration::from_secs(interval)));
+
+    let listener = TcpListener::bind(addr).await?;
+    println!("Listening on http://{}", addr);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let io = TokioIo::new(stream);
+        let view = view.clone();
+        tokio::task::spawn(async move {
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(io, service_fn(move |req| router(view.clone(), req)))
+                .await
+            {
+                println!("Failed to serve connection: {:?}", err);
+            }
+        });
+    }
+}
@@ -0,0 +1,164 @@
+//! A snapshot object whose components are replicated across instances.
+//!
+//! Each instance owns one component of the snapshot, which only it updates,
+//! and any instance can scan every component at once. Each component is
+//! stored in its own [`AtomicRegister`], and scans and updates follow the
+//! unbounded algorithm of Afek et al.
+//! [\[AAD+93\]](https://dl.acm.org/doi/10.1145/153724.153741), in which every
+//! update embeds the result of a scan so that scans are never starved by
+//! concurrent updates. This is the same algorithm as the `UnboundedSnapshot`
+//! of `todc-mem`, with reads and writes of shared memory replaced by
+//! operations on registers that are reached over HTTP.
+//!
+//! The registers of all components are served by the same instance. Internal
+//! requests for the register of component `j` are made to `/snapshot/{j}`,
+//! followed by the usual route of the register, such as `/register/local`.
+use std::error::Error;
+use std::fmt::Debug;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::http::StatusCode;
+use hyper::service::Service;
+use hyper::{Request, Response, Uri};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use todc_net::register::abd_95::RegisterError;
+use todc_net::register::AtomicRegister;
+use todc_net::transport::{HttpTransport, Message, Transport};
+
+type GenericError = Box<dyn Error + Send + Sync>;
+
+/// The contents of a component of the snapshot.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+struct Component<T> {
+    value: T,
+    /// The number of times the component has been updated.
+    sequence: u64,
+    /// The result of the scan performed during the most recent update.
+    view: Vec<T>,
+}
+
+/// A [`Transport`] that sends the messages of the register of a component
+/// to the routes of that component.
+#[derive(Clone, Default)]
+struct ComponentTransport {
+    prefix: String,
+    inner: HttpTransport,
+}
+
+impl Transport for ComponentTransport {
+    async fn send(&self, neighbor: Uri, mut message: Message) -> Result<Bytes, GenericError> {
+        message.path = format!("{}{}", self.prefix, message.path);
+        self.inner.send(neighbor, message).await
+    }
+}
+
+/// A snapshot object with one component for each instance.
+#[derive(Clone)]
+pub struct Snapshot<T>
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+{
+    id: usize,
+    components: Vec<AtomicRegister<Component<T>, ComponentTransport>>,
+}
+
+impl<T> Snapshot<T>
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+{
+    /// Creates the instance of a snapshot with `n` components that owns
+    /// component `id`, and whose neighbors are reachable at the given URLs.
+    pub fn new(id: usize, n: usize, neighbors: Vec<Uri>) -> Self {
+        let components = (0..n)
+            .map(|j| {
+                let transport = ComponentTransport {
+                    prefix: format!("/snapshot/{j}"),
+                    inner: HttpTransport::default(),
+                };
+                AtomicRegister::with_transport(neighbors.clone(), transport)
+            })
+            .collect();
+        Self { id, components }
+    }
+
+    /// Returns the contents of every component, obtained by reading each of
+    /// them in turn.
+    async fn collect(&self) -> Result<Vec<Component<T>>, RegisterError> {
+        let mut contents = Vec::with_capacity(self.components.len());
+        for component in &self.components {
+            contents.push(component.read().await?);
+        }
+        Ok(contents)
+    }
+
+    /// Returns the value of every component, as they were at a single moment
+    /// during the scan.
+    pub async fn scan(&self) -> Result<Vec<T>, RegisterError> {
+        // A component has moved if its sequence number has been incremented.
+        let mut moved = vec![false; self.components.len()];
+        loop {
+            let first = self.collect().await?;
+            let second = self.collect().await?;
+            // If both collects are identical, then their values are a valid scan.
+            if first
+                .iter()
+                .zip(&second)
+                .all(|(first, second)| first.sequence == second.sequence)
+            {
+                return Ok(second.into_iter().map(|c| c.value).collect());
+            }
+            for (j, (first, second)) in first.iter().zip(&second).enumerate() {
+                // If component j is observed to have moved twice, then an
+                // update of it began and ended during this scan. The result
+                // of the scan performed by that update can be returned here.
+                if first.sequence != second.sequence {
+                    if moved[j] {
+                        return Ok(second.view.clone());
+                    }
+                    moved[j] = true;
+                }
+            }
+        }
+    }
+
+    /// Sets the value of the component owned by this instance.
+    pub async fn update(&self, value: T) -> Result<(), RegisterError> {
+        let own = &self.components[self.id];
+        let sequence = own.read().await?.sequence + 1;
+        let view = self.scan().await?;
+        own.write(Component {
+            value,
+            sequence,
+            view,
+        })
+        .await
+    }
+
+    /// Passes an internal request made to `/snapshot/{j}/...` to the
+    /// register of component `j`.
+    pub async fn call(
+        &self,
+        mut req: Request<Incoming>,
+    ) -> Result<Response<Full<Bytes>>, GenericError> {
+        let route = req
+            .uri()
+            .path()
+            .strip_prefix("/snapshot/")
+            .and_then(|path| path.split_once('/'))
+            .and_then(|(j, path)| Some((j.parse::<usize>().ok()?, format!("/{path}"))));
+        match route {
+            Some((j, path)) if j < self.components.len() => {
+                *req.uri_mut() = path.parse()?;
+                self.components[j].call(req).await
+            }
+            _ => Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Full::new(Bytes::new()))
+                .unwrap()),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::env;
+use std::net::SocketAddr;
+
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::http::StatusCode;
+use hyper::server::conn::http1;
+use hyper::service::{service_fn, Service};
+use hyper::{Method, Request, Response, Uri};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+
+use todc_net::consensus::ReplicatedLog;
+
+// The counter is a log of increments, and its value is their sum.
+type Counter = ReplicatedLog<u64>;
+
+fn mk_response(status: StatusCode, body: impl Into<Bytes>) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .body(Full::new(body.into()))
+        .unwrap()
+}
+
+// The main router for our server
+async fn router(
+    counter: Counter,
+    req: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, Box<dyn std::error::Error + Send + Sync>> {
+    match (req.method(), req.uri().path()) {
+        // Allow the counter to be read with GET requests. Reading appends a
+        // marker to the log, so the result includes every increment that
+        // completed before the request was made, on any instance.
+        (&Method::GET, "/counter") => match counter.read().await {
+            Ok(increments) => {
+                let value: u64 = increments.iter().sum();
+                Ok(mk_response(StatusCode::OK, value.to_string()))
+            }
+            Err(err) => Ok(mk_response(
+                StatusCode::SERVICE_UNAVAILABLE,
+                err.to_string(),
+            )),
+        },
+        // Allow the counter to be incremented with POST requests. The body
+        // is the amount to increment by, which defaults to 1.
+        (&Method::POST, "/counter/increment") => {
+            let body = req.collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(&body);
+            let amount = match body.trim() {
+                "" => 1,
+                amount => match amount.parse::<u64>() {
+                    Ok(amount) => amount,
+                    Err(err) => {
+                        return Ok(mk_response(
+                            StatusCode::BAD_REQUEST,
+                            format!("Invalid amount {amount:?}: {err}"),
+                        ))
+                    }
+                },
+            };
+            match counter.append(amount).await {
+                Ok(_) => Ok(mk_response(StatusCode::OK, Bytes::new())),
+                Err(err) => Ok(mk_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    err.to_string(),
+                )),
+            }
+        }
+        // Allow the log to handle all other requests, such as the internal
+        // requests to /paxos/* that instances use to reach consensus.
+        _ => counter.call(req).await,
+    }
+}
+
+/// Returns the id of this instance, and the URLs of its neighbors, from the
+/// environmental variables `INSTANCE_ID` and `NEIGHBORS`.
+///
+/// `NEIGHBORS` is a comma-separated list of URLs, and is empty if there is
+/// only one instance.
+fn configure() -> (u32, Vec<Uri>) {
+    let id: u32 = env::var("INSTANCE_ID")
+        .expect("environmental variable 'INSTANCE_ID' should be set")
+        .parse()
+        .expect("environmental variable 'INSTANCE_ID' should be a valid u32");
+    println!("Instance ID: {id:?}");
+
+    let neighbors: Vec<Uri> = env::var("NEIGHBORS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(|url| {
+            url.parse()
+                .expect("environmental variable 'NEIGHBORS' should contain valid URLs")
+        })
+        .collect();
+    println!("Neighbors: {neighbors:?}");
+
+    (id, neighbors)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Create a counter for this instance.
+    let (id, neighbors) = configure();
+    let counter: Counter = ReplicatedLog::new(id, neighbors);
+
+    // Create a new server with Hyper.
+    let addr: SocketAddr = ([0, 0, 0, 0], 3000).into();
+    let listener = TcpListener::bind(addr).await?;
+    println!("Listening on http://{}", addr);
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let io = TokioIo::new(stream);
+        let counter = counter.clone();
+        tokio::task::spawn(async move {
+            if let Err(err) = http1::Builder::new()
+                // Handle requests by passing them to the router
+                .serve_connection(io, service_fn(move |req| router(counter.clone(), req)))
+                .await
+            {
+                println!("Error serving connection: {:?}", err)
+            }
+        });
+    }
+}
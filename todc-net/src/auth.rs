@@ -0,0 +1,188 @@
+//! Authenticating the messages that instances exchange.
+//!
+//! Algorithms that tolerate crashes trust every message that they receive.
+//! Algorithms that tolerate _Byzantine_ instances, such as the
+//! [`ByzantineRegister`](crate::register::ByzantineRegister), instead assume
+//! that an instance cannot send a message on behalf of another. An
+//! [`Authenticator`] provides this guarantee, by signing each message that an
+//! instance sends, and verifying the signature of each message that it
+//! receives.
+//!
+//! This module contains an [`Unauthenticated`] authenticator, which accepts
+//! every message, for deployments whose network already authenticates
+//! instances, such as with mutual TLS, and an [`HmacAuthenticator`], which
+//! signs messages with a key that is shared by every instance. A shared key
+//! keeps out instances that do not know it, but lets any instance that does
+//! sign messages on behalf of any other. To also guard against Byzantine
+//! instances that know the key, implement [`Authenticator`] with a digital
+//! signature scheme, in which each instance signs with its own private key.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_net::auth::{Authenticator, HmacAuthenticator};
+//!
+//! let authenticator = HmacAuthenticator::new(b"secret key");
+//! let signature = authenticator.sign(1, b"hello");
+//! assert!(authenticator.verify(1, b"hello", &signature));
+//! assert!(!authenticator.verify(1, b"goodbye", &signature));
+//! ```
+use sha2::{Digest, Sha256};
+
+/// A way of signing and verifying the messages that instances exchange.
+///
+/// Each instance is identified by a number, which is unique among all
+/// instances of an object.
+pub trait Authenticator: Send + Sync + 'static {
+    /// Returns the signature of a message sent by the instance `signer`.
+    fn sign(&self, signer: u32, message: &[u8]) -> Vec<u8>;
+
+    /// Returns whether `signature` is a valid signature of a message sent by
+    /// the instance `signer`.
+    fn verify(&self, signer: u32, message: &[u8], signature: &[u8]) -> bool;
+}
+
+/// An [`Authenticator`] that accepts every message.
+///
+/// Messages are signed with an empty signature.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Unauthenticated;
+
+impl Authenticator for Unauthenticated {
+    fn sign(&self, _: u32, _: &[u8]) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn verify(&self, _: u32, _: &[u8], _: &[u8]) -> bool {
+        true
+    }
+}
+
+/// An [`Authenticator`] that signs messages with HMAC-SHA256, as described
+/// in [RFC 2104](https://www.rfc-editor.org/rfc/rfc2104), using a key that
+/// is shared by every instance.
+///
+/// The ID of the signer is signed along with each message, so that a message
+/// cannot be replayed as if it were sent by a different instance. See the
+/// [`auth`](crate::auth) module-level documentation for the limits of a
+/// shared key.
+#[derive(Clone)]
+pub struct HmacAuthenticator {
+    // The key, padded to the block size of SHA-256.
+    key: [u8; Self::BLOCK_SIZE],
+}
+
+impl HmacAuthenticator {
+    /// The block size of SHA-256, in bytes.
+    const BLOCK_SIZE: usize = 64;
+
+    /// Creates an authenticator that signs messages with the given key.
+    ///
+    /// Keys longer than 64 bytes are hashed first, as specified by HMAC.
+    pub fn new(key: &[u8]) -> Self {
+        let mut padded = [0; Self::BLOCK_SIZE];
+        if key.len() > Self::BLOCK_SIZE {
+            padded[..32].copy_from_slice(&Sha256::digest(key));
+        } else {
+            padded[..key.len()].copy_from_slice(key);
+        }
+        Self { key: padded }
+    }
+
+    /// Returns the HMAC of a message.
+    fn mac(&self, message: &[&[u8]]) -> [u8; 32] {
+        let mut inner = Sha256::new();
+        inner.update(self.key.map(|byte| byte ^ 0x36));
+        for part in message {
+            inner.update(part);
+        }
+        let mut outer = Sha256::new();
+        outer.update(self.key.map(|byte| byte ^ 0x5c));
+        outer.update(inner.finalize());
+        outer.finalize().into()
+    }
+}
+
+impl Authenticator for HmacAuthenticator {
+    fn sign(&self, signer: u32, message: &[u8]) -> Vec<u8> {
+        self.mac(&[&signer.to_be_bytes(), message]).to_vec()
+    }
+
+    fn verify(&self, signer: u32, message: &[u8], signature: &[u8]) -> bool {
+        let expected = self.mac(&[&signer.to_be_bytes(), message]);
+        // Compare every byte, so that the time taken does not reveal how
+        // much of the signature was correct.
+        signature.len() == expected.len()
+            && signature
+                .iter()
+                .zip(expected)
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod hmac_authenticator {
+        use super::*;
+
+        fn to_hex(bytes: &[u8]) -> String {
+            bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+        }
+
+        #[test]
+        fn matches_rfc_4231_test_case() {
+            // Test case 2 of RFC 4231.
+            let authenticator = HmacAuthenticator::new(b"Jefe");
+            let mac = authenticator.mac(&[b"what do ya want ", b"for nothing?"]);
+            assert_eq!(
+                to_hex(&mac),
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+            );
+        }
+
+        #[test]
+        fn matches_rfc_4231_test_case_with_long_key() {
+            // Test case 6 of RFC 4231.
+            let authenticator = HmacAuthenticator::new(&[0xaa; 131]);
+            let mac =
+                authenticator.mac(&[b"Test Using Larger Than Block-Size Key - Hash Key First"]);
+            assert_eq!(
+                to_hex(&mac),
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+            );
+        }
+
+        #[test]
+        fn rejects_message_from_different_signer() {
+            let authenticator = HmacAuthenticator::new(b"key");
+            let signature = authenticator.sign(1, b"hello");
+            assert!(!authenticator.verify(2, b"hello", &signature));
+        }
+
+        #[test]
+        fn rejects_signature_made_with_different_key() {
+            let signature = HmacAuthenticator::new(b"key").sign(1, b"hello");
+            let authenticator = HmacAuthenticator::new(b"other key");
+            assert!(!authenticator.verify(1, b"hello", &signature));
+        }
+
+        #[test]
+        fn rejects_truncated_signature() {
+            let authenticator = HmacAuthenticator::new(b"key");
+            let signature = authenticator.sign(1, b"hello");
+            assert!(!authenticator.verify(1, b"hello", &signature[..16]));
+        }
+    }
+
+    mod unauthenticated {
+        use super::*;
+
+        #[test]
+        fn accepts_every_message() {
+            assert!(Unauthenticated.verify(1, b"hello", b"not a signature"));
+        }
+    }
+}
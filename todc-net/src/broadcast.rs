@@ -0,0 +1,26 @@
+//! Primitives for broadcasting messages to a group of instances.
+//!
+//! This module contains two kinds of broadcast, as described by Hadzilacos
+//! and Toueg in _A Modular Approach to Fault-Tolerant Broadcasts and Related
+//! Problems_:
+//!
+//! * [`ReliableBroadcast`], which guarantees that if any correct instance
+//!   delivers a message then every correct instance eventually delivers it,
+//!   even if the original sender crashes part-way through broadcasting it.
+//! * [`TotalOrderBroadcast`], which additionally guarantees that all
+//!   instances deliver messages in the same order. It is built on top of a
+//!   [`ReplicatedLog`](crate::consensus::ReplicatedLog), and so requires a
+//!   majority of instances to be reachable.
+//!
+//! In both cases, messages are delivered to _subscribers_, which receive
+//! each message that is delivered after they subscribed.
+//!
+//! # Examples
+//!
+//! See the [`reliable`] and [`total_order`] module-level documentation for
+//! examples.
+pub mod reliable;
+pub mod total_order;
+
+pub use self::reliable::ReliableBroadcast;
+pub use self::total_order::TotalOrderBroadcast;
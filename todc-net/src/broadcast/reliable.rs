@@ -0,0 +1,292 @@
+//! Reliable broadcast, in which every message is relayed by each instance
+//! that receives it.
+//!
+//! When an instance receives a message for the first time, it forwards the
+//! message to all of its neighbors before delivering it. As a result, as
+//! long as communication between correct instances is reliable, a message
+//! that is delivered by any correct instance is eventually delivered by all
+//! of them. Each message is sent `O(n^2)` times in total.
+//!
+//! Messages are not delivered in any particular order. In particular, two
+//! messages broadcast by the same instance may be delivered in different
+//! orders by different instances. See
+//! [`TotalOrderBroadcast`](crate::broadcast::TotalOrderBroadcast) for a
+//! broadcast that orders messages.
+//!
+//! # Examples
+//!
+//! Instances of [`ReliableBroadcast`] handle messages from their neighbors by
+//! responding to `POST` requests made to `/broadcast/reliable`, in the same
+//! way as an [`AtomicRegister`](crate::register::AtomicRegister).
+//!
+//! ```
+//! # use tokio_test;
+//! use todc_net::broadcast::ReliableBroadcast;
+//!
+//! # tokio_test::block_on(async {
+//! let broadcast: ReliableBroadcast<String> = ReliableBroadcast::new(1, Vec::new());
+//! let mut deliveries = broadcast.subscribe();
+//!
+//! broadcast.broadcast(String::from("hello")).await.unwrap();
+//! assert_eq!(deliveries.recv().await.unwrap(), "hello");
+//! # })
+//! ```
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::http::StatusCode;
+use hyper::service::Service;
+use hyper::{Method, Request, Response, Uri};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::transport::{self, Handler, HttpTransport, Transport};
+use crate::{mk_response, GenericError};
+
+const RELIABLE_PATH: &str = "/broadcast/reliable";
+
+/// A broadcast message, along with an identifier that is unique across all
+/// messages broadcast by any instance.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Envelope<T> {
+    origin: u32,
+    sequence: u64,
+    payload: T,
+}
+
+/// A stream of messages delivered by a [`ReliableBroadcast`].
+pub struct Deliveries<T> {
+    receiver: UnboundedReceiver<T>,
+}
+
+impl<T> Deliveries<T> {
+    /// Waits for the next message to be delivered.
+    ///
+    /// Returns `None` once every clone of the [`ReliableBroadcast`] instance
+    /// that this stream subscribed to has been dropped.
+    pub async fn recv(&mut self) -> Option<T> {
+        self.receiver.recv().await
+    }
+}
+
+/// An instance of a reliable broadcast.
+///
+/// See the [`reliable`](crate::broadcast::reliable) module-level
+/// documentation for more details.
+#[derive(Clone)]
+pub struct ReliableBroadcast<
+    T: Clone + Debug + DeserializeOwned + Send + Serialize,
+    Tr: Transport = HttpTransport,
+> {
+    id: u32,
+    transport: Tr,
+    neighbors: Arc<Vec<Uri>>,
+    sequence: Arc<Mutex<u64>>,
+    delivered: Arc<Mutex<HashSet<(u32, u64)>>>,
+    subscribers: Arc<Mutex<Vec<UnboundedSender<T>>>>,
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static> ReliableBroadcast<T> {
+    /// Creates a new instance with a given set of neighbors.
+    ///
+    /// If there are `n` instances, then each instance must be instantiated
+    /// with a URL for all `n - 1` of its neighbors, and an `id` that is
+    /// different from the ids of all other instances.
+    pub fn new(id: u32, neighbors: Vec<Uri>) -> Self {
+        Self::with_transport(id, neighbors, HttpTransport::default())
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport>
+    ReliableBroadcast<T, Tr>
+{
+    /// Creates a new instance with a given set of neighbors, that
+    /// communicates with them using the given [`Transport`].
+    pub fn with_transport(id: u32, neighbors: Vec<Uri>, transport: Tr) -> Self {
+        Self {
+            id,
+            transport,
+            neighbors: Arc::new(neighbors),
+            sequence: Arc::new(Mutex::new(0)),
+            delivered: Arc::new(Mutex::new(HashSet::new())),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Broadcasts a message to all instances, including this one.
+    ///
+    /// This method returns once the message has been delivered locally and
+    /// sent to all neighbors, without waiting for them to deliver it.
+    pub async fn broadcast(&self, message: T) -> Result<(), GenericError> {
+        let sequence = {
+            let mut sequence = self.sequence.lock().unwrap();
+            *sequence += 1;
+            *sequence
+        };
+        let envelope = Envelope {
+            origin: self.id,
+            sequence,
+            payload: message,
+        };
+        self.receive(envelope)
+    }
+
+    /// Returns a stream of the messages delivered by this instance from now
+    /// on.
+    pub fn subscribe(&self) -> Deliveries<T> {
+        let (sender, receiver) = unbounded_channel();
+        self.subscribers.lock().unwrap().push(sender);
+        Deliveries { receiver }
+    }
+
+    /// Relays and delivers a message, unless it has already been delivered.
+    fn receive(&self, envelope: Envelope<T>) -> Result<(), GenericError> {
+        let id = (envelope.origin, envelope.sequence);
+        if !self.delivered.lock().unwrap().insert(id) {
+            return Ok(());
+        }
+
+        // Relay the message before delivering it, so that if any instance
+        // delivers the message then all of its neighbors will receive it.
+        let body: Bytes = serde_json::to_vec(&envelope)?.into();
+        for neighbor in self.neighbors.iter().cloned() {
+            let transport = self.transport.clone();
+            let message = transport::Message::announce(RELIABLE_PATH, body.clone());
+            tokio::spawn(async move { transport.send(neighbor, message).await });
+        }
+
+        // Deliver the message to all subscribers that are still listening.
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|s| s.send(envelope.payload.clone()).is_ok());
+        Ok(())
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport> Handler
+    for ReliableBroadcast<T, Tr>
+{
+    fn handle(
+        &self,
+        message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        let me = self.clone();
+        async move {
+            if message.path != RELIABLE_PATH {
+                return Err(GenericError::from(format!(
+                    "Unknown route {}",
+                    message.path
+                )));
+            }
+            let body = message.body.ok_or("Broadcast messages must have a body")?;
+            me.receive(serde_json::from_slice(&body)?)?;
+            Ok(serde_json::to_vec(&())?.into())
+        }
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport>
+    Service<Request<Incoming>> for ReliableBroadcast<T, Tr>
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        let me = self.clone();
+        match (req.method(), req.uri().path()) {
+            // POST requests contain messages broadcast by other instances.
+            (&Method::POST, RELIABLE_PATH) => Box::pin(async move {
+                let body = req.collect().await?.to_bytes();
+                match me
+                    .handle(transport::Message::announce(RELIABLE_PATH, body))
+                    .await
+                {
+                    Ok(reply) => Ok(Response::new(Full::new(reply))),
+                    Err(error) => mk_response(StatusCode::BAD_REQUEST, error.to_string().into()),
+                }
+            }),
+            _ => Box::pin(async { mk_response(StatusCode::NOT_FOUND, "404 Not Found".into()) }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod reliable_broadcast {
+        use super::*;
+
+        mod broadcast {
+            use super::*;
+
+            #[tokio::test]
+            async fn delivers_message_locally() {
+                let broadcast: ReliableBroadcast<u32> = ReliableBroadcast::new(1, Vec::new());
+                let mut deliveries = broadcast.subscribe();
+                broadcast.broadcast(123).await.unwrap();
+                assert_eq!(deliveries.recv().await, Some(123));
+            }
+
+            #[tokio::test]
+            async fn delivers_to_every_subscriber() {
+                let broadcast: ReliableBroadcast<u32> = ReliableBroadcast::new(1, Vec::new());
+                let mut first = broadcast.subscribe();
+                let mut second = broadcast.subscribe();
+                broadcast.broadcast(123).await.unwrap();
+                assert_eq!(first.recv().await, Some(123));
+                assert_eq!(second.recv().await, Some(123));
+            }
+
+            #[tokio::test]
+            async fn delivers_identical_messages_separately() {
+                let broadcast: ReliableBroadcast<u32> = ReliableBroadcast::new(1, Vec::new());
+                let mut deliveries = broadcast.subscribe();
+                broadcast.broadcast(123).await.unwrap();
+                broadcast.broadcast(123).await.unwrap();
+                assert_eq!(deliveries.recv().await, Some(123));
+                assert_eq!(deliveries.recv().await, Some(123));
+            }
+        }
+
+        mod receive {
+            use super::*;
+
+            #[tokio::test]
+            async fn ignores_duplicate_messages() {
+                let broadcast: ReliableBroadcast<u32> = ReliableBroadcast::new(1, Vec::new());
+                let mut deliveries = broadcast.subscribe();
+                let envelope = Envelope {
+                    origin: 2,
+                    sequence: 1,
+                    payload: 123,
+                };
+                broadcast.receive(envelope.clone()).unwrap();
+                broadcast.receive(envelope).unwrap();
+                drop(broadcast);
+                assert_eq!(deliveries.recv().await, Some(123));
+                assert_eq!(deliveries.recv().await, None);
+            }
+        }
+
+        mod subscribe {
+            use super::*;
+
+            #[tokio::test]
+            async fn does_not_receive_earlier_messages() {
+                let broadcast: ReliableBroadcast<u32> = ReliableBroadcast::new(1, Vec::new());
+                broadcast.broadcast(1).await.unwrap();
+                let mut deliveries = broadcast.subscribe();
+                broadcast.broadcast(2).await.unwrap();
+                assert_eq!(deliveries.recv().await, Some(2));
+            }
+        }
+    }
+}
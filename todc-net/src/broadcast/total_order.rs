@@ -0,0 +1,218 @@
+//! Total-order (atomic) broadcast, built on top of a
+//! [`ReplicatedLog`](crate::consensus::ReplicatedLog).
+//!
+//! Broadcasting a message appends it to the log, and each instance delivers
+//! the messages in the order that they appear in the log. As a result, all
+//! instances deliver the same messages in the same order.
+//!
+//! Instances learn about new messages when they are announced by the instance
+//! that broadcast them. If an announcement is lost, then an instance does
+//! not deliver any later messages until it learns about the missing one,
+//! which happens the next time it broadcasts a message or calls
+//! [`sync`](TotalOrderBroadcast::sync).
+//!
+//! # Examples
+//!
+//! Instances of [`TotalOrderBroadcast`] handle messages from their neighbors
+//! in the same way as a [`ReplicatedLog`](crate::consensus::ReplicatedLog).
+//!
+//! ```
+//! # use tokio_test;
+//! use todc_net::broadcast::TotalOrderBroadcast;
+//!
+//! # tokio_test::block_on(async {
+//! let broadcast: TotalOrderBroadcast<String> = TotalOrderBroadcast::new(1, Vec::new());
+//! let mut deliveries = broadcast.subscribe();
+//!
+//! broadcast.broadcast(String::from("hello")).await.unwrap();
+//! broadcast.broadcast(String::from("world")).await.unwrap();
+//! assert_eq!(deliveries.recv().await, "hello");
+//! assert_eq!(deliveries.recv().await, "world");
+//! # })
+//! ```
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::service::Service;
+use hyper::{Request, Response, Uri};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::consensus::ReplicatedLog;
+use crate::transport::{self, Handler, HttpTransport, Transport};
+use crate::GenericError;
+
+/// A stream of messages delivered by a [`TotalOrderBroadcast`].
+pub struct Deliveries<
+    T: Clone + Debug + DeserializeOwned + Send + Serialize,
+    Tr: Transport = HttpTransport,
+> {
+    log: ReplicatedLog<T, Tr>,
+    next: u64,
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport>
+    Deliveries<T, Tr>
+{
+    /// Waits for the next message to be delivered.
+    pub async fn recv(&mut self) -> T {
+        let learned = self.log.learned();
+        loop {
+            // Register for notifications before checking the log, so that
+            // entries learned in the meantime are not missed.
+            let notified = learned.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            while let Some(entry) = self.log.entry_at(self.next) {
+                self.next += 1;
+                if let Some(message) = entry {
+                    return message;
+                }
+            }
+            notified.await;
+        }
+    }
+}
+
+/// An instance of a total-order broadcast.
+///
+/// See the [`total_order`](crate::broadcast::total_order) module-level
+/// documentation for more details.
+#[derive(Clone)]
+pub struct TotalOrderBroadcast<
+    T: Clone + Debug + DeserializeOwned + Send + Serialize,
+    Tr: Transport = HttpTransport,
+> {
+    log: ReplicatedLog<T, Tr>,
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static> TotalOrderBroadcast<T> {
+    /// Creates a new instance with a given set of neighbors.
+    ///
+    /// If there are `n` instances, then each instance must be instantiated
+    /// with a URL for all `n - 1` of its neighbors, and an `id` that is
+    /// different from the ids of all other instances.
+    pub fn new(id: u32, neighbors: Vec<Uri>) -> Self {
+        Self::with_transport(id, neighbors, HttpTransport::default())
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport>
+    TotalOrderBroadcast<T, Tr>
+{
+    /// Creates a new instance with a given set of neighbors, that
+    /// communicates with them using the given [`Transport`].
+    pub fn with_transport(id: u32, neighbors: Vec<Uri>, transport: Tr) -> Self {
+        Self {
+            log: ReplicatedLog::with_transport(id, neighbors, transport),
+        }
+    }
+
+    /// Broadcasts a message to all instances, including this one, and
+    /// returns its position in the order of delivery.
+    ///
+    /// If a majority of instances cannot be reached, an error is returned. In
+    /// that case, the message may or may not eventually be delivered.
+    pub async fn broadcast(&self, message: T) -> Result<usize, GenericError> {
+        self.log.append(message).await
+    }
+
+    /// Returns a stream of all messages delivered by this instance,
+    /// starting from the first message that was ever broadcast.
+    pub fn subscribe(&self) -> Deliveries<T, Tr> {
+        Deliveries {
+            log: self.log.clone(),
+            next: 0,
+        }
+    }
+
+    /// Learns about every message that was broadcast before this method was
+    /// called, so that they can be delivered.
+    ///
+    /// If a majority of instances cannot be reached, an error is returned.
+    pub async fn sync(&self) -> Result<(), GenericError> {
+        self.log.read().await?;
+        Ok(())
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport> Handler
+    for TotalOrderBroadcast<T, Tr>
+{
+    fn handle(
+        &self,
+        message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        self.log.handle(message)
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport>
+    Service<Request<Incoming>> for TotalOrderBroadcast<T, Tr>
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        self.log.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod total_order_broadcast {
+        use super::*;
+
+        mod broadcast {
+            use super::*;
+
+            #[tokio::test]
+            async fn returns_position_of_message() {
+                let broadcast: TotalOrderBroadcast<u32> = TotalOrderBroadcast::new(1, Vec::new());
+                assert_eq!(0, broadcast.broadcast(123).await.unwrap());
+                assert_eq!(1, broadcast.broadcast(456).await.unwrap());
+            }
+        }
+
+        mod subscribe {
+            use super::*;
+
+            #[tokio::test]
+            async fn receives_earlier_messages() {
+                let broadcast: TotalOrderBroadcast<u32> = TotalOrderBroadcast::new(1, Vec::new());
+                broadcast.broadcast(1).await.unwrap();
+                let mut deliveries = broadcast.subscribe();
+                broadcast.broadcast(2).await.unwrap();
+                assert_eq!(deliveries.recv().await, 1);
+                assert_eq!(deliveries.recv().await, 2);
+            }
+
+            #[tokio::test]
+            async fn skips_entries_from_sync() {
+                let broadcast: TotalOrderBroadcast<u32> = TotalOrderBroadcast::new(1, Vec::new());
+                let mut deliveries = broadcast.subscribe();
+                broadcast.sync().await.unwrap();
+                broadcast.broadcast(1).await.unwrap();
+                assert_eq!(deliveries.recv().await, 1);
+            }
+
+            #[tokio::test]
+            async fn waits_for_later_messages() {
+                let broadcast: TotalOrderBroadcast<u32> = TotalOrderBroadcast::new(1, Vec::new());
+                let mut deliveries = broadcast.subscribe();
+                let handle = tokio::spawn(async move { deliveries.recv().await });
+                tokio::task::yield_now().await;
+                broadcast.broadcast(123).await.unwrap();
+                assert_eq!(handle.await.unwrap(), 123);
+            }
+        }
+    }
+}
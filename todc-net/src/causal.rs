@@ -0,0 +1,26 @@
+//! Primitives for tracking and respecting causality between events.
+//!
+//! An event _happens before_ another if both occur at the same instance, one
+//! after the other, or if the first is the sending of a message and the
+//! second is its receipt, or if they are related transitively by these two
+//! rules. Events that are not related in this way are _concurrent_.
+//!
+//! This module contains:
+//!
+//! * [`VectorClock`], a logical clock that captures exactly the
+//!   happens-before relation between events.
+//! * [`CausalBroadcast`], which delivers messages in an order that respects
+//!   happens-before. If the broadcast of one message happens before the
+//!   broadcast of another, then every instance delivers the first message
+//!   before the second. Concurrent messages may be delivered in different
+//!   orders by different instances.
+//!
+//! # Examples
+//!
+//! See the [`vector_clock`] and [`broadcast`] module-level documentation for
+//! examples.
+pub mod broadcast;
+pub mod vector_clock;
+
+pub use self::broadcast::CausalBroadcast;
+pub use self::vector_clock::VectorClock;
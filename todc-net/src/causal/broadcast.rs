@@ -0,0 +1,380 @@
+//! Causal broadcast, in which messages are delivered in an order that
+//! respects happens-before, as described by Birman, Schiper and Stephenson in
+//! _Lightweight Causal and Atomic Group Multicast_.
+//!
+//! Each instance keeps a [`VectorClock`] that counts the messages it has
+//! delivered from each instance, and attaches a copy of it to every message
+//! that it broadcasts. When a message arrives, it is buffered until the
+//! receiving instance has delivered every message that its sender had
+//! delivered before broadcasting it, along with all earlier messages from
+//! the same sender.
+//!
+//! Like a [`ReliableBroadcast`](crate::broadcast::ReliableBroadcast), each
+//! instance relays a message to all of its neighbors when it first receives
+//! it, so a message that is delivered by any correct instance is eventually
+//! delivered by all of them.
+//!
+//! # Examples
+//!
+//! Instances of [`CausalBroadcast`] handle messages from their neighbors by
+//! responding to `POST` requests made to `/causal/broadcast`, in the same
+//! way as an [`AtomicRegister`](crate::register::AtomicRegister).
+//!
+//! ```
+//! # use tokio_test;
+//! use todc_net::causal::CausalBroadcast;
+//!
+//! # tokio_test::block_on(async {
+//! let broadcast: CausalBroadcast<String> = CausalBroadcast::new(1, Vec::new());
+//! let mut deliveries = broadcast.subscribe();
+//!
+//! broadcast.broadcast(String::from("question")).await.unwrap();
+//! broadcast.broadcast(String::from("answer")).await.unwrap();
+//! assert_eq!(deliveries.recv().await.unwrap(), "question");
+//! assert_eq!(deliveries.recv().await.unwrap(), "answer");
+//! # })
+//! ```
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::http::StatusCode;
+use hyper::service::Service;
+use hyper::{Method, Request, Response, Uri};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use super::VectorClock;
+use crate::transport::{self, Handler, HttpTransport, Transport};
+use crate::{mk_response, GenericError};
+
+const CAUSAL_PATH: &str = "/causal/broadcast";
+
+/// A broadcast message, along with the clock of its sender at the time it
+/// was broadcast.
+///
+/// The entry of the clock for the sender includes the message itself, and
+/// so uniquely identifies the message among those broadcast by the sender.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Envelope<T> {
+    origin: u32,
+    clock: VectorClock,
+    payload: T,
+}
+
+impl<T> Envelope<T> {
+    /// Returns true if every message that causally precedes this one has
+    /// already been delivered.
+    fn is_deliverable(&self, delivered: &VectorClock) -> bool {
+        self.clock.get(self.origin) == delivered.get(self.origin) + 1
+            && self
+                .clock
+                .iter()
+                .filter(|(id, _)| *id != self.origin)
+                .all(|(id, count)| count <= delivered.get(id))
+    }
+
+    /// Returns true if both envelopes contain the same message.
+    fn is_same_as(&self, other: &Envelope<T>) -> bool {
+        self.origin == other.origin && self.clock.get(self.origin) == other.clock.get(other.origin)
+    }
+}
+
+/// The state of an instance, which must be updated atomically.
+struct State<T> {
+    /// The number of messages delivered from each instance.
+    delivered: VectorClock,
+    /// Messages that have been received but not yet delivered.
+    pending: Vec<Envelope<T>>,
+    subscribers: Vec<UnboundedSender<T>>,
+}
+
+impl<T: Clone> State<T> {
+    /// Delivers a message to all subscribers that are still listening.
+    fn deliver(&mut self, envelope: Envelope<T>) {
+        self.delivered.increment(envelope.origin);
+        self.subscribers
+            .retain(|s| s.send(envelope.payload.clone()).is_ok());
+    }
+}
+
+/// A stream of messages delivered by a [`CausalBroadcast`].
+pub struct Deliveries<T> {
+    receiver: UnboundedReceiver<T>,
+}
+
+impl<T> Deliveries<T> {
+    /// Waits for the next message to be delivered.
+    ///
+    /// Returns `None` once every clone of the [`CausalBroadcast`] instance
+    /// that this stream subscribed to has been dropped.
+    pub async fn recv(&mut self) -> Option<T> {
+        self.receiver.recv().await
+    }
+}
+
+/// An instance of a causal broadcast.
+///
+/// See the [`broadcast`](crate::causal::broadcast) module-level
+/// documentation for more details.
+#[derive(Clone)]
+pub struct CausalBroadcast<
+    T: Clone + Debug + DeserializeOwned + Send + Serialize,
+    Tr: Transport = HttpTransport,
+> {
+    id: u32,
+    transport: Tr,
+    neighbors: Arc<Vec<Uri>>,
+    state: Arc<Mutex<State<T>>>,
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static> CausalBroadcast<T> {
+    /// Creates a new instance with a given set of neighbors.
+    ///
+    /// If there are `n` instances, then each instance must be instantiated
+    /// with a URL for all `n - 1` of its neighbors, and an `id` that is
+    /// different from the ids of all other instances.
+    pub fn new(id: u32, neighbors: Vec<Uri>) -> Self {
+        Self::with_transport(id, neighbors, HttpTransport::default())
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport>
+    CausalBroadcast<T, Tr>
+{
+    /// Creates a new instance with a given set of neighbors, that
+    /// communicates with them using the given [`Transport`].
+    pub fn with_transport(id: u32, neighbors: Vec<Uri>, transport: Tr) -> Self {
+        Self {
+            id,
+            transport,
+            neighbors: Arc::new(neighbors),
+            state: Arc::new(Mutex::new(State {
+                delivered: VectorClock::new(),
+                pending: Vec::new(),
+                subscribers: Vec::new(),
+            })),
+        }
+    }
+
+    /// Broadcasts a message to all instances, including this one.
+    ///
+    /// The message is delivered locally before this method returns, and so
+    /// every message that is broadcast by this instance afterwards is
+    /// delivered after it, by every instance.
+    pub async fn broadcast(&self, message: T) -> Result<(), GenericError> {
+        let envelope = {
+            let mut state = self.state.lock().unwrap();
+            let mut clock = state.delivered.clone();
+            clock.increment(self.id);
+            let envelope = Envelope {
+                origin: self.id,
+                clock,
+                payload: message,
+            };
+            state.deliver(envelope.clone());
+            envelope
+        };
+        self.relay(&envelope)
+    }
+
+    /// Returns a clock that counts the messages this instance has delivered
+    /// from each instance.
+    pub fn clock(&self) -> VectorClock {
+        self.state.lock().unwrap().delivered.clone()
+    }
+
+    /// Returns a stream of the messages delivered by this instance from now
+    /// on.
+    pub fn subscribe(&self) -> Deliveries<T> {
+        let (sender, receiver) = unbounded_channel();
+        self.state.lock().unwrap().subscribers.push(sender);
+        Deliveries { receiver }
+    }
+
+    /// Relays a message that has not been received before, and then
+    /// delivers every buffered message whose causal predecessors have all
+    /// been delivered.
+    fn receive(&self, envelope: Envelope<T>) -> Result<(), GenericError> {
+        {
+            let mut state = self.state.lock().unwrap();
+            let delivered =
+                envelope.clock.get(envelope.origin) <= state.delivered.get(envelope.origin);
+            if delivered || state.pending.iter().any(|e| e.is_same_as(&envelope)) {
+                return Ok(());
+            }
+            state.pending.push(envelope.clone());
+            while let Some(i) = state
+                .pending
+                .iter()
+                .position(|e| e.is_deliverable(&state.delivered))
+            {
+                let envelope = state.pending.swap_remove(i);
+                state.deliver(envelope);
+            }
+        }
+        self.relay(&envelope)
+    }
+
+    /// Sends a message to all neighbors, without waiting for them to
+    /// receive it.
+    fn relay(&self, envelope: &Envelope<T>) -> Result<(), GenericError> {
+        let body: Bytes = serde_json::to_vec(envelope)?.into();
+        for neighbor in self.neighbors.iter().cloned() {
+            let transport = self.transport.clone();
+            let message = transport::Message::announce(CAUSAL_PATH, body.clone());
+            tokio::spawn(async move { transport.send(neighbor, message).await });
+        }
+        Ok(())
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport> Handler
+    for CausalBroadcast<T, Tr>
+{
+    fn handle(
+        &self,
+        message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        let me = self.clone();
+        async move {
+            if message.path != CAUSAL_PATH {
+                return Err(GenericError::from(format!(
+                    "Unknown route {}",
+                    message.path
+                )));
+            }
+            let body = message.body.ok_or("Broadcast messages must have a body")?;
+            me.receive(serde_json::from_slice(&body)?)?;
+            Ok(serde_json::to_vec(&())?.into())
+        }
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport>
+    Service<Request<Incoming>> for CausalBroadcast<T, Tr>
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        let me = self.clone();
+        match (req.method(), req.uri().path()) {
+            // POST requests contain messages broadcast by other instances.
+            (&Method::POST, CAUSAL_PATH) => Box::pin(async move {
+                let body = req.collect().await?.to_bytes();
+                match me
+                    .handle(transport::Message::announce(CAUSAL_PATH, body))
+                    .await
+                {
+                    Ok(reply) => Ok(Response::new(Full::new(reply))),
+                    Err(error) => mk_response(StatusCode::BAD_REQUEST, error.to_string().into()),
+                }
+            }),
+            _ => Box::pin(async { mk_response(StatusCode::NOT_FOUND, "404 Not Found".into()) }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a message from another instance, whose sender had delivered
+    /// messages according to the given clock.
+    fn envelope(origin: u32, entries: &[(u32, u64)], payload: u32) -> Envelope<u32> {
+        let mut clock = VectorClock::new();
+        for &(id, count) in entries {
+            for _ in 0..count {
+                clock.increment(id);
+            }
+        }
+        Envelope {
+            origin,
+            clock,
+            payload,
+        }
+    }
+
+    mod causal_broadcast {
+        use super::*;
+
+        mod broadcast {
+            use super::*;
+
+            #[tokio::test]
+            async fn delivers_message_locally() {
+                let broadcast: CausalBroadcast<u32> = CausalBroadcast::new(1, Vec::new());
+                let mut deliveries = broadcast.subscribe();
+                broadcast.broadcast(123).await.unwrap();
+                assert_eq!(deliveries.recv().await, Some(123));
+                assert_eq!(broadcast.clock().get(1), 1);
+            }
+
+            #[tokio::test]
+            async fn includes_delivered_messages_in_clock() {
+                let broadcast: CausalBroadcast<u32> = CausalBroadcast::new(1, Vec::new());
+                broadcast.receive(envelope(2, &[(2, 1)], 123)).unwrap();
+                broadcast.broadcast(456).await.unwrap();
+                let clock = broadcast.clock();
+                assert_eq!((clock.get(1), clock.get(2)), (1, 1));
+            }
+        }
+
+        mod receive {
+            use super::*;
+
+            #[tokio::test]
+            async fn delivers_message_without_predecessors() {
+                let broadcast: CausalBroadcast<u32> = CausalBroadcast::new(1, Vec::new());
+                let mut deliveries = broadcast.subscribe();
+                broadcast.receive(envelope(2, &[(2, 1)], 123)).unwrap();
+                assert_eq!(deliveries.recv().await, Some(123));
+            }
+
+            #[tokio::test]
+            async fn buffers_message_until_earlier_message_from_sender_arrives() {
+                let broadcast: CausalBroadcast<u32> = CausalBroadcast::new(1, Vec::new());
+                let mut deliveries = broadcast.subscribe();
+                broadcast.receive(envelope(2, &[(2, 2)], 2)).unwrap();
+                assert!(deliveries.receiver.try_recv().is_err());
+                broadcast.receive(envelope(2, &[(2, 1)], 1)).unwrap();
+                assert_eq!(deliveries.recv().await, Some(1));
+                assert_eq!(deliveries.recv().await, Some(2));
+            }
+
+            #[tokio::test]
+            async fn buffers_message_until_message_delivered_by_sender_arrives() {
+                let broadcast: CausalBroadcast<u32> = CausalBroadcast::new(1, Vec::new());
+                let mut deliveries = broadcast.subscribe();
+                broadcast
+                    .receive(envelope(3, &[(2, 1), (3, 1)], 2))
+                    .unwrap();
+                assert!(deliveries.receiver.try_recv().is_err());
+                broadcast.receive(envelope(2, &[(2, 1)], 1)).unwrap();
+                assert_eq!(deliveries.recv().await, Some(1));
+                assert_eq!(deliveries.recv().await, Some(2));
+            }
+
+            #[tokio::test]
+            async fn ignores_duplicate_messages() {
+                let broadcast: CausalBroadcast<u32> = CausalBroadcast::new(1, Vec::new());
+                let mut deliveries = broadcast.subscribe();
+                broadcast.receive(envelope(2, &[(2, 2)], 2)).unwrap();
+                broadcast.receive(envelope(2, &[(2, 2)], 2)).unwrap();
+                broadcast.receive(envelope(2, &[(2, 1)], 1)).unwrap();
+                broadcast.receive(envelope(2, &[(2, 1)], 1)).unwrap();
+                drop(broadcast);
+                assert_eq!(deliveries.recv().await, Some(1));
+                assert_eq!(deliveries.recv().await, Some(2));
+                assert_eq!(deliveries.recv().await, None);
+            }
+        }
+    }
+}
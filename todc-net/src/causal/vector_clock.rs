@@ -0,0 +1,192 @@
+//! Logical clocks that capture the happens-before relation, as described
+//! independently by Fidge and Mattern.
+//!
+//! A [`VectorClock`] maps the id of each instance to the number of events
+//! that have occurred at that instance. An instance increments its own entry
+//! whenever an event occurs, and _merges_ the clock attached to each message
+//! it receives into its own. One event happens before another if and only
+//! if the clock of the first is strictly less than the clock of the second.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_net::causal::VectorClock;
+//!
+//! let mut first = VectorClock::new();
+//! first.increment(1);
+//!
+//! // An instance receives a message stamped with the first clock.
+//! let mut second = VectorClock::new();
+//! second.increment(2);
+//! second.merge(&first);
+//! second.increment(2);
+//! assert!(first.happened_before(&second));
+//!
+//! // An event at the first instance that does not know about the second.
+//! first.increment(1);
+//! assert!(first.is_concurrent_with(&second));
+//! ```
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A vector clock, with an entry for each instance.
+///
+/// Instances are identified by the same ids that are used elsewhere in this
+/// crate, such as by a [`CausalBroadcast`](crate::causal::CausalBroadcast).
+/// Entries for instances that have never been incremented are implicitly
+/// zero, so clocks do not need to know the total number of instances.
+///
+/// Clocks are partially ordered. Two clocks compare as [`None`] if they
+/// are concurrent.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct VectorClock(BTreeMap<u32, u64>);
+
+impl VectorClock {
+    /// Creates a new clock, in which every entry is zero.
+    pub fn new() -> Self {
+        Self(BTreeMap::new())
+    }
+
+    /// Returns the entry for an instance.
+    pub fn get(&self, id: u32) -> u64 {
+        self.0.get(&id).copied().unwrap_or(0)
+    }
+
+    /// Increments the entry for an instance, and returns its new value.
+    pub fn increment(&mut self, id: u32) -> u64 {
+        let entry = self.0.entry(id).or_insert(0);
+        *entry += 1;
+        *entry
+    }
+
+    /// Sets each entry of this clock to the maximum of its value and the
+    /// value of the corresponding entry in another clock.
+    pub fn merge(&mut self, other: &VectorClock) {
+        for (&id, &count) in other.0.iter() {
+            let entry = self.0.entry(id).or_insert(0);
+            *entry = (*entry).max(count);
+        }
+    }
+
+    /// Returns true if the event with this clock happened before the event
+    /// with the other clock.
+    pub fn happened_before(&self, other: &VectorClock) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Less)
+    }
+
+    /// Returns true if neither event happened before the other.
+    pub fn is_concurrent_with(&self, other: &VectorClock) -> bool {
+        self.partial_cmp(other).is_none()
+    }
+
+    /// Returns an iterator over the ids of instances and their non-zero
+    /// entries, in increasing order of id.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u64)> + '_ {
+        self.0
+            .iter()
+            .filter(|(_, &count)| count > 0)
+            .map(|(&id, &count)| (id, count))
+    }
+}
+
+impl PartialEq for VectorClock {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl Eq for VectorClock {}
+
+impl PartialOrd for VectorClock {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let (mut less, mut greater) = (false, false);
+        for id in self.0.keys().chain(other.0.keys()) {
+            match self.get(*id).cmp(&other.get(*id)) {
+                Ordering::Less => less = true,
+                Ordering::Greater => greater = true,
+                Ordering::Equal => {}
+            }
+        }
+        match (less, greater) {
+            (false, false) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (true, true) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(entries: &[(u32, u64)]) -> VectorClock {
+        VectorClock(entries.iter().copied().collect())
+    }
+
+    mod increment {
+        use super::*;
+
+        #[test]
+        fn returns_new_entry() {
+            let mut clock = VectorClock::new();
+            assert_eq!(clock.increment(1), 1);
+            assert_eq!(clock.increment(1), 2);
+            assert_eq!(clock.get(1), 2);
+            assert_eq!(clock.get(2), 0);
+        }
+    }
+
+    mod merge {
+        use super::*;
+
+        #[test]
+        fn takes_maximum_of_each_entry() {
+            let mut first = clock(&[(1, 3), (2, 1)]);
+            first.merge(&clock(&[(2, 2), (3, 1)]));
+            assert_eq!(first, clock(&[(1, 3), (2, 2), (3, 1)]));
+        }
+    }
+
+    mod partial_cmp {
+        use super::*;
+
+        #[test]
+        fn treats_missing_entries_as_zero() {
+            assert_eq!(clock(&[(1, 0)]), VectorClock::new());
+            assert!(VectorClock::new().happened_before(&clock(&[(1, 1)])));
+        }
+
+        #[test]
+        fn is_less_if_no_entry_is_greater() {
+            let first = clock(&[(1, 1), (2, 2)]);
+            let second = clock(&[(1, 1), (2, 3)]);
+            assert!(first < second);
+            assert!(first.happened_before(&second));
+            assert!(!second.happened_before(&first));
+        }
+
+        #[test]
+        fn is_none_if_some_entries_are_less_and_others_greater() {
+            let first = clock(&[(1, 2), (2, 1)]);
+            let second = clock(&[(1, 1), (2, 2)]);
+            assert_eq!(first.partial_cmp(&second), None);
+            assert!(first.is_concurrent_with(&second));
+        }
+    }
+
+    mod serialize {
+        use super::*;
+
+        #[test]
+        fn round_trips_through_json() {
+            let clock = clock(&[(1, 2), (3, 4)]);
+            let json = serde_json::to_string(&clock).unwrap();
+            assert_eq!(json, r#"{"1":2,"3":4}"#);
+            assert_eq!(serde_json::from_str::<VectorClock>(&json).unwrap(), clock);
+        }
+    }
+}
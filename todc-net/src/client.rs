@@ -0,0 +1,756 @@
+//! Clients for interacting with objects served over HTTP.
+//!
+//! Rather than constructing requests by hand, applications can use a
+//! [`RegisterClient`] to read from and write to an
+//! [`AtomicRegister`](crate::register::AtomicRegister) that is being served
+//! by some instance.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use tokio_test;
+//! use std::time::Duration;
+//! use hyper::Uri;
+//! use todc_net::client::{RegisterClient, RetryPolicy};
+//!
+//! # tokio_test::block_on(async {
+//! let url = Uri::from_static("http://my-register-1.com");
+//! let client: RegisterClient<String> = RegisterClient::new(url)
+//!     .with_timeout(Duration::from_secs(5))
+//!     .with_retry_policy(RetryPolicy::new(3, Duration::from_millis(100)));
+//!
+//! client.write(String::from("Hello, World!")).await.unwrap();
+//! assert_eq!(client.read().await.unwrap(), "Hello, World!");
+//! # })
+//! ```
+//!
+//! A [`RegisterClient`] depends on the instance that it sends requests to,
+//! which coordinates each operation on its behalf. A [`QuorumClient`] instead
+//! performs the protocol of Attiya, Bar-Noy and Dolev
+//! [\[ABD95\]](https://dl.acm.org/doi/pdf/10.1145/200836.200869) itself, by
+//! contacting a quorum of instances directly. This saves a round-trip to
+//! the coordinating instance, and operations continue to succeed as long as
+//! a quorum of instances is available, no matter which ones.
+//!
+//! ```no_run
+//! # use tokio_test;
+//! use hyper::Uri;
+//! use todc_net::client::QuorumClient;
+//!
+//! # tokio_test::block_on(async {
+//! let urls: Vec<Uri> = (1..4)
+//!     .map(|i| format!("http://my-register-{i}.com").parse().unwrap())
+//!     .collect();
+//! let client: QuorumClient<String> = QuorumClient::new(urls);
+//!
+//! client.write(String::from("Hello, World!")).await.unwrap();
+//! assert_eq!(client.read().await.unwrap(), "Hello, World!");
+//! # })
+//! ```
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use hyper::header::HeaderMap;
+use hyper::http::uri::PathAndQuery;
+use hyper::http::StatusCode;
+use hyper::Uri;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+#[cfg(feature = "history")]
+use todc_utils::linearizability::history::ProcessId;
+#[cfg(feature = "history")]
+use todc_utils::specifications::register::RegisterOperation;
+use tokio::task::JoinSet;
+
+use crate::quorum::{QuorumError, QuorumPolicy};
+use crate::register::abd_95::{LocalValue, LOCAL_PATH, PROTOCOL_VERSIONS, REQUEST_ID_HEADER};
+#[cfg(feature = "history")]
+use crate::register::history::HistorySink;
+use crate::register::label::Label;
+use crate::time::{self, Clock};
+use crate::transport::http::{EPOCH_HEADER, VERSION_HEADER};
+use crate::GenericError;
+
+/// The route at which a register instance serves reads and writes.
+const REGISTER_PATH: &str = "/register";
+
+/// A policy for retrying requests that fail.
+///
+/// Requests are retried if they cannot be delivered, time out, or are
+/// answered with a server error, such as when the instance cannot reach a
+/// majority of its neighbors. Requests that are rejected as invalid are
+/// never retried.
+///
+/// After each failed attempt, the client waits before trying again. The
+/// first wait lasts for `backoff`, and each subsequent wait is twice as long
+/// as the previous one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates a policy that retries a request up to `max_retries` times.
+    pub fn new(max_retries: u32, backoff: Duration) -> Self {
+        Self {
+            max_retries,
+            backoff,
+        }
+    }
+
+    /// Creates a policy that never retries requests.
+    pub fn never() -> Self {
+        Self::new(0, Duration::ZERO)
+    }
+
+    /// Returns how long to wait before the given retry, where the first
+    /// retry is numbered `0`.
+    fn backoff(&self, retry: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(retry))
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Creates a policy that never retries requests.
+    fn default() -> Self {
+        Self::never()
+    }
+}
+
+/// The reason that an attempt to make a request failed.
+enum Failure {
+    /// A failure that may not occur if the request is made again.
+    Transient(GenericError),
+    /// A failure that will occur again if the request is repeated.
+    Permanent(GenericError),
+}
+
+/// A client for an [`AtomicRegister`](crate::register::AtomicRegister)
+/// containing values of type `T`.
+///
+/// The client sends values to, and receives values from, the instance at
+/// its base URL as JSON. See the [`client`](crate::client) module-level
+/// documentation for more details.
+pub struct RegisterClient<T> {
+    url: Uri,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    clock: Arc<dyn Clock>,
+    contents: PhantomData<fn() -> T>,
+    #[cfg(feature = "history")]
+    history: Option<HistorySink<T>>,
+}
+
+impl<T> Clone for RegisterClient<T> {
+    fn clone(&self) -> Self {
+        Self {
+            url: self.url.clone(),
+            timeout: self.timeout,
+            retry_policy: self.retry_policy,
+            clock: self.clock.clone(),
+            contents: PhantomData,
+            #[cfg(feature = "history")]
+            history: self.history.clone(),
+        }
+    }
+}
+
+impl<T> Debug for RegisterClient<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegisterClient")
+            .field("url", &self.url)
+            .field("timeout", &self.timeout)
+            .field("retry_policy", &self.retry_policy)
+            .finish()
+    }
+}
+
+impl<T: Clone + DeserializeOwned + Serialize> RegisterClient<T> {
+    /// Creates a client for the register instance served at `base_url`.
+    ///
+    /// Requests are made to the `/register` route, relative to any path
+    /// contained in `base_url`. By default, requests never time out and are
+    /// never retried.
+    pub fn new(base_url: Uri) -> Self {
+        Self {
+            url: base_url,
+            timeout: None,
+            retry_policy: RetryPolicy::default(),
+            clock: time::default_clock(),
+            contents: PhantomData,
+            #[cfg(feature = "history")]
+            history: None,
+        }
+    }
+
+    /// Sets how long each attempt to make a request may take before it is
+    /// abandoned.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Records the call and response of every operation performed by this
+    /// client, and its clones, in the given sink.
+    ///
+    /// Operations are recorded as the client observes them, so the history
+    /// of a real deployment can be checked from the outside, without
+    /// recording anything in the instances themselves. Operations that fail
+    /// are recorded without a response, since they may or may not have
+    /// taken effect. See the [`history`](crate::register::history)
+    /// module-level documentation for how such operations are checked.
+    ///
+    /// This method requires the `history` feature.
+    #[cfg(feature = "history")]
+    pub fn with_history(mut self, sink: HistorySink<T>) -> Self {
+        self.history = Some(sink);
+        self
+    }
+
+    /// Records the call of an operation, if this client records its
+    /// operations, and returns the process that performed it.
+    #[cfg(feature = "history")]
+    fn record_call(&self, operation: RegisterOperation<T>) -> Option<ProcessId> {
+        self.history.as_ref().map(|sink| sink.call(operation))
+    }
+
+    /// Records the response of an operation, if this client records its
+    /// operations.
+    #[cfg(feature = "history")]
+    fn record_response(&self, process: Option<ProcessId>, operation: RegisterOperation<T>) {
+        if let (Some(sink), Some(process)) = (&self.history, process) {
+            sink.respond(process, operation);
+        }
+    }
+
+    /// Sets the policy for retrying requests that fail.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the [`Clock`] used to time out requests, and to wait between
+    /// retries.
+    ///
+    /// By default, clients use a [`TokioClock`](crate::time::TokioClock).
+    /// See the [`time`](crate::time) module for details.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Returns the URL to which requests are made.
+    pub fn url(&self) -> Result<Uri, GenericError> {
+        route(&self.url, REGISTER_PATH)
+    }
+
+    /// Returns the value contained in the register.
+    pub async fn read(&self) -> Result<T, GenericError> {
+        #[cfg(feature = "history")]
+        let process = self.record_call(RegisterOperation::Read(None));
+        let body = self.request(None).await?;
+        let value: T = serde_json::from_slice(&body)?;
+        #[cfg(feature = "history")]
+        self.record_response(process, RegisterOperation::Read(Some(value.clone())));
+        Ok(value)
+    }
+
+    /// Sets the contents of the register to the specified value.
+    ///
+    /// Each write is sent with a unique request ID, which is reused if the
+    /// write is retried, so that the instance does not apply it more than
+    /// once. See
+    /// [Idempotent Writes](crate::register::abd_95#idempotent-writes).
+    pub async fn write(&self, value: T) -> Result<(), GenericError> {
+        #[cfg(feature = "history")]
+        let process = self.record_call(RegisterOperation::Write(value.clone()));
+        let body = serde_json::to_vec(&value)?;
+        let id = format!("{:032x}", rand::random::<u128>());
+        self.request(Some((body.into(), id))).await?;
+        #[cfg(feature = "history")]
+        self.record_response(process, RegisterOperation::Write(value));
+        Ok(())
+    }
+
+    /// Makes a request to the register, retrying according to the retry
+    /// policy, and returns the body of the response. Requests with a body
+    /// are sent as `POST` requests, along with their request ID, and all
+    /// others as `GET` requests.
+    async fn request(&self, body: Option<(Bytes, String)>) -> Result<Bytes, GenericError> {
+        let url = self.url()?;
+        let mut retry = 0;
+        loop {
+            let error = match self.attempt(url.clone(), body.clone()).await {
+                Ok(body) => return Ok(body),
+                Err(Failure::Permanent(error)) => return Err(error),
+                Err(Failure::Transient(error)) => error,
+            };
+            if retry >= self.retry_policy.max_retries {
+                return Err(error);
+            }
+            self.clock.sleep(self.retry_policy.backoff(retry)).await;
+            retry += 1;
+        }
+    }
+
+    /// Makes a single attempt at a request.
+    async fn attempt(&self, url: Uri, body: Option<(Bytes, String)>) -> Result<Bytes, Failure> {
+        let request = async {
+            let response = match body {
+                Some((body, id)) => {
+                    let mut headers = HeaderMap::new();
+                    headers.insert(REQUEST_ID_HEADER, id.parse()?);
+                    crate::post_with_headers(url, body, headers).await?
+                }
+                None => crate::get(url).await?,
+            };
+            let status = response.status();
+            let body = response.into_body().collect().await?.to_bytes();
+            Ok::<_, GenericError>((status, body))
+        };
+        let result = match self.timeout {
+            Some(timeout) => match self.clock.timeout(timeout, request).await {
+                Some(result) => result,
+                None => {
+                    let error = format!("Request timed out after {timeout:?}");
+                    return Err(Failure::Transient(error.into()));
+                }
+            },
+            None => request.await,
+        };
+        let (status, body) = result.map_err(Failure::Transient)?;
+        classify(status, body)
+    }
+}
+
+/// A client for an [`AtomicRegister`](crate::register::AtomicRegister)
+/// containing values of type `T`, that performs operations by contacting a
+/// quorum of instances directly.
+///
+/// Rather than asking a single instance to coordinate an operation, the
+/// client sends requests to the `/register/local` route of every instance,
+/// and waits for a quorum of them to reply, exactly as a coordinating
+/// instance would. A read asks for the values of a quorum of instances,
+/// and announces the one with the largest label to a quorum before
+/// returning it. A write also asks for the values of a quorum of instances,
+/// and then announces the new value with a label larger than any that it
+/// was told about.
+///
+/// The client must be given the base URL of _every_ instance of the
+/// register, and exchanges values with them as JSON. Values are ordered by
+/// [`u64`] labels by default, and a different [`Label`] can be chosen with
+/// the second type parameter, which must match that of the instances.
+///
+/// Replies are counted towards a quorum by a [`QuorumPolicy`], which must
+/// match that of the instances. See
+/// [`with_quorum_policy`](QuorumClient::with_quorum_policy). Requests carry
+/// the epoch of the client, if it has one, and are written in the latest
+/// version of [`PROTOCOL_VERSIONS`]. Unlike an instance, the client does not
+/// negotiate an earlier version with instances that reject it, and counts
+/// those instances as unavailable instead.
+///
+/// Since instances only take part in the operations of the client, the
+/// [`Role`](crate::register::abd_95::Role) of an instance does not restrict
+/// the operations that the client performs. Operations performed by the
+/// client are also not recorded in the history of any instance.
+pub struct QuorumClient<T, L = u64> {
+    urls: Vec<Uri>,
+    quorum: QuorumPolicy,
+    epoch: Option<u64>,
+    timeout: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    contents: PhantomData<fn() -> (T, L)>,
+}
+
+impl<T, L> Clone for QuorumClient<T, L> {
+    fn clone(&self) -> Self {
+        Self {
+            urls: self.urls.clone(),
+            quorum: self.quorum.clone(),
+            epoch: self.epoch,
+            timeout: self.timeout,
+            clock: self.clock.clone(),
+            contents: PhantomData,
+        }
+    }
+}
+
+impl<T, L> Debug for QuorumClient<T, L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuorumClient")
+            .field("urls", &self.urls)
+            .field("quorum", &self.quorum)
+            .field("epoch", &self.epoch)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl<T, L> QuorumClient<T, L>
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+    L: Label,
+{
+    /// Creates a client for the register whose instances are served at
+    /// `base_urls`.
+    ///
+    /// Requests are made to the `/register/local` route, relative to any
+    /// path contained in each base URL. By default, operations wait for a
+    /// majority of instances, requests carry no epoch, and requests never
+    /// time out.
+    pub fn new(base_urls: Vec<Uri>) -> Self {
+        Self {
+            urls: base_urls,
+            quorum: QuorumPolicy::Majority,
+            epoch: None,
+            timeout: None,
+            clock: time::default_clock(),
+            contents: PhantomData,
+        }
+    }
+
+    /// Sets the [`QuorumPolicy`] by which replies are counted towards a
+    /// quorum.
+    ///
+    /// The client holds no votes of its own, and so waits for the same
+    /// replies as an operation performed by any one of the instances. The
+    /// weights of a [`WeightedVoting`](crate::quorum::WeightedVoting) policy
+    /// are looked up by the base URLs given to the client, and so each
+    /// instance that does not hold a single vote must be given its weight
+    /// under that URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the instances are too few to tolerate the failure
+    /// threshold of the policy, or if their votes do not suit its weighted
+    /// thresholds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::client::QuorumClient;
+    /// use todc_net::quorum::QuorumPolicy;
+    ///
+    /// let urls: Vec<Uri> = (1..4)
+    ///     .map(|i| format!("http://my-register-{i}.com").parse().unwrap())
+    ///     .collect();
+    /// let client = QuorumClient::<u32>::new(urls.clone())
+    ///     .with_quorum_policy(QuorumPolicy::FailureThreshold(1));
+    /// assert!(client.is_ok());
+    ///
+    /// // Three instances cannot tolerate two crashes.
+    /// let client = QuorumClient::<u32>::new(urls)
+    ///     .with_quorum_policy(QuorumPolicy::FailureThreshold(2));
+    /// assert!(client.is_err());
+    /// ```
+    pub fn with_quorum_policy(mut self, policy: QuorumPolicy) -> Result<Self, QuorumError> {
+        policy.without_vote(&self.urls)?;
+        self.quorum = policy;
+        Ok(self)
+    }
+
+    /// Sets the epoch that is sent with every request. See
+    /// [Epochs](crate::register::abd_95#epochs).
+    ///
+    /// Instances in a later epoch reject requests from the client, and so
+    /// count as unavailable.
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Sets how long each request to an instance may take before it is
+    /// abandoned, and the instance is treated as unavailable.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the [`Clock`] used to time out requests.
+    ///
+    /// By default, clients use a [`TokioClock`](crate::time::TokioClock).
+    /// See the [`time`](crate::time) module for details.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Returns the URLs to which requests are made.
+    pub fn urls(&self) -> Result<Vec<Uri>, GenericError> {
+        self.urls.iter().map(|url| route(url, LOCAL_PATH)).collect()
+    }
+
+    /// Returns the value contained in the register.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a quorum of instances do not reply.
+    pub async fn read(&self) -> Result<T, GenericError> {
+        let max = self.ask().await?;
+        self.announce(&max).await?;
+        Ok(max.value)
+    }
+
+    /// Sets the contents of the register to the specified value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a quorum of instances do not reply, or if the
+    /// largest label of the register cannot be advanced any further. If a
+    /// quorum of instances do not reply to the announcement of the new
+    /// value, then the value may or may not have been written.
+    pub async fn write(&self, value: T) -> Result<(), GenericError> {
+        let max = self.ask().await?;
+        let label = max
+            .label
+            .advance(1)
+            .ok_or("The label of the register cannot be advanced any further")?;
+        self.announce(&LocalValue { label, value }).await
+    }
+
+    /// Returns the value with the largest label among those of a read quorum
+    /// of instances.
+    async fn ask(&self) -> Result<LocalValue<T, L>, GenericError> {
+        let replies = self.broadcast(None).await?;
+        Ok(replies.into_iter().max().unwrap())
+    }
+
+    /// Announces a value to a write quorum of instances.
+    async fn announce(&self, value: &LocalValue<T, L>) -> Result<(), GenericError> {
+        let body = serde_json::to_vec(value)?;
+        self.broadcast(Some(body.into())).await?;
+        Ok(())
+    }
+
+    /// Returns the headers that are sent with every request.
+    fn headers(&self) -> Result<HeaderMap, GenericError> {
+        let mut headers = HeaderMap::new();
+        let version = PROTOCOL_VERSIONS.end().to_string();
+        headers.insert(VERSION_HEADER, version.parse()?);
+        if let Some(epoch) = self.epoch {
+            headers.insert(EPOCH_HEADER, epoch.to_string().parse()?);
+        }
+        Ok(headers)
+    }
+
+    /// Sends a request to every instance, and returns the values with which
+    /// a quorum of them reply. Requests with a body are announcements, which
+    /// wait for the write threshold, and are sent as `POST` requests, while
+    /// all others wait for the read threshold, and are sent as `GET`
+    /// requests.
+    async fn broadcast(&self, body: Option<Bytes>) -> Result<Vec<LocalValue<T, L>>, GenericError> {
+        let quorum = self.quorum.without_vote(&self.urls)?;
+        let thresholds = quorum.thresholds(&self.urls)?;
+        let needed = match body {
+            Some(_) => thresholds.write(),
+            None => thresholds.read(),
+        };
+        let tolerated = thresholds.total() - needed;
+
+        let headers = self.headers()?;
+        let mut handles = JoinSet::new();
+        for (base_url, url) in self.urls.iter().zip(self.urls()?) {
+            let votes = quorum.weight_of(base_url);
+            let body = body.clone();
+            let headers = headers.clone();
+            let timeout = self.timeout;
+            let clock = self.clock.clone();
+            handles.spawn(async move {
+                let request = async {
+                    let response = match body {
+                        Some(body) => crate::post_with_headers(url, body, headers).await?,
+                        None => crate::get_with_headers(url, headers).await?,
+                    };
+                    let status = response.status();
+                    let body = response.into_body().collect().await?.to_bytes();
+                    let body = classify(status, body).map_err(|failure| match failure {
+                        Failure::Transient(error) | Failure::Permanent(error) => error,
+                    })?;
+                    Ok::<_, GenericError>(serde_json::from_slice::<LocalValue<T, L>>(&body)?)
+                };
+                let reply = match timeout {
+                    Some(timeout) => match clock.timeout(timeout, request).await {
+                        Some(reply) => reply,
+                        None => Err(format!("Request timed out after {timeout:?}").into()),
+                    },
+                    None => request.await,
+                };
+                (votes, reply)
+            });
+        }
+
+        // Wait until instances holding enough votes have replied
+        // successfully, or until enough have failed that they never will.
+        let mut replies = Vec::new();
+        let mut acks = 0;
+        let mut failures = 0;
+        while acks < needed && failures <= tolerated {
+            match handles.join_next().await {
+                Some(Ok((votes, Ok(value)))) => {
+                    acks += votes;
+                    replies.push(value);
+                }
+                Some(Ok((votes, Err(_)))) => failures += votes,
+                // A request that panicked holds an unknown number of votes,
+                // and so no quorum is counted on it.
+                Some(Err(_)) | None => break,
+            }
+        }
+        if acks >= needed {
+            Ok(replies)
+        } else {
+            let error =
+                format!("A quorum of instances is unavailable ({acks} of {needed} required votes)");
+            Err(error.into())
+        }
+    }
+}
+
+/// Returns the URL of a route, relative to any path contained in a base URL.
+fn route(base_url: &Uri, route: &str) -> Result<Uri, GenericError> {
+    let base = base_url.path().trim_end_matches('/');
+    let path: PathAndQuery = format!("{base}{route}").parse()?;
+    let mut parts = base_url.clone().into_parts();
+    parts.path_and_query = Some(path);
+    Ok(Uri::from_parts(parts)?)
+}
+
+/// Returns the body of a response if it was successful, or otherwise
+/// whether the failure is worth retrying.
+fn classify(status: StatusCode, body: Bytes) -> Result<Bytes, Failure> {
+    if status.is_success() {
+        return Ok(body);
+    }
+    let error = format!("{status}: {}", String::from_utf8_lossy(&body)).into();
+    if status.is_server_error() {
+        Err(Failure::Transient(error))
+    } else {
+        Err(Failure::Permanent(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod retry_policy {
+        use super::*;
+
+        #[test]
+        fn doubles_backoff_after_each_retry() {
+            let policy = RetryPolicy::new(3, Duration::from_millis(10));
+            assert_eq!(policy.backoff(0), Duration::from_millis(10));
+            assert_eq!(policy.backoff(1), Duration::from_millis(20));
+            assert_eq!(policy.backoff(2), Duration::from_millis(40));
+        }
+
+        #[test]
+        fn saturates_instead_of_overflowing() {
+            let policy = RetryPolicy::new(u32::MAX, Duration::MAX);
+            assert_eq!(policy.backoff(u32::MAX), Duration::MAX);
+        }
+    }
+
+    mod register_client {
+        use super::*;
+
+        mod url {
+            use super::*;
+
+            #[test]
+            fn appends_register_route_to_base_url() {
+                let client: RegisterClient<u32> =
+                    RegisterClient::new(Uri::from_static("http://server-0:9999"));
+                assert_eq!(
+                    client.url().unwrap(),
+                    Uri::from_static("http://server-0:9999/register")
+                );
+            }
+
+            #[test]
+            fn preserves_path_of_base_url() {
+                let client: RegisterClient<u32> =
+                    RegisterClient::new(Uri::from_static("http://example.com/api/"));
+                assert_eq!(
+                    client.url().unwrap(),
+                    Uri::from_static("http://example.com/api/register")
+                );
+            }
+        }
+    }
+
+    mod quorum_client {
+        use super::*;
+        use crate::quorum::WeightedVoting;
+
+        #[test]
+        fn appends_local_route_to_each_base_url() {
+            let client: QuorumClient<u32> = QuorumClient::new(vec![
+                Uri::from_static("http://server-0:9999"),
+                Uri::from_static("http://example.com/api/"),
+            ]);
+            assert_eq!(
+                client.urls().unwrap(),
+                vec![
+                    Uri::from_static("http://server-0:9999/register/local"),
+                    Uri::from_static("http://example.com/api/register/local")
+                ]
+            );
+        }
+
+        #[tokio::test]
+        async fn fails_without_any_instances() {
+            let client: QuorumClient<u32> = QuorumClient::new(Vec::new());
+            let error = client.read().await.unwrap_err();
+            assert!(error.downcast_ref::<QuorumError>().is_some());
+        }
+
+        #[test]
+        fn rejects_policy_that_instances_cannot_satisfy() {
+            let urls = vec![
+                Uri::from_static("http://server-0:9999"),
+                Uri::from_static("http://server-1:9999"),
+            ];
+            let voting = WeightedVoting::new(0).with_thresholds(1, 1);
+            let client =
+                QuorumClient::<u32>::new(urls).with_quorum_policy(QuorumPolicy::Weighted(voting));
+            assert!(matches!(client, Err(QuorumError::InvalidThresholds(_))));
+        }
+
+        #[test]
+        fn sends_epoch_and_protocol_version() {
+            let client: QuorumClient<u32> = QuorumClient::new(Vec::new()).with_epoch(3);
+            let headers = client.headers().unwrap();
+            assert_eq!(headers[EPOCH_HEADER], "3");
+            assert_eq!(headers[VERSION_HEADER], PROTOCOL_VERSIONS.end().to_string());
+        }
+    }
+
+    mod classify {
+        use super::*;
+
+        #[test]
+        fn returns_body_of_successful_response() {
+            let body = Bytes::from_static(b"123");
+            assert!(matches!(classify(StatusCode::OK, body), Ok(b) if b == "123"));
+        }
+
+        #[test]
+        fn retries_server_errors() {
+            let result = classify(StatusCode::SERVICE_UNAVAILABLE, Bytes::new());
+            assert!(matches!(result, Err(Failure::Transient(_))));
+        }
+
+        #[test]
+        fn does_not_retry_client_errors() {
+            let result = classify(StatusCode::BAD_REQUEST, Bytes::new());
+            assert!(matches!(result, Err(Failure::Permanent(_))));
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! Running a whole cluster of instances within a single process.
+//!
+//! A real deployment runs each instance of a register in its own process,
+//! usually on its own machine. During local development it is convenient to
+//! instead run an entire cluster in one process, such as in an example or an
+//! integration test. [`run_local`] serves `n` instances of an
+//! [`AtomicRegister`], each on its own port of the loopback interface, wires
+//! each of them up with the URLs of all of the others, and returns a
+//! [`LocalCluster`] from which clients can be created.
+//!
+//! The instances communicate over real TCP connections, exactly as they
+//! would if they ran in separate processes, except when the `turmoil`
+//! feature is enabled, in which case _all_ network IO performed by this
+//! crate goes through the simulated network. To run a cluster in a
+//! simulation, see the [`testing`](crate::testing) module instead.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use tokio_test;
+//! use std::time::Duration;
+//! use todc_net::cluster;
+//!
+//! # tokio_test::block_on(async {
+//! let cluster = cluster::run_local::<String>(3, 3000).await.unwrap();
+//! let first = cluster.client(0);
+//! let second = cluster.client(1);
+//!
+//! first.write(String::from("Hello, World!")).await.unwrap();
+//! assert_eq!(second.read().await.unwrap(), "Hello, World!");
+//!
+//! cluster.shutdown(Duration::from_secs(5)).await;
+//! # })
+//! ```
+use std::fmt::Debug;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use hyper::server::conn::http1;
+use hyper::Uri;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::task::JoinSet;
+
+use crate::client::RegisterClient;
+use crate::net::TcpListener;
+use crate::register::AtomicRegister;
+use crate::shutdown::Shutdown;
+use crate::{GenericError, TokioIo};
+
+/// Serves `n` instances of an [`AtomicRegister`] within this process, on
+/// consecutive ports of the loopback interface starting from `base_port`.
+///
+/// Each instance is given the URLs of all of the others as its neighbors.
+/// If `base_port` is `0`, then each instance is instead served on a port
+/// chosen by the operating system, which avoids conflicts when many
+/// clusters run at once, such as in tests. Either way, the URL of each
+/// instance is available through [`LocalCluster::url`].
+///
+/// Each instance can be shut down gracefully through its own [`Shutdown`]
+/// handle, which is available through [`LocalCluster::shutdown_handle`].
+/// The instances are served until they are stopped, or the cluster is
+/// [`shutdown`](LocalCluster::shutdown) or dropped. This function must be
+/// called from within a tokio runtime.
+///
+/// # Errors
+///
+/// Returns an error if any of the ports cannot be bound, such as because it
+/// is already in use, or if the ports would exceed [`u16::MAX`]. Instances
+/// that were already being served are stopped.
+pub async fn run_local<T>(n: usize, base_port: u16) -> Result<LocalCluster<T>, GenericError>
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+{
+    let mut listeners = Vec::with_capacity(n);
+    for i in 0..n {
+        let port = match base_port {
+            0 => 0,
+            _ => u16::try_from(i)
+                .ok()
+                .and_then(|i| base_port.checked_add(i))
+                .ok_or("Ports of the cluster exceed the largest port")?,
+        };
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        listeners.push(TcpListener::bind(addr).await?);
+    }
+    let urls = listeners
+        .iter()
+        .map(|listener| Ok(format!("http://{}", listener.local_addr()?).parse()?))
+        .collect::<Result<Vec<Uri>, GenericError>>()?;
+
+    let mut servers = JoinSet::new();
+    let mut instances = Vec::with_capacity(n);
+    let mut shutdowns = Vec::with_capacity(n);
+    for (i, listener) in listeners.into_iter().enumerate() {
+        let mut neighbors = urls.clone();
+        neighbors.remove(i);
+        let shutdown = Shutdown::new();
+        let register = AtomicRegister::new(neighbors).with_shutdown(shutdown.clone());
+        servers.spawn(serve(listener, register.clone(), shutdown.clone()));
+        instances.push(register);
+        shutdowns.push(shutdown);
+    }
+    tracing::info!(?urls, "serving local cluster");
+    Ok(LocalCluster {
+        instances,
+        urls,
+        shutdowns,
+        servers,
+    })
+}
+
+/// A cluster of instances of an [`AtomicRegister`], served within this
+/// process by [`run_local`].
+///
+/// Dropping the cluster stops every instance immediately, and aborts any
+/// requests that they are in the middle of serving.
+pub struct LocalCluster<T>
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+{
+    instances: Vec<AtomicRegister<T>>,
+    urls: Vec<Uri>,
+    shutdowns: Vec<Shutdown>,
+    servers: JoinSet<()>,
+}
+
+impl<T> LocalCluster<T>
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+{
+    /// Returns the number of instances in the cluster.
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// Returns whether the cluster contains no instances.
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// Returns the URL at which the `i`th instance is served.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not smaller than the number of instances.
+    pub fn url(&self, i: usize) -> &Uri {
+        &self.urls[i]
+    }
+
+    /// Returns the URLs at which the instances are served.
+    pub fn urls(&self) -> &[Uri] {
+        &self.urls
+    }
+
+    /// Returns the `i`th instance in the cluster.
+    ///
+    /// The instance shares its state with the instance being served, so
+    /// operations can be performed on it directly, without going through
+    /// HTTP.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not smaller than the number of instances.
+    pub fn instance(&self, i: usize) -> &AtomicRegister<T> {
+        &self.instances[i]
+    }
+
+    /// Returns a client for the `i`th instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not smaller than the number of instances.
+    pub fn client(&self, i: usize) -> RegisterClient<T> {
+        RegisterClient::new(self.urls[i].clone())
+    }
+
+    /// Returns a client for each instance, in order.
+    pub fn clients(&self) -> Vec<RegisterClient<T>> {
+        (0..self.len()).map(|i| self.client(i)).collect()
+    }
+
+    /// Returns the handle through which the `i`th instance can be shut down
+    /// gracefully, such as to simulate a rolling restart.
+    ///
+    /// Once the handle is stopped, the instance stops accepting connections,
+    /// and any requests that it is in the middle of serving are aborted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not smaller than the number of instances.
+    pub fn shutdown_handle(&self, i: usize) -> &Shutdown {
+        &self.shutdowns[i]
+    }
+
+    /// Shuts down every instance gracefully, and waits until they have
+    /// stopped.
+    ///
+    /// Every instance drains until all operations in flight have completed,
+    /// or until the grace period has passed, and then stops. Instances keep
+    /// serving each other while they drain, so that operations in flight can
+    /// reach a majority. Returns whether every operation completed within
+    /// the grace period.
+    pub async fn shutdown(mut self, grace: Duration) -> bool {
+        for shutdown in &self.shutdowns {
+            shutdown.drain();
+        }
+        let drained = tokio::time::timeout(grace, async {
+            for shutdown in &self.shutdowns {
+                shutdown.drained().await;
+            }
+        })
+        .await
+        .is_ok();
+        for shutdown in &self.shutdowns {
+            shutdown.stop();
+        }
+        for shutdown in &self.shutdowns {
+            shutdown.drained().await;
+        }
+        while self.servers.join_next().await.is_some() {}
+        drained
+    }
+}
+
+/// Serves an instance on connections accepted by the listener, until it is
+/// stopped.
+async fn serve<T>(listener: TcpListener, register: AtomicRegister<T>, shutdown: Shutdown)
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+{
+    // Dropping the set, once the instance is stopped, aborts every
+    // connection that is still open.
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(err) => {
+                        tracing::warn!(error = %err, "failed to accept connection");
+                        continue;
+                    }
+                };
+                let io = TokioIo::new(stream);
+                let register = register.clone();
+                connections.spawn(async move {
+                    if let Err(err) = http1::Builder::new().serve_connection(io, register).await {
+                        tracing::debug!(error = %err, "connection failed");
+                    }
+                });
+            }
+            () = shutdown.stopped() => break,
+        }
+        // Reap connections that have closed, so that the set does not grow
+        // without bound.
+        while connections.try_join_next().is_some() {}
+    }
+}
+
+#[cfg(all(test, not(feature = "turmoil")))]
+mod tests {
+    use super::*;
+
+    mod run_local {
+        use super::*;
+
+        #[tokio::test]
+        async fn gives_each_instance_all_other_instances_as_neighbors() {
+            let cluster = run_local::<u32>(3, 0).await.unwrap();
+            let urls = cluster.urls().to_vec();
+            assert_eq!(
+                cluster.instance(1).neighbors(),
+                vec![urls[0].clone(), urls[2].clone()]
+            );
+        }
+
+        #[tokio::test]
+        async fn replicates_writes_between_instances() {
+            let cluster = run_local::<u32>(3, 0).await.unwrap();
+            let clients = cluster.clients();
+            clients[0].write(123).await.unwrap();
+            for client in &clients {
+                assert_eq!(client.read().await.unwrap(), 123);
+            }
+            cluster.shutdown(Duration::from_secs(5)).await;
+        }
+
+        #[tokio::test]
+        async fn fails_if_port_is_in_use() {
+            let first = run_local::<u32>(1, 0).await.unwrap();
+            let port = first.url(0).port_u16().unwrap();
+            assert!(run_local::<u32>(1, port).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn fails_if_ports_exceed_largest_port() {
+            assert!(run_local::<u32>(3, u16::MAX - 1).await.is_err());
+        }
+    }
+
+    mod shutdown {
+        use super::*;
+
+        #[tokio::test]
+        async fn stops_serving_instances() {
+            let cluster = run_local::<u32>(3, 0).await.unwrap();
+            let client = cluster.client(0);
+            client.write(123).await.unwrap();
+            assert!(cluster.shutdown(Duration::from_secs(5)).await);
+            assert!(client.read().await.is_err());
+        }
+
+        #[tokio::test]
+        async fn drains_operations_in_flight() {
+            let cluster = run_local::<u32>(3, 0).await.unwrap();
+            let register = cluster.instance(0).clone();
+            let write = tokio::spawn(async move { register.write(123).await });
+            // Wait until the write is in flight before shutting down.
+            while cluster.shutdown_handle(0).in_flight() == 0 {
+                tokio::task::yield_now().await;
+            }
+            assert!(cluster.shutdown(Duration::from_secs(5)).await);
+            write.await.unwrap().unwrap();
+        }
+
+        #[tokio::test]
+        async fn keeps_serving_neighbors_of_draining_instance() {
+            let cluster = run_local::<u32>(3, 0).await.unwrap();
+            cluster.shutdown_handle(1).drain();
+            cluster.client(0).write(123).await.unwrap();
+            let status = cluster.client(1).read().await.unwrap_err();
+            assert!(status.to_string().contains("503"), "{status}");
+        }
+    }
+}
@@ -0,0 +1,469 @@
+//! Codecs with which values are serialized before being sent between
+//! instances, or to clients.
+//!
+//! By default, values are serialized as JSON with the [`Json`] codec. JSON
+//! is easy to inspect and debug, but inflates binary payloads considerably.
+//! With the `bincode` or `cbor` features enabled, instances can instead
+//! serialize values with the more compact [`Bincode`] or [`Cbor`] codecs.
+//!
+//! The codec used by an object is selected with a type parameter, for example
+//! the fourth type parameter of an
+//! [`AtomicRegister`](crate::register::AtomicRegister), and must be the same
+//! for every instance of the object.
+//!
+//! # Content Negotiation
+//!
+//! Every codec is identified by a media type, such as `application/json`.
+//! Instances label the messages that they send with the media type of their
+//! codec, in the `Content-Type` header of requests that have a body, and the
+//! `Accept` header of requests that expect one in reply. Instances decode
+//! requests, and encode their replies, with whichever of the following
+//! codecs is named by the request:
+//!
+//! * The codec of the instance itself.
+//! * The [`Json`] codec, which is understood by every instance, so that
+//!   clients such as `curl` can interact with an instance regardless of its
+//!   codec.
+//!
+//! Requests that do not name a media type are assumed to use JSON, and
+//! requests that name any other media type are rejected.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_net::codec::{Codec, Json};
+//!
+//! let bytes = Json::encode(&vec![1, 2, 3]).unwrap();
+//! assert_eq!(bytes, b"[1,2,3]");
+//! let decoded: Vec<u32> = Json::decode(&bytes).unwrap();
+//! assert_eq!(decoded, vec![1, 2, 3]);
+//! ```
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::GenericError;
+
+/// A way of serializing values to, and deserializing values from, bytes.
+pub trait Codec: Clone + Copy + Debug + Default + Send + Sync + 'static {
+    /// The media type of values that are encoded with this codec, such as
+    /// `application/json`.
+    const CONTENT_TYPE: &'static str;
+
+    /// Encodes a value as bytes.
+    fn encode<V: Serialize>(value: &V) -> Result<Vec<u8>, CodecError>;
+
+    /// Encodes a value, appending its bytes to the end of a buffer.
+    ///
+    /// By default, the value is encoded with [`encode`](Codec::encode), and
+    /// then copied into the buffer. Codecs that can write to the buffer
+    /// directly should override this method, so that the memory of the
+    /// buffer can be reused. If an error is returned, the buffer may contain
+    /// part of the encoded value.
+    fn encode_into<V: Serialize>(value: &V, buffer: &mut BytesMut) -> Result<(), CodecError> {
+        buffer.extend_from_slice(&Self::encode(value)?);
+        Ok(())
+    }
+
+    /// Decodes a value from bytes.
+    fn decode<V: DeserializeOwned>(bytes: &[u8]) -> Result<V, CodecError>;
+}
+
+/// An error returned when a value cannot be encoded or decoded.
+#[derive(Debug)]
+pub struct CodecError(GenericError);
+
+impl CodecError {
+    /// Creates an error with the given cause.
+    pub fn new(error: impl Into<GenericError>) -> Self {
+        Self(error.into())
+    }
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for CodecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl From<serde_json::Error> for CodecError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::new(error)
+    }
+}
+
+/// A [`Codec`] that serializes values as JSON.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Json;
+
+impl Codec for Json {
+    const CONTENT_TYPE: &'static str = "application/json";
+
+    fn encode<V: Serialize>(value: &V) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn encode_into<V: Serialize>(value: &V, buffer: &mut BytesMut) -> Result<(), CodecError> {
+        Ok(serde_json::to_writer(buffer.writer(), value)?)
+    }
+
+    fn decode<V: DeserializeOwned>(bytes: &[u8]) -> Result<V, CodecError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// A [`Codec`] that serializes values with
+/// [bincode](https://github.com/bincode-org/bincode).
+///
+/// This codec requires the `bincode` feature.
+#[cfg(feature = "bincode")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl Codec for Bincode {
+    const CONTENT_TYPE: &'static str = "application/x-bincode";
+
+    fn encode<V: Serialize>(value: &V) -> Result<Vec<u8>, CodecError> {
+        bincode::serialize(value).map_err(CodecError::new)
+    }
+
+    fn encode_into<V: Serialize>(value: &V, buffer: &mut BytesMut) -> Result<(), CodecError> {
+        bincode::serialize_into(buffer.writer(), value).map_err(CodecError::new)
+    }
+
+    fn decode<V: DeserializeOwned>(bytes: &[u8]) -> Result<V, CodecError> {
+        bincode::deserialize(bytes).map_err(CodecError::new)
+    }
+}
+
+/// A [`Codec`] that serializes values as [CBOR](https://cbor.io/).
+///
+/// This codec requires the `cbor` feature.
+#[cfg(feature = "cbor")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Codec for Cbor {
+    const CONTENT_TYPE: &'static str = "application/cbor";
+
+    fn encode<V: Serialize>(value: &V) -> Result<Vec<u8>, CodecError> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(value, &mut bytes).map_err(CodecError::new)?;
+        Ok(bytes)
+    }
+
+    fn encode_into<V: Serialize>(value: &V, buffer: &mut BytesMut) -> Result<(), CodecError> {
+        ciborium::into_writer(value, buffer.writer()).map_err(CodecError::new)
+    }
+
+    fn decode<V: DeserializeOwned>(bytes: &[u8]) -> Result<V, CodecError> {
+        ciborium::from_reader(bytes).map_err(CodecError::new)
+    }
+}
+
+/// The codec with which a request is decoded and answered, as negotiated
+/// between the media type named by the request and the codec `C` of the
+/// instance that serves it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Negotiated<C: Codec> {
+    /// The request uses the codec of the instance.
+    Native(PhantomData<C>),
+    /// The request uses JSON.
+    Json,
+}
+
+impl<C: Codec> Negotiated<C> {
+    /// Returns the codec named by the value of a `Content-Type` or `Accept`
+    /// header, or `None` if the instance does not understand any of the
+    /// media types that it names.
+    ///
+    /// Requests that do not name a media type are assumed to use JSON.
+    pub(crate) fn from_header(header: Option<&str>) -> Option<Self> {
+        let Some(header) = header else {
+            return Some(Self::Json);
+        };
+        header.split(',').find_map(|media_type| {
+            // Ignore parameters, such as `charset=utf-8` or `q=0.5`.
+            let media_type = media_type.split(';').next().unwrap_or_default().trim();
+            if media_type.eq_ignore_ascii_case(C::CONTENT_TYPE) {
+                Some(Self::Native(PhantomData))
+            } else if media_type.eq_ignore_ascii_case(Json::CONTENT_TYPE) || media_type == "*/*" {
+                Some(Self::Json)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the media type of the negotiated codec.
+    pub(crate) fn content_type(&self) -> &'static str {
+        match self {
+            Self::Native(_) => C::CONTENT_TYPE,
+            Self::Json => Json::CONTENT_TYPE,
+        }
+    }
+
+    /// Encodes a value with the negotiated codec, appending its bytes to the
+    /// end of a buffer.
+    pub(crate) fn encode_into<V: Serialize>(
+        &self,
+        value: &V,
+        buffer: &mut BytesMut,
+    ) -> Result<(), CodecError> {
+        match self {
+            Self::Native(_) => C::encode_into(value, buffer),
+            Self::Json => Json::encode_into(value, buffer),
+        }
+    }
+
+    /// Decodes a value with the negotiated codec.
+    pub(crate) fn decode<V: DeserializeOwned>(&self, bytes: &[u8]) -> Result<V, CodecError> {
+        match self {
+            Self::Native(_) => C::decode(bytes),
+            Self::Json => Json::decode(bytes),
+        }
+    }
+}
+
+/// A buffer into which values are encoded before they are sent.
+///
+/// Each encoded value shares the memory of the buffer, rather than being
+/// copied out of it. Once every value that was encoded into some memory has
+/// been dropped, that memory is reused for the values encoded after it, so
+/// that an instance that repeatedly encodes values of a similar size does not
+/// need to allocate memory for each of them.
+#[derive(Debug, Default)]
+pub(crate) struct EncodeBuffer(Mutex<BytesMut>);
+
+impl EncodeBuffer {
+    /// Encodes a value with the codec `C`.
+    pub(crate) fn encode<C: Codec, V: Serialize>(&self, value: &V) -> Result<Bytes, CodecError> {
+        self.encode_with(Negotiated::<C>::Native(PhantomData), value)
+    }
+
+    /// Encodes a value with a negotiated codec.
+    pub(crate) fn encode_with<C: Codec, V: Serialize>(
+        &self,
+        codec: Negotiated<C>,
+        value: &V,
+    ) -> Result<Bytes, CodecError> {
+        let mut buffer = self.0.lock().unwrap();
+        let result = codec.encode_into(value, &mut buffer);
+        // The contents of the buffer are split off even if encoding failed,
+        // so that a partially encoded value is never sent.
+        let bytes = buffer.split().freeze();
+        result.map(|()| bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A codec that is distinct from JSON, for testing negotiation.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    struct Text;
+
+    impl Codec for Text {
+        const CONTENT_TYPE: &'static str = "text/plain";
+
+        fn encode<V: Serialize>(value: &V) -> Result<Vec<u8>, CodecError> {
+            Json::encode(value)
+        }
+
+        fn decode<V: DeserializeOwned>(bytes: &[u8]) -> Result<V, CodecError> {
+            Json::decode(bytes)
+        }
+    }
+
+    mod json {
+        use super::*;
+
+        #[test]
+        fn decodes_encoded_values() {
+            let bytes = Json::encode(&(1, "two")).unwrap();
+            let decoded: (u32, String) = Json::decode(&bytes).unwrap();
+            assert_eq!(decoded, (1, String::from("two")));
+        }
+
+        #[test]
+        fn returns_error_for_invalid_bytes() {
+            assert!(Json::decode::<u32>(b"not json").is_err());
+        }
+
+        #[test]
+        fn encodes_into_buffer_as_encode_does() {
+            let mut buffer = BytesMut::from("prefix");
+            Json::encode_into(&(1, "two"), &mut buffer).unwrap();
+            assert_eq!(&buffer[6..], Json::encode(&(1, "two")).unwrap());
+        }
+    }
+
+    mod text {
+        use super::*;
+
+        #[test]
+        fn encodes_into_buffer_by_default() {
+            let mut buffer = BytesMut::new();
+            Text::encode_into(&(1, "two"), &mut buffer).unwrap();
+            assert_eq!(&buffer[..], Text::encode(&(1, "two")).unwrap());
+        }
+    }
+
+    #[cfg(feature = "bincode")]
+    mod bincode {
+        use super::*;
+
+        #[test]
+        fn decodes_encoded_values() {
+            let bytes = Bincode::encode(&(1, "two")).unwrap();
+            let decoded: (u32, String) = Bincode::decode(&bytes).unwrap();
+            assert_eq!(decoded, (1, String::from("two")));
+        }
+
+        #[test]
+        fn encodes_bytes_more_compactly_than_json() {
+            let value = vec![u8::MAX; 64];
+            assert!(Bincode::encode(&value).unwrap().len() < Json::encode(&value).unwrap().len());
+        }
+
+        #[test]
+        fn encodes_into_buffer_as_encode_does() {
+            let mut buffer = BytesMut::new();
+            Bincode::encode_into(&(1, "two"), &mut buffer).unwrap();
+            assert_eq!(&buffer[..], Bincode::encode(&(1, "two")).unwrap());
+        }
+    }
+
+    #[cfg(feature = "cbor")]
+    mod cbor {
+        use super::*;
+
+        #[test]
+        fn decodes_encoded_values() {
+            let bytes = Cbor::encode(&(1, "two")).unwrap();
+            let decoded: (u32, String) = Cbor::decode(&bytes).unwrap();
+            assert_eq!(decoded, (1, String::from("two")));
+        }
+
+        #[test]
+        fn encodes_bytes_more_compactly_than_json() {
+            let value = vec![u8::MAX; 64];
+            assert!(Cbor::encode(&value).unwrap().len() < Json::encode(&value).unwrap().len());
+        }
+
+        #[test]
+        fn encodes_into_buffer_as_encode_does() {
+            let mut buffer = BytesMut::new();
+            Cbor::encode_into(&(1, "two"), &mut buffer).unwrap();
+            assert_eq!(&buffer[..], Cbor::encode(&(1, "two")).unwrap());
+        }
+    }
+
+    mod negotiated {
+        use super::*;
+
+        fn negotiate(header: Option<&str>) -> Option<Negotiated<Text>> {
+            Negotiated::from_header(header)
+        }
+
+        #[test]
+        fn uses_json_if_no_media_type_is_named() {
+            assert_eq!(negotiate(None), Some(Negotiated::Json));
+        }
+
+        #[test]
+        fn uses_json_if_json_is_named() {
+            assert_eq!(negotiate(Some("application/json")), Some(Negotiated::Json));
+        }
+
+        #[test]
+        fn uses_native_codec_if_it_is_named() {
+            assert_eq!(
+                negotiate(Some("text/plain")),
+                Some(Negotiated::Native(PhantomData))
+            );
+        }
+
+        #[test]
+        fn ignores_parameters_and_case() {
+            assert_eq!(
+                negotiate(Some("Text/Plain; charset=utf-8")),
+                Some(Negotiated::Native(PhantomData))
+            );
+        }
+
+        #[test]
+        fn uses_first_understood_media_type() {
+            assert_eq!(
+                negotiate(Some("image/png, text/plain;q=0.9, application/json")),
+                Some(Negotiated::Native(PhantomData))
+            );
+        }
+
+        #[test]
+        fn uses_json_for_wildcard() {
+            assert_eq!(negotiate(Some("*/*")), Some(Negotiated::Json));
+        }
+
+        #[test]
+        fn rejects_unknown_media_types() {
+            assert_eq!(negotiate(Some("image/png")), None);
+        }
+
+        #[test]
+        fn reports_content_type_of_negotiated_codec() {
+            assert_eq!(negotiate(None).unwrap().content_type(), "application/json");
+            assert_eq!(
+                negotiate(Some("text/plain")).unwrap().content_type(),
+                "text/plain"
+            );
+        }
+    }
+
+    mod encode_buffer {
+        use std::collections::HashMap;
+
+        use super::*;
+
+        #[test]
+        fn encoded_values_are_not_changed_by_later_values() {
+            let buffer = EncodeBuffer::default();
+            let first = buffer.encode::<Json, _>(&"first").unwrap();
+            let second = buffer.encode::<Json, _>(&vec![2; 1024]).unwrap();
+            assert_eq!(first, Json::encode(&"first").unwrap());
+            assert_eq!(second, Json::encode(&vec![2; 1024]).unwrap());
+        }
+
+        #[test]
+        fn discards_partially_encoded_values() {
+            let buffer = EncodeBuffer::default();
+            // JSON objects must have string keys.
+            let invalid = HashMap::from([((1, 2), 3)]);
+            assert!(buffer.encode::<Json, _>(&invalid).is_err());
+            assert_eq!(buffer.encode::<Json, _>(&123).unwrap(), "123");
+        }
+
+        #[test]
+        fn encodes_with_negotiated_codec() {
+            let buffer = EncodeBuffer::default();
+            let bytes = buffer
+                .encode_with(Negotiated::<Text>::Json, &(1, "two"))
+                .unwrap();
+            assert_eq!(bytes, Json::encode(&(1, "two")).unwrap());
+        }
+    }
+}
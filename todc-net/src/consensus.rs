@@ -0,0 +1,23 @@
+//! Fault-tolerant [consensus](https://en.wikipedia.org/wiki/Consensus_(computer_science))
+//! between instances that communicate by passing messages.
+//!
+//! This module contains an implementation of the Paxos algorithm, as described
+//! by Lamport [\[Lam98\]](https://doi.org/10.1145/279227.279229), along with a
+//! replicated log built on top of it. Both continue to make progress as long
+//! as a majority of instances are reachable. With the `replication`
+//! feature enabled, the log can also be used to replicate any object that
+//! is described by a sequential specification.
+//!
+//! # Examples
+//!
+//! See the [`paxos`] and [`replicated_log`] module-level documentation for
+//! examples.
+pub mod paxos;
+pub mod replicated_log;
+#[cfg(feature = "replication")]
+pub mod replicated_object;
+
+pub use self::paxos::Paxos;
+pub use self::replicated_log::ReplicatedLog;
+#[cfg(feature = "replication")]
+pub use self::replicated_object::ReplicatedObject;
@@ -0,0 +1,734 @@
+//! Single-decree consensus, as described by Lamport
+//! [\[Lam98\]](https://doi.org/10.1145/279227.279229).
+//!
+//! Each instance of [`Paxos`] plays all three roles of the algorithm:
+//!
+//! * As a _proposer_, it attempts to have a value chosen when
+//!   [`propose`](Paxos::propose) is called.
+//! * As an _acceptor_, it votes on values proposed by its neighbors, by
+//!   responding to `POST` requests made to `/paxos/prepare` and
+//!   `/paxos/accept`.
+//! * As a _learner_, it records the value that was chosen, which is announced
+//!   by `POST` requests made to `/paxos/decide`.
+//!
+//! Once a value has been chosen, every subsequent call to
+//! [`propose`](Paxos::propose), on any instance, returns that value. A value is
+//! chosen as long as a majority of instances are reachable and proposers do
+//! not continually interfere with each other.
+//!
+//! # Examples
+//!
+//! Instances of [`Paxos`] handle requests from their neighbors in the same way
+//! as an [`AtomicRegister`](crate::register::AtomicRegister). In the
+//! following example, we run an instance that lets clients propose values
+//! by making `POST` requests to `/propose`.
+//!
+//! ```no_run
+//! use std::net::SocketAddr;
+//!
+//! use http_body_util::{BodyExt, Full};
+//! use hyper::{Method, Request, Response, Uri};
+//! use hyper::body::{Bytes, Incoming};
+//! use hyper::server::conn::http1;
+//! use hyper::service::{Service, service_fn};
+//! use hyper_util::rt::TokioIo;
+//! use tokio::net::TcpListener;
+//!
+//! use todc_net::consensus::Paxos;
+//!
+//! async fn router(
+//!     paxos: Paxos<String>,
+//!     req: Request<Incoming>
+//! ) -> Result<Response<Full<Bytes>>, Box<dyn std::error::Error + Send + Sync>> {
+//!     match (req.method(), req.uri().path()) {
+//!         // Allow clients to propose values with POST requests
+//!         (&Method::POST, "/propose") => {
+//!             let body = req.collect().await?.to_bytes();
+//!             let value = String::from_utf8(body.to_vec())?;
+//!             let chosen = paxos.propose(value).await?;
+//!             Ok(Response::new(Full::new(Bytes::from(chosen))))
+//!         },
+//!         // Allow the instance to handle messages from its neighbors
+//!         _ => paxos.call(req).await
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let neighbors: Vec<Uri> = vec![
+//!         "http://my-paxos-2.com".parse()?,
+//!         "http://my-paxos-3.com".parse()?,
+//!     ];
+//!     let paxos: Paxos<String> = Paxos::new(1, neighbors);
+//!
+//!     let addr: SocketAddr = ([0, 0, 0, 0], 3000).into();
+//!     let listener = TcpListener::bind(addr).await?;
+//!     loop {
+//!         let (stream, _) = listener.accept().await?;
+//!         let io = TokioIo::new(stream);
+//!         let paxos = paxos.clone();
+//!         tokio::task::spawn(async move {
+//!             if let Err(err) = http1::Builder::new()
+//!                 .serve_connection(io, service_fn(move |req| router(paxos.clone(), req)))
+//!                 .await
+//!             {
+//!                 println!("Error serving connection: {:?}", err)
+//!             }
+//!         });
+//!     }
+//! }
+//! ```
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::http::StatusCode;
+use hyper::service::Service;
+use hyper::{Method, Request, Response, Uri};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+
+use crate::quorum::Resilience;
+use crate::time::{self, Clock};
+use crate::transport::{self, Handler, HttpTransport, Transport};
+use crate::{mk_response, GenericError};
+
+const PREPARE_PATH: &str = "/paxos/prepare";
+const ACCEPT_PATH: &str = "/paxos/accept";
+const DECIDE_PATH: &str = "/paxos/decide";
+
+/// The slot used for single-decree consensus.
+const SINGLE_SLOT: u64 = 0;
+
+/// A ballot number, which orders the proposals made by different proposers.
+///
+/// Ballots are ordered by round first, and then by the id of the proposer,
+/// so that no two proposers ever use the same ballot.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+struct Ballot {
+    round: u32,
+    id: u32,
+}
+
+/// A value proposed with a particular ballot.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+struct Proposal<T> {
+    ballot: Ballot,
+    value: T,
+}
+
+/// The state of a single instance of consensus.
+#[derive(Clone, Debug)]
+struct Slot<T> {
+    /// The largest ballot that this acceptor has promised to respect.
+    promised: Ballot,
+    /// The proposal with the largest ballot that this acceptor has accepted.
+    accepted: Option<Proposal<T>>,
+    /// The value that this learner knows to have been chosen.
+    decided: Option<T>,
+}
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self {
+            promised: Ballot::default(),
+            accepted: None,
+            decided: None,
+        }
+    }
+}
+
+/// A request for acceptors to promise not to accept proposals with smaller
+/// ballots.
+#[derive(Deserialize, Serialize)]
+struct Prepare {
+    slot: u64,
+    ballot: Ballot,
+}
+
+/// The response of an acceptor to a [`Prepare`] message.
+#[derive(Deserialize, Serialize)]
+struct Promise<T> {
+    ok: bool,
+    promised: Ballot,
+    accepted: Option<Proposal<T>>,
+}
+
+/// A request for acceptors to accept a proposal.
+#[derive(Deserialize, Serialize)]
+struct Accept<T> {
+    slot: u64,
+    proposal: Proposal<T>,
+}
+
+/// The response of an acceptor to an [`Accept`] message.
+#[derive(Deserialize, Serialize)]
+struct Accepted {
+    ok: bool,
+    promised: Ballot,
+}
+
+/// An announcement that a value has been chosen.
+#[derive(Deserialize, Serialize)]
+struct Decide<T> {
+    slot: u64,
+    value: T,
+}
+
+/// An instance of the Paxos consensus algorithm.
+///
+/// See the [`paxos`](crate::consensus::paxos) module-level documentation for
+/// more details.
+#[derive(Clone)]
+pub struct Paxos<
+    T: Clone + Debug + DeserializeOwned + Send + Serialize,
+    Tr: Transport = HttpTransport,
+> {
+    id: u32,
+    transport: Tr,
+    neighbors: Arc<Vec<Uri>>,
+    slots: Arc<Mutex<BTreeMap<u64, Slot<T>>>>,
+    max_round: Arc<Mutex<u32>>,
+    learned: Arc<Notify>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static> Paxos<T> {
+    /// Creates a new instance with a given set of neighbors.
+    ///
+    /// If there are `n` instances, then each instance must be instantiated
+    /// with a URL for all `n - 1` of its neighbors, and an `id` that is
+    /// different from the ids of all other instances.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::consensus::Paxos;
+    ///
+    /// let neighbor_urls: Vec<Uri> = (2..4)
+    ///     .map(|i| format!("https://my-paxos-{i}").parse().unwrap())
+    ///     .collect();
+    ///
+    /// let paxos: Paxos<String> = Paxos::new(1, neighbor_urls);
+    /// ```
+    pub fn new(id: u32, neighbors: Vec<Uri>) -> Self {
+        Self::with_transport(id, neighbors, HttpTransport::default())
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport> Paxos<T, Tr> {
+    /// Creates a new instance with a given set of neighbors, that
+    /// communicates with them using the given [`Transport`].
+    pub fn with_transport(id: u32, neighbors: Vec<Uri>, transport: Tr) -> Self {
+        Self {
+            id,
+            transport,
+            neighbors: Arc::new(neighbors),
+            slots: Arc::new(Mutex::new(BTreeMap::new())),
+            max_round: Arc::new(Mutex::new(0)),
+            learned: Arc::new(Notify::new()),
+            clock: time::default_clock(),
+        }
+    }
+
+    /// Sets the [`Clock`] used to wait before retrying a proposal that was
+    /// interrupted by another proposer.
+    ///
+    /// By default, instances use a [`TokioClock`](crate::time::TokioClock).
+    /// See the [`time`](crate::time) module for details.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Returns the value that this instance knows to have been chosen, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::consensus::Paxos;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let paxos: Paxos<u32> = Paxos::new(1, Vec::new());
+    /// assert_eq!(paxos.decided(), None);
+    /// paxos.propose(123).await.unwrap();
+    /// assert_eq!(paxos.decided(), Some(123));
+    /// # })
+    /// ```
+    pub fn decided(&self) -> Option<T> {
+        self.decided_at(SINGLE_SLOT)
+    }
+
+    /// Proposes a value, and returns the value that was chosen.
+    ///
+    /// The chosen value may differ from the proposed value if another
+    /// instance proposed a value concurrently, or had already had its value
+    /// chosen.
+    ///
+    /// If a majority of instances cannot be reached, an error is returned. In
+    /// that case, the proposed value may or may not eventually be chosen.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::consensus::Paxos;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let paxos: Paxos<u32> = Paxos::new(1, Vec::new());
+    /// assert_eq!(paxos.propose(123).await.unwrap(), 123);
+    /// // Once a value has been chosen, it can never change.
+    /// assert_eq!(paxos.propose(456).await.unwrap(), 123);
+    /// # })
+    /// ```
+    pub async fn propose(&self, value: T) -> Result<T, GenericError> {
+        self.propose_at(SINGLE_SLOT, value).await
+    }
+
+    /// Returns the value that this instance knows to have been chosen for
+    /// a slot, if any.
+    pub(crate) fn decided_at(&self, slot: u64) -> Option<T> {
+        let slots = self.slots.lock().unwrap();
+        slots.get(&slot).and_then(|s| s.decided.clone())
+    }
+
+    /// Returns the first slot for which this instance does not know which
+    /// value was chosen.
+    pub(crate) fn first_undecided(&self) -> u64 {
+        let slots = self.slots.lock().unwrap();
+        let mut slot = 0;
+        while slots.get(&slot).is_some_and(|s| s.decided.is_some()) {
+            slot += 1;
+        }
+        slot
+    }
+
+    /// Proposes a value for a slot, and returns the value that was chosen
+    /// for it.
+    pub(crate) async fn propose_at(&self, slot: u64, value: T) -> Result<T, GenericError> {
+        loop {
+            if let Some(decided) = self.decided_at(slot) {
+                return Ok(decided);
+            }
+
+            // Phase 1: Have a majority of acceptors promise to ignore
+            // proposals with smaller ballots, and learn about the proposals
+            // they have already accepted.
+            let ballot = self.next_ballot();
+            let promises: Vec<Promise<T>> = self
+                .broadcast(PREPARE_PATH, Prepare { slot, ballot })
+                .await?;
+            if promises.iter().any(|p| !p.ok) {
+                self.observe(promises.iter().map(|p| p.promised));
+                self.back_off().await;
+                continue;
+            }
+
+            // Phase 2: Have a majority of acceptors accept the proposal with
+            // the largest ballot that any of them have accepted, or our own
+            // value if there is none.
+            let value = promises
+                .into_iter()
+                .filter_map(|p| p.accepted)
+                .max_by_key(|p| p.ballot)
+                .map_or(value.clone(), |p| p.value);
+            let proposal = Proposal {
+                ballot,
+                value: value.clone(),
+            };
+            let accepted: Vec<Accepted> = self
+                .broadcast(ACCEPT_PATH, Accept { slot, proposal })
+                .await?;
+            if accepted.iter().any(|a| !a.ok) {
+                self.observe(accepted.iter().map(|a| a.promised));
+                self.back_off().await;
+                continue;
+            }
+
+            // The value has been chosen. Announce it to all learners, but
+            // do not wait for them to respond, as any learner that misses
+            // the announcement will discover the value when it next proposes.
+            self.learn(slot, value.clone());
+            self.announce(
+                DECIDE_PATH,
+                Decide {
+                    slot,
+                    value: value.clone(),
+                },
+            )?;
+            return Ok(value);
+        }
+    }
+
+    /// Returns a ballot that is larger than any ballot this instance has
+    /// observed.
+    fn next_ballot(&self) -> Ballot {
+        let mut max_round = self.max_round.lock().unwrap();
+        *max_round += 1;
+        Ballot {
+            round: *max_round,
+            id: self.id,
+        }
+    }
+
+    /// Records the rounds of ballots that were used by other proposers.
+    fn observe(&self, ballots: impl Iterator<Item = Ballot>) {
+        let mut max_round = self.max_round.lock().unwrap();
+        for ballot in ballots {
+            *max_round = (*max_round).max(ballot.round);
+        }
+    }
+
+    /// Waits before retrying a proposal that was interrupted by another
+    /// proposer.
+    ///
+    /// The amount of time depends on the id of this instance, so that
+    /// competing proposers eventually stop interfering with each other.
+    async fn back_off(&self) {
+        let millis = 10 * (u64::from(self.id) % 8 + 1);
+        self.clock.sleep(Duration::from_millis(millis)).await;
+    }
+
+    /// Records the value that was chosen for a slot.
+    fn learn(&self, slot: u64, value: T) {
+        let mut slots = self.slots.lock().unwrap();
+        slots.entry(slot).or_default().decided = Some(value);
+        self.learned.notify_waiters();
+    }
+
+    /// Returns a notification that is triggered whenever this instance
+    /// learns the value chosen for a slot.
+    pub(crate) fn learned(&self) -> Arc<Notify> {
+        self.learned.clone()
+    }
+
+    /// Sends a message to all neighbors of this instance, without waiting
+    /// for them to reply.
+    fn announce<M: Serialize>(&self, path: &'static str, message: M) -> Result<(), GenericError> {
+        let message = transport::Message::announce(path, serde_json::to_vec(&message)?.into());
+        for neighbor in self.neighbors.iter().cloned() {
+            let transport = self.transport.clone();
+            let message = message.clone();
+            tokio::spawn(async move { transport.send(neighbor, message).await });
+        }
+        Ok(())
+    }
+
+    /// Sends a message to this instance and all of its neighbors, and returns
+    /// the replies from a majority of them.
+    async fn broadcast<M: Serialize + Send, R: DeserializeOwned + Send + 'static>(
+        &self,
+        path: &'static str,
+        message: M,
+    ) -> Result<Vec<R>, GenericError> {
+        let message = transport::Message::announce(path, serde_json::to_vec(&message)?.into());
+        let majority = Resilience::majority(self.neighbors.len() + 1).quorum();
+        let mut replies: Vec<R> = vec![serde_json::from_slice(
+            &self.handle(message.clone()).await?,
+        )?];
+
+        let mut handles = JoinSet::new();
+        for neighbor in self.neighbors.iter().cloned() {
+            let transport = self.transport.clone();
+            let message = message.clone();
+            handles.spawn(async move {
+                let reply = transport.send(neighbor, message).await?;
+                Ok::<R, GenericError>(serde_json::from_slice(&reply)?)
+            });
+        }
+
+        while replies.len() < majority {
+            match handles.join_next().await {
+                Some(Ok(Ok(reply))) => replies.push(reply),
+                Some(_) => continue,
+                None => break,
+            }
+        }
+
+        if replies.len() >= majority {
+            Ok(replies)
+        } else {
+            Err(GenericError::from("A majority of neighbors are offline"))
+        }
+    }
+
+    /// Responds to a [`Prepare`] message as an acceptor.
+    fn prepare(&self, prepare: Prepare) -> Promise<T> {
+        self.observe([prepare.ballot].into_iter());
+        let mut slots = self.slots.lock().unwrap();
+        let state = slots.entry(prepare.slot).or_default();
+        let ok = prepare.ballot > state.promised;
+        if ok {
+            state.promised = prepare.ballot;
+        }
+        Promise {
+            ok,
+            promised: state.promised,
+            accepted: state.accepted.clone(),
+        }
+    }
+
+    /// Responds to an [`Accept`] message as an acceptor.
+    fn accept(&self, accept: Accept<T>) -> Accepted {
+        self.observe([accept.proposal.ballot].into_iter());
+        let mut slots = self.slots.lock().unwrap();
+        let state = slots.entry(accept.slot).or_default();
+        let ok = accept.proposal.ballot >= state.promised;
+        if ok {
+            state.promised = accept.proposal.ballot;
+            state.accepted = Some(accept.proposal);
+        }
+        Accepted {
+            ok,
+            promised: state.promised,
+        }
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport> Handler
+    for Paxos<T, Tr>
+{
+    fn handle(
+        &self,
+        message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        let me = self.clone();
+        async move {
+            let body = message.body.ok_or("Paxos messages must have a body")?;
+            let reply = match message.path.as_ref() {
+                PREPARE_PATH => serde_json::to_vec(&me.prepare(serde_json::from_slice(&body)?))?,
+                ACCEPT_PATH => serde_json::to_vec(&me.accept(serde_json::from_slice(&body)?))?,
+                DECIDE_PATH => {
+                    let decide: Decide<T> = serde_json::from_slice(&body)?;
+                    me.learn(decide.slot, decide.value);
+                    serde_json::to_vec(&())?
+                }
+                path => return Err(GenericError::from(format!("Unknown route {path}"))),
+            };
+            Ok(reply.into())
+        }
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport>
+    Service<Request<Incoming>> for Paxos<T, Tr>
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        let me = self.clone();
+        match (req.method(), req.uri().path()) {
+            // POST requests contain messages from proposers and are answered
+            // by this instance in its role as an acceptor or learner.
+            (&Method::POST, PREPARE_PATH | ACCEPT_PATH | DECIDE_PATH) => Box::pin(async move {
+                let path = req.uri().path().to_string();
+                let body = req.collect().await?.to_bytes();
+                match me.handle(transport::Message::announce(path, body)).await {
+                    Ok(reply) => Ok(Response::new(Full::new(reply))),
+                    Err(error) => mk_response(StatusCode::BAD_REQUEST, error.to_string().into()),
+                }
+            }),
+            _ => Box::pin(async { mk_response(StatusCode::NOT_FOUND, "404 Not Found".into()) }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod ballot {
+        use super::*;
+
+        #[test]
+        fn orders_by_round_first() {
+            let first = Ballot { round: 0, id: 1 };
+            let second = Ballot { round: 1, id: 0 };
+            assert!(first < second)
+        }
+
+        #[test]
+        fn orders_by_id_if_rounds_match() {
+            let first = Ballot { round: 1, id: 0 };
+            let second = Ballot { round: 1, id: 1 };
+            assert!(first < second)
+        }
+    }
+
+    mod paxos {
+        use super::*;
+
+        mod prepare {
+            use super::*;
+
+            #[test]
+            fn promises_larger_ballot() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                let ballot = Ballot { round: 1, id: 1 };
+                let promise = paxos.prepare(Prepare { slot: 0, ballot });
+                assert!(promise.ok);
+                assert_eq!(promise.promised, ballot);
+            }
+
+            #[test]
+            fn rejects_smaller_ballot() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                let larger = Ballot { round: 2, id: 1 };
+                let smaller = Ballot { round: 1, id: 1 };
+                paxos.prepare(Prepare {
+                    slot: 0,
+                    ballot: larger,
+                });
+                let promise = paxos.prepare(Prepare {
+                    slot: 0,
+                    ballot: smaller,
+                });
+                assert!(!promise.ok);
+                assert_eq!(promise.promised, larger);
+            }
+
+            #[test]
+            fn returns_accepted_proposal() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                let proposal = Proposal {
+                    ballot: Ballot { round: 1, id: 1 },
+                    value: 123,
+                };
+                paxos.accept(Accept {
+                    slot: 0,
+                    proposal: proposal.clone(),
+                });
+                let promise = paxos.prepare(Prepare {
+                    slot: 0,
+                    ballot: Ballot { round: 2, id: 1 },
+                });
+                assert_eq!(promise.accepted, Some(proposal));
+            }
+
+            #[test]
+            fn treats_slots_independently() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                let ballot = Ballot { round: 1, id: 1 };
+                paxos.prepare(Prepare { slot: 0, ballot });
+                assert!(paxos.prepare(Prepare { slot: 1, ballot }).ok);
+            }
+        }
+
+        mod accept {
+            use super::*;
+
+            #[test]
+            fn accepts_promised_ballot() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                let ballot = Ballot { round: 1, id: 1 };
+                paxos.prepare(Prepare { slot: 0, ballot });
+                let accepted = paxos.accept(Accept {
+                    slot: 0,
+                    proposal: Proposal { ballot, value: 123 },
+                });
+                assert!(accepted.ok);
+            }
+
+            #[test]
+            fn rejects_ballot_smaller_than_promise() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                paxos.prepare(Prepare {
+                    slot: 0,
+                    ballot: Ballot { round: 2, id: 1 },
+                });
+                let accepted = paxos.accept(Accept {
+                    slot: 0,
+                    proposal: Proposal {
+                        ballot: Ballot { round: 1, id: 1 },
+                        value: 123,
+                    },
+                });
+                assert!(!accepted.ok);
+            }
+        }
+
+        mod next_ballot {
+            use super::*;
+
+            #[test]
+            fn is_larger_than_observed_ballots() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                let other = Ballot { round: 5, id: 1 };
+                paxos.observe([other].into_iter());
+                assert!(paxos.next_ballot() > other);
+            }
+        }
+
+        mod propose {
+            use super::*;
+
+            #[tokio::test]
+            async fn returns_proposed_value_if_none_was_chosen() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                assert_eq!(123, paxos.propose(123).await.unwrap());
+            }
+
+            #[tokio::test]
+            async fn returns_previously_chosen_value() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                paxos.propose(123).await.unwrap();
+                assert_eq!(123, paxos.propose(456).await.unwrap());
+            }
+
+            #[tokio::test]
+            async fn adopts_previously_accepted_value() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                paxos.accept(Accept {
+                    slot: 0,
+                    proposal: Proposal {
+                        ballot: Ballot { round: 1, id: 1 },
+                        value: 123,
+                    },
+                });
+                assert_eq!(123, paxos.propose(456).await.unwrap());
+            }
+        }
+
+        mod back_off {
+            use super::*;
+            use crate::time::MockClock;
+
+            #[tokio::test]
+            async fn waits_until_clock_advances() {
+                let clock = MockClock::new();
+                let paxos: Paxos<u32> = Paxos::new(1, Vec::new()).with_clock(clock.clone());
+                let back_off = tokio::spawn(async move { paxos.back_off().await });
+                tokio::task::yield_now().await;
+                assert!(!back_off.is_finished());
+
+                clock.advance(Duration::from_millis(20));
+                back_off.await.unwrap();
+            }
+        }
+
+        mod first_undecided {
+            use super::*;
+
+            #[test]
+            fn skips_decided_slots() {
+                let paxos: Paxos<u32> = Paxos::new(0, Vec::new());
+                paxos.learn(0, 1);
+                paxos.learn(1, 2);
+                paxos.learn(3, 4);
+                assert_eq!(2, paxos.first_undecided());
+            }
+        }
+    }
+}
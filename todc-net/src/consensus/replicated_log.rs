@@ -0,0 +1,306 @@
+//! A linearizable, append-only log that is replicated using
+//! [`Paxos`](crate::consensus::Paxos).
+//!
+//! The log is divided into _slots_, and the entry in each slot is chosen by
+//! a separate instance of consensus. To append a value, an instance proposes
+//! it for the first slot whose entry it does not know, moving on to the next
+//! slot whenever a different entry is chosen.
+//!
+//! To read the log, an instance appends a _marker_ entry, which is not
+//! visible to clients, and returns all entries that precede it. This
+//! guarantees that a read observes every append that completed before it
+//! began, on any instance.
+//!
+//! # Examples
+//!
+//! Instances of [`ReplicatedLog`] handle requests from their neighbors in the
+//! same way as [`Paxos`](crate::consensus::Paxos), by responding to `POST`
+//! requests made to `/paxos/prepare`, `/paxos/accept` and `/paxos/decide`.
+//!
+//! ```
+//! # use tokio_test;
+//! use todc_net::consensus::ReplicatedLog;
+//!
+//! # tokio_test::block_on(async {
+//! let log: ReplicatedLog<String> = ReplicatedLog::new(1, Vec::new());
+//! log.append(String::from("hello")).await.unwrap();
+//! log.append(String::from("world")).await.unwrap();
+//! assert_eq!(log.read().await.unwrap(), vec!["hello", "world"]);
+//! # })
+//! ```
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::service::Service;
+use hyper::{Request, Response, Uri};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use super::Paxos;
+use crate::time::Clock;
+use crate::transport::{self, Handler, HttpTransport, Transport};
+use crate::GenericError;
+
+/// An entry in a slot of the log.
+///
+/// Each entry is uniquely identified by the id of the instance that proposed
+/// it, along with a sequence number, so that an instance can tell whether its
+/// own entry was chosen even if another instance proposed an identical value.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Entry<T> {
+    id: u32,
+    sequence: u64,
+    /// The appended value, or `None` if the entry marks a read.
+    value: Option<T>,
+}
+
+impl<T> Entry<T> {
+    /// Returns true if both entries were proposed by the same operation.
+    fn is_same_as(&self, other: &Entry<T>) -> bool {
+        self.id == other.id && self.sequence == other.sequence
+    }
+}
+
+/// A linearizable log of values, replicated across multiple instances.
+///
+/// See the [`replicated_log`](crate::consensus::replicated_log) module-level
+/// documentation for more details.
+#[derive(Clone)]
+pub struct ReplicatedLog<
+    T: Clone + Debug + DeserializeOwned + Send + Serialize,
+    Tr: Transport = HttpTransport,
+> {
+    id: u32,
+    paxos: Paxos<Entry<T>, Tr>,
+    sequence: Arc<Mutex<u64>>,
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static> ReplicatedLog<T> {
+    /// Creates a new instance of the log with a given set of neighbors.
+    ///
+    /// If there are `n` instances, then each instance must be instantiated
+    /// with a URL for all `n - 1` of its neighbors, and an `id` that is
+    /// different from the ids of all other instances.
+    pub fn new(id: u32, neighbors: Vec<Uri>) -> Self {
+        Self::with_transport(id, neighbors, HttpTransport::default())
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport>
+    ReplicatedLog<T, Tr>
+{
+    /// Creates a new instance of the log with a given set of neighbors, that
+    /// communicates with them using the given [`Transport`].
+    pub fn with_transport(id: u32, neighbors: Vec<Uri>, transport: Tr) -> Self {
+        Self {
+            id,
+            paxos: Paxos::with_transport(id, neighbors, transport),
+            sequence: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// Sets the [`Clock`] used by the underlying instances of consensus.
+    ///
+    /// See [`Paxos::with_clock`] for details.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.paxos = self.paxos.with_clock(clock);
+        self
+    }
+
+    /// Appends a value to the log, and returns its position.
+    ///
+    /// If a majority of instances cannot be reached, an error is returned. In
+    /// that case, the value may or may not eventually appear in the log.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::consensus::ReplicatedLog;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let log: ReplicatedLog<u32> = ReplicatedLog::new(1, Vec::new());
+    /// assert_eq!(log.append(123).await.unwrap(), 0);
+    /// assert_eq!(log.append(456).await.unwrap(), 1);
+    /// # })
+    /// ```
+    pub async fn append(&self, value: T) -> Result<usize, GenericError> {
+        let slot = self.commit(Some(value)).await?;
+        Ok(self.values_before(slot).len())
+    }
+
+    /// Returns the values in the log.
+    ///
+    /// The result includes every value whose [`append`](ReplicatedLog::append)
+    /// completed before this method was called, on any instance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::consensus::ReplicatedLog;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let log: ReplicatedLog<u32> = ReplicatedLog::new(1, Vec::new());
+    /// assert!(log.read().await.unwrap().is_empty());
+    /// log.append(123).await.unwrap();
+    /// assert_eq!(log.read().await.unwrap(), vec![123]);
+    /// # })
+    /// ```
+    pub async fn read(&self) -> Result<Vec<T>, GenericError> {
+        let slot = self.commit(None).await?;
+        Ok(self.values_before(slot))
+    }
+
+    /// Has an entry chosen for some slot of the log, and returns that slot.
+    ///
+    /// Once this method returns, this instance knows the entries that were
+    /// chosen for all preceding slots.
+    pub(crate) async fn commit(&self, value: Option<T>) -> Result<u64, GenericError> {
+        let entry = Entry {
+            id: self.id,
+            sequence: self.next_sequence(),
+            value,
+        };
+        let mut slot = self.paxos.first_undecided();
+        loop {
+            let chosen = self.paxos.propose_at(slot, entry.clone()).await?;
+            if chosen.is_same_as(&entry) {
+                return Ok(slot);
+            }
+            slot += 1;
+        }
+    }
+
+    /// Returns the values appended to the log before a slot, which this
+    /// instance must have already committed an entry to.
+    fn values_before(&self, slot: u64) -> Vec<T> {
+        (0..slot)
+            .filter_map(|slot| self.paxos.decided_at(slot).and_then(|e| e.value))
+            .collect()
+    }
+
+    /// Returns the entry that this instance knows to have been chosen for a
+    /// slot, if any. Entries that mark reads do not contain a value.
+    pub(crate) fn entry_at(&self, slot: u64) -> Option<Option<T>> {
+        self.paxos.decided_at(slot).map(|entry| entry.value)
+    }
+
+    /// Returns a notification that is triggered whenever this instance
+    /// learns the entry chosen for a slot.
+    pub(crate) fn learned(&self) -> Arc<Notify> {
+        self.paxos.learned()
+    }
+
+    /// Returns a sequence number that has not been used by this instance.
+    fn next_sequence(&self) -> u64 {
+        let mut sequence = self.sequence.lock().unwrap();
+        *sequence += 1;
+        *sequence
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport> Handler
+    for ReplicatedLog<T, Tr>
+{
+    fn handle(
+        &self,
+        message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        self.paxos.handle(message)
+    }
+}
+
+impl<T: Clone + Debug + DeserializeOwned + Send + Serialize + 'static, Tr: Transport>
+    Service<Request<Incoming>> for ReplicatedLog<T, Tr>
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        self.paxos.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod entry {
+        use super::*;
+
+        #[test]
+        fn is_same_as_entry_from_same_operation() {
+            let entry = Entry {
+                id: 1,
+                sequence: 1,
+                value: Some(123),
+            };
+            assert!(entry.is_same_as(&entry.clone()));
+        }
+
+        #[test]
+        fn is_not_same_as_identical_value_from_other_instance() {
+            let first = Entry {
+                id: 1,
+                sequence: 1,
+                value: Some(123),
+            };
+            let second = Entry {
+                id: 2,
+                sequence: 1,
+                value: Some(123),
+            };
+            assert!(!first.is_same_as(&second));
+        }
+    }
+
+    mod replicated_log {
+        use super::*;
+
+        mod append {
+            use super::*;
+
+            #[tokio::test]
+            async fn returns_increasing_positions() {
+                let log: ReplicatedLog<u32> = ReplicatedLog::new(1, Vec::new());
+                assert_eq!(0, log.append(1).await.unwrap());
+                assert_eq!(1, log.append(1).await.unwrap());
+                assert_eq!(2, log.append(1).await.unwrap());
+            }
+
+            #[tokio::test]
+            async fn skips_slots_chosen_by_others() {
+                let log: ReplicatedLog<u32> = ReplicatedLog::new(1, Vec::new());
+                let other = Entry {
+                    id: 2,
+                    sequence: 1,
+                    value: Some(456),
+                };
+                log.paxos.propose_at(0, other).await.unwrap();
+                assert_eq!(1, log.append(123).await.unwrap());
+                assert_eq!(vec![456, 123], log.read().await.unwrap());
+            }
+        }
+
+        mod read {
+            use super::*;
+
+            #[tokio::test]
+            async fn does_not_include_read_markers() {
+                let log: ReplicatedLog<u32> = ReplicatedLog::new(1, Vec::new());
+                log.read().await.unwrap();
+                log.append(123).await.unwrap();
+                log.read().await.unwrap();
+                assert_eq!(vec![123], log.read().await.unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,345 @@
+//! Linearizable objects that are replicated using a
+//! [`ReplicatedLog`](crate::consensus::ReplicatedLog).
+//!
+//! This is the message-passing analogue of Herlihy's _universal
+//! construction_ [\[Her91\]](https://doi.org/10.1145/114005.102808), also
+//! known as _state machine replication_. Any object can be replicated, as
+//! long as its behavior is described by a
+//! [`Specification`](todc_utils::Specification). Each instance keeps a copy of
+//! the state of the object, and to perform an operation, an instance appends
+//! it to the log. Every instance then applies the operations in the log to
+//! its copy of the state, in the order that they appear, using
+//! [`Specification::apply`](todc_utils::Specification::apply). As a result,
+//! every operation appears to take effect at the moment its entry is chosen,
+//! and the object is linearizable.
+//!
+//! An operation that is not valid in the state in which it is applied has no
+//! effect on the state of the object.
+//!
+//! This module requires the `replication` feature.
+//!
+//! # Examples
+//!
+//! Consider a bank account that cannot be overdrawn. Operations on the
+//! object are sent to other instances, and so they must implement
+//! [`Serialize`] and [`Deserialize`](serde::Deserialize).
+//!
+//! ```
+//! # use tokio_test;
+//! use serde::{Deserialize, Serialize};
+//! use todc_net::consensus::ReplicatedObject;
+//! use todc_utils::Specification;
+//!
+//! #[derive(Clone, Debug, Deserialize, Serialize)]
+//! enum AccountOperation {
+//!     Deposit(u32),
+//!     Withdraw(u32),
+//! }
+//!
+//! use AccountOperation::{Deposit, Withdraw};
+//!
+//! struct AccountSpecification;
+//!
+//! impl Specification for AccountSpecification {
+//!     type State = u32;
+//!     type Operation = AccountOperation;
+//!
+//!     fn init() -> Self::State {
+//!         0
+//!     }
+//!
+//!     fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+//!         match operation {
+//!             Deposit(amount) => (true, state + amount),
+//!             Withdraw(amount) => match state.checked_sub(*amount) {
+//!                 Some(balance) => (true, balance),
+//!                 None => (false, *state),
+//!             },
+//!         }
+//!     }
+//! }
+//!
+//! # tokio_test::block_on(async {
+//! let account: ReplicatedObject<AccountSpecification> = ReplicatedObject::new(1, Vec::new());
+//! assert_eq!(account.apply(Deposit(100)).await.unwrap(), (true, 100));
+//! assert_eq!(account.apply(Withdraw(150)).await.unwrap(), (false, 100));
+//! assert_eq!(account.apply(Withdraw(30)).await.unwrap(), (true, 70));
+//! assert_eq!(account.read().await.unwrap(), 70);
+//! # })
+//! ```
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::service::Service;
+use hyper::{Request, Response, Uri};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use todc_utils::Specification;
+use tokio::sync::Mutex;
+
+use super::ReplicatedLog;
+use crate::time::Clock;
+use crate::transport::{self, Handler, HttpTransport, Transport};
+use crate::GenericError;
+
+/// The copy of an object that is kept by an instance.
+struct Replica<T> {
+    /// The number of slots of the log that have been applied.
+    applied: u64,
+    state: T,
+}
+
+/// A linearizable object with specification `S`, replicated across multiple
+/// instances.
+///
+/// Operations performed on the same instance are applied one at a time, in
+/// the order that they were called, while operations performed on different
+/// instances may be concurrent.
+///
+/// See the [`replicated_object`](crate::consensus::replicated_object)
+/// module-level documentation for more details.
+pub struct ReplicatedObject<S: Specification, Tr: Transport = HttpTransport>
+where
+    S::Operation: DeserializeOwned + Send + Serialize,
+{
+    log: ReplicatedLog<S::Operation, Tr>,
+    replica: Arc<Mutex<Replica<S::State>>>,
+}
+
+impl<S: Specification> ReplicatedObject<S>
+where
+    S::Operation: DeserializeOwned + Send + Serialize + 'static,
+    S::State: Send,
+{
+    /// Creates a new instance of the object with a given set of neighbors.
+    ///
+    /// If there are `n` instances, then each instance must be instantiated
+    /// with a URL for all `n - 1` of its neighbors, and an `id` that is
+    /// different from the ids of all other instances.
+    pub fn new(id: u32, neighbors: Vec<Uri>) -> Self {
+        Self::with_transport(id, neighbors, HttpTransport::default())
+    }
+}
+
+impl<S: Specification, Tr: Transport> ReplicatedObject<S, Tr>
+where
+    S::Operation: DeserializeOwned + Send + Serialize + 'static,
+    S::State: Send,
+{
+    /// Creates a new instance of the object with a given set of neighbors,
+    /// that communicates with them using the given [`Transport`].
+    pub fn with_transport(id: u32, neighbors: Vec<Uri>, transport: Tr) -> Self {
+        Self {
+            log: ReplicatedLog::with_transport(id, neighbors, transport),
+            replica: Arc::new(Mutex::new(Replica {
+                applied: 0,
+                state: S::init(),
+            })),
+        }
+    }
+
+    /// Sets the [`Clock`] used by the underlying replicated log.
+    ///
+    /// See [`ReplicatedLog::with_clock`] for details.
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.log = self.log.with_clock(clock);
+        self
+    }
+
+    /// Performs an operation on the object, and returns whether it was valid
+    /// along with the state of the object immediately after it was applied.
+    ///
+    /// If the operation was not valid, then the returned state is the state
+    /// in which it was applied, which it did not change.
+    ///
+    /// If a majority of instances cannot be reached, an error is returned. In
+    /// that case, the operation may or may not eventually be applied.
+    pub async fn apply(&self, operation: S::Operation) -> Result<(bool, S::State), GenericError> {
+        let mut replica = self.replica.lock().await;
+        let slot = self.log.commit(Some(operation)).await?;
+        let is_valid = self.apply_through(&mut replica, slot);
+        Ok((is_valid, replica.state.clone()))
+    }
+
+    /// Returns the state of the object.
+    ///
+    /// The result reflects every operation whose
+    /// [`apply`](ReplicatedObject::apply) completed before this method was
+    /// called, on any instance.
+    ///
+    /// If a majority of instances cannot be reached, an error is returned.
+    pub async fn read(&self) -> Result<S::State, GenericError> {
+        let mut replica = self.replica.lock().await;
+        let slot = self.log.commit(None).await?;
+        self.apply_through(&mut replica, slot);
+        Ok(replica.state.clone())
+    }
+
+    /// Applies the operations in every slot of the log up to, and including,
+    /// a slot that this instance has committed an entry to, and returns
+    /// whether the operation in that slot was valid. Slots that mark reads
+    /// are always valid.
+    fn apply_through(&self, replica: &mut Replica<S::State>, slot: u64) -> bool {
+        let mut is_valid = true;
+        while replica.applied <= slot {
+            let entry = self
+                .log
+                .entry_at(replica.applied)
+                .expect("Entries before a committed slot should be known");
+            is_valid = match entry {
+                Some(operation) => {
+                    let (is_valid, state) = S::apply(&operation, &replica.state);
+                    if is_valid {
+                        replica.state = state;
+                    }
+                    is_valid
+                }
+                None => true,
+            };
+            replica.applied += 1;
+        }
+        is_valid
+    }
+}
+
+impl<S: Specification, Tr: Transport> Clone for ReplicatedObject<S, Tr>
+where
+    S::Operation: DeserializeOwned + Send + Serialize,
+{
+    fn clone(&self) -> Self {
+        Self {
+            log: self.log.clone(),
+            replica: self.replica.clone(),
+        }
+    }
+}
+
+impl<S: Specification + 'static, Tr: Transport> Handler for ReplicatedObject<S, Tr>
+where
+    S::Operation: DeserializeOwned + Send + Serialize + 'static,
+    S::State: Send,
+{
+    fn handle(
+        &self,
+        message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        self.log.handle(message)
+    }
+}
+
+impl<S: Specification + 'static, Tr: Transport> Service<Request<Incoming>>
+    for ReplicatedObject<S, Tr>
+where
+    S::Operation: DeserializeOwned + Send + Serialize + 'static,
+    S::State: Send,
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        self.log.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    enum AccountOperation {
+        Deposit(u32),
+        Withdraw(u32),
+    }
+
+    use AccountOperation::{Deposit, Withdraw};
+
+    struct AccountSpecification;
+
+    impl Specification for AccountSpecification {
+        type State = u32;
+        type Operation = AccountOperation;
+
+        fn init() -> Self::State {
+            0
+        }
+
+        fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+            match operation {
+                Deposit(amount) => (true, state + amount),
+                Withdraw(amount) => match state.checked_sub(*amount) {
+                    Some(balance) => (true, balance),
+                    None => (false, *state),
+                },
+            }
+        }
+    }
+
+    type Account = ReplicatedObject<AccountSpecification>;
+
+    mod apply {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_state_after_operation() {
+            let account = Account::new(1, Vec::new());
+            assert_eq!((true, 100), account.apply(Deposit(100)).await.unwrap());
+            assert_eq!((true, 60), account.apply(Withdraw(40)).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn invalid_operation_does_not_change_state() {
+            let account = Account::new(1, Vec::new());
+            account.apply(Deposit(100)).await.unwrap();
+            assert_eq!((false, 100), account.apply(Withdraw(150)).await.unwrap());
+            assert_eq!(100, account.read().await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn applies_operations_appended_by_others_first() {
+            let account = Account::new(1, Vec::new());
+            account.log.append(Deposit(100)).await.unwrap();
+            assert_eq!((true, 70), account.apply(Withdraw(30)).await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn concurrent_operations_are_all_applied() {
+            let account = Account::new(1, Vec::new());
+            let handles: Vec<_> = (0..10)
+                .map(|_| {
+                    let account = account.clone();
+                    tokio::spawn(async move { account.apply(Deposit(10)).await.unwrap() })
+                })
+                .collect();
+            for handle in handles {
+                handle.await.unwrap();
+            }
+            assert_eq!(100, account.read().await.unwrap());
+        }
+    }
+
+    mod read {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_initial_state() {
+            let account = Account::new(1, Vec::new());
+            assert_eq!(0, account.read().await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn does_not_apply_read_markers() {
+            let account = Account::new(1, Vec::new());
+            account.read().await.unwrap();
+            account.apply(Deposit(100)).await.unwrap();
+            account.read().await.unwrap();
+            assert_eq!(100, account.read().await.unwrap());
+        }
+    }
+}
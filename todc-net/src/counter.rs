@@ -0,0 +1,471 @@
+//! Simulations of a linearizable counter, built from one max-register per
+//! instance.
+//!
+//! A counter that is incremented by reading an
+//! [`AtomicRegister`](crate::register::AtomicRegister) and writing back the
+//! result can lose increments that are made concurrently. An
+//! [`AtomicCounter`] instead gives each instance its own count of the
+//! increments that it has made, which only that instance ever increases.
+//! Each count is stored as a _max-register_, which replaces its value only
+//! with a larger one, using the same quorums as the atomic registers of
+//! Attiya, Bar-Noy and Dolev
+//! [\[ABD95\]](https://dl.acm.org/doi/pdf/10.1145/200836.200869).
+//!
+//! Every instance stores the largest count that it has seen for each
+//! instance. An increment adds one to the count of the instance that
+//! performs it, and announces the result to a quorum. A read asks a quorum
+//! for their counts, takes the largest count reported for each instance,
+//! announces those counts back to a quorum, so that no later read can
+//! return a smaller value, and then returns their sum. As with an
+//! [`AtomicRegister`](crate::register::AtomicRegister), operations tolerate
+//! the crash of any minority of instances by default.
+//!
+//! Instances keep their counts in memory. Before its first increment, an
+//! instance learns its own count from a quorum, so that an instance that
+//! restarts does not count its earlier increments again. An increment that
+//! was interrupted by the crash of its instance may be counted by some reads
+//! but not by later ones.
+//!
+//! # Examples
+//!
+//! Clients can increment an instance with `POST` requests made to `/counter`,
+//! and read it with `GET` requests, which are answered with the value of the
+//! counter as JSON. Instances exchange their counts through requests made to
+//! `/counter/counts`.
+//!
+//! ```
+//! # use tokio_test;
+//! use todc_net::counter::AtomicCounter;
+//!
+//! # tokio_test::block_on(async {
+//! // A single instance, with no neighbors.
+//! let counter: AtomicCounter = AtomicCounter::new(0, Vec::new());
+//!
+//! counter.increment().await.unwrap();
+//! counter.increment().await.unwrap();
+//! assert_eq!(counter.read().await.unwrap(), 2);
+//! # })
+//! ```
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::http::StatusCode;
+use hyper::service::Service;
+use hyper::{Method, Request, Response, Uri};
+use tokio::sync::OnceCell;
+use tokio::task::JoinSet;
+
+use crate::quorum::Resilience;
+use crate::register::abd_95::RegisterError;
+use crate::transport::{self, Handler, HttpTransport, Transport};
+use crate::{mk_response, GenericError};
+
+/// The route at which clients can read and increment the counter.
+const COUNTER_PATH: &str = "/counter";
+
+/// The route at which instances exchange their counts.
+const COUNTS_PATH: &str = "/counter/counts";
+
+/// The largest count that an instance has seen for each instance, by ID.
+type Counts = BTreeMap<u32, u64>;
+
+/// Replaces each count with the larger of itself and the same count in
+/// `other`.
+fn merge(counts: &mut Counts, other: &Counts) {
+    for (&id, &count) in other {
+        let current = counts.entry(id).or_default();
+        *current = (*current).max(count);
+    }
+}
+
+/// Returns the value of a counter whose instances made the given number of
+/// increments.
+fn total(counts: &Counts) -> u64 {
+    counts
+        .values()
+        .fold(0, |total, count| total.saturating_add(*count))
+}
+
+/// An instance of a linearizable counter.
+///
+/// See the [`counter`](crate::counter) module-level documentation for more
+/// details.
+#[derive(Clone)]
+pub struct AtomicCounter<Tr: Transport = HttpTransport> {
+    id: u32,
+    transport: Tr,
+    neighbors: Arc<Vec<Uri>>,
+    resilience: Resilience,
+    counts: Arc<Mutex<Counts>>,
+    recovered: Arc<OnceCell<()>>,
+}
+
+impl AtomicCounter {
+    /// Creates a new instance with a given set of neighbors.
+    ///
+    /// If there are `n` instances, then each instance must be instantiated
+    /// with a URL for all `n - 1` of its neighbors, and an `id` that is
+    /// different from the ids of all other instances.
+    pub fn new(id: u32, neighbors: Vec<Uri>) -> Self {
+        Self::with_transport(id, neighbors, HttpTransport::default())
+    }
+}
+
+impl<Tr: Transport> AtomicCounter<Tr> {
+    /// Creates a new instance with a given set of neighbors, that
+    /// communicates with them using the given [`Transport`].
+    ///
+    /// All instances of the counter must use the same kind of transport.
+    pub fn with_transport(id: u32, neighbors: Vec<Uri>, transport: Tr) -> Self {
+        Self {
+            id,
+            transport,
+            resilience: Resilience::majority(neighbors.len() + 1),
+            neighbors: Arc::new(neighbors),
+            counts: Arc::new(Mutex::new(Counts::new())),
+            recovered: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Sets the number of instances that may crash, while the counter
+    /// remains available.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegisterError::TooFewInstances`] unless there are more than
+    /// `2 * failures` instances, including this one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::counter::AtomicCounter;
+    ///
+    /// let neighbors: Vec<Uri> = (1..5)
+    ///     .map(|i| format!("https://my-counter-{i}.com").parse().unwrap())
+    ///     .collect();
+    /// let counter: AtomicCounter = AtomicCounter::new(0, neighbors)
+    ///     .with_failure_threshold(1)
+    ///     .unwrap();
+    /// assert_eq!(counter.resilience().quorum(), 4);
+    /// ```
+    pub fn with_failure_threshold(mut self, failures: usize) -> Result<Self, RegisterError> {
+        self.resilience = Resilience::new(self.neighbors.len() + 1, failures)?;
+        Ok(self)
+    }
+
+    /// Returns the [`Resilience`] of this instance and its neighbors.
+    pub fn resilience(&self) -> Resilience {
+        self.resilience
+    }
+
+    /// Adds one to the counter.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegisterError::QuorumUnavailable`] if too few instances
+    /// reply.
+    pub async fn increment(&self) -> Result<(), RegisterError> {
+        self.recovered
+            .get_or_try_init(|| async {
+                for counts in self.communicate(None).await? {
+                    self.merge(&counts);
+                }
+                Ok::<_, RegisterError>(())
+            })
+            .await?;
+        // Only this instance increases its own count, which it does while
+        // holding the lock, so concurrent increments are never counted once.
+        let counts = {
+            let mut counts = self.counts.lock().unwrap();
+            *counts.entry(self.id).or_default() += 1;
+            counts.clone()
+        };
+        self.communicate(Some(counts)).await?;
+        Ok(())
+    }
+
+    /// Returns the value of the counter.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegisterError::QuorumUnavailable`] if too few instances
+    /// reply.
+    pub async fn read(&self) -> Result<u64, RegisterError> {
+        let mut counts = Counts::new();
+        for other in self.communicate(None).await? {
+            merge(&mut counts, &other);
+        }
+        let counts = self.merge(&counts);
+        // Announcing the counts ensures that every later read sees counts
+        // that are at least as large, even if the increments that produced
+        // them have not yet completed.
+        self.communicate(Some(counts.clone())).await?;
+        Ok(total(&counts))
+    }
+
+    /// Merges counts into those of this instance, and returns the result.
+    fn merge(&self, other: &Counts) -> Counts {
+        let mut counts = self.counts.lock().unwrap();
+        merge(&mut counts, other);
+        counts.clone()
+    }
+
+    /// Sends a request to every neighbor, asking them to merge the given
+    /// counts if there are any, and returns the counts of a quorum of
+    /// instances, including this one.
+    async fn communicate(&self, counts: Option<Counts>) -> Result<Vec<Counts>, RegisterError> {
+        let message = match &counts {
+            Some(counts) => {
+                transport::Message::announce(COUNTS_PATH, Bytes::from(serde_json::to_vec(counts)?))
+            }
+            None => transport::Message::ask(COUNTS_PATH),
+        };
+        let mut replies = vec![match &counts {
+            Some(counts) => self.merge(counts),
+            None => self.counts.lock().unwrap().clone(),
+        }];
+
+        let mut handles = JoinSet::new();
+        for neighbor in self.neighbors.iter().cloned() {
+            let transport = self.transport.clone();
+            let message = message.clone();
+            handles.spawn(async move { transport.send(neighbor, message).await });
+        }
+
+        // Wait until a quorum of instances have replied, or until so many
+        // have failed that a quorum never will.
+        let tolerated = self.resilience.instances() - self.resilience.quorum();
+        let mut failures = 0;
+        while !self.resilience.is_available(replies.len()) && failures <= tolerated {
+            let Some(result) = handles.join_next().await else {
+                break;
+            };
+            let reply = result
+                .map_err(|error| RegisterError::Transport(error.into()))?
+                .and_then(|body| Ok(serde_json::from_slice::<Counts>(&body)?));
+            match reply {
+                Ok(counts) => replies.push(counts),
+                Err(error) => {
+                    tracing::debug!(%error, "neighbor did not reply");
+                    failures += 1
+                }
+            }
+        }
+        handles.abort_all();
+
+        if self.resilience.is_available(replies.len()) {
+            Ok(replies)
+        } else {
+            let acks = replies.len();
+            let needed = self.resilience.quorum();
+            tracing::warn!(acks, needed, "quorum unavailable");
+            Err(RegisterError::QuorumUnavailable {
+                acks,
+                needed,
+                failures: self.resilience.failures(),
+            })
+        }
+    }
+}
+
+impl<Tr: Transport> Handler for AtomicCounter<Tr> {
+    fn handle(
+        &self,
+        message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        let me = self.clone();
+        async move {
+            if message.path != COUNTS_PATH {
+                return Err(GenericError::from(format!(
+                    "Unknown route {}",
+                    message.path
+                )));
+            }
+            let counts = match message.body {
+                Some(body) => me.merge(&serde_json::from_slice(&body)?),
+                None => me.counts.lock().unwrap().clone(),
+            };
+            Ok(Bytes::from(serde_json::to_vec(&counts)?))
+        }
+    }
+}
+
+impl<Tr: Transport> Service<Request<Incoming>> for AtomicCounter<Tr> {
+    type Response = Response<Full<Bytes>>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        let me = self.clone();
+        match (req.method(), req.uri().path()) {
+            // GET requests read the value of the counter.
+            (&Method::GET, COUNTER_PATH) => Box::pin(async move {
+                match me.read().await {
+                    Ok(value) => Ok(Response::new(Full::new(serde_json::to_vec(&value)?.into()))),
+                    Err(error) => mk_response(error.status_code(), error.to_string().into()),
+                }
+            }),
+            // POST requests increment the counter.
+            (&Method::POST, COUNTER_PATH) => Box::pin(async move {
+                match me.increment().await {
+                    Ok(()) => Ok(Response::new(Full::new(Bytes::new()))),
+                    Err(error) => mk_response(error.status_code(), error.to_string().into()),
+                }
+            }),
+            // GET requests ask for the counts of this instance, and POST
+            // requests contain counts for this instance to merge.
+            (&Method::GET | &Method::POST, COUNTS_PATH) => Box::pin(async move {
+                let message = match *req.method() {
+                    Method::GET => transport::Message::ask(COUNTS_PATH),
+                    _ => {
+                        let body = req.collect().await?.to_bytes();
+                        transport::Message::announce(COUNTS_PATH, body)
+                    }
+                };
+                match me.handle(message).await {
+                    Ok(reply) => Ok(Response::new(Full::new(reply))),
+                    Err(error) => mk_response(StatusCode::BAD_REQUEST, error.to_string().into()),
+                }
+            }),
+            _ => Box::pin(async { mk_response(StatusCode::NOT_FOUND, "404 Not Found".into()) }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::memory::{self, Inbox, MemoryTransport};
+
+    fn neighbors(n: usize) -> Vec<Uri> {
+        (0..n)
+            .map(|i| format!("http://test-{i}.com").parse().unwrap())
+            .collect()
+    }
+
+    /// Returns an instance with two neighbors, whose messages are delivered
+    /// to an inbox.
+    fn scripted() -> (AtomicCounter<MemoryTransport>, Inbox) {
+        let (transport, inbox) = memory::channel();
+        (
+            AtomicCounter::with_transport(0, neighbors(2), transport),
+            inbox,
+        )
+    }
+
+    /// Waits for the messages that an instance sends to its two neighbors,
+    /// and replies to the first with the given counts, while the second
+    /// neighbor never replies. Returns the counts that were sent, if any.
+    async fn reply(inbox: &mut Inbox, counts: &[(u32, u64)]) -> Option<Counts> {
+        let first = inbox.recv().await.unwrap();
+        let _second = inbox.recv().await.unwrap();
+        let sent = first
+            .message()
+            .body
+            .as_ref()
+            .map(|body| serde_json::from_slice(body).unwrap());
+        let counts: Counts = counts.iter().copied().collect();
+        first.reply(serde_json::to_vec(&counts).unwrap());
+        sent
+    }
+
+    mod with_failure_threshold {
+        use super::*;
+
+        #[test]
+        fn rejects_too_few_instances() {
+            let result = AtomicCounter::new(0, neighbors(2)).with_failure_threshold(2);
+            assert!(matches!(result, Err(RegisterError::TooFewInstances(_))));
+        }
+    }
+
+    mod increment {
+        use super::*;
+
+        #[tokio::test]
+        async fn learns_own_count_before_first_increment() {
+            let (counter, mut inbox) = scripted();
+            let (result, ()) = tokio::join!(counter.increment(), async {
+                assert_eq!(reply(&mut inbox, &[(0, 5)]).await, None);
+                let sent = reply(&mut inbox, &[(0, 6)]).await;
+                assert_eq!(sent, Some(Counts::from([(0, 6)])));
+            });
+            result.unwrap();
+        }
+
+        #[tokio::test]
+        async fn fails_if_quorum_is_unreachable() {
+            let (counter, mut inbox) = scripted();
+            let (result, ()) = tokio::join!(counter.increment(), async {
+                inbox.recv().await.unwrap().fail("Neighbor crashed");
+                inbox.recv().await.unwrap().fail("Neighbor crashed");
+            });
+            assert!(matches!(
+                result,
+                Err(RegisterError::QuorumUnavailable {
+                    acks: 1,
+                    needed: 2,
+                    ..
+                })
+            ));
+        }
+    }
+
+    mod read {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_sum_of_largest_counts() {
+            let (counter, mut inbox) = scripted();
+            counter.merge(&Counts::from([(0, 2), (1, 1)]));
+            let (value, ()) = tokio::join!(counter.read(), async {
+                reply(&mut inbox, &[(1, 3), (2, 4)]).await;
+                let sent = reply(&mut inbox, &[]).await;
+                assert_eq!(sent, Some(Counts::from([(0, 2), (1, 3), (2, 4)])));
+            });
+            assert_eq!(value.unwrap(), 9);
+        }
+    }
+
+    mod handle {
+        use super::*;
+
+        #[tokio::test]
+        async fn keeps_largest_of_each_count() {
+            let counter = AtomicCounter::new(0, Vec::new());
+            counter.merge(&Counts::from([(0, 2), (1, 5)]));
+            let body = serde_json::to_vec(&Counts::from([(0, 3), (1, 4)])).unwrap();
+            let reply = counter
+                .handle(transport::Message::announce(COUNTS_PATH, body.into()))
+                .await
+                .unwrap();
+            let counts: Counts = serde_json::from_slice(&reply).unwrap();
+            assert_eq!(counts, Counts::from([(0, 3), (1, 5)]));
+        }
+
+        #[tokio::test]
+        async fn replies_with_counts_when_asked() {
+            let counter = AtomicCounter::new(0, Vec::new());
+            counter.increment().await.unwrap();
+            let reply = counter
+                .handle(transport::Message::ask(COUNTS_PATH))
+                .await
+                .unwrap();
+            let counts: Counts = serde_json::from_slice(&reply).unwrap();
+            assert_eq!(counts, Counts::from([(0, 1)]));
+        }
+
+        #[tokio::test]
+        async fn rejects_unknown_route() {
+            let counter = AtomicCounter::new(0, Vec::new());
+            let result = counter.handle(transport::Message::ask("/register")).await;
+            assert!(result.is_err());
+        }
+    }
+}
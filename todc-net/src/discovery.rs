@@ -0,0 +1,251 @@
+//! Discovering the neighbors of an instance at runtime.
+//!
+//! Instances are usually created with a fixed list of neighbors. In elastic
+//! deployments, where instances come and go, the neighbors of an instance
+//! can instead be found with a [`Discovery`], which an
+//! [`AtomicRegister`](crate::register::AtomicRegister) can poll with
+//! [`spawn_discovery`](crate::register::AtomicRegister::spawn_discovery) to
+//! keep its neighbors up to date.
+//!
+//! This module contains the following ways of discovering neighbors:
+//!
+//! - A [`StaticDiscovery`], which always finds the same neighbors.
+//! - A [`HeadlessService`], which resolves the addresses of every instance
+//!   behind a Kubernetes
+//!   [headless service](https://kubernetes.io/docs/concepts/services-networking/service/#headless-services),
+//!   or any other DNS name with one address record per instance.
+//! - A `SrvDiscovery`, which resolves the targets of a DNS `SRV` record.
+//!   This requires the `dns` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use tokio_test;
+//! use std::net::{IpAddr, Ipv4Addr};
+//! use std::time::Duration;
+//! use todc_net::discovery::{Discovery, HeadlessService};
+//! use todc_net::register::AtomicRegister;
+//!
+//! # tokio_test::block_on(async {
+//! let own_ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+//! let discovery = HeadlessService::new("my-register.default.svc.cluster.local", 3000)
+//!     .excluding(own_ip);
+//!
+//! let neighbors = discovery.discover().await.unwrap();
+//! let register: AtomicRegister<u32> = AtomicRegister::new(neighbors);
+//! let refresh = register.spawn_discovery(discovery, Duration::from_secs(30));
+//! # })
+//! ```
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+
+use hyper::Uri;
+
+use crate::GenericError;
+
+/// A way of finding the neighbors of an instance.
+pub trait Discovery: Send + Sync + 'static {
+    /// Returns the URLs of the neighbors of this instance.
+    ///
+    /// The result must not include this instance.
+    fn discover(&self) -> impl Future<Output = Result<Vec<Uri>, GenericError>> + Send;
+}
+
+/// A [`Discovery`] that always finds the same neighbors.
+///
+/// # Examples
+///
+/// ```
+/// # use tokio_test;
+/// use hyper::Uri;
+/// use todc_net::discovery::{Discovery, StaticDiscovery};
+///
+/// # tokio_test::block_on(async {
+/// let neighbor = Uri::from_static("http://my-register-2.com");
+/// let discovery = StaticDiscovery::new(vec![neighbor.clone()]);
+/// assert_eq!(discovery.discover().await.unwrap(), vec![neighbor]);
+/// # })
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StaticDiscovery {
+    neighbors: Vec<Uri>,
+}
+
+impl StaticDiscovery {
+    /// Creates a discovery that always finds the given neighbors.
+    pub fn new(neighbors: Vec<Uri>) -> Self {
+        Self { neighbors }
+    }
+}
+
+impl Discovery for StaticDiscovery {
+    async fn discover(&self) -> Result<Vec<Uri>, GenericError> {
+        Ok(self.neighbors.clone())
+    }
+}
+
+/// A [`Discovery`] that finds a neighbor at each address that a DNS name
+/// resolves to.
+///
+/// The DNS name of a Kubernetes headless service resolves to the address of
+/// every pod that it selects, so each pod can find the others by resolving
+/// the name of the service, and excluding its own address. Neighbors are
+/// found at `http://{address}:{port}`.
+#[derive(Clone, Debug)]
+pub struct HeadlessService {
+    host: String,
+    port: u16,
+    excluded: Vec<IpAddr>,
+}
+
+impl HeadlessService {
+    /// Creates a discovery that resolves the given host, and finds
+    /// neighbors listening on the given port of each of its addresses.
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            excluded: Vec::new(),
+        }
+    }
+
+    /// Excludes an address from the neighbors that are found, such as the
+    /// address of this instance.
+    pub fn excluding(mut self, address: IpAddr) -> Self {
+        self.excluded.push(address);
+        self
+    }
+
+    /// Returns the URLs of neighbors at the given addresses, in a
+    /// deterministic order and without duplicates.
+    fn neighbors(&self, addresses: impl Iterator<Item = SocketAddr>) -> Vec<Uri> {
+        let mut addresses: Vec<SocketAddr> = addresses
+            .filter(|address| !self.excluded.contains(&address.ip()))
+            .collect();
+        addresses.sort();
+        addresses.dedup();
+        addresses
+            .into_iter()
+            .map(|address| {
+                format!("http://{address}")
+                    .parse()
+                    .expect("socket address is a valid authority")
+            })
+            .collect()
+    }
+}
+
+impl Discovery for HeadlessService {
+    async fn discover(&self) -> Result<Vec<Uri>, GenericError> {
+        let addresses = tokio::net::lookup_host((self.host.as_str(), self.port)).await?;
+        Ok(self.neighbors(addresses))
+    }
+}
+
+/// A [`Discovery`] that finds a neighbor at the target of each DNS `SRV`
+/// record with a given name.
+///
+/// Kubernetes creates an `SRV` record for each named port of a headless
+/// service, such as `_http._tcp.my-register.default.svc.cluster.local`,
+/// whose targets are the DNS names of the pods that it selects. Neighbors
+/// are found at `http://{target}:{port}`, and DNS names are resolved with
+/// the configuration of the system.
+#[cfg(feature = "dns")]
+#[derive(Clone, Debug)]
+pub struct SrvDiscovery {
+    name: String,
+    excluded: Vec<String>,
+}
+
+#[cfg(feature = "dns")]
+impl SrvDiscovery {
+    /// Creates a discovery that resolves the `SRV` records with the given
+    /// name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            excluded: Vec::new(),
+        }
+    }
+
+    /// Excludes a target from the neighbors that are found, such as the DNS
+    /// name of this instance.
+    pub fn excluding(mut self, target: impl Into<String>) -> Self {
+        let target = target.into();
+        self.excluded.push(target.trim_end_matches('.').to_string());
+        self
+    }
+}
+
+#[cfg(feature = "dns")]
+impl Discovery for SrvDiscovery {
+    async fn discover(&self) -> Result<Vec<Uri>, GenericError> {
+        let resolver = hickory_resolver::TokioAsyncResolver::tokio_from_system_conf()?;
+        let lookup = resolver.srv_lookup(self.name.as_str()).await?;
+        let mut targets: Vec<(String, u16)> = lookup
+            .iter()
+            .map(|srv| {
+                let target = srv.target().to_utf8();
+                (target.trim_end_matches('.').to_string(), srv.port())
+            })
+            .filter(|(target, _)| !self.excluded.contains(target))
+            .collect();
+        targets.sort();
+        targets.dedup();
+        targets
+            .into_iter()
+            .map(|(target, port)| Ok(format!("http://{target}:{port}").parse()?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod headless_service {
+        use std::net::Ipv4Addr;
+
+        use super::*;
+
+        fn address(last: u8) -> SocketAddr {
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, last)), 3000)
+        }
+
+        #[test]
+        fn excludes_own_address() {
+            let discovery = HeadlessService::new("my-register", 3000)
+                .excluding(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+            let neighbors = discovery.neighbors([address(1), address(2)].into_iter());
+            assert_eq!(neighbors, vec![Uri::from_static("http://10.0.0.2:3000")]);
+        }
+
+        #[test]
+        fn orders_and_deduplicates_neighbors() {
+            let discovery = HeadlessService::new("my-register", 3000);
+            let neighbors = discovery.neighbors([address(3), address(2), address(3)].into_iter());
+            assert_eq!(
+                neighbors,
+                vec![
+                    Uri::from_static("http://10.0.0.2:3000"),
+                    Uri::from_static("http://10.0.0.3:3000")
+                ]
+            );
+        }
+
+        #[test]
+        fn brackets_ipv6_addresses() {
+            let discovery = HeadlessService::new("my-register", 3000);
+            let address = SocketAddr::new("::1".parse().unwrap(), 3000);
+            let neighbors = discovery.neighbors([address].into_iter());
+            assert_eq!(neighbors, vec![Uri::from_static("http://[::1]:3000")]);
+        }
+
+        #[tokio::test]
+        async fn resolves_localhost() {
+            let discovery = HeadlessService::new("localhost", 3000);
+            let neighbors = discovery.discover().await.unwrap();
+            assert!(!neighbors.is_empty());
+        }
+    }
+}
@@ -1,16 +1,36 @@
 //! Algorithms for message-passing (HTTP) distributed systems.
+use std::future::Future;
+
 use bytes::Bytes;
 use http_body_util::combinators::BoxBody;
 use http_body_util::{BodyExt, Full};
 use hyper::body::Incoming;
+use hyper::header::HeaderMap;
 use hyper::http::StatusCode;
 use hyper::{Method, Request, Response, Uri};
-use serde_json::{json, Value as JSON};
+use serde_json::Value as JSON;
 
 use crate::net::TcpStream;
 
+pub mod auth;
+pub mod broadcast;
+pub mod causal;
+pub mod client;
+pub mod cluster;
+pub mod codec;
+pub mod consensus;
+pub mod counter;
+pub mod discovery;
+pub mod limit;
 pub(crate) mod net;
+pub mod quorum;
 pub mod register;
+pub mod shutdown;
+pub mod storage;
+#[cfg(feature = "turmoil")]
+pub mod testing;
+pub mod time;
+pub mod transport;
 
 // NOTE: This module adds a local copy of some helper types that for integrating
 // tokio with Hyper 1.0. Hopefully, once Hyper 1.0 is released, there will be
@@ -19,23 +39,72 @@ pub mod register;
 mod hyper_util_tokio_io;
 pub use hyper_util_tokio_io::TokioIo;
 
+/// Executes the futures of HTTP/2 connections on the current tokio runtime.
+///
+/// This can be passed to [`hyper::server::conn::http2::Builder::new`] when
+/// serving instances that communicate over HTTP/2.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioExecutor;
+
+impl<F> hyper::rt::Executor<F> for TokioExecutor
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    fn execute(&self, future: F) {
+        tokio::spawn(future);
+    }
+}
+
 type GenericError = Box<dyn std::error::Error + Send + Sync>;
 type ResponseResult = Result<Response<Incoming>, GenericError>;
 
 /// Submits a GET request to the URL.
 pub(crate) async fn get(url: Uri) -> ResponseResult {
-    make_request(url, Method::GET, json!(null)).await
+    make_request(url, Method::GET, Bytes::new()).await
 }
 
-/// Submits a POST request, along with a JSON body, to the URL.
-pub(crate) async fn post(url: Uri, body: JSON) -> ResponseResult {
+/// Submits a POST request, along with a body, to the URL.
+#[cfg(feature = "turmoil")]
+pub(crate) async fn post(url: Uri, body: Bytes) -> ResponseResult {
     make_request(url, Method::POST, body).await
 }
 
-/// Makes a request to the URL, including a JSON body.
-async fn make_request(url: Uri, method: Method, body: JSON) -> ResponseResult {
-    let authority = url.authority().ok_or("Invalid URL")?.as_str();
-    let stream = TcpStream::connect(authority).await?;
+/// Submits a GET request, along with headers, to the URL.
+pub(crate) async fn get_with_headers(url: Uri, headers: HeaderMap) -> ResponseResult {
+    let mut req = Request::builder()
+        .uri(url)
+        .method(Method::GET)
+        .body(full(Bytes::new()))?;
+    req.headers_mut().extend(headers);
+    send_request(req).await
+}
+
+/// Submits a POST request, along with a body and headers, to the URL.
+pub(crate) async fn post_with_headers(url: Uri, body: Bytes, headers: HeaderMap) -> ResponseResult {
+    let mut req = Request::builder()
+        .uri(url)
+        .method(Method::POST)
+        .body(full(body))?;
+    req.headers_mut().extend(headers);
+    send_request(req).await
+}
+
+/// Makes a request to the URL, including a body.
+async fn make_request(url: Uri, method: Method, body: Bytes) -> ResponseResult {
+    let req = Request::builder()
+        .uri(url)
+        .method(method)
+        .body(full(body))?;
+    send_request(req).await
+}
+
+/// Sends a request over a new HTTP/1 connection to the host of its URL.
+async fn send_request(mut req: Request<BoxBody<Bytes, hyper::Error>>) -> ResponseResult {
+    let authority = req.uri().authority().ok_or("Invalid URL")?.clone();
+    req.headers_mut()
+        .insert(hyper::header::HOST, authority.as_str().parse()?);
+    let stream = TcpStream::connect(authority.as_str()).await?;
 
     // Use adapter to access something implementing tokio::io as if they
     // implement hyper::rt.
@@ -45,19 +114,19 @@ async fn make_request(url: Uri, method: Method, body: JSON) -> ResponseResult {
 
     tokio::task::spawn(async move {
         if let Err(err) = conn.await {
-            println!("Connection failed: {err}");
+            tracing::debug!(error = %err, "connection failed");
         }
     });
 
-    let req = Request::builder()
-        .header(hyper::header::HOST, authority)
-        .uri(url)
-        .method(method)
-        .body(full(body))?;
-
     Ok(sender.send_request(req).await?)
 }
 
+/// The body of a response that does not contain a value, encoded as JSON.
+///
+/// Responses with this body share its memory, rather than each serializing
+/// [`JSON::Null`].
+pub(crate) const NULL_BODY: Bytes = Bytes::from_static(b"null");
+
 /// Creates a response containing a JSON value.
 pub(crate) fn mk_response(
     status: StatusCode,
@@ -69,9 +138,9 @@ pub(crate) fn mk_response(
         .unwrap())
 }
 
-/// Returns a JSON body.
-fn full(value: JSON) -> BoxBody<Bytes, hyper::Error> {
-    Full::<Bytes>::new(Bytes::from(value.to_string()))
+/// Returns a body containing the given bytes.
+fn full(body: Bytes) -> BoxBody<Bytes, hyper::Error> {
+    Full::<Bytes>::new(body)
         .map_err(|never| match never {})
         .boxed()
 }
@@ -0,0 +1,444 @@
+//! Limits on the requests that instances handle and send.
+//!
+//! A service that spawns work for every request it receives can be
+//! overwhelmed by a flood of requests, which compete for the same runtime
+//! and slow down every other request along with them. A [`ConcurrencyLimit`]
+//! bounds the number of requests that are handled at once, and the number
+//! of requests that may wait for their turn. Requests that arrive when both
+//! are exhausted are rejected immediately, so that an overloaded instance
+//! degrades gracefully instead of falling ever further behind.
+//!
+//! # Rate Limits
+//!
+//! Instances also send requests of their own, for example to announce values
+//! to their neighbors, and under load these can crowd out the requests of
+//! clients. A [`RateLimit`] bounds the rate at which an instance sends such
+//! requests, with a token bucket that holds up to a fixed number of tokens and
+//! is refilled at a fixed rate. Each request takes a token, and waits for one
+//! if the bucket is empty. Waiting requests are sent in order of their
+//! [`Priority`], so that less important requests, such as background gossip,
+//! are the ones that are delayed.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_net::limit::{ConcurrencyLimit, RateLimit};
+//!
+//! // Handle at most 64 requests at once, and queue at most 256 more.
+//! let limit = ConcurrencyLimit::new(64).with_queue(256);
+//! assert_eq!(limit.max_concurrent(), 64);
+//! assert_eq!(limit.max_queued(), 256);
+//!
+//! // Send 100 requests per second on average, and at most 20 at once.
+//! let limit = RateLimit::new(100).with_burst(20);
+//! assert_eq!(limit.per_second(), 100);
+//! assert_eq!(limit.burst(), 20);
+//! ```
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+use crate::time::Clock;
+
+/// A limit on the number of requests that are handled concurrently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConcurrencyLimit {
+    max_concurrent: usize,
+    max_queued: usize,
+}
+
+impl ConcurrencyLimit {
+    /// Creates a limit that handles at most `max_concurrent` requests at
+    /// once, and rejects any others.
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent,
+            max_queued: 0,
+        }
+    }
+
+    /// Allows up to `max_queued` requests to wait for one of the requests
+    /// that are being handled to finish, instead of being rejected.
+    pub fn with_queue(mut self, max_queued: usize) -> Self {
+        self.max_queued = max_queued;
+        self
+    }
+
+    /// Returns the maximum number of requests that are handled at once.
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    /// Returns the maximum number of requests that wait to be handled.
+    pub fn max_queued(&self) -> usize {
+        self.max_queued
+    }
+}
+
+/// Enforces a [`ConcurrencyLimit`], and can be shared between clones of a
+/// service.
+#[derive(Clone, Debug)]
+pub(crate) struct Limiter {
+    semaphore: Arc<Semaphore>,
+    queued: Arc<AtomicUsize>,
+    max_queued: usize,
+}
+
+impl Limiter {
+    pub(crate) fn new(limit: ConcurrencyLimit) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(limit.max_concurrent)),
+            queued: Arc::new(AtomicUsize::new(0)),
+            max_queued: limit.max_queued,
+        }
+    }
+
+    /// Waits for permission to handle a request, or returns `None` if the
+    /// request should be rejected because the queue is full.
+    ///
+    /// The request may be handled for as long as the returned permit is held.
+    pub(crate) async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+            return Some(permit);
+        }
+        if self.queued.fetch_add(1, Ordering::SeqCst) >= self.max_queued {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+        // Leave the queue even if the request is dropped while waiting.
+        let _queued = Dequeue(&self.queued);
+        self.semaphore.clone().acquire_owned().await.ok()
+    }
+}
+
+/// Removes a request from the queue when dropped.
+struct Dequeue<'a>(&'a AtomicUsize);
+
+impl Drop for Dequeue<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A limit on the rate at which an instance sends requests to its
+/// neighbors.
+///
+/// Requests are limited by a token bucket, which holds at most
+/// [`burst`](RateLimit::burst) tokens, and gains a token
+/// [`per_second`](RateLimit::per_second) times every second. Each request
+/// takes a token, and waits for the bucket to be refilled if it is empty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    per_second: u32,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// Creates a limit that sends at most `per_second` requests every second,
+    /// all of which may be sent at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `per_second` is zero.
+    pub fn new(per_second: u32) -> Self {
+        assert!(per_second > 0, "Rate limit must allow some requests");
+        Self {
+            per_second,
+            burst: per_second,
+        }
+    }
+
+    /// Allows at most `burst` requests to be sent at once, after a period in
+    /// which fewer requests than the limit were sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `burst` is zero.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        assert!(burst > 0, "Burst must allow some requests");
+        self.burst = burst;
+        self
+    }
+
+    /// Returns the number of requests that may be sent every second.
+    pub fn per_second(&self) -> u32 {
+        self.per_second
+    }
+
+    /// Returns the maximum number of requests that may be sent at once.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// Returns the time it takes for the bucket to gain a token.
+    fn period(&self) -> Duration {
+        Duration::from_secs(1) / self.per_second
+    }
+}
+
+/// The priority of a request that is waiting for a [`RateLimit`].
+///
+/// Whenever the limit allows another request to be sent, the waiting request
+/// with the highest priority is sent first. Requests with the same priority
+/// are sent in the order in which they began waiting.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Sent only once no request with a higher priority is waiting.
+    Low,
+    /// The priority of every request, unless configured otherwise.
+    #[default]
+    Normal,
+    /// Sent before any request with a lower priority.
+    High,
+}
+
+/// Enforces a [`RateLimit`], and can be shared between clones of an
+/// instance.
+#[derive(Clone, Debug)]
+pub(crate) struct Throttle {
+    limit: RateLimit,
+    bucket: Arc<Mutex<Bucket>>,
+    /// Notified whenever a request stops waiting, so that the next one can
+    /// check whether it is its turn.
+    changed: Arc<Notify>,
+}
+
+/// A request that is waiting for a token. Requests are ordered by decreasing
+/// priority, and then by the order in which they began waiting.
+type Ticket = (Reverse<Priority>, u64);
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: u32,
+    /// The time at which the bucket last gained a token, or `None` if it has
+    /// not been used yet.
+    refilled: Option<Instant>,
+    waiting: BTreeSet<Ticket>,
+    next: u64,
+}
+
+impl Bucket {
+    /// Adds the tokens that the bucket has gained since it was last refilled.
+    fn refill(&mut self, now: Instant, limit: &RateLimit) {
+        let refilled = *self.refilled.get_or_insert(now);
+        let period = limit.period();
+        let gained = now.saturating_duration_since(refilled).as_nanos() / period.as_nanos();
+        let gained = u32::try_from(gained).unwrap_or(u32::MAX);
+        self.tokens = self.tokens.saturating_add(gained);
+        if self.tokens >= limit.burst {
+            self.tokens = limit.burst;
+            self.refilled = Some(now);
+        } else {
+            self.refilled = Some(refilled + period * gained);
+        }
+    }
+}
+
+impl Throttle {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: limit.burst,
+                refilled: None,
+                waiting: BTreeSet::new(),
+                next: 0,
+            })),
+            changed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Waits until the limit allows a request with the given priority to be
+    /// sent, reading the time from the given clock.
+    pub(crate) async fn acquire(&self, priority: Priority, clock: &dyn Clock) {
+        let ticket = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let ticket = (Reverse(priority), bucket.next);
+            bucket.next += 1;
+            bucket.waiting.insert(ticket);
+            ticket
+        };
+        // Stop waiting even if the request is dropped while waiting.
+        let _waiting = Leave(self, ticket);
+        loop {
+            // Created before checking the bucket, so that no notification
+            // is missed in between.
+            let changed = self.changed.notified();
+            let deadline = {
+                let mut bucket = self.bucket.lock().unwrap();
+                bucket.refill(clock.now(), &self.limit);
+                if bucket.waiting.first() != Some(&ticket) {
+                    None
+                } else if bucket.tokens > 0 {
+                    bucket.tokens -= 1;
+                    break;
+                } else {
+                    bucket
+                        .refilled
+                        .map(|refilled| refilled + self.limit.period())
+                }
+            };
+            match deadline {
+                Some(deadline) => tokio::select! {
+                    _ = clock.sleep_until(deadline) => {},
+                    _ = changed => {},
+                },
+                None => changed.await,
+            }
+        }
+    }
+}
+
+/// Removes a request from those waiting for a token when dropped.
+struct Leave<'a>(&'a Throttle, Ticket);
+
+impl Drop for Leave<'_> {
+    fn drop(&mut self) {
+        self.0.bucket.lock().unwrap().waiting.remove(&self.1);
+        self.0.changed.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod limiter {
+        use super::*;
+
+        #[tokio::test]
+        async fn grants_permits_up_to_limit() {
+            let limiter = Limiter::new(ConcurrencyLimit::new(2));
+            let first = limiter.acquire().await;
+            let second = limiter.acquire().await;
+            assert!(first.is_some() && second.is_some());
+            assert!(limiter.acquire().await.is_none());
+        }
+
+        #[tokio::test]
+        async fn grants_permit_once_another_is_released() {
+            let limiter = Limiter::new(ConcurrencyLimit::new(1));
+            let first = limiter.acquire().await;
+            drop(first);
+            assert!(limiter.acquire().await.is_some());
+        }
+
+        #[tokio::test]
+        async fn queued_requests_wait_for_permit() {
+            let limiter = Limiter::new(ConcurrencyLimit::new(1).with_queue(1));
+            let first = limiter.acquire().await.unwrap();
+
+            let waiter = limiter.clone();
+            let queued = tokio::spawn(async move { waiter.acquire().await.is_some() });
+            tokio::task::yield_now().await;
+            // The queue is full, so further requests are rejected.
+            assert!(limiter.acquire().await.is_none());
+
+            drop(first);
+            assert!(queued.await.unwrap());
+        }
+
+        #[tokio::test]
+        async fn cancelled_requests_leave_queue() {
+            let limiter = Limiter::new(ConcurrencyLimit::new(1).with_queue(1));
+            let _first = limiter.acquire().await.unwrap();
+            let cancelled =
+                tokio::time::timeout(std::time::Duration::from_millis(1), limiter.acquire()).await;
+            assert!(cancelled.is_err());
+            assert_eq!(limiter.queued.load(Ordering::SeqCst), 0);
+        }
+    }
+
+    mod throttle {
+        use super::*;
+        use crate::time::MockClock;
+
+        #[tokio::test]
+        async fn sends_burst_at_once() {
+            let clock = MockClock::new();
+            let throttle = Throttle::new(RateLimit::new(1).with_burst(3));
+            for _ in 0..3 {
+                throttle.acquire(Priority::Normal, &clock).await;
+            }
+            let waiting = tokio::time::timeout(
+                Duration::from_millis(1),
+                throttle.acquire(Priority::Normal, &clock),
+            )
+            .await;
+            assert!(waiting.is_err());
+        }
+
+        #[tokio::test]
+        async fn waits_for_bucket_to_be_refilled() {
+            let clock = MockClock::new();
+            let throttle = Throttle::new(RateLimit::new(2).with_burst(1));
+            throttle.acquire(Priority::Normal, &clock).await;
+
+            let waiting = tokio::spawn({
+                let (throttle, clock) = (throttle.clone(), clock.clone());
+                async move { throttle.acquire(Priority::Normal, &clock).await }
+            });
+            tokio::task::yield_now().await;
+            assert!(!waiting.is_finished());
+
+            clock.advance(Duration::from_millis(500));
+            waiting.await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn sends_requests_with_higher_priority_first() {
+            let clock = MockClock::new();
+            let throttle = Throttle::new(RateLimit::new(1).with_burst(1));
+            throttle.acquire(Priority::Normal, &clock).await;
+
+            let sent = Arc::new(Mutex::new(Vec::new()));
+            let mut handles = Vec::new();
+            for priority in [Priority::Low, Priority::Normal, Priority::High] {
+                let (throttle, clock, sent) = (throttle.clone(), clock.clone(), sent.clone());
+                handles.push(tokio::spawn(async move {
+                    throttle.acquire(priority, &clock).await;
+                    sent.lock().unwrap().push(priority);
+                }));
+                tokio::task::yield_now().await;
+            }
+            for _ in 0..3 {
+                clock.advance(Duration::from_secs(1));
+                for _ in 0..3 {
+                    tokio::task::yield_now().await;
+                }
+            }
+            for handle in handles {
+                handle.await.unwrap();
+            }
+            assert_eq!(
+                *sent.lock().unwrap(),
+                vec![Priority::High, Priority::Normal, Priority::Low]
+            );
+        }
+
+        #[tokio::test]
+        async fn cancelled_requests_stop_waiting() {
+            let clock = MockClock::new();
+            let throttle = Throttle::new(RateLimit::new(1).with_burst(1));
+            throttle.acquire(Priority::Normal, &clock).await;
+            let cancelled = tokio::time::timeout(
+                Duration::from_millis(1),
+                throttle.acquire(Priority::High, &clock),
+            )
+            .await;
+            assert!(cancelled.is_err());
+            assert!(throttle.bucket.lock().unwrap().waiting.is_empty());
+        }
+
+        #[test]
+        #[should_panic(expected = "Rate limit must allow some requests")]
+        fn rejects_rate_of_zero() {
+            RateLimit::new(0);
+        }
+    }
+}
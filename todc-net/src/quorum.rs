@@ -0,0 +1,837 @@
+//! Quorums of instances that tolerate a bounded number of crashes.
+//!
+//! The algorithms in this crate are replicated across `n` instances, of
+//! which up to `f` may crash. Each operation waits for replies from a
+//! _quorum_ of `n - f` instances, which it can always gather, since at least
+//! that many instances remain. If `n > 2f`, then any two quorums intersect
+//! in at least one instance, and so an operation always learns of any
+//! operation that completed before it began. If `n <= 2f`, then two quorums
+//! may be disjoint, and no algorithm in this crate can tolerate `f` crashes.
+//!
+//! A [`Resilience`] captures `n` and `f`, checks that `n > 2f`, and answers
+//! the questions that every quorum-based algorithm asks, such as how many
+//! replies it must wait for.
+//!
+//! # Byzantine Failures
+//!
+//! Instances that are _Byzantine_, rather than crashing, may reply with
+//! arbitrary values. A [`MaskingResilience`] describes the _masking quorums_
+//! of Malkhi and Reiter \[MR98\], which require `n > 4f`. Any two masking
+//! quorums intersect in at least `2f + 1` instances, and so a value that was
+//! stored at a quorum is reported by at least `f + 1` correct instances of
+//! any other quorum, which is more than the Byzantine instances can
+//! fabricate.
+//!
+//! # Weighted Voting
+//!
+//! Some instances may be more reliable than others. With the _weighted
+//! voting_ of Gifford \[Gif79\], each instance is given a number of votes,
+//! and operations wait for replies from instances that hold enough votes,
+//! rather than from enough instances. Learning the values stored at
+//! instances requires a _read threshold_ `r` of votes, and storing a value
+//! at them requires a _write threshold_ `w`. If `r + w` is greater than the
+//! total number of votes, then every read quorum intersects every write
+//! quorum, which is all that an algorithm such as
+//! [`AtomicRegister`](crate::register::AtomicRegister) requires. Thresholds
+//! are checked by [`Thresholds::new`].
+//!
+//! A [`QuorumPolicy`] chooses between counting instances, with an optional
+//! failure threshold, and [`WeightedVoting`].
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_net::quorum::Resilience;
+//!
+//! // Five instances, of which at most one may crash.
+//! let resilience = Resilience::new(5, 1).unwrap();
+//! assert_eq!(resilience.quorum(), 4);
+//! assert!(!resilience.is_available(3));
+//!
+//! // Five instances tolerate at most two crashes.
+//! assert_eq!(Resilience::majority(5).failures(), 2);
+//! assert!(Resilience::new(5, 3).is_err());
+//! ```
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use hyper::Uri;
+
+/// The number of instances of a replicated object, and the number of them
+/// that may crash.
+///
+/// See the [`quorum`](crate::quorum) module-level documentation for more
+/// details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Resilience {
+    instances: usize,
+    failures: usize,
+}
+
+impl Resilience {
+    /// Creates the resilience of `instances` instances, of which at most
+    /// `failures` may crash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error unless `instances > 2 * failures`.
+    pub fn new(instances: usize, failures: usize) -> Result<Self, TooFewInstances> {
+        if instances > 2 * failures {
+            Ok(Self {
+                instances,
+                failures,
+            })
+        } else {
+            Err(TooFewInstances {
+                instances,
+                failures,
+                required: 2 * failures + 1,
+            })
+        }
+    }
+
+    /// Creates the resilience of `instances` instances, of which any
+    /// minority may crash.
+    ///
+    /// This is the largest number of crashes that `instances` instances can
+    /// tolerate.
+    pub fn majority(instances: usize) -> Self {
+        Self {
+            instances,
+            failures: instances.saturating_sub(1) / 2,
+        }
+    }
+
+    /// Returns the number of instances.
+    pub fn instances(&self) -> usize {
+        self.instances
+    }
+
+    /// Returns the number of instances that may crash.
+    pub fn failures(&self) -> usize {
+        self.failures
+    }
+
+    /// Returns the number of instances that form a quorum.
+    pub fn quorum(&self) -> usize {
+        self.instances - self.failures
+    }
+
+    /// Returns whether the given number of instances, that are available,
+    /// forms a quorum.
+    pub fn is_available(&self, available: usize) -> bool {
+        available >= self.quorum()
+    }
+
+    /// Returns the [`Thresholds`] of a system in which every instance has a
+    /// single vote, and both thresholds are a quorum.
+    pub fn thresholds(&self) -> Thresholds {
+        Thresholds {
+            total: self.instances,
+            read: self.quorum(),
+            write: self.quorum(),
+        }
+    }
+}
+
+/// The number of instances of a replicated object, and the number of them
+/// that may be Byzantine, for an object that uses masking quorums.
+///
+/// See [Byzantine Failures](self#byzantine-failures) for more details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaskingResilience {
+    instances: usize,
+    failures: usize,
+}
+
+impl MaskingResilience {
+    /// Creates the resilience of `instances` instances, of which at most
+    /// `failures` may be Byzantine.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error unless `instances > 4 * failures`.
+    pub fn new(instances: usize, failures: usize) -> Result<Self, TooFewInstances> {
+        if instances > 4 * failures {
+            Ok(Self {
+                instances,
+                failures,
+            })
+        } else {
+            Err(TooFewInstances {
+                instances,
+                failures,
+                required: 4 * failures + 1,
+            })
+        }
+    }
+
+    /// Returns the number of instances.
+    pub fn instances(&self) -> usize {
+        self.instances
+    }
+
+    /// Returns the number of instances that may be Byzantine.
+    pub fn failures(&self) -> usize {
+        self.failures
+    }
+
+    /// Returns the number of instances that form a quorum, which is the
+    /// smallest number such that any two quorums intersect in at least
+    /// `2f + 1` instances.
+    pub fn quorum(&self) -> usize {
+        (self.instances + 2 * self.failures + 2) / 2
+    }
+
+    /// Returns the number of instances that must report the same value
+    /// before it can be trusted, which is one more than the number of
+    /// Byzantine instances.
+    pub fn vouchers(&self) -> usize {
+        self.failures + 1
+    }
+
+    /// Returns whether the given number of instances, that are available,
+    /// forms a quorum.
+    pub fn is_available(&self, available: usize) -> bool {
+        available >= self.quorum()
+    }
+}
+
+/// The total number of votes held by the instances of a replicated object,
+/// and the number of votes required to read from, or write to, them.
+///
+/// See [Weighted Voting](self#weighted-voting) for more details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Thresholds {
+    total: usize,
+    read: usize,
+    write: usize,
+}
+
+impl Thresholds {
+    /// Creates the thresholds of `total` votes, of which `read` are required
+    /// to read, and `write` to write.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error unless every read quorum intersects every write
+    /// quorum, which is when `read + write > total`, and unless both
+    /// thresholds can be reached, which is when neither exceeds `total`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_net::quorum::Thresholds;
+    ///
+    /// // Reads are served by any one of three instances, but writes must be
+    /// // acknowledged by all of them.
+    /// assert!(Thresholds::new(3, 1, 3).is_ok());
+    /// assert!(Thresholds::new(3, 1, 2).is_err());
+    /// ```
+    pub fn new(total: usize, read: usize, write: usize) -> Result<Self, InvalidThresholds> {
+        if read + write > total && read <= total && write <= total {
+            Ok(Self { total, read, write })
+        } else {
+            Err(InvalidThresholds { total, read, write })
+        }
+    }
+
+    /// Creates the thresholds of `total` votes, of which a majority is
+    /// required to both read and write.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no votes.
+    pub fn majority(total: usize) -> Result<Self, InvalidThresholds> {
+        let majority = total / 2 + 1;
+        Self::new(total, majority, majority)
+    }
+
+    /// Returns the total number of votes.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the number of votes required to read.
+    pub fn read(&self) -> usize {
+        self.read
+    }
+
+    /// Returns the number of votes required to write.
+    pub fn write(&self) -> usize {
+        self.write
+    }
+
+    /// Returns whether the given number of votes, held by instances that are
+    /// available, is enough to both read and write.
+    pub fn is_available(&self, votes: usize) -> bool {
+        votes >= self.read.max(self.write)
+    }
+}
+
+/// The votes held by an instance of a replicated object and by each of its
+/// neighbors, and the thresholds that operations must reach.
+///
+/// See [Weighted Voting](self#weighted-voting) for more details.
+///
+/// # Examples
+///
+/// ```
+/// use hyper::Uri;
+/// use todc_net::quorum::WeightedVoting;
+///
+/// let reliable = Uri::from_static("https://my-register-2.com");
+/// let flaky = Uri::from_static("https://my-register-3.com");
+/// let voting = WeightedVoting::new(2).with_weight(reliable.clone(), 2);
+///
+/// // A majority of the five votes is held by this instance and the
+/// // reliable neighbor, without the flaky one.
+/// let thresholds = voting.thresholds(&[reliable, flaky]).unwrap();
+/// assert_eq!(thresholds.total(), 5);
+/// assert!(thresholds.is_available(4));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedVoting {
+    weight: usize,
+    weights: HashMap<Uri, usize>,
+    thresholds: Option<(usize, usize)>,
+}
+
+impl WeightedVoting {
+    /// Creates weighted votes in which this instance holds `weight` votes.
+    ///
+    /// Every neighbor holds a single vote, unless given a different weight
+    /// with [`with_weight`](WeightedVoting::with_weight), and operations
+    /// wait for a majority of votes, unless different thresholds are given
+    /// with [`with_thresholds`](WeightedVoting::with_thresholds).
+    pub fn new(weight: usize) -> Self {
+        Self {
+            weight,
+            weights: HashMap::new(),
+            thresholds: None,
+        }
+    }
+
+    /// Sets the number of votes held by a neighbor.
+    pub fn with_weight(mut self, neighbor: Uri, weight: usize) -> Self {
+        self.weights.insert(neighbor, weight);
+        self
+    }
+
+    /// Sets the number of votes required to read and to write.
+    ///
+    /// Thresholds are checked against the total number of votes whenever
+    /// they are used, since that depends on the neighbors of the instance.
+    pub fn with_thresholds(mut self, read: usize, write: usize) -> Self {
+        self.thresholds = Some((read, write));
+        self
+    }
+
+    /// Returns the number of votes held by this instance.
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    /// Returns the number of votes held by a neighbor.
+    pub fn weight_of(&self, neighbor: &Uri) -> usize {
+        self.weights.get(neighbor).copied().unwrap_or(1)
+    }
+
+    /// Returns the [`Thresholds`] of this instance and the given neighbors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the read and write thresholds do not intersect,
+    /// or cannot be reached, with the votes of these neighbors.
+    pub fn thresholds(&self, neighbors: &[Uri]) -> Result<Thresholds, InvalidThresholds> {
+        let total = self.weight + neighbors.iter().map(|n| self.weight_of(n)).sum::<usize>();
+        match self.thresholds {
+            Some((read, write)) => Thresholds::new(total, read, write),
+            None => Thresholds::majority(total),
+        }
+    }
+
+    /// Returns the [`Resilience`] of this instance and the given neighbors,
+    /// in which the failure threshold is the largest number of instances
+    /// that may crash, whichever they are, while both thresholds can still
+    /// be reached.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the thresholds are invalid. See
+    /// [`thresholds`](WeightedVoting::thresholds).
+    pub fn resilience(&self, neighbors: &[Uri]) -> Result<Resilience, InvalidThresholds> {
+        let thresholds = self.thresholds(neighbors)?;
+        let mut weights: Vec<usize> = neighbors.iter().map(|n| self.weight_of(n)).collect();
+        weights.push(self.weight);
+        // The worst case is that the instances with the most votes crash.
+        weights.sort_unstable_by(|a, b| b.cmp(a));
+        let mut remaining = thresholds.total();
+        let failures = weights
+            .iter()
+            .take_while(|&&weight| {
+                remaining -= weight;
+                thresholds.is_available(remaining)
+            })
+            .count();
+        // Since the thresholds intersect, the instances that crash hold
+        // fewer than half of the votes, and so are a minority.
+        Ok(Resilience {
+            instances: weights.len(),
+            failures,
+        })
+    }
+}
+
+/// How the replies of instances are counted towards a quorum.
+///
+/// See the [`quorum`](crate::quorum) module-level documentation for more
+/// details.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum QuorumPolicy {
+    /// Every instance holds a single vote, and any minority may crash.
+    #[default]
+    Majority,
+    /// Every instance holds a single vote, and at most the given number may
+    /// crash.
+    FailureThreshold(usize),
+    /// Instances hold the given [`WeightedVoting`] votes.
+    Weighted(WeightedVoting),
+}
+
+impl QuorumPolicy {
+    /// Returns the number of votes held by this instance.
+    pub fn weight(&self) -> usize {
+        match self {
+            Self::Weighted(voting) => voting.weight(),
+            Self::Majority | Self::FailureThreshold(_) => 1,
+        }
+    }
+
+    /// Returns the number of votes held by a neighbor.
+    pub fn weight_of(&self, neighbor: &Uri) -> usize {
+        match self {
+            Self::Weighted(voting) => voting.weight_of(neighbor),
+            Self::Majority | Self::FailureThreshold(_) => 1,
+        }
+    }
+
+    /// Returns the [`Thresholds`] of this instance and the given neighbors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the neighbors are too few to tolerate the failure
+    /// threshold, or if their votes do not suit the weighted thresholds.
+    pub fn thresholds(&self, neighbors: &[Uri]) -> Result<Thresholds, QuorumError> {
+        match self {
+            Self::Weighted(voting) => Ok(voting.thresholds(neighbors)?),
+            Self::Majority | Self::FailureThreshold(_) => {
+                Ok(self.resilience(neighbors)?.thresholds())
+            }
+        }
+    }
+
+    /// Returns the [`Resilience`] of this instance and the given neighbors.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in the same cases as
+    /// [`thresholds`](QuorumPolicy::thresholds).
+    pub fn resilience(&self, neighbors: &[Uri]) -> Result<Resilience, QuorumError> {
+        let instances = neighbors.len() + 1;
+        match self {
+            Self::Majority => Ok(Resilience::majority(instances)),
+            Self::FailureThreshold(failures) => Ok(Resilience::new(instances, *failures)?),
+            Self::Weighted(voting) => Ok(voting.resilience(neighbors)?),
+        }
+    }
+
+    /// Returns the policy of an instance that holds no votes, and only
+    /// performs operations on behalf of the given `instances`, which hold
+    /// the votes of this policy between them.
+    ///
+    /// Operations performed by such an instance wait for the same replies
+    /// from `instances` as those performed by any one of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `instances` are too few to tolerate the failure
+    /// threshold, or if their votes do not suit the weighted thresholds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::quorum::QuorumPolicy;
+    ///
+    /// let instances: Vec<Uri> = (1..4)
+    ///     .map(|i| format!("https://my-register-{i}.com").parse().unwrap())
+    ///     .collect();
+    /// let policy = QuorumPolicy::Majority.without_vote(&instances).unwrap();
+    /// assert_eq!(policy.weight(), 0);
+    /// assert_eq!(policy.thresholds(&instances).unwrap().write(), 2);
+    /// ```
+    pub fn without_vote(&self, instances: &[Uri]) -> Result<Self, QuorumError> {
+        let voting = match self {
+            Self::Majority => WeightedVoting::new(0),
+            Self::FailureThreshold(failures) => {
+                let quorum = Resilience::new(instances.len(), *failures)?.quorum();
+                WeightedVoting::new(0).with_thresholds(quorum, quorum)
+            }
+            Self::Weighted(voting) => WeightedVoting {
+                weight: 0,
+                ..voting.clone()
+            },
+        };
+        voting.thresholds(instances)?;
+        Ok(Self::Weighted(voting))
+    }
+}
+
+/// An error returned when read and write thresholds do not intersect, or
+/// cannot be reached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidThresholds {
+    /// The total number of votes.
+    pub total: usize,
+    /// The number of votes required to read.
+    pub read: usize,
+    /// The number of votes required to write.
+    pub write: usize,
+}
+
+impl Display for InvalidThresholds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Read and write thresholds of {} and {} votes must each be at most, and together exceed, the total of {} votes",
+            self.read, self.write, self.total
+        )
+    }
+}
+
+impl Error for InvalidThresholds {}
+
+/// An error returned when a [`QuorumPolicy`] cannot be satisfied by the
+/// instances that it is applied to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuorumError {
+    /// There are too few instances to tolerate the failure threshold.
+    TooFewInstances(TooFewInstances),
+    /// The thresholds of weighted voting are invalid.
+    InvalidThresholds(InvalidThresholds),
+}
+
+impl Display for QuorumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFewInstances(error) => write!(f, "{error}"),
+            Self::InvalidThresholds(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for QuorumError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::TooFewInstances(error) => Some(error),
+            Self::InvalidThresholds(error) => Some(error),
+        }
+    }
+}
+
+impl From<TooFewInstances> for QuorumError {
+    fn from(error: TooFewInstances) -> Self {
+        Self::TooFewInstances(error)
+    }
+}
+
+impl From<InvalidThresholds> for QuorumError {
+    fn from(error: InvalidThresholds) -> Self {
+        Self::InvalidThresholds(error)
+    }
+}
+
+/// An error returned when too few instances are given to tolerate the
+/// requested number of failures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooFewInstances {
+    /// The number of instances.
+    pub instances: usize,
+    /// The number of failures that were requested to be tolerated.
+    pub failures: usize,
+    /// The number of instances required to tolerate that many failures.
+    pub required: usize,
+}
+
+impl Display for TooFewInstances {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Tolerating {} failures requires at least {} instances, but there are {}",
+            self.failures, self.required, self.instances
+        )
+    }
+}
+
+impl Error for TooFewInstances {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod new {
+        use super::*;
+
+        #[test]
+        fn accepts_more_than_twice_as_many_instances_as_failures() {
+            let resilience = Resilience::new(3, 1).unwrap();
+            assert_eq!(resilience.instances(), 3);
+            assert_eq!(resilience.failures(), 1);
+        }
+
+        #[test]
+        fn rejects_twice_as_many_instances_as_failures() {
+            let error = Resilience::new(4, 2).unwrap_err();
+            assert_eq!(
+                error,
+                TooFewInstances {
+                    instances: 4,
+                    failures: 2,
+                    required: 5,
+                }
+            );
+            assert!(error.to_string().contains("at least 5 instances"));
+        }
+
+        #[test]
+        fn accepts_no_failures() {
+            assert_eq!(Resilience::new(1, 0).unwrap().quorum(), 1);
+        }
+    }
+
+    mod majority {
+        use super::*;
+
+        #[test]
+        fn tolerates_minority_of_failures() {
+            for (instances, failures) in [(1, 0), (2, 0), (3, 1), (4, 1), (5, 2)] {
+                assert_eq!(Resilience::majority(instances).failures(), failures);
+            }
+        }
+
+        #[test]
+        fn has_quorum_of_majority() {
+            for instances in 1..10 {
+                assert_eq!(Resilience::majority(instances).quorum(), instances / 2 + 1);
+            }
+        }
+    }
+
+    mod masking_resilience {
+        use super::*;
+
+        #[test]
+        fn rejects_four_times_as_many_instances_as_failures() {
+            let error = MaskingResilience::new(4, 1).unwrap_err();
+            assert_eq!(error.required, 5);
+            assert!(MaskingResilience::new(5, 1).is_ok());
+        }
+
+        #[test]
+        fn quorums_intersect_in_more_than_twice_as_many_instances_as_failures() {
+            for failures in 0..4 {
+                for instances in (4 * failures + 1)..20 {
+                    let resilience = MaskingResilience::new(instances, failures).unwrap();
+                    let quorum = resilience.quorum();
+                    assert!(2 * quorum - instances > 2 * failures);
+                    // A quorum is available even if every Byzantine instance
+                    // stops replying.
+                    assert!(resilience.is_available(instances - failures));
+                }
+            }
+        }
+
+        #[test]
+        fn values_are_vouched_for_by_more_than_failures() {
+            assert_eq!(MaskingResilience::new(9, 2).unwrap().vouchers(), 3);
+        }
+    }
+
+    mod thresholds {
+        use super::*;
+
+        #[test]
+        fn accepts_intersecting_thresholds() {
+            let thresholds = Thresholds::new(5, 2, 4).unwrap();
+            assert_eq!(thresholds.read(), 2);
+            assert_eq!(thresholds.write(), 4);
+        }
+
+        #[test]
+        fn rejects_thresholds_that_do_not_intersect() {
+            let error = Thresholds::new(5, 2, 3).unwrap_err();
+            assert_eq!(
+                error,
+                InvalidThresholds {
+                    total: 5,
+                    read: 2,
+                    write: 3
+                }
+            );
+        }
+
+        #[test]
+        fn rejects_thresholds_that_cannot_be_reached() {
+            assert!(Thresholds::new(5, 0, 6).is_err());
+            assert!(Thresholds::new(5, 6, 0).is_err());
+        }
+
+        #[test]
+        fn majority_requires_votes() {
+            assert_eq!(Thresholds::majority(4).unwrap().read(), 3);
+            assert!(Thresholds::majority(0).is_err());
+        }
+
+        #[test]
+        fn is_available_once_both_thresholds_are_reached() {
+            let thresholds = Thresholds::new(5, 2, 4).unwrap();
+            assert!(!thresholds.is_available(3));
+            assert!(thresholds.is_available(4));
+        }
+    }
+
+    mod weighted_voting {
+        use super::*;
+
+        fn neighbors(n: usize) -> Vec<Uri> {
+            (0..n)
+                .map(|i| format!("http://test-{i}.com").parse().unwrap())
+                .collect()
+        }
+
+        #[test]
+        fn neighbors_hold_single_vote_by_default() {
+            let voting = WeightedVoting::new(3);
+            let thresholds = voting.thresholds(&neighbors(2)).unwrap();
+            assert_eq!(thresholds.total(), 5);
+            assert_eq!(thresholds.read(), 3);
+            assert_eq!(thresholds.write(), 3);
+        }
+
+        #[test]
+        fn counts_weights_of_neighbors() {
+            let neighbors = neighbors(2);
+            let voting = WeightedVoting::new(1).with_weight(neighbors[0].clone(), 4);
+            assert_eq!(voting.thresholds(&neighbors).unwrap().total(), 6);
+        }
+
+        #[test]
+        fn checks_thresholds_against_neighbors() {
+            let voting = WeightedVoting::new(1).with_thresholds(2, 2);
+            assert!(voting.thresholds(&neighbors(2)).is_ok());
+            assert!(voting.thresholds(&neighbors(3)).is_err());
+        }
+
+        #[test]
+        fn tolerates_crash_of_instances_with_fewest_votes() {
+            // Five votes, of which this instance holds three. Any one of
+            // the other instances may crash, but not this one.
+            let neighbors = neighbors(2);
+            let voting = WeightedVoting::new(3);
+            let resilience = voting.resilience(&neighbors).unwrap();
+            assert_eq!(resilience.instances(), 3);
+            assert_eq!(resilience.failures(), 0);
+
+            // With equal weights, any minority may crash.
+            let voting = WeightedVoting::new(1);
+            assert_eq!(
+                voting.resilience(&neighbors).unwrap(),
+                Resilience::majority(3)
+            );
+        }
+    }
+
+    mod quorum_policy {
+        use super::*;
+
+        #[test]
+        fn thresholds_of_failure_threshold_are_quorums() {
+            let neighbors: Vec<Uri> = (0..4)
+                .map(|i| format!("http://test-{i}.com").parse().unwrap())
+                .collect();
+            let thresholds = QuorumPolicy::FailureThreshold(1)
+                .thresholds(&neighbors)
+                .unwrap();
+            assert_eq!(thresholds, Thresholds::new(5, 4, 4).unwrap());
+        }
+
+        #[test]
+        fn rejects_too_few_instances_for_failure_threshold() {
+            let result = QuorumPolicy::FailureThreshold(1).thresholds(&[]);
+            assert!(matches!(result, Err(QuorumError::TooFewInstances(_))));
+        }
+
+        #[test]
+        fn instances_hold_single_vote_unless_weighted() {
+            let neighbor = Uri::from_static("http://test.com");
+            assert_eq!(QuorumPolicy::Majority.weight_of(&neighbor), 1);
+            let voting = WeightedVoting::new(2).with_weight(neighbor.clone(), 3);
+            let policy = QuorumPolicy::Weighted(voting);
+            assert_eq!(policy.weight(), 2);
+            assert_eq!(policy.weight_of(&neighbor), 3);
+        }
+
+        #[test]
+        fn without_vote_waits_for_same_quorum_as_instances() {
+            let instances: Vec<Uri> = (0..5)
+                .map(|i| format!("http://test-{i}.com").parse().unwrap())
+                .collect();
+            let policy = QuorumPolicy::FailureThreshold(1)
+                .without_vote(&instances)
+                .unwrap();
+            assert_eq!(policy.weight(), 0);
+            assert_eq!(
+                policy.thresholds(&instances).unwrap(),
+                Thresholds::new(5, 4, 4).unwrap()
+            );
+        }
+
+        #[test]
+        fn without_vote_keeps_weights_of_instances() {
+            let heavy = Uri::from_static("http://heavy.com");
+            let light = Uri::from_static("http://light.com");
+            let voting = WeightedVoting::new(2).with_weight(heavy.clone(), 3);
+            let policy = QuorumPolicy::Weighted(voting)
+                .without_vote(&[heavy.clone(), light])
+                .unwrap();
+            assert_eq!(policy.weight(), 0);
+            assert_eq!(policy.weight_of(&heavy), 3);
+        }
+
+        #[test]
+        fn without_vote_rejects_empty_instances() {
+            let result = QuorumPolicy::Majority.without_vote(&[]);
+            assert!(matches!(result, Err(QuorumError::InvalidThresholds(_))));
+        }
+    }
+
+    mod quorum {
+        use super::*;
+
+        #[test]
+        fn grows_as_fewer_failures_are_tolerated() {
+            assert_eq!(Resilience::new(5, 2).unwrap().quorum(), 3);
+            assert_eq!(Resilience::new(5, 1).unwrap().quorum(), 4);
+            assert_eq!(Resilience::new(5, 0).unwrap().quorum(), 5);
+        }
+
+        #[test]
+        fn is_available_once_quorum_is_reached() {
+            let resilience = Resilience::new(5, 1).unwrap();
+            assert!(!resilience.is_available(3));
+            assert!(resilience.is_available(4));
+        }
+    }
+}
@@ -5,9 +5,24 @@
 //! guarantees such as [atomicity](https://en.wikipedia.org/wiki/Atomic_semantics)
 //! continue to hold even in the face of crashes and arbitrary message delays.
 //!
+//! The registers in [`abd_95`] tolerate instances that crash. The
+//! [`ByzantineRegister`] in [`mr_98`] instead tolerates instances that are
+//! _Byzantine_, and may behave arbitrarily. A [`ShardedRegisterMap`] in
+//! [`sharded`] spreads the registers of many keys across independent groups
+//! of instances.
+//!
 //! # Examples
 //!
 //! See the [`abd_95`] module-level documentation for examples.
 pub mod abd_95;
+pub mod array;
+#[cfg(feature = "history")]
+pub mod history;
+pub mod label;
+pub mod mr_98;
+pub mod sharded;
 
 pub use self::abd_95::AtomicRegister;
+pub use self::array::RegisterArray;
+pub use self::mr_98::ByzantineRegister;
+pub use self::sharded::ShardedRegisterMap;
@@ -3,8 +3,10 @@
 //! as described by Attiya, Bar-Noy and Dolev
 //! [\[ABD95\]](https://dl.acm.org/doi/pdf/10.1145/200836.200869).
 //!
-//! The atomicity guarantee only holds if at most a minority of instances
-//! crash.
+//! The atomicity guarantee only holds if at most `f` of the `n` instances
+//! crash, where `n > 2f`. By default, `f` is the largest such number, so
+//! that any minority of instances may crash. See
+//! [Tolerating Failures](self#tolerating-failures).
 //!
 //! # Examples
 //!
@@ -85,6 +87,13 @@
 //! Although this register isn't fault-tolerant yet, we can still try it out. See
 //! the runnable example at [`todc-net/examples/atomic-register-hyper`](https://github.com/kaymanb/todc/tree/main/todc-net/examples/atomic-register-hyper).
 //!
+//! Instances also respond to `GET` and `POST` requests made to `/register`
+//! themselves, exchanging the contents of the register as JSON, or with the
+//! [`Codec`](crate::codec::Codec) named by their `Content-Type`, whenever
+//! a router like the one above does not handle them first. A
+//! [`RegisterClient`](crate::client::RegisterClient) can be used to read
+//! from and write to an instance served in this way.
+//!
 //! ## Adding Fault Tolerance with Multiple Instances
 //!
 //! To make our register fault tolerant, we need to add more instances. Suppose that
@@ -115,28 +124,794 @@
 //! To interact with a fault-tolerant register backed by multiple instances, see
 //! the runnable example at
 //! [`todc-net/examples/atomic-register-docker-minikube`](https://github.com/kaymanb/todc/tree/main/todc-net/examples/atomic-register-docker-minikube).
-use std::fmt::Debug;
+//!
+//! ## Tolerating Failures
+//!
+//! Each operation waits until a _quorum_ of `n - f` instances, including
+//! the one performing it, have replied. Since `n > 2f`, any two quorums
+//! intersect, which is what makes the register atomic. By default, `f` is
+//! chosen so that a quorum is a majority of instances. A smaller `f` can be
+//! chosen with
+//! [`with_failure_threshold`](AtomicRegister::with_failure_threshold), such
+//! as to state the guarantee of a deployment explicitly, at the cost of
+//! operations that wait for more replies. See the [`quorum`](crate::quorum)
+//! module for details.
+//!
+//! ```
+//! use hyper::Uri;
+//! use todc_net::register::AtomicRegister;
+//!
+//! let neighbors: Vec<Uri> = (1..5)
+//!     .map(|i| format!("https://my-register-{i}.com").parse().unwrap())
+//!     .collect();
+//!
+//! // Five instances, of which at most one may crash.
+//! let register: AtomicRegister<u32> = AtomicRegister::new(neighbors)
+//!     .with_failure_threshold(1)
+//!     .unwrap();
+//! assert_eq!(register.resilience().quorum(), 4);
+//! ```
+//!
+//! ## Weighted Voting
+//!
+//! If some instances are more reliable than others, they can be given more
+//! votes with a [`WeightedVoting`](crate::quorum::WeightedVoting) policy,
+//! set with [`with_quorum_policy`](AtomicRegister::with_quorum_policy).
+//! Operations then wait for replies from instances that hold enough votes,
+//! rather than from enough instances. Asking instances for their values
+//! requires a read threshold of votes, and announcing a value requires a
+//! write threshold, which together must exceed the total number of votes.
+//! Since reads and writes both ask and then announce, the thresholds decide
+//! how many replies each phase waits for, rather than favoring reads over
+//! writes.
+//!
+//! ```
+//! use hyper::Uri;
+//! use todc_net::quorum::{QuorumPolicy, WeightedVoting};
+//! use todc_net::register::AtomicRegister;
+//!
+//! let reliable = Uri::from_static("https://my-register-2.com");
+//! let flaky = Uri::from_static("https://my-register-3.com");
+//!
+//! // Operations complete while the flaky neighbor is down, since this
+//! // instance and the reliable neighbor hold three of the four votes, but
+//! // not while the reliable neighbor is down.
+//! let voting = WeightedVoting::new(1).with_weight(reliable.clone(), 2);
+//! let register: AtomicRegister<u32> = AtomicRegister::new(vec![reliable, flaky])
+//!     .with_quorum_policy(QuorumPolicy::Weighted(voting))
+//!     .unwrap();
+//! assert!(register.status().quorum_reachable);
+//! ```
+//!
+//! ## Witnesses and Read-Only Instances
+//!
+//! Instances can be given a [`Role`] with
+//! [`with_role`](AtomicRegister::with_role). A [`Role::ReadOnly`] instance
+//! serves reads but rejects writes, and a [`Role::Witness`] serves neither.
+//! Both still store the contents of the register and count towards a
+//! majority, so a cluster of two full instances and one witness tolerates
+//! the crash of any one of them, while only accepting operations at two.
+//!
+//! ## Choosing a Codec
+//!
+//! Instances encode the values that they exchange as JSON by default. With
+//! the `bincode` or `cbor` features enabled, a more compact
+//! [`Codec`](crate::codec::Codec) can be chosen with the fourth type parameter
+//! of the register, so that large values are not inflated by JSON encoding.
+//! Instances still answer requests that ask for JSON, so clients can continue
+//! to read and write JSON regardless of the codec. See the
+//! [`codec`](crate::codec) module for details.
+//!
+//! ```
+//! # #[cfg(feature = "cbor")]
+//! # {
+//! use hyper::Uri;
+//! use todc_net::codec::Cbor;
+//! use todc_net::register::AtomicRegister;
+//! use todc_net::transport::HttpTransport;
+//!
+//! let neighbor = Uri::from_static("https://my-register-2.com");
+//! let register: AtomicRegister<Vec<u8>, HttpTransport, u64, Cbor> =
+//!     AtomicRegister::with_transport(vec![neighbor], HttpTransport::default());
+//! # }
+//! ```
+//!
+//! ## Choosing a Transport
+//!
+//! Instances exchange JSON over HTTP/1 by default. To multiplex messages over
+//! a single HTTP/2 connection to each neighbor, construct the register with
+//! [`with_transport`](AtomicRegister::with_transport) and an
+//! [`HttpTransport`] configured with
+//! [`with_http2`](HttpTransport::with_http2). With the `grpc` feature
+//! enabled, instances can instead exchange protobuf messages over HTTP/2.
+//! See the [`transport`](crate::transport) module for details.
+//!
+//! ## Streaming Large Values
+//!
+//! Each announcement normally contains both the value and the label of an
+//! instance, encoded together. An instance configured with
+//! [`with_streaming`](AtomicRegister::with_streaming) instead encodes only
+//! the value, once for all of its neighbors, and sends the label separately.
+//! Over HTTP, the label is sent in a header, and the value is streamed in
+//! chunks along with its digest, so that large values are replicated without
+//! buffering a separate copy for each request. Streaming does not affect the
+//! atomicity of the register.
+//!
+//! ## Read Leases
+//!
+//! Every read normally requires two rounds of communication with a majority
+//! of instances. An instance configured with
+//! [`with_lease`](AtomicRegister::with_lease) instead asks a majority of
+//! instances for a _lease_, which lasts for a fixed duration. Each instance
+//! that grants the lease promises that, until the lease expires, it will
+//! forward every value announced to it to the leaseholder, and wait for the
+//! leaseholder to adopt the value before acknowledging it. Since any two
+//! majorities intersect, no operation can complete without the leaseholder
+//! learning of its value, and so while its lease is valid, the leaseholder can
+//! serve reads from its local value. If its local value has changed since it
+//! was last announced to a majority, then the leaseholder announces it first,
+//! which requires only one round of communication.
+//!
+//! Leases rely on the following timing assumptions, which do not otherwise
+//! affect the atomicity of the register:
+//!
+//! - The clocks of instances advance at the same rate, up to an error of
+//!   [`margin`](LeaseConfig::with_margin) over the duration of a lease. The
+//!   clocks do **not** need to be synchronized, since a leaseholder considers
+//!   its lease to begin when it asks for it, before any instance grants it.
+//! - The neighbors of instances do not change while leases are held.
+//!
+//! Leases make writes more expensive. A value announced to an instance that
+//! has granted a lease is forwarded to the leaseholder before it is
+//! acknowledged, and if the leaseholder cannot be reached, the acknowledgement
+//! is delayed until the lease expires.
+//!
+//! ## Read Consistency
+//!
+//! Reads are linearizable by default, which requires two rounds of
+//! communication with a majority of instances. Applications that tolerate
+//! stale values can instead read with a weaker [`ReadConsistency`], using
+//! [`read_with_consistency`](AtomicRegister::read_with_consistency):
+//!
+//! - A [`Sequential`](ReadConsistency::Sequential) read asks a majority of
+//!   instances for their values, but does not announce the largest before
+//!   returning it. It requires one round of communication, and never returns
+//!   a value older than one written before it began, but two reads that do
+//!   not overlap may observe writes in opposite orders. In particular, a read
+//!   that returns the value of a write that is still in progress can be
+//!   followed by a read, at another instance, that returns the previous
+//!   value.
+//! - A [`Local`](ReadConsistency::Local) read returns the local value of the
+//!   instance without contacting any neighbors. It succeeds even when no
+//!   majority can be reached, but can return a value that is arbitrarily old,
+//!   such as the initial value of an instance that missed every write.
+//!
+//! Writes are unaffected by the consistency of reads.
+//!
+//! ## Timeouts
+//!
+//! An operation waits until a majority of instances reply, which never
+//! happens if too many of them have crashed or are partitioned away. A
+//! default timeout for every operation can be set with
+//! [`with_timeout`](AtomicRegister::with_timeout), and overridden for a single
+//! operation with [`read_with_timeout`](AtomicRegister::read_with_timeout) or
+//! [`write_with_timeout`](AtomicRegister::write_with_timeout). Operations that
+//! time out return [`RegisterError::Timeout`], and their outstanding requests
+//! to neighbors are cancelled.
+//!
+//! ## Hedging
+//!
+//! An operation sends its messages to every neighbor, and then waits for the
+//! fastest quorum to reply. Every instance keeps track of how long each of its
+//! neighbors takes to reply, as an exponentially weighted moving average that
+//! is reported in its [`Status`], which can be used to choose a delay for
+//! hedging.
+//!
+//! A single request that is lost or stalled, such as on a congested
+//! connection, can hold up an operation that needs a reply from that
+//! neighbor to form a quorum. With
+//! [`with_hedging`](AtomicRegister::with_hedging), if a quorum has not
+//! replied within the given delay, then the operation sends a duplicate of
+//! its message to every neighbor that has not replied yet, and counts
+//! whichever copy is answered first. Messages are idempotent, so a neighbor
+//! that recieves both copies acts as if it recieved one. This trades extra
+//! messages to slow neighbors for shorter tail latencies.
+//!
+//! ## Graceful Shutdown
+//!
+//! An instance that is restarted deliberately, such as during a rolling
+//! restart, can be shut down without abandoning the operations that it is
+//! coordinating, by giving it a [`Shutdown`] handle with
+//! [`with_shutdown`](AtomicRegister::with_shutdown). While the handle is
+//! draining, new operations fail with [`RegisterError::ShuttingDown`], and
+//! operations in flight complete as usual. Once it is stopped, quorum rounds
+//! that are still waiting on neighbors are cancelled. See the
+//! [`shutdown`](crate::shutdown) module for details.
+//!
+//! ## Idempotent Writes
+//!
+//! A client that retries a write after an attempt fails, or times out, cannot
+//! know whether the first attempt took effect, and so a retried write might
+//! otherwise be applied twice, with two different labels. Instead, a write
+//! made with [`write_with_request_id`](AtomicRegister::write_with_request_id),
+//! or with a `POST` request to `/register` that carries an
+//! [`x-request-id`](REQUEST_ID_HEADER) header, is assigned a label only once.
+//! Each instance remembers the labels assigned to its most recent requests,
+//! and a retry of a request that it remembers announces the value with the
+//! same label as before, which completes the original write rather than
+//! performing a new one. The request ID is returned in a header of the
+//! response, for tracing. A [`RegisterClient`](crate::client::RegisterClient)
+//! sends a unique ID with each write, and reuses it when retrying.
+//!
+//! ## Anti-Entropy
+//!
+//! An instance only learns of a newer value when an operation contacts it, so
+//! an instance that missed a write, for example because it was partitioned
+//! from the writer, remains stale until the next operation that it takes part
+//! in. Instances can instead converge in the background by _gossiping_ with
+//! their neighbors. In each round of gossip, started with
+//! [`spawn_gossip`](AtomicRegister::spawn_gossip), an instance exchanges its
+//! local value with that of a neighbor chosen uniformly at random, and both
+//! instances adopt the larger of the two. Since values only ever increase,
+//! gossip does not affect the atomicity of the register.
+//!
+//! ## Epochs
+//!
+//! Each instance is in an _epoch_, which is `0` unless it was constructed
+//! [`with_epoch`](AtomicRegister::with_epoch), and attaches its epoch to every
+//! message that it sends to its neighbors. An instance that recieves a
+//! message from a later epoch enters that epoch, and an instance rejects
+//! messages from earlier epochs, which therefore never count towards a
+//! majority. Over HTTP, the epoch is sent in the `X-Epoch` header, and
+//! rejected messages are answered with `409 Conflict`.
+//!
+//! Epochs only ever increase. Entering a new epoch, with
+//! [`enter_epoch`](AtomicRegister::enter_epoch), fences off instances that
+//! have not yet learned of it, such as an instance that returns after a long
+//! partition, so that they cannot complete operations based on outdated
+//! state until they are contacted by an instance in the new epoch. Messages
+//! without an epoch, such as those sent directly by clients to
+//! `/register/local`, are always accepted.
+//!
+//! Epochs are not persisted in [`Storage`]. An instance that recovers its
+//! local value after a restart begins again in epoch `0`, or in the epoch
+//! that it is constructed with, and so is fenced off until it learns of the
+//! current epoch.
+//!
+//! ## Protocol Versions
+//!
+//! Every message that an instance sends to its neighbors is written in a
+//! version of the protocol, which is sent in the `X-Protocol-Version` header
+//! over HTTP, and in the `version` field over gRPC. An instance understands
+//! every version in [`PROTOCOL_VERSIONS`], and rejects messages written in
+//! any other version with [`RegisterError::UnsupportedProtocol`], or with
+//! `426 Upgrade Required` over HTTP. Messages without a version, such as
+//! those sent by instances that predate versioning, are written in the first
+//! version.
+//!
+//! Rejections advertise the range of versions that the instance does
+//! understand, in the `X-Protocol-Versions` header over HTTP, such as `1-3`,
+//! and in the `x-protocol-versions` metadata over gRPC. By default, instances
+//! write messages in the latest version that they understand, and when a
+//! neighbor rejects one, send it again in the latest version that both
+//! understand. That version is used for every later message to the neighbor,
+//! and is reported in its [`NeighborStatus`]. If the instances share no
+//! version, then the neighbor is treated as unreachable.
+//!
+//! As a result, a deployment can be upgraded to a release that introduces a
+//! new version by restarting one instance at a time. Upgraded instances write
+//! the new version to each other, and the previous version to instances that
+//! have not been upgraded yet. Since a negotiated version is only revisited
+//! when the neighbor rejects it, instances keep writing the previous version
+//! to neighbors that were upgraded after they first negotiated with them,
+//! until they are restarted, or the previous version is retired. To hold back
+//! a new version until every instance understands it, instances can be
+//! pinned to an earlier one with
+//! [`with_protocol_version`](AtomicRegister::with_protocol_version).
+//!
+//! ## Service Discovery
+//!
+//! Rather than being given a fixed list of neighbors, instances in elastic
+//! deployments can find their neighbors with a
+//! [`Discovery`](crate::discovery::Discovery), such as the addresses behind a
+//! Kubernetes headless service. An instance polled with
+//! [`spawn_discovery`](AtomicRegister::spawn_discovery) periodically asks its
+//! discovery for the current set of neighbors, and
+//! [`reconfigure`](AtomicRegister::reconfigure)s itself whenever that set
+//! changes. Since each instance refreshes its own neighbors, instances may
+//! briefly disagree about the membership of the cluster while it grows or
+//! shrinks, so changes should be made one instance at a time.
+//!
+//! ## Logging
+//!
+//! Instances log through the [`tracing`](https://docs.rs/tracing) facade,
+//! and so are silent unless the application installs a subscriber, such as
+//! one from [`tracing-subscriber`](https://docs.rs/tracing-subscriber), which
+//! also controls verbosity. Each operation runs within a `DEBUG` span that is
+//! named after it, such as `read` or `write`, and carries a unique
+//! `operation` ID. Each message exchanged with a neighbor as part of an
+//! operation runs within a child `exchange` span that carries the `neighbor`
+//! and `route`. Neighbors that fail to reply are logged at `DEBUG`, and
+//! operations that cannot reach a quorum at `WARN`.
+//!
+//! ```text
+//! WARN read{operation=7}: todc_net::register::abd_95: quorum unavailable acks=1 needed=2
+//! ```
+//!
+//! ## Health Checks
+//!
+//! Each instance responds to `GET` requests made to `/register/status` with
+//! its [`Status`], as JSON: the label of its local value, and for each of its
+//! neighbors, the number of milliseconds since it last replied and whether
+//! the most recent attempt to contact it succeeded. The status also states
+//! whether a quorum is reachable, in which case the response is
+//! `200 OK`, and otherwise `503 Service Unavailable`, so that the route can
+//! be used directly as a readiness probe. Checking the status does not
+//! contact any neighbors.
+//!
+//! The status also shows how well instances keep up with one another. For
+//! each neighbor, it includes the label that the neighbor last replied with,
+//! and its `lag`, the number of labels by which that label trails the local
+//! value of this instance. It also counts the reads that _repaired_ this
+//! instance, by adopting a newer value from a neighbor, and the reads for
+//! which this instance was already _current_. A high proportion of repaired
+//! reads means that this instance often misses writes. Finally, the status
+//! includes the average `latency_ms` of each neighbor, which is the time it
+//! takes to reply. See [Hedging](self#hedging).
+//!
+//! ```text
+//! {
+//!   "label": 3,
+//!   "neighbors": [
+//!     {"url": "http://my-register-2.com/", "last_contact_ms": 120, "reachable": true, "label": 3, "lag": 0, "latency_ms": 1.5, "protocol_version": 1},
+//!     {"url": "http://my-register-3.com/", "last_contact_ms": null, "reachable": false, "label": null, "lag": null, "latency_ms": null, "protocol_version": 1}
+//!   ],
+//!   "quorum_reachable": true,
+//!   "reads": {"repaired": 2, "current": 40},
+//!   "protocol_version": 1
+//! }
+//! ```
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
 use std::future::Future;
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use bytes::{Buf, Bytes};
 use http_body_util::{BodyExt, Full};
 use hyper::body::Incoming;
+use hyper::header::{HeaderMap, HeaderName, ACCEPT, CONTENT_TYPE};
 use hyper::http::StatusCode;
 use hyper::service::Service;
 use hyper::{Method, Request, Response, Uri};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tracing::Instrument;
 
-use crate::{get, mk_response, post, GenericError};
+#[cfg(feature = "history")]
+use todc_utils::linearizability::history::ProcessId;
+#[cfg(feature = "history")]
+use todc_utils::specifications::register::RegisterOperation;
+
+#[cfg(feature = "history")]
+use super::history::HistorySink;
+use super::label::Label;
+use crate::codec::{Codec, CodecError, EncodeBuffer, Json, Negotiated};
+use crate::limit::{Limiter, Priority, Throttle};
+use crate::quorum::{InvalidThresholds, QuorumError, QuorumPolicy, Resilience, TooFewInstances};
+use crate::shutdown::{InFlight, Shutdown};
+use crate::storage::Storage;
+use crate::time::{self, Clock};
+use crate::transport::http::{collect_streamed, LABEL_HEADER};
+use crate::transport::{self, Handler, HttpTransport, Transport, UnsupportedVersion};
+use crate::{mk_response, GenericError, NULL_BODY};
+
+mod discovery;
+mod epochs;
+mod gossip;
+mod hedging;
+mod leases;
+mod limits;
+mod reconfiguration;
+mod status;
+mod storage;
+mod streaming;
+mod versioning;
+
+pub use self::leases::LeaseConfig;
+pub use self::status::{NeighborStatus, ReadStats, Status};
+
+use self::leases::{LeaseRequest, Leases};
+use self::limits::Announcements;
+use self::reconfiguration::{neighbors_from_json, neighbors_to_json};
+use self::status::{status_to_json, Contact};
+use self::versioning::protocol_version;
 
 /// The local value of a register.
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, PartialOrd, Ord, Serialize)]
-struct LocalValue<T: Clone + Debug + Default + Ord + Send> {
-    label: u32,
-    value: T,
+pub(crate) struct LocalValue<T: Clone + Debug + Default + Ord + Send, L = u64> {
+    pub(crate) label: L,
+    pub(crate) value: T,
+}
+
+/// A value that was read from a register instance without the confirmation
+/// of a majority of its neighbors.
+///
+/// See [`AtomicRegister::read_or_stale`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stale<T, L = u64> {
+    /// The local value of the register instance.
+    pub value: T,
+    /// The label associated with the local value.
+    pub label: L,
+    /// The last time at which an operation of this instance reached a
+    /// quorum. The local value may have changed since then.
+    pub last_confirmed: Instant,
+}
+
+/// The votes with which the neighbors in one configuration have answered a
+/// message, out of those needed for a quorum.
+struct Tally {
+    /// The neighbors in the configuration.
+    members: HashSet<Uri>,
+    /// The votes needed for a quorum, including that of the sender.
+    needed: usize,
+    /// The votes that may fail before a quorum can no longer be reached.
+    tolerated: usize,
+    /// The votes of the sender, and of the members that have replied.
+    acks: usize,
+    /// The votes of the members that have failed to reply.
+    failures: usize,
+}
+
+impl Tally {
+    /// Records the outcome of a message to a neighbor, if it is a member.
+    fn record(&mut self, neighbor: &Uri, votes: usize, succeeded: bool) {
+        if !self.members.contains(neighbor) {
+            return;
+        }
+        if succeeded {
+            self.acks += votes;
+        } else {
+            self.failures += votes;
+        }
+    }
+
+    /// Returns whether the members that have replied form a quorum.
+    fn is_reached(&self) -> bool {
+        self.acks >= self.needed
+    }
+
+    /// Returns whether too many members have failed to ever form a quorum.
+    fn is_lost(&self) -> bool {
+        self.failures > self.tolerated
+    }
+}
+
+/// The default number of request IDs that a register instance remembers.
+const DEFAULT_REQUEST_CAPACITY: usize = 1024;
+
+/// The values written by the most recent requests made to a register
+/// instance with a request ID, by ID.
+struct Requests<T: Clone + Debug + Default + Ord + Send, L> {
+    capacity: usize,
+    values: HashMap<String, LocalValue<T, L>>,
+    /// The IDs in the order in which they were first seen, so that the
+    /// oldest can be forgotten once there are more than `capacity`.
+    order: VecDeque<String>,
+}
+
+impl<T: Clone + Debug + Default + Ord + Send, L: Clone> Requests<T, L> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            values: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Returns the value written by the request with the given ID, or
+    /// remembers that it writes the value returned by `new` if the ID has
+    /// not been seen before.
+    fn get_or_insert(
+        &mut self,
+        id: &str,
+        new: impl FnOnce() -> Result<LocalValue<T, L>, RegisterError>,
+    ) -> Result<(LocalValue<T, L>, bool), RegisterError> {
+        if let Some(local) = self.values.get(id) {
+            return Ok((local.clone(), true));
+        }
+        let local = new()?;
+        if self.capacity > 0 {
+            if self.order.len() == self.capacity {
+                let oldest = self.order.pop_front().unwrap();
+                self.values.remove(&oldest);
+            }
+            self.order.push_back(id.to_string());
+            self.values.insert(id.to_string(), local.clone());
+        }
+        Ok((local, false))
+    }
+}
+
+/// The result of a read that is allowed to return a stale value.
+///
+/// See [`AtomicRegister::read_or_stale`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReadOutcome<T, L = u64> {
+    /// A value that was read with the usual atomicity guarantees.
+    Fresh(T),
+    /// A value that was read without contacting a majority of instances, and
+    /// which may not reflect the most-recent write.
+    Stale(Stale<T, L>),
+}
+
+/// The guarantee provided by a read from an [`AtomicRegister`].
+///
+/// See [Read Consistency](self#read-consistency) and
+/// [`read_with_consistency`](AtomicRegister::read_with_consistency).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadConsistency {
+    /// A read that takes effect at a single moment between its call and its
+    /// response, as in [`read`](AtomicRegister::read).
+    #[default]
+    Linearizable,
+    /// A read that returns the largest value of a majority of instances,
+    /// without announcing it.
+    Sequential,
+    /// A read that returns the local value of an instance, without
+    /// contacting its neighbors.
+    Local,
+}
+
+/// An error returned by an operation on an [`AtomicRegister`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RegisterError {
+    /// Fewer than a quorum of instances acknowledged a message, so the
+    /// operation could not complete. See
+    /// [Tolerating Failures](self#tolerating-failures).
+    QuorumUnavailable {
+        /// The number of instances that acknowledged the message, including
+        /// this one, or the number of votes they hold if instances have
+        /// weighted votes.
+        acks: usize,
+        /// The number of acknowledgements required for a quorum.
+        needed: usize,
+        /// The number of instances that may crash, or the number of votes
+        /// they may hold if instances have weighted votes.
+        failures: usize,
+    },
+    /// A value could not be serialized or deserialized.
+    Serialization(CodecError),
+    /// Communication with other instances failed in a way that was not
+    /// caused by an unreachable instance.
+    Transport(GenericError),
+    /// The operation did not complete in time.
+    Timeout,
+    /// The operation is not served by instances with the given [`Role`].
+    Forbidden(Role),
+    /// The local value of the instance could not be loaded from, or stored
+    /// in, its [`Storage`].
+    Storage(GenericError),
+    /// The label of the local value cannot be advanced any further. See the
+    /// [`label`](crate::register::label) module for details.
+    LabelOverflow,
+    /// The neighbor at the given URL could not be reached.
+    Unreachable(Uri),
+    /// A write was made with a request ID that was previously used to write
+    /// a different value.
+    RequestIdReused(String),
+    /// A message was sent from an earlier epoch than that of the instance
+    /// that recieved it. See [Epochs](self#epochs).
+    StaleEpoch {
+        /// The epoch of the sender of the message.
+        epoch: u64,
+        /// The epoch of the reciever.
+        current: u64,
+    },
+    /// The neighbors of the instance could not be found by its
+    /// [`Discovery`](crate::discovery::Discovery).
+    Discovery(GenericError),
+    /// The instance is shutting down, and so does not begin new operations.
+    /// See [Graceful Shutdown](self#graceful-shutdown).
+    ShuttingDown,
+    /// There are too few instances to tolerate the failure threshold of the
+    /// instance. See [Tolerating Failures](self#tolerating-failures).
+    TooFewInstances(TooFewInstances),
+    /// The read and write thresholds of the instance do not intersect, or
+    /// cannot be reached. See [Weighted Voting](self#weighted-voting).
+    InvalidThresholds(InvalidThresholds),
+    /// A message was written in a version of the protocol that the instance
+    /// does not understand. See [Protocol Versions](self#protocol-versions).
+    UnsupportedProtocol {
+        /// The version in which the message was written.
+        version: u32,
+        /// The versions that the instance understands.
+        supported: RangeInclusive<u32>,
+    },
+}
+
+impl RegisterError {
+    /// Returns the status code with which the [`Service`] implementation of
+    /// [`AtomicRegister`] answers requests that fail with this error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::http::StatusCode;
+    /// use todc_net::register::abd_95::RegisterError;
+    ///
+    /// let error = RegisterError::QuorumUnavailable {
+    ///     acks: 1,
+    ///     needed: 2,
+    ///     failures: 1,
+    /// };
+    /// assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    /// ```
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            Self::QuorumUnavailable { .. } | Self::Unreachable(_) | Self::ShuttingDown => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Self::Transport(_) | Self::Discovery(_) => StatusCode::BAD_GATEWAY,
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::RequestIdReused(_) | Self::StaleEpoch { .. } => StatusCode::CONFLICT,
+            Self::TooFewInstances(_) | Self::InvalidThresholds(_) => StatusCode::BAD_REQUEST,
+            Self::UnsupportedProtocol { .. } => StatusCode::UPGRADE_REQUIRED,
+            Self::Serialization(_) | Self::Storage(_) | Self::LabelOverflow => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+impl Display for RegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::QuorumUnavailable {
+                acks,
+                needed,
+                failures,
+            } => write!(
+                f,
+                "More than {failures} of the instances are offline ({acks} of {needed} required acknowledgements)"
+            ),
+            Self::Serialization(error) => write!(f, "Serialization failed: {error}"),
+            Self::Transport(error) => write!(f, "Transport failed: {error}"),
+            Self::Timeout => write!(f, "The operation timed out"),
+            Self::Forbidden(role) => {
+                write!(
+                    f,
+                    "The operation is not served by instances with role {role:?}"
+                )
+            }
+            Self::Storage(error) => write!(f, "Storage failed: {error}"),
+            Self::LabelOverflow => {
+                write!(
+                    f,
+                    "The label of the register cannot be advanced any further"
+                )
+            }
+            Self::Unreachable(neighbor) => write!(f, "Neighbor {neighbor} could not be reached"),
+            Self::RequestIdReused(id) => {
+                write!(
+                    f,
+                    "Request ID {id} was already used to write a different value"
+                )
+            }
+            Self::StaleEpoch { epoch, current } => {
+                write!(
+                    f,
+                    "The message from epoch {epoch} is older than the current epoch {current}"
+                )
+            }
+            Self::Discovery(error) => write!(f, "Discovery failed: {error}"),
+            Self::ShuttingDown => write!(f, "The instance is shutting down"),
+            Self::TooFewInstances(error) => write!(f, "{error}"),
+            Self::InvalidThresholds(error) => write!(f, "{error}"),
+            Self::UnsupportedProtocol { version, supported } => {
+                write!(
+                    f,
+                    "Protocol version {version} is not supported, only versions {} to {} are",
+                    supported.start(),
+                    supported.end()
+                )
+            }
+        }
+    }
+}
+
+impl Error for RegisterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Serialization(error) => Some(error),
+            Self::TooFewInstances(error) => Some(error),
+            Self::InvalidThresholds(error) => Some(error),
+            Self::Transport(error) | Self::Storage(error) | Self::Discovery(error) => {
+                Some(error.as_ref())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for RegisterError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Serialization(error.into())
+    }
+}
+
+impl From<CodecError> for RegisterError {
+    fn from(error: CodecError) -> Self {
+        Self::Serialization(error)
+    }
+}
+
+impl From<UnsupportedVersion> for RegisterError {
+    fn from(error: UnsupportedVersion) -> Self {
+        Self::UnsupportedProtocol {
+            version: error.version,
+            supported: error.supported,
+        }
+    }
+}
+
+impl From<TooFewInstances> for RegisterError {
+    fn from(error: TooFewInstances) -> Self {
+        Self::TooFewInstances(error)
+    }
+}
+
+impl From<QuorumError> for RegisterError {
+    fn from(error: QuorumError) -> Self {
+        match error {
+            QuorumError::TooFewInstances(error) => Self::TooFewInstances(error),
+            QuorumError::InvalidThresholds(error) => Self::InvalidThresholds(error),
+        }
+    }
+}
+
+/// The operations that a register instance serves to clients.
+///
+/// Whatever its role, an instance stores the contents of the register and
+/// acknowledges the values announced by its neighbors, and so counts towards
+/// a majority in exactly the same way as any other instance. Roles only
+/// determine which client operations an instance accepts. A cluster can
+/// therefore add instances that improve fault-tolerance, or serve reads,
+/// without also accepting writes.
+///
+/// See [`with_role`](AtomicRegister::with_role).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Role {
+    /// An instance that serves both reads and writes.
+    #[default]
+    Full,
+    /// An instance that serves neither reads nor writes, and only
+    /// participates in the operations of its neighbors.
+    Witness,
+    /// An instance that serves reads, but not writes.
+    ReadOnly,
+}
+
+impl Role {
+    /// Returns whether instances with this role serve reads.
+    pub fn serves_reads(&self) -> bool {
+        matches!(self, Role::Full | Role::ReadOnly)
+    }
+
+    /// Returns whether instances with this role serve writes.
+    pub fn serves_writes(&self) -> bool {
+        matches!(self, Role::Full)
+    }
 }
 
 /// An [atomic](https://en.wikipedia.org/wiki/Atomic_semantics)
@@ -144,29 +919,171 @@ struct LocalValue<T: Clone + Debug + Default + Ord + Send> {
 ///    
 /// See the [`abd_95`](crate::register::abd_95) module-level documentation for
 /// more details.
+///
+/// The register is a _single-writer_ register: writes label their value with
+/// the next label after the local value of the instance they are performed
+/// on, without first asking its neighbors for a larger one. Writes must
+/// therefore all be performed on one instance, as concurrent writes on
+/// different instances may be given the same label, and reads may then
+/// disagree about which of them took effect.
+///
+/// By default, instances communicate over HTTP/1 using an [`HttpTransport`].
+/// See [`with_transport`](AtomicRegister::with_transport) for using a
+/// different [`Transport`].
+///
+/// Values are ordered by [`u64`] labels by default. A different [`Label`]
+/// can be chosen with the third type parameter, which must be the same for
+/// every instance of the register.
+///
+/// Values are exchanged as JSON by default. A different [`Codec`] can be
+/// chosen with the fourth type parameter, which must also be the same for
+/// every instance of the register.
 #[derive(Clone)]
-pub struct AtomicRegister<T: Clone + Debug + Default + DeserializeOwned + Ord + Send> {
-    neighbors: Vec<Uri>,
-    local: Arc<Mutex<LocalValue<T>>>,
+pub struct AtomicRegister<
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send,
+    Tr: Transport = HttpTransport,
+    L: Label = u64,
+    C: Codec = Json,
+> {
+    transport: Tr,
+    neighbors: Arc<Mutex<Vec<Uri>>>,
+    joining: Arc<Mutex<Option<Vec<Uri>>>>,
+    reconfiguring: Arc<tokio::sync::Mutex<()>>,
+    quorum: QuorumPolicy,
+    local: Arc<Mutex<LocalValue<T, L>>>,
+    last_confirmed: Arc<Mutex<Option<Instant>>>,
+    contacts: Arc<Mutex<HashMap<Uri, Contact<L>>>>,
+    reads: Arc<Mutex<ReadStats>>,
+    lease: Option<LeaseConfig>,
+    leases: Arc<Mutex<Leases<T, L>>>,
+    role: Role,
+    streaming: bool,
+    storage: Option<Arc<dyn Storage>>,
+    persisting: Arc<tokio::sync::Mutex<()>>,
+    clock: Arc<dyn Clock>,
+    timeout: Option<Duration>,
+    hedge: Option<Duration>,
+    shutdown: Option<Shutdown>,
+    limits: Arc<HashMap<Route, Limiter>>,
+    throttle: Option<Throttle>,
+    priorities: Arc<HashMap<Route, Priority>>,
+    announcements: Arc<Mutex<Announcements<T, L>>>,
+    requests: Arc<Mutex<Requests<T, L>>>,
+    buffer: Arc<EncodeBuffer>,
+    epoch: Arc<Mutex<u64>>,
+    protocol: u32,
+    codec: PhantomData<C>,
+    #[cfg(feature = "history")]
+    history: Option<HistorySink<T>>,
 }
 
-impl<T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static> Default
-    for AtomicRegister<T>
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport + Default,
+        L: Label,
+        C: Codec,
+    > Default for AtomicRegister<T, Tr, L, C>
 {
     /// Creates an [`AtomicRegister`] with no neighbors.
     fn default() -> Self {
-        Self::new(Vec::new())
+        Self::with_transport(Vec::new(), Tr::default())
+    }
+}
+
+/// The route at which clients can read from and write to the register.
+const REGISTER_PATH: &str = "/register";
+
+/// The header that carries the ID of a request to `/register`, which is
+/// returned in the same header of the response.
+///
+/// See [Idempotent Writes](self#idempotent-writes).
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// The versions of the protocol in which instances exchange messages, that
+/// this version of the crate understands.
+///
+/// See [Protocol Versions](self#protocol-versions).
+pub const PROTOCOL_VERSIONS: RangeInclusive<u32> = 1..=1;
+
+/// The route at which the local value of an instance can be reached.
+pub(crate) const LOCAL_PATH: &str = "/register/local";
+
+/// The route at which the neighbors of an instance can be reached.
+const NEIGHBORS_PATH: &str = "/register/neighbors";
+
+/// The route at which instances exchange their local values in the
+/// background.
+const GOSSIP_PATH: &str = "/register/gossip";
+
+/// The route at which the status of an instance can be inspected.
+const STATUS_PATH: &str = "/register/status";
+
+/// The route at which an instance can be asked for a lease.
+const LEASE_PATH: &str = "/register/lease";
+
+/// The route at which a leaseholder recieves the values announced to the
+/// instances that granted its lease.
+const LEASE_UPDATES_PATH: &str = "/register/lease/updates";
+
+/// Returns an ID for a new operation, which identifies its span in logs.
+///
+/// IDs are unique among all registers in the same process.
+fn next_operation_id() -> u64 {
+    static NEXT_OPERATION_ID: AtomicU64 = AtomicU64::new(0);
+    NEXT_OPERATION_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A route served by an [`AtomicRegister`].
+///
+/// See [`with_concurrency_limit`](AtomicRegister::with_concurrency_limit)
+/// and [`with_priority`](AtomicRegister::with_priority).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Route {
+    /// The `/register` route, at which clients read and write.
+    Register,
+    /// The `/register/local` route, at which neighbors ask for and announce
+    /// values.
+    Local,
+    /// The `/register/neighbors` route, at which the neighbors of an
+    /// instance are inspected and reconfigured.
+    Neighbors,
+    /// The `/register/lease` and `/register/lease/updates` routes, at which
+    /// instances grant leases and forward values to leaseholders.
+    Lease,
+    /// The `/register/gossip` route, at which instances exchange their local
+    /// values in the background.
+    Gossip,
+    /// The `/register/status` route, at which the status of an instance is
+    /// inspected.
+    Status,
+}
+
+impl Route {
+    /// Returns the route with the given path, if there is one.
+    fn from_path(path: &str) -> Option<Self> {
+        match path {
+            REGISTER_PATH => Some(Self::Register),
+            LOCAL_PATH => Some(Self::Local),
+            NEIGHBORS_PATH => Some(Self::Neighbors),
+            LEASE_PATH | LEASE_UPDATES_PATH => Some(Self::Lease),
+            GOSSIP_PATH => Some(Self::Gossip),
+            STATUS_PATH => Some(Self::Status),
+            _ => None,
+        }
     }
 }
 
 /// A message from one register instance to another.
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 enum Message {
     /// A message _announcing_ the senders value and label, with the intention of
     /// having recievers adopt the value if its label is larger than than theirs.
     Announce,
     /// A message _asking_ for the recievers value and label.
     Ask,
+    /// A message asking the reciever to grant a lease, and for its value and
+    /// label.
+    Lease(LeaseRequest),
 }
 
 impl<T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static>
@@ -198,150 +1115,1312 @@ impl<T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 's
     /// let register: AtomicRegister<Contents> = AtomicRegister::new(neighbor_urls);
     /// ```
     pub fn new(neighbors: Vec<Uri>) -> Self {
-        Self {
-            neighbors,
-            local: Arc::new(Mutex::new(LocalValue::default())),
-        }
+        Self::with_transport(neighbors, HttpTransport::default())
     }
+}
 
-    /// Sends and recieves a message from neighbors.
-    async fn communicate(&self, message: Message) -> Result<Vec<LocalValue<T>>, GenericError> {
-        let local = self.local.lock().unwrap().clone();
-
-        // Communicate the message with all neighbors.
-        let mut handles = JoinSet::new();
-        for url in self.neighbor_urls().into_iter() {
-            let local = local.clone();
-            handles.spawn(async move {
-                let result = match message {
-                    Message::Announce => {
-                        let body = serde_json::to_value(local)?;
-                        post(url, body).await
-                    }
-                    Message::Ask => get(url).await,
-                };
-
-                match result {
-                    Err(error) => Err(error),
-                    Ok(response) => {
-                        if response.status().is_server_error() {
-                            return Err(GenericError::from("Unexpected server error"));
-                        }
-
-                        let body = response.collect().await?.aggregate();
-                        let value: LocalValue<T> = serde_json::from_reader(body.reader())?;
-                        Ok(value)
-                    }
-                }
-            });
-        }
-
-        // Wait until a majority of neighbors have replied succesfully, and
-        // return their values.
-        let mut info: Vec<LocalValue<T>> = vec![local.clone()];
-
-        let mut acks: f32 = 1.0;
-        let mut failures: f32 = 0.0;
-        let minority = (self.neighbors.len() as f32 + 1_f32) / 2_f32;
-        while acks <= minority && failures <= minority {
-            if let Some(result) = handles.join_next().await {
-                match result? {
-                    Err(_) => failures += 1.0,
-                    Ok(value) => {
-                        info.push(value);
-                        acks += 1.0;
-                    }
-                }
-            }
-        }
-
-        if acks > minority {
-            Ok(info)
-        } else {
-            Err(GenericError::from("A majority of neighbors are offline"))
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Creates a new atomic register instance with a given set of neighbors,
+    /// that communicates with them using the given [`Transport`].
+    ///
+    /// All instances of the register must use the same kind of transport.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::register::AtomicRegister;
+    /// use todc_net::transport::HttpTransport;
+    ///
+    /// let neighbor = Uri::from_static("https://my-register-2.com");
+    /// let register: AtomicRegister<u32, HttpTransport> =
+    ///     AtomicRegister::with_transport(vec![neighbor], HttpTransport::default());
+    /// ```
+    pub fn with_transport(neighbors: Vec<Uri>, transport: Tr) -> Self {
+        let clock = time::default_clock();
+        Self {
+            transport,
+            neighbors: Arc::new(Mutex::new(neighbors)),
+            joining: Arc::new(Mutex::new(None)),
+            reconfiguring: Arc::new(tokio::sync::Mutex::new(())),
+            quorum: QuorumPolicy::default(),
+            local: Arc::new(Mutex::new(LocalValue::default())),
+            last_confirmed: Arc::new(Mutex::new(None)),
+            contacts: Arc::new(Mutex::new(HashMap::new())),
+            reads: Arc::new(Mutex::new(ReadStats::default())),
+            lease: None,
+            leases: Arc::new(Mutex::new(Leases::default())),
+            role: Role::default(),
+            streaming: false,
+            storage: None,
+            persisting: Arc::new(tokio::sync::Mutex::new(())),
+            clock,
+            timeout: None,
+            hedge: None,
+            shutdown: None,
+            limits: Arc::new(HashMap::new()),
+            throttle: None,
+            priorities: Arc::new(HashMap::new()),
+            announcements: Arc::new(Mutex::new(HashMap::new())),
+            requests: Arc::new(Mutex::new(Requests::new(DEFAULT_REQUEST_CAPACITY))),
+            buffer: Arc::new(EncodeBuffer::default()),
+            epoch: Arc::new(Mutex::new(0)),
+            protocol: *PROTOCOL_VERSIONS.end(),
+            codec: PhantomData,
+            #[cfg(feature = "history")]
+            history: None,
         }
     }
 
-    /// Returns a set of URLs that neighboring instances can be reached at.
-    fn neighbor_urls(&self) -> Vec<Uri> {
-        let neighbors = self.neighbors.clone();
-        neighbors
-            .into_iter()
-            .map(|addr| {
-                let mut parts = addr.into_parts();
-                parts.path_and_query = Some("/register/local".parse().unwrap());
-                Uri::from_parts(parts).unwrap()
-            })
-            .collect()
-    }
-
-    /// Returns the value contained in the register.
+    /// Sets the [`Role`] of this instance, which determines the client
+    /// operations that it serves.
+    ///
+    /// By default, instances are [`Role::Full`]. Operations that an instance
+    /// does not serve return an error, and the corresponding requests made
+    /// to `/register` are answered with `403 Forbidden`.
     ///
     /// # Examples
     ///
     /// ```
     /// # use tokio_test;
-    /// use todc_net::register::AtomicRegister;
+    /// use todc_net::register::abd_95::{AtomicRegister, Role};
     ///
-    /// type Contents = u32;
     /// # tokio_test::block_on(async {
-    /// let register: AtomicRegister<Contents> = AtomicRegister::default();
+    /// let register: AtomicRegister<u32> = AtomicRegister::default().with_role(Role::ReadOnly);
     /// assert_eq!(register.read().await.unwrap(), 0);
+    /// assert!(register.write(123).await.is_err());
     /// # })
     /// ```
-    pub async fn read(&self) -> Result<T, GenericError> {
-        let info = self.communicate(Message::Ask).await?;
-        let max = info.into_iter().max().unwrap();
-        let local = self.update(&max);
-        self.communicate(Message::Announce).await?;
-        Ok(local.value)
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
     }
 
-    /// Updates the local value of this register instance.
-    fn update(&self, other: &LocalValue<T>) -> LocalValue<T> {
-        let mut local = self.local.lock().unwrap();
-        if *other > *local {
-            *local = other.clone()
-        };
-        local.clone()
+    /// Returns the [`Role`] of this instance.
+    pub fn role(&self) -> Role {
+        self.role
     }
 
-    /// Sets the contents of the register to the specified value.
+    /// Sets the number of instances, `f`, that may crash without violating
+    /// the guarantees of the register. See
+    /// [Tolerating Failures](self#tolerating-failures).
+    ///
+    /// By default, any minority of instances may crash. Operations wait for
+    /// replies from all but `f` instances, and fail with
+    /// [`RegisterError::QuorumUnavailable`] once more than `f` instances
+    /// have failed to reply. Every instance of the register must use the
+    /// same failure threshold.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegisterError::TooFewInstances`] unless there are more than
+    /// `2f` instances, including this one. The same check is made whenever
+    /// the instance is [`reconfigure`](AtomicRegister::reconfigure)d.
     ///
     /// # Examples
     ///
     /// ```
-    /// # use tokio_test;
+    /// use hyper::Uri;
     /// use todc_net::register::AtomicRegister;
     ///
-    /// type Contents = u32;
+    /// let neighbors = vec![
+    ///     Uri::from_static("https://my-register-2.com"),
+    ///     Uri::from_static("https://my-register-3.com"),
+    /// ];
+    /// let register = AtomicRegister::<u32>::new(neighbors.clone()).with_failure_threshold(1);
+    /// assert!(register.is_ok());
     ///
-    /// # tokio_test::block_on(async {
-    /// let register: AtomicRegister<Contents> = AtomicRegister::default();
-    /// register.write(123).await;
-    /// assert_eq!(register.read().await.unwrap(), 123);
-    /// # })
+    /// // Three instances cannot tolerate two crashes.
+    /// let register = AtomicRegister::<u32>::new(neighbors).with_failure_threshold(2);
+    /// assert!(register.is_err());
     /// ```
-    pub async fn write(&self, value: T) -> Result<(), GenericError> {
-        let new = LocalValue {
-            value,
-            label: self.local.lock().unwrap().label + 1,
-        };
-        self.update(&new);
-        self.communicate(Message::Announce).await?;
-        Ok(())
+    pub fn with_failure_threshold(self, failures: usize) -> Result<Self, RegisterError> {
+        self.with_quorum_policy(QuorumPolicy::FailureThreshold(failures))
     }
-}
 
-impl<T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static>
-    Service<Request<Incoming>> for AtomicRegister<T>
-{
-    type Response = Response<Full<Bytes>>;
-    type Error = Box<dyn std::error::Error + Send + Sync>;
-    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
-
-    fn call(&self, req: Request<Incoming>) -> Self::Future {
-        // The Future we return can be send to other tasks or threads and
+    /// Sets the [`QuorumPolicy`] by which replies are counted towards a
+    /// quorum. See [Weighted Voting](self#weighted-voting).
+    ///
+    /// By default, every instance holds a single vote and any minority of
+    /// instances may crash. Every instance of the register must use
+    /// thresholds that intersect those of every other, which is easiest to
+    /// guarantee by giving every instance the same weights and thresholds.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegisterError::TooFewInstances`] or
+    /// [`RegisterError::InvalidThresholds`] if the policy cannot be
+    /// satisfied by this instance and its current neighbors. The same check
+    /// is made whenever the instance is
+    /// [`reconfigure`](AtomicRegister::reconfigure)d.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::quorum::{QuorumPolicy, WeightedVoting};
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// let neighbors = vec![
+    ///     Uri::from_static("https://my-register-2.com"),
+    ///     Uri::from_static("https://my-register-3.com"),
+    /// ];
+    ///
+    /// // Reads wait for any one of the four votes, and writes for all of them.
+    /// let voting = WeightedVoting::new(2).with_thresholds(1, 4);
+    /// let register = AtomicRegister::<u32>::new(neighbors.clone())
+    ///     .with_quorum_policy(QuorumPolicy::Weighted(voting));
+    /// assert!(register.is_ok());
+    ///
+    /// // Reads and writes of two votes each need not intersect.
+    /// let voting = WeightedVoting::new(2).with_thresholds(2, 2);
+    /// let register = AtomicRegister::<u32>::new(neighbors)
+    ///     .with_quorum_policy(QuorumPolicy::Weighted(voting));
+    /// assert!(register.is_err());
+    /// ```
+    pub fn with_quorum_policy(mut self, policy: QuorumPolicy) -> Result<Self, RegisterError> {
+        policy.thresholds(&self.neighbors())?;
+        self.quorum = policy;
+        Ok(self)
+    }
+
+    /// Returns the [`QuorumPolicy`] of this instance.
+    pub fn quorum_policy(&self) -> &QuorumPolicy {
+        &self.quorum
+    }
+
+    /// Returns the [`Resilience`] of this instance and its current
+    /// neighbors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// let neighbor = Uri::from_static("https://my-register-2.com");
+    /// let register: AtomicRegister<u32> = AtomicRegister::new(vec![neighbor]);
+    /// assert_eq!(register.resilience().instances(), 2);
+    /// assert_eq!(register.resilience().failures(), 0);
+    /// ```
+    pub fn resilience(&self) -> Resilience {
+        self.quorum
+            .resilience(&self.neighbors())
+            .expect("neighbors are only installed if they satisfy the quorum policy")
+    }
+
+    /// Sets the number of request IDs that this instance remembers, so that
+    /// writes retried with the same ID are not applied twice.
+    ///
+    /// By default, instances remember the `1024` most recent IDs. Once more
+    /// IDs have been seen, the oldest are forgotten, and a write retried
+    /// with a forgotten ID is applied again. If `capacity` is zero, then no
+    /// IDs are remembered. See [Idempotent Writes](self#idempotent-writes).
+    pub fn with_request_capacity(mut self, capacity: usize) -> Self {
+        self.requests = Arc::new(Mutex::new(Requests::new(capacity)));
+        self
+    }
+
+    /// Returns an error if this instance does not serve reads.
+    fn check_serves_reads(&self) -> Result<(), RegisterError> {
+        if self.role.serves_reads() {
+            Ok(())
+        } else {
+            Err(RegisterError::Forbidden(self.role))
+        }
+    }
+
+    /// Returns an error if this instance does not serve writes.
+    fn check_serves_writes(&self) -> Result<(), RegisterError> {
+        if self.role.serves_writes() {
+            Ok(())
+        } else {
+            Err(RegisterError::Forbidden(self.role))
+        }
+    }
+
+    /// Reads the current time, and sets timers, using the given [`Clock`].
+    ///
+    /// By default, instances use a [`TokioClock`](crate::time::TokioClock).
+    /// The clock determines when leases expire, how often instances gossip,
+    /// and how recently a [`Stale`] value was confirmed, and so a
+    /// [`MockClock`](crate::time::MockClock) can be used to test these
+    /// deterministically. See the [`time`](crate::time) module for details.
+    ///
+    /// Clones of this instance that were created before calling this method
+    /// keep their previous clock.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use std::time::Duration;
+    /// use hyper::Uri;
+    /// use todc_net::register::abd_95::{AtomicRegister, LeaseConfig};
+    /// use todc_net::time::MockClock;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let clock = MockClock::new();
+    /// let address = Uri::from_static("https://my-register-1.com");
+    /// let register: AtomicRegister<u32> = AtomicRegister::default()
+    ///     .with_clock(clock.clone())
+    ///     .with_lease(LeaseConfig::new(address, Duration::from_secs(10)));
+    ///
+    /// register.read().await.unwrap();
+    /// assert!(register.lease_expiry().is_some());
+    ///
+    /// // The lease only expires once the clock is advanced past its expiry.
+    /// clock.advance(Duration::from_secs(10));
+    /// assert!(register.lease_expiry().is_none());
+    /// # })
+    /// ```
+    pub fn with_clock(mut self, clock: impl Clock) -> Self {
+        self.last_confirmed = Arc::new(Mutex::new(None));
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Sets how long operations on this instance may take before they fail
+    /// with [`RegisterError::Timeout`].
+    ///
+    /// By default, an operation waits for as long as it takes a majority of
+    /// instances to reply, which is forever if they never do. The timeout
+    /// applies to every operation, including those made through requests to
+    /// `/register`, which are answered with `504 Gateway Timeout`. It can be
+    /// overridden for individual operations with
+    /// [`read_with_timeout`](AtomicRegister::read_with_timeout) and
+    /// [`write_with_timeout`](AtomicRegister::write_with_timeout).
+    ///
+    /// When an operation times out, its outstanding requests to neighbors
+    /// are cancelled. A write that timed out may or may not have taken
+    /// effect, and can be retried with
+    /// [`write_with_request_id`](AtomicRegister::write_with_request_id).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use std::time::Duration;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<u32> =
+    ///     AtomicRegister::default().with_timeout(Duration::from_secs(5));
+    /// register.write(123).await.unwrap();
+    /// assert_eq!(register.read().await.unwrap(), 123);
+    /// # })
+    /// ```
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the time by which an operation that begins now, and may take
+    /// at most the given timeout, must complete.
+    fn deadline(&self, timeout: Option<Duration>) -> Option<Instant> {
+        timeout.map(|timeout| self.clock.now() + timeout)
+    }
+
+    /// Awaits an operation until the given deadline, and returns
+    /// [`RegisterError::Timeout`] if it did not complete in time.
+    ///
+    /// An operation that times out is dropped, which aborts the requests
+    /// that it was waiting on.
+    async fn within<R>(
+        &self,
+        deadline: Option<Instant>,
+        operation: impl Future<Output = Result<R, RegisterError>>,
+    ) -> Result<R, RegisterError> {
+        match deadline {
+            Some(deadline) => self
+                .clock
+                .timeout_at(deadline, operation)
+                .await
+                .unwrap_or_else(|| {
+                    tracing::warn!("operation timed out");
+                    Err(RegisterError::Timeout)
+                }),
+            None => operation.await,
+        }
+    }
+
+    /// Shuts this instance down gracefully through the given handle.
+    ///
+    /// Once the handle begins draining, new operations fail with
+    /// [`RegisterError::ShuttingDown`], and the corresponding requests made
+    /// to `/register` are answered with `503 Service Unavailable`. Operations
+    /// that are already in flight continue until the handle is stopped, at
+    /// which point any quorum round that is still waiting on neighbors is
+    /// cancelled. Requests from neighbors are served throughout, so that
+    /// their operations are not disrupted. See
+    /// [Graceful Shutdown](self#graceful-shutdown).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use std::time::Duration;
+    /// use todc_net::register::abd_95::{AtomicRegister, RegisterError};
+    /// use todc_net::shutdown::Shutdown;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let shutdown = Shutdown::new();
+    /// let register: AtomicRegister<u32> =
+    ///     AtomicRegister::default().with_shutdown(shutdown.clone());
+    /// shutdown.drain();
+    /// assert!(matches!(register.read().await, Err(RegisterError::ShuttingDown)));
+    /// # })
+    /// ```
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Registers a new operation as being in flight, until the returned
+    /// guard is dropped, or returns [`RegisterError::ShuttingDown`] if this
+    /// instance is draining.
+    fn admit(&self) -> Result<Option<InFlight>, RegisterError> {
+        match &self.shutdown {
+            Some(shutdown) => shutdown
+                .enter()
+                .map(Some)
+                .ok_or(RegisterError::ShuttingDown),
+            None => Ok(None),
+        }
+    }
+
+    /// Waits until this instance has stopped, which never happens if it is
+    /// not shut down through a handle.
+    async fn stopped(&self) {
+        match &self.shutdown {
+            Some(shutdown) => shutdown.stopped().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Records the call and response of every operation performed by this
+    /// instance, and its clones, in the given sink.
+    ///
+    /// Clones of this instance that were created before calling this method
+    /// do not record their operations. See the
+    /// [`history`](crate::register::history) module-level documentation for
+    /// more details.
+    ///
+    /// This method requires the `history` feature.
+    #[cfg(feature = "history")]
+    pub fn with_history(mut self, sink: HistorySink<T>) -> Self {
+        self.history = Some(sink);
+        self
+    }
+
+    /// Records the call of an operation, if this instance records its
+    /// operations, and returns the process that performed it.
+    #[cfg(feature = "history")]
+    fn record_call(&self, operation: RegisterOperation<T>) -> Option<ProcessId> {
+        self.history.as_ref().map(|sink| sink.call(operation))
+    }
+
+    /// Records the response of an operation, if this instance records its
+    /// operations.
+    #[cfg(feature = "history")]
+    fn record_response(&self, process: Option<ProcessId>, operation: RegisterOperation<T>) {
+        if let (Some(sink), Some(process)) = (&self.history, process) {
+            sink.respond(process, operation);
+        }
+    }
+
+    /// Sends and recieves a message from neighbors.
+    ///
+    /// While this instance is being reconfigured, the message must reach a
+    /// quorum of both its old and its new neighbors.
+    async fn communicate(&self, message: Message) -> Result<Vec<LocalValue<T, L>>, RegisterError> {
+        self.communicate_with(self.configurations(), message).await
+    }
+
+    /// Sends and recieves a message from the union of the given sets of
+    /// neighbors, until a quorum of each has replied.
+    async fn communicate_with(
+        &self,
+        configurations: Vec<Vec<Uri>>,
+        message: Message,
+    ) -> Result<Vec<LocalValue<T, L>>, RegisterError> {
+        let local = self.local.lock().unwrap().clone();
+
+        // The size of a quorum is determined by the neighbors that were
+        // contacted, even if the configuration changes in the meantime.
+        // Asking for values, or for a lease, must reach a read quorum, so
+        // that it intersects every announcement, which must reach a write
+        // quorum.
+        let mut tallies = configurations
+            .iter()
+            .map(|neighbors| {
+                let thresholds = self.quorum.thresholds(neighbors)?;
+                let needed = match message {
+                    Message::Announce => thresholds.write(),
+                    Message::Ask | Message::Lease(_) => thresholds.read(),
+                };
+                Ok(Tally {
+                    members: neighbors.iter().cloned().collect(),
+                    needed,
+                    tolerated: thresholds.total() - needed,
+                    acks: self.quorum.weight(),
+                    failures: 0,
+                })
+            })
+            .collect::<Result<Vec<_>, RegisterError>>()?;
+        let mut neighbors: Vec<Uri> = Vec::new();
+        for neighbor in configurations.into_iter().flatten() {
+            if !neighbors.contains(&neighbor) {
+                neighbors.push(neighbor);
+            }
+        }
+
+        // The message is encoded once, and its contents are shared by the
+        // requests to every neighbor.
+        let streamed = self.streaming && matches!(message, Message::Announce);
+        let outgoing = match &message {
+            Message::Announce if streamed => {
+                let body = self.buffer.encode::<C, _>(&local.value)?;
+                transport::Message::announce(LOCAL_PATH, body)
+                    .with_label(serde_json::to_string(&local.label)?)
+            }
+            Message::Announce => {
+                transport::Message::announce(LOCAL_PATH, self.buffer.encode::<C, _>(&local)?)
+            }
+            Message::Ask => transport::Message::ask(LOCAL_PATH),
+            Message::Lease(request) => {
+                transport::Message::announce(LEASE_PATH, self.buffer.encode::<C, _>(request)?)
+            }
+        }
+        .with_content_type(C::CONTENT_TYPE)
+        .with_epoch(self.epoch());
+
+        // Communicate the message with all neighbors.
+        let route = match message {
+            Message::Lease(_) => Route::Lease,
+            Message::Announce | Message::Ask => Route::Local,
+        };
+        let mut handles = JoinSet::new();
+        // Announcements only need their own copy of the local value if they
+        // might be queued behind the rate limit of this instance.
+        let throttled = matches!(message, Message::Announce) && self.throttle.is_some();
+        let me = self.clone();
+        let initial = local.clone();
+        let contact = move |handles: &mut JoinSet<_>, neighbor: Uri| {
+            let votes = me.quorum.weight_of(&neighbor);
+            let outgoing = outgoing.clone();
+            let announced = throttled.then(|| initial.clone());
+            let me = me.clone();
+            let replier = neighbor.clone();
+            let span = tracing::debug_span!("exchange", %neighbor, ?route);
+            let exchange = async move {
+                let start = me.clock.now();
+                let reply = match announced {
+                    Some(local) => {
+                        me.announce(neighbor.clone(), local, outgoing, streamed)
+                            .await
+                    }
+                    None => me.send(route, neighbor.clone(), outgoing).await,
+                };
+                me.record_contact(neighbor.clone(), reply.is_ok());
+                if let Err(error) = &reply {
+                    tracing::debug!(%error, "neighbor did not reply");
+                }
+                let reply = reply?;
+                me.record_latency(neighbor.clone(), me.clock.now() - start);
+                // Neighbors only reply to streamed values with the label of
+                // their local value, which acknowledges the announcement.
+                if streamed {
+                    let label: L = C::decode(&reply)?;
+                    me.record_label(neighbor, label);
+                    return Ok::<_, GenericError>(None);
+                }
+                let value: LocalValue<T, L> = C::decode(&reply)?;
+                me.record_label(neighbor, value.label);
+                Ok(Some(value))
+            }
+            .instrument(span);
+            handles.spawn(async move { (replier, votes, exchange.await) });
+        };
+        // Neighbors that have neither replied nor failed yet, along with the
+        // number of requests to each that are still outstanding.
+        let mut pending: HashMap<Uri, usize> = HashMap::new();
+        for neighbor in neighbors {
+            pending.insert(neighbor.clone(), 1);
+            contact(&mut handles, neighbor);
+        }
+        // The timer fires at most once, and never without hedging.
+        let mut hedged = self.hedge.is_none();
+        let mut hedge = self.clock.sleep(self.hedge.unwrap_or_default());
+
+        // Wait until instances holding enough votes have replied
+        // succesfully, or until more votes have failed than the quorum
+        // tolerates, and return their values.
+        let mut info: Vec<LocalValue<T, L>> = vec![local.clone()];
+
+        while !tallies.iter().all(Tally::is_reached) && !tallies.iter().any(Tally::is_lost) {
+            let next = tokio::select! {
+                next = handles.join_next() => Some(next),
+                () = &mut hedge, if !hedged => None,
+                // Dropping the set aborts the requests that are still
+                // outstanding.
+                () = self.stopped() => return Err(RegisterError::ShuttingDown),
+            };
+            // A quorum has not replied within the hedging delay, so the
+            // neighbors that have not replied yet are sent the message again,
+            // in case their first request was lost or stalled. Whichever copy
+            // is answered first counts.
+            let Some(next) = next else {
+                hedged = true;
+                tracing::debug!(pending = pending.len(), "hedging");
+                for (neighbor, outstanding) in pending.iter_mut() {
+                    *outstanding += 1;
+                    contact(&mut handles, neighbor.clone());
+                }
+                continue;
+            };
+            // Once every neighbor has replied, no more acknowledgements can
+            // arrive, even if too few neighbors have failed to rule out a
+            // quorum. This is the case when there are few neighbors.
+            let Some(result) = next else {
+                break;
+            };
+            let (neighbor, votes, reply) =
+                result.map_err(|error| RegisterError::Transport(error.into()))?;
+            // Each neighbor is counted once, so later replies to duplicate
+            // requests are ignored.
+            let Some(outstanding) = pending.get_mut(&neighbor) else {
+                continue;
+            };
+            match reply {
+                // A neighbor has only failed once every request to it has.
+                Err(_) => {
+                    *outstanding -= 1;
+                    if *outstanding == 0 {
+                        pending.remove(&neighbor);
+                        for tally in tallies.iter_mut() {
+                            tally.record(&neighbor, votes, false);
+                        }
+                    }
+                }
+                Ok(value) => {
+                    pending.remove(&neighbor);
+                    info.extend(value);
+                    for tally in tallies.iter_mut() {
+                        tally.record(&neighbor, votes, true);
+                    }
+                }
+            }
+        }
+        // Requests to neighbors that have not replied yet are no longer
+        // needed, and are cancelled rather than left running. Requests are
+        // also cancelled if the operation is dropped while waiting, such as
+        // when it times out, since dropping a `JoinSet` aborts its tasks.
+        handles.abort_all();
+
+        let unreached = tallies.into_iter().find(|tally| !tally.is_reached());
+        if let Some(Tally {
+            acks,
+            needed,
+            tolerated,
+            ..
+        }) = unreached
+        {
+            tracing::warn!(acks, needed, "quorum unavailable");
+            Err(RegisterError::QuorumUnavailable {
+                acks,
+                needed,
+                failures: tolerated,
+            })
+        } else {
+            *self.last_confirmed.lock().unwrap() = Some(self.clock.now());
+            if let Message::Announce = message {
+                // This instance acknowledges its own announcement, and so
+                // must forward it to any leaseholders, just as it would the
+                // announcement of another instance.
+                self.forward(local.clone()).await?;
+                let mut leases = self.leases.lock().unwrap();
+                if local > leases.confirmed {
+                    leases.confirmed = local;
+                }
+            }
+            Ok(info)
+        }
+    }
+
+    /// Returns the current neighbors of this register instance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// let neighbor = Uri::from_static("https://my-register-2.com");
+    /// let register: AtomicRegister<u32> = AtomicRegister::new(vec![neighbor.clone()]);
+    /// assert_eq!(register.neighbors(), vec![neighbor]);
+    /// ```
+    pub fn neighbors(&self) -> Vec<Uri> {
+        self.neighbors.lock().unwrap().clone()
+    }
+
+    /// Returns the value contained in the register.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// type Contents = u32;
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<Contents> = AtomicRegister::default();
+    /// assert_eq!(register.read().await.unwrap(), 0);
+    /// # })
+    /// ```
+    pub async fn read(&self) -> Result<T, RegisterError> {
+        Ok(self.read_labeled().await?.value)
+    }
+
+    /// Returns the value contained in the register, or
+    /// [`RegisterError::Timeout`] if the read does not complete within the
+    /// given timeout.
+    ///
+    /// The timeout overrides the one set with
+    /// [`with_timeout`](AtomicRegister::with_timeout).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use std::time::Duration;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<u32> = AtomicRegister::default();
+    /// let value = register.read_with_timeout(Duration::from_secs(1)).await;
+    /// assert_eq!(value.unwrap(), 0);
+    /// # })
+    /// ```
+    pub async fn read_with_timeout(&self, timeout: Duration) -> Result<T, RegisterError> {
+        Ok(self.read_labeled_within(Some(timeout)).await?.value)
+    }
+
+    /// Returns the value contained in the register, along with its label.
+    ///
+    /// Successive reads return values with non-decreasing labels, and the
+    /// same label and value are only returned by two reads if no write
+    /// changed the contents of the register between them.
+    pub(crate) async fn read_labeled(&self) -> Result<LocalValue<T, L>, RegisterError> {
+        self.read_labeled_within(self.timeout).await
+    }
+
+    /// Returns the value contained in the register, along with its label, if
+    /// the read completes within the given timeout.
+    #[tracing::instrument(name = "read", level = "debug", skip_all, fields(operation = next_operation_id()))]
+    async fn read_labeled_within(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<LocalValue<T, L>, RegisterError> {
+        self.check_serves_reads()?;
+        let _operation = self.admit()?;
+        #[cfg(feature = "history")]
+        let process = self.record_call(RegisterOperation::Read(None));
+        let local = self
+            .within(self.deadline(timeout), async {
+                match self.lease_expiry() {
+                    Some(_) => self.read_leased().await,
+                    None => {
+                        let info = self.communicate(Message::Ask).await?;
+                        let max = info.into_iter().max().unwrap();
+                        let local = self.repair(max).await?;
+                        self.communicate(Message::Announce).await?;
+                        // The read has already succeeded, so failing to
+                        // acquire a lease only means that the next read will
+                        // not be local.
+                        let _ = self.acquire_lease().await;
+                        Ok(local)
+                    }
+                }
+            })
+            .await?;
+        #[cfg(feature = "history")]
+        self.record_response(process, RegisterOperation::Read(Some(local.value.clone())));
+        Ok(local)
+    }
+
+    /// Returns the value contained in the register, with the given
+    /// [`ReadConsistency`].
+    ///
+    /// A [`Linearizable`](ReadConsistency::Linearizable) read is identical
+    /// to [`read`](AtomicRegister::read). Weaker reads are cheaper, but can
+    /// return stale values. See [Read Consistency](self#read-consistency)
+    /// for the anomalies that each allows. Only linearizable reads are
+    /// recorded in the history of this instance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::abd_95::{AtomicRegister, ReadConsistency};
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<u32> = AtomicRegister::default();
+    /// register.write(123).await.unwrap();
+    /// let value = register.read_with_consistency(ReadConsistency::Local).await.unwrap();
+    /// assert_eq!(value, 123);
+    /// # })
+    /// ```
+    #[tracing::instrument(
+        name = "read",
+        level = "debug",
+        skip_all,
+        fields(operation = next_operation_id(), ?consistency)
+    )]
+    pub async fn read_with_consistency(
+        &self,
+        consistency: ReadConsistency,
+    ) -> Result<T, RegisterError> {
+        match consistency {
+            ReadConsistency::Linearizable => self.read().await,
+            ReadConsistency::Sequential => {
+                self.check_serves_reads()?;
+                let _operation = self.admit()?;
+                let info = self
+                    .within(self.deadline(self.timeout), self.communicate(Message::Ask))
+                    .await?;
+                let max = info.into_iter().max().unwrap();
+                Ok(self.repair(max).await?.value)
+            }
+            ReadConsistency::Local => {
+                self.check_serves_reads()?;
+                Ok(self.local.lock().unwrap().value.clone())
+            }
+        }
+    }
+
+    /// Returns the value contained in the register or, if a majority of
+    /// neighbors cannot be reached, the local value of this instance marked
+    /// as [`Stale`].
+    ///
+    /// A stale value is only returned if reading fails because a quorum is
+    /// unavailable, a neighbor is unreachable, or the read timed out, and if
+    /// an operation of this instance last reached a quorum within the last
+    /// `budget`. Otherwise, including if no operation of this instance has
+    /// ever reached a quorum, the error encountered while reading is
+    /// returned, exactly as in [`read`](AtomicRegister::read). In particular,
+    /// messages from a stale epoch, and failures to persist a value, are
+    /// never masked by a stale value.
+    ///
+    /// Stale values do **not** satisfy atomicity. In particular, a stale value
+    /// may be older than the value returned by an earlier read or write, and
+    /// the local value may have changed since a quorum was last reached, so
+    /// that a quorum never confirmed the value that is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use std::time::Duration;
+    /// use todc_net::register::abd_95::{AtomicRegister, ReadOutcome};
+    ///
+    /// type Contents = u32;
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<Contents> = AtomicRegister::default();
+    /// let budget = Duration::from_secs(60);
+    /// match register.read_or_stale(budget).await.unwrap() {
+    ///     ReadOutcome::Fresh(value) => assert_eq!(value, 0),
+    ///     ReadOutcome::Stale(stale) => println!("Read stale value {}", stale.value),
+    /// }
+    /// # })
+    /// ```
+    #[tracing::instrument(level = "debug", skip_all, fields(operation = next_operation_id()))]
+    pub async fn read_or_stale(
+        &self,
+        budget: Duration,
+    ) -> Result<ReadOutcome<T, L>, RegisterError> {
+        self.check_serves_reads()?;
+        let _operation = self.admit()?;
+        match self.read().await {
+            Ok(value) => Ok(ReadOutcome::Fresh(value)),
+            Err(
+                error @ (RegisterError::QuorumUnavailable { .. }
+                | RegisterError::Unreachable(_)
+                | RegisterError::Timeout),
+            ) => {
+                let Some(last_confirmed) = *self.last_confirmed.lock().unwrap() else {
+                    return Err(error);
+                };
+                if self.clock.now().saturating_duration_since(last_confirmed) > budget {
+                    return Err(error);
+                }
+                let local = self.local.lock().unwrap().clone();
+                Ok(ReadOutcome::Stale(Stale {
+                    value: local.value,
+                    label: local.label,
+                    last_confirmed,
+                }))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Adopts the largest of the values returned to a read, and records
+    /// whether it repaired the local value of this instance.
+    async fn repair(&self, max: LocalValue<T, L>) -> Result<LocalValue<T, L>, RegisterError> {
+        let repaired = max > *self.local.lock().unwrap();
+        let local = self.update(max).await?;
+        let mut reads = self.reads.lock().unwrap();
+        if repaired {
+            reads.repaired += 1;
+        } else {
+            reads.current += 1;
+        }
+        Ok(local)
+    }
+
+    /// Returns the label that is `n` labels larger than the label of the
+    /// local value of this instance.
+    fn next_label(&self, n: usize) -> Result<L, RegisterError> {
+        let label = self.local.lock().unwrap().label;
+        label.advance(n).ok_or(RegisterError::LabelOverflow)
+    }
+
+    /// Sets the contents of the register to the specified value.
+    ///
+    /// Only one instance of the register may perform writes. Writes that are
+    /// performed on more than one instance are not guaranteed to be
+    /// linearizable. See [`AtomicRegister`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the label of the local value of this instance
+    /// cannot be advanced any further, in which case nothing is written. See
+    /// the [`label`](crate::register::label) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// type Contents = u32;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<Contents> = AtomicRegister::default();
+    /// register.write(123).await;
+    /// assert_eq!(register.read().await.unwrap(), 123);
+    /// # })
+    /// ```
+    pub async fn write(&self, value: T) -> Result<(), RegisterError> {
+        self.write_within(value, self.timeout).await
+    }
+
+    /// Sets the contents of the register to the specified value, or returns
+    /// [`RegisterError::Timeout`] if the write does not complete within the
+    /// given timeout.
+    ///
+    /// The timeout overrides the one set with
+    /// [`with_timeout`](AtomicRegister::with_timeout). A write that timed
+    /// out may or may not have taken effect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use std::time::Duration;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<u32> = AtomicRegister::default();
+    /// register.write_with_timeout(123, Duration::from_secs(1)).await.unwrap();
+    /// assert_eq!(register.read().await.unwrap(), 123);
+    /// # })
+    /// ```
+    pub async fn write_with_timeout(
+        &self,
+        value: T,
+        timeout: Duration,
+    ) -> Result<(), RegisterError> {
+        self.write_within(value, Some(timeout)).await
+    }
+
+    /// Sets the contents of the register to the specified value, if the
+    /// write completes within the given timeout.
+    #[tracing::instrument(name = "write", level = "debug", skip_all, fields(operation = next_operation_id()))]
+    async fn write_within(&self, value: T, timeout: Option<Duration>) -> Result<(), RegisterError> {
+        self.check_serves_writes()?;
+        let _operation = self.admit()?;
+        #[cfg(feature = "history")]
+        let process = self.record_call(RegisterOperation::Write(value.clone()));
+        let new = LocalValue {
+            value,
+            label: self.next_label(1)?,
+        };
+        self.update(new.clone()).await?;
+        self.within(self.deadline(timeout), self.communicate(Message::Announce))
+            .await?;
+        #[cfg(feature = "history")]
+        self.record_response(process, RegisterOperation::Write(new.value));
+        Ok(())
+    }
+
+    /// Sets the contents of the register to the specified value, unless this
+    /// instance has already done so for a request with the same ID.
+    ///
+    /// The first write with a given ID behaves exactly like
+    /// [`write`](AtomicRegister::write). A later write with the same ID, such
+    /// as a retry of a write that failed or timed out, is not applied again.
+    /// Instead, it announces the value with the label that it was first
+    /// assigned, and so completes the original write. See
+    /// [Idempotent Writes](self#idempotent-writes).
+    ///
+    /// Only the first write with a given ID is recorded in the history of
+    /// this instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ID was already used to write a different
+    /// value, or for any of the reasons that [`write`](AtomicRegister::write)
+    /// does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<u32> = AtomicRegister::default();
+    /// register.write_with_request_id("first", 1).await.unwrap();
+    /// register.write(2).await.unwrap();
+    ///
+    /// // Retrying the first write does not overwrite the second.
+    /// register.write_with_request_id("first", 1).await.unwrap();
+    /// assert_eq!(register.read().await.unwrap(), 2);
+    /// # })
+    /// ```
+    #[tracing::instrument(
+        name = "write",
+        level = "debug",
+        skip_all,
+        fields(operation = next_operation_id(), request_id = id)
+    )]
+    pub async fn write_with_request_id(&self, id: &str, value: T) -> Result<(), RegisterError> {
+        self.check_serves_writes()?;
+        let _operation = self.admit()?;
+        let (new, retried) = self.requests.lock().unwrap().get_or_insert(id, || {
+            Ok(LocalValue {
+                value: value.clone(),
+                label: self.next_label(1)?,
+            })
+        })?;
+        if retried && new.value != value {
+            return Err(RegisterError::RequestIdReused(id.to_string()));
+        }
+        #[cfg(feature = "history")]
+        let process = (!retried)
+            .then(|| self.record_call(RegisterOperation::Write(value)))
+            .flatten();
+        // A retried write announces the local value of this instance, which
+        // is at least as large as the value of the original write, and so
+        // completes it.
+        self.update(new.clone()).await?;
+        self.within(
+            self.deadline(self.timeout),
+            self.communicate(Message::Announce),
+        )
+        .await?;
+        #[cfg(feature = "history")]
+        self.record_response(process, RegisterOperation::Write(new.value));
+        Ok(())
+    }
+
+    /// Sets the contents of the register to each of the specified values in
+    /// turn, and announces only the last of them to other instances.
+    ///
+    /// The writes take effect one after another, at the same moment, so no
+    /// read returns any of the values except the last. This requires a single
+    /// round of communication, no matter how many values are written. If no
+    /// values are given, then nothing is written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// type Contents = u32;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<Contents> = AtomicRegister::default();
+    /// register.write_many(vec![1, 2, 3]).await.unwrap();
+    /// assert_eq!(register.read().await.unwrap(), 3);
+    /// # })
+    /// ```
+    #[tracing::instrument(name = "write", level = "debug", skip_all, fields(operation = next_operation_id()))]
+    pub async fn write_many(
+        &self,
+        values: impl IntoIterator<Item = T>,
+    ) -> Result<(), RegisterError> {
+        self.check_serves_writes()?;
+        let _operation = self.admit()?;
+        let values: Vec<T> = values.into_iter().collect();
+        let Some(value) = values.last().cloned() else {
+            return Ok(());
+        };
+        // Every write in the batch is called before any of them takes
+        // effect, so that reads concurrent with the batch may be ordered
+        // after all of them.
+        #[cfg(feature = "history")]
+        let processes: Vec<_> = values
+            .iter()
+            .map(|value| self.record_call(RegisterOperation::Write(value.clone())))
+            .collect();
+        // Each value receives its own label, so that the labels of the
+        // values written by this instance continue to increase by one per
+        // write.
+        let new = LocalValue {
+            value,
+            label: self.next_label(values.len())?,
+        };
+        self.update(new).await?;
+        self.within(
+            self.deadline(self.timeout),
+            self.communicate(Message::Announce),
+        )
+        .await?;
+        // The earlier values, which no read can observe, return just before
+        // the last. If the announcement fails, none of the writes return.
+        #[cfg(feature = "history")]
+        for (process, value) in processes.into_iter().zip(values) {
+            self.record_response(process, RegisterOperation::Write(value));
+        }
+        Ok(())
+    }
+
+    /// Replaces the contents of the register with the result of applying `f`
+    /// to them, and returns the new contents.
+    ///
+    /// The current contents are first read from a majority of instances, in
+    /// the same way as by [`read`](AtomicRegister::read). If `f` changes them,
+    /// then the new contents are announced with a label larger than any that
+    /// was read. Otherwise, the current contents are announced, and this
+    /// method behaves exactly like a read.
+    ///
+    /// The read and the write are **not** performed atomically. A write that
+    /// is concurrent with this method may take effect between them, in which
+    /// case it may be overwritten.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// type Contents = u32;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<Contents> = AtomicRegister::default();
+    /// register.write(1).await.unwrap();
+    /// assert_eq!(register.read_modify_write(|value| value + 1).await.unwrap(), 2);
+    /// # })
+    /// ```
+    #[tracing::instrument(level = "debug", skip_all, fields(operation = next_operation_id()))]
+    pub async fn read_modify_write(&self, f: impl Fn(&T) -> T) -> Result<T, RegisterError> {
+        self.check_serves_reads()?;
+        self.check_serves_writes()?;
+        let _operation = self.admit()?;
+        #[cfg(feature = "history")]
+        let process = self.record_call(RegisterOperation::Read(None));
+        // Both phases of the operation must complete before the same
+        // deadline.
+        let deadline = self.deadline(self.timeout);
+        let info = self
+            .within(deadline, self.communicate(Message::Ask))
+            .await?;
+        let max = info.into_iter().max().unwrap();
+        let value = f(&max.value);
+        // The operation is recorded as a read, along with a concurrent write
+        // if the contents of the register change.
+        #[cfg(feature = "history")]
+        let write = (value != max.value)
+            .then(|| self.record_call(RegisterOperation::Write(value.clone())))
+            .flatten();
+        if value == max.value {
+            self.update(max.clone()).await?;
+        } else {
+            let label = max.label.advance(1).ok_or(RegisterError::LabelOverflow)?;
+            self.update(LocalValue {
+                value: value.clone(),
+                label,
+            })
+            .await?;
+        }
+        self.within(deadline, self.communicate(Message::Announce))
+            .await?;
+        #[cfg(feature = "history")]
+        {
+            self.record_response(process, RegisterOperation::Read(Some(max.value)));
+            self.record_response(write, RegisterOperation::Write(value.clone()));
+        }
+        Ok(value)
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > Handler for AtomicRegister<T, Tr, L, C>
+{
+    fn handle(
+        &self,
+        message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        let me = self.clone();
+        async move {
+            if !matches!(
+                Route::from_path(&message.path),
+                Some(Route::Local | Route::Lease | Route::Gossip)
+            ) {
+                return Err(GenericError::from(format!(
+                    "Unknown route {}",
+                    message.path
+                )));
+            }
+            let codec =
+                Negotiated::<C>::from_header(message.content_type.as_deref()).ok_or_else(|| {
+                    GenericError::from(format!(
+                        "Unsupported media type {}",
+                        message.content_type.unwrap_or_default()
+                    ))
+                })?;
+            // Every version so far writes messages in the same way, so the
+            // version only needs to be understood. Later versions may instead
+            // decode messages according to their version.
+            Self::check_version(message.version)?;
+            me.check_epoch(message.epoch)?;
+            if message.label.is_some() && message.path != LOCAL_PATH {
+                return Err(GenericError::from(format!(
+                    "Messages to {} must not have a separate label",
+                    message.path
+                )));
+            }
+            let local = match (message.path.as_ref(), message.body) {
+                // Messages without a body ask for this instances local value
+                // and associated label.
+                (LOCAL_PATH, None) => me.local.lock().unwrap().clone(),
+                // Messages with a separate label contain only a value, and
+                // are acknowledged with the label of the result, so that
+                // large values are not sent back in reply.
+                (LOCAL_PATH, Some(body)) if message.label.is_some() => {
+                    let label: L = serde_json::from_str(message.label.as_deref().unwrap())?;
+                    let other = LocalValue {
+                        label,
+                        value: codec.decode(&body)?,
+                    };
+                    let local = me.update(other).await?;
+                    me.forward(local.clone()).await?;
+                    return Ok(me.buffer.encode_with(codec, &local.label)?);
+                }
+                // Messages with a body contain another value and label, and
+                // update this instances local value to be the _greater_ of the
+                // two. The result is forwarded to any leaseholders before it
+                // is acknowledged.
+                (LOCAL_PATH, Some(body)) => {
+                    let other: LocalValue<T, L> = codec.decode(&body)?;
+                    let local = me.update(other).await?;
+                    me.forward(local.clone()).await?;
+                    local
+                }
+                // Lease requests are granted, and answered with this
+                // instances local value. The lease is recorded first, so that
+                // any later change to the local value is forwarded.
+                (LEASE_PATH, Some(body)) => {
+                    let request: LeaseRequest = codec.decode(&body)?;
+                    me.grant(request)?;
+                    me.local.lock().unwrap().clone()
+                }
+                // Values forwarded to this instance, as a leaseholder, are
+                // adopted but not forwarded again. Values exchanged through
+                // gossip are adopted in the same way, since no operation
+                // awaits their acknowledgement, and answered with the result.
+                (LEASE_UPDATES_PATH | GOSSIP_PATH, Some(body)) => {
+                    let other: LocalValue<T, L> = codec.decode(&body)?;
+                    me.update(other).await?
+                }
+                // Messages to the lease and gossip routes must have a body.
+                (path, _) => {
+                    return Err(GenericError::from(format!(
+                        "Messages to {path} must have a body"
+                    )))
+                }
+            };
+            Ok(me.buffer.encode_with(codec, &local)?)
+        }
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > Service<Request<Incoming>> for AtomicRegister<T, Tr, L, C>
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        let limiter = Route::from_path(req.uri().path())
+            .and_then(|route| self.limits.get(&route))
+            .cloned();
+        let id = req.headers().get(REQUEST_ID_HEADER).cloned();
+        let response = self.serve(req);
+        let response = match limiter {
+            None => response,
+            Some(limiter) => Box::pin(async move {
+                match limiter.acquire().await {
+                    Some(_permit) => response.await,
+                    None => mk_response(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "Too many concurrent requests".into(),
+                    ),
+                }
+            }),
+        };
+        // The ID of the request, if any, is returned with the response, so
+        // that the two can be matched when tracing.
+        match id {
+            None => response,
+            Some(id) => Box::pin(async move {
+                let mut response = response.await?;
+                response.headers_mut().insert(REQUEST_ID_HEADER, id);
+                Ok(response)
+            }),
+        }
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Returns the response to a request, without limiting concurrency.
+    fn serve(&self, req: Request<Incoming>) -> <Self as Service<Request<Incoming>>>::Future {
+        // The Future we return can be send to other tasks or threads and
         // need to make sure that the objects it references remain valid:
         // https://rust-lang.github.io/async-book/03_async_await/01_chapter.html#async-lifetimes
         //
@@ -350,146 +2429,1315 @@ impl<T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 's
         // https://www.philipdaniels.com/blog/2020/self-cloning-for-multiple-threads-in-rust/
         let me = self.clone();
         match (req.method(), req.uri().path()) {
+            // GET requests read the contents of the register, and return them
+            // encoded with the codec named by the Accept header.
+            (&Method::GET, REGISTER_PATH) => Box::pin(async move {
+                let Some(codec) = negotiate::<C>(req.headers(), ACCEPT) else {
+                    return mk_response(StatusCode::NOT_ACCEPTABLE, "Not Acceptable".into());
+                };
+                match me.read().await {
+                    Ok(value) => {
+                        let body = me.buffer.encode_with(codec, &value)?;
+                        Ok(with_content_type(codec, Response::new(Full::new(body))))
+                    }
+                    Err(error) => mk_response(error.status_code(), error.to_string().into()),
+                }
+            }),
+            // POST requests take a value, encoded with the codec named by the
+            // Content-Type header, as input, and write it to the register.
+            (&Method::POST, REGISTER_PATH) => Box::pin(async move {
+                // Check the role before the body, so that instances that do
+                // not serve writes reject them regardless of their contents.
+                if let Err(error) = me.check_serves_writes() {
+                    return mk_response(error.status_code(), error.to_string().into());
+                }
+                let Some(codec) = negotiate::<C>(req.headers(), CONTENT_TYPE) else {
+                    return mk_response(
+                        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                        "Unsupported Media Type".into(),
+                    );
+                };
+                let id = request_id(req.headers());
+                let body = req.collect().await?.to_bytes();
+                let value: T = match codec.decode(&body) {
+                    Ok(value) => value,
+                    Err(error) => {
+                        return mk_response(StatusCode::BAD_REQUEST, error.to_string().into())
+                    }
+                };
+                let written = match id {
+                    Some(id) => me.write_with_request_id(&id, value).await,
+                    None => me.write(value).await,
+                };
+                match written {
+                    Ok(()) => Ok(Response::new(Full::new(NULL_BODY))),
+                    Err(error) => mk_response(error.status_code(), error.to_string().into()),
+                }
+            }),
             // GET requests return this severs local value and associated label
-            (&Method::GET, "/register/local") => {
+            (&Method::GET, LOCAL_PATH) => Box::pin(async move {
+                let Some(codec) = negotiate::<C>(req.headers(), ACCEPT) else {
+                    return mk_response(StatusCode::NOT_ACCEPTABLE, "Not Acceptable".into());
+                };
+                if let Some(response) = Self::reject_version(req.headers()) {
+                    return response;
+                }
+                if let Some(response) = me.reject_epoch(req.headers()) {
+                    return response;
+                }
+                let mut message =
+                    transport::Message::ask(LOCAL_PATH).with_content_type(codec.content_type());
+                message.version = protocol_version(req.headers());
+                let reply = me.handle(message).await?;
+                Ok(with_content_type(codec, Response::new(Full::new(reply))))
+            }),
+            // POST requests take another value and label as input, updates
+            // this servers local value to be the _greater_ of the two, and
+            // returns it, along with the associated label. Requests for, and
+            // updates to, leases, and gossip, are handled in the same way.
+            (
+                &Method::POST,
+                path @ (LOCAL_PATH | LEASE_PATH | LEASE_UPDATES_PATH | GOSSIP_PATH),
+            ) => {
+                // Each message refers to its route by one of the static paths
+                // above, rather than a copy of the path of the request.
+                let path = match path {
+                    LOCAL_PATH => LOCAL_PATH,
+                    LEASE_PATH => LEASE_PATH,
+                    LEASE_UPDATES_PATH => LEASE_UPDATES_PATH,
+                    _ => GOSSIP_PATH,
+                };
+                Box::pin(async move {
+                    let Some(codec) = negotiate::<C>(req.headers(), CONTENT_TYPE) else {
+                        return mk_response(
+                            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                            "Unsupported Media Type".into(),
+                        );
+                    };
+                    if let Some(response) = Self::reject_version(req.headers()) {
+                        return response;
+                    }
+                    if let Some(response) = me.reject_epoch(req.headers()) {
+                        return response;
+                    }
+                    let version = protocol_version(req.headers());
+                    // Values with a separate label are streamed, and must
+                    // match their digest.
+                    let label = match req.headers().get(LABEL_HEADER) {
+                        None => None,
+                        Some(label) => match label.to_str() {
+                            Ok(label) => Some(label.to_string()),
+                            Err(error) => {
+                                return mk_response(
+                                    StatusCode::BAD_REQUEST,
+                                    error.to_string().into(),
+                                )
+                            }
+                        },
+                    };
+                    let message = match label {
+                        None => {
+                            let body = req.collect().await?.to_bytes();
+                            transport::Message::announce(path, body)
+                        }
+                        Some(label) => {
+                            let (parts, body) = req.into_parts();
+                            match collect_streamed(body, &parts.headers).await {
+                                Ok(body) => {
+                                    transport::Message::announce(path, body).with_label(label)
+                                }
+                                Err(error) => {
+                                    return mk_response(
+                                        StatusCode::BAD_REQUEST,
+                                        error.to_string().into(),
+                                    )
+                                }
+                            }
+                        }
+                    };
+                    let mut message = message.with_content_type(codec.content_type());
+                    message.version = version;
+                    let reply = me.handle(message).await?;
+                    Ok(with_content_type(codec, Response::new(Full::new(reply))))
+                })
+            }
+            // GET requests return the URLs of this servers neighbors.
+            (&Method::GET, NEIGHBORS_PATH) => {
                 Box::pin(
-                    async move { mk_response(StatusCode::OK, serde_json::to_value(&me.local)?) },
+                    async move { mk_response(StatusCode::OK, neighbors_to_json(me.neighbors())) },
                 )
             }
-            // POST requests take another value and label as input, updates
-            // this servers local value to be the _greater_ of the two, and
-            // returns it, along with the associated label.
-            (&Method::POST, "/register/local") => Box::pin(async move {
-                let body = req.collect().await?.aggregate();
-                let other: LocalValue<T> = serde_json::from_reader(body.reader())?;
-                let local = me.update(&other);
-                mk_response(StatusCode::OK, serde_json::to_value(&local)?)
-            }),
-            _ => Box::pin(async { mk_response(StatusCode::NOT_FOUND, "404 Not Found".into()) }),
+            // GET requests return the status of this server, and succeed
+            // only if a quorum is reachable, for use as a readiness probe.
+            (&Method::GET, STATUS_PATH) => Box::pin(async move {
+                let status = me.status();
+                let code = if status.quorum_reachable {
+                    StatusCode::OK
+                } else {
+                    StatusCode::SERVICE_UNAVAILABLE
+                };
+                mk_response(code, status_to_json(status)?)
+            }),
+            // POST requests take a list of URLs as input, and reconfigure
+            // this server to use them as its neighbors.
+            (&Method::POST, NEIGHBORS_PATH) => Box::pin(async move {
+                let body = req.collect().await?.aggregate();
+                let neighbors = match neighbors_from_json(body.reader()) {
+                    Ok(neighbors) => neighbors,
+                    Err(error) => {
+                        return mk_response(StatusCode::BAD_REQUEST, error.to_string().into())
+                    }
+                };
+                match me.reconfigure(neighbors).await {
+                    Ok(()) => mk_response(StatusCode::OK, neighbors_to_json(me.neighbors())),
+                    Err(error) => mk_response(error.status_code(), error.to_string().into()),
+                }
+            }),
+            _ => Box::pin(async { mk_response(StatusCode::NOT_FOUND, "404 Not Found".into()) }),
+        }
+    }
+}
+
+/// Returns the codec named by a header of a request, or `None` if the
+/// instance does not understand it.
+fn negotiate<C: Codec>(headers: &HeaderMap, name: HeaderName) -> Option<Negotiated<C>> {
+    match headers.get(name) {
+        None => Negotiated::from_header(None),
+        Some(value) => Negotiated::from_header(Some(value.to_str().ok()?)),
+    }
+}
+
+/// Returns the ID of a request, if it has a valid one.
+fn request_id(headers: &HeaderMap) -> Option<String> {
+    let id = headers.get(REQUEST_ID_HEADER)?.to_str().ok()?;
+    Some(id.to_string())
+}
+
+/// Labels a response with the media type of the given codec.
+fn with_content_type<C: Codec>(
+    codec: Negotiated<C>,
+    mut response: Response<Full<Bytes>>,
+) -> Response<Full<Bytes>> {
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, codec.content_type().parse().unwrap());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod local_value {
+        use super::*;
+
+        #[test]
+        fn orders_by_label_first() {
+            let first: LocalValue<u32> = LocalValue { label: 0, value: 1 };
+            let second = LocalValue { label: 1, value: 0 };
+            assert!(first < second)
+        }
+
+        #[test]
+        fn orders_by_value_if_labels_match() {
+            let first: LocalValue<u32> = LocalValue { label: 0, value: 0 };
+            let second = LocalValue { label: 0, value: 1 };
+            assert!(first < second)
+        }
+    }
+
+    mod next_operation_id {
+        use super::*;
+
+        #[test]
+        fn returns_distinct_ids() {
+            let first = next_operation_id();
+            let second = next_operation_id();
+            assert_ne!(first, second);
+        }
+    }
+
+    mod register_error {
+        use super::*;
+
+        #[test]
+        fn maps_to_status_codes() {
+            let unavailable = RegisterError::QuorumUnavailable {
+                acks: 1,
+                needed: 2,
+                failures: 1,
+            };
+            assert_eq!(unavailable.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+            assert_eq!(
+                RegisterError::Timeout.status_code(),
+                StatusCode::GATEWAY_TIMEOUT
+            );
+            assert_eq!(
+                RegisterError::LabelOverflow.status_code(),
+                StatusCode::INTERNAL_SERVER_ERROR
+            );
+            let reused = RegisterError::RequestIdReused(String::from("id"));
+            assert_eq!(reused.status_code(), StatusCode::CONFLICT);
+            let stale = RegisterError::StaleEpoch {
+                epoch: 1,
+                current: 2,
+            };
+            assert_eq!(stale.status_code(), StatusCode::CONFLICT);
+            let discovery = RegisterError::Discovery("no such host".into());
+            assert_eq!(discovery.status_code(), StatusCode::BAD_GATEWAY);
+            let unsupported = RegisterError::UnsupportedProtocol {
+                version: 2,
+                supported: 1..=1,
+            };
+            assert_eq!(unsupported.status_code(), StatusCode::UPGRADE_REQUIRED);
+        }
+
+        #[test]
+        fn reports_supported_protocol_versions() {
+            let error = RegisterError::UnsupportedProtocol {
+                version: 3,
+                supported: 1..=2,
+            };
+            assert_eq!(
+                error.to_string(),
+                "Protocol version 3 is not supported, only versions 1 to 2 are"
+            );
+        }
+
+        #[test]
+        fn reports_missing_acknowledgements() {
+            let error = RegisterError::QuorumUnavailable {
+                acks: 1,
+                needed: 2,
+                failures: 1,
+            };
+            assert!(error
+                .to_string()
+                .contains("More than 1 of the instances are offline (1 of 2"));
+        }
+
+        #[test]
+        fn exposes_source_of_serialization_errors() {
+            let error: RegisterError = serde_json::from_str::<u32>("").unwrap_err().into();
+            assert!(error.source().is_some());
+            assert!(RegisterError::Timeout.source().is_none());
+        }
+    }
+
+    /// A transport over which no neighbor can be reached.
+    #[derive(Clone, Default)]
+    pub(super) struct Unreachable;
+
+    impl Transport for Unreachable {
+        async fn send(&self, _: Uri, _: transport::Message) -> Result<Bytes, GenericError> {
+            Err("Neighbor is unreachable".into())
+        }
+    }
+
+    mod atomic_register {
+        use super::*;
+        use crate::time::MockClock;
+        use crate::transport::memory::{self, Inbox, MemoryTransport};
+
+        mod communicate {
+            use super::*;
+
+            #[tokio::test]
+            async fn includes_own_local_value_in_response() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                let info = register.communicate(Message::Ask).await.unwrap();
+
+                let local = register.local.lock().unwrap();
+                assert_eq!(info, vec![local.clone()])
+            }
+
+            /// Returns a register with two neighbors, whose messages are
+            /// delivered to an inbox, instead of over a network.
+            fn scripted() -> (AtomicRegister<u32, MemoryTransport>, Inbox) {
+                let (transport, inbox) = memory::channel();
+                let neighbors = vec![
+                    Uri::from_static("http://neighbor-1.com"),
+                    Uri::from_static("http://neighbor-2.com"),
+                ];
+                (AtomicRegister::with_transport(neighbors, transport), inbox)
+            }
+
+            #[tokio::test]
+            async fn returns_once_majority_of_instances_reply() {
+                let (register, mut inbox) = scripted();
+                let reply = LocalValue {
+                    label: 5,
+                    value: 123,
+                };
+                // The second neighbor never replies, but the reply of the
+                // first, along with this instance, forms a majority.
+                let (info, ()) = tokio::join!(register.communicate(Message::Ask), async {
+                    let envelope = inbox.recv().await.unwrap();
+                    assert_eq!(envelope.message().path, LOCAL_PATH);
+                    envelope.reply(serde_json::to_vec(&reply).unwrap());
+                });
+                let info = info.unwrap();
+                assert_eq!(info.len(), 2);
+                assert!(info.contains(&reply));
+            }
+
+            #[tokio::test]
+            async fn fails_once_majority_of_instances_is_unreachable() {
+                let (register, mut inbox) = scripted();
+                let (info, ()) = tokio::join!(register.communicate(Message::Ask), async {
+                    inbox.recv().await.unwrap().fail("Neighbor crashed");
+                    drop(inbox.recv().await.unwrap());
+                });
+                assert!(matches!(
+                    info,
+                    Err(RegisterError::QuorumUnavailable {
+                        acks: 1,
+                        needed: 2,
+                        ..
+                    })
+                ));
+            }
+
+            #[tokio::test]
+            async fn write_announces_value_to_instances_that_reply() {
+                let (register, mut inbox) = scripted();
+                let reachable: AtomicRegister<u32> = AtomicRegister::default();
+
+                let write = register.write(123);
+                inbox
+                    .forward_until_with(&reachable, write, |envelope| {
+                        // The second neighbor has crashed, and never replies.
+                        (envelope.neighbor().host() == Some("neighbor-1.com")).then_some(envelope)
+                    })
+                    .await
+                    .unwrap();
+                assert_eq!(reachable.local.lock().unwrap().value, 123);
+            }
+        }
+
+        mod handle {
+            use super::*;
+
+            #[tokio::test]
+            async fn returns_local_value_when_asked() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                register.write(123).await.unwrap();
+                let reply = register
+                    .handle(transport::Message::ask(LOCAL_PATH))
+                    .await
+                    .unwrap();
+                let local: LocalValue<u32> = serde_json::from_slice(&reply).unwrap();
+                assert_eq!(
+                    local,
+                    LocalValue {
+                        label: 1,
+                        value: 123
+                    }
+                );
+            }
+
+            #[tokio::test]
+            async fn adopts_announced_value_with_larger_label() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                let other = LocalValue {
+                    label: 1,
+                    value: 123,
+                };
+                let body = serde_json::to_vec(&other).unwrap().into();
+                register
+                    .handle(transport::Message::announce(LOCAL_PATH, body))
+                    .await
+                    .unwrap();
+                assert_eq!(*register.local.lock().unwrap(), other);
+            }
+
+            #[tokio::test]
+            async fn acknowledges_value_with_separate_label_with_label() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                let message = transport::Message::announce(LOCAL_PATH, "123".into())
+                    .with_label(String::from("1"));
+                let reply = register.handle(message).await.unwrap();
+                let label: u64 = serde_json::from_slice(&reply).unwrap();
+                assert_eq!(label, 1);
+                assert_eq!(
+                    *register.local.lock().unwrap(),
+                    LocalValue {
+                        label: 1,
+                        value: 123
+                    }
+                );
+            }
+
+            #[tokio::test]
+            async fn rejects_separate_label_outside_local_route() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                let message = transport::Message::announce(GOSSIP_PATH, "123".into())
+                    .with_label(String::from("1"));
+                assert!(register.handle(message).await.is_err());
+                assert_eq!(*register.local.lock().unwrap(), LocalValue::default());
+            }
+
+            #[tokio::test]
+            async fn rejects_unknown_routes() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                let message = transport::Message::ask("/register/foo");
+                assert!(register.handle(message).await.is_err());
+            }
+
+            #[tokio::test]
+            async fn rejects_unknown_media_types() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                let message = transport::Message::ask(LOCAL_PATH).with_content_type("image/png");
+                assert!(register.handle(message).await.is_err());
+            }
+
+            #[cfg(feature = "cbor")]
+            #[tokio::test]
+            async fn replies_with_codec_named_by_message() {
+                use crate::codec::Cbor;
+
+                let register: AtomicRegister<u32, HttpTransport, u64, Cbor> =
+                    AtomicRegister::default();
+                register.write(123).await.unwrap();
+                let message =
+                    transport::Message::ask(LOCAL_PATH).with_content_type(Cbor::CONTENT_TYPE);
+                let reply = register.handle(message).await.unwrap();
+                let local: LocalValue<u32> = Cbor::decode(&reply).unwrap();
+                assert_eq!(local.value, 123);
+            }
+
+            #[cfg(feature = "cbor")]
+            #[tokio::test]
+            async fn replies_with_json_regardless_of_codec() {
+                use crate::codec::Cbor;
+
+                let register: AtomicRegister<u32, HttpTransport, u64, Cbor> =
+                    AtomicRegister::default();
+                register.write(123).await.unwrap();
+                let reply = register
+                    .handle(transport::Message::ask(LOCAL_PATH))
+                    .await
+                    .unwrap();
+                let local: LocalValue<u32> = serde_json::from_slice(&reply).unwrap();
+                assert_eq!(local.value, 123);
+            }
+
+            #[tokio::test]
+            async fn rejects_messages_from_earlier_epochs() {
+                let register: AtomicRegister<u32> = AtomicRegister::default().with_epoch(2);
+                let message = transport::Message::ask(LOCAL_PATH).with_epoch(1);
+                let error = register.handle(message).await.unwrap_err();
+                assert!(error.to_string().contains("older than the current epoch 2"));
+            }
+
+            #[tokio::test]
+            async fn enters_epoch_of_messages_from_later_epochs() {
+                let register: AtomicRegister<u32> = AtomicRegister::default().with_epoch(1);
+                let message = transport::Message::ask(LOCAL_PATH).with_epoch(3);
+                register.handle(message).await.unwrap();
+                assert_eq!(register.epoch(), 3);
+            }
+
+            #[tokio::test]
+            async fn accepts_messages_without_epoch() {
+                let register: AtomicRegister<u32> = AtomicRegister::default().with_epoch(1);
+                let message = transport::Message::ask(LOCAL_PATH);
+                assert!(register.handle(message).await.is_ok());
+                assert_eq!(register.epoch(), 1);
+            }
+
+            #[tokio::test]
+            async fn rejects_messages_in_unsupported_versions() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                let message = transport::Message::ask(LOCAL_PATH).with_version(99);
+                let error = register.handle(message).await.unwrap_err();
+                assert!(error.to_string().contains("version 99 is not supported"));
+                let error = error.downcast_ref::<UnsupportedVersion>().unwrap();
+                assert_eq!(error.supported, PROTOCOL_VERSIONS);
+            }
+
+            #[tokio::test]
+            async fn accepts_messages_in_supported_versions() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                for version in PROTOCOL_VERSIONS {
+                    let message = transport::Message::ask(LOCAL_PATH).with_version(version);
+                    assert!(register.handle(message).await.is_ok());
+                }
+            }
+
+            #[tokio::test]
+            async fn accepts_messages_without_version() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                let message = transport::Message::ask(LOCAL_PATH);
+                assert!(register.handle(message).await.is_ok());
+            }
+        }
+
+        mod read {
+            use super::*;
+
+            #[tokio::test]
+            async fn returns_value_without_label() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                assert_eq!(0, register.read().await.unwrap())
+            }
+        }
+
+        mod read_with_consistency {
+            use super::*;
+
+            /// Returns a register with two neighbors that cannot be reached.
+            fn isolated() -> AtomicRegister<u32, Unreachable> {
+                let neighbors = vec![
+                    Uri::from_static("http://neighbor-1.com"),
+                    Uri::from_static("http://neighbor-2.com"),
+                ];
+                AtomicRegister::with_transport(neighbors, Unreachable)
+            }
+
+            #[tokio::test]
+            async fn local_read_does_not_contact_neighbors() {
+                let register = isolated();
+                let value = register.read_with_consistency(ReadConsistency::Local);
+                assert_eq!(value.await.unwrap(), 0);
+            }
+
+            #[tokio::test]
+            async fn sequential_read_requires_majority() {
+                let register = isolated();
+                let value = register.read_with_consistency(ReadConsistency::Sequential);
+                assert!(value.await.is_err());
+            }
+
+            #[tokio::test]
+            async fn weaker_reads_are_rejected_by_witnesses() {
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_role(Role::Witness);
+                for consistency in [ReadConsistency::Sequential, ReadConsistency::Local] {
+                    assert!(register.read_with_consistency(consistency).await.is_err());
+                }
+            }
+        }
+
+        mod read_or_stale {
+            use super::*;
+
+            #[tokio::test]
+            async fn returns_fresh_value_if_majority_is_reachable() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                let outcome = register.read_or_stale(Duration::ZERO).await.unwrap();
+                assert_eq!(outcome, ReadOutcome::Fresh(0))
+            }
+
+            #[tokio::test]
+            async fn returns_stale_value_only_within_budget() {
+                let clock = MockClock::new();
+                let (transport, mut inbox) = memory::channel();
+                let neighbors = vec![
+                    Uri::from_static("http://neighbor-1.com"),
+                    Uri::from_static("http://neighbor-2.com"),
+                ];
+                let register: AtomicRegister<u32, MemoryTransport> =
+                    AtomicRegister::with_transport(neighbors, transport).with_clock(clock.clone());
+                let reachable: AtomicRegister<u32> = AtomicRegister::default();
+
+                // A majority of instances confirms the local value...
+                let value = inbox.forward_until(&reachable, register.read()).await;
+                assert_eq!(value.unwrap(), 0);
+                // ...and then becomes unreachable.
+                drop(inbox);
+                let budget = Duration::from_secs(5);
+
+                match register.read_or_stale(budget).await.unwrap() {
+                    ReadOutcome::Fresh(_) => panic!("Expected read to return a stale value"),
+                    ReadOutcome::Stale(stale) => assert_eq!(stale.last_confirmed, clock.now()),
+                }
+
+                clock.advance(Duration::from_secs(6));
+                assert!(register.read_or_stale(budget).await.is_err());
+            }
+
+            #[tokio::test]
+            async fn raises_error_if_value_was_never_confirmed() {
+                let neighbors = vec![
+                    Uri::from_static("http://neighbor-1.com"),
+                    Uri::from_static("http://neighbor-2.com"),
+                ];
+                let register: AtomicRegister<u32, Unreachable> =
+                    AtomicRegister::with_transport(neighbors, Unreachable);
+                let budget = Duration::from_secs(60);
+                assert!(register.read_or_stale(budget).await.is_err());
+            }
+
+            /// A storage that fails every write.
+            struct Broken;
+
+            impl Storage for Broken {
+                fn load(&self) -> Result<Option<Vec<u8>>, GenericError> {
+                    Ok(None)
+                }
+
+                fn store(&self, _: &[u8]) -> Result<(), GenericError> {
+                    Err("disk is full".into())
+                }
+            }
+
+            #[tokio::test]
+            async fn raises_error_if_quorum_is_available() {
+                let (transport, mut inbox) = memory::channel();
+                let neighbors = vec![
+                    Uri::from_static("http://neighbor-1.com"),
+                    Uri::from_static("http://neighbor-2.com"),
+                ];
+                let register: AtomicRegister<u32, MemoryTransport> =
+                    AtomicRegister::with_transport(neighbors, transport)
+                        .with_storage(Broken)
+                        .unwrap();
+                let reachable: AtomicRegister<u32> = AtomicRegister::default();
+
+                // A majority of instances confirms the local value...
+                let value = inbox.forward_until(&reachable, register.read()).await;
+                assert_eq!(value.unwrap(), 0);
+                // ...and then returns a newer value, which cannot be persisted.
+                reachable.write(123).await.unwrap();
+
+                let read = register.read_or_stale(Duration::from_secs(60));
+                let result = inbox.forward_until(&reachable, read).await;
+                assert!(matches!(result, Err(RegisterError::Storage(_))));
+            }
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        mod with_failure_threshold {
+            use super::*;
 
-    mod local_value {
-        use super::*;
+            /// A transport over which every neighbor replies with the
+            /// initial value.
+            #[derive(Clone, Default)]
+            struct Initial;
 
-        #[test]
-        fn orders_by_label_first() {
-            let first = LocalValue { label: 0, value: 1 };
-            let second = LocalValue { label: 1, value: 0 };
-            assert!(first < second)
+            impl Transport for Initial {
+                async fn send(&self, _: Uri, _: transport::Message) -> Result<Bytes, GenericError> {
+                    let value: LocalValue<u32> = LocalValue::default();
+                    Ok(serde_json::to_vec(&value)?.into())
+                }
+            }
+
+            fn neighbors(n: usize) -> Vec<Uri> {
+                (0..n)
+                    .map(|i| format!("http://test-{i}.com").parse().unwrap())
+                    .collect()
+            }
+
+            #[test]
+            fn tolerates_minority_by_default() {
+                let register: AtomicRegister<u32> = AtomicRegister::new(neighbors(4));
+                assert_eq!(register.resilience(), Resilience::majority(5));
+                assert_eq!(register.resilience().failures(), 2);
+            }
+
+            #[test]
+            fn rejects_too_few_instances() {
+                let result = AtomicRegister::<u32>::new(neighbors(3)).with_failure_threshold(2);
+                assert!(matches!(
+                    result,
+                    Err(RegisterError::TooFewInstances(TooFewInstances {
+                        instances: 4,
+                        failures: 2,
+                        ..
+                    }))
+                ));
+            }
+
+            #[tokio::test]
+            async fn waits_for_all_but_failure_threshold() {
+                let register: AtomicRegister<u32, Unreachable> =
+                    AtomicRegister::with_transport(neighbors(4), Unreachable)
+                        .with_failure_threshold(1)
+                        .unwrap();
+                let error = register.read().await.unwrap_err();
+                assert!(matches!(
+                    error,
+                    RegisterError::QuorumUnavailable {
+                        acks: 1,
+                        needed: 4,
+                        failures: 1
+                    }
+                ));
+                assert!(error
+                    .to_string()
+                    .starts_with("More than 1 of the instances"));
+            }
+
+            #[tokio::test]
+            async fn reconfigure_rejects_too_few_neighbors() {
+                let register: AtomicRegister<u32, Initial> =
+                    AtomicRegister::with_transport(neighbors(2), Initial)
+                        .with_failure_threshold(1)
+                        .unwrap();
+                let error = register.reconfigure(neighbors(1)).await.unwrap_err();
+                assert!(matches!(error, RegisterError::TooFewInstances(_)));
+                assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+                assert_eq!(register.neighbors(), neighbors(2));
+            }
         }
 
-        #[test]
-        fn orders_by_value_if_labels_match() {
-            let first = LocalValue { label: 0, value: 0 };
-            let second = LocalValue { label: 0, value: 1 };
-            assert!(first < second)
+        mod with_quorum_policy {
+            use super::*;
+            use crate::quorum::WeightedVoting;
+
+            /// A transport over which only the first neighbor can be
+            /// reached, and replies with the initial value.
+            #[derive(Clone, Default)]
+            struct OnlyFirst;
+
+            impl Transport for OnlyFirst {
+                async fn send(
+                    &self,
+                    to: Uri,
+                    _: transport::Message,
+                ) -> Result<Bytes, GenericError> {
+                    if to != neighbors(1)[0] {
+                        return Err("Neighbor is unreachable".into());
+                    }
+                    let value: LocalValue<u32> = LocalValue::default();
+                    Ok(serde_json::to_vec(&value)?.into())
+                }
+            }
+
+            fn neighbors(n: usize) -> Vec<Uri> {
+                (0..n)
+                    .map(|i| format!("http://test-{i}.com").parse().unwrap())
+                    .collect()
+            }
+
+            fn weighted(first: usize) -> QuorumPolicy {
+                QuorumPolicy::Weighted(
+                    WeightedVoting::new(1).with_weight(neighbors(1)[0].clone(), first),
+                )
+            }
+
+            #[tokio::test]
+            async fn completes_once_enough_votes_reply() {
+                // The first neighbor and this instance hold four of the six
+                // votes.
+                let register: AtomicRegister<u32, OnlyFirst> =
+                    AtomicRegister::with_transport(neighbors(3), OnlyFirst)
+                        .with_quorum_policy(weighted(3))
+                        .unwrap();
+                register.write(123).await.unwrap();
+                assert_eq!(register.read().await.unwrap(), 123);
+            }
+
+            #[tokio::test]
+            async fn fails_once_too_many_votes_are_unreachable() {
+                // The unreachable neighbors hold two of the four votes, and
+                // a quorum can only tolerate the loss of one.
+                let register: AtomicRegister<u32, OnlyFirst> =
+                    AtomicRegister::with_transport(neighbors(3), OnlyFirst)
+                        .with_quorum_policy(weighted(1))
+                        .unwrap();
+                let error = register.read().await.unwrap_err();
+                assert!(matches!(
+                    error,
+                    RegisterError::QuorumUnavailable {
+                        needed: 3,
+                        failures: 1,
+                        ..
+                    }
+                ));
+            }
+
+            #[test]
+            fn reports_whether_enough_votes_are_reachable() {
+                let register: AtomicRegister<u32> = AtomicRegister::new(neighbors(3))
+                    .with_quorum_policy(weighted(3))
+                    .unwrap();
+                register.record_contact(neighbors(3)[1].clone(), false);
+                register.record_contact(neighbors(3)[2].clone(), false);
+                assert!(register.status().quorum_reachable);
+                register.record_contact(neighbors(3)[0].clone(), false);
+                assert!(!register.status().quorum_reachable);
+            }
+
+            #[test]
+            fn failure_threshold_holds_whichever_instances_crash() {
+                // Three of the five votes remain if any one instance crashes,
+                // even the first neighbor, but not if two do.
+                let register: AtomicRegister<u32> = AtomicRegister::new(neighbors(3))
+                    .with_quorum_policy(weighted(2))
+                    .unwrap();
+                assert_eq!(register.resilience().instances(), 4);
+                assert_eq!(register.resilience().failures(), 1);
+            }
+
+            #[test]
+            fn rejects_thresholds_that_do_not_intersect() {
+                let voting = WeightedVoting::new(1).with_thresholds(1, 2);
+                let result = AtomicRegister::<u32>::new(neighbors(2))
+                    .with_quorum_policy(QuorumPolicy::Weighted(voting));
+                let error = result.err().unwrap();
+                assert!(matches!(error, RegisterError::InvalidThresholds(_)));
+                assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+            }
+
+            #[tokio::test]
+            async fn reconfigure_rejects_neighbors_that_break_thresholds() {
+                let voting = WeightedVoting::new(1).with_thresholds(2, 2);
+                let register: AtomicRegister<u32, OnlyFirst> =
+                    AtomicRegister::with_transport(neighbors(2), OnlyFirst)
+                        .with_quorum_policy(QuorumPolicy::Weighted(voting))
+                        .unwrap();
+                let error = register.reconfigure(neighbors(3)).await.unwrap_err();
+                assert!(matches!(error, RegisterError::InvalidThresholds(_)));
+                assert_eq!(register.neighbors(), neighbors(2));
+            }
         }
-    }
 
-    mod atomic_register {
-        use super::*;
+        mod with_role {
+            use super::*;
 
-        mod communicate {
+            #[tokio::test]
+            async fn read_only_instance_serves_reads() {
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_role(Role::ReadOnly);
+                assert_eq!(register.read().await.unwrap(), 0);
+            }
+
+            #[tokio::test]
+            async fn read_only_instance_rejects_writes() {
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_role(Role::ReadOnly);
+                assert!(register.write(1).await.is_err());
+                assert!(register.write_many(vec![1, 2]).await.is_err());
+                assert!(register.read_modify_write(|v| v + 1).await.is_err());
+                assert_eq!(*register.local.lock().unwrap(), LocalValue::default());
+            }
+
+            #[tokio::test]
+            async fn witness_rejects_reads_and_writes() {
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_role(Role::Witness);
+                assert!(register.read().await.is_err());
+                assert!(register.read_or_stale(Duration::MAX).await.is_err());
+                assert!(register.write(1).await.is_err());
+            }
+
+            #[tokio::test]
+            async fn rejected_operations_are_forbidden() {
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_role(Role::Witness);
+                let error = register.read().await.unwrap_err();
+                assert!(matches!(error, RegisterError::Forbidden(Role::Witness)));
+                assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+            }
+
+            #[tokio::test]
+            async fn witness_adopts_announced_value() {
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_role(Role::Witness);
+                let other = LocalValue {
+                    label: 1,
+                    value: 123,
+                };
+                let body = serde_json::to_vec(&other).unwrap().into();
+                register
+                    .handle(transport::Message::announce(LOCAL_PATH, body))
+                    .await
+                    .unwrap();
+                assert_eq!(*register.local.lock().unwrap(), other);
+            }
+        }
+
+        mod with_timeout {
             use super::*;
 
+            /// A transport over which no neighbor ever replies, and that
+            /// counts the requests that are still waiting for a reply.
+            #[derive(Clone, Default)]
+            struct Hanging {
+                waiting: Arc<AtomicU64>,
+            }
+
+            /// Decrements the number of waiting requests once a request is
+            /// dropped.
+            struct Waiting(Arc<AtomicU64>);
+
+            impl Drop for Waiting {
+                fn drop(&mut self) {
+                    self.0.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+
+            impl Transport for Hanging {
+                async fn send(&self, _: Uri, _: transport::Message) -> Result<Bytes, GenericError> {
+                    self.waiting.fetch_add(1, Ordering::SeqCst);
+                    let _waiting = Waiting(self.waiting.clone());
+                    std::future::pending().await
+                }
+            }
+
+            fn register(transport: Hanging) -> AtomicRegister<u32, Hanging> {
+                let neighbors = vec![
+                    Uri::from_static("http://neighbor-1.com"),
+                    Uri::from_static("http://neighbor-2.com"),
+                ];
+                AtomicRegister::with_transport(neighbors, transport)
+            }
+
             #[tokio::test]
-            async fn includes_own_local_value_in_response() {
-                let register: AtomicRegister<u32> = AtomicRegister::default();
-                let info = register.communicate(Message::Ask).await.unwrap();
+            async fn read_times_out_if_majority_never_replies() {
+                let register = register(Hanging::default()).with_timeout(Duration::from_millis(10));
+                let error = register.read().await.unwrap_err();
+                assert!(matches!(error, RegisterError::Timeout));
+                assert_eq!(error.status_code(), StatusCode::GATEWAY_TIMEOUT);
+            }
 
-                let local = register.local.lock().unwrap();
-                assert_eq!(info, vec![local.clone()])
+            #[tokio::test]
+            async fn write_times_out_if_majority_never_replies() {
+                let register = register(Hanging::default()).with_timeout(Duration::from_millis(10));
+                let error = register.write(123).await.unwrap_err();
+                assert!(matches!(error, RegisterError::Timeout));
+            }
+
+            #[tokio::test]
+            async fn per_call_timeouts_override_default() {
+                let register = register(Hanging::default()).with_timeout(Duration::from_secs(3600));
+                let timeout = Duration::from_millis(10);
+                let read = register.read_with_timeout(timeout).await;
+                assert!(matches!(read, Err(RegisterError::Timeout)));
+                let write = register.write_with_timeout(123, timeout).await;
+                assert!(matches!(write, Err(RegisterError::Timeout)));
+            }
+
+            #[tokio::test]
+            async fn read_modify_write_shares_deadline_between_phases() {
+                let register = register(Hanging::default()).with_timeout(Duration::from_millis(10));
+                let result = register.read_modify_write(|value| value + 1).await;
+                assert!(matches!(result, Err(RegisterError::Timeout)));
+            }
+
+            #[tokio::test]
+            async fn cancels_outstanding_requests() {
+                let transport = Hanging::default();
+                let register = register(transport.clone());
+                let read = register.read_with_timeout(Duration::from_millis(10)).await;
+                assert!(matches!(read, Err(RegisterError::Timeout)));
+                // Aborted requests are dropped the next time the runtime
+                // schedules them.
+                while transport.waiting.load(Ordering::SeqCst) > 0 {
+                    tokio::task::yield_now().await;
+                }
+            }
+
+            #[tokio::test]
+            async fn completes_operations_in_time() {
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_timeout(Duration::from_secs(1));
+                register.write(123).await.unwrap();
+                assert_eq!(register.read().await.unwrap(), 123);
             }
         }
 
-        mod neighbor_urls {
+        mod with_shutdown {
             use super::*;
 
-            #[test]
-            fn appends_local_suffix() {
-                let neighbor = Uri::from_static("http://test.com");
-                let register = AtomicRegister::<u32>::new(vec![neighbor]);
-                let urls = register.neighbor_urls();
-                let url = urls.first().unwrap();
-                assert_eq!(url.host().unwrap(), "test.com");
-                assert_eq!(url.path(), "/register/local");
+            /// A transport over which no neighbor ever replies.
+            #[derive(Clone, Default)]
+            struct Hanging;
+
+            impl Transport for Hanging {
+                async fn send(&self, _: Uri, _: transport::Message) -> Result<Bytes, GenericError> {
+                    std::future::pending().await
+                }
+            }
+
+            fn register(shutdown: &Shutdown) -> AtomicRegister<u32, Hanging> {
+                let neighbors = vec![
+                    Uri::from_static("http://neighbor-1.com"),
+                    Uri::from_static("http://neighbor-2.com"),
+                ];
+                AtomicRegister::with_transport(neighbors, Hanging).with_shutdown(shutdown.clone())
+            }
+
+            #[tokio::test]
+            async fn rejects_new_operations_while_draining() {
+                let shutdown = Shutdown::new();
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_shutdown(shutdown.clone());
+                register.write(123).await.unwrap();
+                shutdown.drain();
+                let error = register.write(456).await.unwrap_err();
+                assert!(matches!(error, RegisterError::ShuttingDown));
+                assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+                assert!(matches!(
+                    register.read_modify_write(|value| value + 1).await,
+                    Err(RegisterError::ShuttingDown)
+                ));
+                assert_eq!(register.local.lock().unwrap().value, 123);
+            }
+
+            #[tokio::test]
+            async fn tracks_operations_in_flight() {
+                let shutdown = Shutdown::new();
+                let register = register(&shutdown);
+                let read = tokio::spawn(async move { register.read().await });
+                while shutdown.in_flight() == 0 {
+                    tokio::task::yield_now().await;
+                }
+                shutdown.drain();
+                assert_eq!(shutdown.in_flight(), 1);
+                read.abort();
+                let _ = read.await;
+                assert_eq!(shutdown.in_flight(), 0);
+            }
+
+            #[tokio::test]
+            async fn stopping_cancels_quorum_rounds_in_flight() {
+                let shutdown = Shutdown::new();
+                let register = register(&shutdown);
+                let write = tokio::spawn(async move { register.write(123).await });
+                while shutdown.in_flight() == 0 {
+                    tokio::task::yield_now().await;
+                }
+                assert!(!shutdown.shutdown(Duration::from_millis(10)).await);
+                let result = write.await.unwrap();
+                assert!(matches!(result, Err(RegisterError::ShuttingDown)));
+            }
+
+            #[tokio::test]
+            async fn serves_neighbors_while_draining() {
+                let shutdown = Shutdown::new();
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_shutdown(shutdown.clone());
+                shutdown.drain();
+                let message = transport::Message::ask(LOCAL_PATH);
+                assert!(register.handle(message).await.is_ok());
             }
         }
 
-        mod read {
+        mod write {
             use super::*;
 
             #[tokio::test]
-            async fn returns_value_without_label() {
+            async fn updates_local_to_new_value() {
                 let register: AtomicRegister<u32> = AtomicRegister::default();
-                assert_eq!(0, register.read().await.unwrap())
+                register.write(123).await.unwrap();
+
+                let local = register.local.lock().unwrap();
+                assert_eq!(123, local.value);
+            }
+
+            #[tokio::test]
+            async fn increases_local_label_by_one() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                register.write(123).await.unwrap();
+
+                let local = register.local.lock().unwrap();
+                assert_eq!(1, local.label);
+            }
+
+            #[tokio::test]
+            async fn uses_chosen_label_type() {
+                let register: AtomicRegister<u32, HttpTransport, u32> = AtomicRegister::default();
+                register.write(123).await.unwrap();
+
+                let local = register.local.lock().unwrap();
+                assert_eq!(1_u32, local.label);
+            }
+
+            #[tokio::test]
+            async fn fails_if_label_overflows() {
+                let register: AtomicRegister<u32, HttpTransport, u32> = AtomicRegister::default();
+                register.local.lock().unwrap().label = u32::MAX;
+                let result = register.write(123).await;
+                assert!(matches!(result, Err(RegisterError::LabelOverflow)));
+
+                let local = register.local.lock().unwrap();
+                assert_eq!((0, u32::MAX), (local.value, local.label));
             }
         }
 
-        mod update {
+        mod write_with_request_id {
             use super::*;
 
-            #[test]
-            fn returns_current_local_value() {
+            #[tokio::test]
+            async fn retry_does_not_increase_label() {
                 let register: AtomicRegister<u32> = AtomicRegister::default();
-                let other = LocalValue {
-                    value: 123,
-                    label: 123,
-                };
-                let local = register.update(&other);
-                assert_eq!(other, local);
+                register.write_with_request_id("id", 123).await.unwrap();
+                register.write_with_request_id("id", 123).await.unwrap();
+
+                let local = register.local.lock().unwrap();
+                assert_eq!((123, 1), (local.value, local.label));
             }
 
-            #[test]
-            fn changes_local_value_if_other_label_is_larger() {
+            #[tokio::test]
+            async fn retry_does_not_overwrite_later_write() {
                 let register: AtomicRegister<u32> = AtomicRegister::default();
-                register.update(&LocalValue {
-                    value: 123,
-                    label: 123,
-                });
+                register.write_with_request_id("id", 123).await.unwrap();
+                register.write(456).await.unwrap();
+                register.write_with_request_id("id", 123).await.unwrap();
+
                 let local = register.local.lock().unwrap();
-                assert_eq!(local.value, 123);
-                assert_eq!(local.label, 123);
+                assert_eq!((456, 2), (local.value, local.label));
             }
 
-            #[test]
-            fn leaves_local_value_alone_other_label_is_smaller() {
+            #[tokio::test]
+            async fn fails_if_id_is_reused_with_different_value() {
                 let register: AtomicRegister<u32> = AtomicRegister::default();
-                // Update local to have non-zero label
-                register.update(&LocalValue {
-                    value: 123,
-                    label: 123,
-                });
-                // Update again with smaller label
-                register.update(&LocalValue { value: 1, label: 1 });
+                register.write_with_request_id("id", 123).await.unwrap();
+                let result = register.write_with_request_id("id", 456).await;
+                assert!(matches!(result, Err(RegisterError::RequestIdReused(id)) if id == "id"));
+                assert_eq!(register.local.lock().unwrap().value, 123);
+            }
+
+            #[tokio::test]
+            async fn distinct_ids_are_separate_writes() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                register.write_with_request_id("first", 123).await.unwrap();
+                register.write_with_request_id("second", 123).await.unwrap();
+                assert_eq!(register.local.lock().unwrap().label, 2);
+            }
+
+            #[tokio::test]
+            async fn forgets_oldest_id_once_capacity_is_reached() {
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_request_capacity(1);
+                register.write_with_request_id("first", 123).await.unwrap();
+                register.write_with_request_id("second", 456).await.unwrap();
+                register.write_with_request_id("first", 123).await.unwrap();
+
                 let local = register.local.lock().unwrap();
-                assert_eq!(local.value, 123);
-                assert_eq!(local.label, 123);
+                assert_eq!((123, 3), (local.value, local.label));
+            }
+
+            #[tokio::test]
+            async fn applies_every_retry_without_capacity() {
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_request_capacity(0);
+                register.write_with_request_id("id", 123).await.unwrap();
+                register.write_with_request_id("id", 123).await.unwrap();
+                assert_eq!(register.local.lock().unwrap().label, 2);
+            }
+
+            #[tokio::test]
+            async fn retry_after_failure_reuses_label() {
+                let neighbors = vec![
+                    Uri::from_static("http://neighbor-1.com"),
+                    Uri::from_static("http://neighbor-2.com"),
+                ];
+                let register: AtomicRegister<u32, Unreachable> =
+                    AtomicRegister::with_transport(neighbors, Unreachable);
+                assert!(register.write_with_request_id("id", 123).await.is_err());
+                assert!(register.write_with_request_id("id", 123).await.is_err());
+
+                let local = register.local.lock().unwrap();
+                assert_eq!((123, 1), (local.value, local.label));
             }
         }
 
-        mod write {
+        mod write_many {
             use super::*;
 
             #[tokio::test]
-            async fn updates_local_to_new_value() {
+            async fn updates_local_to_last_value() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                register.write_many(vec![1, 2, 3]).await.unwrap();
+
+                let local = register.local.lock().unwrap();
+                assert_eq!(3, local.value);
+            }
+
+            #[tokio::test]
+            async fn increases_local_label_by_number_of_values() {
                 let register: AtomicRegister<u32> = AtomicRegister::default();
                 register.write(123).await.unwrap();
+                register.write_many(vec![1, 2, 3]).await.unwrap();
 
                 let local = register.local.lock().unwrap();
-                assert_eq!(123, local.value);
+                assert_eq!(4, local.label);
             }
 
             #[tokio::test]
-            async fn increases_local_label_by_one() {
+            async fn does_nothing_if_there_are_no_values() {
                 let register: AtomicRegister<u32> = AtomicRegister::default();
                 register.write(123).await.unwrap();
+                register.write_many(Vec::new()).await.unwrap();
+
+                let local = register.local.lock().unwrap();
+                assert_eq!((123, 1), (local.value, local.label));
+            }
+        }
+
+        #[cfg(feature = "history")]
+        mod with_history {
+            use super::*;
+            use crate::register::history::HistorySink;
+            use todc_utils::Action;
+
+            #[tokio::test]
+            async fn records_each_value_written_by_write_many() {
+                let sink = HistorySink::new();
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_history(sink.clone());
+                register.write_many(vec![1, 2, 3]).await.unwrap();
+                assert_eq!(sink.history().len(), 6);
+            }
+
+            #[tokio::test]
+            async fn calls_every_write_of_write_many_before_any_returns() {
+                let sink = HistorySink::new();
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_history(sink.clone());
+                register.write_many(vec![1, 2, 3]).await.unwrap();
+                let actions: Vec<_> = sink.actions().into_iter().map(|a| a.action).collect();
+                assert_eq!(
+                    actions,
+                    [1, 2, 3]
+                        .map(|v| Action::Call(RegisterOperation::Write(v)))
+                        .into_iter()
+                        .chain([1, 2, 3].map(|v| Action::Response(RegisterOperation::Write(v))))
+                        .collect::<Vec<_>>()
+                );
+            }
+
+            #[tokio::test]
+            async fn records_read_modify_write_as_read_and_write() {
+                let sink = HistorySink::new();
+                let register: AtomicRegister<u32> =
+                    AtomicRegister::default().with_history(sink.clone());
+                register.read_modify_write(|v| v + 1).await.unwrap();
+                register.read_modify_write(|v| *v).await.unwrap();
+                assert_eq!(sink.history().len(), 6);
+            }
+        }
+
+        mod read_modify_write {
+            use super::*;
+
+            #[tokio::test]
+            async fn applies_function_to_current_value() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                register.write(1).await.unwrap();
+                let value = register.read_modify_write(|v| v * 10).await.unwrap();
+                assert_eq!(value, 10);
+                assert_eq!(register.read().await.unwrap(), 10);
+            }
+
+            #[tokio::test]
+            async fn increases_local_label_if_value_changes() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                register.write(1).await.unwrap();
+                register.read_modify_write(|v| v + 1).await.unwrap();
+
+                let local = register.local.lock().unwrap();
+                assert_eq!(2, local.label);
+            }
+
+            #[tokio::test]
+            async fn preserves_local_label_if_value_is_unchanged() {
+                let register: AtomicRegister<u32> = AtomicRegister::default();
+                register.write(1).await.unwrap();
+                register.read_modify_write(|v| *v).await.unwrap();
 
                 let local = register.local.lock().unwrap();
                 assert_eq!(1, local.label);
@@ -0,0 +1,166 @@
+//! Finding the neighbors of an instance through a [`Discovery`].
+use std::fmt::Debug;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+
+use super::reconfiguration::same_neighbors;
+use super::{AtomicRegister, RegisterError};
+use crate::codec::Codec;
+use crate::discovery::Discovery;
+use crate::register::label::Label;
+use crate::transport::Transport;
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Replaces the neighbors of this instance with those found by the
+    /// given [`Discovery`], and returns whether they changed.
+    ///
+    /// If the neighbors that are found differ from the current neighbors,
+    /// ignoring their order, then the instance is
+    /// [`reconfigure`](AtomicRegister::reconfigure)d to use them. Finding no
+    /// neighbors at all leaves the current neighbors in place, so that a
+    /// transient failure to resolve them does not shrink the cluster down to
+    /// a single instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the neighbors cannot be found, or if the instance
+    /// cannot be reconfigured to use them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use hyper::Uri;
+    /// use todc_net::discovery::StaticDiscovery;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let neighbor = Uri::from_static("http://my-register-2.com");
+    /// let register: AtomicRegister<u32> = AtomicRegister::new(vec![neighbor.clone()]);
+    ///
+    /// let discovery = StaticDiscovery::new(vec![neighbor]);
+    /// assert!(!register.refresh_neighbors(&discovery).await.unwrap());
+    /// # })
+    /// ```
+    pub async fn refresh_neighbors<D: Discovery>(
+        &self,
+        discovery: &D,
+    ) -> Result<bool, RegisterError> {
+        let found = discovery
+            .discover()
+            .await
+            .map_err(RegisterError::Discovery)?;
+        if found.is_empty() || same_neighbors(&found, &self.neighbors()) {
+            return Ok(false);
+        }
+        tracing::debug!(neighbors = ?found, "discovered new neighbors");
+        self.reconfigure(found).await?;
+        Ok(true)
+    }
+
+    /// Spawns a task that [`refresh_neighbors`](AtomicRegister::refresh_neighbors)
+    /// from the given [`Discovery`] once every `interval`.
+    ///
+    /// Rounds that fail are logged, and otherwise skipped. The task runs
+    /// until it is aborted through the returned handle, and must be spawned
+    /// from within a [`tokio`] runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use std::time::Duration;
+    /// use todc_net::discovery::StaticDiscovery;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<u32> = AtomicRegister::default();
+    /// let discovery = StaticDiscovery::default();
+    /// let refresh = register.spawn_discovery(discovery, Duration::from_secs(30));
+    /// // ...
+    /// refresh.abort();
+    /// # })
+    /// ```
+    pub fn spawn_discovery<D: Discovery>(
+        &self,
+        discovery: D,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let me = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let next = me.clock.now() + interval;
+                if let Err(error) = me.refresh_neighbors(&discovery).await {
+                    tracing::debug!(error = %error, "failed to refresh neighbors");
+                }
+                me.clock.sleep_until(next).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GenericError;
+    use hyper::Uri;
+
+    mod refresh_neighbors {
+        use super::*;
+        use crate::discovery::StaticDiscovery;
+
+        struct FailingDiscovery;
+
+        impl Discovery for FailingDiscovery {
+            async fn discover(&self) -> Result<Vec<Uri>, GenericError> {
+                Err("no such host".into())
+            }
+        }
+
+        fn neighbors() -> Vec<Uri> {
+            vec![
+                Uri::from_static("http://test-1.com"),
+                Uri::from_static("http://test-2.com"),
+            ]
+        }
+
+        #[tokio::test]
+        async fn ignores_order_of_neighbors() {
+            let register: AtomicRegister<u32> = AtomicRegister::new(neighbors());
+            let reversed = neighbors().into_iter().rev().collect();
+            let changed = register
+                .refresh_neighbors(&StaticDiscovery::new(reversed))
+                .await
+                .unwrap();
+            assert!(!changed);
+            assert_eq!(register.neighbors(), neighbors());
+        }
+
+        #[tokio::test]
+        async fn keeps_neighbors_if_none_are_found() {
+            let register: AtomicRegister<u32> = AtomicRegister::new(neighbors());
+            let changed = register
+                .refresh_neighbors(&StaticDiscovery::default())
+                .await
+                .unwrap();
+            assert!(!changed);
+            assert_eq!(register.neighbors(), neighbors());
+        }
+
+        #[tokio::test]
+        async fn returns_error_if_discovery_fails() {
+            let register: AtomicRegister<u32> = AtomicRegister::new(neighbors());
+            let result = register.refresh_neighbors(&FailingDiscovery).await;
+            assert!(matches!(result, Err(RegisterError::Discovery(_))));
+        }
+    }
+}
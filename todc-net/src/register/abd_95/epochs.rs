@@ -0,0 +1,93 @@
+//! Rejecting messages from instances in earlier epochs.
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::header::HeaderMap;
+use hyper::http::StatusCode;
+use hyper::Response;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{AtomicRegister, RegisterError};
+use crate::codec::Codec;
+use crate::register::label::Label;
+use crate::transport::http::EPOCH_HEADER;
+use crate::transport::Transport;
+use crate::{mk_response, GenericError};
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Sets the epoch in which this instance begins. See
+    /// [Epochs](super#epochs).
+    ///
+    /// By default, instances begin in epoch `0`.
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = Arc::new(Mutex::new(epoch));
+        self
+    }
+
+    /// Returns the current epoch of this instance.
+    pub fn epoch(&self) -> u64 {
+        *self.epoch.lock().unwrap()
+    }
+
+    /// Enters the given epoch, after which this instance rejects messages
+    /// from earlier epochs. See [Epochs](super#epochs).
+    ///
+    /// Epochs only ever increase, so this has no effect if the instance is
+    /// already in the given epoch, or a later one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// let register: AtomicRegister<u32> = AtomicRegister::default().with_epoch(2);
+    /// register.enter_epoch(1);
+    /// assert_eq!(register.epoch(), 2);
+    /// register.enter_epoch(3);
+    /// assert_eq!(register.epoch(), 3);
+    /// ```
+    pub fn enter_epoch(&self, epoch: u64) {
+        let mut current = self.epoch.lock().unwrap();
+        *current = epoch.max(*current);
+    }
+
+    /// Returns an error if a message from the given epoch must be rejected,
+    /// and otherwise enters that epoch if it is later than the current one.
+    pub(super) fn check_epoch(&self, epoch: Option<u64>) -> Result<(), RegisterError> {
+        let Some(epoch) = epoch else {
+            return Ok(());
+        };
+        let mut current = self.epoch.lock().unwrap();
+        if epoch < *current {
+            return Err(RegisterError::StaleEpoch {
+                epoch,
+                current: *current,
+            });
+        }
+        *current = epoch;
+        Ok(())
+    }
+
+    /// Returns the response with which a request from a neighbor is
+    /// rejected, if the epoch sent in its headers is invalid or stale.
+    pub(super) fn reject_epoch(
+        &self,
+        headers: &HeaderMap,
+    ) -> Option<Result<Response<Full<Bytes>>, GenericError>> {
+        let epoch = headers.get(EPOCH_HEADER)?;
+        let Some(epoch) = epoch.to_str().ok().and_then(|epoch| epoch.parse().ok()) else {
+            return Some(mk_response(StatusCode::BAD_REQUEST, "Invalid epoch".into()));
+        };
+        let error = self.check_epoch(Some(epoch)).err()?;
+        Some(mk_response(error.status_code(), error.to_string().into()))
+    }
+}
@@ -0,0 +1,131 @@
+//! Exchanging local values with neighbors in the background.
+use std::fmt::Debug;
+use std::time::Duration;
+
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+
+use super::{next_operation_id, AtomicRegister, LocalValue, RegisterError, Route, GOSSIP_PATH};
+use crate::codec::Codec;
+use crate::register::label::Label;
+use crate::transport::{self, Transport};
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Exchanges the local value of this instance with that of a neighbor
+    /// chosen uniformly at random, after which both instances hold the larger
+    /// of the two.
+    ///
+    /// An instance with no neighbors does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chosen neighbor cannot be reached.
+    #[tracing::instrument(level = "trace", skip_all, fields(operation = next_operation_id()))]
+    pub async fn gossip(&self) -> Result<(), RegisterError> {
+        let Some(neighbor) = self.neighbors().choose(&mut thread_rng()).cloned() else {
+            return Ok(());
+        };
+        let local = self.local.lock().unwrap().clone();
+        let message =
+            transport::Message::announce(GOSSIP_PATH, self.buffer.encode::<C, _>(&local)?)
+                .with_content_type(C::CONTENT_TYPE)
+                .with_epoch(self.epoch());
+        let start = self.clock.now();
+        let reply = self.send(Route::Gossip, neighbor.clone(), message).await;
+        self.record_contact(neighbor.clone(), reply.is_ok());
+        if reply.is_ok() {
+            self.record_latency(neighbor.clone(), self.clock.now() - start);
+        }
+        let reply = reply.map_err(|_| RegisterError::Unreachable(neighbor.clone()))?;
+        let other: LocalValue<T, L> = C::decode(&reply)?;
+        self.record_label(neighbor, other.label);
+        self.update(other).await?;
+        Ok(())
+    }
+
+    /// Spawns a task that [`gossip`](AtomicRegister::gossip)s with a random
+    /// neighbor once every `interval`, so that instances that missed a value
+    /// eventually learn of it, even if no operations are performed.
+    ///
+    /// Rounds in which the chosen neighbor cannot be reached are skipped. The
+    /// task runs until it is aborted through the returned handle, and must be
+    /// spawned from within a [`tokio`] runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use std::time::Duration;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<u32> = AtomicRegister::default();
+    /// let gossip = register.spawn_gossip(Duration::from_secs(1));
+    /// // ...
+    /// gossip.abort();
+    /// # })
+    /// ```
+    pub fn spawn_gossip(&self, interval: Duration) -> JoinHandle<()> {
+        let me = self.clone();
+        tokio::spawn(async move {
+            loop {
+                // Rounds begin once every interval, unless a round takes
+                // longer than that, in which case the next begins immediately.
+                let next = me.clock.now() + interval;
+                let _ = me.gossip().await;
+                me.clock.sleep_until(next).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::Handler;
+
+    mod gossip {
+        use super::*;
+
+        #[tokio::test]
+        async fn does_nothing_without_neighbors() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            register.gossip().await.unwrap();
+            assert_eq!(*register.local.lock().unwrap(), LocalValue::default());
+        }
+
+        #[tokio::test]
+        async fn adopts_and_replies_with_larger_value() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            register.write(123).await.unwrap();
+            let stale: LocalValue<u32> = LocalValue::default();
+            let body = serde_json::to_vec(&stale).unwrap().into();
+            let reply = register
+                .handle(transport::Message::announce(GOSSIP_PATH, body))
+                .await
+                .unwrap();
+            let local: LocalValue<u32> = serde_json::from_slice(&reply).unwrap();
+            assert_eq!(local.value, 123);
+
+            let newer = LocalValue {
+                label: 2,
+                value: 456,
+            };
+            let body = serde_json::to_vec(&newer).unwrap().into();
+            register
+                .handle(transport::Message::announce(GOSSIP_PATH, body))
+                .await
+                .unwrap();
+            assert_eq!(*register.local.lock().unwrap(), newer);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Sending duplicates of slow requests.
+use std::fmt::Debug;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::AtomicRegister;
+use crate::codec::Codec;
+use crate::register::label::Label;
+use crate::transport::Transport;
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Hedges against slow requests by sending a duplicate of each message
+    /// to the neighbors that have not replied, if a quorum has not replied
+    /// within `delay`.
+    ///
+    /// Every operation sends its messages to every neighbor, and waits for
+    /// the fastest quorum to reply. With hedging, each neighbor that has
+    /// neither replied nor failed once the delay has passed is sent the
+    /// message again, and only the first reply from each neighbor is counted.
+    /// A neighbor has only failed once every request to it has. The moving
+    /// average of the time that each neighbor takes to reply, as reported by
+    /// [`status`](AtomicRegister::status), is a good guide to the delay.
+    /// See [Hedging](super#hedging).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// let register: AtomicRegister<u32> =
+    ///     AtomicRegister::default().with_hedging(Duration::from_millis(50));
+    /// ```
+    pub fn with_hedging(mut self, delay: Duration) -> Self {
+        self.hedge = Some(delay);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::abd_95::{LocalValue, Message, RegisterError};
+    use crate::time::MockClock;
+    use crate::transport::memory::{self, Envelope, Inbox, MemoryTransport};
+    use hyper::Uri;
+
+    mod with_hedging {
+        use super::*;
+
+        fn neighbors(n: usize) -> Vec<Uri> {
+            (0..n)
+                .map(|i| format!("http://test-{i}.com").parse().unwrap())
+                .collect()
+        }
+
+        /// Returns a register with `n` neighbors, whose messages are
+        /// delivered to an inbox.
+        fn hedged(clock: &MockClock, n: usize) -> (AtomicRegister<u32, MemoryTransport>, Inbox) {
+            let (transport, inbox) = memory::channel();
+            let register: AtomicRegister<u32, MemoryTransport> =
+                AtomicRegister::with_transport(neighbors(n), transport)
+                    .with_clock(clock.clone())
+                    .with_hedging(Duration::from_millis(50));
+            (register, inbox)
+        }
+
+        /// Recieves the next `n` messages, ordered by their recipient.
+        async fn recv(inbox: &mut Inbox, n: usize) -> Vec<Envelope> {
+            let mut envelopes = Vec::new();
+            for _ in 0..n {
+                envelopes.push(inbox.recv().await.unwrap());
+            }
+            envelopes.sort_by_key(|envelope| envelope.neighbor().to_string());
+            envelopes
+        }
+
+        fn reply(envelope: Envelope) {
+            let value: LocalValue<u32> = LocalValue::default();
+            envelope.reply(serde_json::to_vec(&value).unwrap());
+        }
+
+        #[tokio::test]
+        async fn contacts_every_neighbor() {
+            let (register, mut inbox) = hedged(&MockClock::new(), 2);
+            let (info, ()) = tokio::join!(register.communicate(Message::Ask), async {
+                let mut envelopes = recv(&mut inbox, 2).await;
+                let neighbors: Vec<Uri> = envelopes.iter().map(|e| e.neighbor().clone()).collect();
+                assert_eq!(neighbors, self::neighbors(2));
+                reply(envelopes.pop().unwrap());
+            });
+            assert!(info.is_ok());
+            assert!(inbox.try_recv().is_none());
+        }
+
+        #[tokio::test]
+        async fn resends_to_neighbors_that_have_not_replied_after_delay() {
+            let clock = MockClock::new();
+            let (register, mut inbox) = hedged(&clock, 2);
+            let (info, ()) = tokio::join!(register.communicate(Message::Ask), async {
+                let stalled = recv(&mut inbox, 2).await;
+                assert!(inbox.try_recv().is_none());
+                clock.advance(Duration::from_millis(50));
+                let mut duplicates = recv(&mut inbox, 2).await;
+                assert_eq!(duplicates[0].neighbor(), stalled[0].neighbor());
+                assert_eq!(duplicates[1].neighbor(), stalled[1].neighbor());
+                reply(duplicates.pop().unwrap());
+                drop(stalled);
+            });
+            assert!(info.is_ok());
+        }
+
+        #[tokio::test]
+        async fn does_not_resend_to_neighbors_that_failed() {
+            let clock = MockClock::new();
+            let (register, mut inbox) = hedged(&clock, 2);
+            let (info, ()) = tokio::join!(register.communicate(Message::Ask), async {
+                let mut envelopes = recv(&mut inbox, 2).await;
+                let stalled = envelopes.pop().unwrap();
+                envelopes.pop().unwrap().fail("Neighbor crashed");
+                tokio::task::yield_now().await;
+                clock.advance(Duration::from_millis(50));
+                let duplicate = inbox.recv().await.unwrap();
+                assert_eq!(duplicate.neighbor(), stalled.neighbor());
+                reply(duplicate);
+            });
+            assert!(info.is_ok());
+            assert!(inbox.try_recv().is_none());
+        }
+
+        #[tokio::test]
+        async fn counts_each_neighbor_once() {
+            let clock = MockClock::new();
+            let (register, mut inbox) = hedged(&clock, 4);
+            let (info, ()) = tokio::join!(register.communicate(Message::Ask), async {
+                let mut originals = recv(&mut inbox, 4).await;
+                clock.advance(Duration::from_millis(50));
+                let mut duplicates = recv(&mut inbox, 4).await;
+                // Both copies of the message to the first neighbor are
+                // answered, and every other neighbor fails.
+                reply(originals.remove(0));
+                reply(duplicates.remove(0));
+                for envelope in originals.into_iter().chain(duplicates) {
+                    envelope.fail("Neighbor crashed");
+                }
+            });
+            assert!(matches!(
+                info,
+                Err(RegisterError::QuorumUnavailable { acks: 2, .. })
+            ));
+        }
+
+        #[tokio::test]
+        async fn does_not_resend_without_hedging() {
+            let clock = MockClock::new();
+            let (transport, mut inbox) = memory::channel();
+            let register: AtomicRegister<u32, MemoryTransport> =
+                AtomicRegister::with_transport(neighbors(2), transport).with_clock(clock.clone());
+            let (info, ()) = tokio::join!(register.communicate(Message::Ask), async {
+                let mut stalled = recv(&mut inbox, 2).await;
+                clock.advance(Duration::from_secs(60));
+                tokio::task::yield_now().await;
+                assert!(inbox.try_recv().is_none());
+                reply(stalled.pop().unwrap());
+            });
+            assert!(info.is_ok());
+        }
+    }
+}
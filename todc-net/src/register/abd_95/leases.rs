@@ -0,0 +1,324 @@
+//! Read leases, which let an instance serve reads from its local value.
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::time::Duration;
+
+use hyper::Uri;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use super::{AtomicRegister, LocalValue, Message, RegisterError, Route, LEASE_UPDATES_PATH};
+use crate::codec::Codec;
+use crate::register::label::Label;
+use crate::transport::{self, Transport};
+use crate::GenericError;
+
+/// The configuration of the read leases held by a register instance.
+///
+/// See [`with_lease`](AtomicRegister::with_lease).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeaseConfig {
+    address: Uri,
+    duration: Duration,
+    margin: Duration,
+}
+
+impl LeaseConfig {
+    /// Creates a configuration for leases that last for the given duration,
+    /// held by the instance that its neighbors can reach at `address`.
+    ///
+    /// The margin defaults to a tenth of the duration.
+    pub fn new(address: Uri, duration: Duration) -> Self {
+        Self {
+            address,
+            duration,
+            margin: duration / 10,
+        }
+    }
+
+    /// Sets the amount of time by which a leaseholder considers its lease to
+    /// expire early, to account for the clocks of instances advancing at
+    /// different rates.
+    ///
+    /// The margin must be at least the largest amount by which the clocks of
+    /// any two instances can drift apart over the duration of a lease.
+    pub fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+}
+
+/// A request for a lease, sent by a prospective leaseholder.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(super) struct LeaseRequest {
+    /// The URL at which the leaseholder can be reached.
+    holder: String,
+    /// The duration of the lease.
+    duration: Duration,
+}
+
+/// The leases held and granted by a register instance.
+#[derive(Debug, Default)]
+pub(super) struct Leases<T: Clone + Debug + Default + Ord + Send, L> {
+    /// The time at which the lease held by this instance expires, if it
+    /// holds one.
+    expires: Option<Instant>,
+    /// The times at which the leases granted by this instance expire, by the
+    /// URL of their holder.
+    granted: HashMap<Uri, Instant>,
+    /// The largest local value of this instance that is known to have been
+    /// announced to a majority of instances.
+    pub(super) confirmed: LocalValue<T, L>,
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Allows this instance to hold read leases, as configured by the given
+    /// [`LeaseConfig`].
+    ///
+    /// While it holds a valid lease, this instance serves reads from its
+    /// local value, without asking a majority of instances for theirs. When
+    /// it does not hold a lease, a read is performed as usual, after which
+    /// the instance asks a majority of instances for a new lease. Any
+    /// instance grants leases when asked, whether or not it can hold them
+    /// itself.
+    ///
+    /// Leases rely on timing assumptions that the rest of the register does
+    /// not. See the [module-level](crate::register::abd_95#read-leases)
+    /// documentation for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use std::time::Duration;
+    /// use hyper::Uri;
+    /// use todc_net::register::abd_95::{AtomicRegister, LeaseConfig};
+    ///
+    /// # tokio_test::block_on(async {
+    /// let address = Uri::from_static("https://my-register-1.com");
+    /// let register: AtomicRegister<u32> = AtomicRegister::default()
+    ///     .with_lease(LeaseConfig::new(address, Duration::from_secs(10)));
+    ///
+    /// // The first read acquires a lease, which later reads are served by.
+    /// assert_eq!(register.read().await.unwrap(), 0);
+    /// assert!(register.lease_expiry().is_some());
+    /// # })
+    /// ```
+    pub fn with_lease(mut self, config: LeaseConfig) -> Self {
+        self.lease = Some(config);
+        self
+    }
+
+    /// Returns the time at which the lease held by this instance expires, or
+    /// `None` if it does not hold a valid lease.
+    pub fn lease_expiry(&self) -> Option<Instant> {
+        let expires = self.leases.lock().unwrap().expires?;
+        (expires > self.clock.now()).then_some(expires)
+    }
+
+    /// Asks a majority of instances for a lease, and adopts the largest of
+    /// their local values.
+    pub(super) async fn acquire_lease(&self) -> Result<(), RegisterError> {
+        let Some(config) = &self.lease else {
+            return Ok(());
+        };
+        // The lease is considered to begin before any instance grants it.
+        let start = self.clock.now();
+        let request = LeaseRequest {
+            holder: config.address.to_string(),
+            duration: config.duration,
+        };
+        let info = self.communicate(Message::Lease(request)).await?;
+        let max = info.into_iter().max().unwrap();
+        self.update(max).await?;
+        let expires = start + config.duration.saturating_sub(config.margin);
+        let mut leases = self.leases.lock().unwrap();
+        leases.expires = leases.expires.max(Some(expires));
+        Ok(())
+    }
+
+    /// Grants a lease to the instance that requested it.
+    pub(super) fn grant(&self, request: LeaseRequest) -> Result<(), GenericError> {
+        let holder: Uri = request.holder.parse()?;
+        let expires = self.clock.now() + request.duration;
+        let mut leases = self.leases.lock().unwrap();
+        let granted = leases.granted.entry(holder).or_insert(expires);
+        *granted = (*granted).max(expires);
+        Ok(())
+    }
+
+    /// Forwards the local value of this instance to the holder of every lease
+    /// that it has granted, and waits until each of them has adopted it, or
+    /// until their lease has expired.
+    pub(super) async fn forward(&self, local: LocalValue<T, L>) -> Result<(), RegisterError> {
+        let now = self.clock.now();
+        let holders: Vec<(Uri, Instant)> = {
+            let mut leases = self.leases.lock().unwrap();
+            leases.granted.retain(|_, expires| *expires > now);
+            leases
+                .granted
+                .iter()
+                .map(|(holder, expires)| (holder.clone(), *expires))
+                .collect()
+        };
+        if holders.is_empty() {
+            return Ok(());
+        }
+
+        let body = self.buffer.encode::<C, _>(&local)?;
+        let mut handles = JoinSet::new();
+        for (holder, expires) in holders {
+            let message = transport::Message::announce(LEASE_UPDATES_PATH, body.clone())
+                .with_content_type(C::CONTENT_TYPE)
+                .with_epoch(self.epoch());
+            let me = self.clone();
+            handles.spawn(async move {
+                // If the leaseholder cannot be reached, then it can no longer
+                // serve reads once its lease has expired.
+                let reply = me
+                    .clock
+                    .timeout_at(expires, me.send(Route::Lease, holder, message))
+                    .await;
+                if !matches!(reply, Some(Ok(_))) {
+                    me.clock.sleep_until(expires).await;
+                }
+            });
+        }
+        while handles.join_next().await.is_some() {}
+        Ok(())
+    }
+
+    /// Returns the local value of this instance, which holds a valid lease.
+    ///
+    /// While the lease is valid, the local value is at least as large as the
+    /// value of any operation that has completed. If the local value is not
+    /// yet known to have been announced to a majority of instances, then it
+    /// is announced first, so that no later read can return a smaller value.
+    pub(super) async fn read_leased(&self) -> Result<LocalValue<T, L>, RegisterError> {
+        let local = self.local.lock().unwrap().clone();
+        if local == self.leases.lock().unwrap().confirmed {
+            return Ok(local);
+        }
+        let info = self.communicate(Message::Announce).await?;
+        Ok(info.into_iter().next().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::abd_95::tests::Unreachable;
+    use crate::register::abd_95::LEASE_PATH;
+    use crate::time::MockClock;
+    use crate::transport::Handler;
+
+    mod with_lease {
+        use super::*;
+
+        const HOLDER: &str = "http://leaseholder.com";
+
+        fn leaseholder() -> AtomicRegister<u32> {
+            let config = LeaseConfig::new(HOLDER.parse().unwrap(), Duration::from_secs(10));
+            AtomicRegister::default().with_lease(config)
+        }
+
+        #[tokio::test]
+        async fn read_acquires_lease() {
+            let register = leaseholder();
+            assert!(register.lease_expiry().is_none());
+            register.read().await.unwrap();
+            assert!(register.lease_expiry().is_some());
+        }
+
+        #[tokio::test]
+        async fn read_does_not_acquire_lease_if_not_configured() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            register.read().await.unwrap();
+            assert!(register.lease_expiry().is_none());
+        }
+
+        #[tokio::test]
+        async fn leased_read_announces_unconfirmed_value() {
+            let register = leaseholder();
+            register.read().await.unwrap();
+            register
+                .update(LocalValue {
+                    label: 1,
+                    value: 123,
+                })
+                .await
+                .unwrap();
+            assert_eq!(register.read().await.unwrap(), 123);
+            assert_eq!(register.leases.lock().unwrap().confirmed.value, 123);
+        }
+
+        #[tokio::test]
+        async fn lease_expires_once_clock_advances_past_expiry() {
+            let clock = MockClock::new();
+            let register = leaseholder().with_clock(clock.clone());
+            register.read().await.unwrap();
+
+            // The lease expires early by the default margin of a tenth of
+            // its duration.
+            clock.advance(Duration::from_secs(8));
+            assert!(register.lease_expiry().is_some());
+            clock.advance(Duration::from_secs(1));
+            assert!(register.lease_expiry().is_none());
+        }
+
+        #[tokio::test]
+        async fn write_waits_for_unreachable_leaseholder_until_lease_expires() {
+            let clock = MockClock::new();
+            let register: AtomicRegister<u32, Unreachable> =
+                AtomicRegister::with_transport(Vec::new(), Unreachable).with_clock(clock.clone());
+            register
+                .grant(LeaseRequest {
+                    holder: HOLDER.to_string(),
+                    duration: Duration::from_secs(10),
+                })
+                .unwrap();
+
+            let write = tokio::spawn({
+                let register = register.clone();
+                async move { register.write(123).await }
+            });
+            tokio::task::yield_now().await;
+            assert!(!write.is_finished());
+
+            clock.advance(Duration::from_secs(10));
+            write.await.unwrap().unwrap();
+        }
+
+        #[tokio::test]
+        async fn grants_lease_when_asked() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            let request = LeaseRequest {
+                holder: HOLDER.to_string(),
+                duration: Duration::from_secs(10),
+            };
+            let body = serde_json::to_vec(&request).unwrap().into();
+            register
+                .handle(transport::Message::announce(LEASE_PATH, body))
+                .await
+                .unwrap();
+            let holder: Uri = HOLDER.parse().unwrap();
+            let leases = register.leases.lock().unwrap();
+            assert!(leases.granted.contains_key(&holder));
+        }
+
+        #[tokio::test]
+        async fn rejects_lease_requests_without_body() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            let message = transport::Message::ask(LEASE_PATH);
+            assert!(register.handle(message).await.is_err());
+        }
+    }
+}
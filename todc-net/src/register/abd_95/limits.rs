@@ -0,0 +1,335 @@
+//! Limiting the requests that an instance serves, and the rate at which it
+//! sends them.
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use hyper::Uri;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use super::{AtomicRegister, LocalValue, Route};
+use crate::codec::Codec;
+use crate::limit::{ConcurrencyLimit, Limiter, Priority, RateLimit, Throttle};
+use crate::register::label::Label;
+use crate::transport::{self, Transport};
+use crate::GenericError;
+
+/// An announcement to a neighbor that is waiting for the rate limit of a
+/// register instance, along with every request waiting for its reply.
+pub(super) struct QueuedAnnouncement<T: Clone + Debug + Default + Ord + Send, L> {
+    /// The largest local value that was announced while waiting.
+    local: LocalValue<T, L>,
+    message: transport::Message,
+    replies: Vec<oneshot::Sender<Result<Bytes, String>>>,
+}
+
+/// The announcements waiting to be sent, by neighbor, and by whether they
+/// were streamed.
+pub(super) type Announcements<T, L> = HashMap<(Uri, bool), QueuedAnnouncement<T, L>>;
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Limits the number of requests to the given [`Route`] that this
+    /// instance, and its clones, handle concurrently.
+    ///
+    /// Requests beyond the limit wait in a queue, and requests that arrive
+    /// when the queue is full are answered with `503 Service Unavailable`.
+    /// Because neighbors treat such a response as a missing acknowledgement,
+    /// an overloaded instance behaves like one that is briefly offline, and
+    /// operations continue to succeed as long as a majority of instances are
+    /// not overloaded. Each route is limited separately, so that a flood of
+    /// requests to one route does not prevent an instance from serving the
+    /// others. See the [`limit`](crate::limit) module for details.
+    ///
+    /// Only requests served over HTTP are limited, and by default, no route
+    /// is limited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_net::limit::ConcurrencyLimit;
+    /// use todc_net::register::abd_95::{AtomicRegister, Route};
+    ///
+    /// let register: AtomicRegister<u32> = AtomicRegister::default()
+    ///     .with_concurrency_limit(Route::Local, ConcurrencyLimit::new(64).with_queue(256))
+    ///     .with_concurrency_limit(Route::Register, ConcurrencyLimit::new(16));
+    /// ```
+    pub fn with_concurrency_limit(mut self, route: Route, limit: ConcurrencyLimit) -> Self {
+        Arc::make_mut(&mut self.limits).insert(route, Limiter::new(limit));
+        self
+    }
+
+    /// Limits the rate at which this instance, and its clones, send requests
+    /// to their neighbors.
+    ///
+    /// Every request that an instance sends to a neighbor on its own behalf,
+    /// to announce or ask for values, to ask for or forward to leases, or to
+    /// gossip, waits for the limit, in order of the [`Priority`] of its
+    /// [`Route`]. See the [`limit`](crate::limit#rate-limits) module for
+    /// details.
+    ///
+    /// While an announcement to a neighbor is waiting, any other
+    /// announcement to the same neighbor is shed, and instead shares the
+    /// reply to the waiting one, which is sent with the larger of their
+    /// values. Since a neighbor that adopts a value acknowledges every
+    /// smaller one, shedding does not affect the atomicity of the register,
+    /// and an instance that falls behind sends at most one announcement to
+    /// each neighbor, rather than one for every operation.
+    ///
+    /// By default, requests are not limited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_net::limit::{Priority, RateLimit};
+    /// use todc_net::register::abd_95::{AtomicRegister, Route};
+    ///
+    /// let register: AtomicRegister<u32> = AtomicRegister::default()
+    ///     .with_rate_limit(RateLimit::new(1_000).with_burst(100))
+    ///     .with_priority(Route::Gossip, Priority::Low);
+    /// ```
+    pub fn with_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.throttle = Some(Throttle::new(limit));
+        self
+    }
+
+    /// Sets the [`Priority`] with which requests that this instance sends
+    /// to the given [`Route`] of its neighbors wait for its rate limit.
+    ///
+    /// Requests are sent to the [`Route::Local`], [`Route::Lease`] and
+    /// [`Route::Gossip`] routes, and by default, each has
+    /// [`Priority::Normal`]. Priorities have no effect unless a rate limit is
+    /// set with [`with_rate_limit`](AtomicRegister::with_rate_limit).
+    pub fn with_priority(mut self, route: Route, priority: Priority) -> Self {
+        Arc::make_mut(&mut self.priorities).insert(route, priority);
+        self
+    }
+
+    /// Returns the [`Priority`] of requests sent to the given route.
+    fn priority(&self, route: Route) -> Priority {
+        self.priorities.get(&route).copied().unwrap_or_default()
+    }
+
+    /// Sends a message to the given route of a neighbor, once the rate limit
+    /// of this instance allows it.
+    pub(super) async fn send(
+        &self,
+        route: Route,
+        neighbor: Uri,
+        message: transport::Message,
+    ) -> Result<Bytes, GenericError> {
+        if let Some(throttle) = &self.throttle {
+            throttle
+                .acquire(self.priority(route), self.clock.as_ref())
+                .await;
+        }
+        self.deliver(neighbor, message).await
+    }
+
+    /// Announces a local value to a neighbor, and returns its reply.
+    ///
+    /// If another announcement to the neighbor is waiting for the rate limit
+    /// of this instance, then this one is shed, and both share the reply to
+    /// whichever contains the larger value.
+    pub(super) async fn announce(
+        &self,
+        neighbor: Uri,
+        local: LocalValue<T, L>,
+        message: transport::Message,
+        streamed: bool,
+    ) -> Result<Bytes, GenericError> {
+        if self.throttle.is_none() {
+            return self.deliver(neighbor, message).await;
+        }
+        let (sender, reply) = oneshot::channel();
+        let key = (neighbor, streamed);
+        let first = {
+            let mut announcements = self.announcements.lock().unwrap();
+            match announcements.get_mut(&key) {
+                Some(queued) => {
+                    if local > queued.local {
+                        queued.local = local;
+                        queued.message = message;
+                    }
+                    queued.replies.push(sender);
+                    false
+                }
+                None => {
+                    let queued = QueuedAnnouncement {
+                        local,
+                        message,
+                        replies: vec![sender],
+                    };
+                    announcements.insert(key.clone(), queued);
+                    true
+                }
+            }
+        };
+        if first {
+            // The announcement is sent by its own task, so that it is sent
+            // even if the request that queued it is cancelled while others
+            // are still waiting for its reply.
+            let me = self.clone();
+            tokio::spawn(async move {
+                if let Some(throttle) = &me.throttle {
+                    throttle
+                        .acquire(me.priority(Route::Local), me.clock.as_ref())
+                        .await;
+                }
+                let queued = me.announcements.lock().unwrap().remove(&key).unwrap();
+                let (neighbor, _) = key;
+                let result = me
+                    .deliver(neighbor, queued.message)
+                    .await
+                    .map_err(|error| error.to_string());
+                for reply in queued.replies {
+                    let _ = reply.send(result.clone());
+                }
+            });
+        }
+        Ok(reply.await??)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::MockClock;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    mod with_rate_limit {
+        use super::*;
+
+        const NEIGHBOR: &str = "http://neighbor.com";
+
+        /// A transport that records every message that it sends, over
+        /// which neighbors acknowledge every announcement.
+        #[derive(Clone, Default)]
+        struct Recording {
+            sent: Arc<Mutex<Vec<transport::Message>>>,
+        }
+
+        impl Transport for Recording {
+            async fn send(
+                &self,
+                _: Uri,
+                message: transport::Message,
+            ) -> Result<Bytes, GenericError> {
+                self.sent.lock().unwrap().push(message.clone());
+                // Reply with the announced value as if it were adopted.
+                Ok(message.body.unwrap())
+            }
+        }
+
+        fn limited(transport: Recording, clock: &MockClock) -> AtomicRegister<u32, Recording> {
+            AtomicRegister::with_transport(vec![NEIGHBOR.parse().unwrap()], transport)
+                .with_clock(clock.clone())
+                .with_rate_limit(RateLimit::new(1).with_burst(1))
+        }
+
+        /// Lets spawned tasks run until each of them is waiting.
+        async fn settle() {
+            for _ in 0..10 {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        fn announced_labels(transport: &Recording) -> Vec<u64> {
+            let sent = transport.sent.lock().unwrap();
+            sent.iter()
+                .map(|message| {
+                    let local: LocalValue<u32> =
+                        serde_json::from_slice(message.body.as_ref().unwrap()).unwrap();
+                    local.label
+                })
+                .collect()
+        }
+
+        #[tokio::test]
+        async fn write_waits_for_rate_limit() {
+            let clock = MockClock::new();
+            let transport = Recording::default();
+            let register = limited(transport.clone(), &clock);
+            register.write(1).await.unwrap();
+
+            let write = tokio::spawn({
+                let register = register.clone();
+                async move { register.write(2).await }
+            });
+            settle().await;
+            assert!(!write.is_finished());
+
+            clock.advance(Duration::from_secs(1));
+            write.await.unwrap().unwrap();
+            assert_eq!(announced_labels(&transport), vec![1, 2]);
+        }
+
+        #[tokio::test]
+        async fn sheds_duplicate_announcements_to_same_neighbor() {
+            let clock = MockClock::new();
+            let transport = Recording::default();
+            let register = limited(transport.clone(), &clock);
+            register.write(1).await.unwrap();
+
+            // Both writes wait for the same announcement, which is sent
+            // with the value of the second.
+            let mut writes = Vec::new();
+            for value in [2, 3] {
+                let register = register.clone();
+                writes.push(tokio::spawn(async move { register.write(value).await }));
+                settle().await;
+            }
+            clock.advance(Duration::from_secs(1));
+            for write in writes {
+                write.await.unwrap().unwrap();
+            }
+            assert_eq!(announced_labels(&transport), vec![1, 3]);
+        }
+
+        #[tokio::test]
+        async fn sends_every_announcement_without_rate_limit() {
+            let transport = Recording::default();
+            let register: AtomicRegister<u32, Recording> =
+                AtomicRegister::with_transport(vec![NEIGHBOR.parse().unwrap()], transport.clone());
+            let (first, second) = tokio::join!(register.write(1), register.write(2));
+            first.unwrap();
+            second.unwrap();
+            assert_eq!(announced_labels(&transport).len(), 2);
+        }
+
+        #[tokio::test]
+        async fn gossip_waits_behind_requests_with_higher_priority() {
+            let clock = MockClock::new();
+            let transport = Recording::default();
+            let register =
+                limited(transport.clone(), &clock).with_priority(Route::Gossip, Priority::Low);
+            register.write(1).await.unwrap();
+
+            let gossip = tokio::spawn({
+                let register = register.clone();
+                async move { register.gossip().await }
+            });
+            settle().await;
+            let write = tokio::spawn({
+                let register = register.clone();
+                async move { register.write(2).await }
+            });
+            settle().await;
+
+            clock.advance(Duration::from_secs(1));
+            write.await.unwrap().unwrap();
+            assert!(!gossip.is_finished());
+            clock.advance(Duration::from_secs(1));
+            gossip.await.unwrap().unwrap();
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! Replacing the neighbors of an instance.
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use hyper::Uri;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{next_operation_id, AtomicRegister, Message, RegisterError};
+use crate::codec::Codec;
+use crate::register::label::Label;
+use crate::transport::Transport;
+use crate::GenericError;
+
+/// The joint configuration of a register instance that is being
+/// reconfigured, which it leaves when dropped.
+///
+/// See [`reconfigure`](AtomicRegister::reconfigure).
+struct JointConfiguration<'a> {
+    neighbors: &'a Mutex<Vec<Uri>>,
+    joining: &'a Mutex<Option<Vec<Uri>>>,
+}
+
+impl<'a> JointConfiguration<'a> {
+    /// Enters the joint configuration of the current neighbors of an
+    /// instance and the neighbors that it is joining.
+    fn enter<T, Tr, L, C>(register: &'a AtomicRegister<T, Tr, L, C>, joining: Vec<Uri>) -> Self
+    where
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    {
+        *register.joining.lock().unwrap() = Some(joining);
+        Self {
+            neighbors: &register.neighbors,
+            joining: &register.joining,
+        }
+    }
+
+    /// Installs the neighbors that the instance was joining, and leaves the
+    /// joint configuration.
+    fn install(self, neighbors: Vec<Uri>) {
+        *self.neighbors.lock().unwrap() = neighbors;
+    }
+}
+
+impl Drop for JointConfiguration<'_> {
+    fn drop(&mut self) {
+        // The neighbors are locked first, as when they are read.
+        let _neighbors = self.neighbors.lock().unwrap();
+        *self.joining.lock().unwrap() = None;
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Returns the sets of neighbors of which every operation must reach a
+    /// quorum: the current neighbors, and while this instance is being
+    /// reconfigured, the neighbors that it is joining.
+    pub(super) fn configurations(&self) -> Vec<Vec<Uri>> {
+        // The neighbors are locked first, here and wherever both are
+        // changed, so that the new neighbors are never seen to be installed
+        // before they are.
+        let neighbors = self.neighbors.lock().unwrap();
+        let joining = self.joining.lock().unwrap();
+        let mut configurations = vec![neighbors.clone()];
+        configurations.extend(joining.clone());
+        configurations
+    }
+
+    /// Replaces the neighbors of this register instance.
+    ///
+    /// The transition passes through a _joint_ configuration, in which every
+    /// operation of this instance, including the transition itself, must
+    /// reach a quorum of both the _old_ and the _new_ neighbors. To ensure
+    /// that no completed write is lost, the current value of the register is
+    /// read from, and then announced to, a joint quorum. Only once both
+    /// quorums have acknowledged it are the new neighbors installed, after
+    /// which operations only contact the new neighbors. Reconfigurations of
+    /// the same instance are performed one at a time.
+    ///
+    /// Each instance maintains its own set of neighbors, so growing or
+    /// shrinking a cluster requires calling this method (or making a `POST`
+    /// request to `/register/neighbors`) on every instance. Reconfiguring
+    /// different instances to disagreeing sets of neighbors may violate
+    /// atomicity.
+    ///
+    /// If either quorum cannot be reached, or the transition is cancelled,
+    /// then this instance keeps its old neighbors, and an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use hyper::Uri;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<u32> = AtomicRegister::default();
+    /// register.write(123).await.unwrap();
+    ///
+    /// // Shrinking a cluster down to a single instance.
+    /// register.reconfigure(Vec::new()).await.unwrap();
+    /// assert!(register.neighbors().is_empty());
+    /// assert_eq!(register.read().await.unwrap(), 123);
+    /// # })
+    /// ```
+    #[tracing::instrument(level = "debug", skip_all, fields(operation = next_operation_id()))]
+    pub async fn reconfigure(&self, neighbors: Vec<Uri>) -> Result<(), RegisterError> {
+        self.quorum.thresholds(&neighbors)?;
+        let _reconfiguring = self.reconfiguring.lock().await;
+
+        // Enter the joint configuration, which is left when the transition
+        // completes, fails, or is cancelled.
+        let joint = JointConfiguration::enter(self, neighbors.clone());
+
+        // Learn the most recent value known to a quorum of the old and new
+        // neighbors, and make sure that a quorum of both know about it.
+        let info = self.communicate(Message::Ask).await?;
+        let max = info.into_iter().max().unwrap();
+        self.update(max).await?;
+        self.communicate(Message::Announce).await?;
+
+        joint.install(neighbors);
+        Ok(())
+    }
+
+    /// Adds a neighbor to this register instance.
+    ///
+    /// Adding a neighbor that is already present has no effect on the set of
+    /// neighbors. See [`reconfigure`](AtomicRegister::reconfigure) for details
+    /// on how the transition is performed.
+    pub async fn add_neighbor(&self, neighbor: Uri) -> Result<(), RegisterError> {
+        let mut neighbors = self.neighbors();
+        if !neighbors.contains(&neighbor) {
+            neighbors.push(neighbor);
+        }
+        self.reconfigure(neighbors).await
+    }
+
+    /// Removes a neighbor from this register instance.
+    ///
+    /// Removing a neighbor that is not present has no effect on the set of
+    /// neighbors. See [`reconfigure`](AtomicRegister::reconfigure) for details
+    /// on how the transition is performed.
+    pub async fn remove_neighbor(&self, neighbor: &Uri) -> Result<(), RegisterError> {
+        let mut neighbors = self.neighbors();
+        neighbors.retain(|other| other != neighbor);
+        self.reconfigure(neighbors).await
+    }
+}
+
+/// Returns whether two lists contain the same neighbors, in any order.
+pub(super) fn same_neighbors(a: &[Uri], b: &[Uri]) -> bool {
+    let a: HashSet<&Uri> = a.iter().collect();
+    let b: HashSet<&Uri> = b.iter().collect();
+    a == b
+}
+
+/// Returns a list of neighbors as a JSON array of URLs.
+pub(super) fn neighbors_to_json(neighbors: Vec<Uri>) -> serde_json::Value {
+    neighbors.iter().map(|url| url.to_string()).collect()
+}
+
+/// Parses a list of neighbors from a JSON array of URLs.
+pub(super) fn neighbors_from_json(reader: impl std::io::Read) -> Result<Vec<Uri>, GenericError> {
+    let urls: Vec<String> = serde_json::from_reader(reader)?;
+    urls.iter()
+        .map(|url| url.parse().map_err(GenericError::from))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::memory::{self, MemoryTransport};
+
+    mod reconfigure {
+        use super::*;
+
+        #[tokio::test]
+        async fn preserves_local_value() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            register.write(123).await.unwrap();
+            register.reconfigure(Vec::new()).await.unwrap();
+            assert_eq!(123, register.read().await.unwrap())
+        }
+
+        fn old() -> Uri {
+            Uri::from_static("http://old.com")
+        }
+
+        fn new() -> Uri {
+            Uri::from_static("http://new.com")
+        }
+
+        #[tokio::test]
+        async fn contacts_old_and_new_neighbors() {
+            let (transport, mut inbox) = memory::channel();
+            let register: AtomicRegister<u32, MemoryTransport> =
+                AtomicRegister::with_transport(vec![old()], transport);
+            let reachable: AtomicRegister<u32> = AtomicRegister::default();
+            let reconfigure = register.reconfigure(vec![new()]);
+            let mut contacted = Vec::new();
+            inbox
+                .forward_until_with(&reachable, reconfigure, |envelope| {
+                    contacted.push(envelope.neighbor().clone());
+                    Some(envelope)
+                })
+                .await
+                .unwrap();
+            contacted.sort_by_key(Uri::to_string);
+            assert_eq!(contacted, vec![new(), new(), old(), old()]);
+            assert_eq!(register.neighbors(), vec![new()]);
+        }
+
+        #[tokio::test]
+        async fn keeps_old_neighbors_unless_new_quorum_acknowledges() {
+            let (transport, mut inbox) = memory::channel();
+            let register: AtomicRegister<u32, MemoryTransport> =
+                AtomicRegister::with_transport(vec![old()], transport);
+            let reachable: AtomicRegister<u32> = AtomicRegister::default();
+            let reconfigure = register.reconfigure(vec![new()]);
+            let error = inbox
+                .forward_until_with(&reachable, reconfigure, |envelope| {
+                    if envelope.neighbor() == &new() {
+                        envelope.fail("Neighbor crashed");
+                        None
+                    } else {
+                        Some(envelope)
+                    }
+                })
+                .await
+                .unwrap_err();
+            assert!(matches!(error, RegisterError::QuorumUnavailable { .. }));
+            assert_eq!(register.neighbors(), vec![old()]);
+            assert_eq!(register.configurations(), vec![vec![old()]]);
+        }
+
+        #[tokio::test]
+        async fn keeps_old_neighbors_unless_old_quorum_acknowledges() {
+            let (transport, mut inbox) = memory::channel();
+            let register: AtomicRegister<u32, MemoryTransport> =
+                AtomicRegister::with_transport(vec![old()], transport);
+            let reachable: AtomicRegister<u32> = AtomicRegister::default();
+            let reconfigure = register.reconfigure(vec![new()]);
+            let error = inbox
+                .forward_until_with(&reachable, reconfigure, |envelope| {
+                    if envelope.neighbor() == &old() {
+                        envelope.fail("Neighbor crashed");
+                        None
+                    } else {
+                        Some(envelope)
+                    }
+                })
+                .await
+                .unwrap_err();
+            assert!(matches!(error, RegisterError::QuorumUnavailable { .. }));
+            assert_eq!(register.neighbors(), vec![old()]);
+        }
+
+        #[tokio::test]
+        async fn operations_reach_both_quorums_during_reconfiguration() {
+            let (transport, mut inbox) = memory::channel();
+            let register: AtomicRegister<u32, MemoryTransport> =
+                AtomicRegister::with_transport(vec![old()], transport);
+            let (result, ()) = tokio::join!(register.reconfigure(vec![new()]), async {
+                let envelope = inbox.recv().await.unwrap();
+                assert_eq!(register.configurations(), vec![vec![old()], vec![new()]]);
+                envelope.fail("Neighbor crashed");
+            });
+            assert!(result.is_err());
+        }
+
+        #[tokio::test]
+        async fn leaves_joint_configuration_if_cancelled() {
+            let (transport, mut inbox) = memory::channel();
+            let register: AtomicRegister<u32, MemoryTransport> =
+                AtomicRegister::with_transport(vec![old()], transport);
+            let mut reconfigure = Box::pin(register.reconfigure(vec![new()]));
+            tokio::select! {
+                _ = &mut reconfigure => unreachable!(),
+                _ = inbox.recv() => {}
+            }
+            assert_eq!(register.configurations().len(), 2);
+            drop(reconfigure);
+            assert_eq!(register.configurations(), vec![vec![old()]]);
+        }
+
+        #[tokio::test]
+        async fn remove_neighbor_ignores_missing_neighbor() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            let neighbor = Uri::from_static("http://test.com");
+            register.remove_neighbor(&neighbor).await.unwrap();
+            assert!(register.neighbors().is_empty());
+        }
+    }
+
+    mod neighbors_from_json {
+        use super::*;
+
+        #[test]
+        fn parses_array_of_urls() {
+            let json = r#"["http://a.com:80", "http://b.com:80"]"#;
+            let neighbors = neighbors_from_json(json.as_bytes()).unwrap();
+            assert_eq!(
+                neighbors,
+                vec![
+                    Uri::from_static("http://a.com:80"),
+                    Uri::from_static("http://b.com:80")
+                ]
+            );
+        }
+
+        #[test]
+        fn rejects_invalid_urls() {
+            let json = r#"["not a url"]"#;
+            assert!(neighbors_from_json(json.as_bytes()).is_err());
+        }
+    }
+}
@@ -0,0 +1,493 @@
+//! Reporting the status of an instance and its neighbors.
+use std::fmt::Debug;
+use std::time::Duration;
+
+use hyper::Uri;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::time::Instant;
+
+use super::AtomicRegister;
+use crate::codec::Codec;
+use crate::register::label::Label;
+use crate::transport::Transport;
+
+/// The status of a register instance, as reported at `/register/status`.
+///
+/// See [`AtomicRegister::status`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Status<L = u64> {
+    /// The label associated with the local value of the instance.
+    pub label: L,
+    /// The status of each of the current neighbors of the instance.
+    pub neighbors: Vec<NeighborStatus<L>>,
+    /// Whether the instance, along with the neighbors that are reachable,
+    /// forms a majority.
+    pub quorum_reachable: bool,
+    /// Statistics about the reads performed by the instance.
+    pub reads: ReadStats,
+    /// The latest version of the protocol in which the instance writes
+    /// messages to its neighbors. See
+    /// [Protocol Versions](super#protocol-versions).
+    pub protocol_version: u32,
+}
+
+/// The status of a neighbor of a register instance, based on the most recent
+/// attempts of the instance to communicate with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NeighborStatus<L = u64> {
+    /// The URL of the neighbor.
+    pub url: Uri,
+    /// The time since the neighbor last replied to this instance, or `None`
+    /// if it never has.
+    pub last_contact: Option<Duration>,
+    /// Whether the most recent attempt to communicate with the neighbor
+    /// succeeded. Neighbors that have not yet been contacted are assumed to
+    /// be reachable.
+    pub reachable: bool,
+    /// The largest label that the neighbor has replied with, or `None` if it
+    /// has not replied with one.
+    pub label: Option<L>,
+    /// An estimate of the number of labels by which the local value of the
+    /// neighbor trails that of this instance, based on `label`, or `None` if
+    /// it cannot be estimated. See [`Label::lag`].
+    pub lag: Option<u64>,
+    /// An exponentially weighted moving average of the time that the
+    /// neighbor takes to reply, or `None` if it has never replied. See
+    /// [Hedging](super#hedging).
+    pub latency: Option<Duration>,
+    /// The version of the protocol in which the instance writes messages to
+    /// the neighbor. See [Protocol Versions](super#protocol-versions).
+    pub protocol_version: u32,
+}
+
+/// Statistics about the reads performed by a register instance.
+///
+/// Only reads that contact a majority of instances are counted, so reads
+/// served locally, such as those made while holding a lease, are not.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadStats {
+    /// The number of reads that adopted a newer value from a neighbor,
+    /// repairing the local value of this instance.
+    pub repaired: u64,
+    /// The number of reads for which the local value of this instance was
+    /// already the largest.
+    pub current: u64,
+}
+
+/// The weight given to each reply when updating the moving average of the
+/// time that a neighbor takes to reply.
+const LATENCY_WEIGHT: f64 = 0.2;
+
+/// The outcome of the communication of a register instance with a neighbor.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Contact<L> {
+    /// The last time at which the neighbor replied, if ever.
+    last_success: Option<Instant>,
+    /// Whether the most recent attempt to communicate with the neighbor
+    /// succeeded.
+    reachable: bool,
+    /// The largest label that the neighbor has replied with, if any.
+    label: Option<L>,
+    /// A moving average of the time that the neighbor takes to reply, if it
+    /// has ever replied.
+    latency: Option<Duration>,
+    /// The version of the protocol negotiated with the neighbor, if it has
+    /// rejected a message written in another.
+    pub(super) version: Option<u32>,
+}
+
+impl<L> Default for Contact<L> {
+    /// Returns the contact with a neighbor that has not been contacted yet.
+    fn default() -> Self {
+        Self {
+            last_success: None,
+            reachable: true,
+            label: None,
+            latency: None,
+            version: None,
+        }
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Returns the [`Status`] of this register instance.
+    ///
+    /// The status is based only on the outcome of the most recent attempts
+    /// of this instance to communicate with each of its neighbors, during
+    /// operations or rounds of [`gossip`](AtomicRegister::gossip), and so
+    /// does not send any messages itself. A neighbor that has not yet been
+    /// contacted is assumed to be reachable, so that a new instance reports
+    /// that a quorum is reachable until it learns otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// let neighbor = Uri::from_static("https://my-register-2.com");
+    /// let register: AtomicRegister<u32> = AtomicRegister::new(vec![neighbor.clone()]);
+    /// let status = register.status();
+    /// assert_eq!(status.neighbors[0].url, neighbor);
+    /// assert_eq!(status.neighbors[0].last_contact, None);
+    /// assert!(status.quorum_reachable);
+    /// ```
+    pub fn status(&self) -> Status<L> {
+        let now = self.clock.now();
+        let label = self.local.lock().unwrap().label;
+        let contacts = self.contacts.lock().unwrap();
+        let neighbors: Vec<NeighborStatus<L>> = self
+            .neighbors()
+            .into_iter()
+            .map(|url| {
+                let contact = contacts.get(&url).copied().unwrap_or_default();
+                NeighborStatus {
+                    last_contact: contact
+                        .last_success
+                        .map(|last| now.saturating_duration_since(last)),
+                    reachable: contact.reachable,
+                    label: contact.label,
+                    lag: contact.label.and_then(|other| other.lag(&label)),
+                    latency: contact.latency,
+                    protocol_version: contact.version.unwrap_or(self.protocol),
+                    url,
+                }
+            })
+            .collect();
+        // This instance is always reachable by itself.
+        let reachable = self.quorum.weight()
+            + neighbors
+                .iter()
+                .filter(|n| n.reachable)
+                .map(|n| self.quorum.weight_of(&n.url))
+                .sum::<usize>();
+        let thresholds = self
+            .quorum
+            .thresholds(&self.neighbors())
+            .expect("neighbors are only installed if they satisfy the quorum policy");
+        Status {
+            label,
+            quorum_reachable: thresholds.is_available(reachable),
+            neighbors,
+            reads: *self.reads.lock().unwrap(),
+            protocol_version: self.protocol,
+        }
+    }
+
+    /// Records the outcome of an attempt to communicate with a neighbor.
+    pub(super) fn record_contact(&self, neighbor: Uri, succeeded: bool) {
+        let now = self.clock.now();
+        let mut contacts = self.contacts.lock().unwrap();
+        let contact = contacts.entry(neighbor).or_default();
+        contact.reachable = succeeded;
+        if succeeded {
+            contact.last_success = Some(now);
+        }
+    }
+
+    /// Records the time that a neighbor took to reply successfully.
+    pub(super) fn record_latency(&self, neighbor: Uri, elapsed: Duration) {
+        let mut contacts = self.contacts.lock().unwrap();
+        let contact = contacts.entry(neighbor).or_default();
+        contact.latency = Some(match contact.latency {
+            Some(average) => {
+                average.mul_f64(1.0 - LATENCY_WEIGHT) + elapsed.mul_f64(LATENCY_WEIGHT)
+            }
+            None => elapsed,
+        });
+    }
+
+    /// Records the label that a neighbor replied with.
+    pub(super) fn record_label(&self, neighbor: Uri, label: L) {
+        let mut contacts = self.contacts.lock().unwrap();
+        let contact = contacts.entry(neighbor).or_default();
+        // Replies may arrive out of order, but the local value of the
+        // neighbor only ever grows.
+        contact.label = contact.label.max(Some(label));
+    }
+}
+
+/// Returns the status of an instance as a JSON object, in which the time
+/// since each neighbor was last contacted is given in milliseconds.
+pub(super) fn status_to_json<L: Label>(
+    status: Status<L>,
+) -> Result<serde_json::Value, serde_json::Error> {
+    let neighbors = status
+        .neighbors
+        .into_iter()
+        .map(|neighbor| {
+            Ok(serde_json::json!({
+                "url": neighbor.url.to_string(),
+                "last_contact_ms": neighbor.last_contact.map(|since| since.as_millis() as u64),
+                "reachable": neighbor.reachable,
+                "label": serde_json::to_value(neighbor.label)?,
+                "lag": neighbor.lag,
+                "latency_ms": neighbor.latency.map(|latency| latency.as_secs_f64() * 1000.0),
+                "protocol_version": neighbor.protocol_version,
+            }))
+        })
+        .collect::<Result<Vec<_>, serde_json::Error>>()?;
+    Ok(serde_json::json!({
+        "label": serde_json::to_value(status.label)?,
+        "neighbors": neighbors,
+        "quorum_reachable": status.quorum_reachable,
+        "protocol_version": status.protocol_version,
+        "reads": {
+            "repaired": status.reads.repaired,
+            "current": status.reads.current,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::abd_95::tests::Unreachable;
+    use crate::register::abd_95::{LocalValue, ReadConsistency};
+    use crate::time::MockClock;
+    use crate::transport;
+    use crate::transport::memory::{self, MemoryTransport};
+    use crate::GenericError;
+    use bytes::Bytes;
+
+    mod status {
+        use super::*;
+
+        /// A transport over which every neighbor replies with the value
+        /// announced to it, as if it had adopted it.
+        #[derive(Clone, Default)]
+        struct Echo;
+
+        impl Transport for Echo {
+            async fn send(
+                &self,
+                _: Uri,
+                message: transport::Message,
+            ) -> Result<Bytes, GenericError> {
+                Ok(message.body.unwrap())
+            }
+        }
+
+        /// A transport over which every neighbor replies with a value
+        /// that is newer than the initial value.
+        #[derive(Clone, Default)]
+        struct Ahead;
+
+        impl Transport for Ahead {
+            async fn send(&self, _: Uri, _: transport::Message) -> Result<Bytes, GenericError> {
+                let value = LocalValue {
+                    label: 5,
+                    value: 123,
+                };
+                Ok(serde_json::to_vec(&value)?.into())
+            }
+        }
+
+        /// A transport over which every neighbor replies with the
+        /// initial value, as if it ignored every announcement.
+        #[derive(Clone, Default)]
+        struct Behind;
+
+        impl Transport for Behind {
+            async fn send(&self, _: Uri, _: transport::Message) -> Result<Bytes, GenericError> {
+                let value: LocalValue<u32> = LocalValue::default();
+                Ok(serde_json::to_vec(&value)?.into())
+            }
+        }
+
+        fn neighbors() -> Vec<Uri> {
+            vec![
+                Uri::from_static("http://neighbor-1.com"),
+                Uri::from_static("http://neighbor-2.com"),
+            ]
+        }
+
+        #[tokio::test]
+        async fn reports_label_of_local_value() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            register.write(123).await.unwrap();
+            let status = register.status();
+            assert_eq!(status.label, 1);
+            assert!(status.neighbors.is_empty());
+            assert!(status.quorum_reachable);
+        }
+
+        #[test]
+        fn assumes_neighbors_that_were_not_contacted_are_reachable() {
+            let register: AtomicRegister<u32, Unreachable> =
+                AtomicRegister::with_transport(neighbors(), Unreachable);
+            let status = register.status();
+            assert!(status.neighbors.iter().all(|neighbor| neighbor.reachable));
+            assert!(status.quorum_reachable);
+        }
+
+        #[tokio::test]
+        async fn reports_quorum_unreachable_after_failed_operation() {
+            let register: AtomicRegister<u32, Unreachable> =
+                AtomicRegister::with_transport(neighbors(), Unreachable);
+            assert!(register.write(123).await.is_err());
+            let status = register.status();
+            for neighbor in status.neighbors {
+                assert!(!neighbor.reachable);
+                assert_eq!(neighbor.last_contact, None);
+            }
+            assert!(!status.quorum_reachable);
+        }
+
+        #[tokio::test]
+        async fn reports_time_since_last_contact() {
+            let clock = MockClock::new();
+            let neighbor = Uri::from_static("http://neighbor-1.com");
+            let register: AtomicRegister<u32, Echo> =
+                AtomicRegister::with_transport(vec![neighbor.clone()], Echo)
+                    .with_clock(clock.clone());
+            register.gossip().await.unwrap();
+            clock.advance(Duration::from_secs(3));
+            let status = register.status();
+            assert_eq!(
+                status.neighbors,
+                vec![NeighborStatus {
+                    url: neighbor,
+                    last_contact: Some(Duration::from_secs(3)),
+                    reachable: true,
+                    label: Some(0),
+                    lag: Some(0),
+                    latency: Some(Duration::ZERO),
+                    protocol_version: 1,
+                }]
+            );
+        }
+
+        #[tokio::test]
+        async fn counts_repaired_and_current_reads() {
+            let register: AtomicRegister<u32, Ahead> =
+                AtomicRegister::with_transport(neighbors(), Ahead);
+            register.read().await.unwrap();
+            register.read().await.unwrap();
+            assert_eq!(
+                register.status().reads,
+                ReadStats {
+                    repaired: 1,
+                    current: 1
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn does_not_count_local_reads() {
+            let register: AtomicRegister<u32, Ahead> =
+                AtomicRegister::with_transport(neighbors(), Ahead);
+            register
+                .read_with_consistency(ReadConsistency::Local)
+                .await
+                .unwrap();
+            assert_eq!(register.status().reads, ReadStats::default());
+        }
+
+        #[tokio::test]
+        async fn reports_average_latency_of_neighbors() {
+            let clock = MockClock::new();
+            let (transport, mut inbox) = memory::channel();
+            let neighbor = Uri::from_static("http://neighbor-1.com");
+            let register: AtomicRegister<u32, MemoryTransport> =
+                AtomicRegister::with_transport(vec![neighbor], transport).with_clock(clock.clone());
+            for millis in [10, 20] {
+                let (result, ()) = tokio::join!(register.gossip(), async {
+                    let envelope = inbox.recv().await.unwrap();
+                    clock.advance(Duration::from_millis(millis));
+                    let value: LocalValue<u32> = LocalValue::default();
+                    envelope.reply(serde_json::to_vec(&value).unwrap());
+                });
+                result.unwrap();
+            }
+            // The second reply is given a fifth of the weight.
+            let latency = register.status().neighbors[0].latency;
+            assert_eq!(latency, Some(Duration::from_millis(12)));
+        }
+
+        #[tokio::test]
+        async fn reports_lag_of_neighbors() {
+            let register: AtomicRegister<u32, Behind> =
+                AtomicRegister::with_transport(neighbors(), Behind);
+            register.write(1).await.unwrap();
+            register.write(2).await.unwrap();
+            for neighbor in register.status().neighbors {
+                assert_eq!(neighbor.label, Some(0));
+                assert_eq!(neighbor.lag, Some(2));
+            }
+        }
+    }
+
+    mod status_to_json {
+        use super::*;
+
+        #[test]
+        fn reports_time_since_last_contact_in_millis() {
+            let status = Status {
+                label: 3_u64,
+                neighbors: vec![
+                    NeighborStatus {
+                        url: Uri::from_static("http://a.com"),
+                        last_contact: Some(Duration::from_millis(120)),
+                        reachable: true,
+                        label: Some(2),
+                        lag: Some(1),
+                        latency: Some(Duration::from_micros(1500)),
+                        protocol_version: 1,
+                    },
+                    NeighborStatus {
+                        url: Uri::from_static("http://b.com"),
+                        last_contact: None,
+                        reachable: false,
+                        label: None,
+                        lag: None,
+                        latency: None,
+                        protocol_version: 1,
+                    },
+                ],
+                quorum_reachable: true,
+                protocol_version: 1,
+                reads: ReadStats {
+                    repaired: 2,
+                    current: 40,
+                },
+            };
+            assert_eq!(
+                status_to_json(status).unwrap(),
+                serde_json::json!({
+                    "label": 3,
+                    "neighbors": [
+                        {
+                            "url": "http://a.com/",
+                            "last_contact_ms": 120,
+                            "reachable": true,
+                            "label": 2,
+                            "lag": 1,
+                            "latency_ms": 1.5,
+                            "protocol_version": 1,
+                        },
+                        {
+                            "url": "http://b.com/",
+                            "last_contact_ms": null,
+                            "reachable": false,
+                            "label": null,
+                            "lag": null,
+                            "latency_ms": null,
+                            "protocol_version": 1,
+                        },
+                    ],
+                    "quorum_reachable": true,
+                    "protocol_version": 1,
+                    "reads": {"repaired": 2, "current": 40},
+                })
+            );
+        }
+    }
+}
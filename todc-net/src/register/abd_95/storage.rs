@@ -0,0 +1,282 @@
+//! Persisting the local value of an instance.
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{AtomicRegister, LocalValue, RegisterError};
+use crate::codec::Codec;
+use crate::register::label::Label;
+use crate::storage::Storage;
+use crate::transport::Transport;
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Persists the local value of this instance in the given [`Storage`],
+    /// and recovers the local value that was most recently stored there, if
+    /// any.
+    ///
+    /// Whenever the local value changes, it is written to storage before
+    /// any other instance is told about it, including before an announcement
+    /// from another instance is acknowledged. As a result, an instance that
+    /// restarts with the same storage never forgets a value that it has
+    /// acknowledged, and atomicity is preserved even if instances crash and
+    /// recover. See the [`storage`](crate::storage) module for details.
+    ///
+    /// Clones of this instance that were created before calling this method
+    /// do not persist their local value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored state cannot be loaded, or is not a
+    /// valid local value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::AtomicRegister;
+    /// use todc_net::storage::MemoryStorage;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let storage = MemoryStorage::new();
+    /// let register: AtomicRegister<u32> = AtomicRegister::default()
+    ///     .with_storage(storage.clone())
+    ///     .unwrap();
+    /// register.write(123).await.unwrap();
+    ///
+    /// // An instance that restarts with the same storage recovers its value.
+    /// let restarted: AtomicRegister<u32> = AtomicRegister::default()
+    ///     .with_storage(storage)
+    ///     .unwrap();
+    /// assert_eq!(restarted.read().await.unwrap(), 123);
+    /// # })
+    /// ```
+    pub fn with_storage(mut self, storage: impl Storage) -> Result<Self, RegisterError> {
+        if let Some(state) = storage.load().map_err(RegisterError::Storage)? {
+            let stored: LocalValue<T, L> = serde_json::from_slice(&state)?;
+            self.local = Arc::new(Mutex::new(stored));
+        }
+        self.storage = Some(Arc::new(storage));
+        Ok(self)
+    }
+
+    /// Updates the local value of this register instance, and returns the
+    /// result.
+    ///
+    /// If this instance has storage, then a new local value is persisted
+    /// before it replaces the old one. Updates are persisted one at a time,
+    /// so that the stored values only ever increase, but on a blocking
+    /// thread and without holding the lock on the local value, so that
+    /// neither other tasks nor readers of the local value wait for storage.
+    pub(super) async fn update(
+        &self,
+        other: LocalValue<T, L>,
+    ) -> Result<LocalValue<T, L>, RegisterError> {
+        let Some(storage) = &self.storage else {
+            let mut local = self.local.lock().unwrap();
+            if other > *local {
+                *local = other
+            };
+            return Ok(local.clone());
+        };
+        // While this lock is held, only this task changes the local value.
+        let _persisting = self.persisting.lock().await;
+        let local = self.local.lock().unwrap().clone();
+        if other <= local {
+            return Ok(local);
+        }
+        let state = serde_json::to_vec(&other)?;
+        let storage = storage.clone();
+        tokio::task::spawn_blocking(move || storage.store(&state))
+            .await
+            .map_err(|error| RegisterError::Storage(error.into()))?
+            .map_err(RegisterError::Storage)?;
+        *self.local.lock().unwrap() = other.clone();
+        Ok(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::abd_95::LOCAL_PATH;
+    use crate::transport::{self, Handler};
+    use crate::GenericError;
+    use tokio::sync::oneshot;
+
+    mod with_storage {
+        use super::*;
+        use crate::storage::MemoryStorage;
+
+        fn stored(storage: &MemoryStorage) -> Option<LocalValue<u32>> {
+            storage
+                .load()
+                .unwrap()
+                .map(|state| serde_json::from_slice(&state).unwrap())
+        }
+
+        #[tokio::test]
+        async fn recovers_local_value_after_restart() {
+            let storage = MemoryStorage::new();
+            let register: AtomicRegister<u32> = AtomicRegister::default()
+                .with_storage(storage.clone())
+                .unwrap();
+            register.write(123).await.unwrap();
+            drop(register);
+
+            let restarted: AtomicRegister<u32> =
+                AtomicRegister::default().with_storage(storage).unwrap();
+            let local = restarted.local.lock().unwrap();
+            assert_eq!(
+                *local,
+                LocalValue {
+                    label: 1,
+                    value: 123
+                }
+            );
+        }
+
+        #[tokio::test]
+        async fn persists_announced_value_before_acknowledging() {
+            let storage = MemoryStorage::new();
+            let register: AtomicRegister<u32> = AtomicRegister::default()
+                .with_storage(storage.clone())
+                .unwrap();
+            let other = LocalValue {
+                label: 1,
+                value: 123,
+            };
+            let body = serde_json::to_vec(&other).unwrap().into();
+            register
+                .handle(transport::Message::announce(LOCAL_PATH, body))
+                .await
+                .unwrap();
+            assert_eq!(stored(&storage), Some(other));
+        }
+
+        #[tokio::test]
+        async fn does_not_persist_unchanged_value() {
+            let storage = MemoryStorage::new();
+            let register: AtomicRegister<u32> = AtomicRegister::default()
+                .with_storage(storage.clone())
+                .unwrap();
+            register.update(LocalValue::default()).await.unwrap();
+            assert_eq!(stored(&storage), None);
+        }
+
+        /// A storage that blocks each write until it is released.
+        struct Gated {
+            entered: Mutex<Option<oneshot::Sender<()>>>,
+            release: Mutex<std::sync::mpsc::Receiver<()>>,
+        }
+
+        impl Storage for Gated {
+            fn load(&self) -> Result<Option<Vec<u8>>, GenericError> {
+                Ok(None)
+            }
+
+            fn store(&self, _: &[u8]) -> Result<(), GenericError> {
+                if let Some(entered) = self.entered.lock().unwrap().take() {
+                    let _ = entered.send(());
+                }
+                self.release.lock().unwrap().recv()?;
+                Ok(())
+            }
+        }
+
+        #[tokio::test]
+        async fn does_not_lock_local_value_while_persisting() {
+            let (entered, stored) = oneshot::channel();
+            let (release, gate) = std::sync::mpsc::channel();
+            let storage = Gated {
+                entered: Mutex::new(Some(entered)),
+                release: Mutex::new(gate),
+            };
+            let register: AtomicRegister<u32> =
+                AtomicRegister::default().with_storage(storage).unwrap();
+            let other = LocalValue {
+                label: 1,
+                value: 123,
+            };
+            let update = tokio::spawn({
+                let register = register.clone();
+                let other = other.clone();
+                async move { register.update(other).await }
+            });
+            stored.await.unwrap();
+            // While the value is being persisted, the local value can
+            // still be read, and does not change until it is durable.
+            assert_eq!(*register.local.try_lock().unwrap(), LocalValue::default());
+            release.send(()).unwrap();
+            assert_eq!(update.await.unwrap().unwrap(), other);
+            assert_eq!(*register.local.lock().unwrap(), other);
+        }
+
+        #[test]
+        fn rejects_invalid_stored_state() {
+            let storage = MemoryStorage::new();
+            storage.store(b"not a local value").unwrap();
+            let result: Result<AtomicRegister<u32>, _> =
+                AtomicRegister::default().with_storage(storage);
+            assert!(matches!(result, Err(RegisterError::Serialization(_))));
+        }
+    }
+
+    mod update {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_current_local_value() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            let other = LocalValue {
+                value: 123,
+                label: 123,
+            };
+            let local = register.update(other.clone()).await.unwrap();
+            assert_eq!(other, local);
+        }
+
+        #[tokio::test]
+        async fn changes_local_value_if_other_label_is_larger() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            register
+                .update(LocalValue {
+                    value: 123,
+                    label: 123,
+                })
+                .await
+                .unwrap();
+            let local = register.local.lock().unwrap();
+            assert_eq!(local.value, 123);
+            assert_eq!(local.label, 123);
+        }
+
+        #[tokio::test]
+        async fn leaves_local_value_alone_other_label_is_smaller() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            // Update local to have non-zero label
+            register
+                .update(LocalValue {
+                    value: 123,
+                    label: 123,
+                })
+                .await
+                .unwrap();
+            // Update again with smaller label
+            register
+                .update(LocalValue { value: 1, label: 1 })
+                .await
+                .unwrap();
+            let local = register.local.lock().unwrap();
+            assert_eq!(local.value, 123);
+            assert_eq!(local.label, 123);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Announcing values with their label sent separately, so that they can be
+//! streamed.
+use std::fmt::Debug;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::AtomicRegister;
+use crate::codec::Codec;
+use crate::register::label::Label;
+use crate::transport::Transport;
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Sets whether this instance announces values to its neighbors with
+    /// their label sent separately, so that the value itself can be streamed.
+    ///
+    /// When enabled, each announcement contains only the encoded value, which
+    /// is encoded once and shared by the requests to every neighbor. An
+    /// [`HttpTransport`](crate::transport::HttpTransport) sends the label in a
+    /// header, and streams the value in chunks along with its digest, which
+    /// the neighbor checks before adopting it. Neighbors acknowledge such
+    /// announcements with only their label, rather than their entire local
+    /// value. See the [`transport`](crate::transport#streaming) module for
+    /// details.
+    ///
+    /// Instances accept streamed announcements whether or not they stream
+    /// their own, so streaming can be enabled on some instances and not
+    /// others. By default, streaming is disabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let register: AtomicRegister<Vec<u8>> = AtomicRegister::default().with_streaming(true);
+    /// register.write(vec![0; 1 << 20]).await.unwrap();
+    /// assert_eq!(register.read().await.unwrap().len(), 1 << 20);
+    /// # })
+    /// ```
+    pub fn with_streaming(mut self, enabled: bool) -> Self {
+        self.streaming = enabled;
+        self
+    }
+
+    /// Returns whether this instance streams the values that it announces.
+    pub fn is_streaming(&self) -> bool {
+        self.streaming
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod with_streaming {
+        use super::*;
+
+        #[test]
+        fn is_disabled_by_default() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            assert!(!register.is_streaming());
+        }
+
+        #[tokio::test]
+        async fn writes_and_reads_without_neighbors() {
+            let register: AtomicRegister<u32> = AtomicRegister::default().with_streaming(true);
+            register.write(123).await.unwrap();
+            assert_eq!(register.read().await.unwrap(), 123);
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Negotiating the version of the protocol in which messages are written.
+use std::fmt::Debug;
+use std::ops::RangeInclusive;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::header::HeaderMap;
+use hyper::http::StatusCode;
+use hyper::{Response, Uri};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::{AtomicRegister, RegisterError, PROTOCOL_VERSIONS};
+use crate::codec::Codec;
+use crate::register::label::Label;
+use crate::transport::http::{VERSIONS_HEADER, VERSION_HEADER};
+use crate::transport::{self, Transport, UnsupportedVersion};
+use crate::{mk_response, GenericError};
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+        L: Label,
+        C: Codec,
+    > AtomicRegister<T, Tr, L, C>
+{
+    /// Sets the latest version of the protocol in which this instance writes
+    /// the messages that it sends to its neighbors. See
+    /// [Protocol Versions](super#protocol-versions).
+    ///
+    /// By default, instances write messages in the latest version of
+    /// [`PROTOCOL_VERSIONS`], and negotiate an earlier version with neighbors
+    /// that do not understand it. Pinning an earlier version holds back a new
+    /// one until every instance understands it, and saves each neighbor that
+    /// has not been upgraded from rejecting the first message it recieves.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegisterError::UnsupportedProtocol`] if the version is not
+    /// one of [`PROTOCOL_VERSIONS`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_net::register::abd_95::PROTOCOL_VERSIONS;
+    /// use todc_net::register::AtomicRegister;
+    ///
+    /// let register: AtomicRegister<u32> = AtomicRegister::default()
+    ///     .with_protocol_version(*PROTOCOL_VERSIONS.start())
+    ///     .unwrap();
+    /// assert_eq!(register.protocol_version(), 1);
+    /// assert!(AtomicRegister::<u32>::default().with_protocol_version(0).is_err());
+    /// ```
+    pub fn with_protocol_version(mut self, version: u32) -> Result<Self, RegisterError> {
+        self.protocol = Self::check_version(Some(version))?;
+        Ok(self)
+    }
+
+    /// Returns the latest version of the protocol in which this instance
+    /// writes the messages that it sends to its neighbors.
+    pub fn protocol_version(&self) -> u32 {
+        self.protocol
+    }
+
+    /// Returns the version of the protocol in which a message was written,
+    /// or an error if this instance does not understand it.
+    ///
+    /// Messages without a version were sent by instances that predate
+    /// versioning, and are written in the first version.
+    pub(super) fn check_version(version: Option<u32>) -> Result<u32, UnsupportedVersion> {
+        let version = version.unwrap_or(*PROTOCOL_VERSIONS.start());
+        if PROTOCOL_VERSIONS.contains(&version) {
+            Ok(version)
+        } else {
+            Err(UnsupportedVersion {
+                version,
+                supported: PROTOCOL_VERSIONS,
+            })
+        }
+    }
+
+    /// Returns the version of the protocol in which this instance writes
+    /// messages to a neighbor.
+    fn version_for(&self, neighbor: &Uri) -> u32 {
+        let contacts = self.contacts.lock().unwrap();
+        let contact = contacts.get(neighbor).copied().unwrap_or_default();
+        contact.version.unwrap_or(self.protocol)
+    }
+
+    /// Records the versions of the protocol that a neighbor understands, and
+    /// returns the latest of those that this instance also writes, or `None`
+    /// if there are none.
+    fn negotiate_version(&self, neighbor: Uri, supported: &RangeInclusive<u32>) -> Option<u32> {
+        let version = self.protocol.min(*supported.end());
+        if version < *PROTOCOL_VERSIONS.start().max(supported.start()) {
+            return None;
+        }
+        let mut contacts = self.contacts.lock().unwrap();
+        contacts.entry(neighbor).or_default().version = Some(version);
+        Some(version)
+    }
+
+    /// Sends a message to a neighbor, written in the latest version of the
+    /// protocol that both understand, and returns its reply.
+    ///
+    /// If the neighbor rejects the version of the message, and advertises
+    /// the versions that it does understand, then the message is sent again
+    /// in the latest of those that this instance also writes, which is used
+    /// for every later message to the neighbor. See
+    /// [Protocol Versions](super#protocol-versions).
+    pub(super) async fn deliver(
+        &self,
+        neighbor: Uri,
+        mut message: transport::Message,
+    ) -> Result<Bytes, GenericError> {
+        let version = self.version_for(&neighbor);
+        message.version = Some(version);
+        let error = match self.transport.send(neighbor.clone(), message.clone()).await {
+            Ok(reply) => return Ok(reply),
+            Err(error) => error,
+        };
+        let Some(rejected) = error.downcast_ref::<UnsupportedVersion>() else {
+            return Err(error);
+        };
+        match self.negotiate_version(neighbor.clone(), &rejected.supported) {
+            Some(negotiated) if negotiated != version => {
+                tracing::debug!(%neighbor, version = negotiated, "negotiated protocol version");
+                message.version = Some(negotiated);
+                self.transport.send(neighbor, message).await
+            }
+            _ => Err(error),
+        }
+    }
+
+    /// Returns the response with which a request from a neighbor is
+    /// rejected, if the protocol version sent in its headers is invalid or
+    /// not understood by this instance.
+    pub(super) fn reject_version(
+        headers: &HeaderMap,
+    ) -> Option<Result<Response<Full<Bytes>>, GenericError>> {
+        let version = headers.get(VERSION_HEADER)?;
+        let Some(version) = version
+            .to_str()
+            .ok()
+            .and_then(|version| version.parse().ok())
+        else {
+            return Some(mk_response(
+                StatusCode::BAD_REQUEST,
+                "Invalid protocol version".into(),
+            ));
+        };
+        let error = RegisterError::from(Self::check_version(Some(version)).err()?);
+        // The versions that this instance understands are advertised, so that
+        // the neighbor can retry in one of them.
+        let response =
+            mk_response(error.status_code(), error.to_string().into()).map(|mut response| {
+                let versions = transport::format_versions(&PROTOCOL_VERSIONS);
+                response
+                    .headers_mut()
+                    .insert(VERSIONS_HEADER, versions.parse().unwrap());
+                response
+            });
+        Some(response)
+    }
+}
+
+/// Returns the version of the protocol in which a request from a neighbor is
+/// written, if it states a valid one.
+pub(super) fn protocol_version(headers: &HeaderMap) -> Option<u32> {
+    headers.get(VERSION_HEADER)?.to_str().ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::abd_95::LOCAL_PATH;
+    use crate::transport::memory::{self, MemoryTransport};
+
+    mod with_protocol_version {
+        use super::*;
+
+        #[test]
+        fn defaults_to_latest_version() {
+            let register: AtomicRegister<u32> = AtomicRegister::default();
+            assert_eq!(register.protocol_version(), *PROTOCOL_VERSIONS.end());
+        }
+
+        #[test]
+        fn rejects_unsupported_versions() {
+            let result = AtomicRegister::<u32>::default().with_protocol_version(0);
+            assert!(matches!(
+                result,
+                Err(RegisterError::UnsupportedProtocol { version: 0, .. })
+            ));
+        }
+
+        #[tokio::test]
+        async fn sends_messages_in_chosen_version() {
+            let (transport, mut inbox) = memory::channel();
+            let neighbors = vec![Uri::from_static("http://neighbor.com")];
+            let register: AtomicRegister<u32, MemoryTransport> =
+                AtomicRegister::with_transport(neighbors, transport)
+                    .with_protocol_version(*PROTOCOL_VERSIONS.start())
+                    .unwrap();
+            let reachable: AtomicRegister<u32> = AtomicRegister::default();
+            let write = register.write(123);
+            inbox
+                .forward_until_with(&reachable, write, |envelope| {
+                    assert_eq!(envelope.message().version, Some(1));
+                    Some(envelope)
+                })
+                .await
+                .unwrap();
+        }
+    }
+
+    mod deliver {
+        use super::*;
+
+        fn neighbor() -> Uri {
+            Uri::from_static("http://neighbor.com")
+        }
+
+        /// Returns a register that writes a version of the protocol
+        /// that is later than any its neighbor understands, as if it
+        /// were running a newer release.
+        fn upgraded(transport: MemoryTransport) -> AtomicRegister<u32, MemoryTransport> {
+            let mut register = AtomicRegister::with_transport(vec![neighbor()], transport);
+            register.protocol = *PROTOCOL_VERSIONS.end() + 1;
+            register
+        }
+
+        fn rejection(version: u32) -> UnsupportedVersion {
+            UnsupportedVersion {
+                version,
+                supported: PROTOCOL_VERSIONS,
+            }
+        }
+
+        #[tokio::test]
+        async fn retries_in_latest_common_version() {
+            let (transport, mut inbox) = memory::channel();
+            let register = upgraded(transport);
+            let latest = register.protocol;
+            let message = transport::Message::ask(LOCAL_PATH);
+            let (reply, ()) = tokio::join!(register.deliver(neighbor(), message), async {
+                let envelope = inbox.recv().await.unwrap();
+                assert_eq!(envelope.message().version, Some(latest));
+                envelope.fail(rejection(latest));
+                let envelope = inbox.recv().await.unwrap();
+                assert_eq!(envelope.message().version, Some(latest - 1));
+                envelope.reply("123");
+            });
+            assert_eq!(reply.unwrap(), "123");
+        }
+
+        #[tokio::test]
+        async fn uses_negotiated_version_for_later_messages() {
+            let (transport, mut inbox) = memory::channel();
+            let register = upgraded(transport);
+            let latest = register.protocol;
+            let message = transport::Message::ask(LOCAL_PATH);
+            let (first, ()) = tokio::join!(register.deliver(neighbor(), message.clone()), async {
+                inbox.recv().await.unwrap().fail(rejection(latest));
+                inbox.recv().await.unwrap().reply("");
+            });
+            first.unwrap();
+            let (second, ()) = tokio::join!(register.deliver(neighbor(), message), async {
+                let envelope = inbox.recv().await.unwrap();
+                assert_eq!(envelope.message().version, Some(latest - 1));
+                envelope.reply("");
+            });
+            second.unwrap();
+            let status = register.status();
+            assert_eq!(status.neighbors[0].protocol_version, latest - 1);
+            assert_eq!(status.protocol_version, latest);
+        }
+
+        #[tokio::test]
+        async fn fails_if_neighbor_shares_no_version() {
+            let (transport, mut inbox) = memory::channel();
+            let register: AtomicRegister<u32, MemoryTransport> =
+                AtomicRegister::with_transport(vec![neighbor()], transport);
+            let later = *PROTOCOL_VERSIONS.end() + 1;
+            let message = transport::Message::ask(LOCAL_PATH);
+            let (reply, ()) = tokio::join!(register.deliver(neighbor(), message), async {
+                let envelope = inbox.recv().await.unwrap();
+                envelope.fail(UnsupportedVersion {
+                    version: *PROTOCOL_VERSIONS.end(),
+                    supported: later..=later,
+                });
+            });
+            let error = reply.unwrap_err();
+            assert!(error.downcast_ref::<UnsupportedVersion>().is_some());
+            assert!(inbox.try_recv().is_none());
+            assert_eq!(
+                register.status().neighbors[0].protocol_version,
+                *PROTOCOL_VERSIONS.end()
+            );
+        }
+
+        #[tokio::test]
+        async fn does_not_retry_other_failures() {
+            let (transport, mut inbox) = memory::channel();
+            let register = upgraded(transport);
+            let message = transport::Message::ask(LOCAL_PATH);
+            let (reply, ()) = tokio::join!(register.deliver(neighbor(), message), async {
+                inbox.recv().await.unwrap().fail("Scripted failure");
+            });
+            assert_eq!(reply.unwrap_err().to_string(), "Scripted failure");
+            assert!(inbox.try_recv().is_none());
+        }
+
+        #[tokio::test]
+        async fn negotiates_with_registers_that_reject_version() {
+            let (transport, mut inbox) = memory::channel();
+            let register = upgraded(transport);
+            let reachable: AtomicRegister<u32> = AtomicRegister::default();
+            let write = register.write(123);
+            let mut versions = Vec::new();
+            inbox
+                .forward_until_with(&reachable, write, |envelope| {
+                    versions.push(envelope.message().version);
+                    Some(envelope)
+                })
+                .await
+                .unwrap();
+            assert_eq!(reachable.local.lock().unwrap().value, 123);
+            let negotiated = Some(*PROTOCOL_VERSIONS.end());
+            assert_eq!(versions[0], Some(register.protocol));
+            assert!(versions[1..].iter().all(|version| *version == negotiated));
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! An array of [`AtomicRegister`]s, which can be read all at once.
+//!
+//! A [`RegisterArray`] manages `N` independent registers, which are all
+//! replicated across the same instances. Each register can be read and
+//! written on its own, and the array offers two ways of reading all of them:
+//!
+//! * A [`collect`](RegisterArray::collect) reads each register in turn.
+//!   This is cheap, but the result may never have been the contents of the
+//!   array at any single moment, since registers can be written while the
+//!   collect is in progress.
+//! * A [`scan`](RegisterArray::scan) repeats collects until two successive
+//!   collects are identical, as in the _double collect_ of Afek et al.
+//!   [\[AAD+93\]](https://dl.acm.org/doi/10.1145/153724.153741). The result
+//!   was then the contents of the array at some moment between the two
+//!   collects, and so scans and writes are linearizable.
+//!
+//! A scan never returns while writes keep changing the array, so the array is
+//! not a wait-free snapshot. It is instead a simple baseline against which
+//! snapshot protocols can be compared, such as the snapshot in the
+//! [`cluster-monitor`](https://github.com/kaymanb/todc/tree/main/todc-net/examples/cluster-monitor)
+//! example, whose updates embed the result of a scan so that scans always
+//! finish.
+//!
+//! # Routes
+//!
+//! The registers of an array are served by the same instance, and requests
+//! for register `j` are made to `/array/{j}`, followed by the usual route of
+//! an [`AtomicRegister`]. For example, clients can read register `j` with a
+//! `GET` request to `/array/{j}/register`, and neighbors exchange its value
+//! at `/array/{j}/register/local`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use tokio_test;
+//! use todc_net::register::RegisterArray;
+//!
+//! # tokio_test::block_on(async {
+//! let array: RegisterArray<u32, 3> = RegisterArray::new(Vec::new());
+//! array.write(1, 123).await.unwrap();
+//! assert_eq!(array.scan().await.unwrap(), [0, 123, 0]);
+//! # })
+//! ```
+use std::borrow::Cow;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::http::StatusCode;
+use hyper::service::Service;
+use hyper::{Request, Response, Uri};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::abd_95::{AtomicRegister, LocalValue, RegisterError};
+use crate::transport::{self, Handler, HttpTransport, Transport};
+use crate::{mk_response, GenericError};
+
+/// The prefix of the routes of the registers in an array.
+const ARRAY_PATH: &str = "/array/";
+
+/// A [`Transport`] that sends the messages of one register in an array to
+/// the routes of that register.
+#[derive(Clone)]
+pub struct ComponentTransport<Tr: Transport> {
+    index: usize,
+    inner: Tr,
+}
+
+impl<Tr: Transport> Transport for ComponentTransport<Tr> {
+    async fn send(
+        &self,
+        neighbor: Uri,
+        mut message: transport::Message,
+    ) -> Result<Bytes, GenericError> {
+        message.path = Cow::Owned(format!("{ARRAY_PATH}{}{}", self.index, message.path));
+        self.inner.send(neighbor, message).await
+    }
+}
+
+/// An array of `N` atomic registers, replicated across multiple instances.
+///
+/// See the [`array`](crate::register::array) module-level documentation for
+/// more details.
+#[derive(Clone)]
+pub struct RegisterArray<
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send,
+    const N: usize,
+    Tr: Transport = HttpTransport,
+> {
+    registers: [AtomicRegister<T, ComponentTransport<Tr>>; N],
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        const N: usize,
+    > RegisterArray<T, N>
+{
+    /// Creates a new instance of the array with a given set of neighbors.
+    ///
+    /// If there are `n` instances, then each instance must be instantiated
+    /// with a URL for all `n - 1` of its neighbors.
+    pub fn new(neighbors: Vec<Uri>) -> Self {
+        Self::with_transport(neighbors, HttpTransport::default())
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        const N: usize,
+        Tr: Transport,
+    > RegisterArray<T, N, Tr>
+{
+    /// Creates a new instance of the array with a given set of neighbors,
+    /// that communicates with them using the given [`Transport`].
+    pub fn with_transport(neighbors: Vec<Uri>, transport: Tr) -> Self {
+        let registers = std::array::from_fn(|index| {
+            let transport = ComponentTransport {
+                index,
+                inner: transport.clone(),
+            };
+            AtomicRegister::with_transport(neighbors.clone(), transport)
+        });
+        Self { registers }
+    }
+
+    /// Returns the register at position `i` of the array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not less than `N`.
+    pub fn register(&self, i: usize) -> &AtomicRegister<T, ComponentTransport<Tr>> {
+        &self.registers[i]
+    }
+
+    /// Returns the value contained in the register at position `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not less than `N`.
+    pub async fn read(&self, i: usize) -> Result<T, RegisterError> {
+        self.registers[i].read().await
+    }
+
+    /// Sets the contents of the register at position `i` to the specified
+    /// value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not less than `N`.
+    pub async fn write(&self, i: usize, value: T) -> Result<(), RegisterError> {
+        self.registers[i].write(value).await
+    }
+
+    /// Returns the value contained in every register, obtained by reading
+    /// each of them in turn.
+    ///
+    /// The result may never have been the contents of the array at a single
+    /// moment. See [`scan`](RegisterArray::scan) for a result that was.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::RegisterArray;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let array: RegisterArray<u32, 2> = RegisterArray::new(Vec::new());
+    /// array.write(0, 123).await.unwrap();
+    /// assert_eq!(array.collect().await.unwrap(), [123, 0]);
+    /// # })
+    /// ```
+    pub async fn collect(&self) -> Result<[T; N], RegisterError> {
+        Ok(self.collect_labeled().await?.map(|local| local.value))
+    }
+
+    /// Returns the value contained in every register, as they were at a
+    /// single moment during the scan.
+    ///
+    /// Collects are repeated until two successive collects return the same
+    /// label and value for every register, so a scan only returns once no
+    /// write has taken effect for the duration of a collect.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::RegisterArray;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let array: RegisterArray<String, 2> = RegisterArray::new(Vec::new());
+    /// array.write(1, String::from("hello")).await.unwrap();
+    /// assert_eq!(array.scan().await.unwrap(), [String::new(), String::from("hello")]);
+    /// # })
+    /// ```
+    pub async fn scan(&self) -> Result<[T; N], RegisterError> {
+        let mut previous = self.collect_labeled().await?;
+        loop {
+            let current = self.collect_labeled().await?;
+            // If both collects are identical, then no register was written
+            // between them, and their values are a valid scan.
+            if current == previous {
+                return Ok(current.map(|local| local.value));
+            }
+            previous = current;
+        }
+    }
+
+    /// Returns the value contained in every register, along with its label.
+    async fn collect_labeled(&self) -> Result<[LocalValue<T>; N], RegisterError> {
+        let mut contents = Vec::with_capacity(N);
+        for register in &self.registers {
+            contents.push(register.read_labeled().await?);
+        }
+        // The vector has exactly one element for each of the N registers.
+        Ok(contents.try_into().unwrap_or_else(|_| unreachable!()))
+    }
+
+    /// Returns the position of the register that a path is addressed to, and
+    /// the route of that register, if the path is of the form
+    /// `/array/{j}/...`.
+    fn route<'a>(&self, path: &'a str) -> Option<(usize, &'a str)> {
+        let rest = path.strip_prefix(ARRAY_PATH)?;
+        let split = rest.find('/')?;
+        let index = rest[..split].parse::<usize>().ok()?;
+        (index < N).then_some((index, &rest[split..]))
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        const N: usize,
+        Tr: Transport,
+    > Handler for RegisterArray<T, N, Tr>
+{
+    fn handle(
+        &self,
+        mut message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        let me = self.clone();
+        async move {
+            let (index, path) = me
+                .route(&message.path)
+                .map(|(index, path)| (index, path.to_string()))
+                .ok_or_else(|| GenericError::from(format!("Unknown route {}", message.path)))?;
+            message.path = Cow::Owned(path);
+            me.registers[index].handle(message).await
+        }
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        const N: usize,
+        Tr: Transport,
+    > Service<Request<Incoming>> for RegisterArray<T, N, Tr>
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    /// Passes a request made to `/array/{j}/...` to the register at
+    /// position `j`.
+    fn call(&self, mut req: Request<Incoming>) -> Self::Future {
+        let Some((index, path)) = self.route(req.uri().path()) else {
+            return Box::pin(async { mk_response(StatusCode::NOT_FOUND, "404 Not Found".into()) });
+        };
+        let path_and_query = match req.uri().query() {
+            Some(query) => format!("{path}?{query}"),
+            None => path.to_string(),
+        };
+        let mut parts = req.uri().clone().into_parts();
+        parts.path_and_query = match path_and_query.parse() {
+            Ok(path_and_query) => Some(path_and_query),
+            Err(error) => return Box::pin(async move { Err(error.into()) }),
+        };
+        *req.uri_mut() = match Uri::from_parts(parts) {
+            Ok(uri) => uri,
+            Err(error) => return Box::pin(async move { Err(error.into()) }),
+        };
+        self.registers[index].call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod route {
+        use super::*;
+
+        fn array() -> RegisterArray<u32, 2> {
+            RegisterArray::new(Vec::new())
+        }
+
+        #[test]
+        fn splits_index_from_route_of_register() {
+            assert_eq!(
+                array().route("/array/1/register/local"),
+                Some((1, "/register/local"))
+            );
+        }
+
+        #[test]
+        fn rejects_index_out_of_bounds() {
+            assert_eq!(array().route("/array/2/register"), None);
+        }
+
+        #[test]
+        fn rejects_paths_outside_of_array() {
+            assert_eq!(array().route("/register/local"), None);
+            assert_eq!(array().route("/array/register"), None);
+            assert_eq!(array().route("/array/1"), None);
+        }
+    }
+
+    mod scan {
+        use super::*;
+
+        #[tokio::test]
+        async fn returns_initial_values() {
+            let array: RegisterArray<u32, 3> = RegisterArray::new(Vec::new());
+            assert_eq!(array.scan().await.unwrap(), [0, 0, 0]);
+        }
+
+        #[tokio::test]
+        async fn returns_written_values() {
+            let array: RegisterArray<u32, 3> = RegisterArray::new(Vec::new());
+            for i in 0..3 {
+                array.write(i, i as u32 + 1).await.unwrap();
+            }
+            assert_eq!(array.scan().await.unwrap(), [1, 2, 3]);
+        }
+    }
+
+    mod write {
+        use super::*;
+
+        #[tokio::test]
+        async fn only_changes_one_register() {
+            let array: RegisterArray<u32, 2> = RegisterArray::new(Vec::new());
+            array.write(0, 123).await.unwrap();
+            assert_eq!(array.read(0).await.unwrap(), 123);
+            assert_eq!(array.read(1).await.unwrap(), 0);
+        }
+    }
+}
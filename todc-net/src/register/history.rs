@@ -0,0 +1,484 @@
+//! Recording the operations performed on a register, so that they can be
+//! checked for linearizability.
+//!
+//! An [`AtomicRegister`](crate::register::AtomicRegister) that is given a
+//! [`HistorySink`] with
+//! [`with_history`](crate::register::AtomicRegister::with_history) records
+//! the call and response of every operation it performs in the sink. Clones
+//! of a sink share the same underlying record, so a single sink can be
+//! given to every instance of a register in a simulation or deployment, and
+//! then used to retrieve a [`History`] that can be checked with a
+//! [`WGLChecker`](todc_utils::WGLChecker).
+//!
+//! A [`RegisterClient`](crate::client::RegisterClient) can also record the
+//! operations that it performs in a sink, with
+//! [`with_history`](crate::client::RegisterClient::with_history), which
+//! allows a register that is deployed outside of a simulation to be checked
+//! from the point of view of its clients.
+//!
+//! Each call to [`write_many`](crate::register::AtomicRegister::write_many)
+//! is recorded as a sequence of concurrent writes, which are all called
+//! before the batch takes effect, and all return once it completes. Each
+//! call to
+//! [`read_modify_write`](crate::register::AtomicRegister::read_modify_write)
+//! is recorded as a read along with a concurrent write, if the contents of
+//! the register changed.
+//!
+//! # Long Histories
+//!
+//! A sink created with [`HistorySink::new`] keeps every action that it
+//! records, which is too much for a test that runs for hours. A sink created
+//! with [`HistorySink::windowed`] instead divides its actions into windows
+//! with a [`WindowedRecorder`], and checks each window as soon as it is
+//! closed, so that only the current window is held in memory, along with
+//! any windows that the recorder is configured to retain. The result of the
+//! check so far is returned by [`check`](HistorySink::check).
+//!
+//! Windows are only closed at points at which no operation is pending, and
+//! operations that fail, or are abandoned, are left pending forever, since
+//! they may take effect at any later time. Once an operation is abandoned,
+//! a windowed sink therefore keeps every later action in its current window.
+//!
+//! This module requires the `history` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! # use tokio_test;
+//! use todc_net::register::AtomicRegister;
+//! use todc_net::register::history::HistorySink;
+//! use todc_utils::specifications::register::RegisterSpecification;
+//! use todc_utils::WGLChecker;
+//!
+//! # tokio_test::block_on(async {
+//! let sink = HistorySink::new();
+//! let register: AtomicRegister<u32> = AtomicRegister::default().with_history(sink.clone());
+//!
+//! register.write(123).await.unwrap();
+//! assert_eq!(register.read().await.unwrap(), 123);
+//!
+//! let history = sink.history();
+//! assert!(WGLChecker::<RegisterSpecification<u32>>::is_linearizable(history));
+//! # })
+//! ```
+use std::fmt::{self, Debug, Formatter};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use todc_utils::linearizability::history::ProcessId;
+use todc_utils::linearizability::windowed::{Window, WindowedChecker, WindowedRecorder};
+use todc_utils::specifications::register::{RegisterOperation, RegisterSpecification};
+use todc_utils::{Action, CheckResult, CheckerConfig, History, TimedAction, WGLChecker};
+
+/// The actions recorded by a sink, in the order that they happened.
+#[derive(Debug)]
+struct Log<T> {
+    actions: Vec<TimedAction<RegisterOperation<T>>>,
+    processes: ProcessId,
+    /// The windows of a sink created with [`HistorySink::windowed`], in
+    /// which case `actions` is always empty.
+    windows: Option<Windows<T>>,
+}
+
+/// The windows into which a windowed sink divides its actions, along with
+/// the checker of the windows that have been closed.
+struct Windows<T> {
+    recorder: WindowedRecorder<RegisterOperation<T>>,
+    checker: Box<dyn WindowCheck<T>>,
+}
+
+impl<T: Debug> Debug for Windows<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Windows")
+            .field("recorder", &self.recorder)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A checker of the windows of a register history.
+///
+/// Sinks only require the contents of a register to be cloned, so the
+/// checker is boxed by [`HistorySink::windowed`], which knows that they also
+/// satisfy the bounds of a [`RegisterSpecification`].
+trait WindowCheck<T>: Send {
+    fn push(&mut self, window: &Window<RegisterOperation<T>>) -> CheckResult;
+    fn check(&self, window: &Window<RegisterOperation<T>>) -> CheckResult;
+}
+
+impl<T> WindowCheck<T> for WindowedChecker<RegisterSpecification<T>>
+where
+    T: Clone + Debug + Default + Eq + Hash + Send,
+{
+    fn push(&mut self, window: &Window<RegisterOperation<T>>) -> CheckResult {
+        WindowedChecker::push(self, window)
+    }
+
+    fn check(&self, window: &Window<RegisterOperation<T>>) -> CheckResult {
+        WindowedChecker::check(self, window)
+    }
+}
+
+/// A shared record of the operations performed on a register.
+///
+/// Operations performed concurrently by a single register instance are not
+/// ordered with respect to one another, so each operation is recorded as
+/// if it were performed by a distinct process.
+///
+/// See the [`history`](crate::register::history) module-level documentation
+/// for more details.
+#[derive(Debug)]
+pub struct HistorySink<T> {
+    log: Arc<Mutex<Log<T>>>,
+}
+
+impl<T> Clone for HistorySink<T> {
+    fn clone(&self) -> Self {
+        Self {
+            log: self.log.clone(),
+        }
+    }
+}
+
+impl<T> Default for HistorySink<T> {
+    fn default() -> Self {
+        Self {
+            log: Arc::new(Mutex::new(Log {
+                actions: Vec::new(),
+                processes: 0,
+                windows: None,
+            })),
+        }
+    }
+}
+
+impl<T: Clone> HistorySink<T> {
+    /// Creates an empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the actions recorded so far, in the order that they happened.
+    ///
+    /// A windowed sink only returns the actions of its current window.
+    pub fn actions(&self) -> Vec<TimedAction<RegisterOperation<T>>> {
+        let log = self.log.lock().unwrap();
+        match &log.windows {
+            Some(windows) => windows.recorder.current().actions.clone(),
+            None => log.actions.clone(),
+        }
+    }
+
+    /// Returns the windows retained by a windowed sink, in the order that
+    /// they happened. See [`WindowedRecorder::with_recent`] and
+    /// [`WindowedRecorder::with_samples`].
+    ///
+    /// Sinks that are not windowed retain no windows.
+    pub fn retained_windows(&self) -> Vec<Window<RegisterOperation<T>>> {
+        let log = self.log.lock().unwrap();
+        let Some(windows) = &log.windows else {
+            return Vec::new();
+        };
+        let mut retained: Vec<_> = windows
+            .recorder
+            .samples()
+            .iter()
+            .chain(windows.recorder.recent())
+            .cloned()
+            .collect();
+        retained.sort_by_key(|window| window.index);
+        retained
+    }
+
+    /// Returns a history containing the operations recorded so far, with
+    /// the times at which they happened attached as timestamps.
+    ///
+    /// Operations that have not yet returned, or that failed, are handled
+    /// as follows:
+    ///
+    /// * Reads are omitted, since they did not affect the register.
+    /// * Writes may or may not have taken effect, and so are treated as if
+    ///   they returned after every other operation.
+    ///
+    /// A windowed sink only returns the operations of its current window,
+    /// which can not be checked on their own. Use
+    /// [`check`](HistorySink::check) instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no operations have been recorded.
+    pub fn history(&self) -> History<RegisterOperation<T>> {
+        let actions = self.actions();
+        let processes = self.log.lock().unwrap().processes;
+        let mut pending: Vec<Option<usize>> = vec![None; processes];
+        for (i, timed) in actions.iter().enumerate() {
+            pending[timed.process] = match timed.action {
+                Action::Call(_) => Some(i),
+                _ => None,
+            };
+        }
+        let now = Instant::now();
+        let mut completions = Vec::new();
+        let mut omitted = vec![false; actions.len()];
+        for i in pending.into_iter().flatten() {
+            match &actions[i].action {
+                Action::Call(RegisterOperation::Read(_)) => omitted[i] = true,
+                Action::Call(write) => completions.push(TimedAction {
+                    process: actions[i].process,
+                    action: Action::Response(write.clone()),
+                    happened_at: now,
+                }),
+                _ => unreachable!("Pending actions are calls"),
+            }
+        }
+        let actions = actions
+            .into_iter()
+            .zip(omitted)
+            .filter(|(_, omitted)| !omitted)
+            .map(|(timed, _)| timed)
+            .chain(completions)
+            .collect();
+        History::from_timed_actions(actions)
+    }
+
+    /// Records the call of an operation by a new process, and returns the
+    /// process.
+    pub(crate) fn call(&self, operation: RegisterOperation<T>) -> ProcessId {
+        let mut log = self.log.lock().unwrap();
+        let process = log.processes;
+        log.processes += 1;
+        log.push(process, Action::Call(operation));
+        process
+    }
+
+    /// Records the response of an operation by a process.
+    pub(crate) fn respond(&self, process: ProcessId, operation: RegisterOperation<T>) {
+        let mut log = self.log.lock().unwrap();
+        log.push(process, Action::Response(operation));
+    }
+}
+
+impl<T> HistorySink<T>
+where
+    T: Clone + Debug + Default + Eq + Hash + Send + 'static,
+{
+    /// Creates an empty sink that divides the actions it records into
+    /// windows with the given recorder, and checks each window as soon as it
+    /// is closed. See [Long Histories](self#long-histories).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tokio_test;
+    /// use todc_net::register::AtomicRegister;
+    /// use todc_net::register::history::HistorySink;
+    /// use todc_utils::linearizability::windowed::WindowedRecorder;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let sink = HistorySink::windowed(WindowedRecorder::new(100).with_recent(2));
+    /// let register: AtomicRegister<u32> = AtomicRegister::default().with_history(sink.clone());
+    ///
+    /// for value in 0..100 {
+    ///     register.write(value).await.unwrap();
+    ///     assert_eq!(register.read().await.unwrap(), value);
+    /// }
+    /// assert!(sink.check().is_linearizable());
+    /// assert_eq!(sink.retained_windows().len(), 2);
+    /// # })
+    /// ```
+    pub fn windowed(recorder: WindowedRecorder<RegisterOperation<T>>) -> Self {
+        let checker: WindowedChecker<RegisterSpecification<T>> = WindowedChecker::new();
+        Self {
+            log: Arc::new(Mutex::new(Log {
+                actions: Vec::new(),
+                processes: 0,
+                windows: Some(Windows {
+                    recorder,
+                    checker: Box::new(checker),
+                }),
+            })),
+        }
+    }
+
+    /// Returns whether the operations recorded so far are linearizable.
+    ///
+    /// Operations that have not yet returned are handled in the same way as
+    /// by [`history`](HistorySink::history). A windowed sink only checks its
+    /// current window, since every earlier window was checked as soon as it
+    /// was closed.
+    pub fn check(&self) -> CheckResult {
+        let log = self.log.lock().unwrap();
+        match &log.windows {
+            Some(windows) => windows.checker.check(windows.recorder.current()),
+            None if log.actions.is_empty() => CheckResult::Linearizable,
+            None => {
+                drop(log);
+                WGLChecker::<RegisterSpecification<T>>::check_with_config(
+                    self.history(),
+                    &CheckerConfig::new(),
+                )
+            }
+        }
+    }
+}
+
+impl<T: Clone> Log<T> {
+    /// Appends an action to the log. The time at which it happened is taken
+    /// while the log is locked, so that actions are ordered by time.
+    ///
+    /// In a windowed sink, the action is recorded in the current window
+    /// instead, and the window that it closes, if any, is checked.
+    fn push(&mut self, process: ProcessId, action: Action<RegisterOperation<T>>) {
+        let timed = TimedAction::now(process, action);
+        match &mut self.windows {
+            Some(windows) => {
+                if let Some(window) = windows.recorder.record(timed) {
+                    windows.checker.push(&window);
+                }
+            }
+            None => self.actions.push(timed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use todc_utils::linearizability::history::Entry;
+    use RegisterOperation::{Read, Write};
+
+    mod history {
+        use super::*;
+
+        #[test]
+        fn assigns_a_process_to_each_operation() {
+            let sink = HistorySink::new();
+            let first = sink.call(Write(1));
+            let second = sink.call(Read(None));
+            sink.respond(second, Read(Some(1)));
+            sink.respond(first, Write(1));
+            assert_ne!(first, second);
+            assert_eq!(sink.history().len(), 4);
+        }
+
+        #[test]
+        fn omits_pending_reads() {
+            let sink = HistorySink::new();
+            let write = sink.call(Write(1));
+            sink.respond(write, Write(1));
+            sink.call(Read(None));
+            assert_eq!(sink.history().len(), 2);
+        }
+
+        #[test]
+        fn completes_pending_writes_after_all_other_operations() {
+            let sink = HistorySink::new();
+            sink.call(Write(1));
+            let read = sink.call(Read(None));
+            sink.respond(read, Read(Some(1)));
+            let history = sink.history();
+            assert_eq!(history.len(), 4);
+            match history.iter().last() {
+                Some(Entry::Response(response)) => assert!(matches!(response.operation, Write(1))),
+                _ => panic!("Expected the last entry to be the response to the write"),
+            }
+        }
+
+        #[test]
+        fn attaches_timestamps() {
+            let sink = HistorySink::new();
+            let write = sink.call(Write(1));
+            sink.respond(write, Write(1));
+            assert!(sink.history().stats().mean_latency.is_some());
+        }
+    }
+
+    mod check {
+        use super::*;
+
+        #[test]
+        fn accepts_empty_history() {
+            let sink: HistorySink<u32> = HistorySink::new();
+            assert!(sink.check().is_linearizable());
+        }
+
+        #[test]
+        fn rejects_read_of_value_never_written() {
+            let sink = HistorySink::new();
+            let read = sink.call(Read(None));
+            sink.respond(read, Read(Some(1)));
+            assert_eq!(sink.check(), CheckResult::NotLinearizable);
+        }
+    }
+
+    mod windowed {
+        use super::*;
+
+        /// Records a write followed by a read of the given value.
+        fn write_then_read(sink: &HistorySink<u32>, written: u32, read: u32) {
+            let process = sink.call(Write(written));
+            sink.respond(process, Write(written));
+            let process = sink.call(Read(None));
+            sink.respond(process, Read(Some(read)));
+        }
+
+        #[test]
+        fn only_keeps_actions_of_current_window() {
+            let sink = HistorySink::windowed(WindowedRecorder::new(2));
+            for value in 0..10 {
+                write_then_read(&sink, value, value);
+            }
+            sink.call(Read(None));
+            assert_eq!(sink.actions().len(), 1);
+            assert!(sink.check().is_linearizable());
+        }
+
+        #[test]
+        fn reports_violation_in_closed_window() {
+            let sink = HistorySink::windowed(WindowedRecorder::new(2));
+            write_then_read(&sink, 1, 2);
+            write_then_read(&sink, 3, 3);
+            assert!(sink.actions().is_empty());
+            assert_eq!(sink.check(), CheckResult::NotLinearizable);
+        }
+
+        #[test]
+        fn reports_violation_across_windows() {
+            let sink = HistorySink::windowed(WindowedRecorder::new(2));
+            let write = sink.call(Write(1));
+            sink.respond(write, Write(1));
+            let read = sink.call(Read(None));
+            sink.respond(read, Read(Some(0)));
+            assert_eq!(sink.check(), CheckResult::NotLinearizable);
+        }
+
+        #[test]
+        fn accepts_read_of_pending_write_in_current_window() {
+            let sink = HistorySink::windowed(WindowedRecorder::new(2));
+            sink.call(Write(1));
+            let read = sink.call(Read(None));
+            sink.respond(read, Read(Some(1)));
+            assert!(sink.check().is_linearizable());
+        }
+
+        #[test]
+        fn retains_windows_in_order() {
+            let recorder = WindowedRecorder::new(4).with_recent(2).with_samples(2);
+            let sink = HistorySink::windowed(recorder);
+            for value in 0..10 {
+                write_then_read(&sink, value, value);
+            }
+            let retained = sink.retained_windows();
+            assert_eq!(retained.len(), 4);
+            assert!(retained.windows(2).all(|w| w[0].index < w[1].index));
+            assert_eq!(retained.last().unwrap().index, 9);
+        }
+
+        #[test]
+        fn retains_no_windows_unless_windowed() {
+            let sink = HistorySink::new();
+            write_then_read(&sink, 1, 1);
+            assert!(sink.retained_windows().is_empty());
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Labels that order the values written to a register.
+//!
+//! Every value written to an [`AtomicRegister`](super::AtomicRegister) is
+//! associated with a label, and instances adopt the value with the largest
+//! label that they learn about. Each write chooses a label larger than any
+//! it has seen, so labels increase forever, and a label type with a fixed
+//! number of bits eventually runs out of labels.
+//!
+//! Rather than silently wrapping around, which would cause newer values to
+//! be ordered before older ones, a [`Label`] reports when it cannot be
+//! advanced any further, and the write that attempted to advance it fails.
+//! Labels are [`u64`] by default, which for all practical purposes never
+//! overflow, but [`u32`] labels are also supported, for example to remain
+//! compatible with instances that use them.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_net::register::label::Label;
+//!
+//! assert_eq!(1_u64.advance(2), Some(3));
+//! assert_eq!(u32::MAX.advance(1), None);
+//! ```
+use std::fmt::Debug;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// A label that orders the values written to a register.
+///
+/// The default label is the smallest, and is associated with the initial
+/// value of the register.
+pub trait Label:
+    Copy + Debug + Default + Ord + Send + Sync + Serialize + DeserializeOwned + 'static
+{
+    /// Returns the label that is `n` labels larger than this one, or `None`
+    /// if there is no such label.
+    fn advance(&self, n: usize) -> Option<Self>;
+
+    /// Returns the number of labels by which this label trails `other`, or
+    /// `None` if that number is not known.
+    ///
+    /// A label that is at least as large as `other` trails it by `0` labels.
+    /// By default, the number of labels is not known in any other case.
+    fn lag(&self, other: &Self) -> Option<u64> {
+        (self >= other).then_some(0)
+    }
+}
+
+impl Label for u32 {
+    fn advance(&self, n: usize) -> Option<Self> {
+        self.checked_add(n.try_into().ok()?)
+    }
+
+    fn lag(&self, other: &Self) -> Option<u64> {
+        Some(other.saturating_sub(*self).into())
+    }
+}
+
+impl Label for u64 {
+    fn advance(&self, n: usize) -> Option<Self> {
+        self.checked_add(n.try_into().ok()?)
+    }
+
+    fn lag(&self, other: &Self) -> Option<u64> {
+        Some(other.saturating_sub(*self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod advance {
+        use super::*;
+
+        #[test]
+        fn adds_to_label() {
+            assert_eq!(0_u32.advance(5), Some(5));
+            assert_eq!(0_u64.advance(5), Some(5));
+        }
+
+        #[test]
+        fn returns_none_on_overflow() {
+            assert_eq!((u32::MAX - 1).advance(2), None);
+            assert_eq!((u64::MAX - 1).advance(2), None);
+        }
+
+        #[test]
+        fn u64_labels_outlast_u32_labels() {
+            assert_eq!((u32::MAX as u64).advance(1), Some(u32::MAX as u64 + 1));
+        }
+    }
+
+    mod lag {
+        use super::*;
+
+        #[test]
+        fn counts_labels_between_smaller_and_larger() {
+            assert_eq!(2_u32.lag(&5), Some(3));
+            assert_eq!(2_u64.lag(&5), Some(3));
+        }
+
+        #[test]
+        fn larger_labels_do_not_lag() {
+            assert_eq!(5_u32.lag(&2), Some(0));
+            assert_eq!(5_u64.lag(&5), Some(0));
+        }
+    }
+}
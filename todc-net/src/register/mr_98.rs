@@ -0,0 +1,571 @@
+//! Simulations of shared-memory registers that tolerate Byzantine instances,
+//! using the masking quorums of Malkhi and Reiter \[MR98\].
+//!
+//! An [`AtomicRegister`](crate::register::AtomicRegister) tolerates instances
+//! that crash, but trusts the replies of every instance that does not. A
+//! [`ByzantineRegister`] instead tolerates up to `f` instances that are
+//! _Byzantine_, and may reply with arbitrary values, as long as there are
+//! `n > 4f` instances in total.
+//!
+//! Each operation waits for replies from a _masking quorum_ of instances.
+//! Any two masking quorums intersect in at least `2f + 1` instances, and so
+//! a value that was stored at a quorum is reported by at least `f + 1`
+//! correct instances of any other. A read only trusts values that are
+//! reported by at least `f + 1` instances, which is more than the Byzantine
+//! instances can fabricate on their own, and returns the one that was
+//! written most recently. See the [`quorum`](crate::quorum) module for
+//! details.
+//!
+//! The register is _safe_: a read that is not concurrent with any write
+//! returns the value of the latest write that completed before it began. A
+//! read that is concurrent with a write returns either a value that was
+//! written, or the initial value of the register.
+//!
+//! # Authentication
+//!
+//! Byzantine quorums assume that an instance cannot send a message on behalf
+//! of another, and that instances are identified by an ID that is unique
+//! among them. Every message that an instance sends is signed by its
+//! [`Authenticator`], which is chosen with
+//! [`with_authenticator`](ByzantineRegister::with_authenticator), and is
+//! verified by the instance that receives it. Replies that cannot be
+//! verified, or that repeat the ID of an instance that already replied, are
+//! treated as if the instance had failed to reply. Each request carries a
+//! random nonce, which its replies must repeat, so that replies to earlier
+//! requests cannot be replayed.
+//!
+//! By default, messages are not authenticated. See the
+//! [`auth`](crate::auth) module for details.
+//!
+//! # Examples
+//!
+//! Instances of [`ByzantineRegister`] handle messages from their neighbors by
+//! responding to `POST` requests made to `/register/byzantine`, in the same
+//! way as an [`AtomicRegister`](crate::register::AtomicRegister).
+//!
+//! ```
+//! # use tokio_test;
+//! use todc_net::auth::HmacAuthenticator;
+//! use todc_net::register::ByzantineRegister;
+//!
+//! # tokio_test::block_on(async {
+//! // A single instance, which tolerates no Byzantine instances.
+//! let register: ByzantineRegister<u32> = ByzantineRegister::new(0, Vec::new(), 0)
+//!     .unwrap()
+//!     .with_authenticator(HmacAuthenticator::new(b"secret key"));
+//!
+//! register.write(123).await.unwrap();
+//! assert_eq!(register.read().await.unwrap(), 123);
+//!
+//! // Five instances are required to tolerate one Byzantine instance.
+//! assert!(ByzantineRegister::<u32>::new(0, Vec::new(), 1).is_err());
+//! # })
+//! ```
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::http::StatusCode;
+use hyper::service::Service;
+use hyper::{Method, Request, Response, Uri};
+use rand::random;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+
+use crate::auth::{Authenticator, Unauthenticated};
+use crate::quorum::MaskingResilience;
+use crate::register::abd_95::RegisterError;
+use crate::transport::{self, Handler, HttpTransport, Transport};
+use crate::{mk_response, GenericError};
+
+/// The route at which instances exchange messages.
+const BYZANTINE_PATH: &str = "/register/byzantine";
+
+/// The timestamp of a write, which orders it among all other writes.
+///
+/// Ties between writes with the same counter are broken by the ID of the
+/// instance that performed them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+struct Timestamp {
+    counter: u64,
+    writer: u32,
+}
+
+/// A value stored by an instance, along with the timestamp of its write.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+struct Stored<T> {
+    timestamp: Timestamp,
+    value: T,
+}
+
+/// A query from one instance to another.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+enum Query<T> {
+    /// A request asking for the value stored by the reciever.
+    Ask { nonce: u64 },
+    /// A request asking the reciever to store a value, if it is newer than
+    /// the one that it already stores.
+    Store { nonce: u64, stored: Stored<T> },
+}
+
+impl<T> Query<T> {
+    fn nonce(&self) -> u64 {
+        match self {
+            Self::Ask { nonce } | Self::Store { nonce, .. } => *nonce,
+        }
+    }
+}
+
+/// A reply to a [`Query`], containing the value stored by the instance
+/// that sent it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Reply<T> {
+    nonce: u64,
+    stored: Stored<T>,
+}
+
+/// A message, encoded as JSON, along with the ID of the instance that sent
+/// it and its signature.
+#[derive(Debug, Deserialize, Serialize)]
+struct Signed {
+    signer: u32,
+    message: String,
+    signature: Vec<u8>,
+}
+
+/// An instance of a register that tolerates Byzantine instances.
+///
+/// See the [`mr_98`](crate::register::mr_98) module-level documentation for
+/// more details.
+#[derive(Clone)]
+pub struct ByzantineRegister<
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize,
+    Tr: Transport = HttpTransport,
+> {
+    id: u32,
+    transport: Tr,
+    neighbors: Arc<Vec<Uri>>,
+    resilience: MaskingResilience,
+    local: Arc<Mutex<Stored<T>>>,
+    authenticator: Arc<dyn Authenticator>,
+}
+
+impl<T> ByzantineRegister<T>
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+{
+    /// Creates a new instance with a given set of neighbors, of which at most
+    /// `failures` may be Byzantine.
+    ///
+    /// If there are `n` instances, then each instance must be instantiated
+    /// with a URL for all `n - 1` of its neighbors, and an `id` that is
+    /// different from the ids of all other instances.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegisterError::TooFewInstances`] unless there are more than
+    /// `4 * failures` instances, including this one.
+    pub fn new(id: u32, neighbors: Vec<Uri>, failures: usize) -> Result<Self, RegisterError> {
+        Self::with_transport(id, neighbors, failures, HttpTransport::default())
+    }
+}
+
+impl<T, Tr> ByzantineRegister<T, Tr>
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+    Tr: Transport,
+{
+    /// Creates a new instance with a given set of neighbors, of which at most
+    /// `failures` may be Byzantine, that communicates with them using the
+    /// given [`Transport`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegisterError::TooFewInstances`] unless there are more than
+    /// `4 * failures` instances, including this one.
+    pub fn with_transport(
+        id: u32,
+        neighbors: Vec<Uri>,
+        failures: usize,
+        transport: Tr,
+    ) -> Result<Self, RegisterError> {
+        Ok(Self {
+            id,
+            transport,
+            resilience: MaskingResilience::new(neighbors.len() + 1, failures)?,
+            neighbors: Arc::new(neighbors),
+            local: Arc::new(Mutex::new(Stored::default())),
+            authenticator: Arc::new(Unauthenticated),
+        })
+    }
+
+    /// Signs and verifies the messages exchanged by this instance with the
+    /// given [`Authenticator`], which must be the same for every instance.
+    /// See [Authentication](self#authentication).
+    pub fn with_authenticator(mut self, authenticator: impl Authenticator) -> Self {
+        self.authenticator = Arc::new(authenticator);
+        self
+    }
+
+    /// Returns the [`MaskingResilience`] of this instance and its neighbors.
+    pub fn resilience(&self) -> MaskingResilience {
+        self.resilience
+    }
+
+    /// Returns the value contained in the register.
+    ///
+    /// The value that is returned is the most recently written of those that
+    /// are reported by more than `f` instances of a quorum, or the initial
+    /// value of the register if there are none.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegisterError::QuorumUnavailable`] if too few instances
+    /// reply with a valid signature.
+    pub async fn read(&self) -> Result<T, RegisterError> {
+        let replies = self.communicate(None).await?;
+        let vouched = self.vouched(replies);
+        Ok(vouched.map(|stored| stored.value).unwrap_or_default())
+    }
+
+    /// Sets the contents of the register to the specified value.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegisterError::QuorumUnavailable`] if too few instances
+    /// reply with a valid signature, and [`RegisterError::LabelOverflow`] if
+    /// the timestamp of the write cannot be advanced any further, which can
+    /// only happen if a Byzantine instance wrote a value with the largest
+    /// possible timestamp.
+    pub async fn write(&self, value: T) -> Result<(), RegisterError> {
+        let mut counters: Vec<u64> = self
+            .communicate(None)
+            .await?
+            .into_iter()
+            .map(|stored| stored.timestamp.counter)
+            .collect();
+        // At least `f + 1` of the replies come from correct instances that
+        // store the latest completed write, or a later one, and so the
+        // `(f + 1)`th largest counter is at least as large as the counter of
+        // that write. Byzantine instances cannot make it any larger.
+        counters.sort_unstable_by(|a, b| b.cmp(a));
+        let latest = counters[self.resilience.vouchers() - 1];
+        let stored = Stored {
+            timestamp: Timestamp {
+                counter: latest.checked_add(1).ok_or(RegisterError::LabelOverflow)?,
+                writer: self.id,
+            },
+            value,
+        };
+        self.communicate(Some(stored)).await?;
+        Ok(())
+    }
+
+    /// Returns the most recently written value among those that are reported
+    /// by enough instances to be trusted, if there are any.
+    fn vouched(&self, replies: Vec<Stored<T>>) -> Option<Stored<T>> {
+        let mut counts: BTreeMap<Stored<T>, usize> = BTreeMap::new();
+        for stored in replies {
+            *counts.entry(stored).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count >= self.resilience.vouchers())
+            .map(|(stored, _)| stored)
+            .max()
+    }
+
+    /// Sends a request to every neighbor, asking them to store the given
+    /// value if there is one, and returns the values stored by a quorum of
+    /// instances, including this one.
+    async fn communicate(
+        &self,
+        stored: Option<Stored<T>>,
+    ) -> Result<Vec<Stored<T>>, RegisterError> {
+        let nonce = random();
+        let query = match stored {
+            Some(stored) => Query::Store { nonce, stored },
+            None => Query::Ask { nonce },
+        };
+        // This instance replies to its own request, and so is trusted to
+        // have done so.
+        let reply = self.receive(query.clone());
+        let mut signers = HashSet::from([self.id]);
+        let mut replies = vec![reply.stored];
+
+        let body = self.sign(&query)?;
+        let mut handles = JoinSet::new();
+        for neighbor in self.neighbors.iter().cloned() {
+            let transport = self.transport.clone();
+            let message = transport::Message::announce(BYZANTINE_PATH, body.clone());
+            handles.spawn(async move { transport.send(neighbor, message).await });
+        }
+
+        // Wait until a quorum of instances have replied with valid replies,
+        // or until so many have failed that a quorum never will.
+        let tolerated = self.resilience.instances() - self.resilience.quorum();
+        let mut failures = 0;
+        while !self.resilience.is_available(replies.len()) && failures <= tolerated {
+            let Some(result) = handles.join_next().await else {
+                break;
+            };
+            let reply = result
+                .map_err(|error| RegisterError::Transport(error.into()))?
+                .and_then(|body| self.open::<Reply<T>>(&body));
+            match reply {
+                Ok((signer, reply)) if reply.nonce == nonce && signers.insert(signer) => {
+                    replies.push(reply.stored)
+                }
+                Ok(_) => {
+                    tracing::warn!("neighbor replied with a duplicate or replayed reply");
+                    failures += 1
+                }
+                Err(error) => {
+                    tracing::debug!(%error, "neighbor did not reply");
+                    failures += 1
+                }
+            }
+        }
+        handles.abort_all();
+
+        if self.resilience.is_available(replies.len()) {
+            Ok(replies)
+        } else {
+            let acks = replies.len();
+            let needed = self.resilience.quorum();
+            tracing::warn!(acks, needed, "quorum unavailable");
+            Err(RegisterError::QuorumUnavailable {
+                acks,
+                needed,
+                failures: self.resilience.failures(),
+            })
+        }
+    }
+
+    /// Acts on a query, and returns the reply of this instance.
+    fn receive(&self, query: Query<T>) -> Reply<T> {
+        let nonce = query.nonce();
+        let mut local = self.local.lock().unwrap();
+        if let Query::Store { stored, .. } = query {
+            if stored > *local {
+                *local = stored;
+            }
+        }
+        Reply {
+            nonce,
+            stored: local.clone(),
+        }
+    }
+
+    /// Encodes and signs a message on behalf of this instance.
+    fn sign<M: Serialize>(&self, message: &M) -> Result<Bytes, RegisterError> {
+        let message = serde_json::to_string(message)?;
+        let signed = Signed {
+            signer: self.id,
+            signature: self.authenticator.sign(self.id, message.as_bytes()),
+            message,
+        };
+        Ok(serde_json::to_vec(&signed)?.into())
+    }
+
+    /// Verifies and decodes a signed message, and returns it along with the
+    /// ID of the instance that signed it.
+    fn open<M: DeserializeOwned>(&self, body: &[u8]) -> Result<(u32, M), GenericError> {
+        let signed: Signed = serde_json::from_slice(body)?;
+        let valid =
+            self.authenticator
+                .verify(signed.signer, signed.message.as_bytes(), &signed.signature);
+        if !valid {
+            return Err(format!("Invalid signature from instance {}", signed.signer).into());
+        }
+        Ok((signed.signer, serde_json::from_str(&signed.message)?))
+    }
+}
+
+impl<T, Tr> Handler for ByzantineRegister<T, Tr>
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+    Tr: Transport,
+{
+    fn handle(
+        &self,
+        message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        let me = self.clone();
+        async move {
+            if message.path != BYZANTINE_PATH {
+                return Err(GenericError::from(format!(
+                    "Unknown route {}",
+                    message.path
+                )));
+            }
+            let body = message.body.ok_or("Requests must have a body")?;
+            let (_, query) = me.open::<Query<T>>(&body)?;
+            let reply = me.receive(query);
+            Ok(me.sign(&reply)?)
+        }
+    }
+}
+
+impl<T, Tr> Service<Request<Incoming>> for ByzantineRegister<T, Tr>
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+    Tr: Transport,
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        let me = self.clone();
+        match (req.method(), req.uri().path()) {
+            // POST requests contain signed requests from other instances.
+            (&Method::POST, BYZANTINE_PATH) => Box::pin(async move {
+                let body = req.collect().await?.to_bytes();
+                match me
+                    .handle(transport::Message::announce(BYZANTINE_PATH, body))
+                    .await
+                {
+                    Ok(reply) => Ok(Response::new(Full::new(reply))),
+                    Err(error) => mk_response(StatusCode::BAD_REQUEST, error.to_string().into()),
+                }
+            }),
+            _ => Box::pin(async { mk_response(StatusCode::NOT_FOUND, "404 Not Found".into()) }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::HmacAuthenticator;
+
+    fn neighbors(n: usize) -> Vec<Uri> {
+        (0..n)
+            .map(|i| format!("http://test-{i}.com").parse().unwrap())
+            .collect()
+    }
+
+    fn stored(counter: u64, value: u32) -> Stored<u32> {
+        Stored {
+            timestamp: Timestamp { counter, writer: 0 },
+            value,
+        }
+    }
+
+    mod new {
+        use super::*;
+
+        #[test]
+        fn rejects_too_few_instances() {
+            let result = ByzantineRegister::<u32>::new(0, neighbors(3), 1);
+            assert!(matches!(result, Err(RegisterError::TooFewInstances(_))));
+        }
+
+        #[test]
+        fn accepts_more_than_four_times_as_many_instances_as_failures() {
+            let register = ByzantineRegister::<u32>::new(0, neighbors(4), 1).unwrap();
+            assert_eq!(register.resilience().quorum(), 4);
+        }
+    }
+
+    mod receive {
+        use super::*;
+
+        #[test]
+        fn stores_newer_value() {
+            let register = ByzantineRegister::<u32>::new(0, Vec::new(), 0).unwrap();
+            let reply = register.receive(Query::Store {
+                nonce: 7,
+                stored: stored(1, 123),
+            });
+            assert_eq!(reply.nonce, 7);
+            assert_eq!(reply.stored, stored(1, 123));
+        }
+
+        #[test]
+        fn ignores_older_value() {
+            let register = ByzantineRegister::<u32>::new(0, Vec::new(), 0).unwrap();
+            *register.local.lock().unwrap() = stored(2, 123);
+            let reply = register.receive(Query::Store {
+                nonce: 7,
+                stored: stored(1, 456),
+            });
+            assert_eq!(reply.stored, stored(2, 123));
+        }
+    }
+
+    mod vouched {
+        use super::*;
+
+        #[test]
+        fn ignores_values_reported_by_too_few_instances() {
+            let register = ByzantineRegister::<u32>::new(0, neighbors(4), 1).unwrap();
+            let replies = vec![stored(9, 666), stored(1, 123), stored(1, 123), stored(0, 0)];
+            assert_eq!(register.vouched(replies), Some(stored(1, 123)));
+        }
+
+        #[test]
+        fn returns_latest_of_vouched_values() {
+            let register = ByzantineRegister::<u32>::new(0, neighbors(4), 1).unwrap();
+            let replies = vec![stored(1, 1), stored(1, 1), stored(2, 2), stored(2, 2)];
+            assert_eq!(register.vouched(replies), Some(stored(2, 2)));
+        }
+
+        #[test]
+        fn returns_none_if_no_value_is_vouched_for() {
+            let register = ByzantineRegister::<u32>::new(0, neighbors(4), 1).unwrap();
+            let replies = vec![stored(1, 1), stored(2, 2), stored(3, 3), stored(4, 4)];
+            assert_eq!(register.vouched(replies), None);
+        }
+    }
+
+    mod handle {
+        use super::*;
+
+        fn register(key: &[u8]) -> ByzantineRegister<u32> {
+            ByzantineRegister::new(0, Vec::new(), 0)
+                .unwrap()
+                .with_authenticator(HmacAuthenticator::new(key))
+        }
+
+        #[tokio::test]
+        async fn replies_with_signed_value() {
+            let register = register(b"key");
+            let query = register.sign(&Query::<u32>::Ask { nonce: 7 }).unwrap();
+            let reply = register
+                .handle(transport::Message::announce(BYZANTINE_PATH, query))
+                .await
+                .unwrap();
+            let (signer, reply) = register.open::<Reply<u32>>(&reply).unwrap();
+            assert_eq!(signer, 0);
+            assert_eq!(reply.nonce, 7);
+        }
+
+        #[tokio::test]
+        async fn rejects_query_signed_with_different_key() {
+            let forger = register(b"other key");
+            let query = forger
+                .sign(&Query::Store {
+                    nonce: 7,
+                    stored: stored(1, 666),
+                })
+                .unwrap();
+            let register = register(b"key");
+            let result = register
+                .handle(transport::Message::announce(BYZANTINE_PATH, query))
+                .await;
+            assert!(result
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid signature"));
+            assert_eq!(*register.local.lock().unwrap(), Stored::default());
+        }
+    }
+}
@@ -0,0 +1,515 @@
+//! A map of [`AtomicRegister`]s, whose keys are spread across independent
+//! groups of instances.
+//!
+//! Every instance of an [`AtomicRegister`] stores its value, and every
+//! operation waits for a quorum of them, so adding instances to a single
+//! group makes it more fault-tolerant, but not faster. A
+//! [`ShardedRegisterMap`] instead divides its keys between multiple
+//! [`Shard`]s, each of which is a separate group of instances with its own
+//! neighbors and [`QuorumPolicy`]. Each key is stored only by the instances
+//! of its shard, and operations on keys of different shards never wait for
+//! the same instances, so the map scales by adding shards.
+//!
+//! Keys are assigned to shards by hashing them with SHA-256, so that every
+//! instance agrees on the shard of each key. All instances must therefore be
+//! created with the same shards, in the same order.
+//!
+//! An instance of the map can be a member of any number of shards, and
+//! stores the registers of their keys. It can still read and write keys of
+//! the other shards, by performing operations on behalf of their instances,
+//! without holding a vote. See [`QuorumPolicy::without_vote`].
+//!
+//! The register of each key is created the first time that the key is
+//! used, and is kept for as long as the map is, so memory grows with the
+//! number of keys.
+//!
+//! # Routes
+//!
+//! Requests for the key `k` are made to `/map/{k}`, followed by the usual
+//! route of an [`AtomicRegister`], where `k` is encoded as hexadecimal bytes.
+//! For example, clients can read the key `"a"` with a `GET` request to
+//! `/map/61/register`, and the instances of its shard exchange its value at
+//! `/map/61/register/local`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use tokio_test;
+//! use todc_net::register::sharded::{Shard, ShardedRegisterMap};
+//!
+//! # tokio_test::block_on(async {
+//! // Two shards, each of which has a single instance.
+//! let map: ShardedRegisterMap<u32> =
+//!     ShardedRegisterMap::new(vec![Shard::member(Vec::new()), Shard::member(Vec::new())])
+//!         .unwrap();
+//! map.write("a", 123).await.unwrap();
+//! assert_eq!(map.read("a").await.unwrap(), 123);
+//! assert_eq!(map.read("b").await.unwrap(), 0);
+//! # })
+//! ```
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::http::StatusCode;
+use hyper::service::Service;
+use hyper::{Request, Response, Uri};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::abd_95::{AtomicRegister, RegisterError};
+use crate::quorum::QuorumPolicy;
+use crate::transport::{self, Handler, HttpTransport, Transport};
+use crate::{mk_response, GenericError};
+
+/// The prefix of the routes of the registers in a map.
+const MAP_PATH: &str = "/map/";
+
+/// The route at which clients read from and write to a register.
+const REGISTER_PATH: &str = "/register";
+
+/// A group of instances that stores the registers of some of the keys of a
+/// [`ShardedRegisterMap`].
+///
+/// See the [`sharded`](crate::register::sharded) module-level documentation
+/// for more details.
+#[derive(Clone, Debug)]
+pub struct Shard {
+    neighbors: Vec<Uri>,
+    member: bool,
+    quorum: QuorumPolicy,
+}
+
+impl Shard {
+    /// Creates a shard of which this instance is a member, alongside the
+    /// given neighbors.
+    ///
+    /// As for an [`AtomicRegister`], if the shard has `n` instances, then
+    /// each of them must be given the URLs of all `n - 1` of the others.
+    pub fn member(neighbors: Vec<Uri>) -> Self {
+        Self {
+            neighbors,
+            member: true,
+            quorum: QuorumPolicy::default(),
+        }
+    }
+
+    /// Creates a shard of which this instance is not a member, whose
+    /// instances are at the given URLs.
+    pub fn remote(instances: Vec<Uri>) -> Self {
+        Self {
+            neighbors: instances,
+            member: false,
+            quorum: QuorumPolicy::default(),
+        }
+    }
+
+    /// Sets the [`QuorumPolicy`] of the instances of the shard.
+    ///
+    /// Every instance of the map must be given the same policy for the
+    /// shard. Instances that are not members of it perform operations
+    /// without a vote, and wait for the same replies as the members do.
+    pub fn with_quorum_policy(mut self, policy: QuorumPolicy) -> Self {
+        self.quorum = policy;
+        self
+    }
+
+    /// Returns whether this instance is a member of the shard.
+    pub fn is_member(&self) -> bool {
+        self.member
+    }
+
+    /// Returns the URLs of the instances of the shard, other than this one.
+    pub fn neighbors(&self) -> &[Uri] {
+        &self.neighbors
+    }
+}
+
+/// A [`Transport`] that sends the messages of the register of one key in a
+/// map to the routes of that key.
+#[derive(Clone)]
+pub struct KeyTransport<Tr: Transport> {
+    key: Arc<str>,
+    inner: Tr,
+}
+
+impl<Tr: Transport> Transport for KeyTransport<Tr> {
+    async fn send(
+        &self,
+        neighbor: Uri,
+        mut message: transport::Message,
+    ) -> Result<Bytes, GenericError> {
+        message.path = Cow::Owned(format!(
+            "{MAP_PATH}{}{}",
+            encode_key(&self.key),
+            message.path
+        ));
+        self.inner.send(neighbor, message).await
+    }
+}
+
+/// A shard, along with the registers of its keys that have been used.
+struct ShardState<T: Clone + Debug + Default + DeserializeOwned + Ord + Send, Tr: Transport> {
+    shard: Shard,
+    registers: Mutex<HashMap<String, AtomicRegister<T, KeyTransport<Tr>>>>,
+}
+
+/// A map from string keys to atomic registers, whose keys are divided
+/// between independent groups of instances.
+///
+/// See the [`sharded`](crate::register::sharded) module-level documentation
+/// for more details.
+#[derive(Clone)]
+pub struct ShardedRegisterMap<
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send,
+    Tr: Transport = HttpTransport,
+> {
+    shards: Arc<Vec<ShardState<T, Tr>>>,
+    transport: Tr,
+}
+
+impl<T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static>
+    ShardedRegisterMap<T>
+{
+    /// Creates a new instance of the map with the given shards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the quorum policy of some shard cannot be
+    /// satisfied by its instances.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty.
+    pub fn new(shards: Vec<Shard>) -> Result<Self, RegisterError> {
+        Self::with_transport(shards, HttpTransport::default())
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+    > ShardedRegisterMap<T, Tr>
+{
+    /// Creates a new instance of the map with the given shards, that
+    /// communicates with the instances of each shard using the given
+    /// [`Transport`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the quorum policy of some shard cannot be
+    /// satisfied by its instances.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::quorum::QuorumPolicy;
+    /// use todc_net::register::sharded::{Shard, ShardedRegisterMap};
+    /// use todc_net::transport::HttpTransport;
+    ///
+    /// let url = |i: u32| -> Uri { format!("https://my-register-{i}.com").parse().unwrap() };
+    ///
+    /// // This instance is a member of the first shard, alongside instances 2
+    /// // and 3, and reaches the second shard through instances 4, 5 and 6.
+    /// let shards = vec![
+    ///     Shard::member(vec![url(2), url(3)]),
+    ///     Shard::remote(vec![url(4), url(5), url(6)])
+    ///         .with_quorum_policy(QuorumPolicy::FailureThreshold(1)),
+    /// ];
+    /// let map: ShardedRegisterMap<u32, HttpTransport> =
+    ///     ShardedRegisterMap::with_transport(shards, HttpTransport::default()).unwrap();
+    /// assert_eq!(map.shards().len(), 2);
+    ///
+    /// // A shard cannot tolerate a failure with only two instances.
+    /// let shards = vec![Shard::remote(vec![url(4), url(5)])
+    ///     .with_quorum_policy(QuorumPolicy::FailureThreshold(1))];
+    /// assert!(ShardedRegisterMap::<u32>::new(shards).is_err());
+    /// ```
+    pub fn with_transport(shards: Vec<Shard>, transport: Tr) -> Result<Self, RegisterError> {
+        assert!(!shards.is_empty(), "A map must have at least one shard");
+        let shards = shards
+            .into_iter()
+            .map(|mut shard| {
+                if shard.member {
+                    shard.quorum.thresholds(&shard.neighbors)?;
+                } else {
+                    shard.quorum = shard.quorum.without_vote(&shard.neighbors)?;
+                }
+                Ok(ShardState {
+                    shard,
+                    registers: Mutex::new(HashMap::new()),
+                })
+            })
+            .collect::<Result<Vec<_>, RegisterError>>()?;
+        Ok(Self {
+            shards: Arc::new(shards),
+            transport,
+        })
+    }
+
+    /// Returns the shards of the map, in order.
+    pub fn shards(&self) -> Vec<&Shard> {
+        self.shards.iter().map(|state| &state.shard).collect()
+    }
+
+    /// Returns the position of the shard that stores the given key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_net::register::sharded::{Shard, ShardedRegisterMap};
+    ///
+    /// let map: ShardedRegisterMap<u32> =
+    ///     ShardedRegisterMap::new(vec![Shard::member(Vec::new()); 4]).unwrap();
+    /// assert!(map.shard_of("a") < 4);
+    /// assert_eq!(map.shard_of("a"), map.shard_of("a"));
+    /// ```
+    pub fn shard_of(&self, key: &str) -> usize {
+        let digest = Sha256::digest(key.as_bytes());
+        let hash = u64::from_be_bytes(digest[..8].try_into().unwrap());
+        (hash % self.shards.len() as u64) as usize
+    }
+
+    /// Returns the register that stores the given key.
+    ///
+    /// The register is created if the key has not been used before.
+    pub fn register(&self, key: &str) -> AtomicRegister<T, KeyTransport<Tr>> {
+        let shard = &self.shards[self.shard_of(key)];
+        let mut registers = shard.registers.lock().unwrap();
+        if let Some(register) = registers.get(key) {
+            return register.clone();
+        }
+        let transport = KeyTransport {
+            key: Arc::from(key),
+            inner: self.transport.clone(),
+        };
+        let register = AtomicRegister::with_transport(shard.shard.neighbors.clone(), transport)
+            .with_quorum_policy(shard.shard.quorum.clone())
+            // The policy of every shard is checked when the map is created.
+            .unwrap();
+        registers.insert(key.to_string(), register.clone());
+        register
+    }
+
+    /// Returns the value associated with the given key.
+    ///
+    /// Keys that have never been written are associated with
+    /// `T::default()`.
+    pub async fn read(&self, key: &str) -> Result<T, RegisterError> {
+        self.register(key).read().await
+    }
+
+    /// Associates the given key with the specified value.
+    pub async fn write(&self, key: &str, value: T) -> Result<(), RegisterError> {
+        self.register(key).write(value).await
+    }
+
+    /// Returns the key that a path is addressed to, and the route of its
+    /// register, if the path is of the form `/map/{k}/...`.
+    fn route<'a>(&self, path: &'a str) -> Option<(String, &'a str)> {
+        let rest = path.strip_prefix(MAP_PATH)?;
+        let split = rest.find('/')?;
+        let key = decode_key(&rest[..split])?;
+        Some((key, &rest[split..]))
+    }
+
+    /// Returns whether this instance is a member of the shard that stores
+    /// the given key.
+    fn stores(&self, key: &str) -> bool {
+        self.shards[self.shard_of(key)].shard.member
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+    > Handler for ShardedRegisterMap<T, Tr>
+{
+    fn handle(
+        &self,
+        mut message: transport::Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        let me = self.clone();
+        async move {
+            let (key, path) = me
+                .route(&message.path)
+                .filter(|(key, _)| me.stores(key))
+                .map(|(key, path)| (key, path.to_string()))
+                .ok_or_else(|| GenericError::from(format!("Unknown route {}", message.path)))?;
+            message.path = Cow::Owned(path);
+            me.register(&key).handle(message).await
+        }
+    }
+}
+
+impl<
+        T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+        Tr: Transport,
+    > Service<Request<Incoming>> for ShardedRegisterMap<T, Tr>
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    /// Passes a request made to `/map/{k}/...` to the register of the key
+    /// `k`.
+    ///
+    /// Instances that are not members of the shard of `k` only serve
+    /// requests made to `/map/{k}/register`, which they perform on behalf of
+    /// the instances of the shard.
+    fn call(&self, mut req: Request<Incoming>) -> Self::Future {
+        let Some((key, path)) = self
+            .route(req.uri().path())
+            .filter(|(key, path)| self.stores(key) || *path == REGISTER_PATH)
+        else {
+            return Box::pin(async { mk_response(StatusCode::NOT_FOUND, "404 Not Found".into()) });
+        };
+        let path_and_query = match req.uri().query() {
+            Some(query) => format!("{path}?{query}"),
+            None => path.to_string(),
+        };
+        let mut parts = req.uri().clone().into_parts();
+        parts.path_and_query = match path_and_query.parse() {
+            Ok(path_and_query) => Some(path_and_query),
+            Err(error) => return Box::pin(async move { Err(error.into()) }),
+        };
+        *req.uri_mut() = match Uri::from_parts(parts) {
+            Ok(uri) => uri,
+            Err(error) => return Box::pin(async move { Err(error.into()) }),
+        };
+        self.register(&key).call(req)
+    }
+}
+
+/// Encodes a key as the hexadecimal representation of its bytes, so that it
+/// can be part of a path.
+fn encode_key(key: &str) -> String {
+    key.bytes().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decodes a key that was encoded by [`encode_key`].
+fn decode_key(encoded: &str) -> Option<String> {
+    if !encoded.len().is_multiple_of(2) {
+        return None;
+    }
+    let bytes = (0..encoded.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(encoded.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(shards: usize) -> ShardedRegisterMap<u32> {
+        ShardedRegisterMap::new(vec![Shard::member(Vec::new()); shards]).unwrap()
+    }
+
+    mod decode_key {
+        use super::*;
+
+        #[test]
+        fn inverts_encode_key() {
+            for key in ["", "a", "hello/world", "ключ"] {
+                assert_eq!(decode_key(&encode_key(key)).as_deref(), Some(key));
+            }
+        }
+
+        #[test]
+        fn rejects_invalid_hexadecimal() {
+            assert_eq!(decode_key("6"), None);
+            assert_eq!(decode_key("zz"), None);
+            assert_eq!(decode_key("ff"), None);
+        }
+    }
+
+    mod route {
+        use super::*;
+
+        #[test]
+        fn splits_key_from_route_of_register() {
+            assert_eq!(
+                map(2).route("/map/6162/register/local"),
+                Some((String::from("ab"), "/register/local"))
+            );
+        }
+
+        #[test]
+        fn rejects_paths_outside_of_map() {
+            assert_eq!(map(2).route("/register/local"), None);
+            assert_eq!(map(2).route("/map/register"), None);
+            assert_eq!(map(2).route("/map/61"), None);
+        }
+    }
+
+    mod shard_of {
+        use super::*;
+
+        #[test]
+        fn spreads_keys_across_shards() {
+            let map = map(4);
+            let mut counts = [0; 4];
+            for i in 0..100 {
+                counts[map.shard_of(&format!("key-{i}"))] += 1;
+            }
+            assert!(counts.iter().all(|&count| count > 0));
+        }
+
+        #[test]
+        fn places_every_key_in_single_shard() {
+            assert_eq!(map(1).shard_of("a"), 0);
+        }
+    }
+
+    mod with_transport {
+        use super::*;
+
+        #[test]
+        fn removes_vote_of_remote_shards() {
+            let instances: Vec<Uri> = (0..3)
+                .map(|i| format!("http://test-{i}.com").parse().unwrap())
+                .collect();
+            let map: ShardedRegisterMap<u32> =
+                ShardedRegisterMap::new(vec![Shard::remote(instances)]).unwrap();
+            assert_eq!(map.register("a").quorum_policy().weight(), 0);
+        }
+
+        #[test]
+        fn rejects_remote_shard_without_instances() {
+            let result = ShardedRegisterMap::<u32>::new(vec![Shard::remote(Vec::new())]);
+            assert!(matches!(result, Err(RegisterError::InvalidThresholds(_))));
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_without_shards() {
+            let _ = ShardedRegisterMap::<u32>::new(Vec::new());
+        }
+    }
+
+    mod write {
+        use super::*;
+
+        #[tokio::test]
+        async fn only_changes_one_key() {
+            let map = map(2);
+            map.write("a", 123).await.unwrap();
+            assert_eq!(map.read("a").await.unwrap(), 123);
+            assert_eq!(map.read("b").await.unwrap(), 0);
+        }
+    }
+}
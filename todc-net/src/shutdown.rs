@@ -0,0 +1,259 @@
+//! Stopping instances gracefully.
+//!
+//! An instance that is stopped abruptly, such as by killing its process,
+//! abandons the operations that it is coordinating, and a client that was
+//! waiting on one of them cannot tell whether it took effect. When an
+//! instance is restarted deliberately, such as during a rolling restart or
+//! a reconfiguration, it can instead be stopped with a [`Shutdown`], which
+//! lets its ongoing operations complete before it exits.
+//!
+//! A shutdown proceeds in two stages:
+//!
+//! 1. While _draining_, an [`AtomicRegister`](crate::register::AtomicRegister)
+//!    rejects new operations with
+//!    [`RegisterError::ShuttingDown`](crate::register::abd_95::RegisterError::ShuttingDown),
+//!    which requests to `/register` are answered with as
+//!    `503 Service Unavailable`, so that clients retry them elsewhere.
+//!    Operations that were already in flight complete as usual, and the
+//!    instance keeps serving its neighbors, so that their operations can
+//!    complete too.
+//! 2. Once _stopped_, any quorum round that is still waiting on its
+//!    neighbors is cancelled, and fails with
+//!    [`RegisterError::ShuttingDown`](crate::register::abd_95::RegisterError::ShuttingDown),
+//!    and the serving helpers of this crate, such as
+//!    [`run_local`](crate::cluster::run_local), stop accepting connections.
+//!
+//! [`Shutdown::shutdown`] drains an instance for at most a grace period, and
+//! then stops it.
+//!
+//! # Examples
+//!
+//! ```
+//! # use tokio_test;
+//! use std::time::Duration;
+//! use todc_net::register::AtomicRegister;
+//! use todc_net::shutdown::Shutdown;
+//!
+//! # tokio_test::block_on(async {
+//! let shutdown = Shutdown::new();
+//! let register: AtomicRegister<u32> = AtomicRegister::default().with_shutdown(shutdown.clone());
+//! register.write(123).await.unwrap();
+//!
+//! assert!(shutdown.shutdown(Duration::from_secs(5)).await);
+//! assert!(register.write(456).await.is_err());
+//! # })
+//! ```
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// The progress of a [`Shutdown`].
+#[derive(Clone, Copy, Debug, Default)]
+struct State {
+    draining: bool,
+    stopped: bool,
+    in_flight: usize,
+}
+
+/// A handle through which an instance, and every clone of it, can be shut
+/// down gracefully.
+///
+/// Clones of a handle share their state, so an instance can be shut down
+/// through any of them. See the [`shutdown`](crate::shutdown) module-level
+/// documentation for more details.
+#[derive(Clone, Debug)]
+pub struct Shutdown {
+    state: watch::Sender<State>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    /// Creates a handle for an instance that is running.
+    pub fn new() -> Self {
+        let (state, _) = watch::channel(State::default());
+        Self { state }
+    }
+
+    /// Begins draining, so that new operations are rejected, while those
+    /// that are already in flight continue.
+    pub fn drain(&self) {
+        self.state
+            .send_if_modified(|state| !std::mem::replace(&mut state.draining, true));
+    }
+
+    /// Stops the instance, which cancels any operations that are still in
+    /// flight. Stopping an instance also drains it.
+    pub fn stop(&self) {
+        self.state.send_if_modified(|state| {
+            let changed = !(state.draining && state.stopped);
+            state.draining = true;
+            state.stopped = true;
+            changed
+        });
+    }
+
+    /// Returns whether the instance has begun draining.
+    pub fn is_draining(&self) -> bool {
+        self.state.borrow().draining
+    }
+
+    /// Returns whether the instance has stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.state.borrow().stopped
+    }
+
+    /// Returns the number of operations that are in flight.
+    pub fn in_flight(&self) -> usize {
+        self.state.borrow().in_flight
+    }
+
+    /// Registers an operation as being in flight until the returned guard
+    /// is dropped, or returns `None` if the instance is draining, in which
+    /// case the operation must not begin.
+    pub fn enter(&self) -> Option<InFlight> {
+        let mut entered = false;
+        self.state.send_modify(|state| {
+            if !state.draining {
+                state.in_flight += 1;
+                entered = true;
+            }
+        });
+        entered.then(|| InFlight {
+            state: self.state.clone(),
+        })
+    }
+
+    /// Waits until the instance has stopped.
+    pub async fn stopped(&self) {
+        self.wait_for(|state| state.stopped).await;
+    }
+
+    /// Waits until the instance is draining and no operations are in flight.
+    pub async fn drained(&self) {
+        self.wait_for(|state| state.draining && state.in_flight == 0)
+            .await;
+    }
+
+    /// Drains the instance until every operation in flight has completed,
+    /// or until the grace period has passed, and then stops it.
+    ///
+    /// Returns whether every operation completed within the grace period.
+    /// Operations that did not are cancelled, and this method waits until
+    /// they have been.
+    pub async fn shutdown(&self, grace: Duration) -> bool {
+        self.drain();
+        let drained = tokio::time::timeout(grace, self.drained()).await.is_ok();
+        if !drained {
+            tracing::warn!(
+                in_flight = self.in_flight(),
+                "grace period ended before operations completed"
+            );
+        }
+        self.stop();
+        self.drained().await;
+        drained
+    }
+
+    /// Waits until the state satisfies the condition.
+    async fn wait_for(&self, condition: impl FnMut(&State) -> bool) {
+        let mut receiver = self.state.subscribe();
+        // The sender is owned by this handle, and so is never dropped while
+        // waiting.
+        let _ = receiver.wait_for(condition).await;
+    }
+}
+
+/// A guard that marks an operation as being in flight, until it is dropped.
+///
+/// See [`Shutdown::enter`].
+#[derive(Debug)]
+pub struct InFlight {
+    state: watch::Sender<State>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.state.send_modify(|state| state.in_flight -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod enter {
+        use super::*;
+
+        #[test]
+        fn counts_operations_in_flight() {
+            let shutdown = Shutdown::new();
+            let first = shutdown.enter().unwrap();
+            let second = shutdown.enter().unwrap();
+            assert_eq!(shutdown.in_flight(), 2);
+            drop(first);
+            drop(second);
+            assert_eq!(shutdown.in_flight(), 0);
+        }
+
+        #[test]
+        fn is_rejected_while_draining() {
+            let shutdown = Shutdown::new();
+            shutdown.drain();
+            assert!(shutdown.enter().is_none());
+            assert_eq!(shutdown.in_flight(), 0);
+        }
+    }
+
+    mod stop {
+        use super::*;
+
+        #[test]
+        fn also_drains() {
+            let shutdown = Shutdown::new();
+            shutdown.stop();
+            assert!(shutdown.is_draining());
+            assert!(shutdown.is_stopped());
+        }
+
+        #[test]
+        fn is_shared_by_clones() {
+            let shutdown = Shutdown::new();
+            shutdown.clone().stop();
+            assert!(shutdown.is_stopped());
+        }
+    }
+
+    mod shutdown {
+        use super::*;
+
+        #[tokio::test]
+        async fn waits_for_operations_in_flight() {
+            let shutdown = Shutdown::new();
+            let operation = shutdown.enter().unwrap();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                drop(operation);
+            });
+            assert!(shutdown.shutdown(Duration::from_secs(5)).await);
+            assert!(shutdown.is_stopped());
+        }
+
+        #[tokio::test]
+        async fn stops_operations_after_grace_period() {
+            let shutdown = Shutdown::new();
+            let operation = shutdown.enter().unwrap();
+            let handle = shutdown.clone();
+            tokio::spawn(async move {
+                handle.stopped().await;
+                drop(operation);
+            });
+            assert!(!shutdown.shutdown(Duration::from_millis(10)).await);
+            assert_eq!(shutdown.in_flight(), 0);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Durable storage for the state of an instance, so that it survives
+//! restarts.
+//!
+//! Algorithms such as the [`AtomicRegister`](crate::register::AtomicRegister)
+//! assume that instances either run forever or crash and never return. If an
+//! instance instead restarts, and forgets the state it had acknowledged to
+//! its neighbors, then guarantees such as atomicity may be violated. To
+//! support deployments in which instances recover from crashes, an instance
+//! can be given a [`Storage`], to which it writes its state before
+//! acknowledging any change to it, and from which it reads its state when it
+//! starts.
+//!
+//! This module contains a [`FileStorage`], which writes state to a file, and a
+//! [`MemoryStorage`], which keeps state in memory and is intended for tests.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use todc_net::register::AtomicRegister;
+//! use todc_net::storage::FileStorage;
+//!
+//! let storage = FileStorage::new("/var/lib/my-register/state.json");
+//! let register: AtomicRegister<u32> = AtomicRegister::new(Vec::new())
+//!     .with_storage(storage)
+//!     .unwrap();
+//! ```
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::GenericError;
+
+/// A place to durably store the state of an instance.
+///
+/// State is stored as an opaque sequence of bytes, so that the format is
+/// determined by the instance.
+pub trait Storage: Send + Sync + 'static {
+    /// Returns the most recently stored state, or `None` if no state has
+    /// been stored.
+    fn load(&self) -> Result<Option<Vec<u8>>, GenericError>;
+
+    /// Stores a new state, replacing any previous one.
+    ///
+    /// The state must be durable by the time this method returns. Instances
+    /// call this method on a blocking thread, so it may block while waiting
+    /// for I/O.
+    fn store(&self, state: &[u8]) -> Result<(), GenericError>;
+}
+
+/// A [`Storage`] that writes state to a file.
+///
+/// State is first written to a temporary file alongside the destination,
+/// which is then renamed over it, so that a crash in the middle of a write
+/// never leaves the file partially written. The directory containing the
+/// file is synced after the rename, so that the rename itself survives a
+/// crash.
+#[derive(Clone, Debug)]
+pub struct FileStorage {
+    path: PathBuf,
+}
+
+impl FileStorage {
+    /// Creates a storage that writes state to the file at `path`.
+    ///
+    /// The file is not created until state is first stored, but its parent
+    /// directory must already exist.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the path of the temporary file that state is written to.
+    fn temporary_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".tmp");
+        path.into()
+    }
+
+    /// Returns the directory that contains the file.
+    fn directory(&self) -> &Path {
+        match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        }
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&self) -> Result<Option<Vec<u8>>, GenericError> {
+        match fs::read(&self.path) {
+            Ok(state) => Ok(Some(state)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn store(&self, state: &[u8]) -> Result<(), GenericError> {
+        let temporary = self.temporary_path();
+        let mut file = fs::File::create(&temporary)?;
+        file.write_all(state)?;
+        file.sync_all()?;
+        fs::rename(&temporary, &self.path)?;
+        // Directories cannot be opened as files on every platform, but on
+        // those where they can, the rename is only durable once the
+        // directory is synced.
+        #[cfg(unix)]
+        fs::File::open(self.directory())?.sync_all()?;
+        Ok(())
+    }
+}
+
+/// A [`Storage`] that keeps state in memory.
+///
+/// Clones share the same state, so a clone can be given to a new instance to
+/// simulate an instance that restarts without losing its state.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    state: Arc<Mutex<Option<Vec<u8>>>>,
+}
+
+impl MemoryStorage {
+    /// Creates an empty storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&self) -> Result<Option<Vec<u8>>, GenericError> {
+        Ok(self.state.lock().unwrap().clone())
+    }
+
+    fn store(&self, state: &[u8]) -> Result<(), GenericError> {
+        *self.state.lock().unwrap() = Some(state.to_vec());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod file_storage {
+        use super::*;
+
+        fn storage(name: &str) -> FileStorage {
+            let path =
+                std::env::temp_dir().join(format!("todc-net-{}-{name}.json", std::process::id()));
+            let _ = fs::remove_file(&path);
+            FileStorage::new(path)
+        }
+
+        #[test]
+        fn loads_nothing_if_file_does_not_exist() {
+            let storage = storage("missing");
+            assert_eq!(storage.load().unwrap(), None);
+        }
+
+        #[test]
+        fn loads_most_recently_stored_state() {
+            let storage = storage("stored");
+            storage.store(b"first").unwrap();
+            storage.store(b"second").unwrap();
+            assert_eq!(storage.load().unwrap(), Some(b"second".to_vec()));
+            assert!(!storage.temporary_path().exists());
+            fs::remove_file(&storage.path).unwrap();
+        }
+
+        #[test]
+        fn stores_state_in_working_directory_if_path_has_no_parent() {
+            let storage = FileStorage::new("state.json");
+            assert_eq!(storage.directory(), Path::new("."));
+        }
+    }
+
+    mod memory_storage {
+        use super::*;
+
+        #[test]
+        fn shares_state_between_clones() {
+            let storage = MemoryStorage::new();
+            assert_eq!(storage.load().unwrap(), None);
+            storage.clone().store(b"state").unwrap();
+            assert_eq!(storage.load().unwrap(), Some(b"state".to_vec()));
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! Deterministic simulations of clusters of instances, for testing.
+//!
+//! A [`SimulatedCluster`] runs `n` instances of a service, such as an
+//! [`AtomicRegister`], as hosts in a [`turmoil`] simulation, and wires each
+//! of them up with the URLs of all of the others. Tests can then interact
+//! with the instances from simulated clients, and inject faults into the
+//! simulated network, to check that a service behaves correctly no matter
+//! how messages are delayed or lost.
+//!
+//! The `i`th instance is served at [`url(i)`](url), by the host named
+//! [`host(i)`](host). Faults between instances can be injected from within
+//! the simulation using [`partition`], [`repair`], [`hold`] and [`release`],
+//! and instances can be crashed and restarted between steps of the
+//! simulation using [`crash`](SimulatedCluster::crash) and
+//! [`bounce`](SimulatedCluster::bounce).
+//!
+//! For more thorough testing, a [`Nemesis`] can inject randomized faults
+//! into a cluster while it runs, and a [`Workload`] can generate a randomized
+//! mix of operations to perform on a register. Both are derived from seeds,
+//! and so, along with a seeded simulation, any failure they uncover can be
+//! reproduced. With the `history` feature enabled, the operations performed
+//! can be recorded and checked for linearizability.
+//!
+//! # Modeling Deployments
+//!
+//! By default, every message in a simulation is delivered with the same
+//! distribution of latencies. To approximate a realistic deployment, such as
+//! a cluster spread across racks, availability zones or regions, place its
+//! instances according to a [`Topology`]. The latency of each link between
+//! instances then depends on how far apart they are, with [presets](LinkLatency)
+//! for each [`Distance`] that can be overridden per link. This makes it
+//! possible to compare how long quorum operations take in different
+//! deployments.
+//!
+//! # Replaying Failures
+//!
+//! Every test thread has a single [`seed`], which is chosen at random the
+//! first time it is needed. Clusters created without an explicit seed derive
+//! the randomness of their simulation from it, and tests should derive any
+//! workloads and nemeses from it too. Each run of a cluster logs its seed,
+//! and a nemesis logs every fault it injects and heals, all with
+//! [`tracing`](https://docs.rs/tracing). With a subscriber installed, the
+//! output of a failed test therefore describes exactly what happened.
+//!
+//! To replay a failure, call [`replay`] with the logged seed at the start of
+//! the test, or set the [`SEED_VAR`] environment variable to it. Either way,
+//! the test then reconstructs the identical simulation, workload and fault
+//! schedule, provided that the services it runs are themselves
+//! deterministic.
+//!
+//! This module requires the `turmoil` feature. With it enabled, _all_ network
+//! IO performed by this crate goes through the simulated network, so
+//! instances can only communicate from within a simulation.
+//!
+//! # Examples
+//!
+//! Check that a value written through one instance of a register can be read
+//! through another, even if they cannot communicate directly.
+//!
+//! ```no_run
+//! use todc_net::register::AtomicRegister;
+//! use todc_net::testing::{self, SimulatedCluster};
+//!
+//! let mut cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+//! let first = cluster.register_client(0);
+//! let second = cluster.register_client(1);
+//! cluster.client("client", async move {
+//!     testing::partition(0, 1);
+//!     first.write(123).await.unwrap();
+//!     assert_eq!(second.read().await.unwrap(), 123);
+//!     Ok(())
+//! });
+//! cluster.run().unwrap();
+//! ```
+//!
+//! Check that a register remains linearizable while a nemesis injects
+//! faults. This example requires the `history` feature.
+//!
+//! ```no_run
+//! use todc_net::register::AtomicRegister;
+//! use todc_net::register::history::HistorySink;
+//! use todc_net::testing::{self, Nemesis, SimulatedCluster, Workload};
+//! use todc_utils::specifications::register::RegisterSpecification;
+//! use todc_utils::WGLChecker;
+//!
+//! let seed = testing::seed();
+//! let sink = HistorySink::new();
+//! let mut cluster: SimulatedCluster<AtomicRegister<u32>> =
+//!     SimulatedCluster::with_history(3, sink.clone());
+//! cluster.add_workload(&Workload::new(seed).with_operations(50));
+//! cluster.run_with_nemesis(&mut Nemesis::new(seed)).unwrap();
+//!
+//! let history = sink.history();
+//! assert!(WGLChecker::<RegisterSpecification<u32>>::is_linearizable(history));
+//! ```
+use std::cell::Cell;
+use std::env;
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::Service;
+use hyper::{Request, Response, Uri};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value as JSON;
+use turmoil::net::TcpListener;
+use turmoil::{Builder, Sim};
+
+use crate::client::RegisterClient;
+#[cfg(feature = "history")]
+use crate::register::history::HistorySink;
+use crate::register::AtomicRegister;
+use crate::{GenericError, ResponseResult, TokioIo};
+
+mod nemesis;
+mod topology;
+mod workload;
+
+pub use self::nemesis::{Fault, FaultKind, Nemesis};
+pub use self::topology::{Distance, LinkLatency, Location, Topology};
+pub use self::workload::{Operation, ScheduledOperation, Workload};
+
+/// The prefix of the name of each host in a simulated cluster.
+pub const HOST_PREFIX: &str = "server";
+
+/// The port on which each instance in a simulated cluster is served.
+pub const PORT: u16 = 9999;
+
+/// The environment variable that, if set, determines the [`seed`] of every
+/// test thread.
+pub const SEED_VAR: &str = "TODC_SEED";
+
+thread_local! {
+    static SEED: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Returns the seed of the current thread, from which the randomness of a
+/// test should be derived.
+///
+/// The seed is chosen the first time this function is called on a thread,
+/// and every later call on the same thread returns the same seed. It is
+/// taken from the [`SEED_VAR`] environment variable if it is set, and is
+/// otherwise chosen at random.
+///
+/// # Panics
+///
+/// Panics if [`SEED_VAR`] is set to something other than a `u64`.
+pub fn seed() -> u64 {
+    SEED.with(|cell| match cell.get() {
+        Some(seed) => seed,
+        None => {
+            let seed = match env::var(SEED_VAR) {
+                Ok(seed) => seed
+                    .parse()
+                    .unwrap_or_else(|_| panic!("{SEED_VAR} must be a valid u64, not {seed:?}")),
+                Err(_) => rand::thread_rng().gen(),
+            };
+            cell.set(Some(seed));
+            seed
+        }
+    })
+}
+
+/// Sets the [`seed`] of the current thread, so that a test that previously
+/// ran with `seed` reconstructs the identical simulation, workload and fault
+/// schedule.
+///
+/// This must be called before anything on the thread calls [`seed`], such
+/// as by creating a cluster, or else the randomness derived from the old
+/// seed is not replayed.
+pub fn replay(seed: u64) {
+    SEED.with(|cell| cell.set(Some(seed)));
+}
+
+/// Returns the name of the host that serves the `i`th instance of a
+/// simulated cluster.
+pub fn host(i: usize) -> String {
+    format!("{HOST_PREFIX}-{i}")
+}
+
+/// Returns the URL of the `i`th instance of a simulated cluster.
+pub fn url(i: usize) -> Uri {
+    format!("http://{}:{PORT}", host(i)).parse().unwrap()
+}
+
+/// Drops all messages sent between the `i`th and `j`th instances, until
+/// the link between them is [`repair`]ed.
+///
+/// This must be called from within the simulation, such as by a client.
+pub fn partition(i: usize, j: usize) {
+    turmoil::partition(host(i), host(j));
+}
+
+/// Repairs the link between the `i`th and `j`th instances, after it was
+/// [`partition`]ed.
+///
+/// This must be called from within the simulation, such as by a client.
+pub fn repair(i: usize, j: usize) {
+    turmoil::repair(host(i), host(j));
+}
+
+/// Delays all messages sent between the `i`th and `j`th instances, until
+/// they are [`release`]d.
+///
+/// This must be called from within the simulation, such as by a client.
+pub fn hold(i: usize, j: usize) {
+    turmoil::hold(host(i), host(j));
+}
+
+/// Delivers all messages sent between the `i`th and `j`th instances that
+/// were [`hold`]ing.
+///
+/// This must be called from within the simulation, such as by a client.
+pub fn release(i: usize, j: usize) {
+    turmoil::release(host(i), host(j));
+}
+
+/// Submits a GET request to the URL, from within the simulation.
+pub async fn get(url: Uri) -> ResponseResult {
+    crate::get(url).await
+}
+
+/// Submits a POST request, with a JSON body, to the URL, from within the
+/// simulation.
+pub async fn post(url: Uri, body: JSON) -> ResponseResult {
+    crate::post(url, Bytes::from(body.to_string())).await
+}
+
+/// A cluster of instances of a service, each running on its own host in a
+/// [`turmoil`] simulation.
+///
+/// See the [`testing`](crate::testing) module-level documentation for more
+/// details.
+pub struct SimulatedCluster<'a, S> {
+    sim: Sim<'a>,
+    seed: Option<u64>,
+    instances: Vec<S>,
+}
+
+impl<'a, T> SimulatedCluster<'a, AtomicRegister<T>>
+where
+    T: Clone + std::fmt::Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+{
+    /// Creates a cluster of `n` instances of an [`AtomicRegister`].
+    pub fn new(n: usize) -> Self {
+        Self::with_instances(n, |_, neighbors| AtomicRegister::new(neighbors))
+    }
+
+    /// Creates a cluster of `n` instances of an [`AtomicRegister`], which
+    /// record the operations they perform in the given sink.
+    ///
+    /// This method requires the `history` feature.
+    #[cfg(feature = "history")]
+    pub fn with_history(n: usize, sink: HistorySink<T>) -> Self {
+        Self::with_instances(n, |_, neighbors| {
+            AtomicRegister::new(neighbors).with_history(sink.clone())
+        })
+    }
+
+    /// Returns a client for the `i`th instance of the register.
+    pub fn register_client(&self, i: usize) -> RegisterClient<T> {
+        RegisterClient::new(url(i))
+    }
+}
+
+impl<'a> SimulatedCluster<'a, AtomicRegister<u32>> {
+    /// Adds clients to the simulation that perform the operations of a
+    /// workload.
+    ///
+    /// Each instance receives its operations from its own client, named
+    /// `client-{i}`, which performs them one at a time, in order of arrival,
+    /// through a [`RegisterClient`]. Only `client-0` writes, since the
+    /// register is single-writer, and the other clients only read.
+    /// Operations that fail or time out are abandoned, and may or may not
+    /// have taken effect.
+    pub fn add_workload(&mut self, workload: &Workload) {
+        let n = self.instances.len();
+        let mut schedules = vec![Vec::new(); n];
+        for scheduled in workload.generate(n) {
+            schedules[scheduled.instance].push(scheduled);
+        }
+        for (i, schedule) in schedules.into_iter().enumerate() {
+            let client = self.register_client(i).with_timeout(workload.timeout());
+            self.sim.client(format!("client-{i}"), async move {
+                let start = tokio::time::Instant::now();
+                for scheduled in schedule {
+                    tokio::time::sleep_until(start + scheduled.at).await;
+                    // Abandoned operations are left pending in any history
+                    // recorded by the instance.
+                    let _ = match scheduled.operation {
+                        Operation::Read => client.read().await.map(|_| ()),
+                        Operation::Write(value) => client.write(value).await,
+                    };
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
+impl<'a, S> SimulatedCluster<'a, S>
+where
+    S: Service<Request<Incoming>, Response = Response<Full<Bytes>>, Error = GenericError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    /// Creates a cluster of `n` instances of a service, where the `i`th
+    /// instance is created by calling `new` with `i` and the URLs of all
+    /// other instances.
+    ///
+    /// The randomness of the simulation is derived from the [`seed`] of the
+    /// current thread.
+    pub fn with_instances(n: usize, new: impl Fn(usize, Vec<Uri>) -> S) -> Self {
+        Self::from_seed(seed(), n, new)
+    }
+
+    /// Creates a cluster of `n` instances of a service, as in
+    /// [`with_instances`](Self::with_instances), in a simulation whose
+    /// randomness is derived from `seed`.
+    pub fn from_seed(seed: u64, n: usize, new: impl Fn(usize, Vec<Uri>) -> S) -> Self {
+        Self::from_builder(&Builder::new(), seed, n, new)
+    }
+
+    /// Creates a cluster of `n` instances of a service, as in
+    /// [`with_instances`](Self::with_instances), in a simulation that is
+    /// configured by the builder and whose randomness is derived from `seed`.
+    pub fn from_builder(
+        builder: &Builder,
+        seed: u64,
+        n: usize,
+        new: impl Fn(usize, Vec<Uri>) -> S,
+    ) -> Self {
+        let rng = StdRng::seed_from_u64(seed);
+        let mut cluster = Self::from_sim(builder.build_with_rng(Box::new(rng)), n, new);
+        cluster.seed = Some(seed);
+        cluster
+    }
+
+    /// Creates a cluster of `n` instances of a service, as in
+    /// [`with_instances`](Self::with_instances), within an existing
+    /// simulation.
+    ///
+    /// This can be used to configure the simulation before instances are
+    /// added to it. The seed of such a simulation is unknown, so runs of the
+    /// cluster cannot log it.
+    pub fn from_sim(mut sim: Sim<'a>, n: usize, new: impl Fn(usize, Vec<Uri>) -> S) -> Self {
+        let urls: Vec<Uri> = (0..n).map(url).collect();
+        let mut instances = Vec::new();
+        for i in 0..n {
+            let mut neighbors = urls.clone();
+            neighbors.remove(i);
+            let instance = new(i, neighbors);
+            let instance_clone = instance.clone();
+            sim.host(host(i), move || serve(instance_clone.clone()));
+            instances.push(instance);
+        }
+        Self {
+            sim,
+            seed: None,
+            instances,
+        }
+    }
+}
+
+impl<'a, S> SimulatedCluster<'a, S> {
+    /// Returns the instances in the cluster.
+    ///
+    /// Each instance shares its state with the instance served by its host,
+    /// so operations performed on it from within the simulation affect the
+    /// cluster.
+    pub fn instances(&self) -> &[S] {
+        &self.instances
+    }
+
+    /// Returns the `i`th instance in the cluster.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not smaller than the number of instances.
+    pub fn instance(&self, i: usize) -> &S {
+        &self.instances[i]
+    }
+
+    /// Returns the seed from which the randomness of the simulation is
+    /// derived, or `None` if the cluster was created from an existing
+    /// simulation.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Returns the underlying simulation.
+    pub fn sim(&mut self) -> &mut Sim<'a> {
+        &mut self.sim
+    }
+
+    /// Adds a client, with the given name, to the simulation.
+    ///
+    /// The simulation runs until every client completes.
+    pub fn client<F>(&mut self, name: &str, client: F)
+    where
+        F: Future<Output = turmoil::Result> + 'static,
+    {
+        self.sim.client(name, client);
+    }
+
+    /// Places the instances of the cluster according to a topology, which
+    /// determines the latency of the messages that they send to one another.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the topology does not contain exactly one location for each
+    /// instance.
+    pub fn set_topology(&mut self, topology: &Topology) {
+        assert_eq!(
+            topology.len(),
+            self.instances.len(),
+            "topology must place every instance of the cluster"
+        );
+        topology.apply(&mut self.sim);
+    }
+
+    /// Runs the simulation until every client completes.
+    pub fn run(&mut self) -> turmoil::Result {
+        self.log_seed();
+        self.sim.run()
+    }
+
+    /// Runs the simulation until every client completes, while the nemesis
+    /// injects faults into the cluster.
+    ///
+    /// Any fault that is still active once every client has completed is
+    /// healed before this method returns.
+    pub fn run_with_nemesis(&mut self, nemesis: &mut Nemesis) -> turmoil::Result {
+        self.log_seed();
+        tracing::info!(seed = nemesis.seed(), "running with a nemesis");
+        let instances = self.instances.len();
+        loop {
+            nemesis.tick(&mut self.sim, instances);
+            if self.sim.step()? {
+                break;
+            }
+        }
+        nemesis.heal(&mut self.sim);
+        Ok(())
+    }
+
+    /// Runs a single step of the simulation, and returns whether every
+    /// client has completed.
+    pub fn step(&mut self) -> turmoil::Result<bool> {
+        self.sim.step()
+    }
+
+    /// Crashes the host of the `i`th instance, so that it stops responding
+    /// to messages.
+    pub fn crash(&mut self, i: usize) {
+        self.sim.crash(host(i));
+    }
+
+    /// Restarts the host of the `i`th instance.
+    ///
+    /// The restarted host serves the same instance as before, so it keeps
+    /// any state that the instance held in memory.
+    pub fn bounce(&mut self, i: usize) {
+        self.sim.bounce(host(i));
+    }
+
+    /// Logs the seed of the simulation, if it is known, so that a failed run
+    /// can be replayed.
+    fn log_seed(&self) {
+        if let Some(seed) = self.seed {
+            tracing::info!(
+                seed,
+                "running a simulated cluster, replay it with {SEED_VAR}={seed}"
+            );
+        }
+    }
+
+    /// Returns the underlying simulation and the instances in the cluster.
+    pub fn into_parts(self) -> (Sim<'a>, Vec<S>) {
+        (self.sim, self.instances)
+    }
+}
+
+/// Serves an instance of a service on its host.
+async fn serve<S>(service: S) -> turmoil::Result
+where
+    S: Service<Request<Incoming>, Response = Response<Full<Bytes>>, Error = GenericError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    let addr = (IpAddr::from(Ipv4Addr::UNSPECIFIED), PORT);
+    let listener = TcpListener::bind(addr).await?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let io = TokioIo::new(stream);
+        let service = service.clone();
+        tokio::task::spawn(async move {
+            if let Err(err) = http1::Builder::new().serve_connection(io, service).await {
+                tracing::debug!(error = %err, "connection failed");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_refer_to_hosts_by_name() {
+        assert_eq!(host(2), "server-2");
+        assert_eq!(url(2), Uri::from_static("http://server-2:9999"));
+    }
+
+    #[test]
+    fn gives_each_instance_all_other_instances_as_neighbors() {
+        let cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+        assert_eq!(cluster.instance(1).neighbors(), vec![url(0), url(2)]);
+    }
+
+    #[test]
+    fn seed_is_the_same_for_every_call_on_a_thread() {
+        assert_eq!(seed(), seed());
+    }
+
+    #[test]
+    fn replay_sets_the_seed() {
+        replay(123);
+        assert_eq!(seed(), 123);
+    }
+
+    #[test]
+    fn clusters_are_seeded_by_the_seed_of_the_thread() {
+        replay(123);
+        let cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+        assert_eq!(cluster.seed(), Some(123));
+    }
+}
@@ -0,0 +1,200 @@
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use turmoil::Sim;
+
+use super::host;
+
+/// A kind of fault that a [`Nemesis`] can inject.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FaultKind {
+    /// Drop all messages sent between a pair of instances.
+    Partition,
+    /// Delay all messages sent between a pair of instances.
+    Hold,
+    /// Crash an instance, and restart it once the fault is healed.
+    Crash,
+}
+
+/// A fault injected into a simulated cluster, in terms of the indices of the
+/// instances that it affects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// All messages sent between a pair of instances are dropped.
+    Partition(usize, usize),
+    /// All messages sent between a pair of instances are delayed.
+    Hold(usize, usize),
+    /// An instance has crashed.
+    Crash(usize),
+}
+
+/// A fault scheduler that injects randomized faults into a
+/// [`SimulatedCluster`](super::SimulatedCluster) while it runs.
+///
+/// Once every interval of simulated time, the nemesis heals the fault that
+/// it previously injected, if any, and then injects a new fault chosen at
+/// random from the kinds of faults that it is allowed to inject. At most one
+/// fault is active at a time, so in a cluster of three or more instances a
+/// majority of instances can always communicate with one another.
+///
+/// All randomness is derived from a seed, so a nemesis with the same seed,
+/// running alongside the same deterministic clients, injects exactly the
+/// same faults at exactly the same times. Every fault is logged as it is
+/// injected and healed, and the seed of the nemesis is logged by
+/// [`run_with_nemesis`](super::SimulatedCluster::run_with_nemesis), so a
+/// failed run can be replayed. See [`replay`](super::replay).
+///
+/// See [`run_with_nemesis`](super::SimulatedCluster::run_with_nemesis).
+#[derive(Debug)]
+pub struct Nemesis {
+    seed: u64,
+    rng: StdRng,
+    kinds: Vec<FaultKind>,
+    interval: Duration,
+    next_at: Duration,
+    active: Option<Fault>,
+    faults: Vec<(Duration, Fault)>,
+}
+
+impl Nemesis {
+    /// Creates a nemesis that injects every kind of fault, once every
+    /// second, using randomness derived from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            kinds: vec![FaultKind::Partition, FaultKind::Hold, FaultKind::Crash],
+            interval: Duration::from_secs(1),
+            next_at: Duration::ZERO,
+            active: None,
+            faults: Vec::new(),
+        }
+    }
+
+    /// Sets the kinds of faults that the nemesis injects. If no kinds are
+    /// given, then no faults are injected.
+    pub fn with_faults(mut self, kinds: impl IntoIterator<Item = FaultKind>) -> Self {
+        self.kinds = kinds.into_iter().collect();
+        self
+    }
+
+    /// Sets how much simulated time passes between the injection of one
+    /// fault and the next.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Returns the seed from which the randomness of the nemesis is derived.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the faults that have been injected so far, along with the
+    /// simulated time at which each was injected.
+    pub fn faults(&self) -> &[(Duration, Fault)] {
+        &self.faults
+    }
+
+    /// Heals the active fault, and injects a new one, if an interval has
+    /// passed since the last fault was injected.
+    pub(crate) fn tick(&mut self, sim: &mut Sim, instances: usize) {
+        let now = sim.elapsed();
+        if now < self.next_at {
+            return;
+        }
+        self.next_at = now + self.interval;
+        self.heal(sim);
+        if let Some(fault) = self.choose(instances) {
+            tracing::info!(?fault, at = ?now, "nemesis injected fault");
+            inject(sim, fault);
+            self.active = Some(fault);
+            self.faults.push((now, fault));
+        }
+    }
+
+    /// Heals the active fault, if any.
+    pub(crate) fn heal(&mut self, sim: &mut Sim) {
+        if let Some(fault) = self.active {
+            tracing::info!(?fault, at = ?sim.elapsed(), "nemesis healed fault");
+        }
+        match self.active.take() {
+            Some(Fault::Partition(i, j)) => sim.repair(host(i), host(j)),
+            Some(Fault::Hold(i, j)) => sim.release(host(i), host(j)),
+            Some(Fault::Crash(i)) => sim.bounce(host(i)),
+            None => {}
+        }
+    }
+
+    /// Chooses a fault to inject into a cluster with the given number of
+    /// instances, or `None` if no fault can be injected.
+    fn choose(&mut self, instances: usize) -> Option<Fault> {
+        let kind = *self.kinds.choose(&mut self.rng)?;
+        match kind {
+            FaultKind::Crash if instances > 0 => {
+                Some(Fault::Crash(self.rng.gen_range(0..instances)))
+            }
+            FaultKind::Partition | FaultKind::Hold if instances > 1 => {
+                let i = self.rng.gen_range(0..instances);
+                let j = (i + self.rng.gen_range(1..instances)) % instances;
+                match kind {
+                    FaultKind::Partition => Some(Fault::Partition(i, j)),
+                    _ => Some(Fault::Hold(i, j)),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Injects a fault into the simulation.
+fn inject(sim: &mut Sim, fault: Fault) {
+    match fault {
+        Fault::Partition(i, j) => sim.partition(host(i), host(j)),
+        Fault::Hold(i, j) => sim.hold(host(i), host(j)),
+        Fault::Crash(i) => sim.crash(host(i)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod choose {
+        use super::*;
+
+        #[test]
+        fn is_deterministic_given_a_seed() {
+            let mut first = Nemesis::new(123);
+            let mut second = Nemesis::new(123);
+            for _ in 0..100 {
+                assert_eq!(first.choose(5), second.choose(5));
+            }
+        }
+
+        #[test]
+        fn chooses_distinct_instances_for_links() {
+            let mut nemesis = Nemesis::new(0).with_faults([FaultKind::Partition]);
+            for _ in 0..100 {
+                match nemesis.choose(3) {
+                    Some(Fault::Partition(i, j)) => assert!(i != j && i < 3 && j < 3),
+                    fault => panic!("Unexpected fault {fault:?}"),
+                }
+            }
+        }
+
+        #[test]
+        fn chooses_nothing_without_fault_kinds() {
+            let mut nemesis = Nemesis::new(0).with_faults([]);
+            assert_eq!(nemesis.choose(3), None);
+        }
+
+        #[test]
+        fn chooses_nothing_if_links_do_not_exist() {
+            let mut nemesis = Nemesis::new(0).with_faults([FaultKind::Hold]);
+            assert_eq!(nemesis.choose(1), None);
+        }
+    }
+}
@@ -0,0 +1,424 @@
+use std::time::Duration;
+
+use turmoil::Sim;
+
+use super::host;
+
+/// The range of latencies of the messages sent over a link.
+///
+/// The latency of each message is drawn from an exponential distribution,
+/// scaled to lie between `min` and `max`, so most messages are delivered in
+/// close to `min`, and a few take up to `max`. The shape of the
+/// distribution is shared by every link, and can be set with
+/// [`with_latency_curve`](Topology::with_latency_curve).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinkLatency {
+    /// The smallest latency of a message sent over the link.
+    pub min: Duration,
+    /// The largest latency of a message sent over the link.
+    pub max: Duration,
+}
+
+impl LinkLatency {
+    /// The latency between hosts in the same rack.
+    pub const SAME_RACK: Self = Self::new(Duration::ZERO, Duration::from_millis(1));
+
+    /// The latency between hosts in different racks of the same
+    /// availability zone.
+    pub const CROSS_RACK: Self = Self::new(Duration::from_millis(1), Duration::from_millis(2));
+
+    /// The latency between hosts in different availability zones of the same
+    /// region.
+    pub const CROSS_ZONE: Self = Self::new(Duration::from_millis(2), Duration::from_millis(5));
+
+    /// The latency between hosts in different regions, connected over a
+    /// wide-area network.
+    pub const CROSS_REGION: Self = Self::new(Duration::from_millis(40), Duration::from_millis(150));
+
+    /// Creates a link latency between `min` and `max`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is larger than `max`.
+    pub const fn new(min: Duration, max: Duration) -> Self {
+        assert!(min.as_nanos() <= max.as_nanos(), "min must not exceed max");
+        Self { min, max }
+    }
+
+    /// Creates a link latency over which every message takes exactly
+    /// `latency` to be delivered.
+    pub const fn fixed(latency: Duration) -> Self {
+        Self::new(latency, latency)
+    }
+
+    /// Returns the preset latency between hosts at the given distance.
+    pub const fn preset(distance: Distance) -> Self {
+        match distance {
+            Distance::SameRack => Self::SAME_RACK,
+            Distance::CrossRack => Self::CROSS_RACK,
+            Distance::CrossZone => Self::CROSS_ZONE,
+            Distance::CrossRegion => Self::CROSS_REGION,
+        }
+    }
+}
+
+/// How far apart two hosts are, in terms of the smallest unit of a
+/// deployment that contains both of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Distance {
+    /// Both hosts are in the same rack.
+    SameRack,
+    /// The hosts are in different racks of the same availability zone.
+    CrossRack,
+    /// The hosts are in different availability zones of the same region.
+    CrossZone,
+    /// The hosts are in different regions.
+    CrossRegion,
+}
+
+/// Where in a deployment an instance runs.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// The region that contains the instance.
+    pub region: String,
+    /// The availability zone, within the region, that contains the instance.
+    pub zone: String,
+    /// The rack, within the availability zone, that contains the instance.
+    pub rack: String,
+}
+
+impl Location {
+    /// Creates a location in the given rack, of the given zone, of the given
+    /// region.
+    pub fn new(
+        region: impl Into<String>,
+        zone: impl Into<String>,
+        rack: impl Into<String>,
+    ) -> Self {
+        Self {
+            region: region.into(),
+            zone: zone.into(),
+            rack: rack.into(),
+        }
+    }
+
+    /// Returns how far apart this location is from another.
+    pub fn distance(&self, other: &Location) -> Distance {
+        if self.region != other.region {
+            Distance::CrossRegion
+        } else if self.zone != other.zone {
+            Distance::CrossZone
+        } else if self.rack != other.rack {
+            Distance::CrossRack
+        } else {
+            Distance::SameRack
+        }
+    }
+}
+
+/// A model of where the instances of a
+/// [`SimulatedCluster`](super::SimulatedCluster) run, which determines the
+/// latency of the messages that they send to one another.
+///
+/// Each instance is placed at a [`Location`], and the latency of the link
+/// between two instances is chosen by how far apart they are, starting from
+/// the [presets](LinkLatency::preset) for each [`Distance`]. The latency of
+/// any individual link can be overridden with
+/// [`with_link`](Self::with_link), to model asymmetric deployments such as
+/// regions that are further apart than others.
+///
+/// Clients are assumed to run next to the instances that they talk to, and
+/// so messages sent to or from a client are delivered with at most the
+/// same-rack latency.
+///
+/// See [`set_topology`](super::SimulatedCluster::set_topology).
+///
+/// # Examples
+///
+/// Model three instances in three different regions, where the link between
+/// the first two regions is faster than the others.
+///
+/// ```
+/// use std::time::Duration;
+/// use todc_net::testing::{Distance, LinkLatency, Topology};
+///
+/// let fast = LinkLatency::new(Duration::from_millis(10), Duration::from_millis(20));
+/// let topology = Topology::across_regions(3, 3).with_link(0, 1, fast);
+/// assert_eq!(topology.latency(0, 1), fast);
+/// assert_eq!(topology.latency(1, 2), LinkLatency::preset(Distance::CrossRegion));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Topology {
+    locations: Vec<Location>,
+    latencies: [LinkLatency; 4],
+    links: Vec<(usize, usize, LinkLatency)>,
+    curve: Option<f64>,
+}
+
+impl Topology {
+    /// Creates a topology in which the `i`th instance runs at the `i`th
+    /// location.
+    pub fn new(locations: impl IntoIterator<Item = Location>) -> Self {
+        Self {
+            locations: locations.into_iter().collect(),
+            latencies: [
+                Distance::SameRack,
+                Distance::CrossRack,
+                Distance::CrossZone,
+                Distance::CrossRegion,
+            ]
+            .map(LinkLatency::preset),
+            links: Vec::new(),
+            curve: None,
+        }
+    }
+
+    /// Creates a topology of `n` instances that all run in the same rack.
+    pub fn single_rack(n: usize) -> Self {
+        Self::new((0..n).map(|_| Location::new("region-0", "zone-0", "rack-0")))
+    }
+
+    /// Creates a topology of `n` instances, spread evenly across the given
+    /// number of availability zones of a single region.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `zones` is zero.
+    pub fn across_zones(n: usize, zones: usize) -> Self {
+        assert!(zones > 0, "there must be at least one zone");
+        Self::new(
+            (0..n).map(|i| Location::new("region-0", format!("zone-{}", i % zones), "rack-0")),
+        )
+    }
+
+    /// Creates a topology of `n` instances, spread evenly across the given
+    /// number of regions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `regions` is zero.
+    pub fn across_regions(n: usize, regions: usize) -> Self {
+        assert!(regions > 0, "there must be at least one region");
+        Self::new(
+            (0..n).map(|i| Location::new(format!("region-{}", i % regions), "zone-0", "rack-0")),
+        )
+    }
+
+    /// Sets the latency of every link between instances that are the given
+    /// distance apart, unless it is overridden with
+    /// [`with_link`](Self::with_link).
+    pub fn with_distance(mut self, distance: Distance, latency: LinkLatency) -> Self {
+        self.latencies[distance as usize] = latency;
+        self
+    }
+
+    /// Sets the latency of the link between the `i`th and `j`th instances,
+    /// regardless of how far apart they are.
+    pub fn with_link(mut self, i: usize, j: usize, latency: LinkLatency) -> Self {
+        self.links.push((i, j, latency));
+        self
+    }
+
+    /// Sets the rate parameter of the exponential distribution from which the
+    /// latency of each message is drawn. Larger values make messages more
+    /// likely to be delivered close to the minimum latency of their link.
+    ///
+    /// If this is not set, the curve of the simulation is left unchanged.
+    pub fn with_latency_curve(mut self, rate: f64) -> Self {
+        self.curve = Some(rate);
+        self
+    }
+
+    /// Returns the number of instances in the topology.
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    /// Returns whether the topology contains no instances.
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+
+    /// Returns the location of the `i`th instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not smaller than the number of instances.
+    pub fn location(&self, i: usize) -> &Location {
+        &self.locations[i]
+    }
+
+    /// Returns the latency of the link between the `i`th and `j`th
+    /// instances.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either `i` or `j` is not smaller than the number of
+    /// instances.
+    pub fn latency(&self, i: usize, j: usize) -> LinkLatency {
+        // Later overrides take precedence over earlier ones.
+        let link = self
+            .links
+            .iter()
+            .rev()
+            .find(|&&(a, b, _)| (a, b) == (i, j) || (a, b) == (j, i));
+        match link {
+            Some(&(_, _, latency)) => latency,
+            None => {
+                let distance = self.locations[i].distance(&self.locations[j]);
+                self.latencies[distance as usize]
+            }
+        }
+    }
+
+    /// Returns the smallest amount of time in which the `i`th instance can
+    /// exchange a round of messages with a quorum of the given size,
+    /// including itself.
+    ///
+    /// This is a lower bound on the latency of each phase of a quorum-based
+    /// protocol, such as a read or write of an
+    /// [`AtomicRegister`](crate::register::AtomicRegister), coordinated by the
+    /// `i`th instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `quorum` is zero, or larger than the number of instances.
+    pub fn quorum_latency(&self, i: usize, quorum: usize) -> Duration {
+        assert!(
+            0 < quorum && quorum <= self.len(),
+            "quorum must contain between one and all instances"
+        );
+        let mut round_trips: Vec<Duration> = (0..self.len())
+            .filter(|&j| j != i)
+            .map(|j| 2 * self.latency(i, j).min)
+            .collect();
+        round_trips.sort();
+        match quorum {
+            1 => Duration::ZERO,
+            _ => round_trips[quorum - 2],
+        }
+    }
+
+    /// Configures the latency of every link between instances in the
+    /// simulation.
+    pub(crate) fn apply(&self, sim: &mut Sim) {
+        sim.set_max_message_latency(self.latencies[Distance::SameRack as usize].max);
+        if let Some(rate) = self.curve {
+            sim.set_message_latency_curve(rate);
+        }
+        for i in 0..self.len() {
+            for j in (i + 1)..self.len() {
+                let latency = self.latency(i, j);
+                sim.set_link_latency(host(i), host(j), latency.min);
+                sim.set_link_max_message_latency(host(i), host(j), latency.max);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    mod distance {
+        use super::*;
+
+        #[test]
+        fn is_determined_by_largest_differing_unit() {
+            let location = Location::new("a", "a", "a");
+            assert_eq!(location.distance(&location), Distance::SameRack);
+            assert_eq!(
+                location.distance(&Location::new("a", "a", "b")),
+                Distance::CrossRack
+            );
+            assert_eq!(
+                location.distance(&Location::new("a", "b", "a")),
+                Distance::CrossZone
+            );
+            assert_eq!(
+                location.distance(&Location::new("b", "a", "a")),
+                Distance::CrossRegion
+            );
+        }
+
+        #[test]
+        fn presets_increase_with_distance() {
+            let presets = [
+                Distance::SameRack,
+                Distance::CrossRack,
+                Distance::CrossZone,
+                Distance::CrossRegion,
+            ]
+            .map(LinkLatency::preset);
+            assert!(presets.windows(2).all(|w| w[0].max <= w[1].min));
+        }
+    }
+
+    mod latency {
+        use super::*;
+
+        #[test]
+        fn places_instances_round_robin() {
+            let topology = Topology::across_zones(4, 2);
+            assert_eq!(topology.latency(0, 2), LinkLatency::SAME_RACK);
+            assert_eq!(topology.latency(0, 1), LinkLatency::CROSS_ZONE);
+        }
+
+        #[test]
+        fn uses_latency_of_distance() {
+            let latency = LinkLatency::fixed(millis(7));
+            let topology =
+                Topology::across_regions(2, 2).with_distance(Distance::CrossRegion, latency);
+            assert_eq!(topology.latency(0, 1), latency);
+        }
+
+        #[test]
+        fn links_override_distance_in_both_directions() {
+            let latency = LinkLatency::fixed(millis(7));
+            let topology = Topology::single_rack(3).with_link(2, 0, latency);
+            assert_eq!(topology.latency(0, 2), latency);
+            assert_eq!(topology.latency(2, 0), latency);
+            assert_eq!(topology.latency(0, 1), LinkLatency::SAME_RACK);
+        }
+
+        #[test]
+        fn later_links_take_precedence() {
+            let topology = Topology::single_rack(2)
+                .with_link(0, 1, LinkLatency::fixed(millis(1)))
+                .with_link(0, 1, LinkLatency::fixed(millis(2)));
+            assert_eq!(topology.latency(0, 1), LinkLatency::fixed(millis(2)));
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_min_exceeds_max() {
+            LinkLatency::new(millis(2), millis(1));
+        }
+    }
+
+    mod quorum_latency {
+        use super::*;
+
+        #[test]
+        fn is_zero_for_quorum_of_one() {
+            let topology = Topology::across_regions(3, 3);
+            assert_eq!(topology.quorum_latency(0, 1), Duration::ZERO);
+        }
+
+        #[test]
+        fn waits_for_nearest_neighbors() {
+            let topology = Topology::across_regions(5, 5)
+                .with_link(0, 1, LinkLatency::fixed(millis(10)))
+                .with_link(0, 2, LinkLatency::fixed(millis(20)));
+            assert_eq!(topology.quorum_latency(0, 3), millis(40));
+            assert_eq!(
+                topology.quorum_latency(0, 4),
+                2 * LinkLatency::CROSS_REGION.min
+            );
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// An operation that a [`Workload`] performs on a register.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Read the contents of the register.
+    Read,
+    /// Write a value to the register.
+    Write(u32),
+}
+
+/// An operation, along with when and where it is performed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduledOperation {
+    /// The simulated time, since the start of the workload, at which the
+    /// operation arrives.
+    pub at: Duration,
+    /// The index of the instance that the operation is performed on.
+    pub instance: usize,
+    /// The operation to perform.
+    pub operation: Operation,
+}
+
+/// A randomized workload of reads and writes to be performed on a register.
+///
+/// Operations arrive according to a Poisson process with the configured
+/// arrival rate, and each is a write with the configured probability, and
+/// otherwise a read. Reads are performed on an instance chosen uniformly at
+/// random. Every write writes a distinct value, which makes the resulting
+/// history easier to check for linearizability.
+///
+/// The registers of [ABD95](crate::register::abd_95) are single-writer
+/// registers, and so by default every write is performed on the first
+/// instance, while the other instances only perform reads.
+///
+/// All randomness is derived from a seed, so workloads with the same seed
+/// and configuration generate exactly the same operations.
+///
+/// See [`add_workload`](super::SimulatedCluster::add_workload).
+#[derive(Clone, Debug)]
+pub struct Workload {
+    seed: u64,
+    operations: usize,
+    write_probability: f64,
+    writers: usize,
+    arrival_rate: f64,
+    timeout: Duration,
+}
+
+impl Workload {
+    /// Creates a workload of `100` operations, half of which are writes,
+    /// that arrive at a rate of `10` per second, using randomness derived
+    /// from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            operations: 100,
+            write_probability: 0.5,
+            writers: 1,
+            arrival_rate: 10.0,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Sets the number of operations in the workload.
+    pub fn with_operations(mut self, operations: usize) -> Self {
+        self.operations = operations;
+        self
+    }
+
+    /// Sets the probability that each operation is a write.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` is not between `0` and `1`.
+    pub fn with_write_probability(mut self, probability: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&probability),
+            "The probability of a write must be between 0 and 1"
+        );
+        self.write_probability = probability;
+        self
+    }
+
+    /// Sets the number of instances that perform writes, so that writes are
+    /// performed on instances chosen uniformly at random from the first
+    /// `writers`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `writers` is zero.
+    #[deprecated(note = "registers of ABD95 are single-writer, so writes must use one instance")]
+    pub fn with_writers(mut self, writers: usize) -> Self {
+        assert!(writers > 0, "A workload requires at least one writer");
+        self.writers = writers;
+        self
+    }
+
+    /// Sets the average number of operations that arrive per second of
+    /// simulated time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `per_second` is not positive.
+    pub fn with_arrival_rate(mut self, per_second: f64) -> Self {
+        assert!(per_second > 0.0, "The arrival rate must be positive");
+        self.arrival_rate = per_second;
+        self
+    }
+
+    /// Sets how long each operation may take before it is abandoned.
+    ///
+    /// Abandoned operations may or may not take effect.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the seed from which the randomness of the workload is derived.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns how long each operation may take before it is abandoned.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns the operations of the workload, for a cluster with the given
+    /// number of instances, in the order that they arrive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `instances` is zero.
+    pub fn generate(&self, instances: usize) -> Vec<ScheduledOperation> {
+        assert!(instances > 0, "A workload requires at least one instance");
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut at = Duration::ZERO;
+        let mut writes = 0;
+        (0..self.operations)
+            .map(|_| {
+                // The time between arrivals of a Poisson process is
+                // exponentially distributed.
+                let uniform: f64 = rng.gen();
+                at += Duration::from_secs_f64(-(1.0 - uniform).ln() / self.arrival_rate);
+                let (instance, operation) = if rng.gen_bool(self.write_probability) {
+                    writes += 1;
+                    let writers = self.writers.min(instances);
+                    (rng.gen_range(0..writers), Operation::Write(writes))
+                } else {
+                    (rng.gen_range(0..instances), Operation::Read)
+                };
+                ScheduledOperation {
+                    at,
+                    instance,
+                    operation,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod generate {
+        use super::*;
+
+        #[test]
+        fn is_deterministic_given_a_seed() {
+            let workload = Workload::new(123);
+            assert_eq!(workload.generate(3), Workload::new(123).generate(3));
+        }
+
+        #[test]
+        fn generates_requested_number_of_operations() {
+            let workload = Workload::new(0).with_operations(42);
+            assert_eq!(workload.generate(3).len(), 42);
+        }
+
+        #[test]
+        fn generates_operations_in_order_of_arrival() {
+            let operations = Workload::new(0).generate(3);
+            assert!(operations.windows(2).all(|pair| pair[0].at <= pair[1].at));
+        }
+
+        #[test]
+        fn writes_distinct_values() {
+            let workload = Workload::new(0).with_write_probability(1.0);
+            let values: Vec<u32> = workload
+                .generate(3)
+                .into_iter()
+                .map(|scheduled| match scheduled.operation {
+                    Operation::Write(value) => value,
+                    Operation::Read => panic!("Expected only writes"),
+                })
+                .collect();
+            assert_eq!(values, (1..=100).collect::<Vec<u32>>());
+        }
+
+        #[test]
+        fn only_reads_if_write_probability_is_zero() {
+            let workload = Workload::new(0).with_write_probability(0.0);
+            assert!(workload
+                .generate(3)
+                .iter()
+                .all(|scheduled| scheduled.operation == Operation::Read));
+        }
+
+        #[test]
+        fn performs_writes_on_first_instance() {
+            let operations = Workload::new(0).generate(5);
+            assert!(operations
+                .iter()
+                .filter(|scheduled| scheduled.operation != Operation::Read)
+                .all(|scheduled| scheduled.instance == 0));
+            // Reads are still spread across the other instances.
+            assert!(operations.iter().any(|scheduled| scheduled.instance != 0));
+        }
+
+        #[test]
+        #[allow(deprecated)]
+        fn performs_writes_on_writers() {
+            let workload = Workload::new(0).with_writers(2);
+            assert!(workload
+                .generate(5)
+                .iter()
+                .filter(|scheduled| scheduled.operation != Operation::Read)
+                .all(|scheduled| scheduled.instance < 2));
+        }
+
+        #[test]
+        fn performs_operations_on_existing_instances() {
+            let operations = Workload::new(0).generate(2);
+            assert!(operations.iter().all(|scheduled| scheduled.instance < 2));
+        }
+    }
+}
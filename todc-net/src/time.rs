@@ -0,0 +1,218 @@
+//! Sources of time for the timers used by instances.
+//!
+//! Protocols such as the leases and gossip of the
+//! [`AtomicRegister`](crate::register::AtomicRegister), the back-off between
+//! rounds of [`Paxos`](crate::consensus::Paxos), and the retries of the
+//! [`RegisterClient`](crate::client::RegisterClient) all depend on time.
+//! Rather than reading the system clock directly, each of them asks a
+//! [`Clock`] for the current time and for timers, so that the passage of time
+//! can be controlled in tests.
+//!
+//! This module contains a [`TokioClock`], which is used by default, and a
+//! [`MockClock`], whose time only advances when told to and which is intended
+//! for tests.
+//!
+//! # Simulation
+//!
+//! The [`TokioClock`] reads time from the `tokio` runtime it is running on,
+//! rather than from the system. When running inside a
+//! [`turmoil`](https://docs.rs/turmoil) simulation, each host has its own
+//! runtime whose time is driven by the simulation, and so the same clock
+//! follows simulated time without any changes to the code under test.
+//!
+//! # Examples
+//!
+//! Objects are given a clock when they are constructed, for example with
+//! [`AtomicRegister::with_clock`](crate::register::AtomicRegister::with_clock).
+//! The timers of a [`MockClock`] complete as soon as it is advanced past their
+//! deadline, no matter how much time has actually passed.
+//!
+//! ```
+//! # use tokio_test;
+//! use std::time::Duration;
+//! use todc_net::time::{Clock, MockClock};
+//!
+//! # tokio_test::block_on(async {
+//! let clock = MockClock::new();
+//! let timer = clock.sleep_until(clock.now() + Duration::from_secs(60));
+//!
+//! // The timer completes immediately, rather than after a minute.
+//! clock.advance(Duration::from_secs(60));
+//! timer.await;
+//! # })
+//! ```
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// A future that completes once a timer has elapsed.
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A source of the current time, and of timers.
+pub trait Clock: Send + Sync + 'static {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+
+    /// Returns a future that completes once the current time is at least
+    /// `deadline`.
+    fn sleep_until(&self, deadline: Instant) -> Sleep;
+}
+
+impl dyn Clock {
+    /// Returns a future that completes once `duration` has elapsed.
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        self.sleep_until(self.now() + duration)
+    }
+
+    /// Awaits a future until `deadline`, returning its output if it
+    /// completed in time, or `None` otherwise.
+    pub async fn timeout_at<F: Future>(&self, deadline: Instant, future: F) -> Option<F::Output> {
+        tokio::select! {
+            biased;
+            output = future => Some(output),
+            _ = self.sleep_until(deadline) => None,
+        }
+    }
+
+    /// Awaits a future for at most `duration`, returning its output if it
+    /// completed in time, or `None` otherwise.
+    pub async fn timeout<F: Future>(&self, duration: Duration, future: F) -> Option<F::Output> {
+        self.timeout_at(self.now() + duration, future).await
+    }
+}
+
+/// Returns the clock used by instances that are not given one.
+pub(crate) fn default_clock() -> Arc<dyn Clock> {
+    Arc::new(TokioClock)
+}
+
+/// A [`Clock`] that reads time from the current `tokio` runtime.
+///
+/// Time follows the system clock, unless the runtime's clock has been
+/// paused, or the runtime belongs to a host of a `turmoil` simulation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Sleep {
+        Box::pin(tokio::time::sleep_until(deadline))
+    }
+}
+
+/// A [`Clock`] whose time only advances when told to.
+///
+/// Clones share the same time, so a clone can be given to each instance, and
+/// advancing any of them wakes the timers of all of them.
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    now: Arc<watch::Sender<Instant>>,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        let (now, _) = watch::channel(Instant::now());
+        Self { now: Arc::new(now) }
+    }
+}
+
+impl MockClock {
+    /// Creates a clock that starts at the current time.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advances the time by `duration`, completing any timers whose deadline
+    /// has been reached.
+    pub fn advance(&self, duration: Duration) {
+        self.now.send_modify(|now| *now += duration);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *self.now.borrow()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Sleep {
+        let mut now = self.now.subscribe();
+        Box::pin(async move {
+            // The sender lives as long as the clock, so if it is dropped then
+            // time can never again advance, and the timer never completes.
+            if now.wait_for(|now| *now >= deadline).await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod mock_clock {
+        use super::*;
+
+        #[test]
+        fn advances_only_when_told_to() {
+            let clock = MockClock::new();
+            let start = clock.now();
+            std::thread::sleep(Duration::from_millis(1));
+            assert_eq!(clock.now(), start);
+            clock.advance(Duration::from_secs(5));
+            assert_eq!(clock.now(), start + Duration::from_secs(5));
+        }
+
+        #[test]
+        fn shares_time_between_clones() {
+            let clock = MockClock::new();
+            clock.clone().advance(Duration::from_secs(1));
+            assert_eq!(clock.now(), clock.clone().now());
+        }
+
+        #[tokio::test]
+        async fn completes_timers_once_deadline_is_reached() {
+            let mock = MockClock::new();
+            let clock: Arc<dyn Clock> = Arc::new(mock.clone());
+            let timer = tokio::spawn(clock.sleep(Duration::from_secs(2)));
+
+            mock.advance(Duration::from_secs(1));
+            tokio::task::yield_now().await;
+            assert!(!timer.is_finished());
+
+            mock.advance(Duration::from_secs(1));
+            timer.await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn times_out_futures_that_do_not_complete_before_deadline() {
+            let mock = MockClock::new();
+            let clock: Arc<dyn Clock> = Arc::new(mock.clone());
+            let timeout = tokio::spawn(async move {
+                clock
+                    .timeout(Duration::from_secs(1), std::future::pending::<()>())
+                    .await
+            });
+
+            tokio::task::yield_now().await;
+            assert!(!timeout.is_finished());
+
+            mock.advance(Duration::from_secs(1));
+            assert_eq!(timeout.await.unwrap(), None);
+        }
+
+        #[tokio::test]
+        async fn returns_output_of_futures_that_complete_before_deadline() {
+            let clock: Arc<dyn Clock> = Arc::new(MockClock::new());
+            let output = clock.timeout(Duration::ZERO, async { 5 }).await;
+            assert_eq!(output, Some(5));
+        }
+    }
+}
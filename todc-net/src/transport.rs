@@ -0,0 +1,209 @@
+//! Transports over which instances exchange messages.
+//!
+//! By default, instances communicate by sending JSON over HTTP/1 with an
+//! [`HttpTransport`]. This is easy to inspect and debug, but opens a new
+//! connection for every message. An [`HttpTransport`] can instead be
+//! configured to send the same JSON over HTTP/2, with
+//! [`with_http2`](HttpTransport::with_http2), so that messages sent to the
+//! same neighbor share a single connection.
+//!
+//! With the `grpc` feature enabled, instances can instead use a
+//! [`GrpcTransport`](grpc::GrpcTransport), which exchanges protobuf messages
+//! over HTTP/2. Messages sent to the same neighbor are multiplexed as streams
+//! over a single, long-lived connection.
+//!
+//! The transport is selected when an object is constructed, for example with
+//! [`AtomicRegister::with_transport`](crate::register::AtomicRegister::with_transport).
+//! All instances of an object must use the same transport.
+//!
+//! For testing, a [`MemoryTransport`](memory::MemoryTransport) delivers
+//! messages to an in-memory [`Inbox`](memory::Inbox) instead of a network, so
+//! that a test can script how, and in what order, each neighbor replies.
+//!
+//! # Streaming
+//!
+//! Messages that carry a [`label`](Message::label) separately from their
+//! contents are sent by an [`HttpTransport`] with the label in the
+//! `X-Register-Label` header, and the contents streamed as a sequence of
+//! chunks, along with their SHA-256 digest in the `X-Content-Sha256` header.
+//! Chunks share the memory of the original contents, so the same message can
+//! be sent to many neighbors without copying it once per request, and the
+//! reciever checks the digest before acting on the message.
+//!
+//! # Versions
+//!
+//! Messages that state the [`version`](Message::version) of the protocol in
+//! which they are written are sent by an [`HttpTransport`] with the version
+//! in the `X-Protocol-Version` header. Neighbors that do not support the
+//! version reply with `426 Upgrade Required`, and advertise the versions that
+//! they do support in the `X-Protocol-Versions` header, as a range such as
+//! `1-3`. The transport returns such replies as an [`UnsupportedVersion`]
+//! error, so that the sender can retry in a version that both understand.
+//! Over gRPC, the same range is sent in the `x-protocol-versions` metadata of
+//! a `FAILED_PRECONDITION` status.
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::future::Future;
+use std::ops::RangeInclusive;
+
+use bytes::Bytes;
+use hyper::Uri;
+
+use crate::GenericError;
+
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod http;
+pub mod memory;
+
+pub use self::http::HttpTransport;
+
+/// A message from one instance to another.
+///
+/// Messages are cheap to clone, so that the same message can be sent to many
+/// neighbors. Their routes and media types are usually static, and so are
+/// not copied, and their contents are shared.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Message {
+    /// The route that the message is addressed to, such as `/register/local`.
+    pub path: Cow<'static, str>,
+    /// The contents of the message, or `None` if the message only asks for
+    /// information from the reciever.
+    pub body: Option<Bytes>,
+    /// The media type with which the contents of the message, and of the
+    /// reply, are encoded, or `None` if they are encoded as JSON. See the
+    /// [`codec`](crate::codec) module for details.
+    pub content_type: Option<Cow<'static, str>>,
+    /// The label of the value contained in the message, encoded as JSON, if
+    /// the label is sent separately from the value. In that case, the
+    /// contents of the message are only the encoded value, so that large
+    /// values can be sent without re-encoding them alongside their label.
+    pub label: Option<String>,
+    /// The epoch of the sender, if it has one. Recievers that have entered a
+    /// later epoch reject the message, so that instances which fell behind,
+    /// for example while partitioned, cannot act on outdated state. See
+    /// [Epochs](crate::register::abd_95#epochs).
+    pub epoch: Option<u64>,
+    /// The version of the protocol in which the message, and its reply, are
+    /// written, or `None` if the sender does not state one. See
+    /// [Protocol Versions](crate::register::abd_95#protocol-versions).
+    pub version: Option<u32>,
+}
+
+impl Message {
+    /// Creates a message asking for information from the reciever.
+    pub fn ask(path: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            path: path.into(),
+            body: None,
+            content_type: None,
+            label: None,
+            epoch: None,
+            version: None,
+        }
+    }
+
+    /// Creates a message with contents for the reciever to act on.
+    pub fn announce(path: impl Into<Cow<'static, str>>, body: Bytes) -> Self {
+        Self {
+            path: path.into(),
+            body: Some(body),
+            content_type: None,
+            label: None,
+            epoch: None,
+            version: None,
+        }
+    }
+
+    /// Sets the media type with which the contents of the message, and of
+    /// the reply, are encoded.
+    pub fn with_content_type(mut self, content_type: impl Into<Cow<'static, str>>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Sets the label of the value contained in the message, which is then
+    /// sent separately from the value.
+    pub fn with_label(mut self, label: String) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    /// Sets the epoch of the sender of the message.
+    pub fn with_epoch(mut self, epoch: u64) -> Self {
+        self.epoch = Some(epoch);
+        self
+    }
+
+    /// Sets the version of the protocol in which the message is written.
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = Some(version);
+        self
+    }
+}
+
+/// The error with which a neighbor rejects a message written in a version of
+/// the protocol that it does not understand.
+///
+/// [`Handler`]s reject such messages with this error, and [`Transport`]s
+/// return it to the sender, so that the sender can retry in the latest
+/// version that it shares with the neighbor. See
+/// [Protocol Versions](crate::register::abd_95#protocol-versions).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsupportedVersion {
+    /// The version in which the message was written.
+    pub version: u32,
+    /// The versions that the neighbor understands.
+    pub supported: RangeInclusive<u32>,
+}
+
+impl Display for UnsupportedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Protocol version {} is not supported, only versions {} to {} are",
+            self.version,
+            self.supported.start(),
+            self.supported.end()
+        )
+    }
+}
+
+impl Error for UnsupportedVersion {}
+
+/// Encodes a range of protocol versions as it is advertised to neighbors,
+/// such as `1-3`.
+pub(crate) fn format_versions(versions: &RangeInclusive<u32>) -> String {
+    format!("{}-{}", versions.start(), versions.end())
+}
+
+/// Decodes a range of protocol versions advertised by a neighbor, or returns
+/// `None` if it is invalid.
+pub(crate) fn parse_versions(versions: &str) -> Option<RangeInclusive<u32>> {
+    let (start, end) = versions.split_once('-')?;
+    let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+    (start <= end).then_some(start..=end)
+}
+
+/// A way of sending messages to neighboring instances.
+pub trait Transport: Clone + Send + Sync + 'static {
+    /// Sends a message to the instance at `neighbor`, and returns the contents
+    /// of its reply.
+    fn send(
+        &self,
+        neighbor: Uri,
+        message: Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send;
+}
+
+/// An instance that can reply to messages from its neighbors.
+pub trait Handler: Clone + Send + Sync + 'static {
+    /// Handles a message from a neighbor, and returns the contents of the
+    /// reply.
+    ///
+    /// Messages written in a version of the protocol that the handler does
+    /// not understand should be rejected with an [`UnsupportedVersion`]
+    /// error, which transports pass back to the sender.
+    fn handle(&self, message: Message) -> impl Future<Output = Result<Bytes, GenericError>> + Send;
+}
@@ -0,0 +1,388 @@
+//! A transport that sends protobuf messages over HTTP/2 with
+//! [gRPC](https://grpc.io/).
+//!
+//! Each [`Message`] is sent as a unary call to the `/todc.Transport/Send`
+//! method, as described by the following protobuf definition:
+//!
+//! ```protobuf
+//! syntax = "proto3";
+//!
+//! package todc;
+//!
+//! service Transport {
+//!   rpc Send(Envelope) returns (Reply);
+//! }
+//!
+//! message Envelope {
+//!   string path = 1;
+//!   optional bytes body = 2;
+//!   optional string content_type = 3;
+//!   optional string label = 4;
+//!   optional uint64 epoch = 5;
+//!   optional uint32 version = 6;
+//! }
+//!
+//! message Reply {
+//!   bytes body = 1;
+//! }
+//! ```
+//!
+//! # Examples
+//!
+//! Instances that use a [`GrpcTransport`] must serve a [`GrpcServer`], instead
+//! of handling HTTP/1 requests to `/register/local`.
+//!
+//! ```no_run
+//! use std::net::SocketAddr;
+//!
+//! use hyper::Uri;
+//! use tokio::net::TcpListener;
+//!
+//! use todc_net::register::AtomicRegister;
+//! use todc_net::transport::grpc::{GrpcServer, GrpcTransport};
+//! use todc_net::TokioIo;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let neighbors: Vec<Uri> = vec![Uri::from_static("http://my-register-2.com:3000")];
+//!     let register: AtomicRegister<String, GrpcTransport> =
+//!         AtomicRegister::with_transport(neighbors, GrpcTransport::default());
+//!
+//!     let server = GrpcServer::new(register);
+//!     let addr: SocketAddr = ([0, 0, 0, 0], 3000).into();
+//!     let listener = TcpListener::bind(addr).await?;
+//!     loop {
+//!         let (stream, _) = listener.accept().await?;
+//!         let server = server.clone();
+//!         tokio::task::spawn(async move {
+//!             if let Err(err) = server.serve_connection(TokioIo::new(stream)).await {
+//!                 println!("Error serving connection: {:?}", err)
+//!             }
+//!         });
+//!     }
+//! }
+//! ```
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use hyper::body::Incoming;
+use hyper::server::conn::http2;
+use hyper::service::Service;
+use hyper::{Request, Response, Uri};
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::MetadataMap;
+use tonic::server::{Grpc, UnaryService};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Status};
+
+use super::{format_versions, parse_versions, Handler, Message, Transport, UnsupportedVersion};
+use crate::net::TcpStream;
+use crate::{GenericError, TokioExecutor, TokioIo};
+
+/// The path of the gRPC method that messages are sent to.
+const SEND_PATH: &str = "/todc.Transport/Send";
+
+/// The metadata in which a server that rejects a message advertises the
+/// range of protocol versions that it understands.
+const VERSIONS_METADATA: &str = "x-protocol-versions";
+
+/// The protobuf encoding of a [`Message`].
+#[derive(Clone, PartialEq, prost::Message)]
+struct Envelope {
+    #[prost(string, tag = "1")]
+    path: String,
+    #[prost(bytes = "bytes", optional, tag = "2")]
+    body: Option<Bytes>,
+    #[prost(string, optional, tag = "3")]
+    content_type: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    label: Option<String>,
+    #[prost(uint64, optional, tag = "5")]
+    epoch: Option<u64>,
+    #[prost(uint32, optional, tag = "6")]
+    version: Option<u32>,
+}
+
+impl From<Message> for Envelope {
+    fn from(message: Message) -> Self {
+        Self {
+            path: message.path.into_owned(),
+            body: message.body,
+            content_type: message.content_type.map(Cow::into_owned),
+            label: message.label,
+            epoch: message.epoch,
+            version: message.version,
+        }
+    }
+}
+
+impl From<Envelope> for Message {
+    fn from(envelope: Envelope) -> Self {
+        Self {
+            path: envelope.path.into(),
+            body: envelope.body,
+            content_type: envelope.content_type.map(Cow::Owned),
+            label: envelope.label,
+            epoch: envelope.epoch,
+            version: envelope.version,
+        }
+    }
+}
+
+/// The protobuf encoding of a reply to a [`Message`].
+#[derive(Clone, PartialEq, prost::Message)]
+struct Reply {
+    #[prost(bytes = "bytes", tag = "1")]
+    body: Bytes,
+}
+
+/// A [`Transport`] that sends each message as a gRPC call.
+///
+/// A single HTTP/2 connection is maintained for each neighbor, and is shared
+/// by all clones of the transport. Concurrent messages to the same neighbor
+/// are multiplexed as streams over that connection. Connections are
+/// established lazily, when the first message is sent to a neighbor, and
+/// re-established automatically if they fail.
+#[derive(Clone, Default)]
+pub struct GrpcTransport {
+    channels: Arc<Mutex<HashMap<Uri, Channel>>>,
+}
+
+impl GrpcTransport {
+    /// Returns the channel used to communicate with a neighbor.
+    fn channel(&self, neighbor: &Uri) -> Result<Channel, GenericError> {
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(channel) = channels.get(neighbor) {
+            return Ok(channel.clone());
+        }
+        let channel =
+            Endpoint::from_shared(neighbor.to_string())?.connect_with_connector_lazy(Connector);
+        channels.insert(neighbor.clone(), channel.clone());
+        Ok(channel)
+    }
+}
+
+impl Transport for GrpcTransport {
+    fn send(
+        &self,
+        neighbor: Uri,
+        message: Message,
+    ) -> impl Future<Output = Result<Bytes, GenericError>> + Send {
+        let channel = self.channel(&neighbor);
+        async move {
+            let mut client = tonic::client::Grpc::new(channel?);
+            client.ready().await?;
+            let codec: ProstCodec<Envelope, Reply> = ProstCodec::default();
+            let version = message.version;
+            let request = tonic::Request::new(Envelope::from(message));
+            let path = PathAndQuery::from_static(SEND_PATH);
+            match client.unary(request, path, codec).await {
+                Ok(reply) => Ok(reply.into_inner().body),
+                Err(status) => match unsupported_version(version, &status) {
+                    Some(error) => Err(error.into()),
+                    None => Err(status.into()),
+                },
+            }
+        }
+    }
+}
+
+/// Opens connections to neighbors, using simulated networking when
+/// running tests.
+#[derive(Clone, Copy)]
+struct Connector;
+
+impl tonic::codegen::Service<tonic::transport::Uri> for Connector {
+    type Response = TokioIo<TcpStream>;
+    type Error = std::io::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: tonic::transport::Uri) -> Self::Future {
+        Box::pin(async move {
+            let host = uri.host().unwrap_or_default();
+            let port = uri.port_u16().unwrap_or(80);
+            let stream = TcpStream::connect(format!("{host}:{port}")).await?;
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
+
+/// A gRPC server that passes messages from neighbors to a [`Handler`], such
+/// as an [`AtomicRegister`](crate::register::AtomicRegister).
+#[derive(Clone)]
+pub struct GrpcServer<H: Handler> {
+    handler: H,
+}
+
+impl<H: Handler> GrpcServer<H> {
+    /// Creates a new server that passes messages to the handler.
+    pub fn new(handler: H) -> Self {
+        Self { handler }
+    }
+
+    /// Serves gRPC calls made over an HTTP/2 connection.
+    pub async fn serve_connection<I>(&self, io: I) -> Result<(), GenericError>
+    where
+        I: hyper::rt::Read + hyper::rt::Write + Unpin + Send + 'static,
+    {
+        http2::Builder::new(TokioExecutor)
+            .serve_connection(io, self.clone())
+            .await?;
+        Ok(())
+    }
+}
+
+impl<H: Handler> Service<Request<Incoming>> for GrpcServer<H> {
+    type Response = Response<BoxBody>;
+    type Error = GenericError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn call(&self, req: Request<Incoming>) -> Self::Future {
+        let handler = self.handler.clone();
+        Box::pin(async move {
+            if req.uri().path() != SEND_PATH {
+                return Ok(Status::unimplemented(req.uri().path()).into_http());
+            }
+            let codec: ProstCodec<Reply, Envelope> = ProstCodec::default();
+            let mut grpc = Grpc::new(codec);
+            Ok(grpc.unary(SendService { handler }, req).await)
+        })
+    }
+}
+
+/// The implementation of the `/todc.Transport/Send` gRPC method.
+struct SendService<H: Handler> {
+    handler: H,
+}
+
+impl<H: Handler> UnaryService<Envelope> for SendService<H> {
+    type Response = Reply;
+    type Future = Pin<Box<dyn Future<Output = Result<tonic::Response<Reply>, Status>> + Send>>;
+
+    fn call(&mut self, request: tonic::Request<Envelope>) -> Self::Future {
+        let handler = self.handler.clone();
+        Box::pin(async move {
+            let message = Message::from(request.into_inner());
+            match handler.handle(message).await {
+                Ok(body) => Ok(tonic::Response::new(Reply { body })),
+                Err(error) => Err(match error.downcast_ref::<UnsupportedVersion>() {
+                    Some(error) => rejected_version(error),
+                    None => Status::internal(error.to_string()),
+                }),
+            }
+        })
+    }
+}
+
+/// Returns the status with which a server rejects a message written in a
+/// version of the protocol that it does not understand, which advertises the
+/// versions that it does.
+fn rejected_version(error: &UnsupportedVersion) -> Status {
+    let mut metadata = MetadataMap::new();
+    let versions = format_versions(&error.supported).parse().unwrap();
+    metadata.insert(VERSIONS_METADATA, versions);
+    Status::with_metadata(Code::FailedPrecondition, error.to_string(), metadata)
+}
+
+/// Returns the error with which a server rejected a message written in the
+/// given version, if it advertised the versions that it understands.
+fn unsupported_version(version: Option<u32>, status: &Status) -> Option<UnsupportedVersion> {
+    if status.code() != Code::FailedPrecondition {
+        return None;
+    }
+    let supported = parse_versions(status.metadata().get(VERSIONS_METADATA)?.to_str().ok()?)?;
+    Some(UnsupportedVersion {
+        version: version?,
+        supported,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod envelope {
+        use super::*;
+        use prost::Message as _;
+
+        fn encode_and_decode(message: Message) -> Message {
+            let bytes = Envelope::from(message).encode_to_vec();
+            Message::from(Envelope::decode(bytes.as_slice()).unwrap())
+        }
+
+        #[test]
+        fn preserves_messages_without_body() {
+            let message = Message::ask("/register/local");
+            assert_eq!(encode_and_decode(message.clone()), message);
+        }
+
+        #[test]
+        fn preserves_messages_with_body() {
+            let message = Message::announce("/register/local", Bytes::from("123"));
+            assert_eq!(encode_and_decode(message.clone()), message);
+        }
+
+        #[test]
+        fn distinguishes_empty_body_from_no_body() {
+            let message = Message::announce("/register/local", Bytes::new());
+            assert_eq!(encode_and_decode(message.clone()), message);
+        }
+
+        #[test]
+        fn preserves_content_type() {
+            let message = Message::announce("/register/local", Bytes::from("123"))
+                .with_content_type("application/cbor");
+            assert_eq!(encode_and_decode(message.clone()), message);
+        }
+
+        #[test]
+        fn preserves_label() {
+            let message = Message::announce("/register/local", Bytes::from("123"))
+                .with_label(String::from("1"));
+            assert_eq!(encode_and_decode(message.clone()), message);
+        }
+
+        #[test]
+        fn preserves_epoch() {
+            let message = Message::ask("/register/local").with_epoch(3);
+            assert_eq!(encode_and_decode(message.clone()), message);
+        }
+
+        #[test]
+        fn preserves_version() {
+            let message = Message::ask("/register/local").with_version(1);
+            assert_eq!(encode_and_decode(message.clone()), message);
+        }
+    }
+
+    mod unsupported_version {
+        use super::*;
+
+        #[test]
+        fn returns_versions_advertised_by_server() {
+            let rejected = UnsupportedVersion {
+                version: 3,
+                supported: 1..=2,
+            };
+            let status = rejected_version(&rejected);
+            assert_eq!(unsupported_version(Some(3), &status), Some(rejected));
+        }
+
+        #[test]
+        fn returns_none_for_other_statuses() {
+            let status = Status::internal("Scripted failure");
+            assert!(unsupported_version(Some(3), &status).is_none());
+        }
+    }
+}
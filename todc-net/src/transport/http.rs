@@ -0,0 +1,494 @@
+//! A transport that sends messages over HTTP/1 or HTTP/2.
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use bytes::{Bytes, BytesMut};
+use http_body_util::combinators::BoxBody;
+use http_body_util::BodyExt;
+use hyper::body::{Body, Frame, SizeHint};
+use hyper::client::conn::http2::{self, SendRequest};
+use hyper::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE};
+use hyper::http::uri::PathAndQuery;
+use hyper::http::StatusCode;
+use hyper::{Method, Request, Uri};
+use sha2::{Digest, Sha256};
+
+use super::{parse_versions, Message, Transport, UnsupportedVersion};
+use crate::net::TcpStream;
+use crate::{full, send_request, GenericError, TokioExecutor, TokioIo};
+
+/// The header in which the label of a value is sent, when it is sent
+/// separately from the value.
+pub(crate) const LABEL_HEADER: &str = "x-register-label";
+
+/// The header in which the epoch of the sender of a message is sent.
+pub(crate) const EPOCH_HEADER: &str = "x-epoch";
+
+/// The header in which the version of the protocol in which a message is
+/// written is sent.
+pub(crate) const VERSION_HEADER: &str = "x-protocol-version";
+
+/// The header in which an instance that rejects a message advertises the
+/// range of protocol versions that it understands.
+pub(crate) const VERSIONS_HEADER: &str = "x-protocol-versions";
+
+/// The header in which the hex-encoded SHA-256 digest of a streamed body is
+/// sent.
+pub(crate) const DIGEST_HEADER: &str = "x-content-sha256";
+
+/// The size of the largest chunk in which a streamed body is sent.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A [`Transport`] that sends each message as an HTTP request.
+///
+/// Messages without a body are sent as `GET` requests, and all others as
+/// `POST` requests, to the path of the message on the neighbors host. The
+/// media type of the message, if any, is sent in the `Content-Type` and
+/// `Accept` headers of the request, and its epoch, if any, in the `X-Epoch`
+/// header. Messages with a
+/// [`label`](Message::label) are streamed, as described in the
+/// [`transport`](crate::transport#streaming) module. This is the transport
+/// expected by the
+/// [`Service`](hyper::service::Service) implementation of
+/// [`AtomicRegister`](crate::register::AtomicRegister).
+///
+/// By default, each message is sent over a new HTTP/1 connection. With
+/// [`with_http2`](HttpTransport::with_http2), a single HTTP/2 connection is
+/// instead maintained for each neighbor, and is shared by all clones of the
+/// transport. Concurrent messages to the same neighbor are multiplexed as
+/// streams over that connection. Connections are established lazily, when
+/// the first message is sent to a neighbor, and re-established if they fail.
+///
+/// # Examples
+///
+/// Instances that use HTTP/2 must serve connections with
+/// [`http2::Builder`](hyper::server::conn::http2::Builder), instead of
+/// [`http1::Builder`](hyper::server::conn::http1::Builder).
+///
+/// ```no_run
+/// use std::net::SocketAddr;
+///
+/// use hyper::server::conn::http2;
+/// use hyper::Uri;
+/// use tokio::net::TcpListener;
+///
+/// use todc_net::register::AtomicRegister;
+/// use todc_net::transport::HttpTransport;
+/// use todc_net::{TokioExecutor, TokioIo};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+///     let neighbors: Vec<Uri> = vec![Uri::from_static("http://my-register-2.com:3000")];
+///     let transport = HttpTransport::default().with_http2(true);
+///     let register: AtomicRegister<String> =
+///         AtomicRegister::with_transport(neighbors, transport);
+///
+///     let addr: SocketAddr = ([0, 0, 0, 0], 3000).into();
+///     let listener = TcpListener::bind(addr).await?;
+///     loop {
+///         let (stream, _) = listener.accept().await?;
+///         let register = register.clone();
+///         tokio::task::spawn(async move {
+///             if let Err(err) = http2::Builder::new(TokioExecutor)
+///                 .serve_connection(TokioIo::new(stream), register)
+///                 .await
+///             {
+///                 println!("Error serving connection: {:?}", err)
+///             }
+///         });
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HttpTransport {
+    connections: Option<Arc<Mutex<HashMap<Uri, Connection>>>>,
+}
+
+/// A multiplexed HTTP/2 connection to a neighbor.
+type Connection = Arc<tokio::sync::Mutex<Option<SendRequest<BoxBody<Bytes, hyper::Error>>>>>;
+
+impl HttpTransport {
+    /// Sets whether messages are multiplexed over a single HTTP/2 connection
+    /// to each neighbor, instead of each being sent over a new HTTP/1
+    /// connection.
+    ///
+    /// All instances of an object must agree on whether HTTP/2 is used.
+    pub fn with_http2(mut self, enabled: bool) -> Self {
+        self.connections = enabled.then(Default::default);
+        self
+    }
+
+    /// Returns whether messages are sent over HTTP/2.
+    pub fn is_http2(&self) -> bool {
+        self.connections.is_some()
+    }
+
+    /// Returns a handle to an open HTTP/2 connection to a neighbor,
+    /// establishing a new connection if there is not one already.
+    async fn sender(
+        connections: &Mutex<HashMap<Uri, Connection>>,
+        neighbor: &Uri,
+    ) -> Result<SendRequest<BoxBody<Bytes, hyper::Error>>, GenericError> {
+        let connection = connections
+            .lock()
+            .unwrap()
+            .entry(neighbor.clone())
+            .or_default()
+            .clone();
+        // Holding the lock while connecting ensures that concurrent messages
+        // to the same neighbor share a single connection.
+        let mut connection = connection.lock().await;
+        if let Some(sender) = connection.as_ref().filter(|sender| !sender.is_closed()) {
+            return Ok(sender.clone());
+        }
+        let authority = neighbor.authority().ok_or("Invalid URL")?.as_str();
+        let stream = TcpStream::connect(authority).await?;
+        let (sender, conn) = http2::handshake(TokioExecutor, TokioIo::new(stream)).await?;
+        tokio::task::spawn(async move {
+            if let Err(err) = conn.await {
+                tracing::debug!(error = %err, "connection failed");
+            }
+        });
+        *connection = Some(sender.clone());
+        Ok(sender)
+    }
+}
+
+impl Transport for HttpTransport {
+    async fn send(&self, neighbor: Uri, message: Message) -> Result<Bytes, GenericError> {
+        let url = url(neighbor.clone(), message.path)?;
+        let (method, body) = match message.body {
+            None => (Method::GET, Bytes::new()),
+            Some(body) => (Method::POST, body),
+        };
+        let mut req = Request::builder().uri(url).method(&method);
+        if let Some(content_type) = message.content_type {
+            let content_type = HeaderValue::from_maybe_shared(shared(content_type))?;
+            if method == Method::POST {
+                req = req.header(CONTENT_TYPE, content_type.clone());
+            }
+            req = req.header(ACCEPT, content_type);
+        }
+        if let Some(epoch) = message.epoch {
+            req = req.header(EPOCH_HEADER, epoch);
+        }
+        if let Some(version) = message.version {
+            req = req.header(VERSION_HEADER, version);
+        }
+        let req = match message.label {
+            None => req.body(full(body))?,
+            Some(label) => req
+                .header(LABEL_HEADER, label)
+                .header(DIGEST_HEADER, digest(&body))
+                .body(chunked(body))?,
+        };
+
+        let response = match &self.connections {
+            None => send_request(req).await?,
+            Some(connections) => {
+                let mut sender = Self::sender(connections, &neighbor).await?;
+                sender.ready().await?;
+                sender.send_request(req).await?
+            }
+        };
+
+        if response.status().is_server_error() {
+            return Err(GenericError::from("Unexpected server error"));
+        }
+        // The neighbor does not understand the version of the protocol in
+        // which the message was written. Neighbors that advertise the
+        // versions that they do understand are answered in one of them by the
+        // sender, and others explain why in their reply.
+        if response.status() == StatusCode::UPGRADE_REQUIRED {
+            if let Some(error) = unsupported_version(message.version, response.headers()) {
+                return Err(error.into());
+            }
+            let reason = response.collect().await?.to_bytes();
+            return Err(String::from_utf8_lossy(&reason).into_owned().into());
+        }
+        Ok(response.collect().await?.to_bytes())
+    }
+}
+
+/// Returns the error with which a neighbor rejected a message written in the
+/// given version, if it advertised the versions that it understands.
+fn unsupported_version(version: Option<u32>, headers: &HeaderMap) -> Option<UnsupportedVersion> {
+    let supported = parse_versions(headers.get(VERSIONS_HEADER)?.to_str().ok()?)?;
+    Some(UnsupportedVersion {
+        version: version?,
+        supported,
+    })
+}
+
+/// A body that yields its contents in chunks of at most [`CHUNK_SIZE`]
+/// bytes, each of which shares the memory of the original contents.
+struct Chunked(Bytes);
+
+impl Body for Chunked {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        if self.0.is_empty() {
+            return Poll::Ready(None);
+        }
+        let size = self.0.len().min(CHUNK_SIZE);
+        Poll::Ready(Some(Ok(Frame::data(self.0.split_to(size)))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.0.len() as u64)
+    }
+}
+
+/// Returns a body that streams the given bytes in chunks.
+fn chunked(body: Bytes) -> BoxBody<Bytes, hyper::Error> {
+    Chunked(body).map_err(|never| match never {}).boxed()
+}
+
+/// Returns the hex-encoded SHA-256 digest of some contents.
+fn digest(contents: &[u8]) -> String {
+    to_hex(&Sha256::digest(contents))
+}
+
+/// Returns the lowercase hex encoding of some bytes.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Collects the chunks of a streamed body, and checks that they match the
+/// digest sent in the given headers, if there is one.
+///
+/// The digest is computed as each chunk arrives, so the contents are only
+/// held in memory once. Contents that arrive in a single chunk are returned
+/// without being copied.
+pub(crate) async fn collect_streamed<B>(
+    mut body: B,
+    headers: &HeaderMap,
+) -> Result<Bytes, GenericError>
+where
+    B: Body<Data = Bytes> + Unpin,
+    B::Error: Into<GenericError>,
+{
+    let mut first: Option<Bytes> = None;
+    let mut copied: Option<BytesMut> = None;
+    let mut hasher = Sha256::new();
+    while let Some(frame) = body.frame().await {
+        if let Ok(chunk) = frame.map_err(Into::<GenericError>::into)?.into_data() {
+            hasher.update(&chunk);
+            match (&first, &mut copied) {
+                (None, _) => first = Some(chunk),
+                (Some(_), Some(contents)) => contents.extend_from_slice(&chunk),
+                // Once a second chunk arrives, the chunks must be copied into
+                // contiguous memory.
+                (Some(first), None) => {
+                    let mut contents = BytesMut::from(&first[..]);
+                    contents.extend_from_slice(&chunk);
+                    copied = Some(contents);
+                }
+            }
+        }
+    }
+    let contents = match copied {
+        Some(contents) => contents.freeze(),
+        None => first.unwrap_or_default(),
+    };
+    if let Some(expected) = headers.get(DIGEST_HEADER) {
+        if expected.as_bytes() != to_hex(&hasher.finalize()).as_bytes() {
+            return Err(GenericError::from(
+                "The contents of the message do not match their digest",
+            ));
+        }
+    }
+    Ok(contents)
+}
+
+/// Returns the URL at which a route of a neighbor can be reached.
+fn url(neighbor: Uri, path: Cow<'static, str>) -> Result<Uri, GenericError> {
+    let mut parts = neighbor.into_parts();
+    parts.path_and_query = Some(PathAndQuery::from_maybe_shared(shared(path))?);
+    Ok(Uri::from_parts(parts)?)
+}
+
+/// Returns the bytes of some text, without copying them if they are static.
+fn shared(text: Cow<'static, str>) -> Bytes {
+    match text {
+        Cow::Borrowed(text) => Bytes::from_static(text.as_bytes()),
+        Cow::Owned(text) => Bytes::from(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod http_transport {
+        use super::*;
+
+        #[test]
+        fn uses_http1_by_default() {
+            assert!(!HttpTransport::default().is_http2());
+        }
+
+        #[test]
+        fn with_http2_enables_http2() {
+            assert!(HttpTransport::default().with_http2(true).is_http2());
+        }
+
+        #[test]
+        fn with_http2_can_disable_http2() {
+            let transport = HttpTransport::default().with_http2(true).with_http2(false);
+            assert!(!transport.is_http2());
+        }
+
+        #[test]
+        fn clones_share_connections() {
+            let transport = HttpTransport::default().with_http2(true);
+            let clone = transport.clone();
+            assert!(Arc::ptr_eq(
+                transport.connections.as_ref().unwrap(),
+                clone.connections.as_ref().unwrap()
+            ));
+        }
+    }
+
+    mod chunked {
+        use super::*;
+
+        #[tokio::test]
+        async fn yields_contents_in_chunks() {
+            let contents = Bytes::from(vec![7; CHUNK_SIZE + 1]);
+            let mut body = Chunked(contents.clone());
+            let mut chunks = Vec::new();
+            while let Some(frame) = body.frame().await {
+                chunks.push(frame.unwrap().into_data().unwrap());
+            }
+            assert_eq!(
+                chunks.iter().map(Bytes::len).collect::<Vec<_>>(),
+                vec![CHUNK_SIZE, 1]
+            );
+            assert_eq!(chunks.concat(), contents);
+        }
+    }
+
+    mod collect_streamed {
+        use super::*;
+        use http_body_util::Full;
+
+        fn headers(digest: &str) -> HeaderMap {
+            let mut headers = HeaderMap::new();
+            headers.insert(DIGEST_HEADER, digest.parse().unwrap());
+            headers
+        }
+
+        #[test]
+        fn computes_sha256_digest() {
+            assert_eq!(
+                digest(b"abc"),
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+            );
+        }
+
+        #[tokio::test]
+        async fn returns_contents_that_match_digest() {
+            let body = Full::new(Bytes::from("123"));
+            let contents = collect_streamed(body, &headers(&digest(b"123")))
+                .await
+                .unwrap();
+            assert_eq!(contents, "123");
+        }
+
+        #[tokio::test]
+        async fn does_not_copy_contents_of_single_chunk() {
+            let contents = Bytes::from("123");
+            let body = Full::new(contents.clone());
+            let collected = collect_streamed(body, &headers(&digest(b"123")))
+                .await
+                .unwrap();
+            assert_eq!(collected.as_ptr(), contents.as_ptr());
+        }
+
+        #[tokio::test]
+        async fn joins_contents_of_many_chunks() {
+            let contents = Bytes::from(vec![7; CHUNK_SIZE * 2 + 1]);
+            let body = Chunked(contents.clone());
+            let collected = collect_streamed(body, &headers(&digest(&contents)))
+                .await
+                .unwrap();
+            assert_eq!(collected, contents);
+        }
+
+        #[tokio::test]
+        async fn returns_contents_without_digest() {
+            let body = Full::new(Bytes::from("123"));
+            let contents = collect_streamed(body, &HeaderMap::new()).await.unwrap();
+            assert_eq!(contents, "123");
+        }
+
+        #[tokio::test]
+        async fn rejects_contents_that_do_not_match_digest() {
+            let body = Full::new(Bytes::from("123"));
+            let error = collect_streamed(body, &headers(&digest(b"456")))
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("do not match their digest"));
+        }
+    }
+
+    mod unsupported_version {
+        use super::*;
+
+        fn headers(versions: &str) -> HeaderMap {
+            let mut headers = HeaderMap::new();
+            headers.insert(VERSIONS_HEADER, versions.parse().unwrap());
+            headers
+        }
+
+        #[test]
+        fn returns_versions_advertised_by_neighbor() {
+            let error = unsupported_version(Some(3), &headers("1-2")).unwrap();
+            assert_eq!(error.version, 3);
+            assert_eq!(error.supported, 1..=2);
+        }
+
+        #[test]
+        fn returns_none_if_neighbor_does_not_advertise_versions() {
+            assert!(unsupported_version(Some(3), &HeaderMap::new()).is_none());
+        }
+
+        #[test]
+        fn returns_none_if_advertised_versions_are_invalid() {
+            assert!(unsupported_version(Some(3), &headers("1")).is_none());
+            assert!(unsupported_version(Some(3), &headers("2-1")).is_none());
+            assert!(unsupported_version(Some(3), &headers("a-b")).is_none());
+        }
+
+        #[test]
+        fn returns_none_if_message_has_no_version() {
+            assert!(unsupported_version(None, &headers("1-2")).is_none());
+        }
+    }
+
+    mod url {
+        use super::*;
+
+        #[test]
+        fn replaces_path_of_neighbor() {
+            let neighbor = Uri::from_static("http://test.com");
+            let url = url(neighbor, "/register/local".into()).unwrap();
+            assert_eq!(url.host().unwrap(), "test.com");
+            assert_eq!(url.path(), "/register/local");
+        }
+    }
+}
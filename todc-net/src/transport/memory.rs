@@ -0,0 +1,287 @@
+//! An in-memory transport, for testing the logic of an algorithm without a
+//! network.
+//!
+//! A [`MemoryTransport`] delivers every message that it sends to an
+//! [`Inbox`], as an [`Envelope`]. A test then decides, one message at a time,
+//! how each neighbor replies: with scripted contents, with a failure, by
+//! handing the message to a real instance with [`forward`](Envelope::forward),
+//! or not at all. Messages are only delivered when the test asks for them, so
+//! the order in which neighbors reply is entirely up to the test, and the
+//! same test always interleaves messages the same way.
+//!
+//! # Examples
+//!
+//! ```
+//! use hyper::Uri;
+//! use todc_net::transport::{memory, Message, Transport};
+//!
+//! # tokio_test::block_on(async {
+//! let (transport, mut inbox) = memory::channel();
+//! let neighbor = Uri::from_static("http://neighbor");
+//!
+//! let (reply, ()) = tokio::join!(
+//!     transport.send(neighbor.clone(), Message::ask("/hello")),
+//!     async {
+//!         let envelope = inbox.recv().await.unwrap();
+//!         assert_eq!(envelope.neighbor(), &neighbor);
+//!         assert_eq!(envelope.message().path, "/hello");
+//!         envelope.reply("world");
+//!     }
+//! );
+//! assert_eq!(reply.unwrap(), "world");
+//! # })
+//! ```
+use std::future::Future;
+
+use bytes::Bytes;
+use hyper::Uri;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::transport::{Handler, Message, Transport};
+use crate::GenericError;
+
+/// Creates a [`MemoryTransport`], along with the [`Inbox`] to which it
+/// delivers messages.
+pub fn channel() -> (MemoryTransport, Inbox) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    (MemoryTransport { sender }, Inbox { receiver })
+}
+
+/// A [`Transport`] that delivers messages to an [`Inbox`], rather than over
+/// a network.
+///
+/// Sending fails if the inbox has been dropped, or if the envelope containing
+/// the message is dropped without a reply, as if the neighbor were
+/// unreachable.
+#[derive(Clone, Debug)]
+pub struct MemoryTransport {
+    sender: mpsc::UnboundedSender<Envelope>,
+}
+
+impl Transport for MemoryTransport {
+    async fn send(&self, neighbor: Uri, message: Message) -> Result<Bytes, GenericError> {
+        let (reply, replied) = oneshot::channel();
+        let envelope = Envelope {
+            neighbor,
+            message,
+            reply,
+        };
+        self.sender
+            .send(envelope)
+            .map_err(|_| "Neighbor is unreachable")?;
+        replied.await.map_err(|_| "Neighbor did not reply")?
+    }
+}
+
+/// The messages sent over a [`MemoryTransport`], in the order they were sent.
+#[derive(Debug)]
+pub struct Inbox {
+    receiver: mpsc::UnboundedReceiver<Envelope>,
+}
+
+impl Inbox {
+    /// Waits for the next message to be sent, and returns it.
+    ///
+    /// Returns `None` once every [`MemoryTransport`] that sends to this inbox
+    /// has been dropped.
+    pub async fn recv(&mut self) -> Option<Envelope> {
+        self.receiver.recv().await
+    }
+
+    /// Returns the next message, if one has already been sent.
+    pub fn try_recv(&mut self) -> Option<Envelope> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Drives a future to completion, while forwarding every message that
+    /// is sent in the meantime to `handler`, and returns its output.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use hyper::Uri;
+    /// use todc_net::register::AtomicRegister;
+    /// use todc_net::transport::memory::{self, MemoryTransport};
+    ///
+    /// # tokio_test::block_on(async {
+    /// let (transport, mut inbox) = memory::channel();
+    /// let neighbor = Uri::from_static("http://neighbor");
+    /// let register: AtomicRegister<u32, MemoryTransport> =
+    ///     AtomicRegister::with_transport(vec![neighbor], transport);
+    /// let reachable: AtomicRegister<u32> = AtomicRegister::default();
+    ///
+    /// let write = register.write(123);
+    /// inbox.forward_until(&reachable, write).await.unwrap();
+    /// assert_eq!(reachable.read().await.unwrap(), 123);
+    /// # })
+    /// ```
+    pub async fn forward_until<H: Handler, F: Future>(
+        &mut self,
+        handler: &H,
+        future: F,
+    ) -> F::Output {
+        self.forward_until_with(handler, future, Some).await
+    }
+
+    /// Drives a future to completion, while passing every message that is
+    /// sent in the meantime to `intercept`, and returns its output.
+    ///
+    /// The interceptor may reply to a message itself, fail it, or drop it, in
+    /// which case it returns `None`. Otherwise it returns the message, which
+    /// is then forwarded to `handler`.
+    pub async fn forward_until_with<H: Handler, F: Future>(
+        &mut self,
+        handler: &H,
+        future: F,
+        mut intercept: impl FnMut(Envelope) -> Option<Envelope>,
+    ) -> F::Output {
+        tokio::pin!(future);
+        loop {
+            tokio::select! {
+                output = &mut future => return output,
+                Some(envelope) = self.recv() => {
+                    if let Some(envelope) = intercept(envelope) {
+                        envelope.forward(handler).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A message that was sent to a neighbor, and is waiting for a reply.
+///
+/// Dropping an envelope without replying fails the request that sent it.
+#[derive(Debug)]
+pub struct Envelope {
+    neighbor: Uri,
+    message: Message,
+    reply: oneshot::Sender<Result<Bytes, GenericError>>,
+}
+
+impl Envelope {
+    /// Returns the neighbor that the message was sent to.
+    pub fn neighbor(&self) -> &Uri {
+        &self.neighbor
+    }
+
+    /// Returns the message.
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// Replies to the message with the given contents.
+    pub fn reply(self, body: impl Into<Bytes>) {
+        // The sender may have stopped waiting, for example after enough
+        // other neighbors replied, in which case the reply is discarded.
+        let _ = self.reply.send(Ok(body.into()));
+    }
+
+    /// Fails the request that sent the message with the given error.
+    pub fn fail(self, error: impl Into<GenericError>) {
+        let _ = self.reply.send(Err(error.into()));
+    }
+
+    /// Hands the message to an instance, and replies with whatever it
+    /// replies with.
+    pub async fn forward<H: Handler>(self, handler: &H) {
+        let reply = handler.handle(self.message).await;
+        let _ = self.reply.send(reply);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn neighbor() -> Uri {
+        Uri::from_static("http://neighbor")
+    }
+
+    mod memory_transport {
+        use super::*;
+
+        #[tokio::test]
+        async fn fails_if_envelope_is_dropped() {
+            let (transport, mut inbox) = channel();
+            let (reply, ()) = tokio::join!(transport.send(neighbor(), Message::ask("/")), async {
+                drop(inbox.recv().await);
+            });
+            assert!(reply.is_err());
+        }
+
+        #[tokio::test]
+        async fn fails_if_inbox_is_dropped() {
+            let (transport, inbox) = channel();
+            drop(inbox);
+            let reply = transport.send(neighbor(), Message::ask("/")).await;
+            assert!(reply.is_err());
+        }
+
+        #[tokio::test]
+        async fn returns_scripted_failure() {
+            let (transport, mut inbox) = channel();
+            let (reply, ()) = tokio::join!(transport.send(neighbor(), Message::ask("/")), async {
+                inbox.recv().await.unwrap().fail("Scripted failure");
+            });
+            assert_eq!(reply.unwrap_err().to_string(), "Scripted failure");
+        }
+    }
+
+    mod inbox {
+        use super::*;
+        use crate::register::AtomicRegister;
+
+        #[tokio::test]
+        async fn delivers_messages_in_order_they_were_sent() {
+            let (transport, mut inbox) = channel();
+            let first = tokio::spawn({
+                let transport = transport.clone();
+                async move { transport.send(neighbor(), Message::ask("/first")).await }
+            });
+            let envelope = inbox.recv().await.unwrap();
+            let second =
+                tokio::spawn(
+                    async move { transport.send(neighbor(), Message::ask("/second")).await },
+                );
+            assert_eq!(envelope.message().path, "/first");
+            envelope.reply("");
+            assert_eq!(inbox.recv().await.unwrap().message().path, "/second");
+            first.await.unwrap().unwrap();
+            assert!(second.await.unwrap().is_err());
+        }
+
+        #[tokio::test]
+        async fn forwards_only_messages_that_are_not_intercepted() {
+            let (transport, mut inbox) = channel();
+            let handler: AtomicRegister<u32> = AtomicRegister::default();
+            let sends = async {
+                let first = transport.send(neighbor(), Message::ask("/register/local"));
+                let second = transport.send(neighbor(), Message::ask("/register/local"));
+                tokio::join!(first, second)
+            };
+            let mut intercepted = 0;
+            let (first, second) = inbox
+                .forward_until_with(&handler, sends, |envelope| {
+                    intercepted += 1;
+                    if intercepted == 1 {
+                        envelope.fail("Intercepted");
+                        None
+                    } else {
+                        Some(envelope)
+                    }
+                })
+                .await;
+            assert_eq!(first.unwrap_err().to_string(), "Intercepted");
+            assert!(second.is_ok());
+        }
+
+        #[tokio::test]
+        async fn returns_none_once_transport_is_dropped() {
+            let (transport, mut inbox) = channel();
+            assert!(inbox.try_recv().is_none());
+            drop(transport);
+            assert!(inbox.recv().await.is_none());
+        }
+    }
+}
@@ -0,0 +1,9 @@
+#![allow(dead_code, unused_imports)]
+mod broadcast {
+    #[cfg(feature = "turmoil")]
+    mod common;
+    #[cfg(feature = "turmoil")]
+    mod reliable;
+    #[cfg(feature = "turmoil")]
+    mod total_order;
+}
@@ -0,0 +1,20 @@
+use turmoil::Sim;
+
+use todc_net::broadcast::{ReliableBroadcast, TotalOrderBroadcast};
+use todc_net::testing::SimulatedCluster;
+
+/// Simulate n instances of a reliable broadcast.
+pub fn simulate_reliable<'a>(n: usize) -> (Sim<'a>, Vec<ReliableBroadcast<u32>>) {
+    SimulatedCluster::with_instances(n, |i, neighbors| {
+        ReliableBroadcast::new(i as u32, neighbors)
+    })
+    .into_parts()
+}
+
+/// Simulate n instances of a total-order broadcast.
+pub fn simulate_total_order<'a>(n: usize) -> (Sim<'a>, Vec<TotalOrderBroadcast<u32>>) {
+    SimulatedCluster::with_instances(n, |i, neighbors| {
+        TotalOrderBroadcast::new(i as u32, neighbors)
+    })
+    .into_parts()
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use crate::broadcast::common::simulate_reliable;
+
+#[test]
+fn message_is_delivered_by_all_instances() {
+    let (mut sim, instances) = simulate_reliable(3);
+    sim.client("client", async move {
+        let mut deliveries: Vec<_> = instances.iter().map(|i| i.subscribe()).collect();
+        instances[0].broadcast(123).await.unwrap();
+        for deliveries in deliveries.iter_mut() {
+            assert_eq!(deliveries.recv().await, Some(123));
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn message_is_delivered_once_by_each_instance() {
+    let (mut sim, instances) = simulate_reliable(3);
+    sim.client("client", async move {
+        let mut deliveries = instances[1].subscribe();
+        instances[0].broadcast(1).await.unwrap();
+        instances[0].broadcast(2).await.unwrap();
+
+        let mut received = vec![
+            deliveries.recv().await.unwrap(),
+            deliveries.recv().await.unwrap(),
+        ];
+        received.sort();
+        assert_eq!(received, vec![1, 2]);
+
+        // Wait for all relayed copies of the messages to arrive, and make
+        // sure that none of them are delivered again.
+        let result = tokio::time::timeout(Duration::from_secs(1), deliveries.recv()).await;
+        assert!(result.is_err());
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn message_is_relayed_to_instances_unreachable_from_sender() {
+    let (mut sim, instances) = simulate_reliable(3);
+    sim.client("client", async move {
+        let mut deliveries = instances[2].subscribe();
+        // The broadcast is sent from the client, so server-2 can only
+        // receive the message if server-1 relays it.
+        turmoil::partition("client", "server-2");
+        instances[0].broadcast(123).await.unwrap();
+        assert_eq!(deliveries.recv().await, Some(123));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use crate::broadcast::common::simulate_total_order;
+
+#[test]
+fn messages_are_delivered_by_all_instances() {
+    let (mut sim, instances) = simulate_total_order(3);
+    sim.client("client", async move {
+        instances[0].broadcast(1).await.unwrap();
+        instances[1].broadcast(2).await.unwrap();
+        for instance in instances.iter() {
+            instance.sync().await.unwrap();
+            let mut deliveries = instance.subscribe();
+            assert_eq!(deliveries.recv().await, 1);
+            assert_eq!(deliveries.recv().await, 2);
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn concurrent_messages_are_delivered_in_same_order_by_all_instances() {
+    const NUM_INSTANCES: usize = 3;
+    const NUM_MESSAGES: u32 = 5;
+    let (mut sim, instances) = simulate_total_order(NUM_INSTANCES);
+    sim.set_max_message_latency(Duration::from_millis(10));
+
+    for (i, instance) in instances.iter().enumerate() {
+        let instance = instance.clone();
+        sim.client(format!("client-{i}"), async move {
+            for j in 0..NUM_MESSAGES {
+                instance
+                    .broadcast(i as u32 * NUM_MESSAGES + j)
+                    .await
+                    .unwrap();
+            }
+            Ok(())
+        });
+    }
+    sim.run().unwrap();
+
+    sim.client("reader", async move {
+        let total = NUM_INSTANCES * NUM_MESSAGES as usize;
+        let mut orders = Vec::new();
+        for instance in instances.iter() {
+            instance.sync().await.unwrap();
+            let mut deliveries = instance.subscribe();
+            let mut order = Vec::new();
+            for _ in 0..total {
+                order.push(deliveries.recv().await);
+            }
+            orders.push(order);
+        }
+        assert!(orders.iter().all(|order| *order == orders[0]));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn subscribers_wait_for_later_messages() {
+    let (mut sim, instances) = simulate_total_order(3);
+    let subscriber = instances[1].clone();
+    sim.client("subscriber", async move {
+        let mut deliveries = subscriber.subscribe();
+        assert_eq!(deliveries.recv().await, 123);
+        Ok(())
+    });
+    sim.client("broadcaster", async move {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        instances[0].broadcast(123).await.unwrap();
+        // Keep the client running until the message has been announced.
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        Ok(())
+    });
+    sim.run().unwrap();
+}
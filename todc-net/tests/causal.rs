@@ -0,0 +1,7 @@
+#![allow(dead_code, unused_imports)]
+mod causal {
+    #[cfg(feature = "turmoil")]
+    mod broadcast;
+    #[cfg(feature = "turmoil")]
+    mod common;
+}
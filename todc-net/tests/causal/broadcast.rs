@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use crate::causal::common::simulate_causal;
+
+#[test]
+fn message_is_delivered_by_all_instances() {
+    let (mut sim, instances) = simulate_causal(3);
+    sim.client("client", async move {
+        let mut deliveries: Vec<_> = instances.iter().map(|i| i.subscribe()).collect();
+        instances[0].broadcast(123).await.unwrap();
+        for deliveries in deliveries.iter_mut() {
+            assert_eq!(deliveries.recv().await, Some(123));
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn reply_is_not_delivered_before_message_it_responds_to() {
+    let (mut sim, instances) = simulate_causal(3);
+    sim.client("client", async move {
+        let mut deliveries = instances[1].subscribe();
+        let mut observer = instances[2].subscribe();
+
+        // Prevent the first message from reaching server-2.
+        turmoil::hold("client", "server-2");
+        turmoil::hold("server-1", "server-2");
+        instances[0].broadcast(1).await.unwrap();
+        assert_eq!(deliveries.recv().await, Some(1));
+
+        // The reply reaches server-2 when it is relayed by server-0, but
+        // must not be delivered until the message that preceded it is.
+        instances[1].broadcast(2).await.unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(1), observer.recv()).await;
+        assert!(result.is_err());
+
+        turmoil::release("client", "server-2");
+        turmoil::release("server-1", "server-2");
+        assert_eq!(observer.recv().await, Some(1));
+        assert_eq!(observer.recv().await, Some(2));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
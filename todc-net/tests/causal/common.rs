@@ -0,0 +1,10 @@
+use turmoil::Sim;
+
+use todc_net::causal::CausalBroadcast;
+use todc_net::testing::SimulatedCluster;
+
+/// Simulate n instances of a causal broadcast.
+pub fn simulate_causal<'a>(n: usize) -> (Sim<'a>, Vec<CausalBroadcast<u32>>) {
+    SimulatedCluster::with_instances(n, |i, neighbors| CausalBroadcast::new(i as u32, neighbors))
+        .into_parts()
+}
@@ -0,0 +1,11 @@
+#![allow(dead_code, unused_imports)]
+mod consensus {
+    #[cfg(feature = "turmoil")]
+    mod common;
+    #[cfg(feature = "turmoil")]
+    mod paxos;
+    #[cfg(feature = "turmoil")]
+    mod replicated_log;
+    #[cfg(all(feature = "turmoil", feature = "replication"))]
+    mod replicated_object;
+}
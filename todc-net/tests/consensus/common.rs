@@ -0,0 +1,15 @@
+use turmoil::Sim;
+
+use todc_net::consensus::{Paxos, ReplicatedLog};
+use todc_net::testing::SimulatedCluster;
+
+/// Simulate n instances of Paxos.
+pub fn simulate_paxos<'a>(n: usize) -> (Sim<'a>, Vec<Paxos<u32>>) {
+    SimulatedCluster::with_instances(n, |i, neighbors| Paxos::new(i as u32, neighbors)).into_parts()
+}
+
+/// Simulate n instances of a replicated log.
+pub fn simulate_logs<'a>(n: usize) -> (Sim<'a>, Vec<ReplicatedLog<u32>>) {
+    SimulatedCluster::with_instances(n, |i, neighbors| ReplicatedLog::new(i as u32, neighbors))
+        .into_parts()
+}
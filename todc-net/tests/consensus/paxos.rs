@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use crate::consensus::common::simulate_paxos;
+
+#[test]
+fn propose_returns_proposed_value() {
+    let (mut sim, instances) = simulate_paxos(3);
+    sim.client("client", async move {
+        let value = instances[0].propose(123).await.unwrap();
+        assert_eq!(value, 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn propose_returns_value_chosen_by_other_instance() {
+    let (mut sim, instances) = simulate_paxos(3);
+    sim.client("client", async move {
+        instances[1].propose(123).await.unwrap();
+        let value = instances[0].propose(456).await.unwrap();
+        assert_eq!(value, 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn decided_value_is_announced_to_other_instances() {
+    let (mut sim, instances) = simulate_paxos(3);
+    sim.client("client", async move {
+        instances[0].propose(123).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(instances[1].decided(), Some(123));
+        assert_eq!(instances[2].decided(), Some(123));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn concurrent_proposals_agree() {
+    const NUM_INSTANCES: usize = 5;
+    let (mut sim, instances) = simulate_paxos(NUM_INSTANCES);
+    sim.set_max_message_latency(Duration::from_millis(10));
+
+    for (i, instance) in instances.iter().enumerate() {
+        let instance = instance.clone();
+        sim.client(format!("client-{i}"), async move {
+            let value = instance.propose(i as u32).await.unwrap();
+            // All instances must agree on a value that was proposed.
+            assert!((value as usize) < NUM_INSTANCES);
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            assert_eq!(instance.decided(), Some(value));
+            Ok(())
+        });
+    }
+    sim.run().unwrap();
+
+    let decided: Vec<Option<u32>> = instances.iter().map(|i| i.decided()).collect();
+    assert!(decided.iter().all(|d| d.is_some() && *d == decided[0]));
+}
+
+#[test]
+fn propose_returns_even_if_almost_half_of_neighbors_are_offline() {
+    let (mut sim, instances) = simulate_paxos(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-1");
+        assert_eq!(instances[0].propose(123).await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn propose_raises_error_if_more_than_half_of_neighbors_are_offline() {
+    let (mut sim, instances) = simulate_paxos(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-1");
+        turmoil::partition("client", "server-2");
+        let result = instances[0].propose(123).await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("A majority of neighbors are offline"));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use crate::consensus::common::simulate_logs;
+
+#[test]
+fn read_returns_values_appended_to_other_instances() {
+    let (mut sim, logs) = simulate_logs(3);
+    sim.client("client", async move {
+        logs[0].append(1).await.unwrap();
+        logs[1].append(2).await.unwrap();
+        assert_eq!(logs[2].read().await.unwrap(), vec![1, 2]);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn append_returns_position_of_value() {
+    let (mut sim, logs) = simulate_logs(3);
+    sim.client("client", async move {
+        assert_eq!(logs[0].append(1).await.unwrap(), 0);
+        assert_eq!(logs[1].append(2).await.unwrap(), 1);
+        assert_eq!(logs[2].append(3).await.unwrap(), 2);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn concurrent_appends_are_ordered_identically_on_all_instances() {
+    const NUM_INSTANCES: usize = 3;
+    const NUM_APPENDS: u32 = 5;
+    let (mut sim, logs) = simulate_logs(NUM_INSTANCES);
+    sim.set_max_message_latency(Duration::from_millis(10));
+
+    for (i, log) in logs.iter().enumerate() {
+        let log = log.clone();
+        sim.client(format!("client-{i}"), async move {
+            for j in 0..NUM_APPENDS {
+                log.append(i as u32 * NUM_APPENDS + j).await.unwrap();
+            }
+            Ok(())
+        });
+    }
+    sim.run().unwrap();
+
+    let logs_clone = logs.clone();
+    sim.client("reader", async move {
+        let first = logs_clone[0].read().await.unwrap();
+        assert_eq!(first.len(), NUM_INSTANCES * NUM_APPENDS as usize);
+        for log in logs_clone.iter().skip(1) {
+            assert_eq!(log.read().await.unwrap(), first);
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn appends_from_one_instance_preserve_their_order() {
+    let (mut sim, logs) = simulate_logs(3);
+    sim.client("client", async move {
+        for value in 0..5 {
+            logs[0].append(value).await.unwrap();
+        }
+        assert_eq!(logs[1].read().await.unwrap(), vec![0, 1, 2, 3, 4]);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
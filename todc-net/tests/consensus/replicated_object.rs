@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use turmoil::Sim;
+
+use todc_net::consensus::ReplicatedObject;
+use todc_net::testing::SimulatedCluster;
+use todc_utils::Specification;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+enum AccountOperation {
+    Deposit(u32),
+    Withdraw(u32),
+}
+
+use AccountOperation::{Deposit, Withdraw};
+
+/// A bank account that cannot be overdrawn.
+struct AccountSpecification;
+
+impl Specification for AccountSpecification {
+    type State = u32;
+    type Operation = AccountOperation;
+
+    fn init() -> Self::State {
+        0
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        match operation {
+            Deposit(amount) => (true, state + amount),
+            Withdraw(amount) => match state.checked_sub(*amount) {
+                Some(balance) => (true, balance),
+                None => (false, *state),
+            },
+        }
+    }
+}
+
+type Account = ReplicatedObject<AccountSpecification>;
+
+/// Simulate n instances of a replicated account.
+fn simulate_accounts<'a>(n: usize) -> (Sim<'a>, Vec<Account>) {
+    SimulatedCluster::with_instances(n, |i, neighbors| Account::new(i as u32, neighbors))
+        .into_parts()
+}
+
+#[test]
+fn read_reflects_operations_applied_on_other_instances() {
+    let (mut sim, accounts) = simulate_accounts(3);
+    sim.client("client", async move {
+        accounts[0].apply(Deposit(100)).await.unwrap();
+        accounts[1].apply(Withdraw(30)).await.unwrap();
+        assert_eq!(accounts[2].read().await.unwrap(), 70);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn operation_is_applied_to_state_of_other_instances() {
+    let (mut sim, accounts) = simulate_accounts(3);
+    sim.client("client", async move {
+        accounts[0].apply(Deposit(100)).await.unwrap();
+        assert_eq!(
+            accounts[1].apply(Withdraw(150)).await.unwrap(),
+            (false, 100)
+        );
+        assert_eq!(accounts[2].apply(Withdraw(100)).await.unwrap(), (true, 0));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn concurrent_operations_are_applied_identically_on_all_instances() {
+    const NUM_INSTANCES: usize = 3;
+    const NUM_OPERATIONS: u32 = 5;
+    let (mut sim, accounts) = simulate_accounts(NUM_INSTANCES);
+
+    for (i, account) in accounts.iter().enumerate() {
+        let account = account.clone();
+        sim.client(format!("client-{i}"), async move {
+            for _ in 0..NUM_OPERATIONS {
+                account.apply(Deposit(10)).await.unwrap();
+                account.apply(Withdraw(5)).await.unwrap();
+            }
+            Ok(())
+        });
+    }
+    sim.run().unwrap();
+
+    sim.client("reader", async move {
+        let expected = 5 * NUM_INSTANCES as u32 * NUM_OPERATIONS;
+        for account in accounts.iter() {
+            assert_eq!(account.read().await.unwrap(), expected);
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
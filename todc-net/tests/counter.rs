@@ -0,0 +1,100 @@
+#![cfg(feature = "turmoil")]
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use turmoil::Sim;
+
+use todc_net::counter::AtomicCounter;
+use todc_net::register::abd_95::RegisterError;
+use todc_net::testing::SimulatedCluster;
+use todc_utils::linearizability::history::{Action, History, ProcessId};
+use todc_utils::specifications::counter::{CounterOperation, CounterSpecification};
+use todc_utils::WGLChecker;
+
+use CounterOperation::{Increment, Read};
+
+type Actions = Rc<RefCell<Vec<(ProcessId, Action<CounterOperation>)>>>;
+
+/// Simulate n instances of a counter.
+fn simulate_servers<'a>(n: usize) -> (Sim<'a>, Vec<AtomicCounter>) {
+    SimulatedCluster::with_instances(n, |i, neighbors| AtomicCounter::new(i as u32, neighbors))
+        .into_parts()
+}
+
+#[test]
+fn reads_increments_made_by_every_instance() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        for replica in &replicas {
+            replica.increment().await.unwrap();
+            replica.increment().await.unwrap();
+        }
+        for replica in &replicas {
+            assert_eq!(replica.read().await.unwrap(), 6);
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn concurrent_increments_are_linearizable() {
+    let (mut sim, replicas) = simulate_servers(3);
+    let actions: Actions = Rc::new(RefCell::new(Vec::new()));
+    for (process, replica) in replicas.into_iter().enumerate() {
+        let actions = actions.clone();
+        sim.client(format!("client-{process}"), async move {
+            for _ in 0..3 {
+                actions
+                    .borrow_mut()
+                    .push((process, Action::Call(Increment)));
+                replica.increment().await.unwrap();
+                actions
+                    .borrow_mut()
+                    .push((process, Action::Response(Increment)));
+                actions
+                    .borrow_mut()
+                    .push((process, Action::Call(Read(None))));
+                let value = replica.read().await.unwrap();
+                actions
+                    .borrow_mut()
+                    .push((process, Action::Response(Read(Some(value)))));
+            }
+            Ok(())
+        });
+    }
+    sim.run().unwrap();
+    let actions = actions.take();
+    assert_eq!(actions.len(), 36);
+    let history = History::from_actions(actions);
+    assert!(WGLChecker::<CounterSpecification>::is_linearizable(history));
+}
+
+#[test]
+fn tolerates_unreachable_minority() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-2");
+        replicas[0].increment().await.unwrap();
+        replicas[1].increment().await.unwrap();
+        assert_eq!(replicas[1].read().await.unwrap(), 2);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn raises_error_if_majority_is_unreachable() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-1");
+        turmoil::partition("client", "server-2");
+        let result = replicas[0].increment().await;
+        assert!(matches!(
+            result,
+            Err(RegisterError::QuorumUnavailable { needed: 2, .. })
+        ));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
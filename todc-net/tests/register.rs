@@ -1,4 +1,10 @@
 #![allow(dead_code, unused_imports)]
 mod register {
     mod abd_95;
+    #[cfg(feature = "turmoil")]
+    mod array;
+    #[cfg(feature = "turmoil")]
+    mod mr_98;
+    #[cfg(feature = "turmoil")]
+    mod sharded;
 }
@@ -1,10 +1,40 @@
 #[cfg(feature = "turmoil")]
+mod client;
+#[cfg(all(feature = "turmoil", feature = "cbor"))]
+mod codec;
+#[cfg(feature = "turmoil")]
 mod common;
 #[cfg(feature = "turmoil")]
+mod consistency;
+#[cfg(feature = "turmoil")]
+mod epoch;
+#[cfg(feature = "turmoil")]
+mod gossip;
+#[cfg(all(feature = "turmoil", feature = "grpc"))]
+mod grpc;
+#[cfg(feature = "turmoil")]
+mod http2;
+#[cfg(feature = "turmoil")]
+mod lease;
+#[cfg(feature = "turmoil")]
+mod limit;
+#[cfg(feature = "turmoil")]
 mod linearizability;
 #[cfg(feature = "turmoil")]
 mod local;
 #[cfg(feature = "turmoil")]
 mod read;
 #[cfg(feature = "turmoil")]
+mod reconfigure;
+#[cfg(feature = "turmoil")]
+mod roles;
+#[cfg(feature = "turmoil")]
+mod status;
+#[cfg(feature = "turmoil")]
+mod streaming;
+#[cfg(feature = "turmoil")]
+mod version;
+#[cfg(feature = "turmoil")]
+mod voting;
+#[cfg(feature = "turmoil")]
 mod write;
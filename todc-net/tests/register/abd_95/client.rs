@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use bytes::Buf;
+use http_body_util::BodyExt;
+use hyper::Uri;
+use serde_json::{json, Value as JSON};
+
+use todc_net::client::{QuorumClient, RegisterClient, RetryPolicy};
+use todc_net::quorum::{QuorumPolicy, WeightedVoting};
+use todc_net::register::AtomicRegister;
+use todc_net::testing::{url, SimulatedCluster};
+
+use crate::register::abd_95::common::{get, simulate_servers, simulate_servers_with_weights};
+
+/// Returns a client for the register instance served by the given server.
+fn client<T: Clone + serde::de::DeserializeOwned + serde::Serialize>(
+    server: usize,
+) -> RegisterClient<T> {
+    let url: Uri = format!("http://server-{server}:9999").parse().unwrap();
+    RegisterClient::new(url)
+}
+
+/// Returns a client that contacts each of the given number of servers.
+fn quorum_client(servers: usize) -> QuorumClient<u32> {
+    let urls = (0..servers)
+        .map(|i| format!("http://server-{i}:9999").parse().unwrap())
+        .collect();
+    QuorumClient::new(urls)
+}
+
+#[test]
+fn reads_value_written_through_another_instance() {
+    let (mut sim, _) = simulate_servers(3);
+    sim.client("client", async move {
+        client(0).write(123).await.unwrap();
+        let value: u32 = client(1).read().await.unwrap();
+        assert_eq!(value, 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn rejects_values_of_the_wrong_type() {
+    let (mut sim, _) = simulate_servers(3);
+    sim.client("client", async move {
+        let client: RegisterClient<String> = client(0);
+        let error = client.write(String::from("foo")).await.unwrap_err();
+        assert!(error.to_string().starts_with("400"));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn times_out_if_instance_cannot_reach_majority() {
+    let (mut sim, _) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::hold("server-0", "server-1");
+        turmoil::hold("server-0", "server-2");
+        let client: RegisterClient<u32> = client(0).with_timeout(Duration::from_secs(1));
+        let error = client.write(123).await.unwrap_err();
+        assert!(error.to_string().contains("timed out"));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn retries_until_instance_can_reach_majority() {
+    let (mut sim, _) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::hold("server-0", "server-1");
+        turmoil::hold("server-0", "server-2");
+        tokio::spawn(async {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            turmoil::release("server-0", "server-1");
+        });
+        let client: RegisterClient<u32> = client(0)
+            .with_timeout(Duration::from_secs(1))
+            .with_retry_policy(RetryPolicy::new(5, Duration::from_millis(100)));
+        client.write(123).await.unwrap();
+        assert_eq!(client.read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn retried_write_is_applied_once() {
+    let (mut sim, _) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::hold("server-0", "server-1");
+        turmoil::hold("server-0", "server-2");
+        tokio::spawn(async {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            turmoil::release("server-0", "server-1");
+        });
+        let client: RegisterClient<u32> = client(0)
+            .with_timeout(Duration::from_secs(1))
+            .with_retry_policy(RetryPolicy::new(5, Duration::from_millis(100)));
+        client.write(123).await.unwrap();
+
+        let url = Uri::from_static("http://server-0:9999/register/local");
+        let response = get(url).await.unwrap();
+        let body = response.collect().await?.aggregate();
+        let body: JSON = serde_json::from_reader(body.reader())?;
+        assert_eq!(body, json!({"value": 123, "label": 1}));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn quorum_client_reads_value_written_through_instance() {
+    let (mut sim, _) = simulate_servers(3);
+    sim.client("client", async move {
+        client(0).write(123).await.unwrap();
+        assert_eq!(quorum_client(3).read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn instance_reads_value_written_by_quorum_client() {
+    let (mut sim, _) = simulate_servers(3);
+    sim.client("client", async move {
+        quorum_client(3).write(123).await.unwrap();
+        let value: u32 = client(2).read().await.unwrap();
+        assert_eq!(value, 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn quorum_client_tolerates_unreachable_minority() {
+    let (mut sim, _) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-0");
+        let client = quorum_client(3);
+        client.write(123).await.unwrap();
+        assert_eq!(client.read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn quorum_client_fails_if_majority_is_unreachable() {
+    let (mut sim, _) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-0");
+        turmoil::partition("client", "server-1");
+        let error = quorum_client(3).write(123).await.unwrap_err();
+        assert!(error.to_string().contains("quorum"));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn quorum_client_counts_votes_of_each_instance() {
+    let (mut sim, _) = simulate_servers_with_weights(&[3, 1, 1]);
+    sim.client("client", async move {
+        let voting = (0..3).fold(WeightedVoting::new(0), |voting, i| {
+            voting.with_weight(url(i), if i == 0 { 3 } else { 1 })
+        });
+        let client = quorum_client(3)
+            .with_quorum_policy(QuorumPolicy::Weighted(voting))
+            .unwrap();
+        // The first instance holds a majority of the votes on its own.
+        turmoil::partition("client", "server-1");
+        turmoil::partition("client", "server-2");
+        client.write(123).await.unwrap();
+        assert_eq!(client.read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn quorum_client_is_rejected_by_instances_in_later_epoch() {
+    let (mut sim, _) = SimulatedCluster::with_instances(3, |_, neighbors| {
+        AtomicRegister::<u32>::new(neighbors).with_epoch(2)
+    })
+    .into_parts();
+    sim.client("client", async move {
+        assert!(quorum_client(3).with_epoch(1).write(123).await.is_err());
+        let client = quorum_client(3).with_epoch(2);
+        client.write(123).await.unwrap();
+        assert_eq!(client.read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[cfg(feature = "history")]
+#[test]
+fn records_operations_in_history() {
+    use todc_net::register::history::HistorySink;
+    use todc_utils::specifications::register::RegisterSpecification;
+    use todc_utils::WGLChecker;
+
+    let (mut sim, _) = simulate_servers(3);
+    let sink = HistorySink::new();
+    let recorded = sink.clone();
+    sim.client("client", async move {
+        let writer: RegisterClient<u32> = client(0).with_history(sink.clone());
+        let reader: RegisterClient<u32> = client(1).with_history(sink);
+        writer.write(123).await.unwrap();
+        assert_eq!(reader.read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+    let history = recorded.history();
+    assert_eq!(history.len(), 4);
+    assert!(WGLChecker::<RegisterSpecification<u32>>::is_linearizable(
+        history
+    ));
+}
@@ -0,0 +1,50 @@
+use bytes::Buf;
+use http_body_util::BodyExt;
+use hyper::header::CONTENT_TYPE;
+use hyper::Uri;
+use serde_json::{json, Value as JSON};
+use turmoil::Sim;
+
+use todc_net::codec::Cbor;
+use todc_net::register::abd_95::AtomicRegister;
+use todc_net::testing::SimulatedCluster;
+use todc_net::transport::HttpTransport;
+
+use crate::register::abd_95::common::get;
+
+type CborRegister = AtomicRegister<u32, HttpTransport, u64, Cbor>;
+
+/// Simulate n replicas of a register that exchange values as CBOR.
+fn simulate_cbor_servers<'a>(n: usize) -> (Sim<'a>, Vec<CborRegister>) {
+    SimulatedCluster::with_instances(n, |_, neighbors| {
+        CborRegister::with_transport(neighbors, HttpTransport::default())
+    })
+    .into_parts()
+}
+
+#[test]
+fn read_returns_value_from_write_to_other_replica() {
+    let (mut sim, replicas) = simulate_cbor_servers(3);
+    sim.client("client", async move {
+        replicas[1].write(123).await.unwrap();
+        assert_eq!(replicas[0].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn responds_with_json_to_requests_without_media_type() {
+    let (mut sim, replicas) = simulate_cbor_servers(3);
+    sim.client("client", async move {
+        replicas[0].write(123).await.unwrap();
+        let url = Uri::from_static("http://server-0:9999/register/local");
+        let response = get(url).await.unwrap();
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        let body = response.collect().await?.aggregate();
+        let body: JSON = serde_json::from_reader(body.reader())?;
+        assert_eq!(body, json!({"value": 123, "label": 1}));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -1,144 +1,155 @@
-use std::net::{IpAddr, Ipv4Addr};
 use std::time::Duration;
 
-use bytes::Bytes;
-use http_body_util::{combinators::BoxBody, BodyExt, Empty, Full};
-use hyper::body::Incoming;
 use hyper::http::StatusCode;
-use hyper::server::conn::http1;
-use hyper::{Request, Response, Uri};
+use hyper::Uri;
 use rand::rngs::StdRng;
 use rand::{thread_rng, Rng, SeedableRng};
-use serde_json::Value as JSON;
-use turmoil::net::{TcpListener, TcpStream};
 use turmoil::{Builder, Sim};
 
-use todc_net::register::abd_95::AtomicRegister;
-use todc_net::TokioIo;
-
-pub const SERVER_PREFIX: &str = "server";
-pub const PORT: u32 = 9999;
-
-type FetchResult<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+use todc_net::quorum::{QuorumPolicy, WeightedVoting};
+use todc_net::register::abd_95::{AtomicRegister, LeaseConfig, Role};
+use todc_net::testing::{self, host, url, SimulatedCluster, PORT};
+pub use todc_net::testing::{get, post};
+#[cfg(feature = "grpc")]
+use todc_net::transport::grpc::{GrpcServer, GrpcTransport};
+use todc_net::transport::HttpTransport;
+use todc_net::{TokioExecutor, TokioIo};
 
 /// Simulate n replicates of a register.
 pub fn simulate_servers<'a>(n: usize) -> (Sim<'a>, Vec<AtomicRegister<u32>>) {
-    let sim = Builder::new().build();
-    simulate_registers(n, sim)
+    SimulatedCluster::new(n).into_parts()
 }
 
-/// Simulate n replicas of a register with a fixed RNG seed.
-pub fn simulate_servers_with_seed<'a>(n: usize) -> (Sim<'a>, Vec<AtomicRegister<u32>>, u64) {
-    let seed: u64 = thread_rng().gen();
-    let rng = StdRng::seed_from_u64(seed);
-    let sim = Builder::new().build_with_rng(Box::new(rng));
-    let (sim, registers) = simulate_registers(n, sim);
-    (sim, registers, seed)
+/// Simulate replicas of a register, where the ith replica has the ith role.
+pub fn simulate_servers_with_roles<'a>(roles: &[Role]) -> (Sim<'a>, Vec<AtomicRegister<u32>>) {
+    SimulatedCluster::with_instances(roles.len(), |i, neighbors| {
+        AtomicRegister::new(neighbors).with_role(roles[i])
+    })
+    .into_parts()
 }
 
-/// Submits a GET request to the URL.
-pub async fn get(url: Uri) -> FetchResult<Response<Incoming>> {
-    let host = url.host().expect("uri has no host");
-    let port = url.port_u16().unwrap_or(80);
-    let addr = format!("{host}:{port}");
-    let io = TokioIo::new(TcpStream::connect(addr).await?);
-
-    let (mut sender, conn) = hyper::client::conn::http1::handshake(io).await?;
-    tokio::task::spawn(async move {
-        if let Err(err) = conn.await {
-            println!("Connection failed: {err}");
-        }
-    });
-
-    let authority = url.authority().unwrap().clone();
-
-    let req = Request::builder()
-        .uri(url)
-        .header(hyper::header::HOST, authority.as_str())
-        .body(empty())?;
-
-    let res = sender.send_request(req).await?;
-    Ok(res)
+/// Simulate replicas of a register, where each replica holds the given
+/// number of votes.
+pub fn simulate_servers_with_weights<'a>(weights: &[usize]) -> (Sim<'a>, Vec<AtomicRegister<u32>>) {
+    SimulatedCluster::with_instances(weights.len(), |i, neighbors| {
+        let voting = weights
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .fold(WeightedVoting::new(weights[i]), |voting, (j, &weight)| {
+                voting.with_weight(url(j), weight)
+            });
+        AtomicRegister::new(neighbors)
+            .with_quorum_policy(QuorumPolicy::Weighted(voting))
+            .unwrap()
+    })
+    .into_parts()
 }
 
-/// Submits a POST request, with a JSON body, to the URL.
-pub async fn post(url: Uri, body: JSON) -> FetchResult<Response<Incoming>> {
-    let host = url.host().expect("uri has no host");
-    let port = url.port_u16().unwrap_or(80);
-    let addr = format!("{host}:{port}");
-    let io = TokioIo::new(TcpStream::connect(addr).await?);
-
-    let (mut sender, conn) = hyper::client::conn::http1::handshake(io).await?;
-    tokio::task::spawn(async move {
-        if let Err(err) = conn.await {
-            println!("Connection failed: {err}");
+/// Simulate n replicas of a register, where the first replica holds leases
+/// of the given duration.
+pub fn simulate_servers_with_lease<'a>(
+    n: usize,
+    duration: Duration,
+) -> (Sim<'a>, Vec<AtomicRegister<u32>>) {
+    SimulatedCluster::with_instances(n, |i, neighbors| {
+        let register = AtomicRegister::new(neighbors);
+        match i {
+            0 => register.with_lease(LeaseConfig::new(url(0), duration)),
+            _ => register,
         }
-    });
-
-    let authority = url.authority().unwrap().clone();
-
-    let req = Request::builder()
-        .uri(url)
-        .header(hyper::header::HOST, authority.as_str())
-        .method("POST")
-        .body(full(body))?;
+    })
+    .into_parts()
+}
 
-    let res = sender.send_request(req).await?;
-    Ok(res)
+/// Simulate n replicas of a register, seeded by the seed of the test.
+pub fn simulate_servers_with_seed<'a>(n: usize) -> (Sim<'a>, Vec<AtomicRegister<u32>>, u64) {
+    let seed = testing::seed();
+    let (sim, registers) =
+        SimulatedCluster::from_seed(seed, n, |_, neighbors| AtomicRegister::new(neighbors))
+            .into_parts();
+    (sim, registers, seed)
 }
 
-/// Adds n register instances to the simulation.
-fn simulate_registers(n: usize, mut sim: Sim) -> (Sim, Vec<AtomicRegister<u32>>) {
+/// Simulate n replicas of a register that communicate over HTTP/2.
+pub fn simulate_http2_servers<'a>(n: usize) -> (Sim<'a>, Vec<AtomicRegister<u32>>) {
+    let mut sim = Builder::new().build();
     let mut registers = Vec::new();
 
-    let neighbors: Vec<Uri> = (0..n)
-        .map(|i| {
-            format!("http://{SERVER_PREFIX}-{i}:{PORT}")
-                .parse()
-                .unwrap()
-        })
-        .collect();
-
+    let urls: Vec<Uri> = (0..n).map(url).collect();
     for i in 0..n {
-        let mut neighbors = neighbors.clone();
+        let mut neighbors = urls.clone();
         neighbors.remove(i);
-        let register: AtomicRegister<u32> = AtomicRegister::new(neighbors);
-        let name = format!("{SERVER_PREFIX}-{i}");
+        let transport = HttpTransport::default().with_http2(true);
+        let register = AtomicRegister::with_transport(neighbors, transport);
         let register_clone = register.clone();
-        sim.host(name, move || serve(register_clone.clone()));
+        sim.host(host(i), move || serve_http2(register_clone.clone()));
         registers.push(register);
     }
     (sim, registers)
 }
 
-/// Serve a register as a service.
-async fn serve(register: AtomicRegister<u32>) -> Result<(), Box<dyn std::error::Error + 'static>> {
-    let addr = (IpAddr::from(Ipv4Addr::UNSPECIFIED), 9999);
+/// Serve a register over HTTP/2.
+async fn serve_http2(
+    register: AtomicRegister<u32>,
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    use hyper::server::conn::http2;
+    use std::net::{IpAddr, Ipv4Addr};
+    use turmoil::net::TcpListener;
+
+    let addr = (IpAddr::from(Ipv4Addr::UNSPECIFIED), PORT);
     let listener = TcpListener::bind(addr).await?;
     loop {
         let (stream, _) = listener.accept().await?;
-        let io = TokioIo::new(stream);
         let register = register.clone();
         tokio::task::spawn(async move {
-            if let Err(err) = http1::Builder::new().serve_connection(io, register).await {
+            if let Err(err) = http2::Builder::new(TokioExecutor)
+                .serve_connection(TokioIo::new(stream), register)
+                .await
+            {
                 println!("Error Serving Connection: {:?}", err);
             }
         });
     }
 }
 
-/// Returns an empty response body.
-fn empty() -> BoxBody<Bytes, hyper::Error> {
-    Empty::<Bytes>::new()
-        .map_err(|never| match never {})
-        .boxed()
+/// Simulate n replicas of a register that communicate over gRPC.
+#[cfg(feature = "grpc")]
+pub fn simulate_grpc_servers<'a>(n: usize) -> (Sim<'a>, Vec<AtomicRegister<u32, GrpcTransport>>) {
+    let mut sim = Builder::new().build();
+    let mut registers = Vec::new();
+
+    let urls: Vec<Uri> = (0..n).map(url).collect();
+    for i in 0..n {
+        let mut neighbors = urls.clone();
+        neighbors.remove(i);
+        let register = AtomicRegister::with_transport(neighbors, GrpcTransport::default());
+        let server = GrpcServer::new(register.clone());
+        sim.host(host(i), move || serve_grpc(server.clone()));
+        registers.push(register);
+    }
+    (sim, registers)
 }
 
-/// Returns a JSON response body.
-fn full(value: JSON) -> BoxBody<Bytes, hyper::Error> {
-    Full::<Bytes>::new(Bytes::from(value.to_string()))
-        .map_err(|never| match never {})
-        .boxed()
+/// Serve a register as a gRPC service.
+#[cfg(feature = "grpc")]
+async fn serve_grpc(
+    server: GrpcServer<AtomicRegister<u32, GrpcTransport>>,
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    use std::net::{IpAddr, Ipv4Addr};
+    use turmoil::net::TcpListener;
+
+    let addr = (IpAddr::from(Ipv4Addr::UNSPECIFIED), PORT);
+    let listener = TcpListener::bind(addr).await?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let server = server.clone();
+        tokio::task::spawn(async move {
+            if let Err(err) = server.serve_connection(TokioIo::new(stream)).await {
+                println!("Error Serving Connection: {:?}", err);
+            }
+        });
+    }
 }
 
 #[test]
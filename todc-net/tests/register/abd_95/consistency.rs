@@ -0,0 +1,90 @@
+use hyper::Uri;
+use serde_json::json;
+
+use todc_net::register::abd_95::ReadConsistency::{Linearizable, Local, Sequential};
+
+use crate::register::abd_95::common::{post, simulate_servers};
+
+/// Sets the local value of the first replica, as if a write that is still in
+/// progress had only reached it.
+async fn begin_write_at_first_replica(value: u32) {
+    let url = Uri::from_static("http://server-0:9999/register/local");
+    let response = post(url, json!({"value": value, "label": 1}))
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+}
+
+#[test]
+fn local_read_returns_value_missed_by_partitioned_replica() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::hold("client", "server-2");
+        replicas[0].write(123).await.unwrap();
+        let value = replicas[2].read_with_consistency(Local).await.unwrap();
+        assert_eq!(value, 0);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn local_read_returns_without_reaching_majority() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::hold("client", "server-1");
+        turmoil::hold("client", "server-2");
+        let value = replicas[0].read_with_consistency(Local).await.unwrap();
+        assert_eq!(value, 0);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn sequential_read_returns_value_of_completed_write() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        replicas[0].write(123).await.unwrap();
+        let value = replicas[2].read_with_consistency(Sequential).await.unwrap();
+        assert_eq!(value, 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn sequential_reads_can_observe_new_value_before_old_value() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        begin_write_at_first_replica(123).await;
+        let first = replicas[0].read_with_consistency(Sequential).await.unwrap();
+        // The second read does not hear from the first replica, and no
+        // other replica learned of the value returned by the first read.
+        turmoil::hold("client", "server-0");
+        let second = replicas[1].read_with_consistency(Sequential).await.unwrap();
+        assert_eq!((first, second), (123, 0));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn linearizable_reads_never_observe_new_value_before_old_value() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        begin_write_at_first_replica(123).await;
+        let first = replicas[0]
+            .read_with_consistency(Linearizable)
+            .await
+            .unwrap();
+        turmoil::hold("client", "server-0");
+        let second = replicas[1]
+            .read_with_consistency(Linearizable)
+            .await
+            .unwrap();
+        assert_eq!((first, second), (123, 123));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
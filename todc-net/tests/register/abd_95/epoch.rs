@@ -0,0 +1,55 @@
+use todc_net::register::abd_95::RegisterError;
+
+use crate::register::abd_95::common::simulate_servers;
+
+#[test]
+fn operations_fail_once_majority_has_entered_later_epoch() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        replicas[1].enter_epoch(1);
+        replicas[2].enter_epoch(1);
+        let result = replicas[0].write(123).await;
+        assert!(matches!(
+            result,
+            Err(RegisterError::QuorumUnavailable { .. })
+        ));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn operations_succeed_once_minority_has_entered_later_epoch() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        replicas[2].enter_epoch(1);
+        replicas[0].write(123).await.unwrap();
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn neighbors_enter_epoch_of_instance_that_contacts_them() {
+    let (mut sim, replicas) = simulate_servers(2);
+    sim.client("client", async move {
+        replicas[1].enter_epoch(2);
+        replicas[1].write(123).await.unwrap();
+        assert_eq!(replicas[0].epoch(), 2);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn fenced_instance_recovers_once_contacted_from_later_epoch() {
+    let (mut sim, replicas) = simulate_servers(2);
+    sim.client("client", async move {
+        replicas[1].enter_epoch(1);
+        assert!(replicas[0].read().await.is_err());
+        replicas[1].write(123).await.unwrap();
+        assert_eq!(replicas[0].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use bytes::Buf;
+use http_body_util::BodyExt;
+use hyper::Uri;
+use serde_json::{json, Value as JSON};
+
+use crate::register::abd_95::common::{get, simulate_servers};
+
+#[test]
+fn stale_instance_converges_without_operations() {
+    let (mut sim, registers) = simulate_servers(3);
+    sim.client("client", async move {
+        // Server-2 misses the write.
+        turmoil::partition("client", "server-2");
+        registers[0].write(123).await.unwrap();
+        turmoil::repair("client", "server-2");
+
+        let gossip = registers[2].spawn_gossip(Duration::from_millis(100));
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        gossip.abort();
+
+        let url = Uri::from_static("http://server-2:9999/register/local");
+        let body = get(url).await.unwrap().collect().await?.aggregate();
+        let body: JSON = serde_json::from_reader(body.reader())?;
+        assert_eq!(body, json!({"value": 123, "label": 1}));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn gossip_fails_if_neighbor_is_unreachable() {
+    let (mut sim, registers) = simulate_servers(2);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-1");
+        assert!(registers[0].gossip().await.is_err());
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -0,0 +1,52 @@
+use crate::register::abd_95::common::simulate_grpc_servers;
+
+#[test]
+fn read_returns_initial_value() {
+    let (mut sim, replicas) = simulate_grpc_servers(3);
+    sim.client("client", async move {
+        let value = replicas[0].read().await.unwrap();
+        assert_eq!(value, 0);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn read_returns_value_from_write_to_other_replica() {
+    const VALUE: u32 = 123;
+    let (mut sim, replicas) = simulate_grpc_servers(3);
+    sim.client("client", async move {
+        replicas[1].write(VALUE).await.unwrap();
+        let value = replicas[0].read().await.unwrap();
+        assert_eq!(value, VALUE);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn write_returns_even_if_almost_half_of_neighbors_are_offline() {
+    let (mut sim, replicas) = simulate_grpc_servers(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-1");
+        replicas[0].write(123).await.unwrap();
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn write_raises_error_if_more_than_half_of_neighbors_are_offline() {
+    let (mut sim, replicas) = simulate_grpc_servers(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-1");
+        turmoil::partition("client", "server-2");
+        let result = replicas[0].write(123).await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("More than 1 of the instances are offline"));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
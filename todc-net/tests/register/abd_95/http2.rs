@@ -0,0 +1,54 @@
+use crate::register::abd_95::common::simulate_http2_servers;
+
+#[test]
+fn read_returns_initial_value() {
+    let (mut sim, replicas) = simulate_http2_servers(3);
+    sim.client("client", async move {
+        let value = replicas[0].read().await.unwrap();
+        assert_eq!(value, 0);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn read_returns_value_from_write_to_other_replica() {
+    const VALUE: u32 = 123;
+    let (mut sim, replicas) = simulate_http2_servers(3);
+    sim.client("client", async move {
+        replicas[1].write(VALUE).await.unwrap();
+        let value = replicas[0].read().await.unwrap();
+        assert_eq!(value, VALUE);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn concurrent_operations_share_connections() {
+    let (mut sim, replicas) = simulate_http2_servers(3);
+    sim.client("client", async move {
+        let writes: Vec<_> = (0..10)
+            .map(|value| {
+                let replica = replicas[0].clone();
+                tokio::spawn(async move { replica.write(value).await })
+            })
+            .collect();
+        for write in writes {
+            write.await.unwrap().unwrap();
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn write_returns_even_if_almost_half_of_neighbors_are_offline() {
+    let (mut sim, replicas) = simulate_http2_servers(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-1");
+        replicas[0].write(123).await.unwrap();
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use crate::register::abd_95::common::simulate_servers_with_lease;
+
+const LEASE: Duration = Duration::from_secs(2);
+
+#[test]
+fn leaseholder_serves_reads_without_a_majority() {
+    let (mut sim, registers) = simulate_servers_with_lease(3, LEASE);
+    sim.client("client", async move {
+        registers[0].write(123).await.unwrap();
+        // The first read acquires a lease.
+        assert_eq!(registers[0].read().await.unwrap(), 123);
+        assert!(registers[0].lease_expiry().is_some());
+
+        turmoil::partition("client", "server-1");
+        turmoil::partition("client", "server-2");
+        assert_eq!(registers[0].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn leaseholder_reads_value_written_by_another_instance() {
+    let (mut sim, registers) = simulate_servers_with_lease(3, LEASE);
+    sim.client("client", async move {
+        // Only server-2 grants the lease.
+        turmoil::partition("client", "server-1");
+        registers[0].read().await.unwrap();
+        turmoil::repair("client", "server-1");
+
+        // The leaseholder only learns of the write because server-2 forwards
+        // it before acknowledging it.
+        turmoil::hold("client", "server-0");
+        registers[1].write(123).await.unwrap();
+        assert_eq!(registers[0].read().await.unwrap(), 123);
+        turmoil::release("client", "server-0");
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn write_completes_once_lease_of_unreachable_leaseholder_expires() {
+    let (mut sim, registers) = simulate_servers_with_lease(3, LEASE);
+    sim.client("client", async move {
+        registers[0].read().await.unwrap();
+
+        // Whichever instances granted the lease cannot forward the write to
+        // the leaseholder, and so only acknowledge it once the lease expires.
+        turmoil::partition("client", "server-0");
+        turmoil::partition("server-2", "server-0");
+        registers[1].write(123).await.unwrap();
+        assert!(registers[0].lease_expiry().is_none());
+
+        turmoil::repair("client", "server-0");
+        turmoil::repair("server-2", "server-0");
+        assert_eq!(registers[0].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -0,0 +1,75 @@
+use hyper::http::StatusCode;
+use hyper::Uri;
+use turmoil::Sim;
+
+use todc_net::limit::ConcurrencyLimit;
+use todc_net::register::abd_95::{AtomicRegister, RegisterError, Route};
+use todc_net::testing::SimulatedCluster;
+
+use crate::register::abd_95::common::get;
+
+/// Simulate n replicas of a register, where the given route of the last
+/// replica rejects every request.
+fn simulate_servers_with_saturated_route<'a>(
+    n: usize,
+    route: Route,
+) -> (Sim<'a>, Vec<AtomicRegister<u32>>) {
+    SimulatedCluster::with_instances(n, |i, neighbors| {
+        let register = AtomicRegister::new(neighbors);
+        if i == n - 1 {
+            register.with_concurrency_limit(route, ConcurrencyLimit::new(0))
+        } else {
+            register
+        }
+    })
+    .into_parts()
+}
+
+#[test]
+fn saturated_route_responds_service_unavailable() {
+    let (mut sim, _) = simulate_servers_with_saturated_route(3, Route::Register);
+    sim.client("client", async move {
+        let url = Uri::from_static("http://server-2:9999/register");
+        let response = get(url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn other_routes_are_not_limited() {
+    let (mut sim, _) = simulate_servers_with_saturated_route(3, Route::Register);
+    sim.client("client", async move {
+        let url = Uri::from_static("http://server-2:9999/register/local");
+        let response = get(url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn operations_succeed_with_minority_saturated() {
+    let (mut sim, registers) = simulate_servers_with_saturated_route(3, Route::Local);
+    sim.client("client", async move {
+        registers[0].write(123).await.unwrap();
+        assert_eq!(registers[1].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn operations_fail_with_majority_saturated() {
+    let (mut sim, registers) = simulate_servers_with_saturated_route(2, Route::Local);
+    sim.client("client", async move {
+        let result = registers[0].write(123).await;
+        assert!(matches!(
+            result,
+            Err(RegisterError::QuorumUnavailable { .. })
+        ));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
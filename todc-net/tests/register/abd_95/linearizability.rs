@@ -9,15 +9,24 @@ use rand::distributions::Standard;
 use rand::prelude::Distribution;
 use rand::rngs::StdRng;
 use rand::seq::IteratorRandom;
-use rand::{thread_rng, Rng, SeedableRng};
+use rand::{Rng, SeedableRng};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use turmoil::{Builder, Sim};
 
 use todc_net::register::abd_95::AtomicRegister;
+#[cfg(feature = "history")]
+use todc_net::register::history::HistorySink;
+use todc_net::testing::{self, host, SimulatedCluster};
+#[cfg(feature = "history")]
+use todc_net::testing::{Nemesis, Workload};
+#[cfg(feature = "history")]
+use todc_utils::linearizability::windowed::WindowedRecorder;
 use todc_utils::specifications::register::{RegisterOperation, RegisterSpecification};
 use todc_utils::{Action, History, WGLChecker};
 
-use crate::register::abd_95::common::{simulate_servers_with_seed, SERVER_PREFIX};
+#[cfg(feature = "history")]
+use crate::register::abd_95::common::simulate_servers_with_seed;
 
 use RegisterOperation::{Read, Write};
 
@@ -53,16 +62,40 @@ fn assert_linearizable<T>(mut actions: Vec<RecordedAction<T>>)
 where
     T: Clone + Debug + Default + Eq + Hash,
 {
-    actions.sort_by(|a, b| a.happened_at.cmp(&b.happened_at));
+    actions.sort_by_key(|a| a.happened_at);
     let history = History::from_actions(
         actions
             .iter()
             .map(|ta| (ta.process, ta.action.clone()))
             .collect(),
     );
-    assert!(WGLChecker::<RegisterSpecification<T>>::is_linearizable(
-        history
-    ));
+    assert_history_linearizable(history);
+}
+
+/// Asserts that the operations recorded by the sink form a linearizable
+/// history of register operations.
+///
+/// # Panics
+///
+/// Panics if the history of register operations is not linearizable.
+#[cfg(feature = "history")]
+fn assert_recorded_linearizable<T>(sink: HistorySink<T>)
+where
+    T: Clone + Debug + Default + Eq + Hash,
+{
+    assert_history_linearizable(sink.history());
+}
+
+/// Asserts that a history of register operations is linearizable.
+fn assert_history_linearizable<T>(history: History<RegisterOperation<T>>)
+where
+    T: Clone + Debug + Default + Eq + Hash,
+{
+    assert!(
+        WGLChecker::<RegisterSpecification<T>>::is_linearizable(history.clone()),
+        "History is not linearizable:\n{}",
+        history.render_timeline()
+    );
 }
 
 /// A Register client that records call and response information about the
@@ -135,9 +168,72 @@ where
     }
 }
 
+/// Performs a random read or write on the register, which writes with
+/// probability `p`.
+#[cfg(feature = "history")]
+async fn perform_random_operation<T>(
+    register: &AtomicRegister<T>,
+    rng: &mut StdRng,
+    p: f64,
+) -> EmptyResult
+where
+    T: Clone + Debug + Default + DeserializeOwned + Ord + Send + Serialize + 'static,
+    Standard: Distribution<T>,
+{
+    if rng.gen_bool(p) {
+        let value: T = rng.gen::<T>();
+        register.write(value).await.unwrap();
+    } else {
+        register.read().await.unwrap();
+    }
+    Ok(())
+}
+
+/// Simulates `n` replicas of a register, in a network where the links of a
+/// random minority of them fail with non-zero probability.
+///
+/// Most operations in the tests that use this network are reads, which take
+/// two round trips, and so the simulation is given longer to complete.
+fn simulate_faulty_servers<'a>(
+    n: usize,
+    failure_rate: f64,
+    rng: &mut StdRng,
+) -> (Sim<'a>, Vec<AtomicRegister<u32>>) {
+    let mut builder = Builder::new();
+    builder.simulation_duration(Duration::from_secs(60));
+    let (mut sim, registers) =
+        SimulatedCluster::from_builder(&builder, rng.gen(), n, |_, neighbors| {
+            AtomicRegister::new(neighbors)
+        })
+        .into_parts();
+    let servers: Vec<String> = (0..n).map(host).collect();
+    let minority = ((n as f32 / 2.0).ceil() - 1.0) as usize;
+    let faulty_servers: Vec<String> = servers.clone().into_iter().choose_multiple(rng, minority);
+
+    // Set the failure rate for any connection involving a faulty server.
+    // Clients are only run on the correct servers.
+    for faulty in &faulty_servers {
+        for server in &servers {
+            if faulty != server {
+                sim.set_link_fail_rate(faulty.clone(), server.clone(), failure_rate);
+            }
+        }
+    }
+    let correct = registers
+        .into_iter()
+        .zip(servers)
+        .filter(|(_, server)| !faulty_servers.contains(server))
+        .map(|(register, _)| register)
+        .collect();
+    (sim, correct)
+}
+
 /// Asserts that in a network where a random minority of servers are faulty, a
 /// random sequence of reads and writes by correct clients will result in a
 /// linearizable history.
+///
+/// Each client records the operations it performs, and only the first client
+/// writes, since the register is single-writer, while every client reads.
 #[test]
 fn random_reads_and_writes_with_random_failures() {
     // HACK: Run fewer iterations when calculating code coverage.
@@ -158,51 +254,24 @@ fn random_reads_and_writes_with_random_failures() {
     const WRITE_PROBABILITY: f64 = 1.0 / 2.0;
     const FAILURE_RATE: f64 = 0.8;
 
-    // Simulate a network where a random minority of servers
-    // fail with non-zero probability.
-    let (mut sim, registers, seed) = simulate_servers_with_seed(NUM_SERVERS);
-    let servers: Vec<String> = (0..NUM_SERVERS)
-        .map(|i| format!("{SERVER_PREFIX}-{i}"))
-        .collect();
-
+    let seed = testing::seed();
     let mut rng = StdRng::seed_from_u64(seed);
-    let minority = ((NUM_SERVERS as f32 / 2.0).ceil() - 1.0) as usize;
-
-    let faulty_servers: Vec<String> = servers
-        .clone()
-        .into_iter()
-        .choose_multiple(&mut rng, minority);
-    let correct_servers: Vec<String> = servers
-        .clone()
-        .into_iter()
-        .filter(|s| !faulty_servers.contains(s))
-        .collect();
-
-    // Set the failure rate for any connection involving a faulty server
-    for faulty in faulty_servers {
-        for server in servers.clone() {
-            if faulty == server {
-                continue;
-            };
-            let a = faulty.clone();
-            let b = server.clone();
-            sim.set_link_fail_rate(a, b, FAILURE_RATE);
-        }
-    }
+    let (mut sim, registers) = simulate_faulty_servers(NUM_SERVERS, FAILURE_RATE, &mut rng);
 
     let actions: Arc<Mutex<Vec<TimedAction<RegisterOperation<u32>>>>> =
         Arc::new(Mutex::new(vec![]));
 
     // Simulate clients that submit requests.
-    assert!(NUM_CLIENTS <= correct_servers.len());
+    assert!(NUM_CLIENTS <= registers.len());
     for (i, register) in registers.into_iter().enumerate().take(NUM_CLIENTS) {
         let actions = actions.clone();
         let rng = rng.clone();
+        let p = if i == 0 { WRITE_PROBABILITY } else { 0.0 };
         let client_name = format!("client-{i}");
         sim.client(client_name, async move {
             let mut client = RecordingRegisterClient::<u32>::new(i, register.clone(), rng, actions);
             for _ in 0..NUM_OPERATIONS {
-                client.perform_random_operation(WRITE_PROBABILITY).await?;
+                client.perform_random_operation(p).await?;
             }
             Ok(())
         });
@@ -218,3 +287,127 @@ fn random_reads_and_writes_with_random_failures() {
     let actions = Arc::try_unwrap(actions).unwrap().into_inner().unwrap();
     assert_linearizable(actions);
 }
+
+/// Asserts that in a network where a random minority of servers are faulty,
+/// the history recorded by the instances themselves, rather than by their
+/// clients, is linearizable.
+///
+/// Only the first client writes, while every client reads.
+#[cfg(feature = "history")]
+#[test]
+fn random_reads_and_writes_recorded_by_instances() {
+    #[cfg(coverage)]
+    const NUM_CLIENTS: usize = 3;
+    #[cfg(coverage)]
+    const NUM_OPERATIONS: usize = 10;
+    #[cfg(coverage)]
+    const NUM_SERVERS: usize = 6;
+
+    #[cfg(not(coverage))]
+    const NUM_CLIENTS: usize = 5;
+    #[cfg(not(coverage))]
+    const NUM_OPERATIONS: usize = 50;
+    #[cfg(not(coverage))]
+    const NUM_SERVERS: usize = 10;
+
+    const WRITE_PROBABILITY: f64 = 1.0 / 2.0;
+    const FAILURE_RATE: f64 = 0.8;
+
+    let seed = testing::seed();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (mut sim, registers) = simulate_faulty_servers(NUM_SERVERS, FAILURE_RATE, &mut rng);
+
+    let sink: HistorySink<u32> = HistorySink::new();
+    assert!(NUM_CLIENTS <= registers.len());
+    for (i, register) in registers.into_iter().enumerate().take(NUM_CLIENTS) {
+        let register = register.with_history(sink.clone());
+        let mut rng = StdRng::seed_from_u64(rng.gen());
+        let p = if i == 0 { WRITE_PROBABILITY } else { 0.0 };
+        sim.client(format!("client-{i}"), async move {
+            for _ in 0..NUM_OPERATIONS {
+                perform_random_operation(&register, &mut rng, p).await?;
+            }
+            Ok(())
+        });
+    }
+
+    sim.run().unwrap();
+    println!("This test used the random seed: {seed}");
+
+    assert_recorded_linearizable(sink);
+}
+
+/// Asserts that a random workload performed while a nemesis injects random
+/// faults results in a linearizable history.
+///
+/// The workload performs every write on the first instance, while every
+/// instance reads.
+#[cfg(feature = "history")]
+#[test]
+fn random_workload_with_nemesis() {
+    #[cfg(coverage)]
+    const NUM_OPERATIONS: usize = 20;
+    #[cfg(not(coverage))]
+    const NUM_OPERATIONS: usize = 60;
+    const NUM_SERVERS: usize = 3;
+
+    // The simulation, workload and nemesis are all derived from the seed of
+    // the test, which the cluster logs, so that a failure can be replayed.
+    let seed = testing::seed();
+    let mut builder = Builder::new();
+    builder.simulation_duration(Duration::from_secs(60));
+
+    let sink: HistorySink<u32> = HistorySink::new();
+    let mut cluster =
+        SimulatedCluster::from_builder(&builder, seed, NUM_SERVERS, |_, neighbors| {
+            AtomicRegister::new(neighbors).with_history(sink.clone())
+        });
+    let workload = Workload::new(seed)
+        .with_operations(NUM_OPERATIONS)
+        .with_timeout(Duration::from_secs(1));
+    cluster.add_workload(&workload);
+    let mut nemesis = Nemesis::new(seed).with_interval(Duration::from_millis(500));
+    cluster.run_with_nemesis(&mut nemesis).unwrap();
+
+    assert!(!nemesis.faults().is_empty());
+    assert_recorded_linearizable(sink);
+}
+
+/// Asserts that a windowed sink checks the history of a random sequence of
+/// reads and writes one window at a time, and finds it linearizable.
+///
+/// Only the first client writes, while every client reads.
+#[cfg(feature = "history")]
+#[test]
+fn random_reads_and_writes_checked_in_windows() {
+    const NUM_CLIENTS: usize = 3;
+    const NUM_OPERATIONS: usize = 50;
+    const WRITE_PROBABILITY: f64 = 1.0 / 2.0;
+
+    let (mut sim, registers, seed) = simulate_servers_with_seed(NUM_CLIENTS);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let sink: HistorySink<u32> = HistorySink::windowed(WindowedRecorder::new(10).with_recent(1));
+    for (i, register) in registers.into_iter().enumerate() {
+        let register = register.with_history(sink.clone());
+        let mut rng = StdRng::seed_from_u64(rng.gen());
+        let p = if i == 0 { WRITE_PROBABILITY } else { 0.0 };
+        sim.client(format!("client-{i}"), async move {
+            for _ in 0..NUM_OPERATIONS {
+                perform_random_operation(&register, &mut rng, p).await?;
+                // Pause between operations, so that the history reaches
+                // points at which no operation is pending.
+                let pause = Duration::from_millis(rng.gen_range(0..50));
+                tokio::time::sleep(pause).await;
+            }
+            Ok(())
+        });
+    }
+
+    sim.run().unwrap();
+    println!("This test used the random seed: {seed}");
+
+    assert!(sink.check().is_linearizable());
+    let retained = sink.retained_windows();
+    assert!(retained.last().is_some_and(|window| window.index > 0));
+}
@@ -1,3 +1,5 @@
+use todc_net::register::abd_95::RegisterError;
+
 use crate::register::abd_95::common::simulate_servers;
 
 #[test]
@@ -72,12 +74,80 @@ fn raises_error_if_more_than_half_of_neighbors_are_offline() {
         turmoil::partition("client", "server-1");
         turmoil::partition("client", "server-2");
         let result = replicas[0].read().await;
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("A majority of neighbors are offline"));
+        assert!(matches!(
+            result,
+            Err(RegisterError::QuorumUnavailable {
+                acks: 1,
+                needed: 2,
+                ..
+            })
+        ));
         Ok(())
     });
 
     sim.run().unwrap();
 }
+
+mod read_or_stale {
+    use std::time::Duration;
+
+    use todc_net::register::abd_95::ReadOutcome;
+
+    use crate::register::abd_95::common::simulate_servers;
+
+    #[test]
+    fn returns_fresh_value_if_majority_is_reachable() {
+        let (mut sim, replicas) = simulate_servers(3);
+        sim.client("client", async move {
+            replicas[0].write(123).await.unwrap();
+            let outcome = replicas[0].read_or_stale(Duration::ZERO).await.unwrap();
+            assert_eq!(outcome, ReadOutcome::Fresh(123));
+            Ok(())
+        });
+        sim.run().unwrap();
+    }
+
+    #[test]
+    fn returns_stale_value_if_more_than_half_of_neighbors_are_offline() {
+        let (mut sim, replicas) = simulate_servers(3);
+        sim.client("client", async move {
+            replicas[0].write(123).await.unwrap();
+            turmoil::partition("client", "server-1");
+            turmoil::partition("client", "server-2");
+
+            // A strict read fails...
+            assert!(replicas[0].read().await.is_err());
+
+            // ...but a read that allows stale values does not.
+            let budget = Duration::from_secs(60);
+            match replicas[0].read_or_stale(budget).await.unwrap() {
+                ReadOutcome::Fresh(_) => panic!("Expected read to return a stale value"),
+                ReadOutcome::Stale(stale) => {
+                    assert_eq!(stale.value, 123);
+                    assert_eq!(stale.label, 1);
+                }
+            }
+            Ok(())
+        });
+        sim.run().unwrap();
+    }
+
+    #[test]
+    fn raises_error_if_value_was_confirmed_before_budget() {
+        let (mut sim, replicas) = simulate_servers(3);
+        sim.client("client", async move {
+            replicas[0].write(123).await.unwrap();
+            turmoil::partition("client", "server-1");
+            turmoil::partition("client", "server-2");
+
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            let result = replicas[0].read_or_stale(Duration::from_secs(1)).await;
+            assert!(result
+                .unwrap_err()
+                .to_string()
+                .contains("More than 1 of the instances are offline"));
+            Ok(())
+        });
+        sim.run().unwrap();
+    }
+}
@@ -0,0 +1,138 @@
+use bytes::Buf;
+use http_body_util::BodyExt;
+use hyper::http::StatusCode;
+use hyper::Uri;
+use serde_json::{json, Value as JSON};
+
+use crate::register::abd_95::common::{get, post, simulate_servers};
+
+#[test]
+fn transfers_value_to_new_neighbors() {
+    let (mut sim, replicas) = simulate_servers(4);
+    sim.client("client", async move {
+        // Write a value that server-3 never hears about.
+        turmoil::partition("client", "server-3");
+        replicas[0].write(123).await.unwrap();
+        turmoil::repair("client", "server-3");
+
+        // Replace every neighbor of server-0 with server-3.
+        let neighbor: Uri = "http://server-3:9999".parse().unwrap();
+        replicas[0]
+            .reconfigure(vec![neighbor.clone()])
+            .await
+            .unwrap();
+        assert_eq!(replicas[0].neighbors(), vec![neighbor]);
+
+        let url = Uri::from_static("http://server-3:9999/register/local");
+        let response = get(url).await.unwrap();
+        let body = response.collect().await?.aggregate();
+        let body: JSON = serde_json::from_reader(body.reader())?;
+        assert_eq!(body, json!({"value": 123, "label": 1}));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn removed_neighbors_are_no_longer_required() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        replicas[0].write(123).await.unwrap();
+        for i in 1..3 {
+            let neighbor: Uri = format!("http://server-{i}:9999").parse().unwrap();
+            replicas[0].remove_neighbor(&neighbor).await.unwrap();
+        }
+
+        // Without any neighbors, server-0 forms a majority on its own.
+        turmoil::partition("client", "server-1");
+        turmoil::partition("client", "server-2");
+        assert_eq!(replicas[0].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn raises_error_if_majority_of_old_neighbors_are_offline() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-1");
+        turmoil::partition("client", "server-2");
+        let result = replicas[0].reconfigure(Vec::new()).await;
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("More than 1 of the instances are offline"));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+mod get {
+    use super::*;
+
+    #[test]
+    fn responds_with_neighbors_as_json() {
+        let (mut sim, _) = simulate_servers(2);
+        sim.client("client", async move {
+            let url = Uri::from_static("http://server-0:9999/register/neighbors");
+            let response = get(url).await.unwrap();
+            assert!(response.status().is_success());
+
+            let body = response.collect().await?.aggregate();
+            let body: JSON = serde_json::from_reader(body.reader())?;
+            assert_eq!(body, json!(["http://server-1:9999/"]));
+            Ok(())
+        });
+        sim.run().unwrap();
+    }
+}
+
+mod post {
+    use super::*;
+
+    #[test]
+    fn reconfigures_neighbors() {
+        let (mut sim, replicas) = simulate_servers(3);
+        sim.client("client", async move {
+            let url = Uri::from_static("http://server-0:9999/register/neighbors");
+            let response = post(url, json!(["http://server-1:9999"])).await.unwrap();
+            assert!(response.status().is_success());
+
+            let body = response.collect().await?.aggregate();
+            let body: JSON = serde_json::from_reader(body.reader())?;
+            assert_eq!(body, json!(["http://server-1:9999/"]));
+
+            let expected: Uri = "http://server-1:9999".parse().unwrap();
+            assert_eq!(replicas[0].neighbors(), vec![expected]);
+            Ok(())
+        });
+        sim.run().unwrap();
+    }
+
+    #[test]
+    fn responds_with_bad_request_if_body_is_invalid() {
+        let (mut sim, _) = simulate_servers(1);
+        sim.client("client", async move {
+            let url = Uri::from_static("http://server-0:9999/register/neighbors");
+            let response = post(url, json!({"foo": "bar"})).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            Ok(())
+        });
+        sim.run().unwrap();
+    }
+
+    #[test]
+    fn responds_with_service_unavailable_if_majority_is_offline() {
+        let (mut sim, _) = simulate_servers(3);
+        sim.client("client", async move {
+            turmoil::partition("server-0", "server-1");
+            turmoil::partition("server-0", "server-2");
+            let url = Uri::from_static("http://server-0:9999/register/neighbors");
+            let response = post(url, json!([])).await.unwrap();
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+            Ok(())
+        });
+        sim.run().unwrap();
+    }
+}
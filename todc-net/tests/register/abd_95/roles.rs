@@ -0,0 +1,61 @@
+use hyper::http::StatusCode;
+use hyper::Uri;
+use serde_json::json;
+
+use todc_net::register::abd_95::Role;
+
+use crate::register::abd_95::common::{get, post, simulate_servers_with_roles};
+
+#[test]
+fn read_only_instance_responds_forbidden_to_writes() {
+    let (mut sim, _) = simulate_servers_with_roles(&[Role::Full, Role::Full, Role::ReadOnly]);
+    sim.client("client", async move {
+        let url = Uri::from_static("http://server-2:9999/register");
+        let response = post(url, json!(123)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn read_only_instance_reads_value_written_by_another_instance() {
+    let (mut sim, registers) =
+        simulate_servers_with_roles(&[Role::Full, Role::Full, Role::ReadOnly]);
+    sim.client("client", async move {
+        registers[0].write(123).await.unwrap();
+        assert_eq!(registers[2].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn witness_responds_forbidden_to_reads() {
+    let (mut sim, _) = simulate_servers_with_roles(&[Role::Full, Role::Full, Role::Witness]);
+    sim.client("client", async move {
+        let url = Uri::from_static("http://server-2:9999/register");
+        let response = get(url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn witness_counts_towards_majority() {
+    let (mut sim, registers) =
+        simulate_servers_with_roles(&[Role::Full, Role::Full, Role::Witness]);
+    sim.client("client", async move {
+        // Server 0 can only reach a majority through the witness.
+        turmoil::partition("client", "server-1");
+        registers[0].write(123).await.unwrap();
+        turmoil::repair("client", "server-1");
+
+        // Server 1 can only learn of the write through the witness.
+        turmoil::partition("client", "server-0");
+        assert_eq!(registers[1].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -0,0 +1,55 @@
+use bytes::Buf;
+use http_body_util::BodyExt;
+use hyper::http::StatusCode;
+use hyper::Uri;
+use serde_json::Value as JSON;
+
+use crate::register::abd_95::common::{get, simulate_servers};
+
+#[test]
+fn reports_neighbors_contacted_by_operations() {
+    let (mut sim, registers) = simulate_servers(3);
+    sim.client("client", async move {
+        registers[0].write(123).await.unwrap();
+
+        let url = Uri::from_static("http://server-0:9999/register/status");
+        let response = get(url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.collect().await?.aggregate();
+        let body: JSON = serde_json::from_reader(body.reader())?;
+        assert_eq!(body["label"], 1);
+        assert_eq!(body["quorum_reachable"], true);
+        let neighbors = body["neighbors"].as_array().unwrap();
+        assert_eq!(neighbors.len(), 2);
+        // At least a majority of neighbors replied to the write, and so
+        // have caught up with it.
+        assert!(neighbors
+            .iter()
+            .any(|neighbor| neighbor["reachable"] == true && neighbor["last_contact_ms"].is_u64()));
+        assert!(neighbors
+            .iter()
+            .any(|neighbor| neighbor["label"] == 1 && neighbor["lag"] == 0));
+        assert_eq!(body["reads"]["repaired"], 0);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn responds_unavailable_if_quorum_is_unreachable() {
+    let (mut sim, registers) = simulate_servers(3);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-1");
+        turmoil::partition("client", "server-2");
+        assert!(registers[0].write(123).await.is_err());
+
+        let url = Uri::from_static("http://server-0:9999/register/status");
+        let response = get(url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = response.collect().await?.aggregate();
+        let body: JSON = serde_json::from_reader(body.reader())?;
+        assert_eq!(body["quorum_reachable"], false);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
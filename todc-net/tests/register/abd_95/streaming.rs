@@ -0,0 +1,78 @@
+use bytes::{Buf, Bytes};
+use http_body_util::{BodyExt, Full};
+use hyper::http::StatusCode;
+use hyper::{Method, Request, Uri};
+use serde_json::{json, Value as JSON};
+use turmoil::net::TcpStream;
+use turmoil::Sim;
+
+use todc_net::register::AtomicRegister;
+use todc_net::testing::SimulatedCluster;
+use todc_net::TokioIo;
+
+use crate::register::abd_95::common::get;
+
+/// A value that is streamed in more than one chunk.
+fn large_value() -> Vec<u8> {
+    (0..200_000).map(|i| i as u8).collect()
+}
+
+/// Simulate n replicas of a register, where the ith replica streams the
+/// values that it announces if streaming[i] is true.
+fn simulate_streaming_servers<'a>(streaming: &[bool]) -> (Sim<'a>, Vec<AtomicRegister<Vec<u8>>>) {
+    SimulatedCluster::with_instances(streaming.len(), |i, neighbors| {
+        AtomicRegister::new(neighbors).with_streaming(streaming[i])
+    })
+    .into_parts()
+}
+
+#[test]
+fn read_returns_value_streamed_to_other_replicas() {
+    let (mut sim, replicas) = simulate_streaming_servers(&[true, true, true]);
+    sim.client("client", async move {
+        replicas[0].write(large_value()).await.unwrap();
+        assert_eq!(replicas[2].read().await.unwrap(), large_value());
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn streaming_and_non_streaming_replicas_interoperate() {
+    let (mut sim, replicas) = simulate_streaming_servers(&[true, false, false]);
+    sim.client("client", async move {
+        replicas[0].write(large_value()).await.unwrap();
+        assert_eq!(replicas[1].read().await.unwrap(), large_value());
+        replicas[2].write(vec![1, 2, 3]).await.unwrap();
+        assert_eq!(replicas[0].read().await.unwrap(), vec![1, 2, 3]);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn rejects_value_that_does_not_match_digest() {
+    let (mut sim, _) = simulate_streaming_servers(&[true, true, true]);
+    sim.client("client", async move {
+        let stream = TcpStream::connect("server-0:9999").await?;
+        let (mut sender, conn) =
+            hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+        tokio::spawn(conn);
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("http://server-0:9999/register/local")
+            .header("x-register-label", "1")
+            .header("x-content-sha256", "0".repeat(64))
+            .body(Full::new(Bytes::from("[1,2,3]")))?;
+        let response = sender.send_request(request).await?;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // The value was not adopted.
+        let url = Uri::from_static("http://server-0:9999/register/local");
+        let body = get(url).await.unwrap().collect().await?.aggregate();
+        let body: JSON = serde_json::from_reader(body.reader())?;
+        assert_eq!(body, json!({"value": [], "label": 0}));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
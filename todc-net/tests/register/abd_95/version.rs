@@ -0,0 +1,69 @@
+use serde_json::{json, Value as JSON};
+
+use todc_net::register::abd_95::PROTOCOL_VERSIONS;
+use todc_net::testing::url;
+use todc_net::transport::{HttpTransport, Message, Transport, UnsupportedVersion};
+
+use crate::register::abd_95::common::simulate_servers;
+
+#[test]
+fn rejects_messages_in_unsupported_versions() {
+    let (mut sim, _) = simulate_servers(1);
+    sim.client("client", async move {
+        let message = Message::ask("/register/local").with_version(99);
+        let result = HttpTransport::default().send(url(0), message).await;
+        assert!(result.unwrap_err().to_string().contains("not supported"));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn advertises_supported_versions_when_rejecting_messages() {
+    let (mut sim, _) = simulate_servers(1);
+    sim.client("client", async move {
+        let message = Message::ask("/register/local").with_version(99);
+        let error = HttpTransport::default()
+            .send(url(0), message)
+            .await
+            .unwrap_err();
+        let error = error.downcast_ref::<UnsupportedVersion>().unwrap();
+        assert_eq!(error.version, 99);
+        assert_eq!(error.supported, PROTOCOL_VERSIONS);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn accepts_messages_in_supported_versions() {
+    let (mut sim, replicas) = simulate_servers(1);
+    sim.client("client", async move {
+        replicas[0].write(123).await.unwrap();
+        for version in PROTOCOL_VERSIONS {
+            let message = Message::ask("/register/local").with_version(version);
+            let reply = HttpTransport::default()
+                .send(url(0), message)
+                .await
+                .unwrap();
+            let body: JSON = serde_json::from_slice(&reply)?;
+            assert_eq!(body, json!({"value": 123, "label": 1}));
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn instances_pinned_to_earlier_version_interoperate() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        let pinned = replicas[0]
+            .clone()
+            .with_protocol_version(*PROTOCOL_VERSIONS.start())?;
+        pinned.write(123).await.unwrap();
+        assert_eq!(replicas[1].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
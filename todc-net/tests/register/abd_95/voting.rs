@@ -0,0 +1,36 @@
+use todc_net::register::abd_95::RegisterError;
+
+use crate::register::abd_95::common::simulate_servers_with_weights;
+
+#[test]
+fn completes_without_instances_holding_minority_of_votes() {
+    let (mut sim, registers) = simulate_servers_with_weights(&[2, 1, 1]);
+    sim.client("client", async move {
+        // Server 0 and server 1 hold three of the four votes.
+        turmoil::partition("client", "server-2");
+        registers[0].write(123).await.unwrap();
+        turmoil::repair("client", "server-2");
+
+        // As do server 0 and server 2.
+        turmoil::partition("client", "server-1");
+        assert_eq!(registers[2].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn fails_without_instance_holding_most_votes() {
+    let (mut sim, registers) = simulate_servers_with_weights(&[2, 1, 1]);
+    sim.client("client", async move {
+        // A majority of instances hold only two of the four votes.
+        turmoil::partition("client", "server-0");
+        let result = registers[1].read().await;
+        assert!(matches!(
+            result,
+            Err(RegisterError::QuorumUnavailable { needed: 3, .. })
+        ));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -1,3 +1,5 @@
+use todc_net::register::abd_95::RegisterError;
+
 use crate::register::abd_95::common::simulate_servers;
 
 #[test]
@@ -77,12 +79,44 @@ fn raises_error_if_more_than_half_of_neighbors_are_offline() {
         turmoil::partition("client", "server-1");
         turmoil::partition("client", "server-2");
         let result = replicas[0].write(123).await;
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("A majority of neighbors are offline"));
+        assert!(matches!(
+            result,
+            Err(RegisterError::QuorumUnavailable {
+                acks: 1,
+                needed: 2,
+                ..
+            })
+        ));
         Ok(())
     });
 
     sim.run().unwrap();
 }
+
+#[test]
+fn write_many_sets_last_value_of_all_other_replicas() {
+    const NUM_REPLICAS: usize = 3;
+    let (mut sim, replicas) = simulate_servers(NUM_REPLICAS);
+    sim.client("client", async move {
+        replicas[0].write_many(vec![1, 2, 3]).await.unwrap();
+        for i in (0..NUM_REPLICAS).rev() {
+            let value = replicas[i].read().await.unwrap();
+            assert_eq!(value, 3);
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn read_modify_write_uses_value_written_to_other_replica() {
+    let (mut sim, replicas) = simulate_servers(3);
+    sim.client("client", async move {
+        replicas[1].write(1).await.unwrap();
+        let value = replicas[0].read_modify_write(|v| v + 1).await.unwrap();
+        assert_eq!(value, 2);
+        assert_eq!(replicas[2].read().await.unwrap(), 2);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
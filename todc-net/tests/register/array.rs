@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use turmoil::Sim;
+
+use todc_net::register::RegisterArray;
+use todc_net::testing::SimulatedCluster;
+
+/// Simulate n replicas of an array of three registers.
+fn simulate_arrays<'a>(n: usize) -> (Sim<'a>, Vec<RegisterArray<u32, 3>>) {
+    SimulatedCluster::with_instances(n, |_, neighbors| RegisterArray::new(neighbors)).into_parts()
+}
+
+#[test]
+fn write_is_visible_to_other_replicas() {
+    let (mut sim, arrays) = simulate_arrays(3);
+    sim.client("client", async move {
+        arrays[0].write(1, 123).await.unwrap();
+        assert_eq!(arrays[2].read(1).await.unwrap(), 123);
+        assert_eq!(arrays[2].read(0).await.unwrap(), 0);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn collect_returns_values_written_by_every_replica() {
+    let (mut sim, arrays) = simulate_arrays(3);
+    sim.client("client", async move {
+        for (i, array) in arrays.iter().enumerate() {
+            array.write(i, i as u32 + 1).await.unwrap();
+        }
+        assert_eq!(arrays[0].collect().await.unwrap(), [1, 2, 3]);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn scan_returns_contents_at_a_single_moment() {
+    const NUM_WRITES: u32 = 5;
+    let (mut sim, arrays) = simulate_arrays(3);
+    sim.set_max_message_latency(Duration::from_millis(10));
+
+    // Each writer increments its own register.
+    for (i, array) in arrays.iter().enumerate() {
+        let array = array.clone();
+        sim.client(format!("writer-{i}"), async move {
+            for value in 1..=NUM_WRITES {
+                array.write(i, value).await.unwrap();
+            }
+            Ok(())
+        });
+    }
+    let array = arrays[0].clone();
+    sim.client("scanner", async move {
+        let mut previous = [0; 3];
+        for _ in 0..NUM_WRITES {
+            let scan = array.scan().await.unwrap();
+            // Values only ever increase, so each scan dominates the previous one.
+            assert!(scan.iter().zip(&previous).all(|(new, old)| new >= old));
+            previous = scan;
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -0,0 +1,101 @@
+use turmoil::Sim;
+
+use todc_net::auth::HmacAuthenticator;
+use todc_net::register::abd_95::RegisterError;
+use todc_net::register::ByzantineRegister;
+use todc_net::testing::SimulatedCluster;
+
+/// Simulate n instances of a register, of which at most f are Byzantine.
+fn simulate_servers<'a>(n: usize, f: usize) -> (Sim<'a>, Vec<ByzantineRegister<u32>>) {
+    SimulatedCluster::with_instances(n, |i, neighbors| {
+        ByzantineRegister::new(i as u32, neighbors, f).unwrap()
+    })
+    .into_parts()
+}
+
+#[test]
+fn reads_value_written_by_other_instance() {
+    let (mut sim, replicas) = simulate_servers(5, 1);
+    sim.client("client", async move {
+        replicas[0].write(123).await.unwrap();
+        assert_eq!(replicas[1].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn tolerates_unreachable_instances() {
+    let (mut sim, replicas) = simulate_servers(5, 1);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-4");
+        replicas[0].write(123).await.unwrap();
+        assert_eq!(replicas[1].read().await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn raises_error_if_quorum_is_unreachable() {
+    let (mut sim, replicas) = simulate_servers(5, 1);
+    sim.client("client", async move {
+        turmoil::partition("client", "server-3");
+        turmoil::partition("client", "server-4");
+        let result = replicas[0].write(123).await;
+        assert!(matches!(
+            result,
+            Err(RegisterError::QuorumUnavailable {
+                needed: 4,
+                failures: 1,
+                ..
+            })
+        ));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn masks_values_fabricated_by_byzantine_instance() {
+    // The last instance never learns of the writes of any other, and
+    // instead reports a value with a larger timestamp than theirs.
+    let (mut sim, replicas) = SimulatedCluster::with_instances(5, |i, neighbors| match i {
+        4 => ByzantineRegister::new(i as u32, Vec::new(), 0).unwrap(),
+        _ => ByzantineRegister::new(i as u32, neighbors, 1).unwrap(),
+    })
+    .into_parts();
+    sim.client("client", async move {
+        for _ in 0..3 {
+            replicas[4].write(666).await.unwrap();
+        }
+        replicas[0].write(123).await.unwrap();
+        for replica in &replicas[..4] {
+            assert_eq!(replica.read().await.unwrap(), 123);
+        }
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn ignores_instances_that_cannot_be_authenticated() {
+    // Two instances sign their messages with the wrong key, which is more
+    // than the single Byzantine instance that is tolerated.
+    let (mut sim, replicas) = SimulatedCluster::with_instances(5, |i, neighbors| {
+        let key: &[u8] = if i < 3 { b"key" } else { b"wrong key" };
+        ByzantineRegister::new(i as u32, neighbors, 1)
+            .unwrap()
+            .with_authenticator(HmacAuthenticator::new(key))
+    })
+    .into_parts();
+    sim.client("client", async move {
+        let result = replicas[0].write(123).await;
+        assert!(matches!(
+            result,
+            Err(RegisterError::QuorumUnavailable { needed: 4, .. })
+        ));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -0,0 +1,110 @@
+use turmoil::Sim;
+
+use todc_net::quorum::QuorumPolicy;
+use todc_net::register::abd_95::RegisterError;
+use todc_net::register::sharded::{Shard, ShardedRegisterMap};
+use todc_net::testing::{host, url, SimulatedCluster};
+
+/// The number of instances in each shard.
+const SHARD_SIZE: usize = 3;
+
+/// Simulate instances of a map with one shard for each quorum policy, where
+/// the `s`th shard is stored by the `s`th group of three instances.
+fn simulate_maps<'a>(policies: Vec<QuorumPolicy>) -> (Sim<'a>, Vec<ShardedRegisterMap<u32>>) {
+    let n = policies.len() * SHARD_SIZE;
+    SimulatedCluster::with_instances(n, move |i, _| {
+        let shards = policies
+            .iter()
+            .enumerate()
+            .map(|(s, policy)| {
+                let group = s * SHARD_SIZE..(s + 1) * SHARD_SIZE;
+                let shard = if group.contains(&i) {
+                    Shard::member(group.filter(|&j| j != i).map(url).collect())
+                } else {
+                    Shard::remote(group.map(url).collect())
+                };
+                shard.with_quorum_policy(policy.clone())
+            })
+            .collect();
+        ShardedRegisterMap::new(shards).unwrap()
+    })
+    .into_parts()
+}
+
+/// Returns a key that is stored by the given shard.
+fn key_in(map: &ShardedRegisterMap<u32>, shard: usize) -> String {
+    (0..)
+        .map(|i| format!("key-{i}"))
+        .find(|key| map.shard_of(key) == shard)
+        .unwrap()
+}
+
+#[test]
+fn reads_value_written_through_instance_of_other_shard() {
+    let (mut sim, maps) = simulate_maps(vec![QuorumPolicy::Majority; 2]);
+    sim.client("client", async move {
+        let key = key_in(&maps[0], 1);
+        maps[0].write(&key, 123).await.unwrap();
+        assert_eq!(maps[4].read(&key).await.unwrap(), 123);
+        assert_eq!(maps[1].read(&key).await.unwrap(), 123);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn keys_are_independent() {
+    let (mut sim, maps) = simulate_maps(vec![QuorumPolicy::Majority; 2]);
+    sim.client("client", async move {
+        let first = key_in(&maps[0], 0);
+        let second = key_in(&maps[0], 1);
+        maps[0].write(&first, 1).await.unwrap();
+        maps[3].write(&second, 2).await.unwrap();
+        assert_eq!(maps[5].read(&first).await.unwrap(), 1);
+        assert_eq!(maps[2].read(&second).await.unwrap(), 2);
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn shard_remains_available_while_other_shard_is_unreachable() {
+    let (mut sim, maps) = simulate_maps(vec![QuorumPolicy::Majority; 2]);
+    sim.client("client", async move {
+        for i in SHARD_SIZE..2 * SHARD_SIZE {
+            turmoil::partition("client", host(i));
+        }
+        let available = key_in(&maps[0], 0);
+        let unavailable = key_in(&maps[0], 1);
+        maps[0].write(&available, 123).await.unwrap();
+        assert_eq!(maps[1].read(&available).await.unwrap(), 123);
+        let result = maps[0].write(&unavailable, 123).await;
+        assert!(matches!(
+            result,
+            Err(RegisterError::QuorumUnavailable { needed: 2, .. })
+        ));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
+
+#[test]
+fn applies_quorum_policy_of_each_shard() {
+    // The first shard tolerates one failure, but the second tolerates none.
+    let policies = vec![QuorumPolicy::Majority, QuorumPolicy::FailureThreshold(0)];
+    let (mut sim, maps) = simulate_maps(policies);
+    sim.client("client", async move {
+        turmoil::partition("client", host(2));
+        turmoil::partition("client", host(5));
+        let tolerant = key_in(&maps[0], 0);
+        let intolerant = key_in(&maps[0], 1);
+        maps[0].write(&tolerant, 123).await.unwrap();
+        let result = maps[0].write(&intolerant, 123).await;
+        assert!(matches!(
+            result,
+            Err(RegisterError::QuorumUnavailable { needed: 3, .. })
+        ));
+        Ok(())
+    });
+    sim.run().unwrap();
+}
@@ -0,0 +1,161 @@
+#![cfg(feature = "turmoil")]
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use hyper::http::StatusCode;
+
+use todc_net::register::AtomicRegister;
+use todc_net::testing::{self, LinkLatency, Nemesis, SimulatedCluster, Topology, Workload};
+
+#[test]
+fn register_clients_reach_their_instance() {
+    let mut cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+    let first = cluster.register_client(0);
+    let second = cluster.register_client(1);
+    cluster.client("client", async move {
+        testing::partition(0, 1);
+        first.write(123).await.unwrap();
+        assert_eq!(second.read().await.unwrap(), 123);
+        Ok(())
+    });
+    cluster.run().unwrap();
+}
+
+#[test]
+fn crashed_instance_does_not_respond() {
+    let mut cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+    cluster.crash(2);
+    let first = cluster.register_client(0);
+    cluster.client("client", async move {
+        first.write(123).await.unwrap();
+        let request = testing::get(testing::url(2));
+        let response = tokio::time::timeout(Duration::from_secs(1), request).await;
+        assert!(!matches!(response, Ok(Ok(_))));
+        Ok(())
+    });
+    cluster.run().unwrap();
+}
+
+#[test]
+fn bounced_instance_responds_again() {
+    let mut cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+    cluster.crash(2);
+    cluster.bounce(2);
+    let third = cluster.register_client(2);
+    cluster.client("client", async move {
+        third.write(123).await.unwrap();
+        let url = format!("{}register", testing::url(0)).parse().unwrap();
+        let response = testing::get(url).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        Ok(())
+    });
+    cluster.run().unwrap();
+}
+
+#[test]
+fn nemesis_with_same_seed_injects_same_faults() {
+    let faults = || {
+        let mut cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+        cluster.client("client", async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        });
+        let mut nemesis = Nemesis::new(123);
+        cluster.run_with_nemesis(&mut nemesis).unwrap();
+        nemesis.faults().to_vec()
+    };
+    let first = faults();
+    assert!(first.len() >= 5);
+    assert_eq!(first, faults());
+}
+
+#[test]
+fn workload_performs_operations_on_instances() {
+    let mut cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+    let workload = Workload::new(0)
+        .with_operations(10)
+        .with_write_probability(1.0);
+    cluster.add_workload(&workload);
+    let register = cluster.instance(0).clone();
+    cluster.run().unwrap();
+    cluster.client("client", async move {
+        assert_eq!(register.read().await.unwrap(), 10);
+        Ok(())
+    });
+    cluster.run().unwrap();
+}
+
+#[test]
+fn replayed_seed_reconstructs_same_schedule() {
+    let schedule = || {
+        testing::replay(456);
+        let mut cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+        let workload = Workload::new(testing::seed())
+            .with_operations(20)
+            .with_timeout(Duration::from_millis(500));
+        cluster.add_workload(&workload);
+        // Run for a fixed amount of simulated time, so that the nemesis
+        // injects the same number of faults.
+        cluster.client("client", async move {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        });
+        let mut nemesis = Nemesis::new(testing::seed());
+        cluster.run_with_nemesis(&mut nemesis).unwrap();
+        (workload.generate(3), nemesis.faults().to_vec())
+    };
+    let (operations, faults) = schedule();
+    assert!(faults.len() >= 10);
+    assert_eq!((operations, faults), schedule());
+}
+
+/// Returns how long it takes to write to the first instance of a cluster
+/// that is placed according to the topology.
+fn write_latency(topology: Topology) -> Duration {
+    let mut cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+    cluster.set_topology(&topology);
+    let first = cluster.register_client(0);
+    let latency = Rc::new(Cell::new(Duration::ZERO));
+    let elapsed = latency.clone();
+    cluster.client("client", async move {
+        let start = tokio::time::Instant::now();
+        first.write(123).await.unwrap();
+        elapsed.set(start.elapsed());
+        Ok(())
+    });
+    cluster.run().unwrap();
+    latency.get()
+}
+
+#[test]
+fn writes_across_regions_wait_for_a_remote_quorum() {
+    let topology = Topology::across_regions(3, 3);
+    // A write performs two phases, each of which must reach a majority.
+    let lower = 2 * topology.quorum_latency(0, 2);
+    let latency = write_latency(topology);
+    assert!(latency >= lower, "{latency:?} is faster than {lower:?}");
+}
+
+#[test]
+fn writes_within_a_rack_are_faster_than_across_regions() {
+    let local = write_latency(Topology::single_rack(3));
+    let remote = write_latency(Topology::across_regions(3, 3));
+    assert!(local < LinkLatency::CROSS_REGION.min);
+    assert!(local < remote);
+}
+
+#[test]
+fn nearby_instances_form_a_faster_quorum() {
+    let fast = LinkLatency::fixed(Duration::from_millis(5));
+    let nearby = write_latency(Topology::across_regions(3, 3).with_link(0, 1, fast));
+    let remote = write_latency(Topology::across_regions(3, 3));
+    assert!(nearby < remote);
+}
+
+#[test]
+#[should_panic]
+fn topology_must_place_every_instance() {
+    let mut cluster: SimulatedCluster<AtomicRegister<u32>> = SimulatedCluster::new(3);
+    cluster.set_topology(&Topology::single_rack(2));
+}
@@ -2,14 +2,17 @@ use core::time::Duration;
 
 use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 
+use todc_utils::corpus::Corpus;
 use todc_utils::linearizability::WGLChecker;
 use todc_utils::specifications::etcd::{history_from_log, EtcdSpecification};
 
-const LOG_FILE: &str = "benches/static/etcd_log_005.log";
+const LOG_FILE: &str = "etcd_005.log";
 
 // Checks that a relatively complex `etcd` history is in-fact linearizable.
 fn criterion_benchmark(c: &mut Criterion) {
-    let history = history_from_log(LOG_FILE.to_owned());
+    let corpus = Corpus::etcd().with_cache_dir("tests/linearizability/etcd");
+    let path = corpus.path(LOG_FILE).unwrap();
+    let history = history_from_log(path.to_string_lossy().into_owned());
     c.bench_function("WGLChecker - check linearizability of etcd log", |b| {
         b.iter_batched(
             || history.clone(),
@@ -0,0 +1,442 @@
+//! Fetching and caching corpora of recorded histories.
+//!
+//! A [`Corpus`] is a named collection of files, such as the logs of the
+//! [Jepsen](https://github.com/jepsen-io/jepsen) tests of
+//! [etcd](https://github.com/etcd-io/etcd), that are published at some base
+//! URL. Each corpus comes with a manifest of the SHA-256 checksum of every
+//! file, so that tests and benchmarks can share a single copy of each file
+//! and be certain that it is the one they expect.
+//!
+//! Files are stored in a cache directory, and are only downloaded if they are
+//! missing from it. Downloads are performed with `curl`, which must be
+//! available whenever a file is not already cached. By default, corpora are
+//! cached in the directory named by the `TODC_CORPUS_DIR` environment
+//! variable, or in the temporary directory of the system if it is unset.
+//!
+//! # Manifests
+//!
+//! Manifests use the same format as the output of `sha256sum`, with one file
+//! per line:
+//!
+//! ```text
+//! <checksum>  <name>
+//! ```
+//!
+//! Empty lines, and lines starting with `#`, are ignored.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use todc_utils::corpus::Corpus;
+//! use todc_utils::specifications::etcd::history_from_log;
+//!
+//! let corpus = Corpus::etcd();
+//! let path = corpus.path("etcd_005.log").unwrap();
+//! let history = history_from_log(path.to_string_lossy().into_owned());
+//! ```
+use std::env;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter, Write as _};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use sha2::{Digest, Sha256};
+
+/// The environment variable that overrides the default cache directory.
+pub const CORPUS_DIR_VAR: &str = "TODC_CORPUS_DIR";
+
+/// The base URL of the etcd logs recorded by Jepsen.
+const ETCD_BASE_URL: &str =
+    "https://raw.githubusercontent.com/ahorn/linearizability-checker/master/jepsen/";
+
+/// The manifest of the etcd logs recorded by Jepsen.
+const ETCD_MANIFEST: &str = include_str!("corpus/etcd.sha256");
+
+/// An error that occurs while resolving a file of a corpus.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CorpusError {
+    /// The file is not listed in the manifest of the corpus.
+    UnknownFile(String),
+    /// The cache directory could not be read from or written to.
+    Io(io::Error),
+    /// The file could not be downloaded.
+    Fetch { url: String, reason: String },
+    /// The contents of the file do not match its checksum.
+    ChecksumMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    /// A line of a manifest is not of the form `<checksum>  <name>`.
+    InvalidManifest(String),
+}
+
+impl Display for CorpusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFile(name) => write!(f, "{name} is not part of the corpus"),
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Fetch { url, reason } => write!(f, "failed to fetch {url}: {reason}"),
+            Self::ChecksumMismatch {
+                name,
+                expected,
+                actual,
+            } => write!(f, "checksum of {name} is {actual}, but expected {expected}"),
+            Self::InvalidManifest(line) => write!(f, "invalid manifest line: {line:?}"),
+        }
+    }
+}
+
+impl Error for CorpusError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CorpusError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// A file of a corpus, along with its expected checksum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CorpusFile {
+    /// The name of the file, relative to the base URL of the corpus.
+    pub name: String,
+    /// The hex-encoded SHA-256 checksum of the contents of the file.
+    pub sha256: String,
+}
+
+/// A collection of files that are fetched on demand and cached locally.
+///
+/// See the [`corpus`](crate::corpus) module-level documentation for more
+/// details.
+#[derive(Clone, Debug)]
+pub struct Corpus {
+    name: String,
+    base_url: String,
+    files: Vec<CorpusFile>,
+    cache_dir: PathBuf,
+}
+
+impl Corpus {
+    /// Creates a new corpus with the given name, whose files are listed in
+    /// `manifest` and can be fetched relative to `base_url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CorpusError::InvalidManifest`] if a line of the manifest is
+    /// not of the form `<checksum>  <name>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::corpus::Corpus;
+    ///
+    /// let manifest = "\
+    /// e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  empty.log";
+    /// let corpus = Corpus::new("example", "https://example.com/", manifest).unwrap();
+    /// assert_eq!(corpus.files().len(), 1);
+    /// ```
+    pub fn new(name: &str, base_url: &str, manifest: &str) -> Result<Self, CorpusError> {
+        let files = parse_manifest(manifest)?;
+        Ok(Self {
+            name: name.to_string(),
+            base_url: base_url.to_string(),
+            files,
+            cache_dir: default_cache_dir(name),
+        })
+    }
+
+    /// Returns the logs of the Jepsen tests of etcd, as collected by the
+    /// [linearizability-checker](https://github.com/ahorn/linearizability-checker)
+    /// project.
+    ///
+    /// The logs are named `etcd_000.log` to `etcd_102.log`, and can be
+    /// parsed with
+    /// [`history_from_log`](crate::specifications::etcd::history_from_log).
+    pub fn etcd() -> Self {
+        Self::new("etcd", ETCD_BASE_URL, ETCD_MANIFEST).expect("etcd manifest is valid")
+    }
+
+    /// Sets the directory in which the files of the corpus are cached.
+    ///
+    /// Files that are already present in the directory are only used if
+    /// they match their checksum.
+    pub fn with_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = cache_dir.into();
+        self
+    }
+
+    /// Returns the name of the corpus.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the directory in which the files of the corpus are cached.
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Returns the files of the corpus, in the order of its manifest.
+    pub fn files(&self) -> &[CorpusFile] {
+        &self.files
+    }
+
+    /// Returns whether the file with the given name is present in the cache
+    /// directory.
+    ///
+    /// The contents of the file are not checked against its checksum.
+    pub fn is_cached(&self, name: &str) -> bool {
+        self.cache_dir.join(name).is_file()
+    }
+
+    /// Returns the path to the file with the given name, fetching it if it
+    /// is not already cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file is not part of the corpus, if it could
+    /// not be fetched, or if its contents do not match its checksum.
+    pub fn path(&self, name: &str) -> Result<PathBuf, CorpusError> {
+        let file = self
+            .files
+            .iter()
+            .find(|file| file.name == name)
+            .ok_or_else(|| CorpusError::UnknownFile(name.to_string()))?;
+        let path = self.cache_dir.join(&file.name);
+        if !path.is_file() {
+            self.fetch(file, &path)?;
+        }
+        verify(file, &path)?;
+        Ok(path)
+    }
+
+    /// Returns the paths to every file of the corpus, fetching those that
+    /// are not already cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while resolving a file, as with
+    /// [`path`](Corpus::path).
+    pub fn fetch_all(&self) -> Result<Vec<PathBuf>, CorpusError> {
+        self.files
+            .iter()
+            .map(|file| self.path(&file.name))
+            .collect()
+    }
+
+    /// Downloads a file of the corpus to the given path.
+    ///
+    /// The file is first downloaded to a temporary path, and only moved into
+    /// place once its checksum has been verified, so that an interrupted
+    /// download never leaves a partial file in the cache.
+    fn fetch(&self, file: &CorpusFile, path: &Path) -> Result<(), CorpusError> {
+        fs::create_dir_all(&self.cache_dir)?;
+        let url = format!("{}{}", self.base_url, file.name);
+        let partial = path.with_extension("part");
+        let output = Command::new("curl")
+            .args(["--fail", "--silent", "--show-error", "--location"])
+            .arg("--output")
+            .arg(&partial)
+            .arg(&url)
+            .output()
+            .map_err(|error| CorpusError::Fetch {
+                url: url.clone(),
+                reason: error.to_string(),
+            })?;
+        if !output.status.success() {
+            let _ = fs::remove_file(&partial);
+            return Err(CorpusError::Fetch {
+                url,
+                reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        if let Err(error) = verify(file, &partial) {
+            let _ = fs::remove_file(&partial);
+            return Err(error);
+        }
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+/// Returns the default cache directory of the corpus with the given name.
+fn default_cache_dir(name: &str) -> PathBuf {
+    env::var_os(CORPUS_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| env::temp_dir().join("todc-corpora"))
+        .join(name)
+}
+
+/// Parses a manifest in the format of `sha256sum`.
+fn parse_manifest(manifest: &str) -> Result<Vec<CorpusFile>, CorpusError> {
+    manifest
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let invalid = || CorpusError::InvalidManifest(line.to_string());
+            let (sha256, name) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            // sha256sum marks files that were read in binary mode with '*'.
+            let name = name.trim_start().trim_start_matches('*');
+            let is_checksum = sha256.len() == 64 && sha256.chars().all(|c| c.is_ascii_hexdigit());
+            if !is_checksum || name.is_empty() {
+                return Err(invalid());
+            }
+            Ok(CorpusFile {
+                name: name.to_string(),
+                sha256: sha256.to_ascii_lowercase(),
+            })
+        })
+        .collect()
+}
+
+/// Checks that the contents of the file at `path` match the checksum of
+/// `file`.
+fn verify(file: &CorpusFile, path: &Path) -> Result<(), CorpusError> {
+    let actual = sha256_hex(&fs::read(path)?);
+    if actual != file.sha256 {
+        return Err(CorpusError::ChecksumMismatch {
+            name: file.name.clone(),
+            expected: file.sha256.clone(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+/// Returns the hex-encoded SHA-256 checksum of some bytes.
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    /// Returns an empty directory, unique to the calling test.
+    fn cache_dir(test: &str) -> PathBuf {
+        let dir = env::temp_dir()
+            .join("todc-corpus-tests")
+            .join(format!("{test}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    mod etcd {
+        use super::*;
+
+        #[test]
+        fn lists_every_log() {
+            let corpus = Corpus::etcd();
+            assert_eq!(corpus.files().len(), 103);
+            assert_eq!(corpus.files()[0].name, "etcd_000.log");
+            assert_eq!(corpus.files()[102].name, "etcd_102.log");
+        }
+
+        #[test]
+        fn matches_logs_in_repository() {
+            let corpus = Corpus::etcd().with_cache_dir(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/linearizability/etcd"
+            ));
+            for file in corpus.files() {
+                assert!(corpus.is_cached(&file.name));
+            }
+            assert!(corpus.fetch_all().is_ok());
+        }
+    }
+
+    mod parse_manifest {
+        use super::*;
+
+        #[test]
+        fn ignores_comments_and_empty_lines() {
+            let manifest = format!("# comment\n\n{EMPTY_SHA256}  empty.log\n");
+            let files = parse_manifest(&manifest).unwrap();
+            assert_eq!(
+                files,
+                vec![CorpusFile {
+                    name: "empty.log".to_string(),
+                    sha256: EMPTY_SHA256.to_string(),
+                }]
+            );
+        }
+
+        #[test]
+        fn accepts_binary_mode_marker() {
+            let manifest = format!("{EMPTY_SHA256} *empty.log");
+            assert_eq!(parse_manifest(&manifest).unwrap()[0].name, "empty.log");
+        }
+
+        #[test]
+        fn rejects_invalid_checksum() {
+            assert!(matches!(
+                parse_manifest("abc123  empty.log"),
+                Err(CorpusError::InvalidManifest(_))
+            ));
+        }
+
+        #[test]
+        fn rejects_missing_name() {
+            assert!(matches!(
+                parse_manifest(EMPTY_SHA256),
+                Err(CorpusError::InvalidManifest(_))
+            ));
+        }
+    }
+
+    mod path {
+        use super::*;
+
+        fn corpus(test: &str) -> Corpus {
+            let manifest = format!("{EMPTY_SHA256}  empty.log");
+            Corpus::new("test", "http://localhost.invalid/", &manifest)
+                .unwrap()
+                .with_cache_dir(cache_dir(test))
+        }
+
+        #[test]
+        fn returns_cached_file() {
+            let corpus = corpus("returns_cached_file");
+            fs::write(corpus.cache_dir().join("empty.log"), "").unwrap();
+            let path = corpus.path("empty.log").unwrap();
+            assert_eq!(path, corpus.cache_dir().join("empty.log"));
+        }
+
+        #[test]
+        fn rejects_unknown_file() {
+            let corpus = corpus("rejects_unknown_file");
+            assert!(matches!(
+                corpus.path("other.log"),
+                Err(CorpusError::UnknownFile(_))
+            ));
+        }
+
+        #[test]
+        fn rejects_modified_file() {
+            let corpus = corpus("rejects_modified_file");
+            fs::write(corpus.cache_dir().join("empty.log"), "modified").unwrap();
+            assert!(matches!(
+                corpus.path("empty.log"),
+                Err(CorpusError::ChecksumMismatch { .. })
+            ));
+        }
+    }
+}
@@ -1,8 +1,11 @@
 //! Utilities for writing and testing distributed algorithms.
+pub mod corpus;
 pub mod linearizability;
 pub mod specifications;
 
-pub use linearizability::history::{Action, History};
-pub use linearizability::WGLChecker;
+pub use linearizability::history::{Action, History, TimedAction};
+pub use linearizability::{CheckResult, CheckerConfig, WGLChecker};
 
-pub use specifications::Specification;
+pub use specifications::{
+    DurableSpecification, NondeterministicSpecification, PartitionedSpecification, Specification,
+};
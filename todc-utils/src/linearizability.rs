@@ -2,13 +2,243 @@
 //! history of operations applied to a shared object.
 //!
 //! For more information, see the documentation of the [`WGLChecker`] and [`History`] structs.
-use std::collections::HashSet;
+//! For histories of objects that survive crashes, see the [`durable`] module, and for
+//! checking histories while they are still being recorded, see the [`online`] module.
+//! Histories that are too long to keep in memory can be recorded and checked one
+//! window at a time with the [`windowed`] module.
+//! To find a small reproduction of a history that is not linearizable, see
+//! [`WGLChecker::shrink`], and to make a large history faster to check, see
+//! [`History::compact`]. Operations that failed, or whose outcome is unknown, are
+//! described in [`Outcome`].
+use std::collections::hash_map::RandomState;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::linearizability::history::{Entry, History};
-use crate::specifications::Specification;
+use crate::linearizability::cache::{Bitset, Cache};
+use crate::linearizability::history::{Entry, EntryId, History, Outcome};
+use crate::specifications::multi_object::{self, MultiObjectSpecification};
+use crate::specifications::{
+    NondeterministicSpecification, PartitionedSpecification, Specification,
+};
 
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+mod cache;
+pub mod durable;
 pub mod history;
+pub mod online;
+pub mod windowed;
+
+/// Configuration for a [`WGLChecker`].
+///
+/// While searching for a linearization, the checker caches each partial
+/// linearization that it explores, along with the corresponding state of the
+/// object, so that it never explores the same one twice. By default, this
+/// cache grows without bound. For large histories, a capacity can be set,
+/// in which case the least-recently used entries are evicted once the cache
+/// is full. Evicting entries never changes the result of a check, but may
+/// cause the checker to repeat work that it has already done. If the capacity
+/// is much smaller than the number of partial linearizations that the checker
+/// explores, the amount of repeated work can grow exponentially.
+///
+/// When checking a history of a [`PartitionedSpecification`], each partition
+/// is checked one at a time by default. A number of threads can be set, in
+/// which case partitions are checked in parallel.
+///
+/// Some histories cause the search to take exponential time. To bound the
+/// running time of a check, a timeout and a limit on the number of states
+/// that the checker expands can be set. A check that exceeds either of them
+/// gives up, and reports that it could not determine whether the history is
+/// linearizable. Unlike a timeout, the limit on expansions is deterministic,
+/// and so gives the same result on every run. See
+/// [`WGLChecker::check_with_config`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::BuildHasherDefault;
+///
+/// use todc_utils::linearizability::{CheckerConfig, WGLChecker};
+/// use todc_utils::specifications::register::RegisterSpecification;
+/// use todc_utils::{Action::{Call, Response}, History};
+/// use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+///
+/// let config = CheckerConfig::new()
+///     .with_capacity(1_000)
+///     .with_hasher(BuildHasherDefault::<DefaultHasher>::default());
+///
+/// let history = History::from_actions(vec![
+///     (0, Call(Write(1))),
+///     (0, Response(Write(1))),
+///     (1, Call(Read(None))),
+///     (1, Response(Read(Some(1)))),
+/// ]);
+/// assert!(WGLChecker::<RegisterSpecification<u32>>::is_linearizable_with_config(
+///     history, &config
+/// ));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CheckerConfig<H = RandomState> {
+    capacity: Option<usize>,
+    hasher: H,
+    threads: usize,
+    timeout: Option<Duration>,
+    expansions: Option<usize>,
+    real_time: Option<Duration>,
+}
+
+impl CheckerConfig {
+    /// Creates a configuration with an unbounded cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<H: BuildHasher + Clone> CheckerConfig<H> {
+    /// Limits the cache to at most `capacity` entries.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..self
+        }
+    }
+
+    /// Uses a different hasher for entries in the cache.
+    pub fn with_hasher<G: BuildHasher + Clone>(self, hasher: G) -> CheckerConfig<G> {
+        CheckerConfig {
+            capacity: self.capacity,
+            hasher,
+            threads: self.threads,
+            timeout: self.timeout,
+            expansions: self.expansions,
+            real_time: self.real_time,
+        }
+    }
+
+    /// Checks up to `threads` partitions of a history in parallel.
+    pub fn with_threads(self, threads: usize) -> Self {
+        Self { threads, ..self }
+    }
+
+    /// Gives up on a check once it has run for longer than `timeout`.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Gives up on a check once it has expanded more than `expansions`
+    /// states of the object.
+    ///
+    /// A state is expanded each time the checker applies an operation to it,
+    /// in order to find the states that could follow.
+    pub fn with_expansions(self, expansions: usize) -> Self {
+        Self {
+            expansions: Some(expansions),
+            ..self
+        }
+    }
+
+    /// Orders the operations of histories that have timestamps by those
+    /// timestamps, rather than by the order of their entries, and considers
+    /// an operation to precede another only if it returned more than
+    /// `tolerance` before the other was called.
+    ///
+    /// A non-zero tolerance accounts for uncertainty in the timestamps, such
+    /// as skew between the clocks of different processes. Histories without
+    /// timestamps are checked as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use todc_utils::linearizability::{CheckerConfig, WGLChecker};
+    /// use todc_utils::specifications::register::RegisterSpecification;
+    /// use todc_utils::{Action::{Call, Response}, History};
+    /// use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+    ///
+    /// type Checker = WGLChecker<RegisterSpecification<u32>>;
+    ///
+    /// // The read returns 1ms before the write is called, so it cannot
+    /// // observe the write, unless the timestamps are off by at least 1ms.
+    /// let history = History::from_actions(vec![
+    ///     (1, Call(Read(None))),
+    ///     (1, Response(Read(Some(1)))),
+    ///     (0, Call(Write(1))),
+    ///     (0, Response(Write(1))),
+    /// ])
+    /// .with_timestamps([0, 2, 3, 4].into_iter().map(Duration::from_millis).collect());
+    ///
+    /// let config = CheckerConfig::new().with_real_time(Duration::ZERO);
+    /// assert!(!Checker::is_linearizable_with_config(history.clone(), &config));
+    ///
+    /// let config = CheckerConfig::new().with_real_time(Duration::from_millis(1));
+    /// assert!(Checker::is_linearizable_with_config(history, &config));
+    /// ```
+    pub fn with_real_time(self, tolerance: Duration) -> Self {
+        Self {
+            real_time: Some(tolerance),
+            ..self
+        }
+    }
+}
+
+/// The result of checking whether a history is linearizable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckResult {
+    /// The history is linearizable.
+    Linearizable,
+    /// The history is not linearizable.
+    NotLinearizable,
+    /// The checker gave up before determining whether the history is
+    /// linearizable.
+    Unknown(Exhausted),
+}
+
+impl CheckResult {
+    /// Returns whether the history is known to be linearizable.
+    pub fn is_linearizable(&self) -> bool {
+        matches!(self, Self::Linearizable)
+    }
+
+    /// Returns whether the checker gave up before determining whether the
+    /// history is linearizable.
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, Self::Unknown(_))
+    }
+
+    /// Returns whether the history is linearizable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the checker gave up before determining whether the history
+    /// is linearizable.
+    fn unwrap(self) -> bool {
+        match self {
+            Self::Linearizable => true,
+            Self::NotLinearizable => false,
+            Self::Unknown(exhausted) => {
+                panic!("Could not determine whether history is linearizable: {exhausted:?}")
+            }
+        }
+    }
+}
+
+/// A limit, set by a [`CheckerConfig`], that was exceeded during a check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exhausted {
+    /// The check ran for longer than the timeout.
+    Timeout,
+    /// The check expanded more states than the limit on expansions.
+    Expansions,
+}
 
 /// A linearizability checker.
 ///
@@ -28,7 +258,8 @@ pub mod history;
 ///
 /// # Examples
 ///
-/// Consider the following [`Specification`] of a register containing `u32` values.
+/// Consider the following [`Specification`](crate::specifications::Specification) of a register
+/// containing `u32` values.
 ///
 /// ```
 /// use todc_utils::specifications::Specification;
@@ -170,67 +401,496 @@ pub mod history;
 ///
 /// For an implementation in C++, see [`linearizability-checker`](https://github.com/ahorn/linearizability-checker).
 /// For an implementation in Go, see [`porcupine`](https://github.com/anishathalye/porcupine).
-pub struct WGLChecker<S: Specification> {
+pub struct WGLChecker<S: NondeterministicSpecification> {
     data_type: PhantomData<S>,
 }
 
-type OperationEntry<S> = Entry<<S as Specification>::Operation>;
+/// A linearized call, along with the state of the object before it was
+/// linearized and the states that could follow it which have not been tried.
 type OperationCall<S> = (
-    (OperationEntry<S>, OperationEntry<S>),
-    <S as Specification>::State,
+    EntryId,
+    <S as NondeterministicSpecification>::State,
+    Successors<S>,
 );
 
-impl<S: Specification> WGLChecker<S> {
+type Successors<S> = <<S as NondeterministicSpecification>::Successors as IntoIterator>::IntoIter;
+
+impl<S: NondeterministicSpecification> WGLChecker<S> {
     /// Returns whether the history of operations is linearizable with respect to the specification.
-    pub fn is_linearizable(mut history: History<S::Operation>) -> bool {
-        let mut state = S::init();
-        let mut linearized = vec![false; history.len()];
+    pub fn is_linearizable(history: History<S::Operation>) -> bool {
+        Self::is_linearizable_with_config(history, &CheckerConfig::new())
+    }
+
+    /// Returns whether the history of operations is linearizable with respect to the
+    /// specification, using the given configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the check exceeds the timeout or limit on expansions set by
+    /// the configuration. To handle that case, use
+    /// [`check_with_config`](Self::check_with_config) instead.
+    pub fn is_linearizable_with_config<H: BuildHasher + Clone>(
+        history: History<S::Operation>,
+        config: &CheckerConfig<H>,
+    ) -> bool {
+        Self::check_with_config(history, config).unwrap()
+    }
+
+    /// Checks whether the history of operations is linearizable with respect
+    /// to the specification, using the given configuration.
+    ///
+    /// If the check exceeds the timeout or limit on expansions set by the
+    /// configuration, it gives up and returns [`CheckResult::Unknown`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::linearizability::{CheckResult, CheckerConfig, Exhausted, WGLChecker};
+    /// use todc_utils::specifications::register::RegisterSpecification;
+    /// use todc_utils::{Action::{Call, Response}, History};
+    /// use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+    ///
+    /// type Checker = WGLChecker<RegisterSpecification<u32>>;
+    ///
+    /// let history = History::from_actions(vec![
+    ///     (0, Call(Write(1))),
+    ///     (0, Response(Write(1))),
+    ///     (1, Call(Read(None))),
+    ///     (1, Response(Read(Some(1)))),
+    /// ]);
+    ///
+    /// let config = CheckerConfig::new().with_expansions(1);
+    /// assert_eq!(
+    ///     Checker::check_with_config(history.clone(), &config),
+    ///     CheckResult::Unknown(Exhausted::Expansions)
+    /// );
+    ///
+    /// let config = CheckerConfig::new().with_expansions(100);
+    /// assert_eq!(
+    ///     Checker::check_with_config(history, &config),
+    ///     CheckResult::Linearizable
+    /// );
+    /// ```
+    pub fn check_with_config<H: BuildHasher + Clone>(
+        history: History<S::Operation>,
+        config: &CheckerConfig<H>,
+    ) -> CheckResult {
+        let deadline = config.timeout.map(|timeout| Instant::now() + timeout);
+        Self::check_until(history, config, deadline)
+    }
+
+    /// Returns a sub-history of a history that is not linearizable, which is
+    /// also not linearizable, but from which no single operation can be
+    /// removed without it becoming linearizable.
+    ///
+    /// Histories produced by randomized tests can contain thousands of
+    /// operations, most of which have nothing to do with the violation. The
+    /// shrunk history contains only the operations that are needed to
+    /// reproduce it. See [`History::shrink`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the history is linearizable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::linearizability::WGLChecker;
+    /// use todc_utils::specifications::register::RegisterSpecification;
+    /// use todc_utils::{Action::{Call, Response}, History};
+    /// use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+    ///
+    /// type Checker = WGLChecker<RegisterSpecification<u32>>;
+    ///
+    /// // P0 |--------|            Write(1)
+    /// // P1   |---|               Read(0)
+    /// // P2            |--------| Read(0)
+    /// let history = History::from_actions(vec![
+    ///     (0, Call(Write(1))),
+    ///     (1, Call(Read(None))),
+    ///     (1, Response(Read(Some(0)))),
+    ///     (0, Response(Write(1))),
+    ///     (2, Call(Read(None))),
+    ///     (2, Response(Read(Some(0)))),
+    /// ]);
+    ///
+    /// // The read by P1 is not needed to show that the history is not
+    /// // linearizable.
+    /// let shrunk = Checker::shrink(history);
+    /// assert_eq!(shrunk.len(), 4);
+    /// assert!(!Checker::is_linearizable(shrunk));
+    /// ```
+    pub fn shrink(history: History<S::Operation>) -> History<S::Operation> {
+        Self::shrink_with_config(history, &CheckerConfig::new())
+    }
+
+    /// Returns a sub-history of a history that is not linearizable, which is
+    /// also not linearizable, but from which no single operation can be
+    /// removed without it becoming linearizable, using the given
+    /// configuration.
+    ///
+    /// An operation is only removed if the checker determines that the
+    /// history without it is still not linearizable. If a check gives up,
+    /// then the operation is kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the history is linearizable, or if the checker gives up
+    /// before determining that it is not.
+    pub fn shrink_with_config<H: BuildHasher + Clone>(
+        history: History<S::Operation>,
+        config: &CheckerConfig<H>,
+    ) -> History<S::Operation> {
+        history.shrink(|history| {
+            Self::check_with_config(history.clone(), config) == CheckResult::NotLinearizable
+        })
+    }
+
+    /// Checks whether the history of operations is linearizable, giving up
+    /// if the check is still running at the deadline.
+    fn check_until<H: BuildHasher + Clone>(
+        mut history: History<S::Operation>,
+        config: &CheckerConfig<H>,
+        deadline: Option<Instant>,
+    ) -> CheckResult {
+        if let Some(tolerance) = config.real_time {
+            history = history.ordered_by_timestamps(tolerance);
+        }
+        match Self::search(history, S::initial_state(), config, deadline, false) {
+            Ok(states) if states.is_empty() => CheckResult::NotLinearizable,
+            Ok(_) => CheckResult::Linearizable,
+            Err(reason) => CheckResult::Unknown(reason),
+        }
+    }
+
+    /// Searches for linearizations of the history that begin with the object
+    /// in the given state, and returns the states that they leave the object
+    /// in.
+    ///
+    /// Unless `exhaustive` is true, the search stops at the first
+    /// linearization that it finds. Otherwise, the returned states include
+    /// that of every linearization of the history.
+    fn search<H: BuildHasher + Clone>(
+        mut history: History<S::Operation>,
+        initial: S::State,
+        config: &CheckerConfig<H>,
+        deadline: Option<Instant>,
+        exhaustive: bool,
+    ) -> Result<Vec<S::State>, Exhausted> {
+        let mut found: Vec<S::State> = Vec::new();
+        let mut expansions: usize = 0;
+        let mut state = initial;
+        let mut linearized = Bitset::new(history.len());
         let mut calls: Vec<OperationCall<S>> = Vec::new();
-        let mut cache: HashSet<(Vec<bool>, S::State)> = HashSet::new();
-        let mut curr = 0;
+        let mut cache: Cache<(Bitset, S::State), H> =
+            Cache::new(config.capacity, config.hasher.clone());
+        let mut curr = history.first();
+        // After backtracking, the states that could follow the call at `curr`
+        // which have not been tried yet.
+        let mut untried: Option<Successors<S>> = None;
         loop {
-            if history.is_empty() {
-                return true;
-            }
-            match &history[curr] {
-                Entry::Call(call) => match &history[history.index_of_id(call.response)] {
-                    Entry::Call(_) => panic!("Response cannot be a call entry"),
-                    Entry::Response(response) => {
-                        let (is_valid, new_state) = S::apply(&response.operation, &state);
-                        let mut changed = false;
-                        if is_valid {
-                            let mut tmp_linearized = linearized.clone();
-                            tmp_linearized[call.id] = true;
-                            changed = cache.insert((tmp_linearized, new_state.clone()));
+            let backtrack = match curr {
+                // Every remaining operation that took effect is followed by
+                // its response, at which the search backtracks. Reaching the
+                // end of the history means that each remaining operation, if
+                // any, may never have taken effect.
+                None => {
+                    found.push(state.clone());
+                    if !exhaustive {
+                        return Ok(found);
+                    }
+                    true
+                }
+                Some(id) => match &history[id] {
+                    Entry::Call(call) => match &history[call.response] {
+                        Entry::Call(_) => panic!("Response cannot be a call entry"),
+                        // Operations that failed are never linearized.
+                        Entry::Response(response) if response.outcome == Outcome::Failed => {
+                            curr = history.next(id);
+                            false
                         }
-                        if changed {
-                            linearized[call.id] = true;
-                            let call = history.lift(curr);
-                            calls.push((call, state));
-                            state = new_state;
-                            curr = 0;
-                        } else {
-                            curr += 1;
+                        Entry::Response(response) => {
+                            let mut successors = match untried.take() {
+                                Some(successors) => successors,
+                                None => {
+                                    expansions += 1;
+                                    if config.expansions.is_some_and(|limit| expansions > limit) {
+                                        return Err(Exhausted::Expansions);
+                                    }
+                                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                                        return Err(Exhausted::Timeout);
+                                    }
+                                    S::successors(&response.operation, &state).into_iter()
+                                }
+                            };
+                            linearized.insert(id);
+                            let chosen = successors.by_ref().find(|successor| {
+                                cache.insert((linearized.clone(), successor.clone()))
+                            });
+                            match chosen {
+                                Some(successor) => {
+                                    history.lift(id);
+                                    let old_state = std::mem::replace(&mut state, successor);
+                                    calls.push((id, old_state, successors));
+                                    curr = history.first();
+                                }
+                                None => {
+                                    linearized.remove(id);
+                                    curr = history.next(id);
+                                }
+                            }
+                            false
                         }
-                    }
+                    },
+                    Entry::Response(response) => match response.outcome {
+                        Outcome::Completed => true,
+                        // The response of an operation that may not have
+                        // taken effect does not constrain when it is
+                        // linearized, as if it returned after every other
+                        // operation.
+                        Outcome::Failed | Outcome::Indeterminate => {
+                            curr = history.next(id);
+                            false
+                        }
+                    },
                 },
-                Entry::Response(_) => match calls.pop() {
-                    None => return false,
-                    Some(((call, response), old_state)) => {
+            };
+            if backtrack {
+                match calls.pop() {
+                    None => return Ok(found),
+                    Some((call, old_state, successors)) => {
                         state = old_state;
-                        linearized[call.id()] = false;
-                        let (call_index, _) = history.unlift(call, response);
-                        curr = call_index + 1;
+                        linearized.remove(call);
+                        history.unlift(call);
+                        untried = Some(successors);
+                        curr = Some(call);
                     }
-                },
+                }
+            }
+        }
+    }
+}
+
+impl<S: PartitionedSpecification> WGLChecker<S>
+where
+    S::Operation: Send,
+{
+    /// Returns whether the history of operations is linearizable with respect to the
+    /// specification, by checking the operations in each partition separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::BTreeMap;
+    /// # use todc_utils::specifications::{PartitionedSpecification, Specification};
+    /// # #[derive(Copy, Clone, Debug)]
+    /// # enum StoreOp {
+    /// #     Get(u32, Option<u32>),
+    /// #     Put(u32, u32),
+    /// # }
+    /// # use StoreOp::{Get, Put};
+    /// # struct StoreSpec;
+    /// # impl Specification for StoreSpec {
+    /// #     type State = BTreeMap<u32, u32>;
+    /// #     type Operation = StoreOp;
+    /// #     fn init() -> Self::State {
+    /// #         BTreeMap::new()
+    /// #     }
+    /// #     fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+    /// #         match operation {
+    /// #             Get(key, value) => (state.get(key) == value.as_ref(), state.clone()),
+    /// #             Put(key, value) => {
+    /// #                 let mut state = state.clone();
+    /// #                 state.insert(*key, *value);
+    /// #                 (true, state)
+    /// #             }
+    /// #         }
+    /// #     }
+    /// # }
+    /// # impl PartitionedSpecification for StoreSpec {
+    /// #     type Key = u32;
+    /// #     fn partition(operation: &Self::Operation) -> Self::Key {
+    /// #         match operation {
+    /// #             Get(key, _) | Put(key, _) => *key,
+    /// #         }
+    /// #     }
+    /// # }
+    /// use todc_utils::{Action::{Call, Response}, History, WGLChecker};
+    ///
+    /// // P0 |--------------|  Put(1, 1)
+    /// // P1  |--------------| Put(2, 2)
+    /// // P2    |---|          Get(2, Some(2))
+    /// // P3           |---|   Get(1, None)
+    /// let history = History::from_actions(vec![
+    ///     (0, Call(Put(1, 1))),
+    ///     (1, Call(Put(2, 2))),
+    ///     (2, Call(Get(2, None))),
+    ///     (2, Response(Get(2, Some(2)))),
+    ///     (3, Call(Get(1, None))),
+    ///     (3, Response(Get(1, None))),
+    ///     (0, Response(Put(1, 1))),
+    ///     (1, Response(Put(2, 2))),
+    /// ]);
+    /// assert!(WGLChecker::<StoreSpec>::is_linearizable_partitioned(history));
+    /// ```
+    pub fn is_linearizable_partitioned(history: History<S::Operation>) -> bool {
+        Self::is_linearizable_partitioned_with_config(history, &CheckerConfig::new())
+    }
+
+    /// Returns whether the history of operations is linearizable with respect to the
+    /// specification, by checking the operations in each partition separately and
+    /// using the given configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the check exceeds the timeout or limit on expansions set by
+    /// the configuration. To handle that case, use
+    /// [`check_partitioned_with_config`](Self::check_partitioned_with_config)
+    /// instead.
+    pub fn is_linearizable_partitioned_with_config<H: BuildHasher + Clone + Sync>(
+        history: History<S::Operation>,
+        config: &CheckerConfig<H>,
+    ) -> bool {
+        Self::check_partitioned_with_config(history, config).unwrap()
+    }
+
+    /// Checks whether the history of operations is linearizable with respect
+    /// to the specification, by checking the operations in each partition
+    /// separately and using the given configuration.
+    ///
+    /// The timeout set by the configuration applies to the check as a whole,
+    /// while the limit on expansions applies to each partition separately.
+    /// The history is not linearizable if any partition is found not to be,
+    /// even if the checker gave up on some other partition.
+    pub fn check_partitioned_with_config<H: BuildHasher + Clone + Sync>(
+        history: History<S::Operation>,
+        config: &CheckerConfig<H>,
+    ) -> CheckResult {
+        let deadline = config.timeout.map(|timeout| Instant::now() + timeout);
+        let parts = history.partition(S::partition);
+        Self::check_parts(parts, config, deadline)
+    }
+}
+
+impl<S: NondeterministicSpecification> WGLChecker<S>
+where
+    S::Operation: Send,
+{
+    /// Checks whether each of the independent sub-histories is linearizable,
+    /// using as many threads as the configuration allows.
+    ///
+    /// The history as a whole is linearizable if and only if every
+    /// sub-history is.
+    fn check_parts<H: BuildHasher + Clone + Sync>(
+        parts: Vec<History<S::Operation>>,
+        config: &CheckerConfig<H>,
+        deadline: Option<Instant>,
+    ) -> CheckResult {
+        let threads = config.threads.min(parts.len());
+        if threads <= 1 {
+            let mut result = CheckResult::Linearizable;
+            for part in parts {
+                match Self::check_until(part, config, deadline) {
+                    CheckResult::Linearizable => {}
+                    CheckResult::NotLinearizable => return CheckResult::NotLinearizable,
+                    unknown => result = unknown,
+                }
+            }
+            return result;
+        }
+
+        // Each thread repeatedly checks the next unchecked partition, until
+        // none remain or some partition is found not to be linearizable.
+        let parts = Mutex::new(parts.into_iter());
+        let failed = AtomicBool::new(false);
+        let exhausted = Mutex::new(None);
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| {
+                    while !failed.load(Ordering::Relaxed) {
+                        let part = match parts.lock().unwrap().next() {
+                            Some(part) => part,
+                            None => break,
+                        };
+                        match Self::check_until(part, config, deadline) {
+                            CheckResult::Linearizable => {}
+                            CheckResult::NotLinearizable => failed.store(true, Ordering::Relaxed),
+                            CheckResult::Unknown(reason) => {
+                                *exhausted.lock().unwrap() = Some(reason);
+                            }
+                        }
+                    }
+                });
             }
+        });
+        match (failed.into_inner(), exhausted.into_inner().unwrap()) {
+            (true, _) => CheckResult::NotLinearizable,
+            (false, Some(reason)) => CheckResult::Unknown(reason),
+            (false, None) => CheckResult::Linearizable,
         }
     }
 }
 
+impl<K, S> WGLChecker<MultiObjectSpecification<K, S>>
+where
+    K: Clone + Debug + Hash + Ord + Send,
+    S: Specification,
+    S::Operation: Send,
+{
+    /// Returns whether the history of operations on a collection of objects
+    /// is linearizable, by checking each group of objects that are related
+    /// by transactions separately.
+    ///
+    /// See the [`multi_object`](crate::specifications::multi_object) module
+    /// for examples.
+    pub fn is_linearizable_multi_object(
+        history: History<multi_object::MultiObjectOperation<K, S::Operation>>,
+    ) -> bool {
+        Self::is_linearizable_multi_object_with_config(history, &CheckerConfig::new())
+    }
+
+    /// Returns whether the history of operations on a collection of objects
+    /// is linearizable, by checking each group of objects that are related
+    /// by transactions separately and using the given configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the check exceeds the timeout or limit on expansions set by
+    /// the configuration. To handle that case, use
+    /// [`check_multi_object_with_config`](Self::check_multi_object_with_config)
+    /// instead.
+    pub fn is_linearizable_multi_object_with_config<H: BuildHasher + Clone + Sync>(
+        history: History<multi_object::MultiObjectOperation<K, S::Operation>>,
+        config: &CheckerConfig<H>,
+    ) -> bool {
+        Self::check_multi_object_with_config(history, config).unwrap()
+    }
+
+    /// Checks whether the history of operations on a collection of objects
+    /// is linearizable, using the given configuration.
+    ///
+    /// Two objects are related if some transaction is performed on both of
+    /// them. Operations on objects that are not related, directly or through
+    /// other objects, never affect one another, so the operations on each
+    /// group of related objects are checked separately, as with
+    /// [`check_partitioned_with_config`](Self::check_partitioned_with_config).
+    pub fn check_multi_object_with_config<H: BuildHasher + Clone + Sync>(
+        history: History<multi_object::MultiObjectOperation<K, S::Operation>>,
+        config: &CheckerConfig<H>,
+    ) -> CheckResult {
+        let deadline = config.timeout.map(|timeout| Instant::now() + timeout);
+        let groups = multi_object::object_groups(&history);
+        // Empty transactions touch no objects, and are checked on their own.
+        let parts =
+            history.partition(|operation| operation.objects().next().map(|key| groups[key]));
+        Self::check_parts(parts, config, deadline)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::specifications::Specification;
     use history::Action::*;
 
     #[derive(Copy, Clone, Debug)]
@@ -335,4 +995,563 @@ mod test {
             assert!(!RegisterChecker::is_linearizable(history));
         }
     }
+
+    mod outcomes {
+        use super::*;
+
+        #[test]
+        fn ignores_failed_operations() {
+            // P0 |--| Write(1), failed
+            // P1       |--| Read(0)
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (0, Failed(Write(1))),
+                (1, Call(Read(0))),
+                (1, Response(Read(0))),
+            ]);
+            assert!(RegisterChecker::is_linearizable(history));
+        }
+
+        #[test]
+        fn rejects_read_of_failed_write() {
+            // P0 |--------| Write(1), failed
+            // P1   |--|     Read(1)
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (1, Call(Read(1))),
+                (1, Response(Read(1))),
+                (0, Failed(Write(1))),
+            ]);
+            assert!(!RegisterChecker::is_linearizable(history));
+        }
+
+        #[test]
+        fn accepts_indeterminate_write_that_never_took_effect() {
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (0, Indeterminate(Write(1))),
+                (1, Call(Read(0))),
+                (1, Response(Read(0))),
+            ]);
+            assert!(RegisterChecker::is_linearizable(history));
+        }
+
+        #[test]
+        fn accepts_indeterminate_write_that_took_effect_after_it_returned() {
+            // P0 |--|             Write(1), indeterminate
+            // P1       |--|       Read(0)
+            // P1             |--| Read(1)
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (0, Indeterminate(Write(1))),
+                (1, Call(Read(0))),
+                (1, Response(Read(0))),
+                (1, Call(Read(1))),
+                (1, Response(Read(1))),
+            ]);
+            assert!(RegisterChecker::is_linearizable(history));
+        }
+
+        #[test]
+        fn rejects_indeterminate_write_that_took_effect_before_it_was_called() {
+            // P0       |--| Write(1), indeterminate
+            // P1 |--|       Read(1)
+            let history = History::from_actions(vec![
+                (1, Call(Read(1))),
+                (1, Response(Read(1))),
+                (0, Call(Write(1))),
+                (0, Indeterminate(Write(1))),
+            ]);
+            assert!(!RegisterChecker::is_linearizable(history));
+        }
+
+        #[test]
+        fn rejects_indeterminate_write_that_took_effect_twice() {
+            // P0 |--|             Write(1), indeterminate
+            // P1       |--|       Read(1)
+            // P2       |--|       Write(2)
+            // P1             |--| Read(1)
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (0, Indeterminate(Write(1))),
+                (1, Call(Read(1))),
+                (1, Response(Read(1))),
+                (2, Call(Write(2))),
+                (2, Response(Write(2))),
+                (1, Call(Read(1))),
+                (1, Response(Read(1))),
+            ]);
+            assert!(!RegisterChecker::is_linearizable(history));
+        }
+    }
+
+    mod is_linearizable_with_config {
+        use super::*;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::BuildHasherDefault;
+
+        // P0 |--------------------| Write(1)
+        // P1 |--------------------| Write(2)
+        // P2 |--------------------| Write(3)
+        // P3   |--|                 Read(3)
+        // P3          |--|          Read(2)
+        // P3                 |--|   Read(x)
+        fn writes_in_reverse_order(x: u32) -> History<RegisterOperation> {
+            History::from_actions(vec![
+                (0, Call(Write(1))),
+                (1, Call(Write(2))),
+                (2, Call(Write(3))),
+                (3, Call(Read(3))),
+                (3, Response(Read(3))),
+                (3, Call(Read(2))),
+                (3, Response(Read(2))),
+                (3, Call(Read(x))),
+                (3, Response(Read(x))),
+                (0, Response(Write(1))),
+                (1, Response(Write(2))),
+                (2, Response(Write(3))),
+            ])
+        }
+
+        #[test]
+        fn gives_same_result_with_bounded_cache() {
+            for capacity in [0, 1, 2, 10] {
+                let config = CheckerConfig::new().with_capacity(capacity);
+                let history = writes_in_reverse_order(1);
+                assert!(RegisterChecker::is_linearizable_with_config(
+                    history, &config
+                ));
+                let history = writes_in_reverse_order(0);
+                assert!(!RegisterChecker::is_linearizable_with_config(
+                    history, &config
+                ));
+            }
+        }
+
+        #[test]
+        fn accepts_custom_hasher() {
+            let config =
+                CheckerConfig::new().with_hasher(BuildHasherDefault::<DefaultHasher>::default());
+            let history = writes_in_reverse_order(1);
+            assert!(RegisterChecker::is_linearizable_with_config(
+                history, &config
+            ));
+        }
+    }
+
+    mod with_real_time {
+        use super::*;
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::BuildHasherDefault;
+
+        // A read of 1 from 10ms to 12ms, and a write of 1 that starts at
+        // `write_at`ms and takes 2ms. The entries are in the order that the
+        // actions were recorded, which places the read first.
+        fn read_recorded_before_write(write_at: u64) -> History<RegisterOperation> {
+            History::from_actions(vec![
+                (1, Call(Read(1))),
+                (1, Response(Read(1))),
+                (0, Call(Write(1))),
+                (0, Response(Write(1))),
+            ])
+            .with_timestamps(
+                [10, 12, write_at, write_at + 2]
+                    .into_iter()
+                    .map(Duration::from_millis)
+                    .collect(),
+            )
+        }
+
+        #[test]
+        fn orders_operations_by_timestamps() {
+            let history = read_recorded_before_write(0);
+            assert!(!RegisterChecker::is_linearizable(history.clone()));
+
+            let config = CheckerConfig::new().with_real_time(Duration::ZERO);
+            assert!(RegisterChecker::is_linearizable_with_config(
+                history, &config
+            ));
+        }
+
+        #[test]
+        fn rejects_read_that_returned_before_write_was_called() {
+            let config = CheckerConfig::new().with_real_time(Duration::from_millis(1));
+            let history = read_recorded_before_write(14);
+            assert!(!RegisterChecker::is_linearizable_with_config(
+                history, &config
+            ));
+        }
+
+        #[test]
+        fn accepts_read_that_returned_within_tolerance_of_write() {
+            let config = CheckerConfig::new().with_real_time(Duration::from_millis(2));
+            let history = read_recorded_before_write(14);
+            assert!(RegisterChecker::is_linearizable_with_config(
+                history, &config
+            ));
+        }
+
+        #[test]
+        fn is_preserved_by_custom_hasher() {
+            let config = CheckerConfig::new()
+                .with_real_time(Duration::ZERO)
+                .with_hasher(BuildHasherDefault::<DefaultHasher>::default());
+            let history = read_recorded_before_write(0);
+            assert!(RegisterChecker::is_linearizable_with_config(
+                history, &config
+            ));
+        }
+    }
+
+    mod check_with_config {
+        use super::*;
+
+        fn sequential_write_and_read() -> History<RegisterOperation> {
+            History::from_actions(vec![
+                (0, Call(Write(1))),
+                (0, Response(Write(1))),
+                (1, Call(Read(1))),
+                (1, Response(Read(1))),
+            ])
+        }
+
+        #[test]
+        fn gives_up_after_expansions() {
+            let config = CheckerConfig::new().with_expansions(1);
+            assert_eq!(
+                RegisterChecker::check_with_config(sequential_write_and_read(), &config),
+                CheckResult::Unknown(Exhausted::Expansions)
+            );
+        }
+
+        #[test]
+        fn gives_up_after_timeout() {
+            let config = CheckerConfig::new().with_timeout(Duration::ZERO);
+            assert_eq!(
+                RegisterChecker::check_with_config(sequential_write_and_read(), &config),
+                CheckResult::Unknown(Exhausted::Timeout)
+            );
+        }
+
+        #[test]
+        fn completes_within_limits() {
+            let config = CheckerConfig::new()
+                .with_expansions(2)
+                .with_timeout(Duration::from_secs(60));
+            assert_eq!(
+                RegisterChecker::check_with_config(sequential_write_and_read(), &config),
+                CheckResult::Linearizable
+            );
+        }
+
+        #[test]
+        fn reports_histories_that_are_not_linearizable() {
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (0, Response(Write(1))),
+                (1, Call(Read(2))),
+                (1, Response(Read(2))),
+            ]);
+            let config = CheckerConfig::new().with_expansions(100);
+            assert_eq!(
+                RegisterChecker::check_with_config(history, &config),
+                CheckResult::NotLinearizable
+            );
+        }
+
+        #[test]
+        #[should_panic(expected = "Could not determine")]
+        fn is_linearizable_with_config_panics_if_exhausted() {
+            let config = CheckerConfig::new().with_expansions(0);
+            RegisterChecker::is_linearizable_with_config(sequential_write_and_read(), &config);
+        }
+    }
+
+    mod is_linearizable_partitioned {
+        use super::*;
+        use std::collections::BTreeMap;
+
+        struct KeyedRegisterSpec;
+
+        impl Specification for KeyedRegisterSpec {
+            type State = BTreeMap<u32, u32>;
+            type Operation = (u32, RegisterOperation);
+
+            fn init() -> Self::State {
+                BTreeMap::new()
+            }
+
+            fn apply(
+                (key, operation): &Self::Operation,
+                state: &Self::State,
+            ) -> (bool, Self::State) {
+                let value = state.get(key).copied().unwrap_or_default();
+                let (is_valid, value) = IntegerRegisterSpec::apply(operation, &value);
+                let mut state = state.clone();
+                state.insert(*key, value);
+                (is_valid, state)
+            }
+        }
+
+        impl PartitionedSpecification for KeyedRegisterSpec {
+            type Key = u32;
+
+            fn partition((key, _): &Self::Operation) -> Self::Key {
+                *key
+            }
+        }
+
+        type KeyedChecker = WGLChecker<KeyedRegisterSpec>;
+
+        // Each key has the following history, in which the writes must be
+        // linearized in the reverse order that they are called.
+        // P0 |--------------------| Write(1)
+        // P1 |--------------------| Write(2)
+        // P2   |--|                 Read(2)
+        // P2          |--|          Read(x)
+        fn history(keys: u32, x: u32) -> History<(u32, RegisterOperation)> {
+            let mut actions = Vec::new();
+            for key in 0..keys {
+                let p = 3 * key as usize;
+                actions.extend([
+                    (p, Call((key, Write(1)))),
+                    (p + 1, Call((key, Write(2)))),
+                    (p + 2, Call((key, Read(2)))),
+                    (p + 2, Response((key, Read(2)))),
+                    (p + 2, Call((key, Read(x)))),
+                    (p + 2, Response((key, Read(x)))),
+                ]);
+            }
+            for key in 0..keys {
+                let p = 3 * key as usize;
+                actions.extend([
+                    (p, Response((key, Write(1)))),
+                    (p + 1, Response((key, Write(2)))),
+                ]);
+            }
+            History::from_actions(actions)
+        }
+
+        #[test]
+        fn accepts_history_that_is_linearizable_for_each_key() {
+            assert!(KeyedChecker::is_linearizable_partitioned(history(5, 1)));
+        }
+
+        #[test]
+        fn rejects_history_that_is_not_linearizable_for_some_key() {
+            assert!(!KeyedChecker::is_linearizable_partitioned(history(5, 0)));
+        }
+
+        #[test]
+        fn agrees_with_unpartitioned_checker() {
+            for x in [0, 1] {
+                assert_eq!(
+                    KeyedChecker::is_linearizable_partitioned(history(3, x)),
+                    KeyedChecker::is_linearizable(history(3, x))
+                );
+            }
+        }
+
+        #[test]
+        fn limits_expansions_of_each_partition() {
+            for threads in [1, 4] {
+                let config = CheckerConfig::new().with_threads(threads);
+                assert_eq!(
+                    KeyedChecker::check_partitioned_with_config(
+                        history(5, 1),
+                        &config.clone().with_expansions(1_000)
+                    ),
+                    CheckResult::Linearizable
+                );
+                assert_eq!(
+                    KeyedChecker::check_partitioned_with_config(
+                        history(5, 1),
+                        &config.with_expansions(0)
+                    ),
+                    CheckResult::Unknown(Exhausted::Expansions)
+                );
+            }
+        }
+
+        #[test]
+        fn gives_same_result_in_parallel() {
+            let config = CheckerConfig::new().with_threads(4);
+            for x in [0, 1] {
+                assert_eq!(
+                    KeyedChecker::is_linearizable_partitioned_with_config(history(5, x), &config),
+                    x == 1
+                );
+            }
+        }
+    }
+
+    mod is_linearizable_multi_object {
+        use super::*;
+        use crate::specifications::multi_object::MultiObjectOperation;
+        use crate::specifications::multi_object::MultiObjectOperation::{Single, Transaction};
+
+        type MultiObjectChecker = WGLChecker<MultiObjectSpecification<u32, IntegerRegisterSpec>>;
+
+        // P0 |-----------|          Transaction([Write(0, 1), Write(1, 1)])
+        // P1               |---|    Read(0, 1)
+        // P2                 |---|  Read(1, x)
+        fn transaction_then_reads(x: u32) -> History<MultiObjectOperation<u32, RegisterOperation>> {
+            History::from_actions(vec![
+                (0, Call(Transaction(vec![(0, Write(1)), (1, Write(1))]))),
+                (0, Response(Transaction(vec![(0, Write(1)), (1, Write(1))]))),
+                (1, Call(Single(0, Read(1)))),
+                (2, Call(Single(1, Read(x)))),
+                (1, Response(Single(0, Read(1)))),
+                (2, Response(Single(1, Read(x)))),
+            ])
+        }
+
+        #[test]
+        fn accepts_reads_after_transaction() {
+            assert!(MultiObjectChecker::is_linearizable_multi_object(
+                transaction_then_reads(1)
+            ));
+        }
+
+        #[test]
+        fn rejects_read_that_misses_part_of_transaction() {
+            assert!(!MultiObjectChecker::is_linearizable_multi_object(
+                transaction_then_reads(0)
+            ));
+        }
+
+        #[test]
+        fn rejects_transaction_that_observes_partial_transaction() {
+            // P0 |-----------|                 Transaction([Write(0, 1), Write(1, 1)])
+            // P1    |-----------|              Transaction([Read(0, 1), Read(1, 0)])
+            let history = History::from_actions(vec![
+                (0, Call(Transaction(vec![(0, Write(1)), (1, Write(1))]))),
+                (1, Call(Transaction(vec![(0, Read(1)), (1, Read(0))]))),
+                (0, Response(Transaction(vec![(0, Write(1)), (1, Write(1))]))),
+                (1, Response(Transaction(vec![(0, Read(1)), (1, Read(0))]))),
+            ]);
+            assert!(!MultiObjectChecker::is_linearizable_multi_object(history));
+        }
+
+        #[test]
+        fn agrees_with_unpartitioned_checker() {
+            for x in [0, 1] {
+                assert_eq!(
+                    MultiObjectChecker::is_linearizable_multi_object(transaction_then_reads(x)),
+                    MultiObjectChecker::is_linearizable(transaction_then_reads(x))
+                );
+            }
+        }
+
+        #[test]
+        fn accepts_unrelated_objects_and_empty_transactions() {
+            let history = History::from_actions(vec![
+                (0, Call(Single(0, Write(1)))),
+                (1, Call(Single(1, Write(1)))),
+                (0, Response(Single(0, Write(1)))),
+                (1, Response(Single(1, Write(1)))),
+                (2, Call(Transaction(Vec::new()))),
+                (2, Response(Transaction(Vec::new()))),
+            ]);
+            for threads in [1, 4] {
+                let config = CheckerConfig::new().with_threads(threads);
+                assert_eq!(
+                    MultiObjectChecker::check_multi_object_with_config(history.clone(), &config),
+                    CheckResult::Linearizable
+                );
+            }
+        }
+    }
+
+    mod is_linearizable_nondeterministic {
+        use super::*;
+
+        #[derive(Copy, Clone, Debug)]
+        enum UncertainOperation {
+            Read(u32),
+            TimedOutWrite(u32),
+        }
+
+        use UncertainOperation::TimedOutWrite;
+
+        // A register in which writes may time out, without the writer
+        // learning whether they took effect.
+        struct UncertainRegisterSpec;
+
+        impl NondeterministicSpecification for UncertainRegisterSpec {
+            type State = u32;
+            type Operation = UncertainOperation;
+            type Successors = Vec<u32>;
+
+            fn initial_state() -> Self::State {
+                0
+            }
+
+            fn successors(operation: &Self::Operation, state: &Self::State) -> Self::Successors {
+                match operation {
+                    UncertainOperation::Read(value) if value == state => vec![*state],
+                    UncertainOperation::Read(_) => vec![],
+                    TimedOutWrite(value) => vec![*state, *value],
+                }
+            }
+        }
+
+        type UncertainChecker = WGLChecker<UncertainRegisterSpec>;
+
+        #[test]
+        fn accepts_timed_out_write_that_took_effect() {
+            let history = History::from_actions(vec![
+                (0, Call(TimedOutWrite(1))),
+                (0, Response(TimedOutWrite(1))),
+                (1, Call(UncertainOperation::Read(1))),
+                (1, Response(UncertainOperation::Read(1))),
+            ]);
+            assert!(UncertainChecker::is_linearizable(history));
+        }
+
+        #[test]
+        fn accepts_timed_out_write_that_did_not_take_effect() {
+            let history = History::from_actions(vec![
+                (0, Call(TimedOutWrite(1))),
+                (0, Response(TimedOutWrite(1))),
+                (1, Call(UncertainOperation::Read(0))),
+                (1, Response(UncertainOperation::Read(0))),
+            ]);
+            assert!(UncertainChecker::is_linearizable(history));
+        }
+
+        #[test]
+        fn rejects_timed_out_write_that_took_effect_after_later_read() {
+            // P0 |--| TimedOutWrite(1)
+            // P1       |--| Read(1)
+            // P1             |--| Read(0)
+            let history = History::from_actions(vec![
+                (0, Call(TimedOutWrite(1))),
+                (0, Response(TimedOutWrite(1))),
+                (1, Call(UncertainOperation::Read(1))),
+                (1, Response(UncertainOperation::Read(1))),
+                (1, Call(UncertainOperation::Read(0))),
+                (1, Response(UncertainOperation::Read(0))),
+            ]);
+            assert!(!UncertainChecker::is_linearizable(history));
+        }
+
+        #[test]
+        fn tries_each_successor_after_backtracking() {
+            // The first successor of the write leaves the register unchanged,
+            // which only turns out to be wrong once the read by P2 is reached.
+            // P0 |------------| TimedOutWrite(1)
+            // P1  |--|          Read(0)
+            // P2         |--|   Read(1)
+            let history = History::from_actions(vec![
+                (0, Call(TimedOutWrite(1))),
+                (1, Call(UncertainOperation::Read(0))),
+                (1, Response(UncertainOperation::Read(0))),
+                (2, Call(UncertainOperation::Read(1))),
+                (2, Response(UncertainOperation::Read(1))),
+                (0, Response(TimedOutWrite(1))),
+            ]);
+            assert!(UncertainChecker::is_linearizable(history));
+        }
+    }
 }
@@ -0,0 +1,502 @@
+//! Generating random histories for property-based testing with
+//! [`proptest`].
+//!
+//! This module contains [strategies](proptest::strategy::Strategy) that
+//! generate well-formed histories of operations on an object, given its
+//! [`Specification`]. Each history is either _known_ to be linearizable, or
+//! known **not** to be, so that the generated histories can be used to test
+//! changes to a linearizability checker, such as the [`WGLChecker`], at
+//! scale.
+//!
+//! Histories are generated by first choosing a sequence of operations that
+//! is valid according to the specification, and then assigning each of them
+//! to a process, along with a time at which it is called and a time at which
+//! it responds. The interval between the call and response of each operation
+//! always contains the point at which it occurs in the sequence, so the
+//! sequence is a valid linearization of the history. A non-linearizable
+//! history is generated in the same way, except that the response of one of
+//! the operations is then _corrupted_, so that it is invalid, and every
+//! operation before it is made sequential, so that no other linearization can
+//! make it valid.
+//!
+//! Generating histories for an object requires its specification to
+//! implement [`ArbitrarySpecification`], which this module does for the
+//! [register](crate::specifications::register) and
+//! [snapshot](crate::specifications::snapshot) specifications.
+//!
+//! This module requires the `proptest` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use todc_utils::linearizability::arbitrary::{
+//!     linearizable_histories, non_linearizable_histories, HistoryConfig,
+//! };
+//! use todc_utils::specifications::register::RegisterSpecification;
+//! use todc_utils::WGLChecker;
+//!
+//! type Spec = RegisterSpecification<u8>;
+//!
+//! proptest! {
+//!     fn accepts_linearizable_histories(
+//!         history in linearizable_histories::<Spec>(HistoryConfig::new())
+//!     ) {
+//!         prop_assert!(WGLChecker::<Spec>::is_linearizable(history));
+//!     }
+//!
+//!     fn rejects_non_linearizable_histories(
+//!         history in non_linearizable_histories::<Spec>(HistoryConfig::new())
+//!     ) {
+//!         prop_assert!(!WGLChecker::<Spec>::is_linearizable(history));
+//!     }
+//! }
+//! # accepts_linearizable_histories();
+//! # rejects_non_linearizable_histories();
+//! ```
+//!
+//! [`WGLChecker`]: crate::WGLChecker
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::Range;
+
+use proptest::prelude::*;
+use proptest::sample::Index;
+
+use crate::linearizability::history::{Action, History, ProcessId};
+use crate::specifications::register::{RegisterOperation, RegisterSpecification};
+use crate::specifications::snapshot::{SnapshotOperation, SnapshotSpecification};
+use crate::specifications::Specification;
+
+/// A specification of an object for which random operations can be
+/// generated.
+///
+/// Operations are generated in two steps. First, an [`Intent`] is chosen at
+/// random, describing what a process intends to do. Then, once the state of
+/// the object at the moment the operation takes effect is known, the intent
+/// is turned into an operation that is valid in that state, or, optionally,
+/// into one that is not.
+///
+/// [`Intent`]: ArbitrarySpecification::Intent
+pub trait ArbitrarySpecification: Specification {
+    /// A description of an operation that a process intends to perform,
+    /// independent of the state of the object.
+    type Intent: Clone + Debug;
+
+    /// Returns a strategy that generates random intents.
+    fn intents() -> BoxedStrategy<Self::Intent>;
+
+    /// Returns the operation, including its response, that the process
+    /// performs when it carries out its intent in the given state.
+    ///
+    /// Applying the returned operation to the state must be valid.
+    fn operation(process: ProcessId, intent: &Self::Intent, state: &Self::State)
+        -> Self::Operation;
+
+    /// Returns the call of an operation, in which its response is not yet
+    /// known.
+    fn call(operation: &Self::Operation) -> Self::Operation;
+
+    /// Returns an operation, including its response, that is **not** valid
+    /// in the given state, or `None` if the intent cannot be corrupted.
+    ///
+    /// By default, no intent can be corrupted.
+    fn corrupt(
+        _process: ProcessId,
+        _intent: &Self::Intent,
+        _state: &Self::State,
+    ) -> Option<Self::Operation> {
+        None
+    }
+}
+
+/// The intent to read or write a value, for objects such as registers and
+/// snapshots.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccessIntent<T> {
+    /// Read the object. The value is returned in place of the correct
+    /// response if the read is corrupted.
+    Read(T),
+    /// Write the value to the object.
+    Write(T),
+}
+
+impl<T: Arbitrary + 'static> AccessIntent<T> {
+    /// Returns a strategy that generates reads and writes of arbitrary
+    /// values with equal probability.
+    fn arbitrary() -> BoxedStrategy<Self> {
+        prop_oneof![
+            any::<T>().prop_map(AccessIntent::Read),
+            any::<T>().prop_map(AccessIntent::Write),
+        ]
+        .boxed()
+    }
+}
+
+impl<T> ArbitrarySpecification for RegisterSpecification<T>
+where
+    T: Arbitrary + Clone + Debug + Default + Eq + Hash + 'static,
+{
+    type Intent = AccessIntent<T>;
+
+    fn intents() -> BoxedStrategy<Self::Intent> {
+        AccessIntent::arbitrary()
+    }
+
+    fn operation(_: ProcessId, intent: &Self::Intent, state: &T) -> Self::Operation {
+        match intent {
+            AccessIntent::Read(_) => RegisterOperation::Read(Some(state.clone())),
+            AccessIntent::Write(value) => RegisterOperation::Write(value.clone()),
+        }
+    }
+
+    fn call(operation: &Self::Operation) -> Self::Operation {
+        match operation {
+            RegisterOperation::Read(_) => RegisterOperation::Read(None),
+            RegisterOperation::Write(value) => RegisterOperation::Write(value.clone()),
+        }
+    }
+
+    fn corrupt(_: ProcessId, intent: &Self::Intent, state: &T) -> Option<Self::Operation> {
+        match intent {
+            AccessIntent::Read(value) if value != state => {
+                Some(RegisterOperation::Read(Some(value.clone())))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<T, const N: usize> ArbitrarySpecification for SnapshotSpecification<T, N>
+where
+    T: Arbitrary + Clone + Debug + Default + Eq + Hash + 'static,
+{
+    /// Reads are scans, and writes are updates of the component belonging to
+    /// the process.
+    type Intent = AccessIntent<T>;
+
+    fn intents() -> BoxedStrategy<Self::Intent> {
+        AccessIntent::arbitrary()
+    }
+
+    fn operation(process: ProcessId, intent: &Self::Intent, state: &[T; N]) -> Self::Operation {
+        match intent {
+            AccessIntent::Read(_) => SnapshotOperation::Scan(process, Some(state.clone())),
+            AccessIntent::Write(value) => SnapshotOperation::Update(process, value.clone()),
+        }
+    }
+
+    fn call(operation: &Self::Operation) -> Self::Operation {
+        match operation {
+            SnapshotOperation::Scan(process, _) => SnapshotOperation::Scan(*process, None),
+            update => update.clone(),
+        }
+    }
+
+    /// A scan is corrupted by replacing the component belonging to the
+    /// process in its view.
+    fn corrupt(
+        process: ProcessId,
+        intent: &Self::Intent,
+        state: &[T; N],
+    ) -> Option<Self::Operation> {
+        match intent {
+            AccessIntent::Read(value) if *value != state[process] => {
+                let mut view = state.clone();
+                view[process] = value.clone();
+                Some(SnapshotOperation::Scan(process, Some(view)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parameters for the histories generated by [`linearizable_histories`] and
+/// [`non_linearizable_histories`].
+#[derive(Clone, Debug)]
+pub struct HistoryConfig {
+    processes: usize,
+    operations: Range<usize>,
+    concurrency: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            processes: 3,
+            operations: 1..20,
+            concurrency: 2,
+        }
+    }
+}
+
+impl HistoryConfig {
+    /// Creates a configuration for histories of between `1` and `19`
+    /// operations, performed by `3` processes, with a concurrency level of
+    /// `2`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of processes that perform operations.
+    ///
+    /// When generating histories of a [`SnapshotSpecification`] with `N`
+    /// components, the number of processes must be at most `N`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `processes` is zero.
+    pub fn with_processes(self, processes: usize) -> Self {
+        assert!(processes > 0, "A history requires at least one process");
+        Self { processes, ..self }
+    }
+
+    /// Sets the range of the number of operations in each history.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range contains no positive number.
+    pub fn with_operations(self, operations: Range<usize>) -> Self {
+        assert!(
+            operations.end > operations.start.max(1),
+            "A history requires at least one operation"
+        );
+        Self { operations, ..self }
+    }
+
+    /// Sets the concurrency level, which is the largest number of other
+    /// operations that may take effect between the call of an operation and
+    /// the moment it takes effect, and likewise between that moment and its
+    /// response.
+    ///
+    /// With a concurrency level of `0`, every history is sequential.
+    pub fn with_concurrency(self, concurrency: usize) -> Self {
+        Self {
+            concurrency,
+            ..self
+        }
+    }
+}
+
+/// An operation, before it is assigned a time at which it is called and at
+/// which it responds.
+#[derive(Clone, Debug)]
+struct Planned<I> {
+    process: ProcessId,
+    intent: I,
+    before: usize,
+    after: usize,
+}
+
+/// Returns a strategy that generates sequences of planned operations.
+fn planned<S: ArbitrarySpecification>(
+    config: &HistoryConfig,
+) -> impl Strategy<Value = Vec<Planned<S::Intent>>> {
+    let planned = (
+        0..config.processes,
+        S::intents(),
+        0..=config.concurrency,
+        0..=config.concurrency,
+    )
+        .prop_map(|(process, intent, before, after)| Planned {
+            process,
+            intent,
+            before,
+            after,
+        });
+    let operations = config.operations.start.max(1)..config.operations.end;
+    prop::collection::vec(planned, operations)
+}
+
+/// Returns a strategy that generates histories of operations on an object
+/// with the given specification, each of which is linearizable.
+///
+/// See the [`arbitrary`](self) module-level documentation for details.
+pub fn linearizable_histories<S: ArbitrarySpecification>(
+    config: HistoryConfig,
+) -> impl Strategy<Value = History<S::Operation>> {
+    planned::<S>(&config).prop_map(|planned| {
+        let operations = operations::<S>(&planned);
+        build::<S>(&planned, operations, None)
+    })
+}
+
+/// Returns a strategy that generates histories of operations on an object
+/// with the given specification, none of which is linearizable.
+///
+/// Histories for which no operation can be
+/// [corrupted](ArbitrarySpecification::corrupt) are rejected, so the
+/// specification must be able to corrupt a reasonable fraction of intents.
+///
+/// See the [`arbitrary`](self) module-level documentation for details.
+pub fn non_linearizable_histories<S: ArbitrarySpecification>(
+    config: HistoryConfig,
+) -> impl Strategy<Value = History<S::Operation>> {
+    (planned::<S>(&config), any::<Index>()).prop_filter_map(
+        "No operation can be corrupted",
+        |(planned, index)| {
+            let mut operations = operations::<S>(&planned);
+            let start = index.index(planned.len());
+            let (corrupted, operation) = (start..planned.len()).chain(0..start).find_map(|k| {
+                let (state, _) = &operations[k];
+                S::corrupt(planned[k].process, &planned[k].intent, state)
+                    .map(|operation| (k, operation))
+            })?;
+            debug_assert!(!S::apply(&operation, &operations[corrupted].0).0);
+            operations[corrupted].1 = operation;
+            Some(build::<S>(&planned, operations, Some(corrupted)))
+        },
+    )
+}
+
+/// Returns each of the planned operations, along with the state of the
+/// object in which it takes effect, when they take effect in order.
+fn operations<S: ArbitrarySpecification>(
+    planned: &[Planned<S::Intent>],
+) -> Vec<(S::State, S::Operation)> {
+    let mut state = S::init();
+    planned
+        .iter()
+        .map(|planned| {
+            let operation = S::operation(planned.process, &planned.intent, &state);
+            let (valid, next) = S::apply(&operation, &state);
+            assert!(valid, "Generated operation {operation:?} is not valid");
+            (std::mem::replace(&mut state, next), operation)
+        })
+        .collect()
+}
+
+/// Returns a history in which the _k^{th}_ operation takes effect at time
+/// `10 * k`, and is called and responds around that time, according to its
+/// plan.
+///
+/// If an operation is corrupted, then it and every operation before it are
+/// sequential, and every operation after it is called after it responds.
+fn build<S: ArbitrarySpecification>(
+    planned: &[Planned<S::Intent>],
+    operations: Vec<(S::State, S::Operation)>,
+    corrupted: Option<usize>,
+) -> History<S::Operation> {
+    const STEP: i64 = 10;
+    let at = |k: usize| STEP * k as i64;
+    let is_sequential = |k: usize| corrupted.is_some_and(|corrupted| k <= corrupted);
+
+    // The index of the next operation performed by the same process.
+    let mut next: Vec<Option<usize>> = vec![None; planned.len()];
+    let mut last: Vec<Option<usize>> = Vec::new();
+    for (k, plan) in planned.iter().enumerate() {
+        if last.len() <= plan.process {
+            last.resize(plan.process + 1, None);
+        }
+        if let Some(previous) = last[plan.process].replace(k) {
+            next[previous] = Some(k);
+        }
+    }
+
+    // Each operation responds before the next operation of the same process
+    // takes effect, and is called after the previous operation of the same
+    // process responds.
+    let mut responses: Vec<i64> = Vec::with_capacity(planned.len());
+    for (k, plan) in planned.iter().enumerate() {
+        let after = if is_sequential(k) { 0 } else { plan.after };
+        let response = at(k) + STEP * after as i64 + 1;
+        responses.push(match next[k] {
+            Some(next) => response.min(at(next) - 2),
+            None => response,
+        });
+    }
+    let mut previous: Vec<Option<usize>> = vec![None; last.len()];
+    let mut actions: Vec<(i64, ProcessId, Action<S::Operation>)> = Vec::new();
+    for (k, (plan, (_, operation))) in planned.iter().zip(operations).enumerate() {
+        let before = if is_sequential(k) { 0 } else { plan.before };
+        let mut call = at(k) - STEP * before as i64 - 1;
+        if let Some(previous) = previous[plan.process].replace(k) {
+            call = call.max(responses[previous] + 1);
+        }
+        if let Some(corrupted) = corrupted.filter(|corrupted| k > *corrupted) {
+            call = call.max(responses[corrupted] + 1);
+        }
+        actions.push((call, plan.process, Action::Call(S::call(&operation))));
+        actions.push((responses[k], plan.process, Action::Response(operation)));
+    }
+
+    actions.sort_by_key(|(time, _, _)| *time);
+    History::from_actions(
+        actions
+            .into_iter()
+            .map(|(_, process, action)| (process, action))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linearizability::history::Entry;
+    use crate::WGLChecker;
+
+    type RegisterSpec = RegisterSpecification<u8>;
+    type SnapshotSpec = SnapshotSpecification<u8, 3>;
+
+    /// Returns the largest number of operations that are pending at once.
+    fn max_pending<T>(history: &History<T>) -> usize {
+        let mut pending: usize = 0;
+        let mut max = 0;
+        for entry in history.iter() {
+            match entry {
+                Entry::Call(_) => pending += 1,
+                Entry::Response(_) => pending -= 1,
+            }
+            max = max.max(pending);
+        }
+        max
+    }
+
+    proptest! {
+        #[test]
+        fn register_histories_are_linearizable(
+            history in linearizable_histories::<RegisterSpec>(HistoryConfig::new())
+        ) {
+            prop_assert!(WGLChecker::<RegisterSpec>::is_linearizable(history));
+        }
+
+        #[test]
+        fn corrupted_register_histories_are_not_linearizable(
+            history in non_linearizable_histories::<RegisterSpec>(HistoryConfig::new())
+        ) {
+            prop_assert!(!WGLChecker::<RegisterSpec>::is_linearizable(history));
+        }
+
+        #[test]
+        fn snapshot_histories_are_linearizable(
+            history in linearizable_histories::<SnapshotSpec>(HistoryConfig::new())
+        ) {
+            prop_assert!(WGLChecker::<SnapshotSpec>::is_linearizable(history));
+        }
+
+        #[test]
+        fn corrupted_snapshot_histories_are_not_linearizable(
+            history in non_linearizable_histories::<SnapshotSpec>(HistoryConfig::new())
+        ) {
+            prop_assert!(!WGLChecker::<SnapshotSpec>::is_linearizable(history));
+        }
+
+        #[test]
+        fn histories_without_concurrency_are_sequential(
+            history in linearizable_histories::<RegisterSpec>(
+                HistoryConfig::new().with_concurrency(0)
+            )
+        ) {
+            prop_assert_eq!(max_pending(&history), 1);
+        }
+
+        #[test]
+        fn histories_contain_requested_number_of_operations(
+            history in linearizable_histories::<RegisterSpec>(
+                HistoryConfig::new().with_operations(5..6)
+            )
+        ) {
+            prop_assert_eq!(history.len(), 10);
+        }
+    }
+}
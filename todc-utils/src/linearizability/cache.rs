@@ -0,0 +1,197 @@
+//! Memoization of the partial linearizations explored by a [`WGLChecker`].
+//!
+//! [`WGLChecker`]: super::WGLChecker
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+
+/// A fixed-size set of integers, stored as a sequence of bits.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(super) struct Bitset {
+    words: Vec<u64>,
+}
+
+impl Bitset {
+    /// Creates an empty set that can contain the integers `0..len`.
+    pub(super) fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    /// Adds `i` to the set.
+    pub(super) fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    /// Removes `i` from the set.
+    pub(super) fn remove(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+}
+
+/// Indicates the absence of a neighbor in the list of recently used keys.
+const NIL: usize = usize::MAX;
+
+/// A key in the list of recently used keys.
+struct Node<K> {
+    key: K,
+    prev: usize,
+    next: usize,
+}
+
+/// A set of keys that, if given a capacity, evicts the least-recently used
+/// key whenever a new key would exceed it.
+pub(super) struct Cache<K, H> {
+    capacity: Option<usize>,
+    // Maps each key to its node in `nodes`. Nodes are only maintained if
+    // the cache has a capacity.
+    slots: HashMap<K, usize, H>,
+    // A doubly-linked list of keys, from most to least recently used.
+    nodes: Vec<Node<K>>,
+    head: usize,
+    tail: usize,
+}
+
+impl<K: Clone + Eq + Hash, H: BuildHasher> Cache<K, H> {
+    /// Creates an empty cache.
+    pub(super) fn new(capacity: Option<usize>, hasher: H) -> Self {
+        Self {
+            capacity,
+            slots: HashMap::with_hasher(hasher),
+            nodes: Vec::new(),
+            head: NIL,
+            tail: NIL,
+        }
+    }
+
+    /// Adds a key to the cache, and returns whether it was not already
+    /// present. If it was, the key is marked as recently used.
+    pub(super) fn insert(&mut self, key: K) -> bool {
+        let capacity = match self.capacity {
+            None => return self.slots.insert(key, NIL).is_none(),
+            Some(0) => return true,
+            Some(capacity) => capacity,
+        };
+        if let Some(&slot) = self.slots.get(&key) {
+            self.unlink(slot);
+            self.push_front(slot);
+            return false;
+        }
+        let slot = if self.slots.len() < capacity {
+            self.nodes.push(Node {
+                key: key.clone(),
+                prev: NIL,
+                next: NIL,
+            });
+            self.nodes.len() - 1
+        } else {
+            let slot = self.tail;
+            self.unlink(slot);
+            let evicted = std::mem::replace(&mut self.nodes[slot].key, key.clone());
+            self.slots.remove(&evicted);
+            slot
+        };
+        self.push_front(slot);
+        self.slots.insert(key, slot);
+        true
+    }
+
+    fn unlink(&mut self, slot: usize) {
+        let Node { prev, next, .. } = self.nodes[slot];
+        match prev {
+            NIL => self.head = next,
+            prev => self.nodes[prev].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.nodes[next].prev = prev,
+        }
+    }
+
+    fn push_front(&mut self, slot: usize) {
+        self.nodes[slot].prev = NIL;
+        self.nodes[slot].next = self.head;
+        match self.head {
+            NIL => self.tail = slot,
+            head => self.nodes[head].prev = slot,
+        }
+        self.head = slot;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::RandomState;
+
+    mod bitset {
+        use super::*;
+
+        #[test]
+        fn stores_each_integer_in_its_own_bit() {
+            let mut set = Bitset::new(130);
+            set.insert(0);
+            set.insert(64);
+            set.insert(129);
+            assert_eq!(set.words, vec![1, 1, 2]);
+        }
+
+        #[test]
+        fn remove_is_inverse_of_insert() {
+            let empty = Bitset::new(100);
+            let mut set = empty.clone();
+            set.insert(70);
+            set.remove(70);
+            assert_eq!(set, empty);
+        }
+    }
+
+    mod cache {
+        use super::*;
+
+        fn cache(capacity: Option<usize>) -> Cache<u32, RandomState> {
+            Cache::new(capacity, RandomState::new())
+        }
+
+        #[test]
+        fn inserts_each_key_once() {
+            for capacity in [None, Some(2)] {
+                let mut cache = cache(capacity);
+                assert!(cache.insert(1));
+                assert!(!cache.insert(1));
+                assert!(cache.insert(2));
+                assert_eq!(cache.slots.len(), 2);
+            }
+        }
+
+        #[test]
+        fn does_not_exceed_capacity() {
+            let mut cache = cache(Some(2));
+            for key in 0..10 {
+                cache.insert(key);
+            }
+            assert_eq!(cache.slots.len(), 2);
+            assert!(!cache.insert(8));
+            assert!(!cache.insert(9));
+        }
+
+        #[test]
+        fn evicts_least_recently_used_key() {
+            let mut cache = cache(Some(2));
+            cache.insert(1);
+            cache.insert(2);
+            cache.insert(1);
+            cache.insert(3);
+            assert!(!cache.insert(1));
+            assert!(cache.insert(2));
+        }
+
+        #[test]
+        fn stores_nothing_if_capacity_is_zero() {
+            let mut cache = cache(Some(0));
+            assert!(cache.insert(1));
+            assert!(cache.insert(1));
+            assert_eq!(cache.slots.len(), 0);
+        }
+    }
+}
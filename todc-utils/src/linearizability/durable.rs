@@ -0,0 +1,425 @@
+//! Checking [durable linearizability](https://doi.org/10.1007/978-3-662-53426-7_23)
+//! of a history of operations applied to a shared object that survives
+//! crashes.
+//!
+//! Objects stored in persistent memory keep their state when the system
+//! crashes, while the processes operating on them do not. A history of such
+//! an object is interleaved with crashes, after which every process that was
+//! performing an operation never receives its response. Izraelevitz, Mendes,
+//! and Scott [\[IMS16\]](https://doi.org/10.1007/978-3-662-53426-7_23) define
+//! a history to be _durably linearizable_ if it is linearizable once every
+//! operation that was interrupted by a crash is either removed, or completed
+//! at the moment of the crash. That is, an interrupted operation may take
+//! effect, but only before the crash that interrupted it.
+//!
+//! A history with crashes is described by a sequence of [`Event`]s. Checking
+//! it with [`WGLChecker::is_durably_linearizable`] completes each interrupted
+//! operation with a [`DurableOperation::Interrupted`] response at the moment
+//! of the crash, and checks that the result is linearizable with respect to
+//! [`Durable`], in which interrupted operations may or may not take effect.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_utils::linearizability::durable::Event::{Action, Crash};
+//! use todc_utils::specifications::register::RegisterSpecification;
+//! use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+//! use todc_utils::{Action::{Call, Response}, WGLChecker};
+//!
+//! type Checker = WGLChecker<RegisterSpecification<u32>>;
+//!
+//! // P0 |-----X        Write(1), interrupted by a crash
+//! // P1         |---|  Read(Some(1))
+//! let events = vec![
+//!     Action(0, Call(Write(1))),
+//!     Crash,
+//!     Action(1, Call(Read(None))),
+//!     Action(1, Response(Read(Some(1)))),
+//! ];
+//! assert!(Checker::is_durably_linearizable(events));
+//!
+//! // An interrupted write cannot take effect after the crash.
+//! // P0 |-----X                Write(1), interrupted by a crash
+//! // P1         |---|          Read(Some(0))
+//! // P1               |---|    Read(Some(1))
+//! let events = vec![
+//!     Action(0, Call(Write(1))),
+//!     Crash,
+//!     Action(1, Call(Read(None))),
+//!     Action(1, Response(Read(Some(0)))),
+//!     Action(1, Call(Read(None))),
+//!     Action(1, Response(Read(Some(1)))),
+//! ];
+//! assert!(!Checker::is_durably_linearizable(events));
+//! ```
+//!
+//! # Crash-Stop Failures
+//!
+//! In the crash-stop model, a process may stop in the middle of an operation
+//! and never take another step, while the rest of the system carries on. This
+//! is the usual failure model of message-passing systems, and can be
+//! simulated in shared memory by stopping a thread between the call and the
+//! response of an operation. A stopped operation may have taken effect at any
+//! point after it was called, including after every other operation, so a
+//! history with stopped processes is a history without crashes in which some
+//! operations are still pending at the end. Such histories can be checked
+//! directly from their actions with [`WGLChecker::is_linearizable_with_pending`].
+//!
+//! ```
+//! use todc_utils::specifications::register::RegisterSpecification;
+//! use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+//! use todc_utils::{Action::{Call, Response}, WGLChecker};
+//!
+//! type Checker = WGLChecker<RegisterSpecification<u32>>;
+//!
+//! // P0 |-----------X   Write(1), stopped before responding
+//! // P1   |---|         Read(Some(0))
+//! // P1         |---|   Read(Some(1))
+//! let actions = vec![
+//!     (0, Call(Write(1))),
+//!     (1, Call(Read(None))),
+//!     (1, Response(Read(Some(0)))),
+//!     (1, Call(Read(None))),
+//!     (1, Response(Read(Some(1)))),
+//! ];
+//! assert!(Checker::is_linearizable_with_pending(actions));
+//! ```
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::marker::PhantomData;
+
+use crate::linearizability::history::{self, History, ProcessId};
+use crate::linearizability::{CheckerConfig, WGLChecker};
+use crate::specifications::{DurableSpecification, NondeterministicSpecification};
+
+/// An event in a history of operations applied to an object that survives
+/// crashes.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Event<T> {
+    /// An action performed by a process, as in an ordinary [`History`].
+    Action(ProcessId, history::Action<T>),
+    /// A crash, which interrupts every operation that is in progress.
+    Crash,
+}
+
+/// An operation in a history of an object that survives crashes.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum DurableOperation<T> {
+    /// An operation that completed.
+    Completed(T),
+    /// An operation that was interrupted by a crash, and so may or may not
+    /// have taken effect.
+    Interrupted(T),
+}
+
+/// A specification in which each operation of the specification `S` may be
+/// interrupted by a crash.
+///
+/// Interrupted operations that may update the object, as determined by
+/// [`DurableSpecification::is_update`], lead either to the same state as if
+/// they had completed, or to the state they were applied to. All other
+/// interrupted operations leave the state unchanged.
+pub struct Durable<S: DurableSpecification> {
+    data_type: PhantomData<S>,
+}
+
+impl<S: DurableSpecification> NondeterministicSpecification for Durable<S> {
+    type State = S::State;
+    type Operation = DurableOperation<S::Operation>;
+    type Successors = Vec<S::State>;
+
+    fn initial_state() -> Self::State {
+        S::init()
+    }
+
+    fn successors(operation: &Self::Operation, state: &Self::State) -> Self::Successors {
+        match operation {
+            DurableOperation::Completed(operation) => match S::apply(operation, state) {
+                (true, state) => vec![state],
+                (false, _) => vec![],
+            },
+            DurableOperation::Interrupted(operation) => {
+                let mut successors = vec![state.clone()];
+                if S::is_update(operation) {
+                    if let (true, updated) = S::apply(operation, state) {
+                        successors.push(updated);
+                    }
+                }
+                successors
+            }
+        }
+    }
+}
+
+/// Creates a history from a sequence of events, in which every operation
+/// interrupted by a crash is completed by a [`DurableOperation::Interrupted`]
+/// response at the moment of the crash.
+///
+/// Operations that are still in progress at the end of the sequence are
+/// treated as if they were interrupted by one final crash.
+///
+/// # Panics
+///
+/// Panics if `events` does not contain any actions.
+pub fn complete<T: Clone>(events: Vec<Event<T>>) -> History<DurableOperation<T>> {
+    let mut actions: Vec<(ProcessId, history::Action<DurableOperation<T>>)> = Vec::new();
+    // The operation that each process is performing, if any.
+    let mut pending: HashMap<ProcessId, T> = HashMap::new();
+    for event in events {
+        match event {
+            Event::Action(process, action) => {
+                match &action {
+                    history::Action::Call(operation) => pending.insert(process, operation.clone()),
+                    _ => pending.remove(&process),
+                };
+                actions.push((process, action.map(DurableOperation::Completed)));
+            }
+            Event::Crash => interrupt(&mut pending, &mut actions),
+        }
+    }
+    interrupt(&mut pending, &mut actions);
+    History::from_actions(actions)
+}
+
+/// Adds an [`Interrupted`](DurableOperation::Interrupted) response to every
+/// pending operation.
+fn interrupt<T>(
+    pending: &mut HashMap<ProcessId, T>,
+    actions: &mut Vec<(ProcessId, history::Action<DurableOperation<T>>)>,
+) {
+    let mut interrupted: Vec<(ProcessId, T)> = pending.drain().collect();
+    // Order interrupted operations deterministically.
+    interrupted.sort_by_key(|(process, _)| *process);
+    for (process, operation) in interrupted {
+        let response = DurableOperation::Interrupted(operation);
+        actions.push((process, history::Action::Response(response)));
+    }
+}
+
+impl<S: DurableSpecification> WGLChecker<S> {
+    /// Returns whether the history of events is durably linearizable with
+    /// respect to the specification.
+    ///
+    /// See the [`durable`](crate::linearizability::durable) module-level
+    /// documentation for more details.
+    pub fn is_durably_linearizable(events: Vec<Event<S::Operation>>) -> bool {
+        Self::is_durably_linearizable_with_config(events, &CheckerConfig::new())
+    }
+
+    /// Returns whether the history of events is durably linearizable with
+    /// respect to the specification, using the given configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the check exceeds the timeout or limit on expansions set by
+    /// the configuration.
+    pub fn is_durably_linearizable_with_config<H: BuildHasher + Clone>(
+        events: Vec<Event<S::Operation>>,
+        config: &CheckerConfig<H>,
+    ) -> bool {
+        WGLChecker::<Durable<S>>::is_linearizable_with_config(complete(events), config)
+    }
+
+    /// Returns whether a sequence of actions, in which some operations may
+    /// never have returned, is linearizable with respect to the
+    /// specification.
+    ///
+    /// An operation without a response belongs to a process that stopped
+    /// while performing it, and may or may not have taken effect at any
+    /// point after it was called. See the [crash-stop](crate::linearizability::durable#crash-stop-failures)
+    /// section of the module-level documentation for more details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `actions` is empty.
+    pub fn is_linearizable_with_pending(
+        actions: Vec<(ProcessId, history::Action<S::Operation>)>,
+    ) -> bool {
+        Self::is_linearizable_with_pending_with_config(actions, &CheckerConfig::new())
+    }
+
+    /// Returns whether a sequence of actions, in which some operations may
+    /// never have returned, is linearizable with respect to the
+    /// specification, using the given configuration.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `actions` is empty, or if the check exceeds the timeout or
+    /// limit on expansions set by the configuration.
+    pub fn is_linearizable_with_pending_with_config<H: BuildHasher + Clone>(
+        actions: Vec<(ProcessId, history::Action<S::Operation>)>,
+        config: &CheckerConfig<H>,
+    ) -> bool {
+        let events = actions
+            .into_iter()
+            .map(|(process, action)| Event::Action(process, action))
+            .collect();
+        Self::is_durably_linearizable_with_config(events, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linearizability::history::Action::{Call, Response};
+    use crate::linearizability::history::Entry;
+    use crate::specifications::register::RegisterOperation::{Read, Write};
+    use crate::specifications::register::RegisterSpecification;
+    use Event::{Action, Crash};
+
+    type Checker = WGLChecker<RegisterSpecification<u32>>;
+
+    mod complete {
+        use super::*;
+
+        #[test]
+        fn completes_interrupted_operations_at_crash() {
+            let history = complete(vec![
+                Action(0, Call(Write(1))),
+                Crash,
+                Action(1, Call(Read(None))),
+                Action(1, Response(Read(Some(1)))),
+            ]);
+            assert!(matches!(
+                &history[1],
+                Entry::Response(response)
+                    if matches!(response.operation, DurableOperation::Interrupted(Write(1)))
+            ));
+        }
+
+        #[test]
+        fn completes_pending_operations_at_end() {
+            let history = complete(vec![
+                Action(0, Call(Write(1))),
+                Action(1, Call(Write(2))),
+                Action(1, Response(Write(2))),
+            ]);
+            assert_eq!(history.len(), 4);
+        }
+
+        #[test]
+        fn leaves_completed_operations_alone() {
+            let history = complete(vec![
+                Action(0, Call(Write(1))),
+                Action(0, Response(Write(1))),
+                Crash,
+            ]);
+            assert_eq!(history.len(), 2);
+        }
+    }
+
+    mod is_durably_linearizable {
+        use super::*;
+
+        #[test]
+        fn accepts_history_without_crashes() {
+            let events = vec![
+                Action(0, Call(Write(1))),
+                Action(0, Response(Write(1))),
+                Action(1, Call(Read(None))),
+                Action(1, Response(Read(Some(1)))),
+            ];
+            assert!(Checker::is_durably_linearizable(events));
+        }
+
+        #[test]
+        fn accepts_interrupted_write_that_took_effect() {
+            let events = vec![
+                Action(0, Call(Write(1))),
+                Crash,
+                Action(1, Call(Read(None))),
+                Action(1, Response(Read(Some(1)))),
+            ];
+            assert!(Checker::is_durably_linearizable(events));
+        }
+
+        #[test]
+        fn accepts_interrupted_write_that_did_not_take_effect() {
+            let events = vec![
+                Action(0, Call(Write(1))),
+                Crash,
+                Action(1, Call(Read(None))),
+                Action(1, Response(Read(Some(0)))),
+            ];
+            assert!(Checker::is_durably_linearizable(events));
+        }
+
+        #[test]
+        fn accepts_interrupted_read() {
+            let events = vec![
+                Action(0, Call(Read(None))),
+                Crash,
+                Action(0, Call(Write(1))),
+                Action(0, Response(Write(1))),
+            ];
+            assert!(Checker::is_durably_linearizable(events));
+        }
+
+        #[test]
+        fn rejects_interrupted_write_that_took_effect_after_crash() {
+            let events = vec![
+                Action(0, Call(Write(1))),
+                Crash,
+                Action(1, Call(Read(None))),
+                Action(1, Response(Read(Some(0)))),
+                Action(1, Call(Read(None))),
+                Action(1, Response(Read(Some(1)))),
+            ];
+            assert!(!Checker::is_durably_linearizable(events));
+        }
+
+        #[test]
+        fn rejects_lost_write_that_completed_before_crash() {
+            let events = vec![
+                Action(0, Call(Write(1))),
+                Action(0, Response(Write(1))),
+                Crash,
+                Action(1, Call(Read(None))),
+                Action(1, Response(Read(Some(0)))),
+            ];
+            assert!(!Checker::is_durably_linearizable(events));
+        }
+    }
+
+    mod is_linearizable_with_pending {
+        use super::*;
+
+        #[test]
+        fn accepts_pending_write_that_took_effect_after_every_operation() {
+            let actions = vec![
+                (0, Call(Write(1))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(0)))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(1)))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(1)))),
+            ];
+            assert!(Checker::is_linearizable_with_pending(actions));
+        }
+
+        #[test]
+        fn accepts_pending_write_that_never_took_effect() {
+            let actions = vec![
+                (0, Call(Write(1))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(0)))),
+            ];
+            assert!(Checker::is_linearizable_with_pending(actions));
+        }
+
+        #[test]
+        fn rejects_pending_write_that_took_effect_twice() {
+            let actions = vec![
+                (0, Call(Write(1))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(1)))),
+                (2, Call(Write(2))),
+                (2, Response(Write(2))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(1)))),
+            ];
+            assert!(!Checker::is_linearizable_with_pending(actions));
+        }
+    }
+}
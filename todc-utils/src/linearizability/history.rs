@@ -1,7 +1,17 @@
 //! A sequence of operations applied to a shared object.
-use std::collections::VecDeque;
-use std::iter::repeat_with;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::iter::{repeat_with, successors};
 use std::ops::{Index, IndexMut};
+use std::time::{Duration, Instant};
+
+mod compact;
+mod filter;
+mod render;
+mod shrink;
+mod stats;
+
+pub use self::stats::Stats;
 
 /// A identifier for an [`Entry`]
 pub type EntryId = usize;
@@ -10,12 +20,110 @@ pub type EntryId = usize;
 pub type ProcessId = usize;
 
 /// An action that occurs as part of an operation on a shared object.
+///
+/// Every operation begins with a `Call`, and ends with either a `Response`,
+/// a `Failed`, or an `Indeterminate` action, depending on its [`Outcome`].
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum Action<T> {
     /// A `Call` indicates the beginning of an operation.
     Call(T),
-    /// A `Response` indicates the end of an operation.
+    /// A `Response` indicates the end of an operation that took effect.
     Response(T),
+    /// A `Failed` action indicates the end of an operation that is known to
+    /// have not taken effect, such as a request that was rejected.
+    Failed(T),
+    /// An `Indeterminate` action indicates the end of an operation that may
+    /// or may not have taken effect, such as a request that timed out.
+    Indeterminate(T),
+}
+
+impl<T> Action<T> {
+    /// Returns the same kind of action, with its operation transformed by `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::Action::Failed;
+    ///
+    /// assert_eq!(Failed(1).map(|x| x + 1), Failed(2));
+    /// ```
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Action<U> {
+        match self {
+            Action::Call(operation) => Action::Call(f(operation)),
+            Action::Response(operation) => Action::Response(f(operation)),
+            Action::Failed(operation) => Action::Failed(f(operation)),
+            Action::Indeterminate(operation) => Action::Indeterminate(f(operation)),
+        }
+    }
+}
+
+/// Whether an operation took effect.
+///
+/// Operations whose outcome is not [`Completed`](Outcome::Completed) need not
+/// be modeled by a specification. When checking a history, a
+/// [`Failed`](Outcome::Failed) operation is ignored, and an
+/// [`Indeterminate`](Outcome::Indeterminate) operation either takes effect at
+/// some point after it was called, or not at all. Since its response is not
+/// known, the operation of an [`Action::Indeterminate`] is usually the same as
+/// that of its call.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Outcome {
+    /// The operation took effect, and returned its response.
+    #[default]
+    Completed,
+    /// The operation is known to have not taken effect.
+    Failed,
+    /// It is not known whether the operation took effect.
+    Indeterminate,
+}
+
+/// An action performed by a process, along with the time at which it
+/// happened.
+///
+/// See [`History::from_timed_actions`].
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TimedAction<T> {
+    /// The process that performed the action.
+    pub process: ProcessId,
+    /// The call or response of an operation.
+    pub action: Action<T>,
+    /// The time at which the action happened.
+    pub happened_at: Instant,
+}
+
+impl<T> TimedAction<T> {
+    /// Creates an action, performed by the given process, that happens now.
+    pub fn now(process: ProcessId, action: Action<T>) -> Self {
+        Self {
+            process,
+            action,
+            happened_at: Instant::now(),
+        }
+    }
+}
+
+/// Information about the action of an entry that is not needed to check a
+/// history, but helps to find and explain its operations.
+///
+/// See [`History::with_metadata`].
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct Metadata {
+    /// A human-readable name for the process that performed the action, such
+    /// as the hostname of a client.
+    pub process_name: Option<String>,
+    /// Labels attached to the action, such as the phase of a test in which it
+    /// happened.
+    pub tags: Vec<String>,
+    /// The latency of the operation, as measured by the process that
+    /// performed it. This is usually attached to the response.
+    pub latency: Option<Duration>,
+}
+
+impl Metadata {
+    /// Returns whether the action has the given tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
 }
 
 /// An entry in a history that represents the call to an operation.
@@ -37,6 +145,8 @@ pub struct ResponseEntry<T> {
     pub id: EntryId,
     /// The operation being responded to.
     pub operation: T,
+    /// Whether the operation took effect.
+    pub outcome: Outcome,
 }
 
 /// An entry in a history.
@@ -54,6 +164,18 @@ impl<T> Entry<T> {
             Entry::Response(entry) => entry.id,
         }
     }
+
+    /// Returns the action that this entry was created from.
+    fn into_action(self) -> Action<T> {
+        match self {
+            Entry::Call(call) => Action::Call(call.operation),
+            Entry::Response(response) => match response.outcome {
+                Outcome::Completed => Action::Response(response.operation),
+                Outcome::Failed => Action::Failed(response.operation),
+                Outcome::Indeterminate => Action::Indeterminate(response.operation),
+            },
+        }
+    }
 }
 
 /// A sequence of operations applied to a shared object.
@@ -124,11 +246,29 @@ impl<T> Entry<T> {
 /// let history = History::from_actions(actions);
 /// assert!(matches!(&history[0], Entry::Call(x)));
 /// ```
+///
+/// Entries can be indexed by their [`EntryId`], which is the position of the
+/// corresponding action in the sequence the history was created from.
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct History<T> {
-    pub(super) entries: Vec<Entry<T>>,
-    // When an entry is removed from this history, its index is recorded here.
-    removed_from: Vec<Option<EntryId>>,
+    // Entries are stored in the order they were created, so that the entry
+    // with id `i` is always at index `i`, even after it is removed.
+    entries: Vec<Entry<T>>,
+    // The process that performed the operation of each entry, by id.
+    processes: Vec<ProcessId>,
+    // The entries that remain in this history form a doubly-linked list, in
+    // which `next[i]` and `prev[i]` are the neighbors of the entry with id `i`.
+    // The list is circular, and index `entries.len()` is a sentinel that
+    // marks both its head and its tail. Removed entries keep their links,
+    // so that they can be re-inserted in constant time, as long as entries
+    // are re-inserted in the reverse of the order they were removed in.
+    next: Vec<usize>,
+    prev: Vec<usize>,
+    len: usize,
+    // The time at which the action of each entry occurred, by id, if known.
+    timestamps: Option<Vec<Duration>>,
+    // The metadata of the action of each entry, by id, if known.
+    metadata: Option<Vec<Metadata>>,
 }
 
 impl<T> History<T> {
@@ -165,83 +305,479 @@ impl<T> History<T> {
         for (i, process) in processes.iter().enumerate() {
             match &actions[i] {
                 Action::Call(_) => calls[*process].push_back(i),
-                Action::Response(_) => responses[*process].push_back(i),
+                _ => responses[*process].push_back(i),
+            }
+        }
+
+        let entries = actions
+            .into_iter()
+            .enumerate()
+            .map(|(i, action)| match action {
+                Action::Call(operation) => Entry::Call(CallEntry {
+                    id: i,
+                    operation,
+                    response: responses[processes[i]].pop_front().unwrap(),
+                }),
+                Action::Response(operation) => Entry::Response(ResponseEntry {
+                    id: i,
+                    operation,
+                    outcome: Outcome::Completed,
+                }),
+                Action::Failed(operation) => Entry::Response(ResponseEntry {
+                    id: i,
+                    operation,
+                    outcome: Outcome::Failed,
+                }),
+                Action::Indeterminate(operation) => Entry::Response(ResponseEntry {
+                    id: i,
+                    operation,
+                    outcome: Outcome::Indeterminate,
+                }),
+            })
+            .collect();
+        Self::from_entries(entries, processes)
+    }
+
+    /// Creates a history from actions that happened at known times, given in
+    /// any order.
+    ///
+    /// The actions are sorted by the time at which they happened, and the
+    /// resulting history has timestamps attached, measured from the earliest
+    /// action. Actions that happened at the same time keep the order in which
+    /// they were given.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `actions` is empty, or if the resulting history would be
+    /// incomplete.
+    ///
+    /// Panics if, once sorted, the actions of some process do not alternate
+    /// between calls and responses, starting with a call. That is, if a
+    /// process calls an operation before its previous operation returned, or
+    /// an operation returns before it was called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use todc_utils::{History, TimedAction, Action::{Call, Response}};
+    ///
+    /// let start = Instant::now();
+    /// let at = |millis| start + Duration::from_millis(millis);
+    ///
+    /// let history = History::from_timed_actions(vec![
+    ///     TimedAction { process: 1, action: Response("b"), happened_at: at(5) },
+    ///     TimedAction { process: 0, action: Call("a"), happened_at: at(0) },
+    ///     TimedAction { process: 0, action: Response("a"), happened_at: at(2) },
+    ///     TimedAction { process: 1, action: Call("b"), happened_at: at(1) },
+    /// ]);
+    /// let stats = history.stats();
+    /// assert_eq!(stats.max_concurrency, 2);
+    /// assert_eq!(stats.mean_latency, Some(Duration::from_millis(3)));
+    /// ```
+    pub fn from_timed_actions(mut actions: Vec<TimedAction<T>>) -> Self {
+        actions.sort_by_key(|timed| timed.happened_at);
+
+        // Whether each process has an operation that has not yet returned.
+        let mut pending: HashMap<ProcessId, bool> = HashMap::new();
+        for timed in &actions {
+            let pending = pending.entry(timed.process).or_default();
+            match (&timed.action, *pending) {
+                (Action::Call(_), true) => panic!(
+                    "Process {} called an operation before its previous operation returned",
+                    timed.process
+                ),
+                (_, false) if !matches!(timed.action, Action::Call(_)) => panic!(
+                    "Process {} returned from an operation before calling it",
+                    timed.process
+                ),
+                _ => *pending = !*pending,
             }
         }
 
+        let start = actions.first().map(|timed| timed.happened_at);
+        let timestamps = actions
+            .iter()
+            .map(|timed| timed.happened_at - start.unwrap())
+            .collect();
+        let actions = actions
+            .into_iter()
+            .map(|timed| (timed.process, timed.action))
+            .collect();
+        Self::from_actions(actions).with_timestamps(timestamps)
+    }
+
+    /// Creates a history from a sequence of entries, in which the entry at
+    /// index `i` has id `i` and was performed by process `processes[i]`.
+    fn from_entries(entries: Vec<Entry<T>>, processes: Vec<ProcessId>) -> Self {
+        let len = entries.len();
         Self {
-            entries: actions
-                .into_iter()
-                .enumerate()
-                .map(|(i, action)| match action {
-                    Action::Call(operation) => Entry::Call(CallEntry {
-                        id: i,
-                        operation,
-                        response: responses[processes[i]].pop_front().unwrap(),
-                    }),
-                    Action::Response(operation) => {
-                        Entry::Response(ResponseEntry { id: i, operation })
-                    }
-                })
-                .collect(),
-            removed_from: repeat_with(|| None).take(processes.len()).collect(),
+            entries,
+            processes,
+            next: (1..=len).chain([0]).collect(),
+            prev: [len].into_iter().chain(0..len).collect(),
+            len,
+            timestamps: None,
+            metadata: None,
         }
     }
 
-    // TODO: This operation is very expensive. Implementing History as a doubly-linked list could
-    // greatly improve performance.
-    pub(super) fn index_of_id(&self, id: EntryId) -> usize {
-        self.iter().position(|e| e.id() == id).unwrap()
+    /// Attaches the times at which the actions of the history occurred, where
+    /// `timestamps[i]` is the time of the entry with id `i`.
+    ///
+    /// Times can be measured from any fixed point, such as the start of a
+    /// test. They are used to compute the latency of operations in
+    /// [`stats`](History::stats), and are preserved by
+    /// [`partition`](History::partition) and by the methods that return
+    /// sub-histories, such as [`filter_by_process`](History::filter_by_process).
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not exactly one timestamp for each entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use todc_utils::{History, Action::{Call, Response}};
+    ///
+    /// let history = History::from_actions(vec![(0, Call("a")), (0, Response("a"))])
+    ///     .with_timestamps(vec![Duration::from_millis(1), Duration::from_millis(3)]);
+    /// assert_eq!(history.stats().mean_latency, Some(Duration::from_millis(2)));
+    /// ```
+    pub fn with_timestamps(mut self, timestamps: Vec<Duration>) -> Self {
+        assert_eq!(
+            timestamps.len(),
+            self.entries.len(),
+            "History has {} entries, but {} timestamps",
+            self.entries.len(),
+            timestamps.len()
+        );
+        self.timestamps = Some(timestamps);
+        self
     }
 
+    /// Attaches metadata to the actions of the history, where `metadata[i]`
+    /// describes the entry with id `i`.
+    ///
+    /// Metadata is ignored when checking a history, but can be used to select
+    /// the operations to check, for example with
+    /// [`filter_by_tag`](History::filter_by_tag). Like timestamps, it is
+    /// preserved by [`partition`](History::partition) and by the methods that
+    /// return sub-histories.
+    ///
     /// # Panics
     ///
-    /// Panics if input entry was not previously removed from the history.
-    fn insert(&mut self, entry: Entry<T>) -> usize {
-        match self.removed_from[entry.id()].take() {
-            Some(index) => {
-                self.entries.insert(index, entry);
-                index
+    /// Panics if there is not exactly one piece of metadata for each entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::{History, Action::{Call, Response}};
+    /// use todc_utils::linearizability::history::Metadata;
+    ///
+    /// let client = Metadata {
+    ///     process_name: Some(String::from("client-a")),
+    ///     ..Metadata::default()
+    /// };
+    /// let history = History::from_actions(vec![(0, Call("a")), (0, Response("a"))])
+    ///     .with_metadata(vec![client.clone(), client]);
+    /// let name = history.metadata(0).and_then(|m| m.process_name.as_deref());
+    /// assert_eq!(name, Some("client-a"));
+    /// ```
+    pub fn with_metadata(mut self, metadata: Vec<Metadata>) -> Self {
+        assert_eq!(
+            metadata.len(),
+            self.entries.len(),
+            "History has {} entries, but {} pieces of metadata",
+            self.entries.len(),
+            metadata.len()
+        );
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Returns the process that performed the action of the entry with the
+    /// given id.
+    pub fn process(&self, id: EntryId) -> ProcessId {
+        self.processes[id]
+    }
+
+    /// Returns the time at which the action of the entry with the given id
+    /// occurred, if the history has timestamps.
+    pub fn timestamp(&self, id: EntryId) -> Option<Duration> {
+        self.timestamps.as_ref().map(|timestamps| timestamps[id])
+    }
+
+    /// Returns the metadata of the entry with the given id, if the history
+    /// has metadata.
+    pub fn metadata(&self, id: EntryId) -> Option<&Metadata> {
+        self.metadata.as_ref().map(|metadata| &metadata[id])
+    }
+
+    /// Splits the history into independent sub-histories, such that the
+    /// operations in each sub-history all have the same key.
+    ///
+    /// The key of an operation is computed from its call entry, and its
+    /// response is always placed in the same sub-history. Sub-histories are
+    /// returned in the order that their first operations were called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::{History, Action::{Call, Response}};
+    ///
+    /// let history = History::from_actions(vec![
+    ///     (0, Call(("x", 1))),
+    ///     (1, Call(("y", 2))),
+    ///     (0, Response(("x", 1))),
+    ///     (1, Response(("y", 2))),
+    /// ]);
+    /// let parts = history.partition(|(key, _)| *key);
+    /// assert_eq!(parts.len(), 2);
+    /// assert_eq!(parts[0].len(), 2);
+    /// ```
+    pub fn partition<K: Eq + Hash>(self, key: impl Fn(&T) -> K) -> Vec<History<T>> {
+        let mut parts: HashMap<K, usize> = HashMap::new();
+        let mut entries: Vec<Vec<Entry<T>>> = Vec::new();
+        let mut processes: Vec<Vec<ProcessId>> = Vec::new();
+        let mut timestamps: Vec<Vec<Duration>> = Vec::new();
+        let mut metadata: Vec<Vec<Metadata>> = Vec::new();
+        let mut all_metadata = self.metadata.map(|metadata| metadata.into_iter());
+        // The part that each entry belongs to, and its id within that part.
+        let mut locations: Vec<(usize, EntryId)> = vec![(0, 0); self.entries.len()];
+        for (entry, process) in self.entries.into_iter().zip(self.processes) {
+            let part = match &entry {
+                Entry::Call(call) => {
+                    let part = *parts.entry(key(&call.operation)).or_insert_with(|| {
+                        entries.push(Vec::new());
+                        processes.push(Vec::new());
+                        timestamps.push(Vec::new());
+                        metadata.push(Vec::new());
+                        entries.len() - 1
+                    });
+                    locations[call.response].0 = part;
+                    part
+                }
+                Entry::Response(response) => locations[response.id].0,
+            };
+            let id = entries[part].len();
+            locations[entry.id()] = (part, id);
+            if let Some(times) = &self.timestamps {
+                timestamps[part].push(times[entry.id()]);
+            }
+            if let Some(all_metadata) = &mut all_metadata {
+                metadata[part].push(all_metadata.next().unwrap());
+            }
+            entries[part].push(match entry {
+                // The id of the response is not known yet, and is updated
+                // once all entries have been assigned to parts.
+                Entry::Call(call) => Entry::Call(CallEntry { id, ..call }),
+                Entry::Response(response) => Entry::Response(ResponseEntry { id, ..response }),
+            });
+            processes[part].push(process);
+        }
+        let timed = self.timestamps.is_some();
+        let described = all_metadata.is_some();
+        entries
+            .into_iter()
+            .zip(processes)
+            .zip(timestamps)
+            .zip(metadata)
+            .map(|(((mut entries, processes), timestamps), metadata)| {
+                for entry in entries.iter_mut() {
+                    if let Entry::Call(call) = entry {
+                        call.response = locations[call.response].1;
+                    }
+                }
+                let mut part = History::from_entries(entries, processes);
+                part.timestamps = timed.then_some(timestamps);
+                part.metadata = described.then_some(metadata);
+                part
+            })
+            .collect()
+    }
+
+    /// Returns a history of the same operations, in which entries are
+    /// ordered by their timestamps rather than by the order they were
+    /// created in, and an operation only precedes another if it returned more
+    /// than `tolerance` before the other was called.
+    ///
+    /// The operations of each process keep their order. Histories without
+    /// timestamps are returned unchanged.
+    pub(super) fn ordered_by_timestamps(self, tolerance: Duration) -> Self {
+        let Some(timestamps) = self.timestamps else {
+            return self;
+        };
+        // Responses are considered to happen `tolerance` after they actually
+        // did, but never after the next call by the same process. Among
+        // entries at the same time, calls are placed before responses, so
+        // that the operations are concurrent, except for responses that
+        // must precede the next call by their process.
+        let mut keys: Vec<(Duration, u8)> = vec![(Duration::ZERO, 0); self.entries.len()];
+        let mut next_call: HashMap<ProcessId, Duration> = HashMap::new();
+        for (id, entry) in self.entries.iter().enumerate().rev() {
+            let process = self.processes[id];
+            keys[id] = match entry {
+                Entry::Call(_) => {
+                    next_call.insert(process, timestamps[id]);
+                    (timestamps[id], 1)
+                }
+                Entry::Response(_) => {
+                    let time = timestamps[id].saturating_add(tolerance);
+                    match next_call.get(&process) {
+                        Some(&next) if next <= time => (next, 0),
+                        _ => (time, 2),
+                    }
+                }
+            };
+        }
+
+        let mut order: Vec<EntryId> = (0..self.entries.len()).collect();
+        order.sort_by_key(|&id| keys[id]);
+        let mut actions: Vec<Option<(ProcessId, Action<T>)>> = self
+            .entries
+            .into_iter()
+            .zip(self.processes)
+            .map(|(entry, process)| Some((process, entry.into_action())))
+            .collect();
+        let actions = order
+            .iter()
+            .map(|&id| actions[id].take().unwrap())
+            .collect();
+        let timestamps = order.iter().map(|&id| timestamps[id]).collect();
+        let mut history = Self::from_actions(actions).with_timestamps(timestamps);
+        if let Some(mut metadata) = self.metadata {
+            let metadata = order
+                .iter()
+                .map(|&id| std::mem::take(&mut metadata[id]))
+                .collect();
+            history = history.with_metadata(metadata);
+        }
+        history
+    }
+
+    /// Returns the sub-history containing only the operations whose calls
+    /// have the given ids, along with their timestamps and metadata.
+    fn restrict(&self, calls: &[EntryId]) -> History<T>
+    where
+        T: Clone,
+    {
+        let mut keep = vec![false; self.entries.len()];
+        for &id in calls {
+            if let Entry::Call(call) = &self.entries[id] {
+                keep[id] = true;
+                keep[call.response] = true;
             }
-            None => panic!(
-                "Index that entry {} was removed from is unknown",
-                entry.id()
-            ),
         }
+        let ids: Vec<EntryId> = successors(self.first(), |&id| self.next(id))
+            .filter(|&id| keep[id])
+            .collect();
+        let actions = ids
+            .iter()
+            .map(|&id| (self.processes[id], self.entries[id].clone().into_action()))
+            .collect::<Vec<_>>();
+        let mut history = if actions.is_empty() {
+            History::from_entries(Vec::new(), Vec::new())
+        } else {
+            History::from_actions(actions)
+        };
+        history.timestamps = self
+            .timestamps
+            .as_ref()
+            .map(|timestamps| ids.iter().map(|&id| timestamps[id]).collect());
+        history.metadata = self
+            .metadata
+            .as_ref()
+            .map(|metadata| ids.iter().map(|&id| metadata[id].clone()).collect());
+        history
     }
 
-    pub(super) fn is_empty(&self) -> bool {
-        self.entries.is_empty()
+    /// Returns the id of the first entry in the history, if any.
+    pub(super) fn first(&self) -> Option<EntryId> {
+        self.after(self.sentinel())
     }
 
-    pub(super) fn iter(&self) -> impl Iterator<Item = &Entry<T>> {
-        self.entries.iter()
+    /// Returns the id of the entry that follows the entry with the given id,
+    /// if any.
+    pub(super) fn next(&self, id: EntryId) -> Option<EntryId> {
+        self.after(id)
     }
 
-    pub(super) fn len(&self) -> usize {
+    fn after(&self, i: usize) -> Option<EntryId> {
+        let next = self.next[i];
+        (next != self.sentinel()).then_some(next)
+    }
+
+    fn sentinel(&self) -> usize {
         self.entries.len()
     }
 
-    pub(super) fn lift(&mut self, i: usize) -> (Entry<T>, Entry<T>) {
-        match self.remove(i) {
+    /// Re-inserts a removed entry into the history.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry is not the one most recently removed from the
+    /// history.
+    fn insert(&mut self, id: EntryId) {
+        let (prev, next) = (self.prev[id], self.next[id]);
+        assert!(
+            self.next[prev] == next && self.prev[next] == prev,
+            "Entry {id} was not the most recently removed entry"
+        );
+        self.next[prev] = id;
+        self.prev[next] = id;
+        self.len += 1;
+    }
+
+    /// Returns whether there are no entries in the history.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the entries in the history, in order.
+    pub fn iter(&self) -> impl Iterator<Item = &Entry<T>> {
+        successors(self.first(), |&id| self.next(id)).map(|id| &self.entries[id])
+    }
+
+    /// Returns the number of entries in the history.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Removes a call entry, along with its response, from the history and
+    /// returns the id of the response.
+    pub(super) fn lift(&mut self, id: EntryId) -> EntryId {
+        match &self.entries[id] {
             Entry::Response(_) => panic!("Cannot lift a response entry out of the history"),
             Entry::Call(call) => {
-                let response = self.remove(self.index_of_id(call.response));
-                (Entry::Call(call), response)
+                let response = call.response;
+                self.remove(id);
+                self.remove(response);
+                response
             }
         }
     }
 
-    fn remove(&mut self, i: usize) -> Entry<T> {
-        let entry = self.entries.remove(i);
-        self.removed_from[entry.id()] = Some(i);
-        entry
+    /// Removes an entry from the history.
+    fn remove(&mut self, id: EntryId) {
+        let (prev, next) = (self.prev[id], self.next[id]);
+        self.next[prev] = next;
+        self.prev[next] = prev;
+        self.len -= 1;
     }
 
-    pub(super) fn unlift(&mut self, call: Entry<T>, response: Entry<T>) -> (usize, usize) {
-        let response_index = self.insert(response);
-        let call_index = self.insert(call);
-        (call_index, response_index)
+    /// Re-inserts a call entry and its response, which must be the entries
+    /// most recently lifted out of the history.
+    pub(super) fn unlift(&mut self, id: EntryId) {
+        match &self.entries[id] {
+            Entry::Response(_) => panic!("Cannot unlift a response entry into the history"),
+            Entry::Call(call) => {
+                let response = call.response;
+                self.insert(response);
+                self.insert(id);
+            }
+        }
     }
 }
 
@@ -281,6 +817,29 @@ mod tests {
             }
         }
 
+        #[test]
+        fn records_outcome_of_each_operation() {
+            let history = History::from_actions(vec![
+                (0, Call("a")),
+                (0, Response("a")),
+                (0, Call("b")),
+                (0, Action::Failed("b")),
+                (0, Call("c")),
+                (0, Action::Indeterminate("c")),
+            ]);
+            let outcomes: Vec<Outcome> = history
+                .iter()
+                .filter_map(|entry| match entry {
+                    Entry::Call(_) => None,
+                    Entry::Response(response) => Some(response.outcome),
+                })
+                .collect();
+            assert_eq!(
+                outcomes,
+                vec![Outcome::Completed, Outcome::Failed, Outcome::Indeterminate]
+            );
+        }
+
         #[test]
         fn links_actions_of_multiple_processes() {
             let history = History::from_actions(vec![
@@ -300,7 +859,7 @@ mod tests {
             for entry in history.iter() {
                 println!("{:?}", entry);
                 if let Entry::Call(call) = entry {
-                    match &history[history.index_of_id(call.response)] {
+                    match &history[call.response] {
                         Entry::Response(response) => assert_eq!(call.operation, response.operation),
                         Entry::Call(_) => panic!("Call entry was linked to another call entry"),
                     }
@@ -320,7 +879,7 @@ mod tests {
             ]);
             for entry in history.iter() {
                 if let Entry::Call(call) = entry {
-                    match &history[history.index_of_id(call.response)] {
+                    match &history[call.response] {
                         Entry::Response(response) => assert_eq!(call.operation, response.operation),
                         Entry::Call(_) => panic!("Call entry was linked to another call entry"),
                     }
@@ -329,6 +888,187 @@ mod tests {
         }
     }
 
+    mod from_timed_actions {
+        use super::*;
+
+        fn timed<T>(
+            start: Instant,
+            process: ProcessId,
+            action: Action<T>,
+            millis: u64,
+        ) -> TimedAction<T> {
+            TimedAction {
+                process,
+                action,
+                happened_at: start + Duration::from_millis(millis),
+            }
+        }
+
+        #[test]
+        fn sorts_actions_by_time() {
+            let start = Instant::now();
+            let history = History::from_timed_actions(vec![
+                timed(start, 1, Response("b"), 3),
+                timed(start, 0, Call("a"), 0),
+                timed(start, 1, Call("b"), 1),
+                timed(start, 0, Response("a"), 2),
+            ]);
+            let expected = History::from_actions(vec![
+                (0, Call("a")),
+                (1, Call("b")),
+                (0, Response("a")),
+                (1, Response("b")),
+            ]);
+            assert_eq!(history.entries, expected.entries);
+        }
+
+        #[test]
+        fn keeps_order_of_simultaneous_actions() {
+            let start = Instant::now();
+            let history = History::from_timed_actions(vec![
+                timed(start, 0, Call("a"), 0),
+                timed(start, 0, Response("a"), 0),
+                timed(start, 0, Call("b"), 0),
+                timed(start, 0, Response("b"), 0),
+            ]);
+            assert_eq!(
+                history[1],
+                Entry::Response(ResponseEntry {
+                    id: 1,
+                    operation: "a",
+                    outcome: Outcome::Completed,
+                })
+            );
+        }
+
+        #[test]
+        fn measures_timestamps_from_earliest_action() {
+            let start = Instant::now();
+            let history = History::from_timed_actions(vec![
+                timed(start, 0, Response("a"), 7),
+                timed(start, 0, Call("a"), 5),
+            ]);
+            assert_eq!(
+                history.timestamps,
+                Some(vec![Duration::ZERO, Duration::from_millis(2)])
+            );
+        }
+
+        #[test]
+        #[should_panic(expected = "Process 0 returned from an operation before calling it")]
+        fn panics_if_response_happened_before_call() {
+            let start = Instant::now();
+            History::from_timed_actions(vec![
+                timed(start, 0, Call("a"), 2),
+                timed(start, 0, Response("a"), 1),
+            ]);
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "Process 1 called an operation before its previous operation returned"
+        )]
+        fn panics_if_process_has_overlapping_operations() {
+            let start = Instant::now();
+            History::from_timed_actions(vec![
+                timed(start, 1, Call("a"), 0),
+                timed(start, 1, Call("b"), 1),
+                timed(start, 1, Response("a"), 2),
+                timed(start, 1, Response("b"), 3),
+            ]);
+        }
+    }
+
+    mod ordered_by_timestamps {
+        use super::*;
+
+        fn millis(times: &[u64]) -> Vec<Duration> {
+            times.iter().copied().map(Duration::from_millis).collect()
+        }
+
+        #[test]
+        fn returns_history_without_timestamps_unchanged() {
+            let history = History::from_actions(vec![
+                (0, Call("a")),
+                (0, Response("a")),
+                (1, Call("b")),
+                (1, Response("b")),
+            ]);
+            assert_eq!(
+                history
+                    .clone()
+                    .ordered_by_timestamps(Duration::from_secs(1)),
+                history
+            );
+        }
+
+        #[test]
+        fn orders_entries_by_timestamps() {
+            let history = History::from_actions(vec![
+                (0, Call("a")),
+                (0, Response("a")),
+                (1, Call("b")),
+                (1, Response("b")),
+            ])
+            .with_timestamps(millis(&[0, 2, 1, 3]));
+            let expected = History::from_actions(vec![
+                (0, Call("a")),
+                (1, Call("b")),
+                (0, Response("a")),
+                (1, Response("b")),
+            ])
+            .with_timestamps(millis(&[0, 1, 2, 3]));
+            assert_eq!(history.ordered_by_timestamps(Duration::ZERO), expected);
+        }
+
+        #[test]
+        fn overlaps_operations_within_tolerance() {
+            let history = History::from_actions(vec![
+                (0, Call("a")),
+                (0, Response("a")),
+                (1, Call("b")),
+                (1, Response("b")),
+            ])
+            .with_timestamps(millis(&[0, 1, 2, 3]));
+            let expected = History::from_actions(vec![
+                (0, Call("a")),
+                (1, Call("b")),
+                (0, Response("a")),
+                (1, Response("b")),
+            ])
+            .with_timestamps(millis(&[0, 2, 1, 3]));
+            assert_eq!(
+                history
+                    .clone()
+                    .ordered_by_timestamps(Duration::from_millis(1)),
+                expected
+            );
+            assert_eq!(
+                history
+                    .clone()
+                    .ordered_by_timestamps(Duration::from_micros(999)),
+                history
+            );
+        }
+
+        #[test]
+        fn keeps_order_of_operations_by_same_process() {
+            let history = History::from_actions(vec![
+                (0, Call("a")),
+                (0, Response("a")),
+                (0, Call("b")),
+                (0, Response("b")),
+            ])
+            .with_timestamps(millis(&[0, 1, 2, 3]));
+            assert_eq!(
+                history
+                    .clone()
+                    .ordered_by_timestamps(Duration::from_secs(1)),
+                history
+            );
+        }
+    }
+
     mod insert {
         use super::*;
 
@@ -343,11 +1083,23 @@ mod tests {
                 (2, Response("c")),
             ]);
             let mut copy = history.clone();
-            let entry = copy.remove(1);
-            copy.insert(entry);
-            for (copy, entry) in zip(copy.iter(), history.iter()) {
-                assert_eq!(copy, entry);
-            }
+            copy.remove(1);
+            copy.insert(1);
+            assert_eq!(copy, history);
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_entry_was_not_most_recently_removed() {
+            let mut history = History::from_actions(vec![
+                (0, Call("a")),
+                (1, Call("b")),
+                (0, Response("a")),
+                (1, Response("b")),
+            ]);
+            history.remove(1);
+            history.remove(2);
+            history.insert(1);
         }
     }
 
@@ -364,7 +1116,8 @@ mod tests {
                 (1, Response("b")),
                 (2, Response("c")),
             ]);
-            history.lift(0);
+            assert_eq!(history.lift(0), 3);
+            assert_eq!(history.len(), 4);
             for (entry, letter) in zip(history.iter(), ["b", "c", "b", "c"]) {
                 match entry {
                     Entry::Call(call) => assert_eq!(call.operation, letter),
@@ -374,6 +1127,98 @@ mod tests {
         }
     }
 
+    mod partition {
+        use super::*;
+
+        #[test]
+        fn groups_operations_by_key() {
+            let history = History::from_actions(vec![
+                (0, Call(("x", 1))),
+                (1, Call(("y", 2))),
+                (2, Call(("x", 3))),
+                (1, Response(("y", 2))),
+                (0, Response(("x", 1))),
+                (2, Response(("x", 3))),
+            ]);
+            let parts = history.partition(|(key, _)| *key);
+            let values: Vec<Vec<u32>> = parts
+                .iter()
+                .map(|part| {
+                    part.iter()
+                        .map(|entry| match entry {
+                            Entry::Call(call) => call.operation.1,
+                            Entry::Response(response) => response.operation.1,
+                        })
+                        .collect()
+                })
+                .collect();
+            assert_eq!(values, vec![vec![1, 3, 1, 3], vec![2, 2]]);
+        }
+
+        #[test]
+        fn links_calls_to_responses_within_each_part() {
+            let history = History::from_actions(vec![
+                (0, Call(("x", 1))),
+                (1, Call(("y", 2))),
+                (2, Call(("x", 3))),
+                (1, Response(("y", 2))),
+                (2, Response(("x", 3))),
+                (0, Response(("x", 1))),
+            ]);
+            for part in history.partition(|(key, _)| *key) {
+                for (i, entry) in part.iter().enumerate() {
+                    assert_eq!(entry.id(), i);
+                    if let Entry::Call(call) = entry {
+                        match &part[call.response] {
+                            Entry::Response(response) => {
+                                assert_eq!(call.operation, response.operation)
+                            }
+                            Entry::Call(_) => panic!("Call entry was linked to another call entry"),
+                        }
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn preserves_timestamps() {
+            let history = History::from_actions(vec![
+                (0, Call(("x", 1))),
+                (1, Call(("y", 2))),
+                (0, Response(("x", 1))),
+                (1, Response(("y", 2))),
+            ])
+            .with_timestamps((0..4).map(Duration::from_secs).collect());
+            let parts = history.partition(|(key, _)| *key);
+            assert_eq!(
+                parts[0].timestamps,
+                Some(vec![Duration::from_secs(0), Duration::from_secs(2)])
+            );
+            assert_eq!(
+                parts[1].timestamps,
+                Some(vec![Duration::from_secs(1), Duration::from_secs(3)])
+            );
+        }
+
+        #[test]
+        fn places_responses_with_their_calls() {
+            let history =
+                History::from_actions(vec![(0, Call(("x", None))), (0, Response(("y", Some(1))))]);
+            assert_eq!(history.partition(|(key, _)| *key).len(), 1);
+        }
+    }
+
+    mod with_timestamps {
+        use super::*;
+
+        #[test]
+        #[should_panic(expected = "History has 2 entries, but 1 timestamps")]
+        fn panics_if_number_of_timestamps_differs() {
+            History::from_actions(vec![(0, Call("a")), (0, Response("a"))])
+                .with_timestamps(vec![Duration::ZERO]);
+        }
+    }
+
     mod remove {
         use super::*;
 
@@ -385,10 +1230,8 @@ mod tests {
                 (0, Response("a")),
                 (1, Response("b")),
             ]);
-            match history.remove(1) {
-                Entry::Call(call) => assert_eq!(call.operation, "b"),
-                Entry::Response(_) => panic!("Removed incorrect entry"),
-            }
+            history.remove(1);
+            assert_eq!(history.len(), 3);
             for (entry, letter) in zip(history.iter(), ["a", "a", "b"]) {
                 match entry {
                     Entry::Call(entry) => assert_eq!(entry.operation, letter),
@@ -410,8 +1253,30 @@ mod tests {
                 (1, Response("b")),
             ]);
             let copy = history.clone();
-            let (call, response) = history.lift(0);
-            history.unlift(call, response);
+            history.lift(0);
+            history.unlift(0);
+            assert_eq!(history, copy)
+        }
+
+        #[test]
+        fn is_inverse_of_nested_lifts() {
+            let mut history = History::from_actions(vec![
+                (0, Call("a")),
+                (1, Call("b")),
+                (2, Call("c")),
+                (0, Response("a")),
+                (1, Response("b")),
+                (2, Response("c")),
+            ]);
+            let copy = history.clone();
+            history.lift(1);
+            history.lift(0);
+            history.lift(2);
+            assert!(history.is_empty());
+            assert_eq!(history.first(), None);
+            history.unlift(2);
+            history.unlift(0);
+            history.unlift(1);
             assert_eq!(history, copy)
         }
     }
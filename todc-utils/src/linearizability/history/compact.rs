@@ -0,0 +1,270 @@
+//! Compacting histories, by removing operations that cannot change whether
+//! they are linearizable.
+//!
+//! Long stress tests record histories with millions of operations, most of
+//! which are reads that return the same value as the read before them. The
+//! time taken to check a history grows quickly with its size, and so
+//! removing these reads before checking it can make a large history
+//! checkable.
+use super::{Entry, EntryId, History};
+use crate::specifications::DurableSpecification;
+
+impl<T: Clone + PartialEq> History<T> {
+    /// Returns the sub-history in which each repetition of an operation that
+    /// does not change the state of the object, such as a read, is removed
+    /// when doing so does not affect whether the history is linearizable.
+    ///
+    /// Timestamps and metadata, if any, are kept.
+    ///
+    /// # Soundness
+    ///
+    /// An operation `b` of some process is removed if all of the following hold,
+    /// where `a` is the last operation of the same process that was kept:
+    ///
+    /// 1. The process performed no other operations between `a` and `b`, except
+    ///    for operations that were themselves removed.
+    /// 2. `a` and `b` were called with equal operations, and returned equal
+    ///    operations.
+    /// 3. The operation does not change the state of the object, according to
+    ///    [`DurableSpecification::is_update`]. That is, for every state `s`,
+    ///    applying the operation to `s` either fails or leaves the object in
+    ///    state `s`.
+    /// 4. No operation of any other process returned between the call of `a`
+    ///    and the call of `b`.
+    ///
+    /// Under these conditions, the history is linearizable if and only if the
+    /// compacted history is. Removing an operation that does not change the state
+    /// of the object can never make a linearizable history non-linearizable.
+    /// Conversely, given a linearization of the compacted history, placing `b`
+    /// immediately after `a` yields a linearization of the original history:
+    /// `b` is valid in the state that `a` was valid in, and leaves it unchanged,
+    /// and by (4), every operation that returned before `b` was called also
+    /// returned before `a` was called, and so is already linearized before `a`.
+    ///
+    /// Condition (4) is what makes the compaction sound, but it also limits how
+    /// many operations are removed. Reads by a process that is the only one
+    /// performing operations at the time are always removed, while reads that
+    /// overlap with operations that complete are kept. In particular, a read
+    /// that returns a stale value after a concurrent write completed is never
+    /// removed, and so compaction cannot hide that violation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::{History, Action::{Call, Response}};
+    /// use todc_utils::specifications::register::{RegisterOperation::{Read, Write}, RegisterSpecification};
+    ///
+    /// let history = History::from_actions(vec![
+    ///     (0, Call(Write(1))),
+    ///     (0, Response(Write(1))),
+    ///     (1, Call(Read(None))),
+    ///     (1, Response(Read(Some(1)))),
+    ///     (1, Call(Read(None))),
+    ///     (1, Response(Read(Some(1)))),
+    ///     (1, Call(Read(None))),
+    ///     (1, Response(Read(Some(1)))),
+    /// ]);
+    /// let compacted = history.compact::<RegisterSpecification<u32>>();
+    /// assert_eq!(compacted.len(), 4);
+    /// ```
+    pub fn compact<S>(&self) -> History<T>
+    where
+        S: DurableSpecification<Operation = T>,
+    {
+        let processes = self.processes.iter().max().map_or(0, |&max| max + 1);
+        // The last operation of each process that was kept, along with the
+        // number of operations of other processes that had returned when it
+        // was called.
+        let mut last: Vec<Option<(EntryId, usize)>> = vec![None; processes];
+        // The number of responses seen so far, in total and by each process.
+        let mut returned = 0;
+        let mut own = vec![0; processes];
+
+        let mut calls: Vec<EntryId> = Vec::new();
+        for entry in self.iter() {
+            let process = self.processes[entry.id()];
+            let call = match entry {
+                Entry::Response(_) => {
+                    returned += 1;
+                    own[process] += 1;
+                    continue;
+                }
+                Entry::Call(call) => call,
+            };
+            let others = returned - own[process];
+            if let Some((previous, before)) = last[process] {
+                if before == others
+                    && self.repeats(previous, call.id)
+                    && !S::is_update(&call.operation)
+                {
+                    continue;
+                }
+            }
+            last[process] = Some((call.id, others));
+            calls.push(call.id);
+        }
+        self.restrict(&calls)
+    }
+
+    /// Returns whether the operations with the given calls were called with
+    /// equal operations, and returned equal operations with equal outcomes.
+    fn repeats(&self, first: EntryId, second: EntryId) -> bool {
+        match (&self.entries[first], &self.entries[second]) {
+            (Entry::Call(first), Entry::Call(second)) => {
+                let responses = (
+                    &self.entries[first.response],
+                    &self.entries[second.response],
+                );
+                let (Entry::Response(a), Entry::Response(b)) = responses else {
+                    return false;
+                };
+                first.operation == second.operation
+                    && a.operation == b.operation
+                    && a.outcome == b.outcome
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::linearizability::history::Action::{Call, Response};
+    use crate::specifications::register::RegisterOperation::{self, Read, Write};
+    use crate::specifications::register::RegisterSpecification;
+    use crate::WGLChecker;
+
+    type Operation = RegisterOperation<u32>;
+    type Spec = RegisterSpecification<u32>;
+
+    fn is_linearizable(history: &History<Operation>) -> bool {
+        WGLChecker::<Spec>::is_linearizable(history.clone())
+    }
+
+    mod compact {
+        use super::*;
+
+        #[test]
+        fn removes_repeated_reads_of_same_process() {
+            let history = History::from_actions(vec![
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+            ]);
+            assert_eq!(history.compact::<Spec>().len(), 2);
+        }
+
+        #[test]
+        fn keeps_reads_of_different_values() {
+            let history = History::from_actions(vec![
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+                (1, Call(Write(1))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(1)))),
+                (1, Response(Write(1))),
+            ]);
+            assert_eq!(history.compact::<Spec>().len(), 6);
+        }
+
+        #[test]
+        fn keeps_repeated_writes() {
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (0, Response(Write(1))),
+                (0, Call(Write(1))),
+                (0, Response(Write(1))),
+            ]);
+            assert_eq!(history.compact::<Spec>().len(), 4);
+        }
+
+        #[test]
+        fn keeps_reads_separated_by_other_operation_of_same_process() {
+            let history = History::from_actions(vec![
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+                (0, Call(Write(0))),
+                (0, Response(Write(0))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+            ]);
+            assert_eq!(history.compact::<Spec>().len(), 6);
+        }
+
+        #[test]
+        fn keeps_stale_read_after_concurrent_write_returns() {
+            // P0 |--|                  Read(0)
+            // P1      |--| |--|        Write(1), Read(1)
+            // P0                 |--|  Read(0)
+            let history = History::from_actions(vec![
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+                (1, Call(Write(1))),
+                (1, Response(Write(1))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(1)))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+            ]);
+            let compacted = history.compact::<Spec>();
+            assert_eq!(compacted.len(), 8);
+            assert!(!is_linearizable(&compacted));
+        }
+
+        #[test]
+        fn removes_reads_overlapping_operations_that_are_still_running() {
+            let history = History::from_actions(vec![
+                (1, Call(Write(1))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+                (1, Response(Write(1))),
+            ]);
+            let compacted = history.compact::<Spec>();
+            assert_eq!(compacted.len(), 4);
+            assert!(is_linearizable(&compacted));
+        }
+
+        #[test]
+        fn preserves_whether_history_is_linearizable() {
+            let history = History::from_actions(vec![
+                (0, Call(Read(None))),
+                (1, Call(Write(1))),
+                (0, Response(Read(Some(0)))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+                (0, Call(Read(None))),
+                (1, Response(Write(1))),
+                (0, Response(Read(Some(0)))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(1)))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(1)))),
+            ]);
+            let compacted = history.compact::<Spec>();
+            assert_eq!(compacted.len(), 6);
+            assert_eq!(is_linearizable(&compacted), is_linearizable(&history));
+        }
+
+        #[test]
+        fn keeps_timestamps_of_remaining_entries() {
+            let millis = |ms| Duration::from_millis(ms);
+            let history = History::from_actions(vec![
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(0)))),
+            ])
+            .with_timestamps(vec![millis(0), millis(3), millis(5), millis(6)]);
+            let compacted = history.compact::<Spec>();
+            assert_eq!(compacted.stats().mean_latency, Some(millis(3)));
+        }
+    }
+}
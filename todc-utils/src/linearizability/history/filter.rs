@@ -0,0 +1,356 @@
+//! Selecting and transforming the operations of a history, so that large
+//! recorded histories can be trimmed before they are checked.
+//!
+//! Every method returns a history that is complete, in which each operation
+//! that remains has both its call and its response, and in which entries
+//! keep their relative order, timestamps and metadata.
+use std::ops::RangeBounds;
+use std::time::Duration;
+
+use super::{CallEntry, Entry, EntryId, History, ProcessId, ResponseEntry};
+
+impl<T: Clone> History<T> {
+    /// Returns the sub-history containing only the operations performed by
+    /// processes for which `keep` returns `true`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::{History, Action::{Call, Response}};
+    ///
+    /// let history = History::from_actions(vec![
+    ///     (0, Call("a")),
+    ///     (1, Call("b")),
+    ///     (2, Call("c")),
+    ///     (0, Response("a")),
+    ///     (1, Response("b")),
+    ///     (2, Response("c")),
+    /// ]);
+    /// let filtered = history.filter_by_process(|process| process != 1);
+    /// assert_eq!(filtered.len(), 4);
+    /// ```
+    pub fn filter_by_process(&self, mut keep: impl FnMut(ProcessId) -> bool) -> History<T> {
+        self.filter_operations(|history, call| keep(history.processes[call.id]))
+    }
+
+    /// Returns the sub-history containing only the operations whose call or
+    /// response has the given tag in its [`Metadata`](super::Metadata).
+    ///
+    /// If the history has no metadata, then no operations have tags, and the
+    /// returned history is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::{History, Action::{Call, Response}};
+    /// use todc_utils::linearizability::history::Metadata;
+    ///
+    /// let tagged = |tags: &[&str]| Metadata {
+    ///     tags: tags.iter().map(|tag| tag.to_string()).collect(),
+    ///     ..Metadata::default()
+    /// };
+    /// let history = History::from_actions(vec![
+    ///     (0, Call("a")),
+    ///     (0, Response("a")),
+    ///     (0, Call("b")),
+    ///     (0, Response("b")),
+    /// ])
+    /// .with_metadata(vec![tagged(&["warmup"]), tagged(&[]), tagged(&[]), tagged(&[])]);
+    /// assert_eq!(history.filter_by_tag("warmup").len(), 2);
+    /// ```
+    pub fn filter_by_tag(&self, tag: &str) -> History<T> {
+        self.filter_operations(|history, call| {
+            [call.id, call.response].iter().any(|&id| {
+                history
+                    .metadata(id)
+                    .is_some_and(|metadata| metadata.has_tag(tag))
+            })
+        })
+    }
+
+    /// Returns the sub-history containing only the operations that were both
+    /// called and returned within a range of time.
+    ///
+    /// Operations that are only partly within the range are removed. As a
+    /// result, an operation that remains may depend on one that was removed,
+    /// such as a read of a value that was written before the range began, in
+    /// which case the sub-history may not be linearizable even though the
+    /// history is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the history does not have timestamps.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use todc_utils::{History, Action::{Call, Response}};
+    ///
+    /// let history = History::from_actions(vec![
+    ///     (0, Call("a")),
+    ///     (1, Call("b")),
+    ///     (0, Response("a")),
+    ///     (1, Response("b")),
+    /// ])
+    /// .with_timestamps([0, 4, 5, 7].into_iter().map(Duration::from_millis).collect());
+    /// let sliced = history.slice_by_time_range(Duration::from_millis(3)..);
+    /// assert_eq!(sliced.len(), 2);
+    /// ```
+    pub fn slice_by_time_range(&self, range: impl RangeBounds<Duration>) -> History<T> {
+        assert!(
+            self.timestamps.is_some(),
+            "Cannot slice a history without timestamps"
+        );
+        self.filter_operations(|history, call| {
+            [call.id, call.response]
+                .iter()
+                .all(|&id| range.contains(&history.timestamp(id).unwrap()))
+        })
+    }
+
+    /// Returns the sub-history containing only the operations whose calls
+    /// satisfy a predicate.
+    fn filter_operations(&self, mut keep: impl FnMut(&Self, &CallEntry<T>) -> bool) -> History<T> {
+        let calls: Vec<EntryId> = self
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Call(call) if keep(self, call) => Some(call.id),
+                _ => None,
+            })
+            .collect();
+        self.restrict(&calls)
+    }
+}
+
+impl<T> History<T> {
+    /// Returns a history of the same actions, in which the operation of each
+    /// entry is transformed by `f`.
+    ///
+    /// This is useful to check a history against a simpler specification,
+    /// for example by dropping the parts of each operation that the
+    /// specification does not model. Calls and responses are transformed
+    /// separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::{History, Action::{Call, Response}};
+    /// use todc_utils::linearizability::history::Entry;
+    ///
+    /// let history = History::from_actions(vec![
+    ///     (0, Call(("x", 1))),
+    ///     (0, Response(("x", 1))),
+    /// ]);
+    /// let values = history.project(|(_, value)| *value);
+    /// assert!(matches!(&values[0], Entry::Call(call) if call.operation == 1));
+    /// ```
+    pub fn project<U>(&self, mut f: impl FnMut(&T) -> U) -> History<U> {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                Entry::Call(call) => Entry::Call(CallEntry {
+                    id: call.id,
+                    operation: f(&call.operation),
+                    response: call.response,
+                }),
+                Entry::Response(response) => Entry::Response(ResponseEntry {
+                    id: response.id,
+                    operation: f(&response.operation),
+                    outcome: response.outcome,
+                }),
+            })
+            .collect();
+        History {
+            entries,
+            processes: self.processes.clone(),
+            next: self.next.clone(),
+            prev: self.prev.clone(),
+            len: self.len,
+            timestamps: self.timestamps.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use super::*;
+    use crate::linearizability::history::Action::{Call, Failed, Indeterminate, Response};
+    use crate::linearizability::history::{Metadata, Outcome};
+
+    /// Returns a history in which processes `0` and `1` each perform two
+    /// operations, concurrently, and each entry happens `1ms` after the
+    /// previous one.
+    fn history() -> History<&'static str> {
+        History::from_actions(vec![
+            (0, Call("a")),
+            (1, Call("b")),
+            (0, Response("a")),
+            (0, Call("c")),
+            (1, Response("b")),
+            (1, Call("d")),
+            (0, Response("c")),
+            (1, Response("d")),
+        ])
+        .with_timestamps((0..8).map(Duration::from_millis).collect())
+    }
+
+    /// Returns the operations of the calls in a history, in order.
+    fn calls<T: Clone>(history: &History<T>) -> Vec<T> {
+        history
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Call(call) => Some(call.operation.clone()),
+                Entry::Response(_) => None,
+            })
+            .collect()
+    }
+
+    /// Asserts that every call in a history is linked to a response to the
+    /// same operation, by the same process.
+    fn assert_is_well_formed<T: Debug + PartialEq>(history: &History<T>) {
+        for entry in history.iter() {
+            if let Entry::Call(call) = entry {
+                match &history[call.response] {
+                    Entry::Response(response) => assert_eq!(call.operation, response.operation),
+                    Entry::Call(_) => panic!("Call entry was linked to another call entry"),
+                }
+                assert_eq!(history.process(call.id), history.process(call.response));
+            }
+        }
+    }
+
+    mod filter_by_process {
+        use super::*;
+
+        #[test]
+        fn keeps_only_operations_of_selected_processes() {
+            let filtered = history().filter_by_process(|process| process == 1);
+            assert_eq!(calls(&filtered), vec!["b", "d"]);
+            assert_is_well_formed(&filtered);
+        }
+
+        #[test]
+        fn keeps_timestamps_of_remaining_entries() {
+            let filtered = history().filter_by_process(|process| process == 1);
+            let times: Vec<_> = (0..filtered.len())
+                .map(|id| filtered.timestamp(id).unwrap())
+                .collect();
+            assert_eq!(times, [1, 4, 5, 7].map(Duration::from_millis));
+        }
+
+        #[test]
+        fn keeps_outcomes_of_remaining_operations() {
+            let history = History::from_actions(vec![
+                (0, Call("a")),
+                (1, Call("b")),
+                (0, Failed("a")),
+                (1, Indeterminate("b")),
+            ]);
+            let filtered = history.filter_by_process(|process| process == 1);
+            match &filtered[1] {
+                Entry::Response(response) => assert_eq!(response.outcome, Outcome::Indeterminate),
+                Entry::Call(_) => panic!("Expected a response entry"),
+            }
+        }
+
+        #[test]
+        fn returns_empty_history_if_no_process_is_selected() {
+            let filtered = history().filter_by_process(|_| false);
+            assert!(filtered.is_empty());
+        }
+    }
+
+    mod filter_by_tag {
+        use super::*;
+
+        fn tagged(tags: &[&str]) -> Metadata {
+            Metadata {
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                ..Metadata::default()
+            }
+        }
+
+        #[test]
+        fn keeps_operations_tagged_on_call_or_response() {
+            let history = history().with_metadata(vec![
+                tagged(&["x"]),
+                tagged(&[]),
+                tagged(&[]),
+                tagged(&["y"]),
+                tagged(&[]),
+                tagged(&[]),
+                tagged(&["x"]),
+                tagged(&["y", "x"]),
+            ]);
+            let filtered = history.filter_by_tag("x");
+            assert_eq!(calls(&filtered), vec!["a", "c", "d"]);
+            assert_is_well_formed(&filtered);
+            assert!(filtered.metadata(0).unwrap().has_tag("x"));
+        }
+
+        #[test]
+        fn returns_empty_history_without_metadata() {
+            assert!(history().filter_by_tag("x").is_empty());
+        }
+    }
+
+    mod slice_by_time_range {
+        use super::*;
+
+        #[test]
+        fn keeps_operations_entirely_within_range() {
+            let millis = Duration::from_millis;
+            let sliced = history().slice_by_time_range(millis(1)..=millis(6));
+            assert_eq!(calls(&sliced), vec!["b", "c"]);
+            assert_is_well_formed(&sliced);
+        }
+
+        #[test]
+        fn keeps_every_operation_within_unbounded_range() {
+            assert_eq!(history().slice_by_time_range(..).len(), 8);
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_without_timestamps() {
+            let history = History::from_actions(vec![(0, Call("a")), (0, Response("a"))]);
+            history.slice_by_time_range(..);
+        }
+    }
+
+    mod project {
+        use super::*;
+
+        #[test]
+        fn transforms_every_operation() {
+            let projected = history().project(|operation| operation.to_uppercase());
+            assert_eq!(calls(&projected), vec!["A", "B", "C", "D"]);
+            assert_is_well_formed(&projected);
+        }
+
+        #[test]
+        fn keeps_processes_and_timestamps() {
+            let history = history();
+            let projected = history.project(|operation| operation.len());
+            for id in 0..history.len() {
+                assert_eq!(projected.process(id), history.process(id));
+                assert_eq!(projected.timestamp(id), history.timestamp(id));
+            }
+        }
+
+        #[test]
+        fn keeps_entries_that_were_lifted_out() {
+            let mut history = history();
+            history.lift(0);
+            let mut projected = history.project(|operation| operation.to_uppercase());
+            assert_eq!(projected.len(), 6);
+            projected.unlift(0);
+            assert_eq!(calls(&projected), vec!["A", "B", "C", "D"]);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! Rendering histories as timelines, for humans to inspect.
+use std::fmt::{Debug, Write};
+
+use super::{Entry, History, ProcessId};
+
+/// The width, in characters, of each entry in an ASCII timeline.
+const CHARS_PER_ENTRY: usize = 2;
+
+/// The width, in pixels, of each entry in an SVG timeline.
+const PIXELS_PER_ENTRY: usize = 24;
+
+/// The height, in pixels, of each operation in an SVG timeline.
+const PIXELS_PER_OPERATION: usize = 28;
+
+/// The width, in pixels, of the process labels in an SVG timeline.
+const LABEL_WIDTH: usize = 48;
+
+/// An operation in a history, along with the positions of its call and
+/// response entries.
+struct Span<'a, T> {
+    process: ProcessId,
+    call: &'a T,
+    response: &'a T,
+    start: usize,
+    end: usize,
+}
+
+impl<T: Debug> History<T> {
+    /// Returns an ASCII diagram of the operations in the history.
+    ///
+    /// Each operation is drawn on its own row, in the order that operations
+    /// were called, as a span from its call to its response. Rows are
+    /// labelled with the process that performed the operation, and the
+    /// operation contained in its response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::{History, Action::{Call, Response}};
+    /// use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+    ///
+    /// let history = History::from_actions(vec![
+    ///     (0, Call(Write(0))),
+    ///     (1, Call(Read(None))),
+    ///     (1, Response(Read(Some(0)))),
+    ///     (0, Response(Write(0))),
+    /// ]);
+    /// assert_eq!(
+    ///     history.render_timeline(),
+    ///     "P0 |-----| Write(0)\n\
+    ///      P1   |-|   Read(Some(0))\n"
+    /// );
+    /// ```
+    pub fn render_timeline(&self) -> String {
+        let spans = self.spans();
+        let width = CHARS_PER_ENTRY * self.len().saturating_sub(1) + 1;
+        let labels: Vec<String> = spans.iter().map(|s| format!("P{}", s.process)).collect();
+        let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+
+        let mut timeline = String::new();
+        for (span, label) in spans.iter().zip(labels) {
+            let start = CHARS_PER_ENTRY * span.start;
+            let end = CHARS_PER_ENTRY * span.end;
+            let bar = format!("{}|{}|", " ".repeat(start), "-".repeat(end - start - 1));
+            writeln!(
+                timeline,
+                "{label:<label_width$} {bar:<width$} {:?}",
+                span.response
+            )
+            .unwrap();
+        }
+        timeline
+    }
+
+    /// Returns an HTML document containing an SVG diagram of the operations
+    /// in the history.
+    ///
+    /// The diagram is laid out in the same way as
+    /// [`render_timeline`](History::render_timeline). Hovering over an
+    /// operation shows the process that performed it, along with its call and
+    /// response.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use todc_utils::{History, Action::{Call, Response}};
+    /// use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+    ///
+    /// let history = History::from_actions(vec![
+    ///     (0, Call(Write(0))),
+    ///     (1, Call(Read(None))),
+    ///     (1, Response(Read(Some(0)))),
+    ///     (0, Response(Write(0))),
+    /// ]);
+    /// std::fs::write("history.html", history.render_html()).unwrap();
+    /// ```
+    pub fn render_html(&self) -> String {
+        let spans = self.spans();
+        let width = LABEL_WIDTH + PIXELS_PER_ENTRY * (self.len() + 1);
+        let height = PIXELS_PER_OPERATION * spans.len();
+
+        let mut html = String::new();
+        writeln!(html, "<!DOCTYPE html>").unwrap();
+        writeln!(html, "<html>").unwrap();
+        writeln!(
+            html,
+            "<head><meta charset=\"utf-8\"><title>History</title></head>"
+        )
+        .unwrap();
+        writeln!(html, "<body>").unwrap();
+        writeln!(
+            html,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             font-family=\"monospace\" font-size=\"12\">"
+        )
+        .unwrap();
+        for (row, span) in spans.iter().enumerate() {
+            let x = LABEL_WIDTH + PIXELS_PER_ENTRY * span.start + PIXELS_PER_ENTRY / 2;
+            let y = PIXELS_PER_OPERATION * row;
+            let span_width = PIXELS_PER_ENTRY * (span.end - span.start);
+            let text_y = y + PIXELS_PER_OPERATION / 2 + 4;
+            let hue = (span.process * 67) % 360;
+            let hover = escape(&format!(
+                "P{}: called {:?}, returned {:?}",
+                span.process, span.call, span.response
+            ));
+            writeln!(html, "<g><title>{hover}</title>").unwrap();
+            writeln!(
+                html,
+                "<text x=\"4\" y=\"{text_y}\">P{}</text>",
+                span.process
+            )
+            .unwrap();
+            writeln!(
+                html,
+                "<rect x=\"{x}\" y=\"{}\" width=\"{span_width}\" height=\"{}\" rx=\"4\" \
+                 fill=\"hsl({hue}, 60%, 80%)\" stroke=\"hsl({hue}, 60%, 40%)\"/>",
+                y + 4,
+                PIXELS_PER_OPERATION - 8
+            )
+            .unwrap();
+            writeln!(
+                html,
+                "<text x=\"{}\" y=\"{text_y}\">{}</text>",
+                x + 4,
+                escape(&format!("{:?}", span.response))
+            )
+            .unwrap();
+            writeln!(html, "</g>").unwrap();
+        }
+        writeln!(html, "</svg>").unwrap();
+        writeln!(html, "</body>").unwrap();
+        writeln!(html, "</html>").unwrap();
+        html
+    }
+
+    /// Returns the operations in the history, in the order they were called.
+    fn spans(&self) -> Vec<Span<'_, T>> {
+        let mut positions = vec![0; self.entries.len()];
+        for (position, entry) in self.iter().enumerate() {
+            positions[entry.id()] = position;
+        }
+        self.iter()
+            .filter_map(|entry| match entry {
+                Entry::Call(call) => match &self.entries[call.response] {
+                    Entry::Response(response) => Some(Span {
+                        process: self.processes[call.id],
+                        call: &call.operation,
+                        response: &response.operation,
+                        start: positions[call.id],
+                        end: positions[call.response],
+                    }),
+                    Entry::Call(_) => panic!("Response cannot be a call entry"),
+                },
+                Entry::Response(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Escapes text so that it can be included in an HTML document.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linearizability::history::Action::{Call, Response};
+
+    mod render_timeline {
+        use super::*;
+
+        #[test]
+        fn draws_concurrent_operations() {
+            let history = History::from_actions(vec![
+                (0, Call("a")),
+                (1, Call("b")),
+                (0, Response("a")),
+                (1, Response("b")),
+            ]);
+            assert_eq!(
+                history.render_timeline(),
+                "P0 |---|   \"a\"\n\
+                 P1   |---| \"b\"\n"
+            );
+        }
+
+        #[test]
+        fn aligns_labels_of_different_lengths() {
+            let history = History::from_actions(vec![
+                (10, Call("a")),
+                (10, Response("a")),
+                (2, Call("b")),
+                (2, Response("b")),
+            ]);
+            assert_eq!(
+                history.render_timeline(),
+                "P10 |-|     \"a\"\n\
+                 P2      |-| \"b\"\n"
+            );
+        }
+    }
+
+    mod render_html {
+        use super::*;
+
+        #[test]
+        fn draws_each_operation() {
+            let history = History::from_actions(vec![
+                (0, Call("a")),
+                (1, Call("b")),
+                (0, Response("a")),
+                (1, Response("b")),
+            ]);
+            assert_eq!(history.render_html().matches("<rect").count(), 2);
+        }
+
+        #[test]
+        fn escapes_operations() {
+            let history = History::from_actions(vec![(0, Call("<a>")), (0, Response("<a>"))]);
+            let html = history.render_html();
+            assert!(html.contains("&quot;&lt;a&gt;&quot;"));
+            assert!(!html.contains("<a>"));
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Shrinking histories, to find small reproductions of failures.
+use super::{Entry, EntryId, History};
+
+impl<T: Clone> History<T> {
+    /// Returns a sub-history that still fails, from which no single operation
+    /// can be removed without it passing.
+    ///
+    /// Operations are removed from the history in chunks, following the
+    /// _delta debugging_ algorithm of Zeller and Hildebrandt
+    /// [\[ZH02\]](https://doi.org/10.1109/32.988498). A chunk is removed if the
+    /// history that remains still `fails`, after which the chunks are halved
+    /// in size until they contain single operations. Every call is removed
+    /// along with its response, so each sub-history is complete, and each
+    /// process still performs its remaining operations one at a time, in
+    /// their original order. Timestamps and metadata, if any, are kept.
+    ///
+    /// For a history that is not linearizable, see
+    /// [`WGLChecker::shrink`](crate::WGLChecker::shrink).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the history does not fail.
+    ///
+    /// # Examples
+    ///
+    /// Find the operation that causes a read to return a value that was
+    /// never written.
+    ///
+    /// ```
+    /// use todc_utils::{History, Action::{Call, Response}};
+    /// use todc_utils::linearizability::history::Entry;
+    /// use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+    ///
+    /// let history = History::from_actions(vec![
+    ///     (0, Call(Write(1))),
+    ///     (1, Call(Read(None))),
+    ///     (0, Response(Write(1))),
+    ///     (1, Response(Read(Some(2)))),
+    ///     (0, Call(Read(None))),
+    ///     (0, Response(Read(Some(1)))),
+    /// ]);
+    ///
+    /// let shrunk = history.shrink(|history| {
+    ///     history.iter().any(|entry| {
+    ///         matches!(entry, Entry::Response(response) if matches!(response.operation, Read(Some(2))))
+    ///     })
+    /// });
+    /// assert_eq!(shrunk.len(), 2);
+    /// ```
+    pub fn shrink(&self, mut fails: impl FnMut(&History<T>) -> bool) -> History<T> {
+        assert!(fails(self), "Cannot shrink a history that does not fail");
+        // The ids of the calls of the operations that have not been removed.
+        let mut calls: Vec<EntryId> = self
+            .iter()
+            .filter_map(|entry| match entry {
+                Entry::Call(call) => Some(call.id),
+                Entry::Response(_) => None,
+            })
+            .collect();
+        let mut chunk = (calls.len() / 2).max(1);
+        loop {
+            let mut removed = false;
+            let mut start = 0;
+            while start < calls.len() {
+                let end = (start + chunk).min(calls.len());
+                // A history without any operations cannot fail.
+                if end - start < calls.len() {
+                    let remaining: Vec<EntryId> = calls[..start]
+                        .iter()
+                        .chain(&calls[end..])
+                        .copied()
+                        .collect();
+                    if fails(&self.restrict(&remaining)) {
+                        calls = remaining;
+                        removed = true;
+                        continue;
+                    }
+                }
+                start = end;
+            }
+            if !removed {
+                if chunk == 1 {
+                    return self.restrict(&calls);
+                }
+                chunk /= 2;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::linearizability::history::Action::{Call, Response};
+    use crate::specifications::register::RegisterOperation::{self, Read, Write};
+
+    type Operation = RegisterOperation<u32>;
+
+    /// Returns whether some read in the history returns the given value.
+    fn reads(history: &History<Operation>, value: u32) -> bool {
+        history.iter().any(|entry| match entry {
+            Entry::Response(response) => matches!(response.operation, Read(Some(v)) if v == value),
+            Entry::Call(_) => false,
+        })
+    }
+
+    mod shrink {
+        use super::*;
+
+        #[test]
+        fn removes_operations_that_do_not_cause_failure() {
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (1, Call(Read(None))),
+                (0, Response(Write(1))),
+                (1, Response(Read(Some(1)))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(2)))),
+                (1, Call(Write(3))),
+                (1, Response(Write(3))),
+            ]);
+            let shrunk = history.shrink(|history| reads(history, 2));
+            assert_eq!(shrunk.len(), 2);
+            assert!(reads(&shrunk, 2));
+        }
+
+        #[test]
+        fn keeps_every_operation_needed_for_failure() {
+            let history = History::from_actions(vec![
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(1)))),
+                (1, Call(Write(4))),
+                (1, Response(Write(4))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(2)))),
+            ]);
+            let shrunk = history.shrink(|history| reads(history, 1) && reads(history, 2));
+            assert_eq!(shrunk.len(), 4);
+            assert!(reads(&shrunk, 1) && reads(&shrunk, 2));
+        }
+
+        #[test]
+        fn keeps_timestamps_of_remaining_entries() {
+            let millis = |ms| Duration::from_millis(ms);
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (0, Response(Write(1))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(2)))),
+            ])
+            .with_timestamps(vec![millis(0), millis(1), millis(2), millis(5)]);
+            let shrunk = history.shrink(|history| reads(history, 2));
+            assert_eq!(shrunk.stats().mean_latency, Some(millis(3)));
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_history_does_not_fail() {
+            let history = History::from_actions(vec![(0, Call(Write(1))), (0, Response(Write(1)))]);
+            history.shrink(|history| reads(history, 1));
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! Summarizing histories, to check that they exercise an object as intended.
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::time::Duration;
+
+use super::{Entry, History, ProcessId};
+
+/// A summary of the operations in a [`History`].
+///
+/// A history in which no operations overlap is trivially linearizable, so
+/// a checker that accepts it says little about the object that produced it.
+/// Statistics can be used to check that a workload actually performs
+/// operations concurrently, and of the intended kinds, before trusting the
+/// result of a check.
+///
+/// See [`History::stats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of operations in the history.
+    pub operations: usize,
+    /// The number of operations performed by each process.
+    pub operations_per_process: BTreeMap<ProcessId, usize>,
+    /// The number of operations of each kind. The kind of an operation is
+    /// the name of its variant, as printed by its [`Debug`] implementation.
+    pub operations_per_kind: BTreeMap<String, usize>,
+    /// The largest number of operations that were pending at the same time.
+    pub max_concurrency: usize,
+    /// The mean time between the call and response of each operation, or
+    /// `None` if the history has no timestamps or no operations.
+    pub mean_latency: Option<Duration>,
+}
+
+impl Stats {
+    /// Returns whether any two operations in the history overlap.
+    pub fn is_concurrent(&self) -> bool {
+        self.max_concurrency > 1
+    }
+}
+
+impl<T: Debug> History<T> {
+    /// Returns statistics about the operations in the history.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use todc_utils::{History, Action::{Call, Response}};
+    /// use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+    ///
+    /// let history = History::from_actions(vec![
+    ///     (0, Call(Write(0))),
+    ///     (1, Call(Read(None))),
+    ///     (1, Response(Read(Some(0)))),
+    ///     (0, Response(Write(0))),
+    ///     (1, Call(Read(None))),
+    ///     (1, Response(Read(Some(0)))),
+    /// ]);
+    /// let stats = history.stats();
+    /// assert_eq!(stats.operations, 3);
+    /// assert_eq!(stats.operations_per_process[&1], 2);
+    /// assert_eq!(stats.operations_per_kind["Read"], 2);
+    /// assert_eq!(stats.max_concurrency, 2);
+    /// assert!(stats.is_concurrent());
+    /// ```
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        let mut pending: usize = 0;
+        let mut total_latency = Duration::ZERO;
+        for entry in self.iter() {
+            match entry {
+                Entry::Call(call) => {
+                    stats.operations += 1;
+                    *stats
+                        .operations_per_process
+                        .entry(self.processes[call.id])
+                        .or_default() += 1;
+                    *stats
+                        .operations_per_kind
+                        .entry(kind(&call.operation))
+                        .or_default() += 1;
+                    pending += 1;
+                    stats.max_concurrency = stats.max_concurrency.max(pending);
+                    if let Some(times) = &self.timestamps {
+                        total_latency += times[call.response].saturating_sub(times[call.id]);
+                    }
+                }
+                Entry::Response(_) => pending -= 1,
+            }
+        }
+        if self.timestamps.is_some() && stats.operations > 0 {
+            stats.mean_latency = Some(total_latency / stats.operations as u32);
+        }
+        stats
+    }
+}
+
+/// Returns the name of the variant of an operation, which is everything that
+/// its [`Debug`] implementation prints before any fields.
+fn kind<T: Debug>(operation: &T) -> String {
+    let debug = format!("{operation:?}");
+    match debug.find(['(', '{', ' ']) {
+        Some(end) => debug[..end].to_string(),
+        None => debug,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linearizability::history::Action::{Call, Response};
+    use crate::specifications::register::RegisterOperation::{Read, Write};
+
+    mod stats {
+        use super::*;
+
+        #[test]
+        fn counts_operations_of_each_process() {
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (0, Response(Write(1))),
+                (2, Call(Write(2))),
+                (2, Response(Write(2))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(2)))),
+            ]);
+            let stats = history.stats();
+            assert_eq!(stats.operations, 3);
+            assert_eq!(
+                stats.operations_per_process,
+                BTreeMap::from([(0, 2), (2, 1)])
+            );
+        }
+
+        #[test]
+        fn counts_operations_of_each_kind() {
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (1, Call(Read(None))),
+                (0, Response(Write(1))),
+                (1, Response(Read(Some(1)))),
+            ]);
+            assert_eq!(
+                history.stats().operations_per_kind,
+                BTreeMap::from([(String::from("Read"), 1), (String::from("Write"), 1)])
+            );
+        }
+
+        #[test]
+        fn sequential_history_is_not_concurrent() {
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (0, Response(Write(1))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(1)))),
+            ]);
+            let stats = history.stats();
+            assert_eq!(stats.max_concurrency, 1);
+            assert!(!stats.is_concurrent());
+        }
+
+        #[test]
+        fn finds_largest_number_of_overlapping_operations() {
+            // P0 |-----------|
+            // P1   |---|
+            // P2         |-----|
+            // P3               |-|
+            let history = History::from_actions(vec![
+                (0, Call(Write(0))),
+                (1, Call(Write(1))),
+                (1, Response(Write(1))),
+                (2, Call(Write(2))),
+                (0, Response(Write(0))),
+                (3, Call(Read(None))),
+                (2, Response(Write(2))),
+                (3, Response(Read(Some(2)))),
+            ]);
+            assert_eq!(history.stats().max_concurrency, 2);
+        }
+
+        #[test]
+        fn has_no_latency_without_timestamps() {
+            let history = History::from_actions(vec![(0, Call(Write(1))), (0, Response(Write(1)))]);
+            assert_eq!(history.stats().mean_latency, None);
+        }
+
+        #[test]
+        fn averages_latency_of_operations() {
+            let history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (1, Call(Read(None))),
+                (1, Response(Read(Some(1)))),
+                (0, Response(Write(1))),
+            ])
+            .with_timestamps(
+                [0, 1, 2, 4]
+                    .into_iter()
+                    .map(Duration::from_millis)
+                    .collect(),
+            );
+            // The write takes 4ms, and the read takes 1ms.
+            assert_eq!(
+                history.stats().mean_latency,
+                Some(Duration::from_micros(2500))
+            );
+        }
+
+        #[test]
+        fn ignores_lifted_operations() {
+            let mut history = History::from_actions(vec![
+                (0, Call(Write(1))),
+                (1, Call(Read(None))),
+                (0, Response(Write(1))),
+                (1, Response(Read(Some(1)))),
+            ]);
+            history.lift(0);
+            let stats = history.stats();
+            assert_eq!(stats.operations, 1);
+            assert_eq!(stats.max_concurrency, 1);
+        }
+    }
+
+    mod kind {
+        use super::*;
+
+        #[test]
+        fn returns_name_of_variant() {
+            assert_eq!(kind(&Write(1)), "Write");
+            assert_eq!(kind(&Read::<u32>(None)), "Read");
+        }
+
+        #[test]
+        fn returns_entire_debug_output_of_values_without_fields() {
+            assert_eq!(kind(&None::<u32>), "None");
+            assert_eq!(kind(&123), "123");
+        }
+    }
+}
@@ -0,0 +1,448 @@
+//! Checking linearizability of a history of operations while it is still
+//! being recorded.
+//!
+//! An [`OnlineChecker`] is given the actions of a running test one at a
+//! time, and reports a violation as soon as the history recorded so far can
+//! no longer be linearized. Because linearizability is _prefix-closed_, once
+//! some prefix of a history is not linearizable, no continuation of it can
+//! be, and so a violation can be reported without waiting for the test to
+//! finish.
+//!
+//! Some of the operations in a prefix may not have returned yet. As with the
+//! operations interrupted by a crash in a [`durable`](super::durable) history,
+//! each of these may or may not have taken effect already, and so they are
+//! checked with respect to [`Durable`]. In particular, the checker relies on
+//! [`DurableSpecification::is_update`] to tell which pending operations could
+//! have changed the state of the object.
+//!
+//! # Incremental Checking
+//!
+//! Whenever no operation is pending, every operation that follows must be
+//! linearized after every operation that came before. At these _quiescent_
+//! points the checker records the states that the object could be in, and
+//! discards the actions that led to them. Later checks only search the
+//! actions since the most recent quiescent point, starting from each of these
+//! states, so the cost of each check does not grow with the length of the
+//! whole history. Tests in which some operation is always pending never reach
+//! a quiescent point, and so each check searches the entire history.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_utils::linearizability::online::OnlineChecker;
+//! use todc_utils::linearizability::CheckResult;
+//! use todc_utils::specifications::register::RegisterSpecification;
+//! use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+//! use todc_utils::Action::{Call, Response};
+//!
+//! let mut checker: OnlineChecker<RegisterSpecification<u32>> = OnlineChecker::new();
+//!
+//! // P0 |-------------------------  Write(1), still pending
+//! // P1     |---|                   Read(Some(1))
+//! // P1           |---|             Read(Some(0))
+//! checker.push(0, Call(Write(1)));
+//! checker.push(1, Call(Read(None)));
+//! assert_eq!(checker.push(1, Response(Read(Some(1)))), CheckResult::Linearizable);
+//! checker.push(1, Call(Read(None)));
+//! assert_eq!(checker.push(1, Response(Read(Some(0)))), CheckResult::NotLinearizable);
+//! ```
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+use std::time::Instant;
+
+use crate::linearizability::durable::{Durable, DurableOperation};
+use crate::linearizability::history::{Action, History, ProcessId};
+use crate::linearizability::{CheckResult, CheckerConfig, WGLChecker};
+use crate::specifications::DurableSpecification;
+
+/// A linearizability checker for histories that are given one action at a
+/// time.
+///
+/// See the [`online`](crate::linearizability::online) module-level
+/// documentation for more details.
+///
+/// The checker uses the capacity, timeout, and limit on expansions of its
+/// [`CheckerConfig`] for each check that it performs. Actions have no
+/// timestamps, and so the real-time tolerance of the configuration is
+/// ignored, as is the number of threads.
+pub struct OnlineChecker<S: DurableSpecification, H = RandomState> {
+    config: CheckerConfig<H>,
+    /// The states that the object could be in at the most recent quiescent
+    /// point of the history.
+    states: Vec<S::State>,
+    /// The actions since the most recent quiescent point.
+    actions: Vec<(ProcessId, Action<S::Operation>)>,
+    /// The operation that each process is performing, if any.
+    pending: HashMap<ProcessId, S::Operation>,
+    result: CheckResult,
+}
+
+impl<S: DurableSpecification> OnlineChecker<S> {
+    /// Creates a checker for an empty history, with the default
+    /// configuration.
+    pub fn new() -> Self {
+        Self::with_config(CheckerConfig::new())
+    }
+}
+
+impl<S: DurableSpecification> Default for OnlineChecker<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: DurableSpecification, H: BuildHasher + Clone> OnlineChecker<S, H> {
+    /// Creates a checker for an empty history, with the given configuration.
+    pub fn with_config(config: CheckerConfig<H>) -> Self {
+        Self {
+            config,
+            states: vec![S::init()],
+            actions: Vec::new(),
+            pending: HashMap::new(),
+            result: CheckResult::Linearizable,
+        }
+    }
+
+    /// Adds an action to the end of the history, and returns whether the
+    /// history so far is linearizable.
+    ///
+    /// Calling an operation can never make a history non-linearizable, and
+    /// so only responses are checked. Once the history is found not to be
+    /// linearizable, every later action is ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a process calls an operation before its previous operation
+    /// returned, or if an operation returns before it was called.
+    pub fn push(&mut self, process: ProcessId, action: Action<S::Operation>) -> CheckResult {
+        if self.result == CheckResult::NotLinearizable {
+            return CheckResult::NotLinearizable;
+        }
+        match &action {
+            Action::Call(operation) => {
+                if self.pending.insert(process, operation.clone()).is_some() {
+                    panic!("Process {process} called an operation before its previous operation returned");
+                }
+                self.actions.push((process, action));
+                return self.result;
+            }
+            _ => {
+                if self.pending.remove(&process).is_none() {
+                    panic!("Process {process} returned from an operation before calling it");
+                }
+                self.actions.push((process, action));
+            }
+        }
+        self.result = if self.pending.is_empty() {
+            self.advance()
+        } else {
+            self.check()
+        };
+        self.result
+    }
+
+    /// Returns whether the history so far is linearizable, as of the most
+    /// recent response.
+    pub fn result(&self) -> CheckResult {
+        self.result
+    }
+
+    /// Returns the actions since the most recent quiescent point, in which
+    /// each pending operation is completed by an
+    /// [`Interrupted`](DurableOperation::Interrupted) response.
+    fn history(&self) -> History<DurableOperation<S::Operation>> {
+        let mut actions: Vec<(ProcessId, Action<DurableOperation<S::Operation>>)> = self
+            .actions
+            .iter()
+            .map(|(process, action)| (*process, action.clone().map(DurableOperation::Completed)))
+            .collect();
+        let mut pending: Vec<(&ProcessId, &S::Operation)> = self.pending.iter().collect();
+        // Order pending operations deterministically.
+        pending.sort_by_key(|(process, _)| **process);
+        for (process, operation) in pending {
+            let response = DurableOperation::Interrupted(operation.clone());
+            actions.push((*process, Action::Response(response)));
+        }
+        History::from_actions(actions)
+    }
+
+    /// Checks whether the actions since the most recent quiescent point can
+    /// be linearized, starting from any of the states recorded there.
+    fn check(&self) -> CheckResult {
+        let deadline = self.config.timeout.map(|timeout| Instant::now() + timeout);
+        let mut result = CheckResult::NotLinearizable;
+        for state in &self.states {
+            let found = WGLChecker::<Durable<S>>::search(
+                self.history(),
+                state.clone(),
+                &self.config,
+                deadline,
+                false,
+            );
+            match found {
+                Ok(states) if states.is_empty() => {}
+                Ok(_) => return CheckResult::Linearizable,
+                Err(reason) => result = CheckResult::Unknown(reason),
+            }
+        }
+        result
+    }
+
+    /// Records the states that the object could be in at a new quiescent
+    /// point, and discards the actions that led to them.
+    ///
+    /// If the search gives up before finding every such state, the actions
+    /// are kept, and the next check searches them again.
+    fn advance(&mut self) -> CheckResult {
+        let deadline = self.config.timeout.map(|timeout| Instant::now() + timeout);
+        let mut states: HashSet<S::State> = HashSet::new();
+        for state in &self.states {
+            let found = WGLChecker::<Durable<S>>::search(
+                self.history(),
+                state.clone(),
+                &self.config,
+                deadline,
+                true,
+            );
+            match found {
+                Ok(found) => states.extend(found),
+                Err(reason) => return CheckResult::Unknown(reason),
+            }
+        }
+        if states.is_empty() {
+            return CheckResult::NotLinearizable;
+        }
+        self.states = states.into_iter().collect();
+        self.actions.clear();
+        CheckResult::Linearizable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linearizability::Exhausted;
+    use crate::specifications::register::RegisterOperation::{self, Read, Write};
+    use crate::specifications::register::RegisterSpecification;
+    use Action::{Call, Response};
+
+    type Checker = OnlineChecker<RegisterSpecification<u32>>;
+
+    fn push_all(
+        checker: &mut Checker,
+        actions: Vec<(ProcessId, Action<RegisterOperation<u32>>)>,
+    ) -> Vec<CheckResult> {
+        actions
+            .into_iter()
+            .map(|(process, action)| checker.push(process, action))
+            .collect()
+    }
+
+    mod push {
+        use super::*;
+
+        #[test]
+        fn accepts_sequential_read_and_write() {
+            let mut checker = Checker::new();
+            let results = push_all(
+                &mut checker,
+                vec![
+                    (0, Call(Write(1))),
+                    (0, Response(Write(1))),
+                    (0, Call(Read(None))),
+                    (0, Response(Read(Some(1)))),
+                ],
+            );
+            assert!(results.iter().all(|r| *r == CheckResult::Linearizable));
+        }
+
+        #[test]
+        fn reports_violation_at_response_that_causes_it() {
+            let mut checker = Checker::new();
+            let results = push_all(
+                &mut checker,
+                vec![
+                    (0, Call(Write(1))),
+                    (0, Response(Write(1))),
+                    (1, Call(Read(None))),
+                    (1, Response(Read(Some(0)))),
+                ],
+            );
+            assert_eq!(results[2], CheckResult::Linearizable);
+            assert_eq!(results[3], CheckResult::NotLinearizable);
+        }
+
+        #[test]
+        fn accepts_read_of_pending_write() {
+            let mut checker = Checker::new();
+            let results = push_all(
+                &mut checker,
+                vec![
+                    (0, Call(Write(1))),
+                    (1, Call(Read(None))),
+                    (1, Response(Read(Some(1)))),
+                ],
+            );
+            assert_eq!(results[2], CheckResult::Linearizable);
+        }
+
+        #[test]
+        fn accepts_read_that_ignores_pending_write() {
+            let mut checker = Checker::new();
+            let results = push_all(
+                &mut checker,
+                vec![
+                    (0, Call(Write(1))),
+                    (1, Call(Read(None))),
+                    (1, Response(Read(Some(0)))),
+                ],
+            );
+            assert_eq!(results[2], CheckResult::Linearizable);
+        }
+
+        #[test]
+        fn rejects_read_of_value_never_written() {
+            let mut checker = Checker::new();
+            let results = push_all(
+                &mut checker,
+                vec![
+                    (0, Call(Write(1))),
+                    (1, Call(Read(None))),
+                    (1, Response(Read(Some(2)))),
+                ],
+            );
+            assert_eq!(results[2], CheckResult::NotLinearizable);
+        }
+
+        #[test]
+        fn keeps_every_possible_state_at_quiescent_point() {
+            // P0 |--------|          Write(1)
+            // P1 |--------|          Write(2)
+            // P2            |---|    Read(Some(1))
+            let actions = vec![
+                (0, Call(Write(1))),
+                (1, Call(Write(2))),
+                (0, Response(Write(1))),
+                (1, Response(Write(2))),
+            ];
+            for value in [1, 2] {
+                let mut checker = Checker::new();
+                push_all(&mut checker, actions.clone());
+                checker.push(2, Call(Read(None)));
+                let result = checker.push(2, Response(Read(Some(value))));
+                assert_eq!(result, CheckResult::Linearizable);
+            }
+        }
+
+        #[test]
+        fn discards_actions_at_quiescent_point() {
+            let mut checker = Checker::new();
+            push_all(
+                &mut checker,
+                vec![
+                    (0, Call(Write(1))),
+                    (1, Call(Write(2))),
+                    (0, Response(Write(1))),
+                    (1, Response(Write(2))),
+                ],
+            );
+            assert!(checker.actions.is_empty());
+            assert_eq!(checker.states.len(), 2);
+        }
+
+        #[test]
+        fn keeps_actions_while_operations_are_pending() {
+            let mut checker = Checker::new();
+            push_all(
+                &mut checker,
+                vec![
+                    (0, Call(Write(1))),
+                    (1, Call(Write(2))),
+                    (1, Response(Write(2))),
+                ],
+            );
+            assert_eq!(checker.actions.len(), 3);
+        }
+
+        #[test]
+        fn ignores_actions_after_violation() {
+            let mut checker = Checker::new();
+            push_all(
+                &mut checker,
+                vec![(0, Call(Read(None))), (0, Response(Read(Some(1))))],
+            );
+            let results = push_all(
+                &mut checker,
+                vec![(0, Call(Write(1))), (0, Response(Write(1)))],
+            );
+            assert!(results.iter().all(|r| *r == CheckResult::NotLinearizable));
+            assert_eq!(checker.result(), CheckResult::NotLinearizable);
+        }
+
+        #[test]
+        fn returns_unknown_if_expansions_are_exhausted() {
+            let config = CheckerConfig::new().with_expansions(0);
+            let mut checker: Checker = OnlineChecker::with_config(config);
+            let results = push_all(
+                &mut checker,
+                vec![(0, Call(Write(1))), (0, Response(Write(1)))],
+            );
+            assert_eq!(results[1], CheckResult::Unknown(Exhausted::Expansions));
+            assert_eq!(checker.actions.len(), 2);
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "Process 0 called an operation before its previous operation returned"
+        )]
+        fn panics_if_process_calls_twice() {
+            let mut checker = Checker::new();
+            checker.push(0, Call(Write(1)));
+            checker.push(0, Call(Write(2)));
+        }
+
+        #[test]
+        #[should_panic(expected = "Process 0 returned from an operation before calling it")]
+        fn panics_if_process_returns_before_calling() {
+            let mut checker = Checker::new();
+            checker.push(0, Response(Write(1)));
+        }
+    }
+
+    mod agrees_with_wgl_checker {
+        use super::*;
+
+        #[test]
+        fn on_writes_in_reverse_order() {
+            // P0 |--------------------| Write(1)
+            // P1 |--------------------| Write(2)
+            // P2 |--------------------| Write(3)
+            // P3   |--|                 Read(3)
+            // P3          |--|          Read(2)
+            // P3                 |--|   Read(1)
+            let actions = vec![
+                (0, Call(Write(1))),
+                (1, Call(Write(2))),
+                (2, Call(Write(3))),
+                (3, Call(Read(None))),
+                (3, Response(Read(Some(3)))),
+                (3, Call(Read(None))),
+                (3, Response(Read(Some(2)))),
+                (3, Call(Read(None))),
+                (3, Response(Read(Some(1)))),
+                (0, Response(Write(1))),
+                (1, Response(Write(2))),
+                (2, Response(Write(3))),
+            ];
+            let expected = WGLChecker::<RegisterSpecification<u32>>::check_with_config(
+                History::from_actions(actions.clone()),
+                &CheckerConfig::new(),
+            );
+            let mut checker = Checker::new();
+            let results = push_all(&mut checker, actions);
+            assert_eq!(expected, CheckResult::Linearizable);
+            assert_eq!(results.last(), Some(&expected));
+        }
+    }
+}
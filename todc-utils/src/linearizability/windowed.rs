@@ -0,0 +1,781 @@
+//! Recording and checking long histories with a bounded amount of memory.
+//!
+//! A soak test that runs for hours cannot keep every action that it performs
+//! in memory, and no checker could search the resulting history anyway. A
+//! [`WindowedRecorder`] instead divides the actions of a test into
+//! [`Window`]s of roughly a fixed number of actions, and hands each window
+//! back as soon as it is closed, so that it can be checked and then
+//! discarded.
+//!
+//! # Windows
+//!
+//! A window is only closed at a _quiescent_ point, at which no operation is
+//! pending. Every operation that follows such a point must be linearized
+//! after every operation that came before it, and so a [`WindowedChecker`]
+//! can check the windows of a history one at a time, starting each window
+//! from the states that the object could be in at the end of the previous
+//! one. Checking every window in order is equivalent to checking the whole
+//! history at once, while only ever holding a single window in memory.
+//!
+//! Once a window has reached its size, it is closed at the next quiescent
+//! point. Tests in which some operation is always pending never reach one,
+//! and so their current window grows without bound. Harnesses that keep
+//! every client busy can make room for quiescent points by pausing their
+//! clients once in a while, such as whenever
+//! [`WindowedRecorder::is_full`] holds.
+//!
+//! # Retaining Windows
+//!
+//! Windows that have been checked are only useful for diagnosing failures.
+//! A recorder retains copies of the most recent windows in a ring buffer,
+//! and of a uniformly random sample of the windows that were evicted from
+//! it, chosen by reservoir sampling. Both are empty by default, and can be
+//! enabled with [`with_recent`](WindowedRecorder::with_recent) and
+//! [`with_samples`](WindowedRecorder::with_samples). The sample is chosen
+//! from a fixed seed, and so the same test retains the same windows on every
+//! run.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_utils::linearizability::windowed::{WindowedChecker, WindowedRecorder};
+//! use todc_utils::linearizability::CheckResult;
+//! use todc_utils::specifications::register::RegisterSpecification;
+//! use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+//! use todc_utils::Action::{Call, Response};
+//! use todc_utils::TimedAction;
+//!
+//! let mut recorder = WindowedRecorder::new(2).with_recent(1);
+//! let mut checker: WindowedChecker<RegisterSpecification<u32>> = WindowedChecker::new();
+//!
+//! for (process, action) in [
+//!     (0, Call(Write(1))),
+//!     (0, Response(Write(1))),
+//!     (1, Call(Read(None))),
+//!     (1, Response(Read(Some(1)))),
+//! ] {
+//!     if let Some(window) = recorder.record(TimedAction::now(process, action)) {
+//!         assert_eq!(checker.push(&window), CheckResult::Linearizable);
+//!     }
+//! }
+//! assert_eq!(checker.windows(), 2);
+//! assert_eq!(recorder.recent().count(), 1);
+//! ```
+use std::collections::hash_map::RandomState;
+use std::collections::{HashSet, VecDeque};
+use std::hash::BuildHasher;
+use std::time::Instant;
+
+use crate::linearizability::durable::{Durable, DurableOperation};
+use crate::linearizability::history::{Action, History, ProcessId, TimedAction};
+use crate::linearizability::{CheckResult, CheckerConfig, WGLChecker};
+use crate::specifications::DurableSpecification;
+
+/// The seed from which recorders choose which windows to sample, by default.
+const DEFAULT_SEED: u64 = 0x5eed;
+
+/// A contiguous sequence of the actions of a history.
+///
+/// Windows closed by a [`WindowedRecorder`] begin and end at quiescent
+/// points, except for its [`current`](WindowedRecorder::current) window,
+/// which may end while some operations are still pending.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Window<T> {
+    /// The position of the window in the history, starting from zero.
+    pub index: usize,
+    /// The actions of the window, in the order that they happened.
+    pub actions: Vec<TimedAction<T>>,
+}
+
+impl<T> Window<T> {
+    /// Returns the processes whose operations had not yet returned by the end
+    /// of the window, in increasing order.
+    pub fn pending(&self) -> Vec<ProcessId> {
+        let mut pending = HashSet::new();
+        for timed in &self.actions {
+            match timed.action {
+                Action::Call(_) => pending.insert(timed.process),
+                _ => pending.remove(&timed.process),
+            };
+        }
+        let mut pending: Vec<ProcessId> = pending.into_iter().collect();
+        pending.sort();
+        pending
+    }
+
+    /// Returns whether every operation called in the window has returned.
+    pub fn is_complete(&self) -> bool {
+        self.pending().is_empty()
+    }
+}
+
+impl<T: Clone> Window<T> {
+    /// Returns a history of the actions in the window, in which each pending
+    /// operation is completed by an
+    /// [`Interrupted`](DurableOperation::Interrupted) response, since it may
+    /// or may not have taken effect.
+    pub fn history(&self) -> History<DurableOperation<T>> {
+        let mut actions: Vec<(ProcessId, Action<DurableOperation<T>>)> = self
+            .actions
+            .iter()
+            .map(|timed| {
+                let action = timed.action.clone().map(DurableOperation::Completed);
+                (timed.process, action)
+            })
+            .collect();
+        for process in self.pending() {
+            let operation = self
+                .actions
+                .iter()
+                .rev()
+                .find_map(|timed| match &timed.action {
+                    Action::Call(operation) if timed.process == process => Some(operation),
+                    _ => None,
+                })
+                .expect("Pending processes have called an operation");
+            let response = DurableOperation::Interrupted(operation.clone());
+            actions.push((process, Action::Response(response)));
+        }
+        History::from_actions(actions)
+    }
+}
+
+/// A recorder that divides the actions of a history into windows.
+///
+/// See the [`windowed`](crate::linearizability::windowed) module-level
+/// documentation for more details.
+#[derive(Debug)]
+pub struct WindowedRecorder<T> {
+    size: usize,
+    current: Window<T>,
+    /// The processes with an operation in the current window that has not
+    /// yet returned.
+    pending: HashSet<ProcessId>,
+    recent: VecDeque<Window<T>>,
+    recent_capacity: usize,
+    samples: Vec<Window<T>>,
+    sample_capacity: usize,
+    /// The number of windows that have been evicted from `recent`.
+    evicted: usize,
+    rng: u64,
+}
+
+impl<T> WindowedRecorder<T> {
+    /// Creates a recorder that closes each window at the first quiescent
+    /// point after it contains `size` actions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "Windows must contain at least one action");
+        Self {
+            size,
+            current: Window {
+                index: 0,
+                actions: Vec::new(),
+            },
+            pending: HashSet::new(),
+            recent: VecDeque::new(),
+            recent_capacity: 0,
+            samples: Vec::new(),
+            sample_capacity: 0,
+            evicted: 0,
+            rng: DEFAULT_SEED,
+        }
+    }
+
+    /// Retains copies of the `n` most recently closed windows.
+    pub fn with_recent(mut self, n: usize) -> Self {
+        self.recent_capacity = n;
+        self
+    }
+
+    /// Retains copies of a uniformly random sample of `n` of the windows
+    /// that are no longer among the most recent windows.
+    pub fn with_samples(mut self, n: usize) -> Self {
+        self.sample_capacity = n;
+        self
+    }
+
+    /// Sets the seed from which the sample of windows is chosen.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = seed;
+        self
+    }
+
+    /// Returns the window that actions are currently recorded in.
+    pub fn current(&self) -> &Window<T> {
+        &self.current
+    }
+
+    /// Returns the most recently closed windows, from oldest to newest.
+    pub fn recent(&self) -> impl Iterator<Item = &Window<T>> {
+        self.recent.iter()
+    }
+
+    /// Returns the sample of older windows, in no particular order.
+    pub fn samples(&self) -> &[Window<T>] {
+        &self.samples
+    }
+
+    /// Returns the number of windows that have been closed.
+    pub fn closed(&self) -> usize {
+        self.current.index
+    }
+
+    /// Returns whether the current window has reached its size, and will be
+    /// closed at the next quiescent point.
+    pub fn is_full(&self) -> bool {
+        self.current.actions.len() >= self.size
+    }
+
+    /// Returns whether no operation is pending.
+    pub fn is_quiescent(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Offers a window that was evicted from the recent windows to the
+    /// sample, which keeps it with probability `capacity / evicted`.
+    fn sample(&mut self, window: Window<T>) {
+        self.evicted += 1;
+        if self.samples.len() < self.sample_capacity {
+            self.samples.push(window);
+            return;
+        }
+        let i = (self.next_random() % self.evicted as u64) as usize;
+        if i < self.sample_capacity {
+            self.samples[i] = window;
+        }
+    }
+
+    /// Returns the next number of a SplitMix64 sequence.
+    fn next_random(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl<T: Clone> WindowedRecorder<T> {
+    /// Records an action, and returns the window that it closed, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a process calls an operation before its previous operation
+    /// returned, or if an operation returns before it was called.
+    pub fn record(&mut self, timed: TimedAction<T>) -> Option<Window<T>> {
+        let process = timed.process;
+        match timed.action {
+            Action::Call(_) => {
+                if !self.pending.insert(process) {
+                    panic!("Process {process} called an operation before its previous operation returned");
+                }
+            }
+            _ => {
+                if !self.pending.remove(&process) {
+                    panic!("Process {process} returned from an operation before calling it");
+                }
+            }
+        }
+        self.current.actions.push(timed);
+        if !(self.is_full() && self.is_quiescent()) {
+            return None;
+        }
+        let next = Window {
+            index: self.current.index + 1,
+            actions: Vec::new(),
+        };
+        let window = std::mem::replace(&mut self.current, next);
+        self.retain(window.clone());
+        Some(window)
+    }
+
+    /// Records the call of an operation by a process, at the current time.
+    pub fn call(&mut self, process: ProcessId, operation: T) -> Option<Window<T>> {
+        self.record(TimedAction::now(process, Action::Call(operation)))
+    }
+
+    /// Records the response of an operation by a process, at the current
+    /// time.
+    pub fn respond(&mut self, process: ProcessId, operation: T) -> Option<Window<T>> {
+        self.record(TimedAction::now(process, Action::Response(operation)))
+    }
+
+    /// Keeps a copy of a window that was just closed, if any are retained.
+    fn retain(&mut self, window: Window<T>) {
+        if self.recent_capacity == 0 {
+            if self.sample_capacity > 0 {
+                self.sample(window);
+            }
+            return;
+        }
+        self.recent.push_back(window);
+        if self.recent.len() > self.recent_capacity {
+            let evicted = self.recent.pop_front().unwrap();
+            if self.sample_capacity > 0 {
+                self.sample(evicted);
+            }
+        }
+    }
+}
+
+/// A linearizability checker for histories that are given one window at a
+/// time.
+///
+/// Between windows, the checker only keeps the states that the object could
+/// be in at the end of the most recent window. See the
+/// [`windowed`](crate::linearizability::windowed) module-level
+/// documentation for more details.
+///
+/// The checker uses the capacity, timeout, and limit on expansions of its
+/// [`CheckerConfig`] for each window that it checks. The real-time
+/// tolerance of the configuration is ignored, as is the number of threads.
+pub struct WindowedChecker<S: DurableSpecification, H = RandomState> {
+    config: CheckerConfig<H>,
+    /// The states that the object could be in at the end of the most recent
+    /// window.
+    states: Vec<S::State>,
+    /// The number of windows that have been checked.
+    windows: usize,
+    result: CheckResult,
+}
+
+impl<S: DurableSpecification> WindowedChecker<S> {
+    /// Creates a checker for an empty history, with the default
+    /// configuration.
+    pub fn new() -> Self {
+        Self::with_config(CheckerConfig::new())
+    }
+}
+
+impl<S: DurableSpecification> Default for WindowedChecker<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: DurableSpecification, H: BuildHasher + Clone> WindowedChecker<S, H> {
+    /// Creates a checker for an empty history, with the given configuration.
+    pub fn with_config(config: CheckerConfig<H>) -> Self {
+        Self {
+            config,
+            states: vec![S::init()],
+            windows: 0,
+            result: CheckResult::Linearizable,
+        }
+    }
+
+    /// Checks the next window of the history, and returns whether the
+    /// history up to the end of it is linearizable.
+    ///
+    /// Once the history is found not to be linearizable, or the checker gives
+    /// up on a window, the states that the object could be in are no longer
+    /// known, and so every later window is ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the window does not immediately follow the most recently
+    /// checked window, or if some operation in it is still pending. To check
+    /// a window with pending operations, such as the last window of a test,
+    /// use [`check`](WindowedChecker::check).
+    pub fn push(&mut self, window: &Window<S::Operation>) -> CheckResult {
+        assert_eq!(
+            window.index, self.windows,
+            "Windows must be checked in order"
+        );
+        assert!(
+            window.is_complete(),
+            "Window {} ends while operations are pending",
+            window.index
+        );
+        self.windows += 1;
+        if self.result != CheckResult::Linearizable || window.actions.is_empty() {
+            return self.result;
+        }
+        let deadline = self.config.timeout.map(|timeout| Instant::now() + timeout);
+        let mut states: HashSet<S::State> = HashSet::new();
+        for state in &self.states {
+            let found = WGLChecker::<Durable<S>>::search(
+                window.history(),
+                state.clone(),
+                &self.config,
+                deadline,
+                true,
+            );
+            match found {
+                Ok(found) => states.extend(found),
+                Err(reason) => {
+                    self.result = CheckResult::Unknown(reason);
+                    return self.result;
+                }
+            }
+        }
+        if states.is_empty() {
+            self.result = CheckResult::NotLinearizable;
+        } else {
+            self.states = states.into_iter().collect();
+        }
+        self.result
+    }
+
+    /// Returns whether the history up to the end of the given window is
+    /// linearizable, without advancing past it.
+    ///
+    /// Operations that are pending at the end of the window may or may not
+    /// have taken effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the window does not immediately follow the most recently
+    /// checked window.
+    pub fn check(&self, window: &Window<S::Operation>) -> CheckResult {
+        assert_eq!(
+            window.index, self.windows,
+            "Windows must be checked in order"
+        );
+        if self.result != CheckResult::Linearizable || window.actions.is_empty() {
+            return self.result;
+        }
+        let deadline = self.config.timeout.map(|timeout| Instant::now() + timeout);
+        let mut result = CheckResult::NotLinearizable;
+        for state in &self.states {
+            let found = WGLChecker::<Durable<S>>::search(
+                window.history(),
+                state.clone(),
+                &self.config,
+                deadline,
+                false,
+            );
+            match found {
+                Ok(states) if states.is_empty() => {}
+                Ok(_) => return CheckResult::Linearizable,
+                Err(reason) => result = CheckResult::Unknown(reason),
+            }
+        }
+        result
+    }
+
+    /// Returns whether the history is linearizable, as of the end of the most
+    /// recently checked window.
+    pub fn result(&self) -> CheckResult {
+        self.result
+    }
+
+    /// Returns the number of windows that have been checked.
+    pub fn windows(&self) -> usize {
+        self.windows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linearizability::Exhausted;
+    use crate::specifications::register::RegisterOperation::{self, Read, Write};
+    use crate::specifications::register::RegisterSpecification;
+    use Action::{Call, Response};
+
+    type Checker = WindowedChecker<RegisterSpecification<u32>>;
+    type Recorder = WindowedRecorder<RegisterOperation<u32>>;
+
+    fn record_all(
+        recorder: &mut Recorder,
+        actions: Vec<(ProcessId, Action<RegisterOperation<u32>>)>,
+    ) -> Vec<Window<RegisterOperation<u32>>> {
+        actions
+            .into_iter()
+            .filter_map(|(process, action)| recorder.record(TimedAction::now(process, action)))
+            .collect()
+    }
+
+    /// Returns a window of the given actions.
+    fn window(
+        index: usize,
+        actions: Vec<(ProcessId, Action<RegisterOperation<u32>>)>,
+    ) -> Window<RegisterOperation<u32>> {
+        let actions = actions
+            .into_iter()
+            .map(|(process, action)| TimedAction::now(process, action))
+            .collect();
+        Window { index, actions }
+    }
+
+    /// Records `n` sequential writes, each of which closes a window of size
+    /// two.
+    fn record_writes(recorder: &mut Recorder, n: u32) {
+        for value in 0..n {
+            recorder.call(0, Write(value));
+            assert!(recorder.respond(0, Write(value)).is_some());
+        }
+    }
+
+    mod window {
+        use super::*;
+
+        #[test]
+        fn reports_pending_processes() {
+            let window = window(
+                0,
+                vec![
+                    (1, Call(Write(1))),
+                    (0, Call(Read(None))),
+                    (0, Response(Read(Some(0)))),
+                ],
+            );
+            assert_eq!(window.pending(), vec![1]);
+            assert!(!window.is_complete());
+        }
+
+        #[test]
+        fn interrupts_pending_operations_in_history() {
+            let window = window(0, vec![(0, Call(Write(1)))]);
+            let history = window.history();
+            assert_eq!(history.len(), 2);
+        }
+    }
+
+    mod windowed_recorder {
+        use super::*;
+
+        #[test]
+        fn closes_window_once_it_is_full() {
+            let mut recorder = Recorder::new(2);
+            let windows = record_all(
+                &mut recorder,
+                vec![
+                    (0, Call(Write(1))),
+                    (0, Response(Write(1))),
+                    (0, Call(Read(None))),
+                ],
+            );
+            assert_eq!(windows.len(), 1);
+            assert_eq!(windows[0].index, 0);
+            assert_eq!(windows[0].actions.len(), 2);
+            assert_eq!(recorder.current().index, 1);
+            assert_eq!(recorder.current().actions.len(), 1);
+        }
+
+        #[test]
+        fn waits_for_quiescent_point_to_close_window() {
+            let mut recorder = Recorder::new(2);
+            let windows = record_all(
+                &mut recorder,
+                vec![
+                    (0, Call(Write(1))),
+                    (1, Call(Write(2))),
+                    (0, Response(Write(1))),
+                ],
+            );
+            assert!(windows.is_empty());
+            assert!(recorder.is_full());
+            assert!(!recorder.is_quiescent());
+            let window = recorder.respond(1, Write(2)).unwrap();
+            assert_eq!(window.actions.len(), 4);
+        }
+
+        #[test]
+        fn retains_most_recent_windows() {
+            let mut recorder = Recorder::new(2).with_recent(2);
+            record_writes(&mut recorder, 5);
+            let recent: Vec<usize> = recorder.recent().map(|window| window.index).collect();
+            assert_eq!(recent, vec![3, 4]);
+            assert_eq!(recorder.closed(), 5);
+        }
+
+        #[test]
+        fn retains_nothing_by_default() {
+            let mut recorder = Recorder::new(2);
+            record_writes(&mut recorder, 5);
+            assert_eq!(recorder.recent().count(), 0);
+            assert!(recorder.samples().is_empty());
+        }
+
+        #[test]
+        fn samples_windows_evicted_from_recent_windows() {
+            let mut recorder = Recorder::new(2).with_recent(2).with_samples(3);
+            record_writes(&mut recorder, 100);
+            let samples = recorder.samples();
+            assert_eq!(samples.len(), 3);
+            assert!(samples.iter().all(|window| window.index < 98));
+            let mut indices: Vec<usize> = samples.iter().map(|window| window.index).collect();
+            indices.sort();
+            indices.dedup();
+            assert_eq!(indices.len(), 3);
+        }
+
+        #[test]
+        fn samples_same_windows_from_same_seed() {
+            let indices = |seed| {
+                let mut recorder = Recorder::new(2).with_samples(4).with_seed(seed);
+                record_writes(&mut recorder, 100);
+                recorder
+                    .samples()
+                    .iter()
+                    .map(|window| window.index)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(indices(1), indices(1));
+            assert_ne!(indices(1), indices(2));
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "Process 0 called an operation before its previous operation returned"
+        )]
+        fn panics_if_process_calls_twice() {
+            let mut recorder = Recorder::new(2);
+            recorder.call(0, Write(1));
+            recorder.call(0, Write(2));
+        }
+
+        #[test]
+        #[should_panic(expected = "Process 0 returned from an operation before calling it")]
+        fn panics_if_process_returns_before_calling() {
+            let mut recorder = Recorder::new(2);
+            recorder.respond(0, Write(1));
+        }
+    }
+
+    mod windowed_checker {
+        use super::*;
+
+        #[test]
+        fn carries_state_between_windows() {
+            let mut checker = Checker::new();
+            let first = window(0, vec![(0, Call(Write(1))), (0, Response(Write(1)))]);
+            assert_eq!(checker.push(&first), CheckResult::Linearizable);
+            let second = window(1, vec![(1, Call(Read(None))), (1, Response(Read(Some(1))))]);
+            assert_eq!(checker.push(&second), CheckResult::Linearizable);
+        }
+
+        #[test]
+        fn rejects_read_of_value_overwritten_in_earlier_window() {
+            let mut checker = Checker::new();
+            let first = window(
+                0,
+                vec![
+                    (0, Call(Write(1))),
+                    (0, Response(Write(1))),
+                    (0, Call(Write(2))),
+                    (0, Response(Write(2))),
+                ],
+            );
+            checker.push(&first);
+            let second = window(1, vec![(1, Call(Read(None))), (1, Response(Read(Some(1))))]);
+            assert_eq!(checker.push(&second), CheckResult::NotLinearizable);
+            assert_eq!(checker.result(), CheckResult::NotLinearizable);
+        }
+
+        #[test]
+        fn keeps_every_possible_state_at_end_of_window() {
+            // P0 |--------|          Write(1)
+            // P1 |--------|          Write(2)
+            // P2            |---|    Read(Some(1))
+            let first = window(
+                0,
+                vec![
+                    (0, Call(Write(1))),
+                    (1, Call(Write(2))),
+                    (0, Response(Write(1))),
+                    (1, Response(Write(2))),
+                ],
+            );
+            for value in [1, 2] {
+                let mut checker = Checker::new();
+                checker.push(&first);
+                let second = window(
+                    1,
+                    vec![(2, Call(Read(None))), (2, Response(Read(Some(value))))],
+                );
+                assert_eq!(checker.push(&second), CheckResult::Linearizable);
+            }
+        }
+
+        #[test]
+        fn checks_window_with_pending_operations_without_advancing() {
+            let checker = Checker::new();
+            let last = window(
+                0,
+                vec![
+                    (0, Call(Write(1))),
+                    (1, Call(Read(None))),
+                    (1, Response(Read(Some(1)))),
+                ],
+            );
+            assert_eq!(checker.check(&last), CheckResult::Linearizable);
+            assert_eq!(checker.windows(), 0);
+            let invalid = window(0, vec![(1, Call(Read(None))), (1, Response(Read(Some(2))))]);
+            assert_eq!(checker.check(&invalid), CheckResult::NotLinearizable);
+        }
+
+        #[test]
+        fn returns_unknown_if_expansions_are_exhausted() {
+            let config = CheckerConfig::new().with_expansions(0);
+            let mut checker: Checker = WindowedChecker::with_config(config);
+            let first = window(0, vec![(0, Call(Write(1))), (0, Response(Write(1)))]);
+            assert_eq!(
+                checker.push(&first),
+                CheckResult::Unknown(Exhausted::Expansions)
+            );
+            let second = window(1, vec![(0, Call(Write(2))), (0, Response(Write(2)))]);
+            assert_eq!(
+                checker.push(&second),
+                CheckResult::Unknown(Exhausted::Expansions)
+            );
+        }
+
+        #[test]
+        fn agrees_with_wgl_checker_on_recorded_windows() {
+            let actions = vec![
+                (0, Call(Write(1))),
+                (1, Call(Read(None))),
+                (0, Response(Write(1))),
+                (1, Response(Read(Some(1)))),
+                (0, Call(Write(2))),
+                (0, Response(Write(2))),
+                (1, Call(Read(None))),
+                (2, Call(Write(3))),
+                (1, Response(Read(Some(3)))),
+                (2, Response(Write(3))),
+                (0, Call(Read(None))),
+                (0, Response(Read(Some(2)))),
+            ];
+            let mut recorder = Recorder::new(2);
+            let mut checker = Checker::new();
+            for window in record_all(&mut recorder, actions.clone()) {
+                checker.push(&window);
+            }
+            assert_eq!(checker.windows(), 4);
+            let expected = WGLChecker::<RegisterSpecification<u32>>::is_linearizable(
+                History::from_actions(actions),
+            );
+            assert!(!expected);
+            assert_eq!(checker.result(), CheckResult::NotLinearizable);
+        }
+
+        #[test]
+        fn accepts_empty_windows() {
+            let mut checker = Checker::new();
+            assert_eq!(checker.check(&window(0, vec![])), CheckResult::Linearizable);
+            assert_eq!(checker.push(&window(0, vec![])), CheckResult::Linearizable);
+            assert_eq!(checker.windows(), 1);
+        }
+
+        #[test]
+        #[should_panic(expected = "Windows must be checked in order")]
+        fn panics_if_windows_are_skipped() {
+            let mut checker = Checker::new();
+            checker.push(&window(1, vec![]));
+        }
+
+        #[test]
+        #[should_panic(expected = "Window 0 ends while operations are pending")]
+        fn panics_if_pushed_window_is_incomplete() {
+            let mut checker = Checker::new();
+            checker.push(&window(0, vec![(0, Call(Write(1)))]));
+        }
+    }
+}
@@ -2,9 +2,17 @@
 use std::fmt::Debug;
 use std::hash::Hash;
 
+pub mod combinators;
+pub mod counter;
+pub mod deque;
 pub mod etcd;
+pub mod lattice_agreement;
+pub mod multi_object;
+pub mod queue;
 pub mod register;
+pub mod renaming;
 pub mod snapshot;
+pub mod stack;
 
 /// A (sequential) specification of an object.
 ///
@@ -110,7 +118,6 @@ pub mod snapshot;
 /// let (is_valid, _) = RegisterSpec::apply(&Read(42), &new_state);
 /// assert!(!is_valid);
 /// ```
-
 pub trait Specification {
     type State: Clone + Eq + Hash + Debug;
     type Operation: Clone + Debug;
@@ -124,3 +131,213 @@ pub trait Specification {
     /// If the operation is not valid, then the state of the object should not change.
     fn apply(op: &Self::Operation, state: &Self::State) -> (bool, Self::State);
 }
+
+/// A (sequential) specification of an object, in which applying an operation
+/// may lead to one of several possible states.
+///
+/// Some objects are not fully determined by the operations performed on them.
+/// For example, a write that times out may or may not have taken effect, and
+/// a set that removes an arbitrary element may remove any one of them. For
+/// such objects, [`successors`](NondeterministicSpecification::successors) returns every
+/// state that could follow an operation, and a history is linearizable if any
+/// choice of these states leads to a valid linearization.
+///
+/// Every [`Specification`] is also a [`NondeterministicSpecification`], in
+/// which each valid operation has exactly one successor state.
+///
+/// # Examples
+///
+/// Consider a register that stores a single `u32` value, in which a write
+/// may time out without the client learning whether it took effect.
+///
+/// ```
+/// use todc_utils::specifications::NondeterministicSpecification;
+///
+/// #[derive(Copy, Clone, Debug)]
+/// enum RegisterOp {
+///     Read(u32),
+///     Write(u32),
+///     TimedOutWrite(u32),
+/// }
+///
+/// use RegisterOp::{Read, TimedOutWrite, Write};
+///
+/// struct RegisterSpec;
+///
+/// impl NondeterministicSpecification for RegisterSpec {
+///     type State = u32;
+///     type Operation = RegisterOp;
+///     type Successors = Vec<u32>;
+///
+///     fn initial_state() -> Self::State {
+///         0
+///     }
+///
+///     fn successors(operation: &Self::Operation, state: &Self::State) -> Self::Successors {
+///         match operation {
+///             Read(value) if value == state => vec![*state],
+///             Read(_) => vec![],
+///             Write(value) => vec![*value],
+///             TimedOutWrite(value) => vec![*state, *value],
+///         }
+///     }
+/// }
+///
+/// assert_eq!(RegisterSpec::successors(&TimedOutWrite(1), &0), vec![0, 1]);
+/// assert!(RegisterSpec::successors(&Read(1), &0).is_empty());
+/// ```
+pub trait NondeterministicSpecification {
+    type State: Clone + Eq + Hash + Debug;
+    type Operation: Clone + Debug;
+    /// A collection of the states that could follow an operation.
+    type Successors: IntoIterator<Item = Self::State>;
+
+    /// Returns an initial state for the object.
+    fn initial_state() -> Self::State;
+
+    /// Returns every state that could occur after applying an operation to
+    /// a given state.
+    ///
+    /// If the operation is not valid, then no states should be returned.
+    fn successors(op: &Self::Operation, state: &Self::State) -> Self::Successors;
+}
+
+impl<S: Specification> NondeterministicSpecification for S {
+    type State = S::State;
+    type Operation = S::Operation;
+    type Successors = Option<S::State>;
+
+    fn initial_state() -> Self::State {
+        S::init()
+    }
+
+    fn successors(op: &Self::Operation, state: &Self::State) -> Self::Successors {
+        match S::apply(op, state) {
+            (true, state) => Some(state),
+            (false, _) => None,
+        }
+    }
+}
+
+/// A specification of an object whose operations can be partitioned by key,
+/// such that operations on different keys never affect one another.
+///
+/// For example, in a key-value store, reading or writing the value of one key
+/// has no effect on the values of other keys. A history of operations on such
+/// an object is linearizable if and only if, for each key, the sub-history of
+/// operations on that key is linearizable
+/// [\[HW90\]](https://doi.org/10.1145/78969.78972). Since the cost of checking
+/// linearizability is exponential in the number of concurrent operations,
+/// checking each sub-history separately can be much faster than checking the
+/// entire history at once.
+///
+/// Each sub-history is checked starting from [`Specification::init`], so the
+/// initial state must be valid for operations on any key.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use todc_utils::specifications::{PartitionedSpecification, Specification};
+///
+/// #[derive(Copy, Clone, Debug)]
+/// enum StoreOp {
+///     Get(u32, Option<u32>),
+///     Put(u32, u32),
+/// }
+///
+/// use StoreOp::{Get, Put};
+///
+/// struct StoreSpec;
+///
+/// impl Specification for StoreSpec {
+///     type State = BTreeMap<u32, u32>;
+///     type Operation = StoreOp;
+///
+///     fn init() -> Self::State {
+///         BTreeMap::new()
+///     }
+///
+///     fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+///         match operation {
+///             Get(key, value) => (state.get(key) == value.as_ref(), state.clone()),
+///             Put(key, value) => {
+///                 let mut state = state.clone();
+///                 state.insert(*key, *value);
+///                 (true, state)
+///             }
+///         }
+///     }
+/// }
+///
+/// impl PartitionedSpecification for StoreSpec {
+///     type Key = u32;
+///
+///     fn partition(operation: &Self::Operation) -> Self::Key {
+///         match operation {
+///             Get(key, _) | Put(key, _) => *key,
+///         }
+///     }
+/// }
+/// ```
+pub trait PartitionedSpecification: Specification {
+    type Key: Eq + Hash;
+
+    /// Returns the key of the partition that an operation belongs to.
+    fn partition(op: &Self::Operation) -> Self::Key;
+}
+
+/// A specification of an object that can be checked for
+/// [durable linearizability](crate::linearizability::durable).
+///
+/// When a crash occurs, every operation that is in progress is interrupted,
+/// and may or may not take effect. An interrupted operation that leaves the
+/// state of the object unchanged, such as a read, can always be treated as
+/// having no effect. An interrupted operation that updates the object may
+/// instead have taken effect exactly as it would have if it had completed,
+/// which is only known from the values that it was called with.
+///
+/// # Examples
+///
+/// ```
+/// use todc_utils::specifications::{DurableSpecification, Specification};
+///
+/// #[derive(Copy, Clone, Debug)]
+/// enum RegisterOp {
+///     Read(Option<u32>),
+///     Write(u32),
+/// }
+///
+/// use RegisterOp::{Read, Write};
+///
+/// struct RegisterSpec;
+///
+/// impl Specification for RegisterSpec {
+///     type State = u32;
+///     type Operation = RegisterOp;
+///
+///     fn init() -> Self::State {
+///         0
+///     }
+///
+///     fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+///         match operation {
+///             Read(value) => (*value == Some(*state), *state),
+///             Write(value) => (true, *value),
+///         }
+///     }
+/// }
+///
+/// impl DurableSpecification for RegisterSpec {
+///     fn is_update(operation: &Self::Operation) -> bool {
+///         matches!(operation, Write(_))
+///     }
+/// }
+/// ```
+pub trait DurableSpecification: Specification {
+    /// Returns whether an operation may change the state of the object, and
+    /// so may have taken effect if it was interrupted by a crash.
+    ///
+    /// The operation is the one given when the operation was called.
+    fn is_update(op: &Self::Operation) -> bool;
+}
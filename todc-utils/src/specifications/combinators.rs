@@ -0,0 +1,301 @@
+//! Combinators for building specifications of larger objects out of the
+//! specifications of smaller ones.
+//!
+//! A [`ProductSpecification`] specifies an object that is composed of two
+//! independent objects, such as a register and a counter that are stored
+//! side-by-side. Each of its operations is performed on exactly one of the
+//! two objects, and never affects the other.
+//!
+//! A [`MappedSpecification`] specifies an object whose operations are
+//! translated, by an [`OperationMap`], into the operations of an existing
+//! specification. This allows the operations of an object to be modelled in
+//! whatever way is most natural, without re-implementing its behavior.
+//!
+//! The methods of a [`Specification`] take no `self`, so the translation is
+//! given as an implementation of [`OperationMap`], rather than as a closure.
+//!
+//! # Examples
+//!
+//! Specify a counter in terms of a register that supports fetch-and-add, and
+//! then an object composed of an ordinary register and such a counter.
+//!
+//! ```
+//! use todc_utils::specifications::combinators::{
+//!     MappedSpecification, OperationMap, ProductOperation, ProductSpecification,
+//! };
+//! use todc_utils::specifications::register::{
+//!     RegisterOperation, RegisterSpecification, RmwRegisterOperation, RmwRegisterSpecification,
+//! };
+//! use todc_utils::{Action::{Call, Response}, History, WGLChecker};
+//!
+//! #[derive(Copy, Clone, Debug)]
+//! enum CounterOperation {
+//!     /// Increment the counter, and return its previous value.
+//!     Increment(Option<u32>),
+//!     /// Return the value of the counter.
+//!     Get(Option<u32>),
+//! }
+//!
+//! struct Counter;
+//!
+//! impl OperationMap<RmwRegisterSpecification<u32>> for Counter {
+//!     type Operation = CounterOperation;
+//!
+//!     fn map(operation: &Self::Operation) -> RmwRegisterOperation<u32> {
+//!         match operation {
+//!             CounterOperation::Increment(previous) => {
+//!                 RmwRegisterOperation::FetchAndAdd(1, *previous)
+//!             }
+//!             CounterOperation::Get(value) => RmwRegisterOperation::Read(*value),
+//!         }
+//!     }
+//! }
+//!
+//! type CounterSpecification = MappedSpecification<RmwRegisterSpecification<u32>, Counter>;
+//! type Spec = ProductSpecification<RegisterSpecification<u32>, CounterSpecification>;
+//!
+//! use ProductOperation::{First, Second};
+//!
+//! // P0 |------------|                  Write(1)
+//! // P1    |---|                        Increment(Some(0))
+//! // P2             |-------|           Read(Some(1))
+//! // P1                 |------|        Get(Some(1))
+//! let history = History::from_actions(vec![
+//!     (0, Call(First(RegisterOperation::Write(1)))),
+//!     (1, Call(Second(CounterOperation::Increment(None)))),
+//!     (1, Response(Second(CounterOperation::Increment(Some(0))))),
+//!     (2, Call(First(RegisterOperation::Read(None)))),
+//!     (0, Response(First(RegisterOperation::Write(1)))),
+//!     (1, Call(Second(CounterOperation::Get(None)))),
+//!     (2, Response(First(RegisterOperation::Read(Some(1))))),
+//!     (1, Response(Second(CounterOperation::Get(Some(1))))),
+//! ]);
+//! assert!(WGLChecker::<Spec>::is_linearizable(history));
+//! ```
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use crate::specifications::{DurableSpecification, PartitionedSpecification, Specification};
+
+/// An operation for an object composed of two independent objects.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProductOperation<A, B> {
+    /// An operation performed on the first object.
+    First(A),
+    /// An operation performed on the second object.
+    Second(B),
+}
+
+use ProductOperation::*;
+
+/// A sequential specification of an object composed of two independent
+/// objects, specified by `A` and `B`.
+///
+/// The state of the object is the pair of the states of its parts, and each
+/// operation is applied to exactly one of them. Since operations on one part
+/// never affect the other, the object is also a [`PartitionedSpecification`],
+/// whose key is `0` for operations on the first part and `1` for operations
+/// on the second.
+pub struct ProductSpecification<A: Specification, B: Specification> {
+    parts: PhantomData<(A, B)>,
+}
+
+impl<A: Specification, B: Specification> Specification for ProductSpecification<A, B> {
+    type State = (A::State, B::State);
+    type Operation = ProductOperation<A::Operation, B::Operation>;
+
+    fn init() -> Self::State {
+        (A::init(), B::init())
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        let (first, second) = state;
+        match operation {
+            First(operation) => {
+                let (is_valid, first) = A::apply(operation, first);
+                (is_valid, (first, second.clone()))
+            }
+            Second(operation) => {
+                let (is_valid, second) = B::apply(operation, second);
+                (is_valid, (first.clone(), second))
+            }
+        }
+    }
+}
+
+impl<A: Specification, B: Specification> PartitionedSpecification for ProductSpecification<A, B> {
+    type Key = usize;
+
+    fn partition(operation: &Self::Operation) -> Self::Key {
+        match operation {
+            First(_) => 0,
+            Second(_) => 1,
+        }
+    }
+}
+
+impl<A, B> DurableSpecification for ProductSpecification<A, B>
+where
+    A: DurableSpecification,
+    B: DurableSpecification,
+{
+    fn is_update(operation: &Self::Operation) -> bool {
+        match operation {
+            First(operation) => A::is_update(operation),
+            Second(operation) => B::is_update(operation),
+        }
+    }
+}
+
+/// A translation of the operations of an object into the operations of the
+/// specification `S`.
+pub trait OperationMap<S: Specification> {
+    type Operation: Clone + Debug;
+
+    /// Returns the operation of `S` that an operation corresponds to.
+    fn map(op: &Self::Operation) -> S::Operation;
+}
+
+/// A sequential specification of an object whose operations are translated
+/// into the operations of `S` by `M`.
+///
+/// The object has the same states as `S`, and an operation is valid exactly
+/// when the operation of `S` that it is mapped to is valid.
+pub struct MappedSpecification<S: Specification, M: OperationMap<S>> {
+    spec: PhantomData<(S, M)>,
+}
+
+impl<S: Specification, M: OperationMap<S>> Specification for MappedSpecification<S, M> {
+    type State = S::State;
+    type Operation = M::Operation;
+
+    fn init() -> Self::State {
+        S::init()
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        S::apply(&M::map(operation), state)
+    }
+}
+
+impl<S, M> PartitionedSpecification for MappedSpecification<S, M>
+where
+    S: PartitionedSpecification,
+    M: OperationMap<S>,
+{
+    type Key = S::Key;
+
+    fn partition(operation: &Self::Operation) -> Self::Key {
+        S::partition(&M::map(operation))
+    }
+}
+
+impl<S, M> DurableSpecification for MappedSpecification<S, M>
+where
+    S: DurableSpecification,
+    M: OperationMap<S>,
+{
+    fn is_update(operation: &Self::Operation) -> bool {
+        S::is_update(&M::map(operation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::specifications::register::{
+        RegisterOperation, RegisterSpecification, RmwRegisterOperation, RmwRegisterSpecification,
+    };
+    use crate::specifications::stack::{StackOperation, StackSpecification};
+
+    type Product = ProductSpecification<RegisterSpecification<u32>, StackSpecification<u32>>;
+
+    /// Maps each read and write of a register onto the corresponding
+    /// operation of a register that supports read-modify-write operations.
+    struct Upgrade;
+
+    impl OperationMap<RmwRegisterSpecification<u32>> for Upgrade {
+        type Operation = RegisterOperation<u32>;
+
+        fn map(operation: &Self::Operation) -> RmwRegisterOperation<u32> {
+            match operation {
+                RegisterOperation::Read(value) => RmwRegisterOperation::Read(*value),
+                RegisterOperation::Write(value) => RmwRegisterOperation::Write(*value),
+            }
+        }
+    }
+
+    type Mapped = MappedSpecification<RmwRegisterSpecification<u32>, Upgrade>;
+
+    mod product {
+        use super::*;
+
+        #[test]
+        fn initializes_both_parts() {
+            assert_eq!(Product::init(), (0, Vec::new()));
+        }
+
+        #[test]
+        fn applies_first_operation_to_first_part_only() {
+            let state = (0, vec![1]);
+            let (is_valid, state) = Product::apply(&First(RegisterOperation::Write(2)), &state);
+            assert!(is_valid);
+            assert_eq!(state, (2, vec![1]));
+        }
+
+        #[test]
+        fn applies_second_operation_to_second_part_only() {
+            let state = (1, Vec::new());
+            let (is_valid, state) = Product::apply(&Second(StackOperation::Push(2)), &state);
+            assert!(is_valid);
+            assert_eq!(state, (1, vec![2]));
+        }
+
+        #[test]
+        fn operation_is_not_valid_if_it_is_not_valid_for_its_part() {
+            let state = (1, Vec::new());
+            let (is_valid, _) = Product::apply(&First(RegisterOperation::Read(Some(0))), &state);
+            assert!(!is_valid);
+        }
+
+        #[test]
+        fn partitions_operations_by_part() {
+            assert_eq!(Product::partition(&First(RegisterOperation::Write(0))), 0);
+            assert_eq!(Product::partition(&Second(StackOperation::Push(0))), 1);
+        }
+
+        #[test]
+        fn delegates_updates_to_parts() {
+            type Durable =
+                ProductSpecification<RegisterSpecification<u32>, RmwRegisterSpecification<u32>>;
+            assert!(Durable::is_update(&First(RegisterOperation::Write(0))));
+            assert!(!Durable::is_update(&Second(RmwRegisterOperation::Read(
+                None
+            ))));
+        }
+    }
+
+    mod mapped {
+        use super::*;
+
+        #[test]
+        fn initializes_to_initial_state_of_specification() {
+            assert_eq!(Mapped::init(), RmwRegisterSpecification::<u32>::init());
+        }
+
+        #[test]
+        fn applies_mapped_operation() {
+            let (is_valid, state) = Mapped::apply(&RegisterOperation::Write(1), &0);
+            assert!(is_valid);
+            assert_eq!(state, 1);
+            let (is_valid, _) = Mapped::apply(&RegisterOperation::Read(Some(0)), &state);
+            assert!(!is_valid);
+        }
+
+        #[test]
+        fn delegates_updates_to_specification() {
+            assert!(Mapped::is_update(&RegisterOperation::Write(1)));
+            assert!(!Mapped::is_update(&RegisterOperation::Read(None)));
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! A sequential specification of a counter.
+//!
+//! A counter can only be incremented and read, and so, unlike a register
+//! that is incremented by reading it and then writing the result, never
+//! loses concurrent increments.
+use crate::specifications::{DurableSpecification, Specification};
+
+use CounterOperation::*;
+
+/// An operation for a counter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CounterOperation {
+    /// Add one to the counter.
+    Increment,
+    /// Read the value of the counter.
+    ///
+    /// If the return value of the operation is not-yet-known, then this can be
+    /// represented as `Read(None)`.
+    Read(Option<u64>),
+}
+
+/// A sequential specification of a counter, which is initially zero.
+pub struct CounterSpecification;
+
+impl Specification for CounterSpecification {
+    type State = u64;
+    type Operation = CounterOperation;
+
+    fn init() -> Self::State {
+        0
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        match operation {
+            Increment => (true, state + 1),
+            Read(value) => {
+                let value = value.expect("Cannot apply `Read` with unknown return value");
+                (value == *state, *state)
+            }
+        }
+    }
+}
+
+impl DurableSpecification for CounterSpecification {
+    fn is_update(operation: &Self::Operation) -> bool {
+        matches!(operation, Increment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CounterOperation::*, CounterSpecification, DurableSpecification, Specification};
+
+    type Spec = CounterSpecification;
+
+    mod init {
+        use super::*;
+
+        #[test]
+        fn returns_zero() {
+            assert_eq!(Spec::init(), 0);
+        }
+    }
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn increment_adds_one() {
+            let (valid, new_state) = Spec::apply(&Increment, &2);
+            assert!(valid);
+            assert_eq!(new_state, 3);
+        }
+
+        #[test]
+        fn read_valid_if_value_is_current_state() {
+            let (valid, new_state) = Spec::apply(&Read(Some(2)), &2);
+            assert!(valid);
+            assert_eq!(new_state, 2);
+        }
+
+        #[test]
+        fn read_not_valid_if_value_is_not_current_state() {
+            let (valid, _) = Spec::apply(&Read(Some(1)), &2);
+            assert!(!valid);
+        }
+
+        #[test]
+        #[should_panic]
+        fn read_panics_if_return_value_is_unknown() {
+            Spec::apply(&Read(None), &Spec::init());
+        }
+    }
+
+    mod is_update {
+        use super::*;
+
+        #[test]
+        fn only_increments_are_updates() {
+            assert!(Spec::is_update(&Increment));
+            assert!(!Spec::is_update(&Read(None)));
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! A sequential specification of a [double-ended queue](https://en.wikipedia.org/wiki/Double-ended_queue).
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use crate::specifications::Specification;
+
+use DequeOperation::*;
+
+/// An operation for a double-ended queue.
+///
+/// The specification does not bound the number of values in the deque, so
+/// pushes are always valid. Pushes that are rejected by a bounded deque
+/// should be left out of the history.
+#[derive(Debug, Clone)]
+pub enum DequeOperation<T> {
+    /// Add a value of type `T` to the front of the deque.
+    PushFront(T),
+    /// Add a value of type `T` to the back of the deque.
+    PushBack(T),
+    /// Remove and return the value at the front of the deque, or `None` if
+    /// the deque is empty.
+    ///
+    /// If the return value of the operation is not-yet-known, then this can be
+    /// represented as `PopFront(None)`.
+    PopFront(Option<Option<T>>),
+    /// Remove and return the value at the back of the deque, or `None` if
+    /// the deque is empty.
+    ///
+    /// If the return value of the operation is not-yet-known, then this can be
+    /// represented as `PopBack(None)`.
+    PopBack(Option<Option<T>>),
+}
+
+/// A sequential specification of a double-ended queue.
+pub struct DequeSpecification<T: Clone + Debug + Eq + Hash> {
+    data_type: PhantomData<T>,
+}
+
+impl<T: Clone + Debug + Eq + Hash> Specification for DequeSpecification<T> {
+    type State = VecDeque<T>;
+    type Operation = DequeOperation<T>;
+
+    fn init() -> Self::State {
+        VecDeque::new()
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        match operation {
+            PushFront(value) => {
+                let mut state = state.clone();
+                state.push_front(value.clone());
+                (true, state)
+            }
+            PushBack(value) => {
+                let mut state = state.clone();
+                state.push_back(value.clone());
+                (true, state)
+            }
+            PopFront(value) => {
+                let value = value
+                    .as_ref()
+                    .expect("Cannot apply `PopFront` with unknown return value");
+                if value.as_ref() != state.front() {
+                    return (false, state.clone());
+                }
+                let mut state = state.clone();
+                state.pop_front();
+                (true, state)
+            }
+            PopBack(value) => {
+                let value = value
+                    .as_ref()
+                    .expect("Cannot apply `PopBack` with unknown return value");
+                if value.as_ref() != state.back() {
+                    return (false, state.clone());
+                }
+                let mut state = state.clone();
+                state.pop_back();
+                (true, state)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{DequeOperation::*, DequeSpecification, Specification};
+
+    type Spec = DequeSpecification<u32>;
+
+    mod init {
+        use super::*;
+
+        #[test]
+        fn returns_empty_deque() {
+            assert_eq!(Spec::init(), VecDeque::new());
+        }
+    }
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn push_front_adds_value_to_front() {
+            let state = VecDeque::from([1]);
+            let (valid, new_state) = Spec::apply(&PushFront(2), &state);
+            assert!(valid);
+            assert_eq!(new_state, VecDeque::from([2, 1]));
+        }
+
+        #[test]
+        fn push_back_adds_value_to_back() {
+            let state = VecDeque::from([1]);
+            let (valid, new_state) = Spec::apply(&PushBack(2), &state);
+            assert!(valid);
+            assert_eq!(new_state, VecDeque::from([1, 2]));
+        }
+
+        #[test]
+        fn pop_front_removes_value_from_front() {
+            let state = VecDeque::from([1, 2]);
+            let (valid, new_state) = Spec::apply(&PopFront(Some(Some(1))), &state);
+            assert!(valid);
+            assert_eq!(new_state, VecDeque::from([2]));
+        }
+
+        #[test]
+        fn pop_back_removes_value_from_back() {
+            let state = VecDeque::from([1, 2]);
+            let (valid, new_state) = Spec::apply(&PopBack(Some(Some(2))), &state);
+            assert!(valid);
+            assert_eq!(new_state, VecDeque::from([1]));
+        }
+
+        #[test]
+        fn pop_empty_valid_if_deque_is_empty() {
+            assert!(Spec::apply(&PopFront(Some(None)), &Spec::init()).0);
+            assert!(Spec::apply(&PopBack(Some(None)), &Spec::init()).0);
+        }
+
+        #[test]
+        fn pop_empty_not_valid_if_deque_is_not_empty() {
+            let state = VecDeque::from([1]);
+            assert!(!Spec::apply(&PopFront(Some(None)), &state).0);
+            assert!(!Spec::apply(&PopBack(Some(None)), &state).0);
+        }
+
+        #[test]
+        fn pop_not_valid_if_value_is_not_at_that_end() {
+            let state = VecDeque::from([1, 2]);
+            let (valid, new_state) = Spec::apply(&PopFront(Some(Some(2))), &state);
+            assert!(!valid);
+            assert_eq!(new_state, state);
+            let (valid, new_state) = Spec::apply(&PopBack(Some(Some(1))), &state);
+            assert!(!valid);
+            assert_eq!(new_state, state);
+        }
+
+        #[test]
+        #[should_panic]
+        fn pop_front_panics_if_return_value_is_unknown() {
+            Spec::apply(&PopFront(None), &Spec::init());
+        }
+
+        #[test]
+        #[should_panic]
+        fn pop_back_panics_if_return_value_is_unknown() {
+            Spec::apply(&PopBack(None), &Spec::init());
+        }
+    }
+}
@@ -25,8 +25,13 @@ where
 /// The history is created by parsing logs from Jepsen. See
 /// [here](https://github.com/kaymanb/todc/blob/main/todc-utils/tests/linearizability/etcd/etcd_000.log)
 /// for an example of such a log file.
+///
+/// Operations marked with `:fail` are known to have not taken effect, and
+/// end with a [`Failed`](Action::Failed) action. Operations marked with
+/// `:info` may or may not have taken effect, and end with an
+/// [`Indeterminate`](Action::Indeterminate) action, containing the operation
+/// that was called. See: <https://aphyr.com/posts/316-jepsen-etcd-and-consul#writing-a-client>
 pub fn history_from_log(filename: String) -> History<EtcdOperation> {
-    let mut unknowns: Vec<(ProcessID, Action<EtcdOperation>)> = Vec::new();
     let mut actions: Vec<(ProcessID, Action<EtcdOperation>)> = Vec::new();
     for line in read_lines(filename).unwrap() {
         let line = line.unwrap();
@@ -42,107 +47,55 @@ pub fn history_from_log(filename: String) -> History<EtcdOperation> {
         };
 
         let process: usize = words[3].parse().unwrap();
-        // Logs are marked with :info when the success of the operation is unknown. It
-        // suffices to consider a history where all such operations eventually finish,
-        // but at the very end of the history.
-        // See: https://aphyr.com/posts/316-jepsen-etcd-and-consul#writing-a-client
-        if words[4] == ":info" {
-            let (_, call) = actions
-                .iter()
-                .rev()
-                .find(|(pid, _)| *pid == process)
-                .unwrap()
-                .clone();
-            let response = match call {
-                Action::Call(operation) => match operation {
-                    // Reads are a special case, in that they do not affect the state of the
-                    // object. Instead of the operations success being unknown, they can simply
-                    // be treated as having failed, and we expect them to be marked as such in the logs.
-                    Read(_, _) => panic!("Success of read operation cannot be unknown"),
-                    Write(_, value) => Write(Unknown, value),
-                    CompareAndSwap(_, cas) => CompareAndSwap(Unknown, cas),
-                },
-                Action::Response(_) => {
-                    panic!("Expected previous operation by process {process} to be a call")
+        let action = match words[4] {
+            ":invoke" => Action::Call(EtcdOperation::from_log(&words[5..])),
+            ":ok" => Action::Response(EtcdOperation::from_log(&words[5..])),
+            ":fail" => Action::Failed(EtcdOperation::from_log(&words[5..])),
+            ":info" => {
+                let (_, call) = actions
+                    .iter()
+                    .rev()
+                    .find(|(pid, _)| *pid == process)
+                    .unwrap();
+                match call {
+                    Action::Call(operation) => Action::Indeterminate(*operation),
+                    _ => panic!("Expected previous operation by process {process} to be a call"),
                 }
-            };
-            unknowns.push((process, Action::Response(response)));
-            continue;
-        }
-
-        let status = EtcdStatus::from_log(words[4]);
-        let operation = EtcdOperation::from_log(&words[4..]);
-        let action = match status {
-            EtcdStatus::Invoke => Action::Call(operation),
-            _ => Action::Response(operation),
+            }
+            status => panic!("Unexpected status: '{status}'"),
         };
-
         actions.push((process, action))
     }
-
-    // Append responses for operations whose status was unknown to the end of the
-    // history.
-    for item in unknowns.into_iter() {
-        actions.push(item);
-    }
     History::from_actions(actions)
 }
 
-/// The status of an etcd operation.
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
-pub enum EtcdStatus {
-    Invoke,
-    Okay,
-    Fail,
-    Unknown,
-}
-
-impl EtcdStatus {
-    fn from_log(string: &str) -> Self {
-        if string == ":invoke" {
-            Self::Invoke
-        } else if string == ":ok" {
-            Self::Okay
-        } else if string == ":fail" {
-            Self::Fail
-        } else if string == ":info" {
-            Self::Unknown
-        } else {
-            panic!("Unexpected status: '{string}'")
-        }
-    }
-}
-
-use EtcdStatus::*;
-
 /// An etcd operation containing [`u32`] values.
 #[derive(Debug, Copy, Clone)]
 pub enum EtcdOperation {
-    Read(EtcdStatus, Option<u32>),
-    Write(EtcdStatus, u32),
-    CompareAndSwap(EtcdStatus, (u32, u32)),
+    Read(Option<u32>),
+    Write(u32),
+    CompareAndSwap((u32, u32)),
 }
 
 impl EtcdOperation {
     fn from_log(words: &[&str]) -> Self {
-        let status = EtcdStatus::from_log(words[0]);
-        let operation = words[1];
+        let operation = words[0];
         if operation == ":read" {
-            let value = if words[2] == "nil" || words[2] == ":timed-out" {
+            let value = if words[1] == "nil" || words[1] == ":timed-out" {
                 None
             } else {
-                Some(words[2].parse::<u32>().unwrap())
+                Some(words[1].parse::<u32>().unwrap())
             };
-            Self::Read(status, value)
+            Self::Read(value)
         } else if operation == ":write" {
-            let value = words[2].parse::<u32>().unwrap();
-            Self::Write(status, value)
+            let value = words[1].parse::<u32>().unwrap();
+            Self::Write(value)
         } else if operation == ":cas" {
             let value = (
-                words[2][1..].parse().unwrap(),
-                words[3][..1].parse().unwrap(),
+                words[1][1..].parse().unwrap(),
+                words[2][..1].parse().unwrap(),
             );
-            Self::CompareAndSwap(status, value)
+            Self::CompareAndSwap(value)
         } else {
             panic!("Unexpected operation: '{operation}'")
         }
@@ -156,6 +109,9 @@ use EtcdOperation::*;
 /// The specification allows for reads, writes, and compare-and-swap (CAS) operations to be
 /// performed on a single shared register containing [`u32`] values. In practice, etcd
 /// stores exposes many such registers, each indexed by unique key.
+///
+/// Operations that failed, or whose outcome is unknown, are handled by the
+/// checker, and so only operations that took effect are modeled.
 pub struct EtcdSpecification;
 
 impl Specification for EtcdSpecification {
@@ -168,35 +124,12 @@ impl Specification for EtcdSpecification {
 
     fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
         match operation {
-            Read(status, value) => match status {
-                Okay => (value == state, *state),
-                Fail => (value != state, *state),
-                _ => panic!("Cannot apply read that has not succeeded or failed"),
-            },
-            Write(status, value) => match status {
-                Invoke => panic!("Cannot apply write that has only been invoked"),
-                Okay => (true, Some(*value)),
-                Fail => (true, *state),
-                // A write whose status is unknown can be assumed to have completed
-                // successfuly. If, in reality, the write failed, then the result
-                // is indistinguishable to a success at the very end of a sequence
-                // of operations.
-                Unknown => (true, Some(*value)),
+            Read(value) => (value == state, *state),
+            Write(value) => (true, Some(*value)),
+            CompareAndSwap((compare, swap)) => match state {
+                Some(value) if compare == value => (true, Some(*swap)),
+                _ => (false, *state),
             },
-            CompareAndSwap(status, (compare, swap)) => {
-                let success = match state {
-                    Some(value) => compare == value,
-                    None => false,
-                };
-                match status {
-                    Invoke => panic!("Cannot apply CAS that has only been invoked"),
-                    Okay => (success, if success { Some(*swap) } else { *state }),
-                    Fail => (!success, *state),
-                    // A CAS whose status is unkown can be assumed to have completed
-                    // successfuly, for the same reason as explained above for writes.
-                    Unknown => (true, if success { Some(*swap) } else { *state }),
-                }
-            }
         }
     }
 }
@@ -221,33 +154,40 @@ mod test {
 
         #[test]
         fn read_does_not_mutate_state() {
-            let (_, new_state) = Spec::apply(&Read(Okay, None), &Spec::init());
+            let (_, new_state) = Spec::apply(&Read(None), &Spec::init());
             assert_eq!(new_state, Spec::init());
         }
 
         #[test]
         fn read_of_state_is_valid() {
             let state = Some(42);
-            let (is_valid, _) = Spec::apply(&Read(Okay, state), &state);
+            let (is_valid, _) = Spec::apply(&Read(state), &state);
             assert!(is_valid);
         }
 
         #[test]
         fn read_of_bad_value_is_invalid() {
-            let (is_valid, _) = Spec::apply(&Read(Okay, Some(42)), &None);
+            let (is_valid, _) = Spec::apply(&Read(Some(42)), &None);
             assert!(!is_valid);
         }
 
         #[test]
         fn write_sets_new_state_to_written_value() {
             let value = 123;
-            let (_, new_state) = Spec::apply(&Write(Okay, value), &Spec::init());
+            let (_, new_state) = Spec::apply(&Write(value), &Spec::init());
             assert_eq!(new_state, Some(value));
         }
 
+        #[test]
+        fn cas_of_current_value_swaps_state() {
+            let (is_valid, new_state) = Spec::apply(&CompareAndSwap((1, 2)), &Some(1));
+            assert!(is_valid);
+            assert_eq!(new_state, Some(2));
+        }
+
         #[test]
         fn cas_of_bad_value_is_invalid() {
-            let (is_valid, _) = Spec::apply(&CompareAndSwap(Okay, (1, 2)), &None);
+            let (is_valid, _) = Spec::apply(&CompareAndSwap((1, 2)), &None);
             assert!(!is_valid);
         }
     }
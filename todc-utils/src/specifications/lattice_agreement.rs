@@ -0,0 +1,124 @@
+//! A sequential specification of a long-lived lattice agreement object.
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use crate::specifications::Specification;
+
+use LatticeAgreementOperation::Propose;
+
+/// A process identifier.
+pub type ProcessId = usize;
+
+/// An operation for a lattice agreement object.
+#[derive(Debug, Clone)]
+pub enum LatticeAgreementOperation<T> {
+    /// Propose a set of values, and return the decided set of values.
+    ///
+    /// If the return value of a proposal is not-yet-known, this can be
+    /// represented as `Propose(pid, values, None)`.
+    Propose(ProcessId, BTreeSet<T>, Option<BTreeSet<T>>),
+}
+
+/// A specification of a long-lived lattice agreement object, whose values
+/// are taken from the lattice of sets of `T`, ordered by inclusion.
+///
+/// The state of the object is the largest decision made so far. A proposal
+/// is valid only if its decision contains both the proposed set and the
+/// current state, which ensures that the decision of each process includes
+/// its own proposal, and that the decisions of any two processes are
+/// comparable.
+///
+/// The requirement that each decision is contained in the union of all
+/// proposed sets depends on proposals that may be linearized _after_ the
+/// decision, and so cannot be checked by a sequential specification.
+pub struct LatticeAgreementSpecification<T: Clone + Debug + Eq + Hash + Ord> {
+    data_type: PhantomData<T>,
+}
+
+impl<T: Clone + Debug + Eq + Hash + Ord> Specification for LatticeAgreementSpecification<T> {
+    type State = BTreeSet<T>;
+    type Operation = LatticeAgreementOperation<T>;
+
+    fn init() -> Self::State {
+        BTreeSet::new()
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        match operation {
+            Propose(_, values, result) => match result {
+                Some(decision) => {
+                    let valid = values.is_subset(decision) && state.is_subset(decision);
+                    if !valid {
+                        return (false, state.clone());
+                    }
+                    (true, decision.clone())
+                }
+                None => panic!("Cannot apply Propose with an unknown return value."),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{LatticeAgreementOperation::*, LatticeAgreementSpecification, Specification};
+
+    type Spec = LatticeAgreementSpecification<u32>;
+
+    mod init {
+        use super::*;
+
+        #[test]
+        fn returns_empty_set() {
+            assert_eq!(Spec::init(), BTreeSet::new());
+        }
+    }
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn propose_sets_state_to_decision() {
+            let decision = BTreeSet::from([1, 2]);
+            let (valid, new_state) = Spec::apply(
+                &Propose(0, BTreeSet::from([1]), Some(decision.clone())),
+                &Spec::init(),
+            );
+            assert!(valid);
+            assert_eq!(new_state, decision);
+        }
+
+        #[test]
+        fn propose_not_valid_if_decision_excludes_proposal() {
+            let (valid, _) = Spec::apply(
+                &Propose(0, BTreeSet::from([1]), Some(BTreeSet::from([2]))),
+                &Spec::init(),
+            );
+            assert!(!valid);
+        }
+
+        #[test]
+        fn propose_not_valid_if_decision_excludes_earlier_decision() {
+            let state = BTreeSet::from([1, 2]);
+            let (valid, _) = Spec::apply(
+                &Propose(1, BTreeSet::from([3]), Some(BTreeSet::from([1, 3]))),
+                &state,
+            );
+            assert!(!valid);
+        }
+
+        #[test]
+        fn propose_not_valid_does_not_change_state() {
+            let state = BTreeSet::from([1]);
+            let (_, new_state) = Spec::apply(
+                &Propose(0, BTreeSet::from([2]), Some(BTreeSet::new())),
+                &state,
+            );
+            assert_eq!(new_state, state);
+        }
+    }
+}
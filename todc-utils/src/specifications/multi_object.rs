@@ -0,0 +1,318 @@
+//! A sequential specification of a collection of named objects, on which
+//! operations can be performed one object at a time, or as transactions that
+//! span several objects.
+//!
+//! Systems such as key-value stores often contain many objects, each of
+//! which behaves according to the same specification, like a register per
+//! key. A [`MultiObjectSpecification`] composes the specification of a
+//! single object into a specification of the whole collection, so that a
+//! history that mixes operations on many objects can be checked in one pass.
+//!
+//! An operation on a single object never affects any other object, while a
+//! transaction applies several operations atomically, as though no other
+//! operation took effect between them. As with a
+//! [`PartitionedSpecification`](crate::specifications::PartitionedSpecification),
+//! objects that are never touched by the same transaction are independent,
+//! and [`WGLChecker::check_multi_object_with_config`] checks each group of
+//! related objects separately.
+//!
+//! # Examples
+//!
+//! Consider two registers, `x` and `y`, that are always written together. A
+//! transaction that reads a new value from one and an old value from the
+//! other is not linearizable, even though the reads of each register are
+//! linearizable on their own.
+//!
+//! ```
+//! use todc_utils::specifications::multi_object::{
+//!     MultiObjectOperation::{Single, Transaction},
+//!     MultiObjectSpecification,
+//! };
+//! use todc_utils::specifications::register::RegisterOperation::{Read, Write};
+//! use todc_utils::specifications::register::RegisterSpecification;
+//! use todc_utils::{Action::{Call, Response}, History, WGLChecker};
+//!
+//! type Spec = MultiObjectSpecification<&'static str, RegisterSpecification<u32>>;
+//!
+//! // P0 |--------|                 Transaction([Write(x, 1), Write(y, 1)])
+//! // P1             |--------|     Transaction([Read(x, 1), Read(y, 0)])
+//! let history = History::from_actions(vec![
+//!     (0, Call(Transaction(vec![("x", Write(1)), ("y", Write(1))]))),
+//!     (0, Response(Transaction(vec![("x", Write(1)), ("y", Write(1))]))),
+//!     (1, Call(Transaction(vec![("x", Read(None)), ("y", Read(None))]))),
+//!     (1, Response(Transaction(vec![("x", Read(Some(1))), ("y", Read(Some(0)))]))),
+//! ]);
+//! assert!(!WGLChecker::<Spec>::is_linearizable_multi_object(history));
+//!
+//! // P0 |--------|                 Transaction([Write(x, 1), Write(y, 1)])
+//! // P1             |---|          Read(x, 1)
+//! // P2                   |---|    Read(y, 1)
+//! let history = History::from_actions(vec![
+//!     (0, Call(Transaction(vec![("x", Write(1)), ("y", Write(1))]))),
+//!     (0, Response(Transaction(vec![("x", Write(1)), ("y", Write(1))]))),
+//!     (1, Call(Single("x", Read(None)))),
+//!     (1, Response(Single("x", Read(Some(1))))),
+//!     (2, Call(Single("y", Read(None)))),
+//!     (2, Response(Single("y", Read(Some(1))))),
+//! ]);
+//! assert!(WGLChecker::<Spec>::is_linearizable_multi_object(history));
+//! ```
+//!
+//! [`WGLChecker::check_multi_object_with_config`]: crate::WGLChecker::check_multi_object_with_config
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use crate::linearizability::history::{Entry, History};
+use crate::specifications::{DurableSpecification, Specification};
+
+/// An operation on a collection of objects, each identified by a key of type
+/// `K`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiObjectOperation<K, O> {
+    /// An operation performed on a single object.
+    Single(K, O),
+    /// A sequence of operations, performed atomically and in order, on one
+    /// or more objects.
+    Transaction(Vec<(K, O)>),
+}
+
+use MultiObjectOperation::*;
+
+impl<K, O> MultiObjectOperation<K, O> {
+    /// Returns the keys of the objects that the operation is performed on.
+    ///
+    /// A key is returned once for every operation performed on its object,
+    /// so keys may be repeated.
+    pub fn objects(&self) -> impl Iterator<Item = &K> {
+        let (single, transaction) = match self {
+            Single(key, _) => (Some(key), &[][..]),
+            Transaction(operations) => (None, operations.as_slice()),
+        };
+        single
+            .into_iter()
+            .chain(transaction.iter().map(|(key, _)| key))
+    }
+}
+
+/// A sequential specification of a collection of objects, each of which is
+/// specified by `S` and identified by a key of type `K`.
+///
+/// Every object starts in the initial state of `S`. The state of the
+/// collection only records the objects whose states differ from the initial
+/// state, so that equivalent collections always have equal states.
+///
+/// See the [`multi_object`](crate::specifications::multi_object) module-level
+/// documentation for more details.
+pub struct MultiObjectSpecification<K, S: Specification> {
+    data_type: PhantomData<(K, S)>,
+}
+
+impl<K, S> Specification for MultiObjectSpecification<K, S>
+where
+    K: Clone + Debug + Hash + Ord,
+    S: Specification,
+{
+    type State = BTreeMap<K, S::State>;
+    type Operation = MultiObjectOperation<K, S::Operation>;
+
+    fn init() -> Self::State {
+        BTreeMap::new()
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        let mut next = state.clone();
+        let is_valid = match operation {
+            Single(key, operation) => Self::apply_to_object(key, operation, &mut next),
+            Transaction(operations) => operations
+                .iter()
+                .all(|(key, operation)| Self::apply_to_object(key, operation, &mut next)),
+        };
+        if is_valid {
+            (true, next)
+        } else {
+            (false, state.clone())
+        }
+    }
+}
+
+impl<K, S> MultiObjectSpecification<K, S>
+where
+    K: Clone + Debug + Hash + Ord,
+    S: Specification,
+{
+    /// Applies an operation to the object with the given key, and returns
+    /// whether it was valid.
+    fn apply_to_object(
+        key: &K,
+        operation: &S::Operation,
+        state: &mut BTreeMap<K, S::State>,
+    ) -> bool {
+        let (is_valid, object) = match state.get(key) {
+            Some(object) => S::apply(operation, object),
+            None => S::apply(operation, &S::init()),
+        };
+        if object == S::init() {
+            state.remove(key);
+        } else {
+            state.insert(key.clone(), object);
+        }
+        is_valid
+    }
+}
+
+impl<K, S> DurableSpecification for MultiObjectSpecification<K, S>
+where
+    K: Clone + Debug + Hash + Ord,
+    S: DurableSpecification,
+{
+    fn is_update(operation: &Self::Operation) -> bool {
+        match operation {
+            Single(_, operation) => S::is_update(operation),
+            Transaction(operations) => operations
+                .iter()
+                .any(|(_, operation)| S::is_update(operation)),
+        }
+    }
+}
+
+/// Returns, for each object that is operated on in a history, an identifier
+/// of the group of objects that it belongs to.
+///
+/// Two objects are in the same group if some transaction is performed on
+/// both of them, or if they are both in the same group as some third object.
+/// Operations on objects in different groups never affect one another.
+pub(crate) fn object_groups<K: Clone + Eq + Hash, O>(
+    history: &History<MultiObjectOperation<K, O>>,
+) -> HashMap<K, usize> {
+    let mut indices: HashMap<K, usize> = HashMap::new();
+    let mut parents: Vec<usize> = Vec::new();
+    for entry in history.iter() {
+        let Entry::Call(call) = entry else {
+            continue;
+        };
+        let mut first = None;
+        for key in call.operation.objects() {
+            let index = *indices.entry(key.clone()).or_insert_with(|| {
+                parents.push(parents.len());
+                parents.len() - 1
+            });
+            match first {
+                None => first = Some(index),
+                Some(first) => {
+                    let (a, b) = (root(&mut parents, first), root(&mut parents, index));
+                    parents[b] = a;
+                }
+            }
+        }
+    }
+    indices
+        .into_iter()
+        .map(|(key, index)| (key, root(&mut parents, index)))
+        .collect()
+}
+
+/// Returns the root of the tree containing `index`, compressing the path to
+/// it along the way.
+fn root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linearizability::history::Action::{Call, Response};
+    use crate::specifications::register::{RegisterOperation, RegisterSpecification};
+
+    use RegisterOperation::{Read, Write};
+
+    type Spec = MultiObjectSpecification<u32, RegisterSpecification<u32>>;
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn single_operation_only_changes_its_object() {
+            let (is_valid, state) = Spec::apply(&Single(1, Write(2)), &Spec::init());
+            assert!(is_valid);
+            assert_eq!(state, BTreeMap::from([(1, 2)]));
+        }
+
+        #[test]
+        fn unwritten_objects_have_initial_state() {
+            let (is_valid, _) = Spec::apply(&Single(1, Read(Some(0))), &Spec::init());
+            assert!(is_valid);
+        }
+
+        #[test]
+        fn objects_in_initial_state_are_not_recorded() {
+            let state = BTreeMap::from([(1, 2)]);
+            let (_, state) = Spec::apply(&Single(1, Write(0)), &state);
+            assert_eq!(state, Spec::init());
+        }
+
+        #[test]
+        fn transaction_applies_operations_in_order() {
+            let transaction = Transaction(vec![(1, Write(2)), (1, Read(Some(2))), (3, Write(4))]);
+            let (is_valid, state) = Spec::apply(&transaction, &Spec::init());
+            assert!(is_valid);
+            assert_eq!(state, BTreeMap::from([(1, 2), (3, 4)]));
+        }
+
+        #[test]
+        fn invalid_transaction_does_not_change_state() {
+            let transaction = Transaction(vec![(1, Write(2)), (3, Read(Some(4)))]);
+            let (is_valid, state) = Spec::apply(&transaction, &Spec::init());
+            assert!(!is_valid);
+            assert_eq!(state, Spec::init());
+        }
+
+        #[test]
+        fn empty_transaction_is_valid() {
+            let (is_valid, state) = Spec::apply(&Transaction(Vec::new()), &Spec::init());
+            assert!(is_valid);
+            assert_eq!(state, Spec::init());
+        }
+    }
+
+    mod is_update {
+        use super::*;
+
+        #[test]
+        fn transaction_is_update_if_any_operation_is() {
+            assert!(Spec::is_update(&Transaction(vec![
+                (1, Read(None)),
+                (2, Write(1))
+            ])));
+            assert!(!Spec::is_update(&Transaction(vec![
+                (1, Read(None)),
+                (2, Read(None))
+            ])));
+        }
+    }
+
+    mod object_groups {
+        use super::*;
+
+        #[test]
+        fn transactions_join_groups_of_their_objects() {
+            let history = History::from_actions(vec![
+                (0, Call(Transaction(vec![(1, Write(1)), (2, Write(1))]))),
+                (0, Response(Transaction(vec![(1, Write(1)), (2, Write(1))]))),
+                (1, Call(Single(3, Write(1)))),
+                (1, Response(Single(3, Write(1)))),
+                (2, Call(Transaction(vec![(2, Write(2)), (4, Write(2))]))),
+                (2, Response(Transaction(vec![(2, Write(2)), (4, Write(2))]))),
+            ]);
+            let groups = object_groups(&history);
+            assert_eq!(groups[&1], groups[&2]);
+            assert_eq!(groups[&2], groups[&4]);
+            assert_ne!(groups[&1], groups[&3]);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! A sequential specification of a [queue](https://en.wikipedia.org/wiki/Queue_(abstract_data_type)).
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use crate::specifications::Specification;
+
+use QueueOperation::*;
+
+/// An operation for a queue.
+#[derive(Debug, Clone)]
+pub enum QueueOperation<T> {
+    /// Add a value of type `T` to the back of the queue.
+    Enqueue(T),
+    /// Remove and return the value at the front of the queue, or `None` if
+    /// the queue is empty.
+    ///
+    /// If the return value of the operation is not-yet-known, then this can be
+    /// represented as `Dequeue(None)`.
+    Dequeue(Option<Option<T>>),
+}
+
+/// A sequential specification of a queue.
+pub struct QueueSpecification<T: Clone + Debug + Eq + Hash> {
+    data_type: PhantomData<T>,
+}
+
+impl<T: Clone + Debug + Eq + Hash> Specification for QueueSpecification<T> {
+    type State = VecDeque<T>;
+    type Operation = QueueOperation<T>;
+
+    fn init() -> Self::State {
+        VecDeque::new()
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        match operation {
+            Enqueue(value) => {
+                let mut state = state.clone();
+                state.push_back(value.clone());
+                (true, state)
+            }
+            Dequeue(value) => {
+                let value = value
+                    .as_ref()
+                    .expect("Cannot apply `Dequeue` with unknown return value");
+                if value.as_ref() != state.front() {
+                    return (false, state.clone());
+                }
+                let mut state = state.clone();
+                state.pop_front();
+                (true, state)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{QueueOperation::*, QueueSpecification, Specification};
+
+    type Spec = QueueSpecification<u32>;
+
+    mod init {
+        use super::*;
+
+        #[test]
+        fn returns_empty_queue() {
+            assert_eq!(Spec::init(), VecDeque::new());
+        }
+    }
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn enqueue_adds_value_to_back() {
+            let state = VecDeque::from([1]);
+            let (valid, new_state) = Spec::apply(&Enqueue(2), &state);
+            assert!(valid);
+            assert_eq!(new_state, VecDeque::from([1, 2]));
+        }
+
+        #[test]
+        fn dequeue_removes_value_from_front() {
+            let state = VecDeque::from([1, 2]);
+            let (valid, new_state) = Spec::apply(&Dequeue(Some(Some(1))), &state);
+            assert!(valid);
+            assert_eq!(new_state, VecDeque::from([2]));
+        }
+
+        #[test]
+        fn dequeue_empty_valid_if_queue_is_empty() {
+            let (valid, _) = Spec::apply(&Dequeue(Some(None)), &Spec::init());
+            assert!(valid);
+        }
+
+        #[test]
+        fn dequeue_empty_not_valid_if_queue_is_not_empty() {
+            let (valid, _) = Spec::apply(&Dequeue(Some(None)), &VecDeque::from([1]));
+            assert!(!valid);
+        }
+
+        #[test]
+        fn dequeue_not_valid_if_value_is_not_at_front() {
+            let state = VecDeque::from([1, 2]);
+            let (valid, new_state) = Spec::apply(&Dequeue(Some(Some(2))), &state);
+            assert!(!valid);
+            assert_eq!(new_state, state);
+        }
+
+        #[test]
+        #[should_panic]
+        fn dequeue_panics_if_return_value_is_unknown() {
+            Spec::apply(&Dequeue(None), &Spec::init());
+        }
+    }
+}
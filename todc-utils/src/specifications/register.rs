@@ -1,12 +1,22 @@
-//! A sequential specification of a [register](https://en.wikipedia.org/wiki/Shared_register).
+//! Sequential specifications of [registers](https://en.wikipedia.org/wiki/Shared_register).
+//!
+//! A [`RegisterSpecification`] supports only reads and writes. A
+//! [`RmwRegisterSpecification`] additionally supports read-modify-write
+//! operations, such as compare-and-swap and fetch-and-add.
+//!
+//! Histories of operations performed on a register served over HTTP can be
+//! reconstructed from its access logs with the [`access_log`] module.
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::marker::PhantomData;
+use std::ops::Add;
 
-use crate::specifications::Specification;
+use crate::specifications::{DurableSpecification, Specification};
+
+pub mod access_log;
 
 /// An operation for a [register](https://en.wikipedia.org/wiki/Shared_register).
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum RegisterOperation<T> {
     /// Read a value of type `T` from the register.
     ///
@@ -45,6 +55,113 @@ impl<T: Clone + Debug + Default + Eq + Hash> Specification for RegisterSpecifica
     }
 }
 
+impl<T: Clone + Debug + Default + Eq + Hash> DurableSpecification for RegisterSpecification<T> {
+    fn is_update(operation: &Self::Operation) -> bool {
+        matches!(operation, Write(_))
+    }
+}
+
+/// An operation for a register that supports read-modify-write operations.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RmwRegisterOperation<T> {
+    /// Read a value of type `T` from the register.
+    ///
+    /// If the return value of the operation is not-yet-known, then this can be
+    /// represented as `Read(None)`.
+    Read(Option<T>),
+    /// Write a value of type `T` to the register.
+    Write(T),
+    /// Replace the value of the register with `new` if it is equal to
+    /// `expected`, and return whether it was replaced.
+    ///
+    /// The operation `CompareAndSwap(expected, new, result)` contains the
+    /// result as its last field. If the result is unknown, for example
+    /// because the operation timed out, then this can be represented as
+    /// `CompareAndSwap(expected, new, None)`, in which case the operation is
+    /// assumed to have taken effect.
+    CompareAndSwap(T, T, Option<bool>),
+    /// Add a value of type `T` to the register, and return the previous value.
+    ///
+    /// If the return value of the operation is unknown, then this can be
+    /// represented as `FetchAndAdd(delta, None)`, in which case the operation
+    /// is assumed to have taken effect.
+    FetchAndAdd(T, Option<T>),
+}
+
+/// A sequential specification of a register that supports reads, writes,
+/// compare-and-swap, and fetch-and-add operations.
+///
+/// # Examples
+///
+/// ```
+/// use todc_utils::specifications::register::RmwRegisterOperation::{CompareAndSwap, FetchAndAdd};
+/// use todc_utils::specifications::register::RmwRegisterSpecification;
+/// use todc_utils::{Action::{Call, Response}, History, WGLChecker};
+///
+/// type Checker = WGLChecker<RmwRegisterSpecification<u32>>;
+///
+/// // P0 |----------|   FetchAndAdd(1, Some(1))
+/// // P1   |------|     CompareAndSwap(0, 5, Some(false))
+/// // P2 |---|          FetchAndAdd(1, Some(0))
+/// let history = History::from_actions(vec![
+///     (0, Call(FetchAndAdd(1, None))),
+///     (2, Call(FetchAndAdd(1, None))),
+///     (1, Call(CompareAndSwap(0, 5, None))),
+///     (2, Response(FetchAndAdd(1, Some(0)))),
+///     (1, Response(CompareAndSwap(0, 5, Some(false)))),
+///     (0, Response(FetchAndAdd(1, Some(1)))),
+/// ]);
+/// assert!(Checker::is_linearizable(history));
+/// ```
+pub struct RmwRegisterSpecification<T: Default + Eq> {
+    data_type: PhantomData<T>,
+}
+
+impl<T> Specification for RmwRegisterSpecification<T>
+where
+    T: Add<Output = T> + Clone + Debug + Default + Eq + Hash,
+{
+    type State = T;
+    type Operation = RmwRegisterOperation<T>;
+
+    fn init() -> Self::State {
+        T::default()
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        match operation {
+            RmwRegisterOperation::Read(value) => {
+                let value = value
+                    .as_ref()
+                    .expect("Cannot apply `Read` with unknown return value");
+                (value == state, state.clone())
+            }
+            RmwRegisterOperation::Write(value) => (true, value.clone()),
+            RmwRegisterOperation::CompareAndSwap(expected, new, swapped) => {
+                let success = expected == state;
+                let new_state = if success { new.clone() } else { state.clone() };
+                match swapped {
+                    Some(swapped) if *swapped != success => (false, state.clone()),
+                    _ => (true, new_state),
+                }
+            }
+            RmwRegisterOperation::FetchAndAdd(delta, previous) => match previous {
+                Some(previous) if previous != state => (false, state.clone()),
+                _ => (true, state.clone() + delta.clone()),
+            },
+        }
+    }
+}
+
+impl<T> DurableSpecification for RmwRegisterSpecification<T>
+where
+    T: Add<Output = T> + Clone + Debug + Default + Eq + Hash,
+{
+    fn is_update(operation: &Self::Operation) -> bool {
+        !matches!(operation, RmwRegisterOperation::Read(_))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -95,4 +212,95 @@ mod test {
             assert_eq!(value, new_state);
         }
     }
+
+    mod is_update {
+        use super::*;
+
+        #[test]
+        fn writes_are_updates() {
+            assert!(Spec::is_update(&Write(1)));
+        }
+
+        #[test]
+        fn reads_are_not_updates() {
+            assert!(!Spec::is_update(&Read(None)));
+        }
+    }
+
+    mod rmw_register_specification {
+        use super::*;
+        use RmwRegisterOperation::{CompareAndSwap, FetchAndAdd};
+
+        type Spec = RmwRegisterSpecification<u32>;
+
+        #[test]
+        fn read_is_valid_if_value_is_current_state() {
+            let (is_valid, _) = Spec::apply(&RmwRegisterOperation::Read(Some(0)), &Spec::init());
+            assert!(is_valid);
+        }
+
+        #[test]
+        fn write_sets_new_state_to_written_value() {
+            let (_, new_state) = Spec::apply(&RmwRegisterOperation::Write(123), &Spec::init());
+            assert_eq!(new_state, 123);
+        }
+
+        #[test]
+        fn successful_compare_and_swap_replaces_value() {
+            let (is_valid, new_state) = Spec::apply(&CompareAndSwap(1, 2, Some(true)), &1);
+            assert!(is_valid);
+            assert_eq!(new_state, 2);
+        }
+
+        #[test]
+        fn failed_compare_and_swap_does_not_affect_state() {
+            let (is_valid, new_state) = Spec::apply(&CompareAndSwap(1, 2, Some(false)), &3);
+            assert!(is_valid);
+            assert_eq!(new_state, 3);
+        }
+
+        #[test]
+        fn compare_and_swap_is_not_valid_if_result_is_wrong() {
+            let (is_valid, _) = Spec::apply(&CompareAndSwap(1, 2, Some(true)), &3);
+            assert!(!is_valid);
+            let (is_valid, _) = Spec::apply(&CompareAndSwap(1, 2, Some(false)), &1);
+            assert!(!is_valid);
+        }
+
+        #[test]
+        fn compare_and_swap_with_unknown_result_takes_effect() {
+            let (is_valid, new_state) = Spec::apply(&CompareAndSwap(1, 2, None), &1);
+            assert!(is_valid);
+            assert_eq!(new_state, 2);
+        }
+
+        #[test]
+        fn fetch_and_add_adds_to_value() {
+            let (is_valid, new_state) = Spec::apply(&FetchAndAdd(5, Some(1)), &1);
+            assert!(is_valid);
+            assert_eq!(new_state, 6);
+        }
+
+        #[test]
+        fn fetch_and_add_is_not_valid_if_previous_value_is_wrong() {
+            let (is_valid, new_state) = Spec::apply(&FetchAndAdd(5, Some(2)), &1);
+            assert!(!is_valid);
+            assert_eq!(new_state, 1);
+        }
+
+        #[test]
+        fn fetch_and_add_with_unknown_result_takes_effect() {
+            let (is_valid, new_state) = Spec::apply(&FetchAndAdd(5, None), &1);
+            assert!(is_valid);
+            assert_eq!(new_state, 6);
+        }
+
+        #[test]
+        fn only_reads_are_not_updates() {
+            assert!(!Spec::is_update(&RmwRegisterOperation::Read(None)));
+            assert!(Spec::is_update(&RmwRegisterOperation::Write(1)));
+            assert!(Spec::is_update(&CompareAndSwap(1, 2, None)));
+            assert!(Spec::is_update(&FetchAndAdd(1, None)));
+        }
+    }
 }
@@ -0,0 +1,493 @@
+//! Reconstructing histories of register operations from HTTP access logs.
+//!
+//! A register served over HTTP, such as an
+//! [`AtomicRegister`](https://docs.rs/todc-net/latest/todc_net/register/abd_95/struct.AtomicRegister.html)
+//! from `todc-net`, is read with `GET /register` and written with
+//! `POST /register`. If each of these requests is logged along with the time
+//! at which it was received and the time at which it was answered, then the
+//! operations performed on the register can be reconstructed after the fact,
+//! and checked for linearizability.
+//!
+//! # Format
+//!
+//! Logs contain one request per line, as whitespace-separated fields:
+//!
+//! ```text
+//! <start> <end> <client> <method> <path> <status> <body>
+//! ```
+//!
+//! - `start` and `end` are the times at which the request was received and
+//!   answered, in seconds with an optional fractional part, such as
+//!   `1700000000.000123`. Times can be measured from any fixed point, as
+//!   long as all lines measure from the same one.
+//! - `client` identifies the client that made the request, such as its
+//!   address.
+//! - `method` and `path` are those of the request.
+//! - `status` is the status code of the response.
+//! - `body` is the rest of the line. For a write it is the body of the
+//!   request, and for a read it is the body of the response. Values are
+//!   parsed with [`FromStr`], so registers of numbers can be imported
+//!   directly from their JSON encoding.
+//!
+//! If a request was never answered, for example because the instance serving
+//! it crashed, then its `end` and `status` are `-`.
+//!
+//! Empty lines, and lines starting with `#`, are ignored, as are requests to
+//! any other route. Such logs can be produced by a reverse proxy in front of
+//! each instance, or by wrapping the service of each instance.
+//!
+//! # Unknown Outcomes
+//!
+//! A request that was rejected with a `4xx` status never reached the
+//! register, and so is ignored. On the other hand, a write that failed with
+//! a `5xx` status, or that was never answered, may or may not have taken
+//! effect. As with [Jepsen](https://github.com/jepsen-io/jepsen), such writes
+//! are treated as having finished at the very end of the history, which
+//! allows them to take effect at any point after they were received. Reads
+//! whose outcome is unknown have no effect, and are ignored.
+//!
+//! # Examples
+//!
+//! ```
+//! use todc_utils::specifications::register::access_log::history_from_entries;
+//! use todc_utils::specifications::register::access_log::AccessLogEntry;
+//! use todc_utils::specifications::register::RegisterSpecification;
+//! use todc_utils::WGLChecker;
+//!
+//! let log = "\
+//! 0.000 0.010 10.0.0.1 POST /register 200 123
+//! 0.005 0.020 10.0.0.2 GET /register 200 123
+//! 0.015 0.025 10.0.0.2 GET /register 200 123";
+//!
+//! let entries = log.lines().map(AccessLogEntry::parse).collect();
+//! let history = history_from_entries::<u32>(entries);
+//! assert!(WGLChecker::<RegisterSpecification<u32>>::is_linearizable(history));
+//! ```
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::linearizability::history::{Action, History, ProcessId};
+
+use super::RegisterOperation::{self, Read, Write};
+
+/// The route at which clients read from and write to a register.
+const REGISTER_PATH: &str = "/register";
+
+/// A request to a register, as recorded in an access log.
+///
+/// See the [`access_log`](self) module-level documentation for details.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessLogEntry {
+    /// The time at which the request was received.
+    pub start: Duration,
+    /// The time at which the request was answered, if it was.
+    pub end: Option<Duration>,
+    /// The client that made the request.
+    pub client: String,
+    /// The method of the request.
+    pub method: String,
+    /// The path of the request.
+    pub path: String,
+    /// The status code of the response, if there was one.
+    pub status: Option<u16>,
+    /// The body of the request, for a write, or of the response, for a read.
+    pub body: String,
+}
+
+impl AccessLogEntry {
+    /// Parses a line of an access log.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line does not match the format described in the
+    /// [`access_log`](self) module-level documentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use todc_utils::specifications::register::access_log::AccessLogEntry;
+    ///
+    /// let entry = AccessLogEntry::parse("1.5 - 10.0.0.1 POST /register - 123");
+    /// assert_eq!(entry.start, Duration::from_millis(1500));
+    /// assert_eq!(entry.end, None);
+    /// assert_eq!(entry.body, "123");
+    /// ```
+    pub fn parse(line: &str) -> Self {
+        let mut rest = line.trim();
+        let mut next = |name: &str| {
+            let (field, remainder) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if field.is_empty() {
+                panic!("Missing {name} in access log entry: '{line}'");
+            }
+            rest = remainder.trim_start();
+            field
+        };
+        let start = parse_time(next("start"));
+        let end = optional(next("end")).map(parse_time);
+        let client = next("client").to_string();
+        let method = next("method").to_string();
+        let path = next("path").to_string();
+        let status = optional(next("status")).map(|status| {
+            status
+                .parse()
+                .unwrap_or_else(|_| panic!("Unexpected status: '{status}'"))
+        });
+        let body = rest.to_string();
+        Self {
+            start,
+            end,
+            client,
+            method,
+            path,
+            status,
+            body,
+        }
+    }
+
+    /// Returns the operation that this entry records, if it is a request to
+    /// the register, along with whether its outcome is known.
+    fn operation<T>(&self) -> Option<(RegisterOperation<T>, bool)>
+    where
+        T: FromStr,
+        T::Err: Debug,
+    {
+        let path = self.path.split('?').next().unwrap_or_default();
+        if path != REGISTER_PATH {
+            return None;
+        }
+        let known = match (self.end, self.status) {
+            (Some(_), Some(200..=299)) => true,
+            (Some(_), Some(400..=499)) => return None,
+            _ => false,
+        };
+        match (self.method.as_str(), known) {
+            ("GET", true) => Some((Read(Some(self.value())), true)),
+            ("POST", _) => Some((Write(self.value()), known)),
+            _ => None,
+        }
+    }
+
+    /// Returns the value contained in the body of this entry.
+    fn value<T>(&self) -> T
+    where
+        T: FromStr,
+        T::Err: Debug,
+    {
+        self.body
+            .parse()
+            .unwrap_or_else(|error| panic!("Unexpected value '{}': {error:?}", self.body))
+    }
+}
+
+/// Returns `None` if the field is `-`, which marks a missing value.
+fn optional(field: &str) -> Option<&str> {
+    (field != "-").then_some(field)
+}
+
+/// Parses a time given in seconds, with an optional fractional part.
+///
+/// This is done by hand, rather than through [`f64`], so that times measured
+/// from the Unix epoch keep their full precision.
+fn parse_time(time: &str) -> Duration {
+    let parse = || {
+        let (secs, fraction) = time.split_once('.').unwrap_or((time, ""));
+        if fraction.len() > 9 || !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let nanos = format!("{fraction:0<9}").parse().ok()?;
+        Some(Duration::new(secs.parse().ok()?, nanos))
+    };
+    parse().unwrap_or_else(|| panic!("Unexpected time: '{time}'"))
+}
+
+/// Returns a history of the operations performed on a register, reconstructed
+/// from an access log.
+///
+/// See the [`access_log`](self) module-level documentation for the format of
+/// the log.
+///
+/// # Errors
+///
+/// Returns an error if the log cannot be read.
+///
+/// # Panics
+///
+/// Panics if some line of the log is malformed, or if the log contains no
+/// requests to the register. See [`history_from_entries`] for details.
+pub fn history_from_access_log<T>(
+    filename: impl AsRef<Path>,
+) -> io::Result<History<RegisterOperation<T>>>
+where
+    T: Clone + FromStr,
+    T::Err: Debug,
+{
+    let file = File::open(filename)?;
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        entries.push(AccessLogEntry::parse(line));
+    }
+    Ok(history_from_entries(entries))
+}
+
+/// Returns a history of the operations performed on a register, reconstructed
+/// from the entries of an access log, given in any order.
+///
+/// Operations are ordered by the times at which they were received and
+/// answered. If one operation was answered at the same time as another was
+/// received, then they are treated as concurrent, so that the coarse clocks
+/// used by many logs do not cause linearizable histories to be rejected.
+///
+/// The operations of each client are performed by a single process, unless
+/// a client sends a request before its previous one was answered. In that
+/// case, the request is performed by a new process, as the order in which
+/// it is served relative to the previous one is unknown.
+///
+/// The resulting history has timestamps attached, measured from the earliest
+/// request.
+///
+/// # Panics
+///
+/// Panics if the entries contain no requests to the register, or if the body
+/// of some request cannot be parsed as a value of type `T`.
+///
+/// # Examples
+///
+/// A write whose outcome is unknown may take effect after later reads.
+///
+/// ```
+/// use todc_utils::specifications::register::access_log::{history_from_entries, AccessLogEntry};
+/// use todc_utils::specifications::register::RegisterSpecification;
+/// use todc_utils::WGLChecker;
+///
+/// let entries = vec![
+///     AccessLogEntry::parse("0 1 a POST /register 503 1"),
+///     AccessLogEntry::parse("2 3 b GET /register 200 0"),
+///     AccessLogEntry::parse("4 5 b GET /register 200 1"),
+/// ];
+/// let history = history_from_entries::<u32>(entries);
+/// assert!(WGLChecker::<RegisterSpecification<u32>>::is_linearizable(history));
+/// ```
+pub fn history_from_entries<T>(mut entries: Vec<AccessLogEntry>) -> History<RegisterOperation<T>>
+where
+    T: Clone + FromStr,
+    T::Err: Debug,
+{
+    entries.sort_by_key(|entry| entry.start);
+
+    // The time at which each process finishes its latest operation, or
+    // `None` if it never does, grouped by client.
+    let mut processes: HashMap<&str, Vec<(ProcessId, Option<Duration>)>> = HashMap::new();
+    let mut num_processes = 0;
+    // Actions, along with the time at which they happened. Responses of
+    // operations whose outcome is unknown are instead collected separately,
+    // and placed at the end of the history.
+    let mut actions = Vec::new();
+    let mut unknowns = Vec::new();
+    for entry in &entries {
+        let Some((operation, known)) = entry.operation::<T>() else {
+            continue;
+        };
+        let end = entry.end.filter(|_| known);
+
+        let candidates = processes.entry(&entry.client).or_default();
+        let free = candidates
+            .iter_mut()
+            .find(|(_, finished)| finished.is_some_and(|finished| finished < entry.start));
+        let process = match free {
+            Some((process, finished)) => {
+                *finished = end;
+                *process
+            }
+            None => {
+                num_processes += 1;
+                candidates.push((num_processes - 1, end));
+                num_processes - 1
+            }
+        };
+
+        let call = match &operation {
+            Read(_) => Read(None),
+            Write(value) => Write(value.clone()),
+        };
+        actions.push((entry.start, process, Action::Call(call)));
+        match end {
+            Some(end) => actions.push((end, process, Action::Response(operation))),
+            None => unknowns.push((process, Action::Response(operation))),
+        }
+    }
+
+    // Calls are placed before responses that happened at the same time.
+    actions.sort_by_key(|(time, _, action)| (*time, matches!(action, Action::Response(_))));
+
+    let start = actions
+        .first()
+        .map(|(time, _, _)| *time)
+        .unwrap_or_default();
+    let last = entries
+        .iter()
+        .flat_map(|entry| [Some(entry.start), entry.end])
+        .flatten()
+        .max()
+        .unwrap_or_default();
+    let mut timestamps: Vec<Duration> = actions.iter().map(|(time, _, _)| *time - start).collect();
+    timestamps.extend(unknowns.iter().map(|_| last - start));
+
+    let actions = actions
+        .into_iter()
+        .map(|(_, process, action)| (process, action))
+        .chain(unknowns)
+        .collect();
+    History::from_actions(actions).with_timestamps(timestamps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linearizability::history::Entry;
+
+    mod parse {
+        use super::*;
+
+        #[test]
+        fn parses_all_fields() {
+            let entry =
+                AccessLogEntry::parse("1.25 2.000000001 10.0.0.1 GET /register 200 \"a b\"");
+            assert_eq!(
+                entry,
+                AccessLogEntry {
+                    start: Duration::from_millis(1250),
+                    end: Some(Duration::new(2, 1)),
+                    client: String::from("10.0.0.1"),
+                    method: String::from("GET"),
+                    path: String::from("/register"),
+                    status: Some(200),
+                    body: String::from("\"a b\""),
+                }
+            );
+        }
+
+        #[test]
+        fn keeps_full_precision_of_epoch_times() {
+            let entry = AccessLogEntry::parse("1700000000.123456789 - a GET /register - ");
+            assert_eq!(entry.start, Duration::new(1_700_000_000, 123_456_789));
+        }
+
+        #[test]
+        fn parses_missing_end_and_status() {
+            let entry = AccessLogEntry::parse("0 - a POST /register - 1");
+            assert_eq!(entry.end, None);
+            assert_eq!(entry.status, None);
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_fields_are_missing() {
+            AccessLogEntry::parse("0 1 a GET");
+        }
+
+        #[test]
+        #[should_panic]
+        fn panics_if_time_is_malformed() {
+            AccessLogEntry::parse("0.1.2 1 a GET /register 200 1");
+        }
+    }
+
+    mod history_from_entries {
+        use super::*;
+
+        fn history(lines: &[&str]) -> History<RegisterOperation<u32>> {
+            history_from_entries(
+                lines
+                    .iter()
+                    .map(|line| AccessLogEntry::parse(line))
+                    .collect(),
+            )
+        }
+
+        fn operations(history: &History<RegisterOperation<u32>>) -> Vec<String> {
+            history
+                .iter()
+                .map(|entry| match entry {
+                    Entry::Call(call) => format!("call {:?}", call.operation),
+                    Entry::Response(response) => format!("return {:?}", response.operation),
+                })
+                .collect()
+        }
+
+        #[test]
+        fn orders_actions_by_time() {
+            let history = history(&["2 3 b GET /register 200 1", "0 1 a POST /register 200 1"]);
+            assert_eq!(
+                operations(&history),
+                [
+                    "call Write(1)",
+                    "return Write(1)",
+                    "call Read(None)",
+                    "return Read(Some(1))"
+                ]
+            );
+        }
+
+        #[test]
+        fn ignores_other_routes_and_rejected_requests() {
+            let history = history(&[
+                "0 1 a POST /register 200 1",
+                "0 1 a GET /register/local 200 {}",
+                "2 3 b POST /register 403 2",
+                "2 3 b GET /register 503 0",
+            ]);
+            assert_eq!(history.len(), 2);
+        }
+
+        #[test]
+        fn places_unknown_writes_at_end() {
+            let history = history(&["0 - a POST /register - 1", "2 3 b GET /register 200 0"]);
+            assert_eq!(
+                operations(&history),
+                [
+                    "call Write(1)",
+                    "call Read(None)",
+                    "return Read(Some(0))",
+                    "return Write(1)"
+                ]
+            );
+        }
+
+        #[test]
+        fn treats_operations_that_touch_as_concurrent() {
+            let history = history(&["0 1 a GET /register 200 1", "1 2 b POST /register 200 1"]);
+            assert_eq!(
+                operations(&history),
+                [
+                    "call Read(None)",
+                    "call Write(1)",
+                    "return Read(Some(1))",
+                    "return Write(1)"
+                ]
+            );
+        }
+
+        #[test]
+        fn overlapping_requests_from_one_client_are_concurrent() {
+            let history = history(&["0 3 a GET /register 200 1", "1 2 a POST /register 200 1"]);
+            assert_eq!(history.stats().max_concurrency, 2);
+        }
+
+        #[test]
+        fn measures_timestamps_from_earliest_request() {
+            let history = history(&["10 12 a POST /register 200 1"]);
+            assert_eq!(history.stats().mean_latency, Some(Duration::from_secs(2)));
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! A sequential specification of a long-lived [renaming](https://en.wikipedia.org/wiki/Renaming_problem) object.
+use core::array::from_fn;
+
+use crate::specifications::Specification;
+
+use RenamingOperation::{Acquire, Release};
+
+/// A process identifier.
+pub type ProcessId = usize;
+
+/// An operation for a renaming object.
+#[derive(Debug, Copy, Clone)]
+pub enum RenamingOperation {
+    /// Acquire a name, and return it.
+    ///
+    /// If the return value of an acquire is not-yet-known, this can be
+    /// represented as `Acquire(pid, None)`.
+    Acquire(ProcessId, Option<usize>),
+    /// Release the name held by this process.
+    Release(ProcessId),
+}
+
+/// A specification of an `N`-process long-lived renaming object, whose
+/// names are taken from the range `0..2N-1`.
+///
+/// A process can acquire a name only if it does not already hold one, and
+/// only if the name is not held by any other process.
+pub struct RenamingSpecification<const N: usize>;
+
+impl<const N: usize> Specification for RenamingSpecification<N> {
+    type State = [Option<usize>; N];
+    type Operation = RenamingOperation;
+
+    fn init() -> Self::State {
+        from_fn(|_| None)
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        match operation {
+            Acquire(i, result) => match result {
+                Some(name) => {
+                    let valid =
+                        state[*i].is_none() && *name < 2 * N - 1 && !state.contains(&Some(*name));
+                    if !valid {
+                        return (false, *state);
+                    }
+                    let mut new_state = *state;
+                    new_state[*i] = Some(*name);
+                    (true, new_state)
+                }
+                None => panic!("Cannot apply Acquire with an unknown return value."),
+            },
+            Release(i) => {
+                let mut new_state = *state;
+                new_state[*i] = None;
+                (true, new_state)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RenamingOperation::*, RenamingSpecification, Specification};
+
+    type Spec = RenamingSpecification<3>;
+
+    mod init {
+        use super::*;
+
+        #[test]
+        fn returns_array_without_names() {
+            assert_eq!(Spec::init(), [None, None, None]);
+        }
+    }
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn acquire_gives_name_to_process() {
+            let (valid, new_state) = Spec::apply(&Acquire(1, Some(4)), &Spec::init());
+            assert!(valid);
+            assert_eq!(new_state, [None, Some(4), None]);
+        }
+
+        #[test]
+        fn acquire_not_valid_if_name_held_by_other_process() {
+            let (_, state) = Spec::apply(&Acquire(0, Some(2)), &Spec::init());
+            let (valid, _) = Spec::apply(&Acquire(1, Some(2)), &state);
+            assert!(!valid);
+        }
+
+        #[test]
+        fn acquire_not_valid_if_process_holds_name() {
+            let (_, state) = Spec::apply(&Acquire(0, Some(2)), &Spec::init());
+            let (valid, _) = Spec::apply(&Acquire(0, Some(3)), &state);
+            assert!(!valid);
+        }
+
+        #[test]
+        fn acquire_not_valid_if_name_out_of_range() {
+            let (valid, _) = Spec::apply(&Acquire(0, Some(5)), &Spec::init());
+            assert!(!valid);
+        }
+
+        #[test]
+        fn release_allows_name_to_be_acquired_again() {
+            let (_, state) = Spec::apply(&Acquire(0, Some(2)), &Spec::init());
+            let (valid, state) = Spec::apply(&Release(0), &state);
+            assert!(valid);
+            let (valid, _) = Spec::apply(&Acquire(1, Some(2)), &state);
+            assert!(valid);
+        }
+    }
+}
@@ -4,7 +4,7 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::marker::PhantomData;
 
-use crate::specifications::Specification;
+use crate::specifications::{DurableSpecification, Specification};
 
 use SnapshotOperation::{Scan, Update};
 
@@ -55,6 +55,14 @@ impl<T: Clone + Debug + Default + Eq + Hash, const N: usize> Specification
     }
 }
 
+impl<T: Clone + Debug + Default + Eq + Hash, const N: usize> DurableSpecification
+    for SnapshotSpecification<T, N>
+{
+    fn is_update(operation: &Self::Operation) -> bool {
+        matches!(operation, Update(_, _))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{SnapshotOperation::*, SnapshotSpecification, Specification};
@@ -0,0 +1,116 @@
+//! A sequential specification of a [stack](https://en.wikipedia.org/wiki/Stack_(abstract_data_type)).
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use crate::specifications::Specification;
+
+use StackOperation::*;
+
+/// An operation for a stack.
+#[derive(Debug, Clone)]
+pub enum StackOperation<T> {
+    /// Add a value of type `T` to the top of the stack.
+    Push(T),
+    /// Remove and return the value at the top of the stack, or `None` if the
+    /// stack is empty.
+    ///
+    /// If the return value of the operation is not-yet-known, then this can be
+    /// represented as `Pop(None)`.
+    Pop(Option<Option<T>>),
+}
+
+/// A sequential specification of a stack.
+pub struct StackSpecification<T: Clone + Debug + Eq + Hash> {
+    data_type: PhantomData<T>,
+}
+
+impl<T: Clone + Debug + Eq + Hash> Specification for StackSpecification<T> {
+    type State = Vec<T>;
+    type Operation = StackOperation<T>;
+
+    fn init() -> Self::State {
+        Vec::new()
+    }
+
+    fn apply(operation: &Self::Operation, state: &Self::State) -> (bool, Self::State) {
+        match operation {
+            Push(value) => {
+                let mut state = state.clone();
+                state.push(value.clone());
+                (true, state)
+            }
+            Pop(value) => {
+                let value = value
+                    .as_ref()
+                    .expect("Cannot apply `Pop` with unknown return value");
+                if value.as_ref() != state.last() {
+                    return (false, state.clone());
+                }
+                let mut state = state.clone();
+                state.pop();
+                (true, state)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Specification, StackOperation::*, StackSpecification};
+
+    type Spec = StackSpecification<u32>;
+
+    mod init {
+        use super::*;
+
+        #[test]
+        fn returns_empty_stack() {
+            assert_eq!(Spec::init(), Vec::new());
+        }
+    }
+
+    mod apply {
+        use super::*;
+
+        #[test]
+        fn push_adds_value_to_top() {
+            let (valid, new_state) = Spec::apply(&Push(2), &vec![1]);
+            assert!(valid);
+            assert_eq!(new_state, vec![1, 2]);
+        }
+
+        #[test]
+        fn pop_removes_value_from_top() {
+            let (valid, new_state) = Spec::apply(&Pop(Some(Some(2))), &vec![1, 2]);
+            assert!(valid);
+            assert_eq!(new_state, vec![1]);
+        }
+
+        #[test]
+        fn pop_empty_valid_if_stack_is_empty() {
+            let (valid, _) = Spec::apply(&Pop(Some(None)), &Spec::init());
+            assert!(valid);
+        }
+
+        #[test]
+        fn pop_empty_not_valid_if_stack_is_not_empty() {
+            let (valid, _) = Spec::apply(&Pop(Some(None)), &vec![1]);
+            assert!(!valid);
+        }
+
+        #[test]
+        fn pop_not_valid_if_value_is_not_at_top() {
+            let state = vec![1, 2];
+            let (valid, new_state) = Spec::apply(&Pop(Some(Some(1))), &state);
+            assert!(!valid);
+            assert_eq!(new_state, state);
+        }
+
+        #[test]
+        #[should_panic]
+        fn pop_panics_if_return_value_is_unknown() {
+            Spec::apply(&Pop(None), &Spec::init());
+        }
+    }
+}
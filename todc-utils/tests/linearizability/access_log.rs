@@ -0,0 +1,26 @@
+use todc_utils::linearizability::WGLChecker;
+use todc_utils::specifications::register::access_log::history_from_access_log;
+use todc_utils::specifications::register::RegisterSpecification;
+
+type RegisterChecker = WGLChecker<RegisterSpecification<u32>>;
+
+#[test]
+fn linearizable_log_is_linearizable() {
+    let history =
+        history_from_access_log("tests/linearizability/access_log/linearizable.log").unwrap();
+    assert!(RegisterChecker::is_linearizable(history));
+}
+
+#[test]
+fn not_linearizable_log_is_not_linearizable() {
+    let history =
+        history_from_access_log("tests/linearizability/access_log/not_linearizable.log").unwrap();
+    assert!(!RegisterChecker::is_linearizable(history));
+}
+
+#[test]
+fn missing_log_is_an_error() {
+    assert!(
+        history_from_access_log::<u32>("tests/linearizability/access_log/missing.log").is_err()
+    );
+}
@@ -1,8 +1,17 @@
-use todc_utils::linearizability::WGLChecker;
-use todc_utils::specifications::etcd::{history_from_log, EtcdSpecification};
+use todc_utils::corpus::Corpus;
+use todc_utils::linearizability::{CheckerConfig, WGLChecker};
+use todc_utils::specifications::etcd::{history_from_log, EtcdOperation, EtcdSpecification};
+use todc_utils::History;
 
 type EtcdChecker = WGLChecker<EtcdSpecification>;
 
+/// Returns the history recorded in the etcd log with the given number.
+fn etcd_history(log_number: &str) -> History<EtcdOperation> {
+    let corpus = Corpus::etcd().with_cache_dir("tests/linearizability/etcd");
+    let path = corpus.path(&format!("etcd_{log_number}.log")).unwrap();
+    history_from_log(path.to_string_lossy().into_owned())
+}
+
 #[macro_export]
 macro_rules! etcd_tests {
     ( $($name:ident: $values:expr,)* )=> {
@@ -10,9 +19,7 @@ macro_rules! etcd_tests {
             #[test]
             fn $name() {
                 let (log_number, expected_result) = $values;
-                let history = history_from_log(
-                    format!("tests/linearizability/etcd/etcd_{}.log", log_number)
-                );
+                let history = etcd_history(log_number);
                 let result = EtcdChecker::is_linearizable(history);
                 assert_eq!(result, expected_result);
             }
@@ -128,3 +135,38 @@ etcd_tests! {
     test_101: ("101", true),
     test_102: ("102", true),
 }
+
+mod bounded_cache {
+    use super::*;
+
+    // Checking etcd_099 with an unbounded cache stores roughly 64,000
+    // entries, so a smaller capacity forces some to be evicted.
+    const CAPACITY: usize = 50_000;
+
+    #[test]
+    fn accepts_linearizable_history() {
+        let history = etcd_history("031");
+        let config = CheckerConfig::new().with_capacity(CAPACITY);
+        assert!(EtcdChecker::is_linearizable_with_config(history, &config));
+    }
+
+    #[test]
+    fn rejects_non_linearizable_history() {
+        let history = etcd_history("099");
+        let config = CheckerConfig::new().with_capacity(CAPACITY);
+        assert!(!EtcdChecker::is_linearizable_with_config(history, &config));
+    }
+}
+
+mod shrink {
+    use super::*;
+
+    #[test]
+    fn shrinks_non_linearizable_history() {
+        let history = etcd_history("000");
+        let len = history.len();
+        let shrunk = EtcdChecker::shrink(history);
+        assert!(shrunk.len() < len);
+        assert!(!EtcdChecker::is_linearizable(shrunk));
+    }
+}
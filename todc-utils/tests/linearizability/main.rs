@@ -1 +1,2 @@
+mod access_log;
 mod etcd;